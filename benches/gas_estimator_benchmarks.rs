@@ -1,6 +1,7 @@
 use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
-use neo3::neo_builder::{GasEstimator, ScriptBuilder};
+use neo3::neo_builder::{GasEstimator, InteropService, ScriptBuilder};
 use neo3::neo_types::OpCode;
+use num_bigint::BigInt;
 
 fn benchmark_script_building(c: &mut Criterion) {
 	let mut group = c.benchmark_group("script_building");
@@ -9,9 +10,9 @@ fn benchmark_script_building(c: &mut Criterion) {
 	group.bench_function("simple_script", |b| {
 		b.iter(|| {
 			ScriptBuilder::new()
-				.push_integer(black_box(42))
-				.push_integer(black_box(13))
-				.emit(OpCode::Add)
+				.push_integer(BigInt::from(black_box(42)))
+				.push_integer(BigInt::from(black_box(13)))
+				.op_code(&[OpCode::Add])
 				.to_bytes()
 		})
 	});
@@ -21,9 +22,9 @@ fn benchmark_script_building(c: &mut Criterion) {
 		b.iter(|| {
 			let mut builder = ScriptBuilder::new();
 			for i in 0..100 {
-				builder = builder.push_integer(black_box(i));
+				builder.push_integer(BigInt::from(black_box(i)));
 			}
-			builder.emit(OpCode::Pack).to_bytes()
+			builder.op_code(&[OpCode::Pack]).to_bytes()
 		})
 	});
 
@@ -32,9 +33,9 @@ fn benchmark_script_building(c: &mut Criterion) {
 		let test_string = "Hello, Neo Blockchain!";
 		b.iter(|| {
 			ScriptBuilder::new()
-				.push_string(black_box(test_string.to_string()))
-				.push_string(black_box("World".to_string()))
-				.emit(OpCode::Cat)
+				.push_data(black_box(test_string.as_bytes().to_vec()))
+				.push_data(black_box(b"World".to_vec()))
+				.op_code(&[OpCode::Cat])
 				.to_bytes()
 		})
 	});
@@ -78,7 +79,7 @@ fn benchmark_script_sizes(c: &mut Criterion) {
 			b.iter(|| {
 				let mut builder = ScriptBuilder::new();
 				for i in 0..size {
-					builder = builder.push_integer(black_box(i as i64));
+					builder.push_integer(BigInt::from(black_box(i as i64)));
 				}
 				builder.to_bytes()
 			})
@@ -93,18 +94,18 @@ fn benchmark_opcode_emission(c: &mut Criterion) {
 
 	// Benchmark single opcode emission
 	group.bench_function("single_opcode", |b| {
-		b.iter(|| ScriptBuilder::new().emit(black_box(OpCode::Nop)).to_bytes())
+		b.iter(|| ScriptBuilder::new().op_code(&[black_box(OpCode::Nop)]).to_bytes())
 	});
 
 	// Benchmark multiple opcode emission
 	group.bench_function("multiple_opcodes", |b| {
 		b.iter(|| {
 			ScriptBuilder::new()
-				.emit(black_box(OpCode::Push1))
-				.emit(black_box(OpCode::Push2))
-				.emit(black_box(OpCode::Add))
-				.emit(black_box(OpCode::Push3))
-				.emit(black_box(OpCode::Mul))
+				.op_code(&[black_box(OpCode::Push1)])
+				.op_code(&[black_box(OpCode::Push2)])
+				.op_code(&[black_box(OpCode::Add)])
+				.op_code(&[black_box(OpCode::Push3)])
+				.op_code(&[black_box(OpCode::Mul)])
 				.to_bytes()
 		})
 	});
@@ -114,8 +115,8 @@ fn benchmark_opcode_emission(c: &mut Criterion) {
 		let contract_hash = [0u8; 20];
 		b.iter(|| {
 			ScriptBuilder::new()
-				.emit_push(&black_box(contract_hash))
-				.emit(OpCode::Appcall)
+				.push_data(black_box(contract_hash).to_vec())
+				.sys_call(InteropService::SystemContractCall)
 				.to_bytes()
 		})
 	});
@@ -124,7 +124,7 @@ async fn example_batch_estimation(
 	println!("--- Example 3: Batch Gas Estimation ---");
 
 	// Prepare multiple scripts for batch estimation
-	let scripts = vec![
+	let scripts = [
 		(
 			ScriptBuilder::new()
 				.push_integer(BigInt::from(100))
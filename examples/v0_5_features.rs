@@ -17,7 +17,6 @@ use neo3::sdk::{
 	Neo,
 };
 use std::sync::Arc;
-use tokio;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -0,0 +1,115 @@
+//! Derive macros for the `neo3` crate.
+//!
+//! - `FromStackItem` decodes a struct from a `Struct` or `Array` stack item holding one item
+//!   per field, in declaration order.
+//! - `IntoContractParameter` encodes a struct as an array parameter holding one parameter per
+//!   field, in declaration order.
+//!
+//! Both support structs with named fields and tuple structs, and are re-exported by
+//! `neo3::neo_types` next to the traits they implement.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, spanned::Spanned, Data, DeriveInput, Fields, Index};
+
+#[proc_macro_derive(FromStackItem)]
+pub fn derive_from_stack_item(input: TokenStream) -> TokenStream {
+	let input = parse_macro_input!(input as DeriveInput);
+	from_stack_item(&input).unwrap_or_else(syn::Error::into_compile_error).into()
+}
+
+#[proc_macro_derive(IntoContractParameter)]
+pub fn derive_into_contract_parameter(input: TokenStream) -> TokenStream {
+	let input = parse_macro_input!(input as DeriveInput);
+	into_contract_parameter(&input)
+		.unwrap_or_else(syn::Error::into_compile_error)
+		.into()
+}
+
+fn struct_fields(input: &DeriveInput) -> syn::Result<&Fields> {
+	match &input.data {
+		Data::Struct(data) => Ok(&data.fields),
+		_ => Err(syn::Error::new(input.span(), "only structs can be derived")),
+	}
+}
+
+fn from_stack_item(input: &DeriveInput) -> syn::Result<TokenStream2> {
+	let name = &input.ident;
+	let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+	let fields = struct_fields(input)?;
+	let len = fields.len();
+
+	let values = fields.iter().enumerate().map(|(index, field)| {
+		let ty = &field.ty;
+		let path = match &field.ident {
+			Some(ident) => format!("{}.{}", name, ident),
+			None => format!("{}.{}", name, index),
+		};
+		quote! {
+			<#ty as ::neo3::neo_types::FromStackItem>::from_stack_item(&items[#index])
+				.map_err(|err| ::neo3::neo_types::field_error(#path, err))?
+		}
+	});
+	let value = match fields {
+		Fields::Named(_) => {
+			let idents = fields.iter().map(|field| &field.ident);
+			quote! { Self { #(#idents: #values),* } }
+		},
+		Fields::Unnamed(_) => quote! { Self(#(#values),*) },
+		Fields::Unit => quote! { Self },
+	};
+	let type_name = name.to_string();
+
+	Ok(quote! {
+		impl #impl_generics ::neo3::neo_types::FromStackItem for #name #ty_generics #where_clause {
+			fn from_stack_item(
+				item: &::neo3::neo_types::StackItem,
+			) -> ::std::result::Result<Self, ::neo3::neo_types::TypeError> {
+				let items = ::neo3::neo_types::stack_items(item, #len)
+					.map_err(|err| ::neo3::neo_types::field_error(#type_name, err))?;
+				let _ = items;
+				::std::result::Result::Ok(#value)
+			}
+		}
+	})
+}
+
+fn into_contract_parameter(input: &DeriveInput) -> syn::Result<TokenStream2> {
+	let name = &input.ident;
+	let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+	let fields = struct_fields(input)?;
+
+	let parameters = fields.iter().enumerate().map(|(index, field)| {
+		let ty = &field.ty;
+		let member = match &field.ident {
+			Some(ident) => quote! { #ident },
+			None => {
+				let index = Index::from(index);
+				quote! { #index }
+			},
+		};
+		quote! {
+			<#ty as ::neo3::neo_types::IntoContractParameter>::into_contract_parameter(
+				self.#member,
+			)?
+		}
+	});
+
+	Ok(quote! {
+		impl #impl_generics ::neo3::neo_types::IntoContractParameter for #name #ty_generics
+			#where_clause
+		{
+			fn into_contract_parameter(
+				self,
+			) -> ::std::result::Result<
+				::neo3::neo_types::ContractParameter,
+				::neo3::neo_types::TypeError,
+			> {
+				::std::result::Result::Ok(::neo3::neo_types::ContractParameter::array(
+					::std::vec![#(#parameters),*],
+				))
+			}
+		}
+	})
+}
@@ -79,6 +79,7 @@
 //! - [**neo_protocol**](neo_protocol): Core blockchain protocol implementations
 //! - [**neo_types**](neo_types): Core data types and primitives for Neo N3
 //! - [**neo_utils**](neo_utils): General utility functions
+//! - [**neo_vm**](neo_vm): Local NeoVM execution engine
 //! - [**neo_wallets**](neo_wallets): Wallet management for Neo N3
 //! - [**neo_x**](neo_x): Neo X EVM compatibility layer
 //!
@@ -443,6 +444,7 @@ pub mod neo_fs;
 pub mod neo_protocol;
 #[cfg(all(feature = "sgx", target_env = "sgx"))]
 pub mod neo_sgx;
+pub mod neo_vm;
 pub mod neo_wallets;
 pub mod neo_x;
 
@@ -5,7 +5,7 @@ use thiserror::Error;
 
 #[derive(Debug, Error, PartialEq, Clone)]
 pub enum BuilderError {
-	#[error("Invalid operation")]
+	#[error("Invalid script: {0}")]
 	InvalidScript(String),
 	#[error("Invalid operation")]
 	InvalidOperation,
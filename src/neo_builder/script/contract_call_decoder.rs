@@ -0,0 +1,614 @@
+use std::{collections::HashMap, fmt};
+
+use num_bigint::BigInt;
+use num_traits::ToPrimitive;
+use primitive_types::{H160, H256};
+
+use crate::{
+	builder::{BuilderError, CallFlags, InteropService, Operand, ScriptReader, Transaction},
+	neo_clients::JsonRpcProvider,
+	neo_types::{
+		encode_string_h160, ContractManifest, ContractMethod, ContractParameter,
+		ContractParameterMap, ContractParameterType, OpCode, ParameterValue, ScriptHashExtension,
+	},
+};
+
+/// Explains the contract calls made by a script.
+///
+/// The decoder recognizes the pattern emitted by [`ScriptBuilder::contract_call`]: the packed
+/// arguments, the call flags, the method name and the contract hash, followed by a
+/// `System.Contract.Call` syscall. Every call is reported with typed [`ContractParameter`]
+/// arguments. Registering the manifest of a contract with [`with_manifest`] adds the contract
+/// name, the matching ABI method and the parameter names, and types the arguments according to
+/// the ABI instead of guessing them from their encoding.
+///
+/// A `System.Contract.Call` whose contract, method, flags or arguments are computed at runtime
+/// makes decoding fail rather than being left out, so a script never does more than what was
+/// shown to the signer. So do the instructions that run code the decoder can't follow: jumps,
+/// calls, exception handlers, `CALLT`, `System.Contract.CallNative` and interop services it
+/// doesn't know, like `System.Runtime.LoadScript`.
+///
+/// # Examples
+///
+/// ```rust
+/// use neo3::neo_builder::{ContractCallDecoder, ScriptBuilder};
+/// use neo3::neo_types::ContractParameter;
+/// use primitive_types::H160;
+/// use std::str::FromStr;
+///
+/// let gas = H160::from_str("0xd2a4cff31913016155e38e474a2c06d08be276cf").unwrap();
+/// let to = H160::from_slice(&[0x11; 20]);
+/// let script = ScriptBuilder::new()
+/// 	.contract_call(
+/// 		&gas,
+/// 		"transfer",
+/// 		&[to.into(), to.into(), ContractParameter::integer(10), ContractParameter::any()],
+/// 		None,
+/// 	)
+/// 	.unwrap()
+/// 	.to_bytes();
+///
+/// let calls = ContractCallDecoder::new().decode(&script).unwrap();
+/// assert_eq!(calls[0].method, "transfer");
+/// assert_eq!(calls[0].arguments[2], ContractParameter::integer(10));
+/// ```
+///
+/// [`ScriptBuilder::contract_call`]: crate::neo_builder::ScriptBuilder::contract_call
+/// [`with_manifest`]: ContractCallDecoder::with_manifest
+#[derive(Debug, Clone, Default)]
+pub struct ContractCallDecoder {
+	manifests: HashMap<H160, ContractManifest>,
+}
+
+impl ContractCallDecoder {
+	/// Creates a decoder without any known contracts.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Registers the manifest of the contract with the given hash.
+	pub fn with_manifest(mut self, contract: H160, manifest: ContractManifest) -> Self {
+		self.manifests.insert(contract, manifest);
+		self
+	}
+
+	/// Decodes the contract calls made by the script of `transaction`.
+	pub fn decode_transaction<P: JsonRpcProvider + 'static>(
+		&self,
+		transaction: &Transaction<P>,
+	) -> Result<Vec<DecodedContractCall>, BuilderError> {
+		self.decode(transaction.script())
+	}
+
+	/// Decodes the contract calls made by `script`, in the order they appear in the script.
+	pub fn decode(&self, script: &[u8]) -> Result<Vec<DecodedContractCall>, BuilderError> {
+		let mut stack: Vec<Option<Value>> = Vec::new();
+		let mut calls = Vec::new();
+
+		for instruction in ScriptReader::disassemble(script)? {
+			if let Some(integer) = instruction.pushed_integer() {
+				stack.push(Some(Value::Integer(integer)));
+				continue;
+			}
+			match (instruction.opcode, &instruction.operand) {
+				(_, Operand::Data(data)) => stack.push(Some(Value::Data(data.to_vec()))),
+				(OpCode::PushTrue, _) => stack.push(Some(Value::Boolean(true))),
+				(OpCode::PushFalse, _) => stack.push(Some(Value::Boolean(false))),
+				(OpCode::PushNull, _) => stack.push(Some(Value::Null)),
+				(OpCode::NewArray0, _) => stack.push(Some(Value::Array(Vec::new()))),
+				(OpCode::NewMap, _) => stack.push(Some(Value::Map(Vec::new()))),
+				(OpCode::Pack | OpCode::PackStruct, _) => {
+					let items = pop_count(&mut stack).and_then(|count| {
+						(0..count).map(|_| stack.pop().flatten()).collect::<Option<Vec<_>>>()
+					});
+					stack.push(items.map(Value::Array));
+				},
+				(OpCode::PackMap, _) => {
+					let entries = pop_count(&mut stack).and_then(|count| {
+						(0..count)
+							.map(|_| Some((stack.pop().flatten()?, stack.pop().flatten()?)))
+							.collect::<Option<Vec<_>>>()
+					});
+					stack.push(entries.map(Value::Map));
+				},
+				(
+					OpCode::Syscall,
+					Operand::Syscall { service: Some(InteropService::SystemContractCall), .. },
+				) => {
+					let contract = stack.pop().flatten();
+					let method = stack.pop().flatten();
+					let call_flags = stack.pop().flatten();
+					let arguments = stack.pop().flatten();
+					let call = match (contract, method, call_flags, arguments) {
+						(
+							Some(Value::Data(contract)),
+							Some(Value::Data(method)),
+							Some(Value::Integer(call_flags)),
+							Some(Value::Array(arguments)),
+						) if contract.len() == 20 => String::from_utf8(method).ok().zip(
+							call_flags.to_u8().map(|call_flags| (contract, call_flags, arguments)),
+						),
+						_ => None,
+					};
+					let (method, (contract, call_flags, arguments)) = call.ok_or_else(|| {
+						BuilderError::InvalidScript(format!(
+							"System.Contract.Call at offset {} takes operands computed at runtime",
+							instruction.offset
+						))
+					})?;
+					let contract = hash160(&contract);
+					calls.push(self.describe(
+						instruction.offset,
+						contract,
+						method,
+						call_flags,
+						arguments,
+					));
+					// The return value of the called method is only known at runtime
+					stack.push(None);
+				},
+				(
+					OpCode::Syscall,
+					Operand::Syscall {
+						hash,
+						service: None | Some(InteropService::SystemContractCallNative),
+					},
+				) =>
+					return Err(BuilderError::InvalidScript(format!(
+						"SYSCALL at offset {} calls the interop service {} that may run code the \
+						 script doesn't show",
+						instruction.offset, hash
+					))),
+				(OpCode::CallT, _) =>
+					return Err(BuilderError::InvalidScript(format!(
+						"CALLT at offset {} calls a method token, which transaction scripts can't \
+						 declare",
+						instruction.offset
+					))),
+				(OpCode::CallA | OpCode::EndFinally, _)
+				| (_, Operand::Jump { .. } | Operand::Try { .. }) =>
+					return Err(BuilderError::InvalidScript(format!(
+						"{} at offset {} changes the control flow, the calls of the script can't \
+						 be decoded in order",
+						instruction.opcode_name(),
+						instruction.offset
+					))),
+				// Anything else consumes or produces values that are only known at runtime
+				_ => stack.clear(),
+			}
+		}
+		Ok(calls)
+	}
+
+	fn describe(
+		&self,
+		offset: usize,
+		contract: H160,
+		method: String,
+		call_flags: u8,
+		arguments: Vec<Value>,
+	) -> DecodedContractCall {
+		let manifest = self.manifests.get(&contract);
+		let abi_method = manifest
+			.and_then(|manifest| manifest.abi.as_ref())
+			.and_then(|abi| {
+				abi.methods
+					.iter()
+					.find(|m| m.name == method && m.parameters.len() == arguments.len())
+			})
+			.cloned();
+		let arguments = match &abi_method {
+			Some(abi_method) => arguments
+				.iter()
+				.zip(&abi_method.parameters)
+				.map(|(argument, parameter)| {
+					argument.to_typed_parameter(parameter.typ).with_name(parameter.name.clone())
+				})
+				.collect(),
+			None => arguments.iter().map(Value::to_parameter).collect(),
+		};
+
+		DecodedContractCall {
+			offset,
+			contract,
+			contract_name: manifest.and_then(|manifest| manifest.name.clone()),
+			method,
+			call_flags,
+			arguments,
+			abi_method,
+		}
+	}
+}
+
+/// A contract call decoded by [`ContractCallDecoder`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecodedContractCall {
+	/// The offset of the `System.Contract.Call` syscall in the script.
+	pub offset: usize,
+	/// The hash of the called contract.
+	pub contract: H160,
+	/// The name of the called contract, if its manifest is known.
+	pub contract_name: Option<String>,
+	/// The name of the called method.
+	pub method: String,
+	/// The raw call flags passed to `System.Contract.Call`.
+	pub call_flags: u8,
+	/// The arguments of the call, named after the ABI parameters if the manifest is known.
+	pub arguments: Vec<ContractParameter>,
+	/// The ABI method matching the method name and argument count, if the manifest is known.
+	pub abi_method: Option<ContractMethod>,
+}
+
+impl DecodedContractCall {
+	/// The call flags, if they are one of the combinations defined by [`CallFlags`].
+	pub fn flags(&self) -> Option<CallFlags> {
+		CallFlags::from_value(self.call_flags).ok()
+	}
+}
+
+impl fmt::Display for DecodedContractCall {
+	/// Renders the call as `transfer(from: NX…, to: NX…, amount: 10, data: null) on GasToken
+	/// (0xd2a4…)`.
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{}(", self.method)?;
+		for (index, argument) in self.arguments.iter().enumerate() {
+			if index > 0 {
+				write!(f, ", ")?;
+			}
+			if let Some(name) = argument.name() {
+				write!(f, "{}: ", name)?;
+			}
+			write!(f, "{}", render(argument))?;
+		}
+		write!(f, ") on ")?;
+		match &self.contract_name {
+			Some(name) => write!(f, "{} ({})", name, encode_string_h160(&self.contract)),
+			None => write!(f, "{}", encode_string_h160(&self.contract)),
+		}
+	}
+}
+
+fn render(parameter: &ContractParameter) -> String {
+	match &parameter.value {
+		None | Some(ParameterValue::Any) => "null".to_string(),
+		Some(ParameterValue::Boolean(value)) => value.to_string(),
+		Some(ParameterValue::Integer(value)) => value.to_string(),
+		Some(ParameterValue::String(value)) => format!("{:?}", value),
+		Some(ParameterValue::H160(_)) => match parameter.to_h160() {
+			Ok(hash) => hash.to_address(),
+			Err(_) => "<invalid hash>".to_string(),
+		},
+		Some(ParameterValue::ByteArray(_)) => match parameter.to_byte_array() {
+			Ok(bytes) => format!("0x{}", hex::encode(bytes)),
+			Err(_) => "<invalid bytes>".to_string(),
+		},
+		Some(
+			ParameterValue::H256(value)
+			| ParameterValue::PublicKey(value)
+			| ParameterValue::Signature(value),
+		) => format!("0x{}", value),
+		Some(ParameterValue::Array(items)) =>
+			format!("[{}]", items.iter().map(render).collect::<Vec<_>>().join(", ")),
+		Some(ParameterValue::Map(map)) => format!(
+			"{{{}}}",
+			map.0
+				.iter()
+				.map(|(key, value)| format!("{}: {}", render(key), render(value)))
+				.collect::<Vec<_>>()
+				.join(", ")
+		),
+	}
+}
+
+/// Reads a hash pushed in little-endian order into an `H160` holding the display order.
+fn hash160(data: &[u8]) -> H160 {
+	H160::from_slice(&reversed(data))
+}
+
+fn reversed(data: &[u8]) -> Vec<u8> {
+	data.iter().rev().copied().collect()
+}
+
+fn pop_count(stack: &mut Vec<Option<Value>>) -> Option<usize> {
+	match stack.pop().flatten() {
+		Some(Value::Integer(count)) => count.to_usize(),
+		_ => None,
+	}
+}
+
+/// A value whose content is known before the script runs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Value {
+	Null,
+	Boolean(bool),
+	Integer(BigInt),
+	Data(Vec<u8>),
+	Array(Vec<Value>),
+	/// Map entries as (key, value) pairs.
+	Map(Vec<(Value, Value)>),
+}
+
+impl Value {
+	/// Converts the value without type information.
+	///
+	/// Data is shown as a string if it is printable UTF-8, as a script hash if it is 20 bytes
+	/// long and as a byte array otherwise.
+	fn to_parameter(&self) -> ContractParameter {
+		match self {
+			Value::Null => ContractParameter::any(),
+			Value::Boolean(value) => ContractParameter::bool(*value),
+			Value::Integer(value) => match value.to_i64() {
+				Some(value) => ContractParameter::integer(value),
+				None => ContractParameter::byte_array(value.to_signed_bytes_le()),
+			},
+			Value::Data(data) => match std::str::from_utf8(data) {
+				Ok(text) if !text.is_empty() && !text.chars().any(char::is_control) =>
+					ContractParameter::string(text.to_string()),
+				_ if data.len() == 20 => ContractParameter::h160(&hash160(data)),
+				_ => ContractParameter::byte_array(data.clone()),
+			},
+			Value::Array(items) =>
+				ContractParameter::array(items.iter().map(Value::to_parameter).collect()),
+			Value::Map(entries) => ContractParameter::map(ContractParameterMap::from_map(
+				entries
+					.iter()
+					.map(|(key, value)| (key.to_parameter(), value.to_parameter()))
+					.collect(),
+			)),
+		}
+	}
+
+	/// Converts the value to a parameter of type `typ`, falling back to [`Value::to_parameter`]
+	/// if the value does not have the shape the type requires.
+	fn to_typed_parameter(&self, typ: ContractParameterType) -> ContractParameter {
+		match (typ, self) {
+			(ContractParameterType::H160, Value::Data(data)) if data.len() == 20 =>
+				ContractParameter::h160(&hash160(data)),
+			(ContractParameterType::H256, Value::Data(data)) if data.len() == 32 =>
+				ContractParameter::h256(&H256::from_slice(&reversed(data))),
+			(ContractParameterType::PublicKey, Value::Data(data)) if data.len() == 33 =>
+				ContractParameter::with_value(typ, ParameterValue::PublicKey(hex::encode(data))),
+			(ContractParameterType::Signature, Value::Data(data)) if data.len() == 64 =>
+				ContractParameter::with_value(typ, ParameterValue::Signature(hex::encode(data))),
+			(ContractParameterType::ByteArray, Value::Data(data)) =>
+				ContractParameter::byte_array(data.clone()),
+			(ContractParameterType::String, Value::Data(data)) =>
+				match String::from_utf8(data.clone()) {
+					Ok(text) => ContractParameter::string(text),
+					Err(_) => self.to_parameter(),
+				},
+			_ => self.to_parameter(),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::str::FromStr;
+
+	use super::*;
+	use crate::{
+		builder::ScriptBuilder,
+		crypto::HashableForVec,
+		neo_types::{ContractABI, ContractParameter2},
+	};
+
+	fn gas_token() -> H160 {
+		H160::from_str("0xd2a4cff31913016155e38e474a2c06d08be276cf").unwrap()
+	}
+
+	fn gas_manifest() -> ContractManifest {
+		let transfer = ContractMethod::new(
+			"transfer".to_string(),
+			Some(vec![
+				ContractParameter2::new("from".to_string(), ContractParameterType::H160),
+				ContractParameter2::new("to".to_string(), ContractParameterType::H160),
+				ContractParameter2::new("amount".to_string(), ContractParameterType::Integer),
+				ContractParameter2::new("data".to_string(), ContractParameterType::Any),
+			]),
+			0,
+			ContractParameterType::Boolean,
+			false,
+		);
+		ContractManifest::new(
+			Some("GasToken".to_string()),
+			vec![],
+			None,
+			vec!["NEP-17".to_string()],
+			Some(ContractABI::new(Some(vec![transfer]), None)),
+			vec![],
+			vec![],
+			None,
+		)
+	}
+
+	#[test]
+	fn test_decode_contract_call_arguments() {
+		let contract = H160::from_low_u64_be(7);
+		let account = H160::from_low_u64_be(0xfe01);
+		let arguments = vec![
+			ContractParameter::integer(-5),
+			ContractParameter::bool(true),
+			ContractParameter::string("hello".to_string()),
+			ContractParameter::h160(&account),
+			ContractParameter::byte_array(vec![0x00, 0x01, 0xff]),
+			ContractParameter::any(),
+			ContractParameter::array(vec![
+				ContractParameter::integer(1),
+				ContractParameter::array(vec![]),
+			]),
+		];
+		let script = ScriptBuilder::new()
+			.contract_call(&contract, "doIt", &arguments, Some(CallFlags::ReadOnly))
+			.unwrap()
+			.to_bytes();
+
+		let calls = ContractCallDecoder::new().decode(&script).unwrap();
+
+		assert_eq!(calls.len(), 1);
+		assert_eq!(calls[0].contract, contract);
+		assert_eq!(calls[0].method, "doIt");
+		assert_eq!(calls[0].flags(), Some(CallFlags::ReadOnly));
+		assert_eq!(calls[0].arguments, arguments);
+		assert_eq!(calls[0].offset, script.len() - 5);
+		assert_eq!(calls[0].contract_name, None);
+	}
+
+	#[test]
+	fn test_decode_multiple_calls() {
+		let script = ScriptBuilder::new()
+			.contract_call(&gas_token(), "symbol", &[], None)
+			.unwrap()
+			.op_code(&[OpCode::Drop])
+			.contract_call(&gas_token(), "decimals", &[], None)
+			.unwrap()
+			.to_bytes();
+
+		let calls = ContractCallDecoder::new().decode(&script).unwrap();
+
+		assert_eq!(
+			calls.iter().map(|call| call.method.as_str()).collect::<Vec<_>>(),
+			vec!["symbol", "decimals"]
+		);
+		assert!(calls[0].arguments.is_empty());
+		assert_eq!(calls[1].flags(), Some(CallFlags::All));
+	}
+
+	#[test]
+	fn test_decode_with_manifest() {
+		let from = H160::from_low_u64_be(0x22);
+		let to = H160::from_slice(&[0x11; 20]);
+		let script = ScriptBuilder::new()
+			.contract_call(
+				&gas_token(),
+				"transfer",
+				&[from.into(), to.into(), ContractParameter::integer(10), ContractParameter::any()],
+				None,
+			)
+			.unwrap()
+			.to_bytes();
+
+		let calls = ContractCallDecoder::new()
+			.with_manifest(gas_token(), gas_manifest())
+			.decode(&script)
+			.unwrap();
+
+		let call = &calls[0];
+		assert_eq!(call.contract_name.as_deref(), Some("GasToken"));
+		assert_eq!(
+			call.abi_method.as_ref().map(|m| m.return_type),
+			Some(ContractParameterType::Boolean)
+		);
+		assert_eq!(call.arguments[0], ContractParameter::h160(&from).with_name("from"));
+		assert_eq!(call.arguments[2], ContractParameter::integer(10).with_name("amount"));
+		assert_eq!(
+			call.to_string(),
+			format!(
+				"transfer(from: {}, to: {}, amount: 10, data: null) on GasToken \
+				 (0xd2a4cff31913016155e38e474a2c06d08be276cf)",
+				from.to_address(),
+				to.to_address()
+			)
+		);
+	}
+
+	#[test]
+	fn test_decode_rejects_runtime_operands() {
+		// The contract hash is read from a static field instead of being pushed
+		let mut builder = ScriptBuilder::new();
+		builder
+			.op_code(&[OpCode::NewArray0])
+			.push_integer(BigInt::from(CallFlags::All.value()))
+			.push_data(b"transfer".to_vec())
+			.op_code(&[OpCode::LdSFLd0])
+			.sys_call(InteropService::SystemContractCall);
+
+		let err = ContractCallDecoder::new().decode(&builder.to_bytes()).unwrap_err();
+
+		assert_eq!(
+			err,
+			BuilderError::InvalidScript(
+				"System.Contract.Call at offset 13 takes operands computed at runtime".to_string()
+			)
+		);
+	}
+
+	/// Decodes a transfer of GAS preceded by `prefix` and followed by `suffix`.
+	fn decode_around(
+		prefix: &[u8],
+		suffix: &[u8],
+	) -> Result<Vec<DecodedContractCall>, BuilderError> {
+		let to = H160::from_slice(&[0x11; 20]);
+		let transfer = ScriptBuilder::new()
+			.contract_call(
+				&gas_token(),
+				"transfer",
+				&[to.into(), to.into(), ContractParameter::integer(10), ContractParameter::any()],
+				None,
+			)
+			.unwrap()
+			.to_bytes();
+		ContractCallDecoder::new().decode(&[prefix, &transfer, suffix].concat())
+	}
+
+	fn syscall(name: &str) -> Vec<u8> {
+		let mut script = vec![OpCode::Syscall as u8];
+		script.extend(&name.as_bytes().hash256()[..4]);
+		script
+	}
+
+	#[test]
+	fn test_decode_rejects_control_flow() {
+		let ret = OpCode::Ret as u8;
+		let pointer = vec![OpCode::PushA as u8, 0, 0, 0, 0, OpCode::CallA as u8];
+		for (prefix, suffix, message) in [
+			// A jump over the transfer
+			(vec![OpCode::Jmp as u8, 0x00], vec![], "JMP at offset 0 changes the control flow"),
+			(vec![OpCode::JmpIfL as u8, 0, 0, 0, 0], vec![], "JMPIFL at offset 0"),
+			// The transfer run again as a function
+			(vec![], vec![OpCode::Call as u8, 0x00, ret], "CALL at offset 86"),
+			(vec![], pointer, "CALLA at offset 91"),
+			(vec![OpCode::Try as u8, 0x00, 0x00], vec![], "TRY at offset 0"),
+			(vec![], vec![OpCode::EndTry as u8, 0x00], "ENDTRY at offset 86"),
+			(vec![], vec![OpCode::EndFinally as u8], "ENDFINALLY at offset 86"),
+		] {
+			let err = decode_around(&prefix, &suffix).unwrap_err();
+			assert!(
+				matches!(&err, BuilderError::InvalidScript(text) if text.starts_with(message)),
+				"{:?}",
+				err
+			);
+		}
+	}
+
+	#[test]
+	fn test_decode_rejects_hidden_code() {
+		let err = decode_around(&[], &[OpCode::CallT as u8, 0x00, 0x00]).unwrap_err();
+		assert_eq!(
+			err,
+			BuilderError::InvalidScript(
+				"CALLT at offset 86 calls a method token, which transaction scripts can't declare"
+					.to_string()
+			)
+		);
+
+		for service in ["System.Runtime.LoadScript", "System.Contract.CallNative"] {
+			let err = decode_around(&syscall(service), &[]).unwrap_err();
+			let hash = hex::encode(&service.as_bytes().hash256()[..4]);
+			assert_eq!(
+				err,
+				BuilderError::InvalidScript(format!(
+					"SYSCALL at offset 0 calls the interop service {} that may run code the script \
+					 doesn't show",
+					hash
+				))
+			);
+		}
+
+		// Other interop services only read the state or emit events
+		let calls = decode_around(&syscall("System.Runtime.GetTime"), &[]).unwrap();
+		assert_eq!(calls.len(), 1);
+		let calls = decode_around(&[], &syscall("System.Runtime.Notify")).unwrap();
+		assert_eq!(calls.len(), 1);
+	}
+}
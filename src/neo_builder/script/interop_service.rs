@@ -15,7 +15,7 @@ lazy_static! {
 		Arc::new(Mutex::new(HashMap::new()));
 }
 
-#[derive(EnumString, EnumIter, Display, Copy, Clone, PartialEq, Eq)]
+#[derive(EnumString, EnumIter, Display, Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum InteropService {
 	#[strum(serialize = "System.Crypto.CheckSig")]
 	SystemCryptoCheckSig,
@@ -6,10 +6,18 @@
 //!
 //! ## Modules
 //!
+//! ### `contract_call_decoder`
+//!
+//! Provides the [`ContractCallDecoder`] struct for explaining the contract calls a script will make.
+//!
 //! ### `interop_service`
 //!
 //! Contains the [`InteropService`] enum, which represents various system calls available in the Neo virtual machine.
 //!
+//! ### `script_assembler`
+//!
+//! Provides the [`ScriptAssembler`] struct for compiling human-readable NeoVM assembly into script bytes.
+//!
 //! ### `script_builder`
 //!
 //! Provides the [`ScriptBuilder`] struct for constructing Neo smart contract scripts programmatically.
@@ -23,17 +31,23 @@
 //! To use the functionality provided by this module, you can import the necessary components:
 //!
 //! ```rust
-//! use neo3::neo_builder::{InteropService, ScriptBuilder, ScriptReader};
+//! use neo3::neo_builder::{ContractCallDecoder, InteropService, ScriptAssembler, ScriptBuilder, ScriptReader};
 //! ```
 //!
+//! [`ContractCallDecoder`]: contract_call_decoder::ContractCallDecoder
 //! [`InteropService`]: interop_service::InteropService
+//! [`ScriptAssembler`]: script_assembler::ScriptAssembler
 //! [`ScriptBuilder`]: script_builder::ScriptBuilder
 //! [`ScriptReader`]: script_reader::ScriptReader
 
+pub use contract_call_decoder::*;
 pub use interop_service::*;
+pub use script_assembler::*;
 pub use script_builder::*;
 pub use script_reader::*;
 
+mod contract_call_decoder;
 mod interop_service;
+mod script_assembler;
 mod script_builder;
 mod script_reader;
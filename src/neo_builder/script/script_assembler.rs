@@ -0,0 +1,641 @@
+use std::{collections::HashMap, str::FromStr};
+
+use num_bigint::BigInt;
+
+use crate::{
+	builder::{BuilderError, InteropService, ScriptBuilder},
+	neo_vm::StackItemType,
+	Bytes, OpCode,
+};
+
+/// Branch instructions whose short form can be widened automatically, paired with their long form.
+const BRANCHES: [(OpCode, OpCode); 11] = [
+	(OpCode::Jmp, OpCode::JmpL),
+	(OpCode::JmpIf, OpCode::JmpIfL),
+	(OpCode::JmpIfNot, OpCode::JmpIfNotL),
+	(OpCode::JmpEq, OpCode::JmpEqL),
+	(OpCode::JmpNe, OpCode::JmpNeL),
+	(OpCode::JmpGt, OpCode::JmpGtL),
+	(OpCode::JmpGe, OpCode::JmpGeL),
+	(OpCode::JmpLt, OpCode::JmpLtL),
+	(OpCode::JmpLe, OpCode::JmpLeL),
+	(OpCode::Call, OpCode::CallL),
+	(OpCode::EndTry, OpCode::EndTryL),
+];
+
+/// Compiles human-readable NeoVM assembly into script bytes.
+///
+/// The source contains one instruction per line. Opcode names are case-insensitive and may be
+/// written with or without underscores (`JMPIF_L`, `JmpIfL`). Comments start with `;`, `#` or `//`.
+///
+/// - **Labels**: `name:` marks the next instruction. Jumps, calls, `ENDTRY`, `TRY` and `PUSHA`
+///   accept a label or a signed relative offset (`+5`, `-3`). Short jumps are widened to their
+///   `_L` form automatically when the target is out of range; writing the `_L` form forces it.
+/// - **`TRY catch, finally`**: either target may be `0` when the block has no such handler.
+/// - **`PUSH literal`**: pushes an integer, `true`, `false`, `null`, a `"string"` or `0x` hex bytes
+///   using the shortest encoding, like [`ScriptBuilder`].
+/// - **Explicit operands**: `PUSHINT16 -5`, `PUSHDATA1 "Neo"`, `SYSCALL System.Contract.Call`,
+///   `INITSLOT 1, 2`, `LDLOC 3`, `CONVERT ByteString`, `CALLT 0`.
+/// - **Raw operands**: any fixed-size operand may be given as `0x` hex of exactly its size.
+///
+/// The output of [`ScriptReader::convert_to_op_code_string`](crate::neo_builder::ScriptReader::convert_to_op_code_string)
+/// for `PUSHDATA` (`PUSHDATA1 5 48656c6c6f`) and `SYSCALL` (`SYSCALL 9bf667ce`) is accepted as well.
+///
+/// # Example
+///
+/// ```rust
+/// use neo3::neo_builder::ScriptAssembler;
+///
+/// let script = ScriptAssembler::assemble(
+///     r#"
+///         PUSH 3
+///     loop:
+///         DEC
+///         DUP
+///         JMPIF loop      ; jump back while the counter is not zero
+///         PUSH "done"
+///         SYSCALL System.Runtime.Log
+///     "#,
+/// )
+/// .unwrap();
+/// assert_eq!(hex::encode(&script[..5]), "139d4a24fe");
+/// ```
+pub struct ScriptAssembler;
+
+impl ScriptAssembler {
+	/// Assembles `source` into script bytes.
+	///
+	/// Errors are reported as [`BuilderError::InvalidScript`] with the offending line number.
+	pub fn assemble(source: &str) -> Result<Bytes, BuilderError> {
+		let opcodes = Self::opcode_names();
+		let mut items: Vec<(usize, Item)> = Vec::new();
+		let mut labels: HashMap<String, usize> = HashMap::new();
+
+		for (index, line) in source.lines().enumerate() {
+			let number = index + 1;
+			let error = |message: String| line_error(number, message);
+			let mut tokens = tokenize(line).map_err(error)?;
+
+			while let Some(Token::Word(word)) = tokens.first() {
+				let Some(label) = word.strip_suffix(':') else {
+					break;
+				};
+				if !is_label(label) {
+					return Err(error(format!("Invalid label name '{}'", label)));
+				}
+				if labels.insert(label.to_string(), items.len()).is_some() {
+					return Err(error(format!("Duplicate label '{}'", label)));
+				}
+				tokens.remove(0);
+			}
+
+			let Some(first) = tokens.first() else {
+				continue;
+			};
+			let mnemonic = match first {
+				Token::Word(word) => word.to_uppercase().replace('_', ""),
+				Token::Str(_) => return Err(error("Expected an opcode".to_string())),
+			};
+			let operands = &tokens[1..];
+
+			let item = if mnemonic == "PUSH" {
+				Item::Bytes(Self::push_literal(operands).map_err(error)?)
+			} else {
+				let opcode = *opcodes
+					.get(&mnemonic)
+					.ok_or_else(|| error(format!("Unknown opcode '{}'", mnemonic)))?;
+				Self::instruction(opcode, operands).map_err(error)?
+			};
+			items.push((number, item));
+		}
+
+		// Widen short branches until every target is in range. Widening only ever moves targets
+		// further away, so this terminates.
+		loop {
+			let offsets = Self::offsets(&items);
+			let mut changed = false;
+			for (index, (number, item)) in items.iter_mut().enumerate() {
+				let resolve = |target: &Target| {
+					resolve(target, offsets[index], &labels, &offsets)
+						.map_err(|message| line_error(*number, message))
+				};
+				match item {
+					Item::Branch { target, long_form: long_form @ false, fixed: false, .. } =>
+						if i8::try_from(resolve(target)?).is_err() {
+							*long_form = true;
+							changed = true;
+						},
+					Item::Try { catch, finally, long_form: long_form @ false, fixed: false } =>
+						if i8::try_from(resolve(catch)?).is_err()
+							|| i8::try_from(resolve(finally)?).is_err()
+						{
+							*long_form = true;
+							changed = true;
+						},
+					_ => {},
+				}
+			}
+			if !changed {
+				break;
+			}
+		}
+
+		let offsets = Self::offsets(&items);
+		let mut script = Vec::with_capacity(offsets[items.len()]);
+		for (index, (number, item)) in items.iter().enumerate() {
+			let resolve = |target: &Target| {
+				resolve(target, offsets[index], &labels, &offsets)
+					.map_err(|message| line_error(*number, message))
+			};
+			let short = |offset: i32| {
+				i8::try_from(offset).map(|offset| offset as u8).map_err(|_| {
+					line_error(*number, format!("Offset {} does not fit in a short jump", offset))
+				})
+			};
+			match item {
+				Item::Bytes(bytes) => script.extend_from_slice(bytes),
+				Item::Branch { short: opcode, long, target, long_form, .. } => {
+					let offset = resolve(target)?;
+					if *long_form {
+						script.push(*long as u8);
+						script.extend_from_slice(&offset.to_le_bytes());
+					} else {
+						script.extend_from_slice(&[*opcode as u8, short(offset)?]);
+					}
+				},
+				Item::Try { catch, finally, long_form, .. } => {
+					let (catch, finally) = (resolve(catch)?, resolve(finally)?);
+					if *long_form {
+						script.push(OpCode::TryL as u8);
+						script.extend_from_slice(&catch.to_le_bytes());
+						script.extend_from_slice(&finally.to_le_bytes());
+					} else {
+						script.extend_from_slice(&[
+							OpCode::Try as u8,
+							short(catch)?,
+							short(finally)?,
+						]);
+					}
+				},
+				Item::PushA(target) => {
+					script.push(OpCode::PushA as u8);
+					script.extend_from_slice(&resolve(target)?.to_le_bytes());
+				},
+			}
+		}
+		Ok(script)
+	}
+
+	fn opcode_names() -> HashMap<String, OpCode> {
+		let mut names: HashMap<String, OpCode> = (0..=u8::MAX)
+			.filter_map(|byte| OpCode::try_from(byte).ok())
+			.map(|opcode| (format!("{:?}", opcode).to_uppercase(), opcode))
+			.collect();
+		names.insert("PUSHT".to_string(), OpCode::PushTrue);
+		names.insert("PUSHF".to_string(), OpCode::PushFalse);
+		names
+	}
+
+	/// Returns the offset of every item, followed by the total script length.
+	fn offsets(items: &[(usize, Item)]) -> Vec<usize> {
+		let mut offsets = Vec::with_capacity(items.len() + 1);
+		let mut offset = 0;
+		for (_, item) in items {
+			offsets.push(offset);
+			offset += item.size();
+		}
+		offsets.push(offset);
+		offsets
+	}
+
+	fn push_literal(operands: &[Token]) -> Result<Bytes, String> {
+		let [operand] = operands else {
+			return Err("PUSH takes exactly one literal".to_string());
+		};
+		let mut builder = ScriptBuilder::new();
+		match operand {
+			Token::Str(bytes) => {
+				builder.push_data(bytes.clone());
+			},
+			Token::Word(word) => match word.to_lowercase().as_str() {
+				"true" => {
+					builder.push_bool(true);
+				},
+				"false" => {
+					builder.push_bool(false);
+				},
+				"null" => {
+					builder.op_code(&[OpCode::PushNull]);
+				},
+				_ if word.starts_with("0x") => {
+					builder.push_data(parse_hex(word)?);
+				},
+				_ => {
+					builder.push_integer(parse_integer(word)?);
+				},
+			},
+		}
+		Ok(builder.to_bytes())
+	}
+
+	fn instruction(opcode: OpCode, operands: &[Token]) -> Result<Item, String> {
+		if let Some((short, long)) = BRANCHES.iter().find(|(s, l)| *s == opcode || *l == opcode) {
+			let [target] = operands else {
+				return Err(format!("{} takes exactly one target", opcode));
+			};
+			let fixed = opcode == *long;
+			return Ok(Item::Branch {
+				short: *short,
+				long: *long,
+				target: parse_target(target)?,
+				long_form: fixed,
+				fixed,
+			});
+		}
+
+		match opcode {
+			OpCode::Try | OpCode::TryL => {
+				let [catch, finally] = operands else {
+					return Err(format!("{} takes a catch and a finally target", opcode));
+				};
+				let fixed = opcode == OpCode::TryL;
+				Ok(Item::Try {
+					catch: parse_target(catch)?,
+					finally: parse_target(finally)?,
+					long_form: fixed,
+					fixed,
+				})
+			},
+			OpCode::PushA => {
+				let [target] = operands else {
+					return Err("PUSHA takes exactly one target".to_string());
+				};
+				Ok(Item::PushA(parse_target(target)?))
+			},
+			_ => {
+				let mut bytes = vec![opcode as u8];
+				bytes.extend(Self::operand(opcode, operands)?);
+				Ok(Item::Bytes(bytes))
+			},
+		}
+	}
+
+	fn operand(opcode: OpCode, operands: &[Token]) -> Result<Bytes, String> {
+		let Some(operand_size) = opcode.operand_size() else {
+			if !operands.is_empty() {
+				return Err(format!("{} takes no operand", opcode));
+			}
+			return Ok(Vec::new());
+		};
+
+		let prefix_size = *operand_size.prefix_size() as usize;
+		if prefix_size > 0 {
+			let data = match operands {
+				[Token::Str(bytes)] => bytes.clone(),
+				[Token::Word(word)] => parse_hex(word)?,
+				// The `PUSHDATA1 5 48656c6c6f` form produced by `convert_to_op_code_string`.
+				[Token::Word(length), Token::Word(hex)] => {
+					let data =
+						hex::decode(hex).map_err(|_| format!("Invalid hex data '{}'", hex))?;
+					if length.parse::<usize>().ok() != Some(data.len()) {
+						return Err(format!("Length {} does not match the data", length));
+					}
+					data
+				},
+				_ => return Err(format!("{} takes a string or hex data", opcode)),
+			};
+			let max =
+				if prefix_size == 4 { u32::MAX as u64 } else { (1u64 << (prefix_size * 8)) - 1 };
+			if data.len() as u64 > max {
+				return Err(format!("{} bytes do not fit in {}", data.len(), opcode));
+			}
+			let mut bytes = (data.len() as u32).to_le_bytes()[..prefix_size].to_vec();
+			bytes.extend(data);
+			return Ok(bytes);
+		}
+
+		let size = *operand_size.size() as usize;
+		if let [Token::Word(word)] = operands {
+			if word.starts_with("0x") {
+				let bytes = parse_hex(word)?;
+				if bytes.len() != size {
+					return Err(format!("{} takes a {} byte operand", opcode, size));
+				}
+				return Ok(bytes);
+			}
+		}
+
+		match opcode {
+			OpCode::PushInt8
+			| OpCode::PushInt16
+			| OpCode::PushInt32
+			| OpCode::PushInt64
+			| OpCode::PushInt128
+			| OpCode::PushInt256 => {
+				let [Token::Word(word)] = operands else {
+					return Err(format!("{} takes an integer", opcode));
+				};
+				let value = parse_integer(word)?;
+				let mut bytes = value.to_signed_bytes_le();
+				if bytes.len() > size {
+					return Err(format!("{} does not fit in {}", value, opcode));
+				}
+				let padding = if value < BigInt::from(0) { 0xff } else { 0 };
+				bytes.resize(size, padding);
+				Ok(bytes)
+			},
+			OpCode::Syscall => {
+				let [Token::Word(word)] = operands else {
+					return Err("SYSCALL takes an interop service name".to_string());
+				};
+				if let Ok(service) = InteropService::from_str(word) {
+					return hex::decode(service.hash()).map_err(|err| err.to_string());
+				}
+				match hex::decode(word) {
+					Ok(bytes) if bytes.len() == 4 => Ok(bytes),
+					_ => Err(format!("Unknown interop service '{}'", word)),
+				}
+			},
+			OpCode::CallT => {
+				let [Token::Word(word)] = operands else {
+					return Err("CALLT takes a method token index".to_string());
+				};
+				let token: u16 =
+					word.parse().map_err(|_| format!("Invalid token index '{}'", word))?;
+				Ok(token.to_le_bytes().to_vec())
+			},
+			OpCode::InitSlot => {
+				let [Token::Word(locals), Token::Word(arguments)] = operands else {
+					return Err("INITSLOT takes a local variable and an argument count".to_string());
+				};
+				Ok(vec![parse_u8(locals)?, parse_u8(arguments)?])
+			},
+			OpCode::NewArrayT | OpCode::IsType | OpCode::Convert => {
+				let [Token::Word(word)] = operands else {
+					return Err(format!("{} takes a stack item type", opcode));
+				};
+				let item_type = (0..=u8::MAX)
+					.filter_map(|byte| StackItemType::try_from(byte).ok())
+					.find(|item_type| item_type.to_string().eq_ignore_ascii_case(word))
+					.map(|item_type| item_type as u8);
+				match item_type {
+					Some(item_type) => Ok(vec![item_type]),
+					None => Ok(vec![parse_u8(word)
+						.map_err(|_| format!("Unknown stack item type '{}'", word))?]),
+				}
+			},
+			_ => {
+				let [Token::Word(word)] = operands else {
+					return Err(format!("{} takes a single operand", opcode));
+				};
+				Ok(vec![parse_u8(word)?])
+			},
+		}
+	}
+}
+
+/// An assembled line, with the branch targets still to be resolved.
+enum Item {
+	Bytes(Bytes),
+	Branch { short: OpCode, long: OpCode, target: Target, long_form: bool, fixed: bool },
+	Try { catch: Target, finally: Target, long_form: bool, fixed: bool },
+	PushA(Target),
+}
+
+impl Item {
+	fn size(&self) -> usize {
+		match self {
+			Item::Bytes(bytes) => bytes.len(),
+			Item::Branch { long_form, .. } =>
+				if *long_form {
+					5
+				} else {
+					2
+				},
+			Item::Try { long_form, .. } =>
+				if *long_form {
+					9
+				} else {
+					3
+				},
+			Item::PushA(_) => 5,
+		}
+	}
+}
+
+enum Target {
+	Label(String),
+	Offset(i32),
+}
+
+enum Token {
+	Word(String),
+	Str(Vec<u8>),
+}
+
+fn line_error(number: usize, message: String) -> BuilderError {
+	BuilderError::InvalidScript(format!("line {}: {}", number, message))
+}
+
+fn resolve(
+	target: &Target,
+	offset: usize,
+	labels: &HashMap<String, usize>,
+	offsets: &[usize],
+) -> Result<i32, String> {
+	match target {
+		Target::Offset(relative) => Ok(*relative),
+		Target::Label(label) => {
+			let index = labels.get(label).ok_or_else(|| format!("Unknown label '{}'", label))?;
+			i32::try_from(offsets[*index] as i64 - offset as i64)
+				.map_err(|_| format!("Label '{}' is out of range", label))
+		},
+	}
+}
+
+fn is_label(name: &str) -> bool {
+	let mut chars = name.chars();
+	matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+		&& chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+}
+
+fn parse_target(token: &Token) -> Result<Target, String> {
+	match token {
+		Token::Word(word) if is_label(word) => Ok(Target::Label(word.clone())),
+		Token::Word(word) => word
+			.trim_start_matches('+')
+			.parse()
+			.map(Target::Offset)
+			.map_err(|_| format!("Invalid target '{}'", word)),
+		Token::Str(_) => Err("Expected a label or an offset".to_string()),
+	}
+}
+
+fn parse_integer(word: &str) -> Result<BigInt, String> {
+	BigInt::from_str(word).map_err(|_| format!("Invalid integer '{}'", word))
+}
+
+fn parse_u8(word: &str) -> Result<u8, String> {
+	word.parse()
+		.map_err(|_| format!("Invalid operand '{}', expected 0 to 255", word))
+}
+
+fn parse_hex(word: &str) -> Result<Bytes, String> {
+	let hex = word.strip_prefix("0x").unwrap_or(word);
+	hex::decode(hex).map_err(|_| format!("Invalid hex data '{}'", word))
+}
+
+/// Splits a line into words and string literals, dropping commas and comments.
+fn tokenize(line: &str) -> Result<Vec<Token>, String> {
+	let mut tokens = Vec::new();
+	let mut chars = line.char_indices().peekable();
+	while let Some(&(index, c)) = chars.peek() {
+		match c {
+			c if c.is_whitespace() || c == ',' => {
+				chars.next();
+			},
+			';' | '#' => break,
+			'/' if line[index..].starts_with("//") => break,
+			'"' => {
+				chars.next();
+				let mut text = String::new();
+				loop {
+					match chars.next().map(|(_, c)| c) {
+						Some('"') => break,
+						Some('\\') => match chars.next().map(|(_, c)| c) {
+							Some('n') => text.push('\n'),
+							Some('r') => text.push('\r'),
+							Some('t') => text.push('\t'),
+							Some('0') => text.push('\0'),
+							Some(c @ ('"' | '\\')) => text.push(c),
+							Some(c) => return Err(format!("Invalid escape sequence '\\{}'", c)),
+							None => return Err("Unterminated string literal".to_string()),
+						},
+						Some(c) => text.push(c),
+						None => return Err("Unterminated string literal".to_string()),
+					}
+				}
+				tokens.push(Token::Str(text.into_bytes()));
+			},
+			_ => {
+				let mut word = String::new();
+				while let Some(&(_, c)) = chars.peek() {
+					if c.is_whitespace() || matches!(c, ',' | ';' | '#' | '"') {
+						break;
+					}
+					word.push(c);
+					chars.next();
+				}
+				tokens.push(Token::Word(word));
+			},
+		}
+	}
+	Ok(tokens)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		builder::ScriptReader,
+		neo_crypto::Secp256r1PublicKey,
+		neo_types::{StackItem, VMState},
+		neo_vm::ExecutionEngine,
+	};
+
+	#[test]
+	fn test_assemble_verification_script() {
+		let key = "035fdb1d1f06759547020891ae97c729327853aeb1256b6fe0473bc2e9fa42ff50";
+		let public_key = Secp256r1PublicKey::from_encoded(key).unwrap();
+		let source = format!("PUSHDATA1 0x{}\nSYSCALL System.Crypto.CheckSig", key);
+
+		let script = ScriptAssembler::assemble(&source).unwrap();
+		assert_eq!(script, ScriptBuilder::build_verification_script(&public_key));
+	}
+
+	#[test]
+	fn test_assemble_push_literals() {
+		let script = ScriptAssembler::assemble(
+			"PUSH -1\nPUSH 16\nPUSH 255\nPUSH true\nPUSH null\nPUSH \"Neo\"\nPUSH 0x0102",
+		)
+		.unwrap();
+		assert_eq!(hex::encode(script), "0f2001ff00080b0c034e656f0c020102");
+	}
+
+	#[test]
+	fn test_assemble_round_trips_op_code_string() {
+		let script =
+			hex::decode("0c0548656c6c6f0c05576f726c642150419bf667ce41e63f18841140").unwrap();
+		let listing = ScriptReader::convert_to_op_code_string(&script);
+		assert_eq!(ScriptAssembler::assemble(&listing).unwrap(), script);
+	}
+
+	#[test]
+	fn test_assemble_resolves_labels() {
+		let script = ScriptAssembler::assemble(
+			"
+			; count down from 3 to 0
+			    PUSH 3
+			loop:
+			    DEC
+			    DUP
+			    JMPIF loop
+			    JMP_L end
+			    PUSH 100
+			end:
+			    RET
+			",
+		)
+		.unwrap();
+		assert_eq!(hex::encode(&script), "139d4a24fe2307000000006440");
+
+		let result = ExecutionEngine::run(script);
+		assert_eq!(result.state, VMState::Halt);
+		assert_eq!(result.stack, vec![StackItem::Integer { value: 0 }]);
+	}
+
+	#[test]
+	fn test_assemble_widens_out_of_range_jumps() {
+		let padding = "NOP\n".repeat(200);
+		let source = format!("JMP end\n{}end:\nRET", padding);
+		let script = ScriptAssembler::assemble(&source).unwrap();
+		assert_eq!(script[0], OpCode::JmpL as u8);
+		assert_eq!(i32::from_le_bytes(script[1..5].try_into().unwrap()), 205);
+
+		let source = format!("start:\n{}JMP start", padding);
+		let script = ScriptAssembler::assemble(&source).unwrap();
+		assert_eq!(script[200], OpCode::JmpL as u8);
+		assert_eq!(i32::from_le_bytes(script[201..205].try_into().unwrap()), -200);
+	}
+
+	#[test]
+	fn test_assemble_try_and_operands() {
+		let script = ScriptAssembler::assemble(
+			"
+			INITSLOT 1, 2
+			TRY handler, 0
+			PUSHINT16 -2
+			THROW
+			handler: DROP
+			ENDTRY done
+			done: CONVERT Integer
+			",
+		)
+		.unwrap();
+		assert_eq!(hex::encode(script), "5701023b070001feff3a453d02db21");
+	}
+
+	#[test]
+	fn test_assemble_errors() {
+		let error = |source: &str| match ScriptAssembler::assemble(source) {
+			Err(BuilderError::InvalidScript(message)) => message,
+			other => panic!("expected an error, got {:?}", other),
+		};
+		assert_eq!(error("PUSH1\nFOO"), "line 2: Unknown opcode 'FOO'");
+		assert_eq!(error("JMP nowhere"), "line 1: Unknown label 'nowhere'");
+		assert_eq!(error("a:\na: RET"), "line 2: Duplicate label 'a'");
+		assert!(error("PUSHINT8 300").contains("does not fit"));
+		assert!(error("RET 1").contains("takes no operand"));
+		assert!(error("PUSH \"open").contains("Unterminated"));
+	}
+}
@@ -153,7 +153,7 @@ impl ScriptBuilder {
 		if params.is_empty() {
 			self.op_code(&[OpCode::NewArray0]);
 		} else {
-			self.push_params(params);
+			self.push_params(params)?;
 		}
 
 		Ok(self
@@ -162,7 +162,7 @@ impl ScriptBuilder {
 				None => CallFlags::All.value(),
 			}))
 			.push_data(method.as_bytes().to_vec())
-			.push_data(hash160.to_vec())
+			.push_data(little_endian(hash160.as_bytes()))
 			.sys_call(InteropService::SystemContractCall))
 	}
 
@@ -222,7 +222,9 @@ impl ScriptBuilder {
 	/// ]);
 	/// ```
 	pub fn push_params(&mut self, params: &[ContractParameter]) -> Result<&mut Self, BuilderError> {
-		for param in params {
+		// `PACK` pops the first array element first, so the parameters go on the stack in
+		// reverse order.
+		for param in params.iter().rev() {
 			self.push_param(param).map_err(|e| {
 				BuilderError::IllegalArgument(format!("Failed to push parameter: {}", e))
 			})?;
@@ -273,11 +275,20 @@ impl ScriptBuilder {
 				})?;
 				self.push_data(bytes)
 			},
-			ParameterValue::Signature(b) | ParameterValue::PublicKey(b) => {
-				self.push_data(b.as_bytes().to_vec())
+			ParameterValue::Signature(h)
+			| ParameterValue::PublicKey(h)
+			| ParameterValue::H160(h)
+			| ParameterValue::H256(h) => {
+				// These values are stored hex-encoded, hashes in their big-endian display order
+				let bytes = hex::decode(h.trim_start_matches("0x")).map_err(|e| {
+					BuilderError::IllegalArgument(format!("Failed to decode hex value: {}", e))
+				})?;
+				match param.get_type() {
+					ContractParameterType::H160 | ContractParameterType::H256 =>
+						self.push_data(little_endian(&bytes)),
+					_ => self.push_data(bytes),
+				}
 			},
-			ParameterValue::H160(h) => self.push_data(h.as_bytes().to_vec()),
-			ParameterValue::H256(h) => self.push_data(h.as_bytes().to_vec()),
 			ParameterValue::String(s) => self.push_data(s.as_bytes().to_vec()),
 			ParameterValue::Array(arr) => self.push_array(arr).map_err(|e| {
 				BuilderError::IllegalArgument(format!("Failed to push array: {}", e))
@@ -319,20 +330,13 @@ impl ScriptBuilder {
 	pub fn push_integer(&mut self, i: BigInt) -> &mut Self {
 		if i >= BigInt::from(-1) && i <= BigInt::from(16) {
 			self.op_code(
-				vec![OpCode::try_from(i.to_i32().unwrap() as u8 + OpCode::Push0 as u8).unwrap()]
+				vec![OpCode::try_from((OpCode::Push0 as i32 + i.to_i32().unwrap()) as u8).unwrap()]
 					.as_slice(),
 			);
 		} else {
-			let mut bytes = i.to_signed_bytes_le();
-
-			// Remove unnecessary zero padding for positive numbers
-			// BigInt::to_signed_bytes_le() adds extra zero bytes for positive numbers
-			// to ensure they're not interpreted as negative
-			// For positive numbers, we can remove trailing zeros if the previous byte doesn't have the sign bit set
-			// OR if the number is positive and we have a trailing zero
-			while bytes.len() > 1 && bytes[bytes.len() - 1] == 0 && !i.is_negative() {
-				bytes.pop();
-			}
+			// BigInt::to_signed_bytes_le() already returns the shortest two's complement encoding.
+			// A trailing zero byte on a positive number is its sign byte and must be kept.
+			let bytes = i.to_signed_bytes_le();
 
 			let len = bytes.len();
 
@@ -491,7 +495,7 @@ impl ScriptBuilder {
 		if arr.is_empty() {
 			self.op_code(&[OpCode::NewArray0]);
 		} else {
-			self.push_params(arr);
+			self.push_params(arr)?;
 		};
 		Ok(self)
 	}
@@ -618,7 +622,7 @@ impl ScriptBuilder {
 	) -> Result<Bytes, BuilderError> {
 		let mut sb = ScriptBuilder::new();
 		sb.op_code(&[OpCode::Abort])
-			.push_data(sender.to_vec())
+			.push_data(little_endian(sender.as_bytes()))
 			.push_integer(BigInt::from(nef_checksum))
 			.push_data(name.as_bytes().to_vec());
 		Ok(sb.to_bytes())
@@ -749,6 +753,12 @@ impl ScriptBuilder {
 	}
 }
 
+/// Returns the little-endian form of a hash held in its big-endian display order, which is how
+/// scripts pass hashes to contracts.
+fn little_endian(hash: &[u8]) -> Vec<u8> {
+	hash.iter().rev().copied().collect()
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -803,11 +813,11 @@ mod tests {
 		// Test larger integers
 		let mut builder = ScriptBuilder::new();
 		builder.push_integer(BigInt::from(255));
-		assert_builder(&builder, &[OpCode::PushInt8 as u8, 0xff]);
+		assert_builder(&builder, &[OpCode::PushInt16 as u8, 0xff, 0x00]);
 
 		let mut builder = ScriptBuilder::new();
 		builder.push_integer(BigInt::from(65535));
-		assert_builder(&builder, &[OpCode::PushInt16 as u8, 0xff, 0xff]);
+		assert_builder(&builder, &[OpCode::PushInt32 as u8, 0xff, 0xff, 0x00, 0x00]);
 
 		// Test negative integers - update expectations to match our more efficient implementation
 		let mut builder = ScriptBuilder::new();
@@ -913,6 +923,23 @@ mod tests {
 		assert_eq!(expected, manual_expected);
 	}
 
+	#[test]
+	fn test_contract_call() {
+		let gas = H160::from_str("0xd2a4cff31913016155e38e474a2c06d08be276cf").unwrap();
+		let account = H160::from_str("0x969a77db482f74ce27105f760efa139223431394").unwrap();
+
+		let script = ScriptBuilder::new()
+			.contract_call(&gas, "balanceOf", &[ContractParameter::h160(&account)], None)
+			.unwrap()
+			.to_bytes();
+
+		// Hashes are pushed little-endian and the arguments are packed in parameter order
+		assert_eq!(
+			script.to_hex_string(),
+			"0c14941343239213fa0e765f1027ce742f48db779a9611c01f0c0962616c616e63654f660c14cf76e28bd0062c4a478ee35561011319f3cfa4d241627d5b52"
+		);
+	}
+
 	fn assert_builder(builder: &ScriptBuilder, expected: &[u8]) {
 		assert_eq!(builder.to_bytes().to_vec(), expected);
 	}
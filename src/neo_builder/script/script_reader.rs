@@ -1,10 +1,13 @@
+use std::fmt;
+
+use num_bigint::BigInt;
+
 use crate::{
 	builder::{BuilderError, InteropService},
-	codec::Decoder,
 	neo_crypto::utils::ToHexString,
-	Bytes, OpCode, OperandSize,
+	neo_vm::{Instruction as VMInstruction, StackItemType, VMError},
+	Bytes, OpCode,
 };
-use tokio::io::AsyncReadExt;
 
 /// A utility struct for reading and interpreting Neo smart contract scripts.
 pub struct ScriptReader;
@@ -38,6 +41,9 @@ impl ScriptReader {
 
 	/// Converts a byte script to a human-readable string of OpCodes.
 	///
+	/// Decoding stops at the first malformed instruction. Use [`ScriptReader::disassemble`] to
+	/// get a structured listing and an error for malformed scripts.
+	///
 	/// # Arguments
 	///
 	/// * `script` - The byte script to convert.
@@ -57,71 +63,306 @@ impl ScriptReader {
 	/// // Output: OpCodes: PUSHDATA1 5 48656c6c6f
 	/// ```
 	pub fn convert_to_op_code_string(script: &Bytes) -> String {
-		let mut reader = Decoder::new(script);
 		let mut result = String::new();
+		let mut offset = 0;
+
+		while offset < script.len() {
+			let Ok(instruction) = VMInstruction::decode(script, offset) else {
+				break;
+			};
+			result.push_str(&format!("{:?}", instruction.opcode).to_uppercase());
 
-		while reader.pointer().clone() < script.len() {
-			if let Ok(op_code) = OpCode::try_from(reader.read_u8()) {
-				// Add the OpCode to the result string
-				result.push_str(&format!("{:?}", op_code).to_uppercase());
-
-				// Handle operands if present
-				if let Some(size) = op_code.operand_size() {
-					if size.size().clone() > 0 {
-						// Fixed size operand
-						result.push_str(&format!(
-							" {}",
-							reader
-								.read_bytes(size.size().clone() as usize)
-								.unwrap()
-								.to_hex_string()
-						));
-					} else if size.prefix_size().clone() > 0 {
-						// Variable size operand with prefix
-						let prefix_size = Self::get_prefix_size(&mut reader, size).unwrap();
-						result.push_str(&format!(
-							" {} {}",
-							prefix_size,
-							reader.read_bytes(prefix_size).unwrap().to_hex_string()
-						));
-					}
+			if let Some(size) = instruction.opcode.operand_size() {
+				if *size.prefix_size() > 0 {
+					result.push_str(&format!(
+						" {} {}",
+						instruction.operand.len(),
+						instruction.operand.to_hex_string()
+					));
+				} else if *size.size() > 0 {
+					result.push_str(&format!(" {}", instruction.operand.to_hex_string()));
 				}
-				result.push('\n');
 			}
+			result.push('\n');
+			offset += instruction.size;
 		}
 		result
 	}
 
-	/// Helper function to get the size of a variable-length operand.
-	///
-	/// # Arguments
-	///
-	/// * `reader` - The Decoder to read from.
-	/// * `size` - The OperandSize specifying the prefix size.
-	///
-	/// # Returns
+	/// Disassembles a script into a list of structured instructions.
 	///
-	/// A Result containing the size of the operand or a BuilderError.
+	/// Operands are decoded: jump, call and try offsets are resolved to absolute targets,
+	/// SYSCALL hashes are mapped to their [`InteropService`], and pushed integers and data are
+	/// decoded. Truncated operands, unknown opcodes, invalid type operands and targets that do
+	/// not point to the start of an instruction are reported as [`BuilderError::InvalidScript`].
 	///
 	/// # Example
 	///
-	/// ```rust,no_run
+	/// ```rust
 	/// use neo3::neo_builder::ScriptReader;
-	/// use neo3::neo_codec::Decoder;
-	/// use neo3::neo_types::OperandSize;
 	///
-	/// let mut decoder = Decoder::new(&[0x05]); // Example: prefix size of 5
-	/// let operand_size = OperandSize::with_prefix_size(1); // 1-byte prefix
-	/// // Note: get_prefix_size is a private function used internally
+	/// let script = hex::decode("0c0548656c6c6f41f827ec8c").unwrap();
+	/// let instructions = ScriptReader::disassemble(&script).unwrap();
+	/// assert_eq!(instructions.len(), 2);
+	/// assert_eq!(instructions[0].operand.text(), Some("Hello"));
+	/// assert_eq!(instructions[1].to_string(), "0007 SYSCALL System.Runtime.CheckWitness");
 	/// ```
-	fn get_prefix_size(reader: &mut Decoder, size: OperandSize) -> Result<usize, BuilderError> {
-		match size.prefix_size() {
-			1 => Ok(reader.read_u8() as usize),
-			2 => Ok(reader.read_i16().map(|v| v as usize)?),
-			4 => Ok(reader.read_i32().map(|v| v as usize)?),
-			_ => Err(BuilderError::UnsupportedOperation(
-				"Only operand prefix sizes 1, 2, and 4 are supported".to_string(),
-			)),
+	pub fn disassemble(script: &[u8]) -> Result<Vec<Instruction>, BuilderError> {
+		let mut instructions = Vec::new();
+		let mut offset = 0;
+
+		while offset < script.len() {
+			let decoded = VMInstruction::decode(script, offset).map_err(|err| match err {
+				VMError::InvalidOpCode(byte) => BuilderError::InvalidScript(format!(
+					"Invalid opcode 0x{:02X} at offset {}",
+					byte, offset
+				)),
+				err => BuilderError::InvalidScript(err.to_string()),
+			})?;
+			let operand = Self::decode_operand(&decoded, offset)?;
+			instructions.push(Instruction {
+				offset,
+				opcode: decoded.opcode,
+				operand,
+				size: decoded.size,
+			});
+			offset += decoded.size;
+		}
+
+		for instruction in &instructions {
+			for target in instruction.operand.targets() {
+				if instructions.binary_search_by_key(&target, |other| other.offset).is_err() {
+					return Err(BuilderError::InvalidScript(format!(
+						"{} at offset {} targets {}, which is not the start of an instruction",
+						instruction.opcode_name(),
+						instruction.offset,
+						target
+					)));
+				}
+			}
+		}
+		Ok(instructions)
+	}
+
+	fn decode_operand(decoded: &VMInstruction, offset: usize) -> Result<Operand, BuilderError> {
+		let target = |relative: i32| -> Result<usize, BuilderError> {
+			usize::try_from(offset as i64 + relative as i64).map_err(|_| {
+				BuilderError::InvalidScript(format!(
+					"{} at offset {} targets a negative offset",
+					decoded.opcode, offset
+				))
+			})
+		};
+		let jump = |relative: i32| -> Result<Operand, BuilderError> {
+			Ok(Operand::Jump { offset: relative, target: target(relative)? })
+		};
+		let try_target = |relative: i32| -> Result<Option<usize>, BuilderError> {
+			if relative == 0 {
+				Ok(None)
+			} else {
+				target(relative).map(Some)
+			}
+		};
+
+		let operand = match decoded.opcode {
+			OpCode::PushInt8
+			| OpCode::PushInt16
+			| OpCode::PushInt32
+			| OpCode::PushInt64
+			| OpCode::PushInt128
+			| OpCode::PushInt256 => Operand::Integer(BigInt::from_signed_bytes_le(&decoded.operand)),
+			OpCode::PushData1 | OpCode::PushData2 | OpCode::PushData4 =>
+				Operand::Data(decoded.operand.clone()),
+			OpCode::PushA => {
+				let relative = decoded.token_i32();
+				Operand::Pointer { offset: relative, target: target(relative)? }
+			},
+			OpCode::Jmp
+			| OpCode::JmpIf
+			| OpCode::JmpIfNot
+			| OpCode::JmpEq
+			| OpCode::JmpNe
+			| OpCode::JmpGt
+			| OpCode::JmpGe
+			| OpCode::JmpLt
+			| OpCode::JmpLe
+			| OpCode::Call
+			| OpCode::EndTry => jump(decoded.token_i8() as i32)?,
+			OpCode::JmpL
+			| OpCode::JmpIfL
+			| OpCode::JmpIfNotL
+			| OpCode::JmpEqL
+			| OpCode::JmpNeL
+			| OpCode::JmpGtL
+			| OpCode::JmpGeL
+			| OpCode::JmpLtL
+			| OpCode::JmpLeL
+			| OpCode::CallL
+			| OpCode::EndTryL => jump(decoded.token_i32())?,
+			OpCode::Try => Operand::Try {
+				catch: try_target(decoded.token_i8() as i32)?,
+				finally: try_target(decoded.token_i8_1() as i32)?,
+			},
+			OpCode::TryL => Operand::Try {
+				catch: try_target(decoded.token_i32())?,
+				finally: try_target(decoded.token_i32_1())?,
+			},
+			OpCode::Syscall => {
+				let hash = decoded.operand.to_hex_string();
+				Operand::Syscall { service: InteropService::from_hash(hash.clone()), hash }
+			},
+			OpCode::CallT => Operand::Token(decoded.token_u16()),
+			OpCode::InitSlot =>
+				Operand::InitSlot { locals: decoded.token_u8(), arguments: decoded.token_u8_1() },
+			OpCode::InitSSLot
+			| OpCode::LdSFLd
+			| OpCode::StSFLd
+			| OpCode::LdLoc
+			| OpCode::StLoc
+			| OpCode::LdArg
+			| OpCode::StArg => Operand::Index(decoded.token_u8()),
+			OpCode::NewArrayT | OpCode::IsType | OpCode::Convert => {
+				match StackItemType::try_from(decoded.token_u8()) {
+					Ok(item_type) if item_type != StackItemType::Any => Operand::Type(item_type),
+					_ =>
+						return Err(BuilderError::InvalidScript(format!(
+							"Invalid stack item type 0x{:02X} for {} at offset {}",
+							decoded.token_u8(),
+							decoded.opcode,
+							offset
+						))),
+				}
+			},
+			_ => Operand::None,
+		};
+		Ok(operand)
+	}
+}
+
+/// A decoded instruction produced by [`ScriptReader::disassemble`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Instruction {
+	/// The offset of the instruction in the script.
+	pub offset: usize,
+	/// The opcode of the instruction.
+	pub opcode: OpCode,
+	/// The decoded operand.
+	pub operand: Operand,
+	/// The encoded size of the instruction in bytes.
+	pub size: usize,
+}
+
+impl Instruction {
+	/// The opcode name as used in Neo tooling, e.g. `PUSHDATA1` or `SYSCALL`.
+	pub fn opcode_name(&self) -> String {
+		format!("{:?}", self.opcode).to_uppercase()
+	}
+
+	/// The integer pushed by this instruction, including the constants `PUSHM1` to `PUSH16`.
+	pub fn pushed_integer(&self) -> Option<BigInt> {
+		match (&self.operand, self.opcode) {
+			(Operand::Integer(value), _) => Some(value.clone()),
+			(_, opcode)
+				if (OpCode::PushM1 as u8..=OpCode::Push16 as u8).contains(&(opcode as u8)) =>
+				Some(BigInt::from(opcode as i32 - OpCode::Push0 as i32)),
+			_ => None,
+		}
+	}
+
+	/// The data pushed by this instruction, if it is a `PUSHDATA` instruction.
+	pub fn pushed_data(&self) -> Option<&[u8]> {
+		match &self.operand {
+			Operand::Data(data) => Some(data),
+			_ => None,
+		}
+	}
+}
+
+impl fmt::Display for Instruction {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{:04} {}", self.offset, self.opcode_name())?;
+		match self.operand {
+			Operand::None => Ok(()),
+			ref operand => write!(f, " {}", operand),
+		}
+	}
+}
+
+/// The decoded operand of an [`Instruction`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Operand {
+	/// The instruction has no operand.
+	None,
+	/// The integer pushed by a `PUSHINT` instruction.
+	Integer(BigInt),
+	/// The bytes pushed by a `PUSHDATA` instruction.
+	Data(Bytes),
+	/// A relative jump, call or `ENDTRY` offset and the absolute target it resolves to.
+	Jump { offset: i32, target: usize },
+	/// A `PUSHA` pointer and the absolute target it resolves to.
+	Pointer { offset: i32, target: usize },
+	/// The absolute catch and finally targets of a `TRY` instruction.
+	Try { catch: Option<usize>, finally: Option<usize> },
+	/// A SYSCALL hash and the interop service it identifies, if known.
+	Syscall { hash: String, service: Option<InteropService> },
+	/// The method token index of a `CALLT` instruction.
+	Token(u16),
+	/// The local variable and argument counts of an `INITSLOT` instruction.
+	InitSlot { locals: u8, arguments: u8 },
+	/// A slot index, or the static field count of `INITSSLOT`.
+	Index(u8),
+	/// The stack item type of `NEWARRAY_T`, `ISTYPE` and `CONVERT`.
+	Type(StackItemType),
+}
+
+impl Operand {
+	/// The pushed data as text, if it is printable UTF-8.
+	pub fn text(&self) -> Option<&str> {
+		match self {
+			Operand::Data(data) => std::str::from_utf8(data)
+				.ok()
+				.filter(|text| !text.is_empty() && !text.chars().any(char::is_control)),
+			_ => None,
+		}
+	}
+
+	/// The absolute offsets this operand refers to.
+	pub fn targets(&self) -> Vec<usize> {
+		match self {
+			Operand::Jump { target, .. } | Operand::Pointer { target, .. } => vec![*target],
+			Operand::Try { catch, finally } =>
+				catch.iter().chain(finally.iter()).copied().collect(),
+			_ => Vec::new(),
+		}
+	}
+}
+
+impl fmt::Display for Operand {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let target = |target: &Option<usize>| match target {
+			Some(target) => format!("{:04}", target),
+			None => "none".to_string(),
+		};
+		match self {
+			Operand::None => Ok(()),
+			Operand::Integer(value) => write!(f, "{}", value),
+			Operand::Data(data) => match self.text() {
+				Some(text) => write!(f, "{:?}", text),
+				None => write!(f, "0x{}", data.to_hex_string()),
+			},
+			Operand::Jump { target, .. } | Operand::Pointer { target, .. } =>
+				write!(f, "{:04}", target),
+			Operand::Try { catch, finally } =>
+				write!(f, "catch: {}, finally: {}", target(catch), target(finally)),
+			Operand::Syscall { hash, service } => match service {
+				Some(service) => write!(f, "{}", service),
+				None => write!(f, "0x{}", hash),
+			},
+			Operand::Token(token) => write!(f, "{}", token),
+			Operand::InitSlot { locals, arguments } =>
+				write!(f, "locals: {}, arguments: {}", locals, arguments),
+			Operand::Index(index) => write!(f, "{}", index),
+			Operand::Type(item_type) => write!(f, "{}", item_type),
 		}
 	}
 }
@@ -145,4 +386,62 @@ mod tests {
 		// Assert that the conversion matches the expected output
 		assert_eq!(op_code_string.as_str(), expected_op_code_string);
 	}
+
+	#[test]
+	fn test_convert_to_op_code_string_stops_at_truncated_operand() {
+		let script = hex::decode("110c05486565").unwrap();
+		assert_eq!(ScriptReader::convert_to_op_code_string(&script), "PUSH1\n");
+	}
+
+	#[test]
+	fn test_disassemble() {
+		// PUSHDATA1 "Hello", PUSHINT8 100, JMPIF +7, PUSHA +0, SYSCALL CheckWitness, PUSH5, RET
+		let script = hex::decode("0c0548656c6c6f006424070a0000000041f827ec8c1540").unwrap();
+		let instructions = ScriptReader::disassemble(&script).unwrap();
+
+		let offsets: Vec<usize> = instructions.iter().map(|i| i.offset).collect();
+		assert_eq!(offsets, vec![0, 7, 9, 11, 16, 21, 22]);
+
+		assert_eq!(instructions[0].operand, Operand::Data(b"Hello".to_vec()));
+		assert_eq!(instructions[1].pushed_integer(), Some(BigInt::from(100)));
+		assert_eq!(instructions[2].operand, Operand::Jump { offset: 7, target: 16 });
+		assert_eq!(instructions[3].operand, Operand::Pointer { offset: 0, target: 11 });
+		assert_eq!(
+			instructions[4].operand,
+			Operand::Syscall {
+				hash: "f827ec8c".to_string(),
+				service: Some(InteropService::SystemRuntimeCheckWitness),
+			}
+		);
+		assert_eq!(instructions[5].pushed_integer(), Some(BigInt::from(5)));
+		assert_eq!(instructions[6].opcode, OpCode::Ret);
+	}
+
+	#[test]
+	fn test_disassemble_resolves_try_targets() {
+		// TRY catch=+5 finally=0, PUSH1, THROW, DROP(catch), ENDTRY +2, RET
+		let script = hex::decode("3b0500113a453d0240").unwrap();
+		let instructions = ScriptReader::disassemble(&script).unwrap();
+		assert_eq!(instructions[0].operand, Operand::Try { catch: Some(5), finally: None });
+		assert_eq!(instructions[4].operand, Operand::Jump { offset: 2, target: 8 });
+		assert_eq!(instructions[0].to_string(), "0000 TRY catch: 0005, finally: none");
+	}
+
+	#[test]
+	fn test_disassemble_rejects_malformed_scripts() {
+		// Truncated PUSHDATA1
+		assert!(matches!(
+			ScriptReader::disassemble(&hex::decode("0c0548").unwrap()),
+			Err(BuilderError::InvalidScript(_))
+		));
+		// Unknown opcode
+		assert_eq!(
+			ScriptReader::disassemble(&[0x11, 0xff]),
+			Err(BuilderError::InvalidScript("Invalid opcode 0xFF at offset 1".to_string()))
+		);
+		// JMP into the operand of PUSHINT16
+		assert!(ScriptReader::disassemble(&hex::decode("220301010040").unwrap()).is_err());
+		// CONVERT to an unknown stack item type
+		assert!(ScriptReader::disassemble(&hex::decode("11db99").unwrap()).is_err());
+	}
 }
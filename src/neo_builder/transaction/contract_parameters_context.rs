@@ -1,10 +1,58 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, str::FromStr};
 
-use crate::ContractParameter;
+use crate::{
+	builder::{
+		InvocationScript, Transaction, TransactionData, TransactionError, VerificationScript,
+		Witness,
+	},
+	codec::{Decoder, NeoSerializable},
+	crypto::{HashableForVec, KeyPair, Secp256r1PublicKey, Secp256r1Signature},
+	neo_clients::{HttpProvider, JsonRpcProvider},
+	neo_crypto::utils::{FromBase64String, ToBase64String},
+	neo_types::ScriptHashExtension,
+	Bytes, ContractParameter, ContractParameterType,
+};
+use primitive_types::H160;
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Debug)]
+/// The signing state of a transaction shared between its co-signers, in the JSON format of the
+/// C# neo-cli (`sign` and `relay` commands).
+///
+/// The context holds the unsigned transaction and, for each signer whose verification script is
+/// known, the signatures collected so far. Once every signer has enough signatures, the
+/// witnesses are assembled with [`get_witnesses`](Self::get_witnesses).
+///
+/// # Examples
+///
+/// ```rust
+/// use neo3::neo_builder::{AccountSigner, ContractParametersContext, Transaction, VerificationScript};
+/// use neo3::neo_clients::HttpProvider;
+/// use neo3::neo_crypto::KeyPair;
+/// use neo3::neo_protocol::{Account, AccountTrait};
+///
+/// let key_pairs: Vec<_> = (0..3).map(|_| KeyPair::new_random()).collect();
+/// let mut public_keys: Vec<_> = key_pairs.iter().map(|key_pair| key_pair.public_key()).collect();
+/// let script = VerificationScript::from_multi_sig(&mut public_keys, 2);
+/// let account = Account::multi_sig_from_public_keys(&mut public_keys, 2).unwrap();
+///
+/// let mut tx = Transaction::<HttpProvider>::new();
+/// tx.set_script(vec![0x11]);
+/// tx.set_signers(vec![AccountSigner::called_by_entry(&account).unwrap().into()]);
+///
+/// // Each co-signer imports the JSON, signs, and exports it again
+/// let mut context = ContractParametersContext::from_transaction(&tx, 860833102).unwrap();
+/// for key_pair in &key_pairs[..2] {
+///     let json = context.to_json().unwrap();
+///     context = ContractParametersContext::from_json(&json).unwrap();
+///     context.sign(&script, key_pair).unwrap();
+/// }
+///
+/// assert!(context.is_completed().unwrap());
+/// tx.set_witnesses(context.get_witnesses().unwrap());
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct ContractParametersContext {
+	#[serde(rename = "type")]
 	pub type_: String,
 	pub hash: String,
 	pub data: String,
@@ -13,6 +61,8 @@ pub struct ContractParametersContext {
 }
 
 impl ContractParametersContext {
+	pub const TRANSACTION_TYPE: &'static str = "Neo.Network.P2P.Payloads.Transaction";
+
 	pub fn new(
 		hash: String,
 		data: String,
@@ -20,18 +70,217 @@ impl ContractParametersContext {
 		network: u32,
 	) -> Self {
 		Self {
-			type_: "Neo.Network.P2P.Payloads.Transaction".to_string(),
+			type_: Self::TRANSACTION_TYPE.to_string(),
 			hash,
 			data,
 			items: items.unwrap_or_default(),
 			network,
 		}
 	}
+
+	/// Creates the context of the unsigned `tx` on the network with the magic number `network`.
+	/// The witnesses `tx` already has are ignored.
+	pub fn from_transaction<P: JsonRpcProvider + 'static>(
+		tx: &Transaction<'_, P>,
+		network: u32,
+	) -> Result<Self, TransactionError> {
+		Ok(Self::from_transaction_data(&TransactionData::from(tx), network))
+	}
+
+	/// Creates the context of the unsigned `tx` on the network with the magic number `network`.
+	/// The witnesses `tx` already has are ignored.
+	pub fn from_transaction_data(tx: &TransactionData, network: u32) -> Self {
+		let hash = format!("{:#x}", tx.tx_hash());
+		Self::new(hash, tx.unsigned_bytes().to_base64_string(), None, network)
+	}
+
+	pub fn from_json(json: &str) -> Result<Self, TransactionError> {
+		let context: Self = serde_json::from_str(json).map_err(|e| {
+			TransactionError::TransactionConfiguration(format!("Invalid context JSON: {}", e))
+		})?;
+		if context.type_ != Self::TRANSACTION_TYPE {
+			return Err(TransactionError::TransactionConfiguration(format!(
+				"Unsupported context type {}",
+				context.type_
+			)));
+		}
+		Ok(context)
+	}
+
+	pub fn to_json(&self) -> Result<String, TransactionError> {
+		serde_json::to_string_pretty(self).map_err(|e| {
+			TransactionError::TransactionConfiguration(format!("Failed to encode context: {}", e))
+		})
+	}
+
+	/// The unsigned transaction of the context, without witnesses.
+	pub fn get_transaction<P: JsonRpcProvider + 'static>(
+		&self,
+	) -> Result<Transaction<'static, P>, TransactionError> {
+		let data = self.unsigned_data()?;
+		let mut reader = Decoder::new(&data);
+		let tx = Transaction::<P>::decode(&mut reader)?;
+		if format!("{:#x}", tx.get_tx_id()?) != self.hash.to_lowercase() {
+			return Err(TransactionError::TransactionConfiguration(
+				"The context hash doesn't match its transaction".to_string(),
+			));
+		}
+		Ok(tx)
+	}
+
+	/// The data the signers sign, for the network of the context.
+	pub fn get_sign_data(&self) -> Result<Bytes, TransactionError> {
+		let mut data = self.unsigned_data()?.hash256();
+		data.splice(0..0, self.network.to_le_bytes());
+		Ok(data)
+	}
+
+	/// Adds the signature of `public_key` for the signer with `verification_script`.
+	///
+	/// Returns whether the signature was added, or `false` if the signer already had enough
+	/// signatures. Fails if the signature is invalid, `public_key` isn't part of the script, or
+	/// the script's account isn't a signer of the transaction.
+	pub fn add_signature(
+		&mut self,
+		verification_script: &VerificationScript,
+		public_key: &Secp256r1PublicKey,
+		signature: &Secp256r1Signature,
+	) -> Result<bool, TransactionError> {
+		let public_keys = Self::public_keys(verification_script)?;
+		if !public_keys.contains(public_key) {
+			return Err(TransactionError::SignerConfiguration(format!(
+				"The public key {} is not part of the verification script",
+				public_key.get_encoded_compressed_hex()
+			)));
+		}
+		let script_hash = verification_script.hash();
+		if !self.signer_hashes()?.contains(&script_hash) {
+			return Err(TransactionError::SignerConfiguration(format!(
+				"The account {} is not a signer of the transaction",
+				script_hash.to_address()
+			)));
+		}
+		public_key.verify(&self.get_sign_data()?, signature)?;
+
+		let threshold = verification_script.get_signing_threshold()?;
+		let item = self.items.entry(Self::item_key(&script_hash)).or_insert_with(|| {
+			ContextItem::new(
+				verification_script.script().to_base64_string(),
+				Some(vec![ContractParameter::new(ContractParameterType::Signature); threshold]),
+				None,
+			)
+		});
+		if item.signatures.len() >= threshold {
+			return Ok(false);
+		}
+		item.signatures.insert(
+			public_key.get_encoded_compressed_hex(),
+			signature.to_bytes().to_base64_string(),
+		);
+
+		// Fill in the parameters in the order of the keys in the script, as the invocation script
+		// must push the signatures in that order.
+		let signatures = item.ordered_signatures(&public_keys)?;
+		item.parameters = Some(
+			signatures
+				.iter()
+				.map(|signature| ContractParameter::signature(&hex::encode(signature.to_bytes())))
+				.chain(std::iter::repeat(ContractParameter::new(ContractParameterType::Signature)))
+				.take(threshold)
+				.collect(),
+		);
+		Ok(true)
+	}
+
+	/// Signs the transaction with `key_pair` for the signer with `verification_script`.
+	pub fn sign(
+		&mut self,
+		verification_script: &VerificationScript,
+		key_pair: &KeyPair,
+	) -> Result<bool, TransactionError> {
+		let signature = key_pair.private_key.sign_tx(&self.get_sign_data()?)?;
+		self.add_signature(verification_script, &key_pair.public_key(), &signature)
+	}
+
+	/// The signers of the transaction that don't have enough signatures yet, in order.
+	pub fn get_missing_signers(&self) -> Result<Vec<H160>, TransactionError> {
+		let mut missing = vec![];
+		for signer_hash in self.signer_hashes()? {
+			match self.items.get(&Self::item_key(&signer_hash)) {
+				Some(item) if item.is_completed()? => {},
+				_ => missing.push(signer_hash),
+			}
+		}
+		Ok(missing)
+	}
+
+	pub fn is_completed(&self) -> Result<bool, TransactionError> {
+		Ok(self.get_missing_signers()?.is_empty())
+	}
+
+	/// The witnesses of the transaction, in the order of its signers.
+	pub fn get_witnesses(&self) -> Result<Vec<Witness>, TransactionError> {
+		let mut witnesses = vec![];
+		for signer_hash in self.signer_hashes()? {
+			let item = match self.items.get(&Self::item_key(&signer_hash)) {
+				Some(item) if item.is_completed()? => item,
+				_ =>
+					return Err(TransactionError::SignerConfiguration(format!(
+						"The signer {} doesn't have enough signatures",
+						signer_hash.to_address()
+					))),
+			};
+			let verification_script = item.verification_script()?;
+			let public_keys = Self::public_keys(&verification_script)?;
+			let signatures = item.ordered_signatures(&public_keys)?;
+			let witness = if verification_script.is_single_sig() {
+				Witness::from_scripts_obj(
+					InvocationScript::from_signature(signatures[0].clone()),
+					verification_script,
+				)
+			} else {
+				Witness::create_multi_sig_witness_script(signatures, verification_script)?
+			};
+			witnesses.push(witness);
+		}
+		Ok(witnesses)
+	}
+
+	fn unsigned_data(&self) -> Result<Bytes, TransactionError> {
+		self.data.from_base64_string().map_err(|e| {
+			TransactionError::TransactionConfiguration(format!("Invalid context data: {}", e))
+		})
+	}
+
+	fn signer_hashes(&self) -> Result<Vec<H160>, TransactionError> {
+		Ok(self
+			.get_transaction::<HttpProvider>()?
+			.signers
+			.iter()
+			.map(|signer| *signer.get_signer_hash())
+			.collect())
+	}
+
+	fn item_key(script_hash: &H160) -> String {
+		format!("0x{}", script_hash.to_hex())
+	}
+
+	fn public_keys(
+		verification_script: &VerificationScript,
+	) -> Result<Vec<Secp256r1PublicKey>, TransactionError> {
+		if !verification_script.is_single_sig() && !verification_script.is_multi_sig() {
+			return Err(TransactionError::SignerConfiguration(
+				"Only single-sig and multi-sig verification scripts can be signed".to_string(),
+			));
+		}
+		Ok(verification_script.get_public_keys()?)
+	}
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct ContextItem {
 	pub script: String,
+	#[serde(with = "signature_parameters")]
 	pub parameters: Option<Vec<ContractParameter>>,
 	pub signatures: HashMap<String, String>,
 }
@@ -44,4 +293,229 @@ impl ContextItem {
 	) -> Self {
 		Self { script, parameters, signatures: signatures.unwrap_or_default() }
 	}
+
+	pub fn verification_script(&self) -> Result<VerificationScript, TransactionError> {
+		let script = self.script.from_base64_string().map_err(|e| {
+			TransactionError::TransactionConfiguration(format!("Invalid item script: {}", e))
+		})?;
+		Ok(VerificationScript::from(script))
+	}
+
+	/// Whether the item has as many signatures as its verification script needs.
+	pub fn is_completed(&self) -> Result<bool, TransactionError> {
+		let threshold = self.verification_script()?.get_signing_threshold()?;
+		Ok(self.signatures.len() >= threshold)
+	}
+
+	/// The signatures of the item, in the order of `public_keys`.
+	fn ordered_signatures(
+		&self,
+		public_keys: &[Secp256r1PublicKey],
+	) -> Result<Vec<Secp256r1Signature>, TransactionError> {
+		let mut signatures = vec![];
+		for public_key in public_keys {
+			if let Some(signature) = self.signatures.get(&public_key.get_encoded_compressed_hex()) {
+				let bytes = signature.from_base64_string().map_err(|e| {
+					TransactionError::TransactionConfiguration(format!("Invalid signature: {}", e))
+				})?;
+				signatures.push(Secp256r1Signature::from_bytes(&bytes)?);
+			}
+		}
+		Ok(signatures)
+	}
+}
+
+/// Signature parameters are hex-encoded in [`ContractParameter`], but base64-encoded in the
+/// neo-cli format.
+mod signature_parameters {
+	use super::*;
+	use serde::{de, ser, Deserializer, Serializer};
+	use serde_json::Value;
+
+	pub(super) fn serialize<S: Serializer>(
+		parameters: &Option<Vec<ContractParameter>>,
+		serializer: S,
+	) -> Result<S::Ok, S::Error> {
+		let parameters = parameters
+			.as_ref()
+			.map(|parameters| {
+				parameters
+					.iter()
+					.map(|parameter| match parameter.to_signature() {
+						Ok(signature) => hex::decode(&signature)
+							.map(|bytes| ContractParameter::signature(&bytes.to_base64_string()))
+							.map_err(|err| {
+								let message = format!("Invalid signature {}: {}", signature, err);
+								ser::Error::custom(message)
+							}),
+						Err(_) => Ok(parameter.clone()),
+					})
+					.collect::<Result<Vec<_>, _>>()
+			})
+			.transpose()?;
+		parameters.serialize(serializer)
+	}
+
+	pub(super) fn deserialize<'de, D: Deserializer<'de>>(
+		deserializer: D,
+	) -> Result<Option<Vec<ContractParameter>>, D::Error> {
+		let Some(values) = Option::<Vec<Value>>::deserialize(deserializer)? else {
+			return Ok(None);
+		};
+		values
+			.into_iter()
+			.map(|value| {
+				let typ = value.get("type").and_then(Value::as_str).unwrap_or_default();
+				if ContractParameterType::from_str(typ) != Ok(ContractParameterType::Signature) {
+					return serde_json::from_value(value).map_err(de::Error::custom);
+				}
+				match value.get("value").and_then(Value::as_str) {
+					Some(signature) => {
+						let bytes = signature.from_base64_string().map_err(de::Error::custom)?;
+						Ok(ContractParameter::signature(&hex::encode(bytes)))
+					},
+					None => Ok(ContractParameter::new(ContractParameterType::Signature)),
+				}
+			})
+			.collect::<Result<_, _>>()
+			.map(Some)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		builder::AccountSigner,
+		neo_protocol::{Account, AccountTrait},
+	};
+
+	const NETWORK: u32 = 860833102;
+
+	fn multi_sig() -> (Vec<KeyPair>, VerificationScript, Transaction<'static, HttpProvider>) {
+		let key_pairs: Vec<_> = (0..3).map(|_| KeyPair::new_random()).collect();
+		let mut public_keys: Vec<_> = key_pairs.iter().map(KeyPair::public_key).collect();
+		let script = VerificationScript::from_multi_sig(&mut public_keys, 2);
+		let account = Account::multi_sig_from_public_keys(&mut public_keys, 2).unwrap();
+		let mut tx = Transaction::new();
+		tx.set_script(vec![0x11]);
+		tx.set_valid_until_block(100);
+		tx.set_signers(vec![AccountSigner::called_by_entry(&account).unwrap().into()]);
+		(key_pairs, script, tx)
+	}
+
+	#[test]
+	fn test_multi_sig_workflow() {
+		let (key_pairs, script, tx) = multi_sig();
+		let mut context = ContractParametersContext::from_transaction(&tx, NETWORK).unwrap();
+		assert_eq!(context.get_missing_signers().unwrap(), vec![script.hash()]);
+		assert!(context.get_witnesses().is_err());
+
+		// Sign in reverse key order, exchanging the context as JSON
+		for key_pair in key_pairs.iter().rev().take(2) {
+			let json = context.to_json().unwrap();
+			context = ContractParametersContext::from_json(&json).unwrap();
+			assert!(context.sign(&script, key_pair).unwrap());
+		}
+		assert!(!context.sign(&script, &key_pairs[0]).unwrap());
+		assert!(context.is_completed().unwrap());
+
+		let witnesses = context.get_witnesses().unwrap();
+		assert_eq!(witnesses.len(), 1);
+		assert_eq!(witnesses[0].verification, script);
+		let sign_data = context.get_sign_data().unwrap();
+		let public_keys = script.get_public_keys().unwrap();
+		let signers: Vec<_> = witnesses[0]
+			.invocation
+			.get_signatures()
+			.iter()
+			.map(|signature| {
+				public_keys
+					.iter()
+					.position(|key| key.verify(&sign_data, signature).is_ok())
+					.unwrap()
+			})
+			.collect();
+		assert!(signers.windows(2).all(|pair| pair[0] < pair[1]));
+	}
+
+	#[test]
+	fn test_rejects_invalid_signatures() {
+		let (key_pairs, script, tx) = multi_sig();
+		let mut context = ContractParametersContext::from_transaction(&tx, NETWORK).unwrap();
+
+		let signature = key_pairs[0].private_key.sign_tx(b"other data").unwrap();
+		assert!(context.add_signature(&script, &key_pairs[0].public_key(), &signature).is_err());
+		assert!(context.sign(&script, &KeyPair::new_random()).is_err());
+
+		let other = KeyPair::new_random();
+		let other_script = VerificationScript::from_public_key(&other.public_key());
+		assert!(context.sign(&other_script, &other).is_err());
+		assert!(context.items.is_empty());
+	}
+
+	#[test]
+	fn test_single_sig_witness() {
+		let account = Account::create().unwrap();
+		let mut tx = Transaction::<HttpProvider>::new();
+		tx.set_script(vec![0x11]);
+		tx.set_signers(vec![AccountSigner::none(&account).unwrap().into()]);
+		let key_pair = account.key_pair().clone().unwrap();
+
+		let mut context = ContractParametersContext::from_transaction(&tx, NETWORK).unwrap();
+		let script = account.get_verification_script().unwrap();
+		context.sign(&script, &key_pair).unwrap();
+
+		let expected = Witness::create(context.get_sign_data().unwrap(), &key_pair).unwrap();
+		let witness = &context.get_witnesses().unwrap()[0];
+		assert_eq!(witness.verification, expected.verification);
+		assert!(key_pair
+			.public_key()
+			.verify(&tx.get_sign_data(NETWORK), &witness.invocation.get_signatures()[0])
+			.is_ok());
+	}
+
+	#[test]
+	fn test_json_format() {
+		let (key_pairs, script, tx) = multi_sig();
+		let mut context = ContractParametersContext::from_transaction(&tx, NETWORK).unwrap();
+		context.sign(&script, &key_pairs[1]).unwrap();
+
+		let json: serde_json::Value = serde_json::from_str(&context.to_json().unwrap()).unwrap();
+		assert_eq!(json["type"], "Neo.Network.P2P.Payloads.Transaction");
+		assert_eq!(json["network"], NETWORK);
+		let key = format!("0x{}", script.hash().to_hex());
+		let item = &json["items"][&key];
+		assert_eq!(item["script"], script.script().to_base64_string());
+		let public_key = key_pairs[1].public_key().get_encoded_compressed_hex();
+		let signature = item["signatures"][&public_key].as_str().unwrap();
+		assert_eq!(item["parameters"][0]["type"], "Signature");
+		assert_eq!(item["parameters"][0]["value"], signature);
+		assert!(item["parameters"][1].get("value").is_none());
+
+		// neo-cli writes null for the signatures it doesn't have yet
+		let mut json = json;
+		json["items"][&key]["parameters"][1]["value"] = serde_json::Value::Null;
+		let imported = ContractParametersContext::from_json(&json.to_string()).unwrap();
+		assert_eq!(imported, context);
+	}
+
+	#[test]
+	fn test_corrupted_items_are_errors() {
+		let (key_pairs, script, tx) = multi_sig();
+		let mut context = ContractParametersContext::from_transaction(&tx, NETWORK).unwrap();
+		context.sign(&script, &key_pairs[1]).unwrap();
+		let key = format!("0x{}", script.hash().to_hex());
+
+		let mut corrupted = context.clone();
+		let item = corrupted.items.get_mut(&key).unwrap();
+		item.parameters.as_mut().unwrap()[0] = ContractParameter::signature("not hex");
+		let err = corrupted.to_json().unwrap_err();
+		assert!(err.to_string().contains("Invalid signature not hex"), "{}", err);
+
+		let mut corrupted = context;
+		corrupted.items.get_mut(&key).unwrap().script = "not base64!".to_string();
+		let err = corrupted.get_witnesses().unwrap_err();
+		assert!(err.to_string().contains("Invalid item script"), "{}", err);
+	}
 }
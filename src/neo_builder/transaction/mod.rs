@@ -4,34 +4,48 @@ pub use call_flags::*;
 pub use contract_parameters_context::*;
 pub use gas_estimator::*;
 pub use invocation_script::*;
+pub use network_fee_calculator::*;
+pub use notary_request::*;
 pub use oracle_response_code::*;
 pub use signers::*;
+pub use signing_envelope::*;
 pub use transaction::*;
 pub use transaction_attribute::*;
 pub use transaction_builder::*;
+pub use transaction_data::*;
 pub use transaction_error::*;
+pub use transaction_replacement::*;
+pub use transaction_sender::*;
 pub use transaction_send_token::*;
 pub use verification_script::*;
 pub use witness::*;
 pub use witness_rule::*;
 pub use witness_scope::*;
+pub use witness_verification::*;
 
 mod call_flags;
 mod contract_parameters_context;
 mod gas_estimator;
 mod invocation_script;
+mod network_fee_calculator;
+mod notary_request;
 mod oracle_response_code;
 mod signers;
+mod signing_envelope;
 mod transaction;
 mod transaction_attribute;
 mod transaction_builder;
 mod transaction_builder_tests;
+mod transaction_data;
 mod transaction_error;
+mod transaction_replacement;
+mod transaction_sender;
 mod transaction_send_token;
 mod verification_script;
 mod witness;
 mod witness_rule;
 mod witness_scope;
+mod witness_verification;
 
 use std::sync::Once;
 use tracing_subscriber;
@@ -0,0 +1,337 @@
+use getset::{CopyGetters, Setters};
+use num_bigint::BigInt;
+
+use crate::{
+	builder::{
+		InteropService, ScriptBuilder, Signer, Transaction, TransactionAttribute, TransactionError,
+		VerificationScript, Witness,
+	},
+	codec::{NeoSerializable, VarSizeTrait},
+	neo_clients::{HttpProvider, JsonRpcProvider},
+	neo_types::OpCode,
+	var_size,
+};
+
+/// Calculates network fees offline, for transactions whose witnesses use standard single-sig
+/// and multi-sig verification scripts.
+///
+/// The network fee of a transaction is its size times the fee per byte, plus the cost of
+/// executing the verification scripts of its witnesses and the fees of its attributes. These
+/// depend on policy values, which are either supplied manually or fetched once through
+/// [`PolicyContract::network_fee_calculator`](crate::neo_contract::PolicyContract::network_fee_calculator).
+///
+/// The cost of verifying a contract signer depends on its `verify` method and can't be
+/// calculated offline, so it must be set with
+/// [`set_contract_verification_fee`](Self::set_contract_verification_fee).
+///
+/// # Examples
+///
+/// ```rust
+/// use neo3::neo_builder::{NetworkFeeCalculator, VerificationScript};
+/// use neo3::neo_crypto::KeyPair;
+///
+/// let calculator = NetworkFeeCalculator::new(1000, 30);
+/// let key_pair = KeyPair::new_random();
+/// let script = VerificationScript::from_public_key(&key_pair.public_key());
+///
+/// // The fee of a witness without the size of the rest of the transaction
+/// let fee = calculator.network_fee(0, &[script]).unwrap();
+/// assert_eq!(fee, 1000 * 109 + 30 * 32784);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, CopyGetters, Setters)]
+pub struct NetworkFeeCalculator {
+	/// The fee per byte of transaction, in datoshi.
+	#[getset(get_copy = "pub")]
+	fee_per_byte: i64,
+	/// The factor applied to the price of opcodes and interop services.
+	#[getset(get_copy = "pub")]
+	exec_fee_factor: i64,
+	/// The fee of a `NotaryAssisted` attribute, charged once per key and once for the notary.
+	#[getset(get_copy = "pub", set = "pub")]
+	notary_assisted_fee: i64,
+	/// The fee of a `Conflicts` attribute, charged once per signer.
+	#[getset(get_copy = "pub", set = "pub")]
+	conflicts_fee: i64,
+	/// The cost of executing the invocation script and the `verify` method of a contract
+	/// signer, charged once per contract signer.
+	#[getset(get_copy = "pub", set = "pub")]
+	contract_verification_fee: Option<i64>,
+}
+
+impl Default for NetworkFeeCalculator {
+	/// A calculator with the default policy values of the Policy contract.
+	fn default() -> Self {
+		Self::new(Self::DEFAULT_FEE_PER_BYTE, Self::DEFAULT_EXEC_FEE_FACTOR)
+	}
+}
+
+impl NetworkFeeCalculator {
+	pub const DEFAULT_FEE_PER_BYTE: i64 = 1000;
+	pub const DEFAULT_EXEC_FEE_FACTOR: i64 = 30;
+	pub const DEFAULT_NOTARY_ASSISTED_FEE: i64 = 10_000_000;
+	pub const DEFAULT_ATTRIBUTE_FEE: i64 = 0;
+
+	/// The size of the invocation script pushing a signature.
+	const SIGNATURE_INVOCATION_SIZE: usize = 66;
+
+	/// A calculator with the default attribute fees and no contract verification fee.
+	pub fn new(fee_per_byte: i64, exec_fee_factor: i64) -> Self {
+		Self {
+			fee_per_byte,
+			exec_fee_factor,
+			notary_assisted_fee: Self::DEFAULT_NOTARY_ASSISTED_FEE,
+			conflicts_fee: Self::DEFAULT_ATTRIBUTE_FEE,
+			contract_verification_fee: None,
+		}
+	}
+
+	/// The network fee of `tx`, whose account signers are signed with `verification_scripts`,
+	/// in order. Contract signers are witnessed with their verification parameters. The
+	/// witnesses `tx` already has are ignored.
+	pub fn transaction_fee<P: JsonRpcProvider + 'static>(
+		&self,
+		tx: &Transaction<'_, P>,
+		verification_scripts: &[VerificationScript],
+	) -> Result<i64, TransactionError> {
+		let contract_witnesses = tx
+			.signers
+			.iter()
+			.filter_map(|signer| match signer {
+				Signer::ContractSigner(signer) =>
+					Some(Witness::create_contract_witness(signer.verify_params().to_vec())),
+				_ => None,
+			})
+			.collect::<Result<Vec<_>, _>>()?;
+		let account_signers = tx.signers.len() - contract_witnesses.len();
+		if verification_scripts.len() != account_signers {
+			return Err(TransactionError::TransactionConfiguration(format!(
+				"Expected {} verification scripts, one per account signer, but got {}",
+				account_signers,
+				verification_scripts.len()
+			)));
+		}
+		let mut fee = self.attribute_fee(tx);
+		if !contract_witnesses.is_empty() {
+			let contract_fee = self.contract_verification_fee.ok_or_else(|| {
+				TransactionError::TransactionConfiguration(
+					"The network fee of contract signers can't be calculated offline without a \
+					 contract verification fee"
+						.to_string(),
+				)
+			})?;
+			fee += contract_fee * contract_witnesses.len() as i64;
+		}
+		let (witnesses_size, verification_fee) = self.witnesses_fee(verification_scripts)?;
+		let size = Transaction::<HttpProvider>::HEADER_SIZE
+			+ tx.signers.var_size()
+			+ tx.attributes.var_size()
+			+ tx.script.var_size()
+			+ var_size(tx.signers.len())
+			+ contract_witnesses.iter().map(NeoSerializable::size).sum::<usize>()
+			+ witnesses_size;
+		Ok(fee + verification_fee + size as i64 * self.fee_per_byte)
+	}
+
+	/// The fees of the attributes of `tx`. Attributes other than `NotaryAssisted` and
+	/// `Conflicts` are free under the default policy.
+	pub fn attribute_fee<P: JsonRpcProvider + 'static>(&self, tx: &Transaction<'_, P>) -> i64 {
+		tx.attributes
+			.iter()
+			.map(|attribute| match attribute {
+				TransactionAttribute::NotaryAssisted { nkeys } =>
+					(*nkeys as i64 + 1) * self.notary_assisted_fee,
+				TransactionAttribute::Conflicts { .. } =>
+					tx.signers.len() as i64 * self.conflicts_fee,
+				_ => 0,
+			})
+			.sum()
+	}
+
+	/// The network fee of a transaction of `unsigned_size` bytes without its witnesses, signed
+	/// with `verification_scripts`.
+	pub fn network_fee(
+		&self,
+		unsigned_size: usize,
+		verification_scripts: &[VerificationScript],
+	) -> Result<i64, TransactionError> {
+		let (witnesses_size, fee) = self.witnesses_fee(verification_scripts)?;
+		let size = unsigned_size + var_size(verification_scripts.len()) + witnesses_size;
+		Ok(fee + size as i64 * self.fee_per_byte)
+	}
+
+	/// The size of the witnesses of `verification_scripts` and the cost of executing them.
+	fn witnesses_fee(
+		&self,
+		verification_scripts: &[VerificationScript],
+	) -> Result<(usize, i64), TransactionError> {
+		let mut size = 0;
+		let mut fee = 0;
+		for script in verification_scripts {
+			size += Self::witness_size(script)?;
+			fee += self.verification_fee(script)?;
+		}
+		Ok((size, fee))
+	}
+
+	/// The size of the witness of `script`, with the signatures it needs.
+	pub fn witness_size(script: &VerificationScript) -> Result<usize, TransactionError> {
+		let (signatures, _) = Self::signature_counts(script)?;
+		let invocation_size = Self::SIGNATURE_INVOCATION_SIZE * signatures;
+		Ok(var_size(invocation_size) + invocation_size + script.size())
+	}
+
+	/// The cost of executing `script` and the invocation script pushing its signatures.
+	pub fn verification_fee(&self, script: &VerificationScript) -> Result<i64, TransactionError> {
+		let (signatures, keys) = Self::signature_counts(script)?;
+		let check_sig = InteropService::SystemCryptoCheckSig.price() as i64;
+		let price = if script.is_single_sig() {
+			OpCode::PushData1.price() as i64 * 2 + OpCode::Syscall.price() as i64 + check_sig
+		} else {
+			OpCode::PushData1.price() as i64 * (signatures + keys) as i64
+				+ push_price(signatures)
+				+ push_price(keys)
+				+ OpCode::Syscall.price() as i64
+				+ check_sig * keys as i64
+		};
+		Ok(price * self.exec_fee_factor)
+	}
+
+	/// The number of signatures `script` needs and the number of public keys it has.
+	fn signature_counts(script: &VerificationScript) -> Result<(usize, usize), TransactionError> {
+		if script.is_single_sig() {
+			Ok((1, 1))
+		} else if script.is_multi_sig() {
+			Ok((script.get_signing_threshold()?, script.get_nr_of_accounts()?))
+		} else {
+			Err(TransactionError::TransactionConfiguration(
+				"The network fee of non-standard verification scripts can't be calculated offline"
+					.to_string(),
+			))
+		}
+	}
+}
+
+/// The price of the opcode pushing `value`.
+fn push_price(value: usize) -> i64 {
+	let script = ScriptBuilder::new().push_integer(BigInt::from(value)).to_bytes();
+	OpCode::try_from(script[0]).map_or(0, |opcode| opcode.price() as i64)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		builder::{AccountSigner, ContractSigner},
+		crypto::KeyPair,
+		neo_protocol::{Account, AccountTrait},
+		neo_types::ContractParameter,
+	};
+	use primitive_types::{H160, H256};
+
+	#[test]
+	fn test_single_sig_fee() {
+		let key_pair = KeyPair::new_random();
+		let script = VerificationScript::from_public_key(&key_pair.public_key());
+		let calculator = NetworkFeeCalculator::default();
+
+		assert_eq!(NetworkFeeCalculator::witness_size(&script).unwrap(), 108);
+		assert_eq!(calculator.verification_fee(&script).unwrap(), 32784 * 30);
+		assert_eq!(calculator.network_fee(100, &[script]).unwrap(), (101 + 108) * 1000 + 983520);
+	}
+
+	#[test]
+	fn test_multi_sig_fee() {
+		let mut keys: Vec<_> =
+			(0..3).map(|_| KeyPair::new_random().public_key()).collect::<Vec<_>>();
+		let script = VerificationScript::from_multi_sig(&mut keys, 2);
+		let calculator = NetworkFeeCalculator::new(1000, 30);
+
+		// PUSHDATA1 * 5 + PUSH2 + PUSH3 + SYSCALL + CheckSig * 3
+		assert_eq!(calculator.verification_fee(&script).unwrap(), (8 * 5 + 1 + 1 + 3 * 32768) * 30);
+		assert_eq!(NetworkFeeCalculator::witness_size(&script).unwrap(), 1 + 132 + script.size());
+	}
+
+	#[test]
+	fn test_transaction_fee_matches_signed_size() {
+		let account = Account::create().unwrap();
+		let mut tx = Transaction::<HttpProvider>::new();
+		tx.set_script(vec![OpCode::Push1 as u8]);
+		tx.set_signers(vec![AccountSigner::called_by_entry(&account).unwrap().into()]);
+		let script = account.get_verification_script().unwrap();
+		let calculator = NetworkFeeCalculator::default();
+		let fee = calculator.transaction_fee(&tx, std::slice::from_ref(&script)).unwrap();
+
+		let key_pair = account.key_pair().clone().unwrap();
+		tx.add_witness(Witness::create(vec![0; 32], &key_pair).unwrap());
+		let size = NeoSerializable::size(&tx) as i64;
+		assert_eq!(fee, size * 1000 + calculator.verification_fee(&script).unwrap());
+	}
+
+	#[test]
+	fn test_attribute_fees() {
+		let accounts = [Account::create().unwrap(), Account::create().unwrap()];
+		let mut tx = Transaction::<HttpProvider>::new();
+		tx.set_script(vec![OpCode::Push1 as u8]);
+		tx.set_signers(
+			accounts
+				.iter()
+				.map(|account| AccountSigner::called_by_entry(account).unwrap().into())
+				.collect(),
+		);
+		let scripts: Vec<_> =
+			accounts.iter().map(|account| account.get_verification_script().unwrap()).collect();
+		let mut calculator = NetworkFeeCalculator::default();
+		calculator.set_conflicts_fee(500);
+		let fee = calculator.transaction_fee(&tx, &scripts).unwrap();
+
+		tx.set_attributes(vec![
+			TransactionAttribute::NotaryAssisted { nkeys: 2 },
+			TransactionAttribute::Conflicts { hash: H256::zero() },
+		]);
+		let attributes_size = (tx.attributes.var_size() - 1) as i64;
+		assert_eq!(calculator.attribute_fee(&tx), 3 * 10_000_000 + 2 * 500);
+		assert_eq!(
+			calculator.transaction_fee(&tx, &scripts).unwrap(),
+			fee + attributes_size * 1000 + 3 * 10_000_000 + 2 * 500
+		);
+	}
+
+	#[test]
+	fn test_contract_signer_fee() {
+		let account = Account::create().unwrap();
+		let params = [ContractParameter::integer(1)];
+		let mut tx = Transaction::<HttpProvider>::new();
+		tx.set_script(vec![OpCode::Push1 as u8]);
+		tx.set_signers(vec![
+			AccountSigner::called_by_entry(&account).unwrap().into(),
+			ContractSigner::called_by_entry(H160::repeat_byte(1), &params).into(),
+		]);
+		let script = account.get_verification_script().unwrap();
+		let mut calculator = NetworkFeeCalculator::default();
+
+		// The verification cost of the contract signer is unknown
+		assert!(matches!(
+			calculator.transaction_fee(&tx, std::slice::from_ref(&script)),
+			Err(TransactionError::TransactionConfiguration(_))
+		));
+		// One verification script per account signer
+		calculator.set_contract_verification_fee(Some(1_000_000));
+		assert!(calculator.transaction_fee(&tx, &[]).is_err());
+
+		let fee = calculator.transaction_fee(&tx, std::slice::from_ref(&script)).unwrap();
+		let key_pair = account.key_pair().clone().unwrap();
+		tx.add_witness(Witness::create(vec![0; 32], &key_pair).unwrap());
+		tx.add_witness(Witness::create_contract_witness(params.to_vec()).unwrap());
+		let size = NeoSerializable::size(&tx) as i64;
+		assert_eq!(fee, size * 1000 + calculator.verification_fee(&script).unwrap() + 1_000_000);
+	}
+
+	#[test]
+	fn test_non_standard_script() {
+		let script = VerificationScript::from(vec![OpCode::Push1 as u8]);
+		assert!(NetworkFeeCalculator::default().network_fee(0, &[script]).is_err());
+		assert!(NetworkFeeCalculator::default()
+			.transaction_fee(&Transaction::<HttpProvider>::new(), &[VerificationScript::new()])
+			.is_err());
+	}
+}
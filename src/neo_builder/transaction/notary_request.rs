@@ -0,0 +1,361 @@
+use std::str::FromStr;
+
+use once_cell::sync::Lazy;
+use primitive_types::H256;
+
+use crate::{
+	builder::{AccountSigner, Transaction, TransactionAttribute, TransactionError, Witness},
+	codec::{Decoder, Encoder, NeoSerializable},
+	crypto::HashableForVec,
+	neo_clients::{APITrait, JsonRpcProvider},
+	neo_crypto::utils::ToHexString,
+	neo_protocol::{Account, AccountTrait},
+	neo_types::{Bytes, OpCode, ScriptHash},
+};
+
+/// The script hash of the Notary native contract.
+pub static NOTARY_HASH: Lazy<ScriptHash> = Lazy::new(|| {
+	ScriptHash::from_str("c1e14f19c3e60d0b9244d06dd7ba9b113135ec3b")
+		.expect("Notary contract hash is a valid script hash")
+});
+
+/// A request to the P2P notary service of a neo-go node.
+///
+/// The main transaction is signed by some of its signers only, and the notary service collects
+/// the signatures of the others from their own requests before relaying it. It has the Notary
+/// contract among its signers, with the [`notary_witness`](Self::notary_witness) placeholder as
+/// witness, and a `NotaryAssisted` attribute with the number of keys the service collects.
+///
+/// The fallback transaction is relayed instead if the main transaction isn't completed before
+/// its `NotValidBefore` height. It pays the request's sender's fees from its Notary deposit, and
+/// conflicts with the main transaction so only one of them is accepted.
+#[derive(Debug, Clone)]
+pub struct P2PNotaryRequest<'a, P: JsonRpcProvider + 'static> {
+	pub main_transaction: Transaction<'a, P>,
+	pub fallback_transaction: Transaction<'a, P>,
+	/// The witness of the fallback transaction's sender over the request.
+	pub witness: Witness,
+}
+
+impl<'a, P: JsonRpcProvider + 'static> P2PNotaryRequest<'a, P> {
+	/// The invocation script of the Notary contract's witness before the service signs it.
+	pub fn notary_witness() -> Witness {
+		let mut invocation = vec![OpCode::PushData1 as u8, 64];
+		invocation.extend([0u8; 64]);
+		Witness::from_scripts(invocation, vec![])
+	}
+
+	/// Builds the fallback transaction of `main_transaction` for `sender`, valid from the height
+	/// `not_valid_before`, and signs it and the request with the key of `sender`.
+	///
+	/// Without `fallback_network_fee`, the network fee of the fallback transaction is calculated
+	/// through the `calculatenetworkfee` RPC, which includes the fee of its `NotaryAssisted`
+	/// attribute.
+	pub async fn create(
+		main_transaction: Transaction<'a, P>,
+		sender: &Account,
+		not_valid_before: u32,
+		fallback_network_fee: Option<i64>,
+	) -> Result<Self, TransactionError> {
+		let client = main_transaction.network.ok_or_else(|| {
+			TransactionError::TransactionConfiguration(
+				"The main transaction network client is not set".to_string(),
+			)
+		})?;
+		let fallback_network_fee = match fallback_network_fee {
+			Some(fee) => fee,
+			None => {
+				let fallback = Self::build_fallback_transaction(
+					&main_transaction,
+					sender,
+					not_valid_before,
+					0,
+				)?;
+				client
+					.calculate_network_fee(fallback.to_array().to_hex_string())
+					.await?
+					.network_fee
+			},
+		};
+		let network = client.network().await?;
+		Self::create_with_network(
+			main_transaction,
+			sender,
+			not_valid_before,
+			fallback_network_fee,
+			network,
+		)
+	}
+
+	/// Like [`create`](Self::create), for the network with the magic number `network` and
+	/// without any RPC call.
+	pub fn create_with_network(
+		main_transaction: Transaction<'a, P>,
+		sender: &Account,
+		not_valid_before: u32,
+		fallback_network_fee: i64,
+		network: u32,
+	) -> Result<Self, TransactionError> {
+		let key_pair = sender.key_pair().as_ref().ok_or_else(|| {
+			TransactionError::TransactionConfiguration(format!(
+				"Cannot sign the notary request because account {} does not hold a private key.",
+				sender.get_address()
+			))
+		})?;
+		let mut fallback_transaction = Self::build_fallback_transaction(
+			&main_transaction,
+			sender,
+			not_valid_before,
+			fallback_network_fee,
+		)?;
+		fallback_transaction.witnesses[1] =
+			Witness::create(fallback_transaction.get_sign_data(network), key_pair)?;
+
+		let mut request = Self { main_transaction, fallback_transaction, witness: Witness::new() };
+		request.witness = Witness::create(request.get_sign_data(network), key_pair)?;
+		Ok(request)
+	}
+
+	/// The hash of the request, which its witness signs.
+	pub fn hash(&self) -> H256 {
+		let mut hash = self.hashable_data().hash256();
+		hash.reverse();
+		H256::from_slice(&hash)
+	}
+
+	/// The data signed by the witness of the request on the network `network`.
+	pub fn get_sign_data(&self, network: u32) -> Bytes {
+		let mut data = self.hashable_data().hash256();
+		data.splice(0..0, network.to_le_bytes());
+		data
+	}
+
+	/// Submits the request to the notary service of the main transaction's node, and returns the
+	/// hash of the fallback transaction.
+	pub async fn submit(&self) -> Result<H256, TransactionError> {
+		let client = self.main_transaction.network.ok_or_else(|| {
+			TransactionError::TransactionConfiguration(
+				"The main transaction network client is not set".to_string(),
+			)
+		})?;
+		let result = client.submit_notary_request(self.to_array().to_hex_string()).await?;
+		Ok(result.hash)
+	}
+
+	fn hashable_data(&self) -> Bytes {
+		let mut writer = Encoder::new();
+		self.main_transaction.encode(&mut writer);
+		self.fallback_transaction.encode(&mut writer);
+		writer.to_bytes()
+	}
+
+	/// The fallback transaction of `main_transaction`, without the signature of `sender`.
+	fn build_fallback_transaction(
+		main_transaction: &Transaction<'a, P>,
+		sender: &Account,
+		not_valid_before: u32,
+		network_fee: i64,
+	) -> Result<Transaction<'a, P>, TransactionError> {
+		if !main_transaction
+			.signers
+			.iter()
+			.any(|signer| *signer.get_signer_hash() == *NOTARY_HASH)
+		{
+			return Err(TransactionError::TransactionConfiguration(
+				"The main transaction must have the Notary contract as signer".to_string(),
+			));
+		}
+		if !main_transaction.attributes.iter().any(|attr| attr.get_nkeys().is_some()) {
+			return Err(TransactionError::TransactionConfiguration(
+				"The main transaction must have a NotaryAssisted attribute".to_string(),
+			));
+		}
+		let verification_script = sender.get_verification_script().ok_or_else(|| {
+			TransactionError::TransactionConfiguration(format!(
+				"Account {} does not have a verification script",
+				sender.get_address()
+			))
+		})?;
+		if !verification_script.is_single_sig() {
+			return Err(TransactionError::TransactionConfiguration(
+				"The sender of a notary request must be a single-sig account".to_string(),
+			));
+		}
+
+		let mut fallback_transaction = Transaction::new();
+		fallback_transaction.network = main_transaction.network;
+		fallback_transaction.nonce = rand::random();
+		fallback_transaction.valid_until_block = main_transaction.valid_until_block;
+		fallback_transaction.net_fee = network_fee;
+		fallback_transaction.signers = vec![
+			AccountSigner::none_hash160(*NOTARY_HASH)?.into(),
+			AccountSigner::none(sender)?.into(),
+		];
+		fallback_transaction.attributes = vec![
+			TransactionAttribute::NotValidBefore { height: not_valid_before },
+			TransactionAttribute::Conflicts { hash: main_transaction.get_tx_id()? },
+			TransactionAttribute::NotaryAssisted { nkeys: 0 },
+		];
+		fallback_transaction.script = vec![OpCode::Ret as u8];
+		fallback_transaction.witnesses = vec![
+			Self::notary_witness(),
+			Witness::from_scripts(vec![], verification_script.script().clone()),
+		];
+		fallback_transaction.size = NeoSerializable::size(&fallback_transaction) as i32;
+		Ok(fallback_transaction)
+	}
+}
+
+impl<'a, P: JsonRpcProvider + 'static> Eq for P2PNotaryRequest<'a, P> {}
+
+impl<'a, P: JsonRpcProvider + 'static> PartialEq for P2PNotaryRequest<'a, P> {
+	fn eq(&self, other: &Self) -> bool {
+		self.to_array() == other.to_array()
+	}
+}
+
+impl<'a, P: JsonRpcProvider + 'static> NeoSerializable for P2PNotaryRequest<'a, P> {
+	type Error = TransactionError;
+
+	fn size(&self) -> usize {
+		NeoSerializable::size(&self.main_transaction)
+			+ NeoSerializable::size(&self.fallback_transaction)
+			+ self.witness.size()
+	}
+
+	fn encode(&self, writer: &mut Encoder) {
+		self.main_transaction.encode(writer);
+		self.fallback_transaction.encode(writer);
+		self.witness.encode(writer);
+	}
+
+	fn decode(reader: &mut Decoder) -> Result<Self, Self::Error>
+	where
+		Self: Sized,
+	{
+		let main_transaction = Transaction::decode(reader)?;
+		let fallback_transaction = Transaction::decode(reader)?;
+		let witness = Witness::decode(reader)?;
+		Ok(Self { main_transaction, fallback_transaction, witness })
+	}
+
+	fn to_array(&self) -> Vec<u8> {
+		let mut writer = Encoder::new();
+		self.encode(&mut writer);
+		writer.to_bytes()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		builder::{Signer, VerificationScript},
+		neo_clients::HttpProvider,
+	};
+
+	const NETWORK: u32 = 860833102;
+
+	fn main_transaction(account: &Account) -> Transaction<'static, HttpProvider> {
+		let mut tx = Transaction::new();
+		tx.set_script(vec![OpCode::Push1 as u8]);
+		tx.set_valid_until_block(1000);
+		tx.set_signers(vec![
+			AccountSigner::called_by_entry(account).unwrap().into(),
+			AccountSigner::none_hash160(*NOTARY_HASH).unwrap().into(),
+		]);
+		tx.set_attributes(vec![TransactionAttribute::NotaryAssisted { nkeys: 1 }]);
+		tx.set_witnesses(vec![
+			Witness::create(tx.get_sign_data(NETWORK), account.key_pair().as_ref().unwrap())
+				.unwrap(),
+			P2PNotaryRequest::<HttpProvider>::notary_witness(),
+		]);
+		tx
+	}
+
+	#[test]
+	fn test_fallback_transaction() {
+		let account = Account::create().unwrap();
+		let main = main_transaction(&account);
+		let request = P2PNotaryRequest::create_with_network(
+			main.clone(),
+			&account,
+			900,
+			2_0000_0000,
+			NETWORK,
+		)
+		.unwrap();
+
+		let fallback = &request.fallback_transaction;
+		assert_eq!(fallback.valid_until_block, 1000);
+		assert_eq!(fallback.net_fee, 2_0000_0000);
+		assert_eq!(fallback.script, vec![OpCode::Ret as u8]);
+		let signers: Vec<_> = fallback.signers.iter().map(Signer::get_signer_hash).collect();
+		assert_eq!(signers, vec![&*NOTARY_HASH, &account.get_script_hash()]);
+		assert_eq!(
+			fallback.attributes,
+			vec![
+				TransactionAttribute::NotValidBefore { height: 900 },
+				TransactionAttribute::Conflicts { hash: main.get_tx_id().unwrap() },
+				TransactionAttribute::NotaryAssisted { nkeys: 0 },
+			]
+		);
+		assert_eq!(fallback.witnesses[0], P2PNotaryRequest::<HttpProvider>::notary_witness());
+
+		let public_key = account.key_pair().as_ref().unwrap().public_key();
+		let signature = &fallback.witnesses[1].invocation.get_signatures()[0];
+		assert!(public_key.verify(&fallback.get_sign_data(NETWORK), signature).is_ok());
+		let signature = &request.witness.invocation.get_signatures()[0];
+		assert!(public_key.verify(&request.get_sign_data(NETWORK), signature).is_ok());
+		// The network magic is prefixed in little-endian order
+		assert_eq!(request.get_sign_data(NETWORK)[..4], NETWORK.to_le_bytes());
+		assert_eq!(fallback.get_sign_data(NETWORK)[..4], NETWORK.to_le_bytes());
+		assert_eq!(request.witness.verification, VerificationScript::from_public_key(&public_key));
+	}
+
+	#[test]
+	fn test_serialization() {
+		let account = Account::create().unwrap();
+		let main = main_transaction(&account);
+		let request = P2PNotaryRequest::create_with_network(
+			main.clone(),
+			&account,
+			900,
+			2_0000_0000,
+			NETWORK,
+		)
+		.unwrap();
+
+		let bytes = request.to_array();
+		assert_eq!(bytes.len(), request.size());
+		let mut expected = main.to_array();
+		expected.extend(request.fallback_transaction.to_array());
+		expected.extend(request.witness.to_array());
+		assert_eq!(bytes, expected);
+		let decoded = P2PNotaryRequest::<HttpProvider>::decode(&mut Decoder::new(&bytes)).unwrap();
+		assert_eq!(decoded, request);
+
+		let mut hash = bytes[..bytes.len() - request.witness.size()].hash256();
+		hash.reverse();
+		assert_eq!(request.hash(), H256::from_slice(&hash));
+
+		// Requests received from the node decode back to the same request
+		let decoded = P2PNotaryRequest::<HttpProvider>::decode(&mut Decoder::new(&bytes)).unwrap();
+		assert_eq!(decoded.main_transaction.signers, main.signers);
+		assert_eq!(decoded.to_array(), bytes);
+	}
+
+	#[test]
+	fn test_main_transaction_requirements() {
+		let account = Account::create().unwrap();
+		let mut main = main_transaction(&account);
+		main.set_attributes(vec![]);
+		assert!(
+			P2PNotaryRequest::create_with_network(main.clone(), &account, 900, 0, NETWORK).is_err()
+		);
+
+		main.set_attributes(vec![TransactionAttribute::NotaryAssisted { nkeys: 1 }]);
+		main.set_signers(vec![AccountSigner::called_by_entry(&account).unwrap().into()]);
+		assert!(P2PNotaryRequest::create_with_network(main, &account, 900, 0, NETWORK).is_err());
+	}
+}
@@ -0,0 +1,386 @@
+use primitive_types::H160;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+	builder::{
+		ContractParametersContext, SignerTrait, TransactionData, TransactionError,
+		VerificationScript,
+	},
+	crypto::KeyPair,
+	neo_clients::{APITrait, JsonRpcProvider, RpcClient},
+	neo_crypto::utils::{FromBase64String, ToBase64String},
+	neo_protocol::RawTransaction,
+	neo_types::ScriptHashExtension,
+};
+
+/// An unsigned transaction packaged for an air-gapped signer, in a versioned JSON format.
+///
+/// The envelope carries the unsigned transaction and the network magic in a
+/// [`ContractParametersContext`], the verification scripts of the signers, a human-readable
+/// summary and the fee breakdown. The online side exports it with
+/// [`TransactionBuilder::export_for_signing`](crate::neo_builder::TransactionBuilder::export_for_signing),
+/// the offline wallet signs it with
+/// [`Wallet::sign_envelope`](crate::neo_wallets::Wallet::sign_envelope), and the online side
+/// merges the witnesses and broadcasts the transaction with [`send`](Self::send).
+///
+/// The summary and fees are checked against the transaction when an envelope is imported, so
+/// the signer can trust what it displays.
+///
+/// # Examples
+///
+/// ```rust
+/// use neo3::neo_builder::{AccountSigner, SigningEnvelope, TransactionData};
+/// use neo3::neo_protocol::{Account, AccountTrait};
+///
+/// let account = Account::create().unwrap();
+/// let tx = TransactionData {
+///     signers: vec![AccountSigner::called_by_entry(&account).unwrap().into()],
+///     script: vec![0x11],
+///     ..Default::default()
+/// };
+/// let script = account.verification_script().clone().unwrap();
+///
+/// // Online: export the unsigned transaction
+/// let json = SigningEnvelope::new(&tx, 860833102, &[script]).unwrap().to_json().unwrap();
+///
+/// // Offline: review and sign
+/// let mut envelope = SigningEnvelope::from_json(&json).unwrap();
+/// println!("{}", envelope.summary);
+/// envelope.sign(account.key_pair().as_ref().unwrap()).unwrap();
+/// let json = envelope.to_json().unwrap();
+///
+/// // Online: merge the witnesses
+/// let signed = SigningEnvelope::from_json(&json).unwrap().signed_transaction().unwrap();
+/// assert_eq!(signed.witnesses.len(), 1);
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SigningEnvelope {
+	pub version: u32,
+	pub summary: String,
+	pub fees: FeeBreakdown,
+	pub signers: Vec<EnvelopeSigner>,
+	pub context: ContractParametersContext,
+}
+
+/// The fees of a transaction, in datoshi.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FeeBreakdown {
+	#[serde(rename = "systemfee")]
+	pub system_fee: i64,
+	#[serde(rename = "networkfee")]
+	pub network_fee: i64,
+	#[serde(rename = "totalfee")]
+	pub total_fee: i64,
+}
+
+/// A signer of the transaction of a [`SigningEnvelope`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct EnvelopeSigner {
+	pub account: String,
+	/// The base64 verification script, if the signer is an account that signs with its keys.
+	#[serde(rename = "verificationscript")]
+	pub verification_script: Option<String>,
+}
+
+impl SigningEnvelope {
+	/// The version of the envelope format.
+	pub const VERSION: u32 = 1;
+
+	/// Creates the envelope of the unsigned `tx` on the network with the magic number `network`.
+	///
+	/// `verification_scripts` are matched to the signers of `tx` by their hash. Signers without a
+	/// script, such as contracts, are listed without one and can't be signed offline.
+	pub fn new(
+		tx: &TransactionData,
+		network: u32,
+		verification_scripts: &[VerificationScript],
+	) -> Result<Self, TransactionError> {
+		let signers = tx
+			.signers
+			.iter()
+			.map(|signer| {
+				let hash = signer.get_signer_hash();
+				EnvelopeSigner {
+					account: hash.to_address(),
+					verification_script: verification_scripts
+						.iter()
+						.find(|script| script.hash() == *hash)
+						.map(|script| script.script().to_base64_string()),
+				}
+			})
+			.collect();
+		let fees = FeeBreakdown {
+			system_fee: tx.sys_fee,
+			network_fee: tx.net_fee,
+			total_fee: tx.sys_fee.checked_add(tx.net_fee).ok_or_else(|| {
+				TransactionError::TransactionConfiguration("The total fee overflows".to_string())
+			})?,
+		};
+		Ok(Self {
+			version: Self::VERSION,
+			summary: Self::summarize(tx, network),
+			fees,
+			signers,
+			context: ContractParametersContext::from_transaction_data(tx, network),
+		})
+	}
+
+	/// Imports an envelope, checking that its summary, fees and signers match its transaction.
+	pub fn from_json(json: &str) -> Result<Self, TransactionError> {
+		let envelope: Self = serde_json::from_str(json).map_err(|e| {
+			TransactionError::TransactionConfiguration(format!("Invalid envelope JSON: {}", e))
+		})?;
+		envelope.validate()?;
+		Ok(envelope)
+	}
+
+	pub fn to_json(&self) -> Result<String, TransactionError> {
+		serde_json::to_string_pretty(self).map_err(|e| {
+			TransactionError::TransactionConfiguration(format!("Failed to encode envelope: {}", e))
+		})
+	}
+
+	/// The network magic the transaction is signed for.
+	pub fn network(&self) -> u32 {
+		self.context.network
+	}
+
+	/// The unsigned transaction of the envelope.
+	pub fn transaction(&self) -> Result<TransactionData, TransactionError> {
+		let tx = TransactionData::from_base64(&self.context.data)?;
+		if format!("{:#x}", tx.tx_hash()) != self.context.hash.to_lowercase() {
+			return Err(TransactionError::TransactionConfiguration(
+				"The envelope hash doesn't match its transaction".to_string(),
+			));
+		}
+		Ok(tx)
+	}
+
+	/// The verification scripts of the signers that can be signed offline.
+	pub fn verification_scripts(&self) -> Result<Vec<VerificationScript>, TransactionError> {
+		self.signers
+			.iter()
+			.filter_map(|signer| signer.verification_script.as_ref())
+			.map(|script| {
+				script.from_base64_string().map(VerificationScript::from).map_err(|e| {
+					TransactionError::TransactionConfiguration(format!(
+						"Invalid verification script: {}",
+						e
+					))
+				})
+			})
+			.collect()
+	}
+
+	/// Signs the transaction with `key_pair` for every signer whose verification script contains
+	/// its public key, and returns the number of signatures added.
+	pub fn sign(&mut self, key_pair: &KeyPair) -> Result<usize, TransactionError> {
+		let public_key = key_pair.public_key();
+		let mut added = 0;
+		for script in self.verification_scripts()? {
+			if !script.is_single_sig() && !script.is_multi_sig() {
+				continue;
+			}
+			if script.get_public_keys()?.contains(&public_key)
+				&& self.context.sign(&script, key_pair)?
+			{
+				added += 1;
+			}
+		}
+		Ok(added)
+	}
+
+	/// The signers that don't have enough signatures yet, in order.
+	pub fn get_missing_signers(&self) -> Result<Vec<H160>, TransactionError> {
+		self.context.get_missing_signers()
+	}
+
+	pub fn is_completed(&self) -> Result<bool, TransactionError> {
+		self.context.is_completed()
+	}
+
+	/// The transaction with the witnesses built from the signatures of the envelope.
+	pub fn signed_transaction(&self) -> Result<TransactionData, TransactionError> {
+		let mut tx = self.transaction()?;
+		tx.witnesses = self.context.get_witnesses()?;
+		Ok(tx)
+	}
+
+	/// Merges the witnesses and sends the transaction through `client`, which must be connected
+	/// to the network of the envelope.
+	pub async fn send<P: JsonRpcProvider + 'static>(
+		&self,
+		client: &RpcClient<P>,
+	) -> Result<RawTransaction, TransactionError> {
+		let network = client.network().await?;
+		if network != self.network() {
+			return Err(TransactionError::TransactionConfiguration(format!(
+				"The envelope is for network {}, but the client is connected to network {}",
+				self.network(),
+				network
+			)));
+		}
+		self.signed_transaction()?.bind(client).send_tx().await
+	}
+
+	fn validate(&self) -> Result<(), TransactionError> {
+		if self.version != Self::VERSION {
+			return Err(TransactionError::TransactionConfiguration(format!(
+				"Unsupported envelope version {}",
+				self.version
+			)));
+		}
+		let tx = self.transaction()?;
+		let expected = Self::new(&tx, self.network(), &self.verification_scripts()?)?;
+		if self.summary != expected.summary {
+			return Err(TransactionError::TransactionConfiguration(
+				"The envelope summary doesn't match its transaction".to_string(),
+			));
+		}
+		if self.fees != expected.fees {
+			return Err(TransactionError::TransactionConfiguration(
+				"The envelope fees don't match its transaction".to_string(),
+			));
+		}
+		if self.signers != expected.signers {
+			return Err(TransactionError::TransactionConfiguration(
+				"The envelope signers don't match its transaction".to_string(),
+			));
+		}
+		Ok(())
+	}
+
+	fn summarize(tx: &TransactionData, network: u32) -> String {
+		let mut lines = vec![
+			format!("Transaction: {:#x}", tx.tx_hash()),
+			format!("Network: {}", network),
+			format!("Valid until block: {}", tx.valid_until_block),
+		];
+		for signer in &tx.signers {
+			let scopes: Vec<String> =
+				signer.get_scopes().iter().map(|scope| scope.to_string()).collect();
+			lines.push(format!(
+				"Signer: {} ({})",
+				signer.get_signer_hash().to_address(),
+				scopes.join(", ")
+			));
+		}
+		lines.push(format!("Script: {}", hex::encode(&tx.script)));
+		lines.push(format!("System fee: {} GAS", Self::format_gas(tx.sys_fee)));
+		lines.push(format!("Network fee: {} GAS", Self::format_gas(tx.net_fee)));
+		lines.push(format!(
+			"Total fee: {} GAS",
+			Self::format_gas(tx.sys_fee.saturating_add(tx.net_fee))
+		));
+		lines.join("\n")
+	}
+
+	fn format_gas(datoshi: i64) -> String {
+		let sign = if datoshi < 0 { "-" } else { "" };
+		let datoshi = datoshi.unsigned_abs();
+		format!("{}{}.{:08}", sign, datoshi / 100_000_000, datoshi % 100_000_000)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		builder::AccountSigner,
+		neo_protocol::{Account, AccountTrait},
+	};
+
+	const NETWORK: u32 = 860833102;
+
+	fn transaction(signers: &[&Account]) -> TransactionData {
+		TransactionData {
+			nonce: 1,
+			valid_until_block: 1000,
+			signers: signers
+				.iter()
+				.map(|account| AccountSigner::called_by_entry(account).unwrap().into())
+				.collect(),
+			sys_fee: 1_0000_0000,
+			net_fee: 123_4567,
+			script: vec![0x11, 0x40],
+			..Default::default()
+		}
+	}
+
+	#[test]
+	fn test_export() {
+		let account = Account::create().unwrap();
+		let tx = transaction(&[&account]);
+		let script = account.verification_script().clone().unwrap();
+		let envelope = SigningEnvelope::new(&tx, NETWORK, std::slice::from_ref(&script)).unwrap();
+
+		assert_eq!(envelope.version, SigningEnvelope::VERSION);
+		assert_eq!(envelope.network(), NETWORK);
+		assert_eq!(envelope.transaction().unwrap(), tx);
+		assert_eq!(
+			envelope.fees,
+			FeeBreakdown { system_fee: 1_0000_0000, network_fee: 123_4567, total_fee: 1_0123_4567 }
+		);
+		assert_eq!(
+			envelope.signers,
+			vec![EnvelopeSigner {
+				account: account.get_address(),
+				verification_script: Some(script.script().to_base64_string()),
+			}]
+		);
+		assert!(envelope.summary.contains(&format!("Transaction: {:#x}", tx.tx_hash())));
+		assert!(envelope
+			.summary
+			.contains(&format!("Signer: {} (CalledByEntry)", account.get_address())));
+		assert!(envelope.summary.contains("Total fee: 1.01234567 GAS"));
+
+		let json = envelope.to_json().unwrap();
+		assert_eq!(SigningEnvelope::from_json(&json).unwrap(), envelope);
+	}
+
+	#[test]
+	fn test_sign_and_merge() {
+		let first = Account::create().unwrap();
+		let second = Account::create().unwrap();
+		let tx = transaction(&[&first, &second]);
+		let scripts = [
+			first.verification_script().clone().unwrap(),
+			second.verification_script().clone().unwrap(),
+		];
+		let mut envelope = SigningEnvelope::new(&tx, NETWORK, &scripts).unwrap();
+
+		assert_eq!(envelope.sign(first.key_pair().as_ref().unwrap()).unwrap(), 1);
+		assert_eq!(envelope.get_missing_signers().unwrap(), vec![second.get_script_hash()]);
+		assert!(envelope.signed_transaction().is_err());
+
+		let mut envelope = SigningEnvelope::from_json(&envelope.to_json().unwrap()).unwrap();
+		assert_eq!(envelope.sign(second.key_pair().as_ref().unwrap()).unwrap(), 1);
+		assert_eq!(envelope.sign(second.key_pair().as_ref().unwrap()).unwrap(), 0);
+		assert!(envelope.is_completed().unwrap());
+
+		let signed = envelope.signed_transaction().unwrap();
+		assert_eq!(signed.tx_hash(), tx.tx_hash());
+		assert_eq!(signed.witnesses.len(), 2);
+		assert_eq!(signed.witnesses[1].verification, scripts[1]);
+	}
+
+	#[test]
+	fn test_import_rejects_tampered_envelope() {
+		let account = Account::create().unwrap();
+		let tx = transaction(&[&account]);
+		let script = account.verification_script().clone().unwrap();
+		let envelope = SigningEnvelope::new(&tx, NETWORK, &[script]).unwrap();
+
+		let mut tampered = envelope.clone();
+		tampered.summary = tampered.summary.replace("1.01234567", "0.00000001");
+		assert!(SigningEnvelope::from_json(&tampered.to_json().unwrap()).is_err());
+
+		let mut tampered = envelope.clone();
+		tampered.fees.network_fee = 0;
+		assert!(SigningEnvelope::from_json(&tampered.to_json().unwrap()).is_err());
+
+		let mut tampered = envelope;
+		tampered.version = 2;
+		assert!(SigningEnvelope::from_json(&tampered.to_json().unwrap()).is_err());
+	}
+}
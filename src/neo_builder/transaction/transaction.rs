@@ -9,7 +9,9 @@ use serde_with::__private__::DeError;
 use tracing::info;
 
 use crate::{
-	builder::{init_logger, Signer, TransactionAttribute, TransactionError, Witness},
+	builder::{
+		init_logger, Signer, TransactionAttribute, TransactionData, TransactionError, Witness,
+	},
 	codec::{Decoder, Encoder, NeoSerializable, VarSizeTrait},
 	config::NeoConstants,
 	crypto::HashableForVec,
@@ -213,7 +215,7 @@ impl<'a, P: JsonRpcProvider + 'static> Hash for Transaction<'a, P> {
 }
 
 impl<'a, T: JsonRpcProvider + 'static> Transaction<'a, T> {
-	const HEADER_SIZE: usize = 25;
+	pub(crate) const HEADER_SIZE: usize = 25;
 	pub fn new() -> Self {
 		Self::default()
 	}
@@ -233,16 +235,20 @@ impl<'a, T: JsonRpcProvider + 'static> Transaction<'a, T> {
 				"Transaction network magic is not set".to_string(),
 			));
 		}
+		let network_value = self.network.as_ref().unwrap().network().await?;
+		Ok(self.get_sign_data(network_value))
+	}
+
+	/// The data signed by the witnesses of the transaction on the network `network`.
+	pub(crate) fn get_sign_data(&self, network: u32) -> Bytes {
 		let mut encoder = Encoder::new();
 		self.serialize_without_witnesses(&mut encoder);
 		let mut data = encoder.to_bytes().hash256();
-		let network_value = self.network.as_ref().unwrap().network().await?;
-		data.splice(0..0, network_value.to_be_bytes());
-
-		Ok(data)
+		data.splice(0..0, network.to_le_bytes());
+		data
 	}
 
-	fn get_tx_id(&self) -> Result<primitive_types::H256, TransactionError> {
+	pub(crate) fn get_tx_id(&self) -> Result<primitive_types::H256, TransactionError> {
 		let mut encoder = Encoder::new();
 		self.serialize_without_witnesses(&mut encoder);
 		let data = encoder.to_bytes().hash256();
@@ -250,19 +256,8 @@ impl<'a, T: JsonRpcProvider + 'static> Transaction<'a, T> {
 		Ok(primitive_types::H256::from_slice(&reversed_data))
 	}
 
-	fn serialize_without_witnesses(&self, writer: &mut Encoder) {
-		writer.write_u8(self.version);
-		writer.write_u32(self.nonce);
-		writer.write_i64(self.sys_fee);
-		writer.write_i64(self.net_fee);
-		writer.write_u32(self.valid_until_block);
-		writer
-			.write_serializable_variable_list(&self.signers)
-			.expect("Failed to encode signers");
-		writer
-			.write_serializable_variable_list(&self.attributes)
-			.expect("Failed to encode attributes");
-		writer.write_var_bytes(&self.script).expect("Failed to encode script");
+	pub(crate) fn serialize_without_witnesses(&self, writer: &mut Encoder) {
+		TransactionData::from(self).serialize_without_witnesses(writer);
 	}
 
 	/// Sends the transaction to the Neo N3 network.
@@ -652,52 +647,7 @@ impl<'a, P: JsonRpcProvider + 'static> NeoSerializable for Transaction<'a, P> {
 	where
 		Self: Sized,
 	{
-		let version = reader.read_u8();
-		let nonce = reader.read_u32().map_err(|e| {
-			TransactionError::TransactionConfiguration(format!("Failed to read nonce: {}", e))
-		})?;
-		let system_fee = reader.read_i64().map_err(|e| {
-			TransactionError::TransactionConfiguration(format!("Failed to read system fee: {}", e))
-		})?;
-		let network_fee = reader.read_i64().map_err(|e| {
-			TransactionError::TransactionConfiguration(format!("Failed to read network fee: {}", e))
-		})?;
-		let valid_until_block = reader.read_u32().map_err(|e| {
-			TransactionError::TransactionConfiguration(format!(
-				"Failed to read valid until block: {}",
-				e
-			))
-		})?;
-
-		// Read signers
-		let signers: Vec<Signer> = reader.read_serializable_list::<Signer>()?;
-
-		// Read attributes
-		let attributes: Vec<TransactionAttribute> =
-			reader.read_serializable_list::<TransactionAttribute>()?;
-
-		let script = reader.read_var_bytes()?.to_vec();
-
-		let mut witnesses = vec![];
-		if reader.available() > 0 {
-			witnesses.append(&mut reader.read_serializable_list::<Witness>()?);
-		}
-
-		Ok(Self {
-			network: None,
-			version,
-			nonce,
-			valid_until_block,
-			size: 0,
-			sys_fee: system_fee,
-			net_fee: network_fee,
-			signers,
-			attributes,
-			script,
-			witnesses,
-			// block_time: None,
-			block_count_when_sent: None,
-		})
+		TransactionData::decode(reader).map(Transaction::from)
 	}
 
 	fn to_array(&self) -> Vec<u8> {
@@ -1,7 +1,4 @@
-use std::{
-	fmt::Debug,
-	hash::{Hash, Hasher},
-};
+use std::{fmt::Debug, hash::Hash};
 
 use crate::neo_crypto::utils::FromBase64String;
 use primitive_types::H256;
@@ -9,7 +6,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::{
 	builder::TransactionError,
-	codec::{Decoder, Encoder, NeoSerializable},
+	codec::{Decoder, Encoder, NeoSerializable, VarSizeTrait},
 	prelude::Base64Encode,
 };
 
@@ -32,11 +29,17 @@ pub enum TransactionAttribute {
 	Conflicts {
 		hash: H256,
 	},
+
+	#[serde(rename = "NotaryAssisted")]
+	NotaryAssisted {
+		#[serde(rename = "nkeys")]
+		nkeys: u8,
+	},
 }
 
 #[derive(Serialize, Deserialize, PartialEq, Hash, Debug, Clone)]
 pub struct OracleResponse {
-	pub id: u32,
+	pub id: u64,
 	pub response_code: OracleResponseCode,
 	pub result: String,
 }
@@ -44,20 +47,37 @@ pub struct OracleResponse {
 impl TransactionAttribute {
 	pub const MAX_RESULT_SIZE: usize = 0xffff;
 
+	const HIGH_PRIORITY: u8 = 0x01;
+	const ORACLE_RESPONSE: u8 = 0x11;
+	const NOT_VALID_BEFORE: u8 = 0x20;
+	pub(crate) const CONFLICTS: u8 = 0x21;
+	pub(crate) const NOTARY_ASSISTED: u8 = 0x22;
+
 	pub fn to_bytes(&self) -> Vec<u8> {
 		let mut bytes = vec![];
 
 		match self {
 			TransactionAttribute::HighPriority => {
-				bytes.push(0x01);
+				bytes.push(Self::HIGH_PRIORITY);
 			},
 			TransactionAttribute::OracleResponse(OracleResponse { id, response_code, result }) => {
-				bytes.push(0x11);
+				bytes.push(Self::ORACLE_RESPONSE);
 				bytes.extend(&id.to_be_bytes());
 				bytes.push(*response_code as u8);
 				bytes.extend(result.as_bytes());
 			},
-			_ => {},
+			TransactionAttribute::NotValidBefore { height } => {
+				bytes.push(Self::NOT_VALID_BEFORE);
+				bytes.extend(&height.to_le_bytes());
+			},
+			TransactionAttribute::Conflicts { hash } => {
+				bytes.push(Self::CONFLICTS);
+				bytes.extend(hash.as_bytes().iter().rev());
+			},
+			TransactionAttribute::NotaryAssisted { nkeys } => {
+				bytes.push(Self::NOTARY_ASSISTED);
+				bytes.push(*nkeys);
+			},
 		}
 
 		bytes
@@ -65,8 +85,8 @@ impl TransactionAttribute {
 
 	pub fn from_bytes(bytes: &[u8]) -> Result<Self, &'static str> {
 		match bytes[0] {
-			0x01 => Ok(TransactionAttribute::HighPriority),
-			0x11 => {
+			Self::HIGH_PRIORITY => Ok(TransactionAttribute::HighPriority),
+			Self::ORACLE_RESPONSE => {
 				if bytes.len() < 9 {
 					return Err("Not enough bytes for OracleResponse");
 				}
@@ -79,11 +99,27 @@ impl TransactionAttribute {
 					String::from_utf8(bytes[10..].to_vec()).map_err(|_| "Invalid UTF-8").unwrap();
 
 				Ok(TransactionAttribute::OracleResponse(OracleResponse {
-					id: id as u32,
+					id,
 					response_code,
 					result,
 				}))
 			},
+			Self::NOT_VALID_BEFORE => {
+				let height = bytes.get(1..5).ok_or("Not enough bytes for NotValidBefore")?;
+				Ok(TransactionAttribute::NotValidBefore {
+					height: u32::from_le_bytes(height.try_into().unwrap()),
+				})
+			},
+			Self::CONFLICTS => {
+				let hash = bytes.get(1..33).ok_or("Not enough bytes for Conflicts")?;
+				Ok(TransactionAttribute::Conflicts {
+					hash: H256::from_slice(&hash.iter().rev().copied().collect::<Vec<u8>>()),
+				})
+			},
+			Self::NOTARY_ASSISTED => {
+				let nkeys = *bytes.get(1).ok_or("Not enough bytes for NotaryAssisted")?;
+				Ok(TransactionAttribute::NotaryAssisted { nkeys })
+			},
 			_ => Err("Invalid attribute type byte"),
 		}
 	}
@@ -100,13 +136,21 @@ impl TransactionAttribute {
 		}
 	}
 
-	// Get the height for NotValidBefore attribute
+	// Get the hash for Conflicts attribute
 	pub fn get_hash(&self) -> Option<&H256> {
 		match self {
 			TransactionAttribute::Conflicts { hash } => Some(hash),
 			_ => None,
 		}
 	}
+
+	// Get the number of keys for NotaryAssisted attribute
+	pub fn get_nkeys(&self) -> Option<u8> {
+		match self {
+			TransactionAttribute::NotaryAssisted { nkeys } => Some(*nkeys),
+			_ => None,
+		}
+	}
 }
 
 impl NeoSerializable for TransactionAttribute {
@@ -119,43 +163,55 @@ impl NeoSerializable for TransactionAttribute {
 				id: _,
 				response_code: _,
 				result,
-			}) => 1 + 9 + result.len(),
+			}) => 1 + 8 + 1 + result.from_base64_string().unwrap_or_default().var_size(),
 			TransactionAttribute::NotValidBefore { height: _ } => 1 + 4, // 1 byte type + 4 bytes height
 			TransactionAttribute::Conflicts { hash: _ } => 1 + 32,       // 1 byte type + 32 bytes hash
+			TransactionAttribute::NotaryAssisted { nkeys: _ } => 1 + 1,  // 1 byte type + 1 byte nkeys
 		}
 	}
 
 	fn encode(&self, writer: &mut Encoder) {
 		match self {
 			TransactionAttribute::HighPriority => {
-				writer.write_u8(0x01);
+				writer.write_u8(Self::HIGH_PRIORITY);
 			},
 			TransactionAttribute::OracleResponse(OracleResponse { id, response_code, result }) => {
-				writer.write_u8(0x11);
-				let mut v = id.to_be_bytes();
-				v.reverse();
-				writer.write(&v);
+				writer.write_u8(Self::ORACLE_RESPONSE);
+				writer.write_u64(*id);
 				writer.write_u8(*response_code as u8);
 				writer
 					.write_var_bytes(result.from_base64_string().unwrap().as_slice())
 					.expect("Failed to encode oracle response");
 			},
-			_ => {},
+			TransactionAttribute::NotValidBefore { height } => {
+				writer.write_u8(Self::NOT_VALID_BEFORE);
+				writer.write_u32(*height);
+			},
+			TransactionAttribute::Conflicts { hash } => {
+				writer.write_u8(Self::CONFLICTS);
+				let mut v = hash.to_fixed_bytes();
+				v.reverse();
+				writer.write_bytes(&v);
+			},
+			TransactionAttribute::NotaryAssisted { nkeys } => {
+				writer.write_u8(Self::NOTARY_ASSISTED);
+				writer.write_u8(*nkeys);
+			},
 		}
 	}
 
 	fn decode(reader: &mut Decoder) -> Result<Self, Self::Error> {
-		match reader.read_u8() {
-			0x01 => Ok(TransactionAttribute::HighPriority),
-			0x11 => {
-				let id = reader.read_u32().map_err(|e| {
+		match reader.try_read_u8()? {
+			Self::HIGH_PRIORITY => Ok(TransactionAttribute::HighPriority),
+			Self::ORACLE_RESPONSE => {
+				let id = reader.read_u64().map_err(|e| {
 					TransactionError::TransactionConfiguration(format!(
 						"Failed to read oracle response ID: {}",
 						e
 					))
 				})?;
 				let response_code =
-					OracleResponseCode::try_from(reader.read_u8()).map_err(|_| {
+					OracleResponseCode::try_from(reader.try_read_u8()?).map_err(|_| {
 						TransactionError::TransactionConfiguration(
 							"Invalid oracle response code".to_string(),
 						)
@@ -176,6 +232,34 @@ impl NeoSerializable for TransactionAttribute {
 					result,
 				}))
 			},
+			Self::NOT_VALID_BEFORE => {
+				let height = reader.read_u32().map_err(|e| {
+					TransactionError::TransactionConfiguration(format!(
+						"Failed to read not valid before height: {}",
+						e
+					))
+				})?;
+				Ok(TransactionAttribute::NotValidBefore { height })
+			},
+			Self::CONFLICTS => {
+				let mut hash = reader.read_bytes(32).map_err(|e| {
+					TransactionError::TransactionConfiguration(format!(
+						"Failed to read conflicting transaction hash: {}",
+						e
+					))
+				})?;
+				hash.reverse();
+				Ok(TransactionAttribute::Conflicts { hash: H256::from_slice(&hash) })
+			},
+			Self::NOTARY_ASSISTED => {
+				let nkeys = reader.try_read_u8().map_err(|e| {
+					TransactionError::TransactionConfiguration(format!(
+						"Failed to read notary assisted key count: {}",
+						e
+					))
+				})?;
+				Ok(TransactionAttribute::NotaryAssisted { nkeys })
+			},
 			_ => Err(TransactionError::InvalidTransaction),
 		}
 	}
@@ -61,8 +61,9 @@ use crate::neo_types::{Bytes, ContractParameter, InvocationResult, ScriptHash};
 // Import transaction types from neo_builder
 use crate::neo_builder::{
 	transaction::{
-		Signer, SignerType, Transaction, TransactionAttribute, TransactionError,
-		VerificationScript, Witness, WitnessScope,
+		NetworkFeeCalculator, Signer, SignerType, SigningEnvelope, Transaction,
+		TransactionAttribute, TransactionData, TransactionError, VerificationScript, Witness,
+		WitnessScope,
 	},
 	BuilderError,
 };
@@ -101,6 +102,10 @@ pub struct TransactionBuilder<'a, P: JsonRpcProvider + 'static> {
 	attributes: Vec<TransactionAttribute>,
 	#[getset(get = "pub", set = "pub")]
 	script: Option<Bytes>,
+	/// Calculates the network fee offline instead of through the `calculatenetworkfee` RPC.
+	#[getset(get = "pub", set = "pub")]
+	network_fee_calculator: Option<NetworkFeeCalculator>,
+	system_fee: Option<u64>,
 	fee_consumer: Option<Box<dyn Fn(i64, i64)>>,
 	fee_error: Option<TransactionError>,
 }
@@ -116,6 +121,8 @@ impl<'a, P: JsonRpcProvider + 'static> Debug for TransactionBuilder<'a, P> {
 			.field("additional_system_fee", &self.additional_system_fee)
 			.field("attributes", &self.attributes)
 			.field("script", &self.script)
+			.field("network_fee_calculator", &self.network_fee_calculator)
+			.field("system_fee", &self.system_fee)
 			// .field("fee_consumer", &self.fee_consumer)
 			.field("fee_error", &self.fee_error)
 			.finish()
@@ -134,6 +141,8 @@ impl<'a, P: JsonRpcProvider + 'static> Clone for TransactionBuilder<'a, P> {
 			additional_system_fee: self.additional_system_fee,
 			attributes: self.attributes.clone(),
 			script: self.script.clone(),
+			network_fee_calculator: self.network_fee_calculator,
+			system_fee: self.system_fee,
 			// fee_consumer: self.fee_consumer.clone(),
 			fee_consumer: None,
 			fee_error: None,
@@ -153,6 +162,8 @@ impl<'a, P: JsonRpcProvider + 'static> PartialEq for TransactionBuilder<'a, P> {
 			&& self.additional_system_fee == other.additional_system_fee
 			&& self.attributes == other.attributes
 			&& self.script == other.script
+			&& self.network_fee_calculator == other.network_fee_calculator
+			&& self.system_fee == other.system_fee
 	}
 }
 
@@ -166,6 +177,8 @@ impl<'a, P: JsonRpcProvider + 'static> Hash for TransactionBuilder<'a, P> {
 		self.additional_system_fee.hash(state);
 		self.attributes.hash(state);
 		self.script.hash(state);
+		self.network_fee_calculator.hash(state);
+		self.system_fee.hash(state);
 	}
 }
 
@@ -205,6 +218,8 @@ impl<'a, P: JsonRpcProvider + 'static> TransactionBuilder<'a, P> {
 			additional_system_fee: 0,
 			attributes: Vec::new(),
 			script: None,
+			network_fee_calculator: None,
+			system_fee: None,
 			fee_consumer: None,
 			fee_error: None,
 		}
@@ -241,6 +256,8 @@ impl<'a, P: JsonRpcProvider + 'static> TransactionBuilder<'a, P> {
 			additional_system_fee: 0,
 			attributes: Vec::new(),
 			script: None,
+			network_fee_calculator: None,
+			system_fee: None,
 			fee_consumer: None,
 			fee_error: None,
 		}
@@ -346,6 +363,16 @@ impl<'a, P: JsonRpcProvider + 'static> TransactionBuilder<'a, P> {
 		Ok(self)
 	}
 
+	/// Sets the system fee, instead of fetching it through the `invokescript` RPC.
+	///
+	/// Together with [`valid_until_block`](Self::valid_until_block) and a
+	/// [`NetworkFeeCalculator`], this allows building a transaction without a client. The
+	/// additional system fee is still added on top.
+	pub fn system_fee(&mut self, fee: u64) -> &mut Self {
+		self.system_fee = Some(fee);
+		self
+	}
+
 	// Set script
 	// pub fn set_script(&mut self, script: Vec<u8>) -> &mut Self {
 	// 	self.script = Some(script);
@@ -456,11 +483,14 @@ impl<'a, P: JsonRpcProvider + 'static> TransactionBuilder<'a, P> {
 		}
 
 		if self.valid_until_block.is_none() {
-			self.valid_until_block = Some(
-				self.fetch_current_block_count().await?
-					+ self.client.unwrap().max_valid_until_block_increment()
-					- 1,
-			)
+			let client = self.client.ok_or_else(|| {
+				TransactionError::TransactionConfiguration(
+					"A client is required to fetch the block count, set valid_until_block instead"
+						.to_string(),
+				)
+			})?;
+			self.valid_until_block =
+				Some(client.get_block_count().await? + client.max_valid_until_block_increment() - 1)
 		}
 
 		// Check committe member
@@ -481,13 +511,16 @@ impl<'a, P: JsonRpcProvider + 'static> TransactionBuilder<'a, P> {
 		// 	.await
 		// 	.map_err(|e| TransactionError::ProviderError(e))?;
 
-		let system_fee = self.get_system_fee().await? + self.additional_system_fee as i64;
+		let system_fee = match self.system_fee {
+			Some(fee) => fee as i64,
+			None => self.get_system_fee().await?,
+		} + self.additional_system_fee as i64;
 
 		let network_fee = self.get_network_fee().await? + self.additional_network_fee as i64;
 
 		// Check sender balance if needed
 		let tx = Transaction {
-			network: Some(self.client.unwrap()),
+			network: self.client,
 			version: self.version,
 			nonce: self.nonce,
 			valid_until_block: self.valid_until_block.unwrap_or(100),
@@ -511,7 +544,9 @@ impl<'a, P: JsonRpcProvider + 'static> TransactionBuilder<'a, P> {
 				return Err(supplier.clone());
 			}
 		} else if let Some(fee_consumer) = &self.fee_consumer {
-			let sender_balance = self.get_sender_balance().await?.try_into().unwrap(); // self.get_sender_balance().await.unwrap();
+			let sender_balance = i64::try_from(self.get_sender_balance().await?).map_err(|_| {
+				TransactionError::IllegalState("Sender balance out of range for i64".to_string())
+			})?;
 			if network_fee + system_fee > sender_balance {
 				fee_consumer(network_fee + system_fee, sender_balance);
 			}
@@ -524,9 +559,11 @@ impl<'a, P: JsonRpcProvider + 'static> TransactionBuilder<'a, P> {
 	async fn get_system_fee(&self) -> Result<i64, TransactionError> {
 		let script = self.script.as_ref().ok_or_else(|| TransactionError::NoScript)?;
 
-		let client = self
-			.client
-			.ok_or_else(|| TransactionError::IllegalState("Client is not set".to_string()))?;
+		let client = self.client.ok_or_else(|| {
+			TransactionError::TransactionConfiguration(
+				"A client is required to fetch the system fee, set it manually instead".to_string(),
+			)
+		})?;
 
 		let response = client
 			.invoke_script(script.to_hex_string(), vec![self.signers[0].clone()])
@@ -558,17 +595,12 @@ impl<'a, P: JsonRpcProvider + 'static> TransactionBuilder<'a, P> {
 	}
 
 	async fn get_network_fee(&mut self) -> Result<i64, TransactionError> {
-		// Check sender balance if needed
-		let client = self
-			.client
-			.ok_or_else(|| TransactionError::IllegalState("Client is not set".to_string()))?;
-
 		let script = self.script.clone().unwrap_or_default(); // Use default if None
 
 		let valid_until_block = self.valid_until_block.unwrap_or(100);
 
 		let mut tx = Transaction {
-			network: Some(client),
+			network: self.client,
 			version: self.version,
 			nonce: self.nonce,
 			valid_until_block,
@@ -582,6 +614,7 @@ impl<'a, P: JsonRpcProvider + 'static> TransactionBuilder<'a, P> {
 			block_count_when_sent: None,
 		};
 		let mut has_atleast_one_signing_account = false;
+		let mut verification_scripts = Vec::new();
 
 		for signer in self.signers.iter() {
 			match signer {
@@ -625,6 +658,7 @@ impl<'a, P: JsonRpcProvider + 'static> TransactionBuilder<'a, P> {
 						vec![],
 						verification_script.script().to_vec(),
 					));
+					verification_scripts.push(verification_script);
 					has_atleast_one_signing_account = true;
 				},
 				// If there's a case for TransactionSigner, it can be handled here if necessary.
@@ -637,19 +671,21 @@ impl<'a, P: JsonRpcProvider + 'static> TransactionBuilder<'a, P> {
 			return Err(TransactionError::TransactionConfiguration("A transaction requires at least one signing account (i.e. an AccountSigner). None was provided.".to_string()));
 		}
 
+		if let Some(calculator) = &self.network_fee_calculator {
+			return calculator.transaction_fee(&tx, &verification_scripts);
+		}
+
+		let client = self.client.ok_or_else(|| {
+			TransactionError::TransactionConfiguration(
+				"A client or a network fee calculator is required to calculate the network fee"
+					.to_string(),
+			)
+		})?;
 		let fee = client.calculate_network_fee(tx.to_array().to_hex_string()).await?;
 		Ok(fee.network_fee)
 	}
 
-	async fn fetch_current_block_count(&mut self) -> Result<u32, TransactionError> {
-		let client = self
-			.client
-			.ok_or_else(|| TransactionError::IllegalState("Client is not set".to_string()))?;
-		let count = client.get_block_count().await?;
-		Ok(count)
-	}
-
-	async fn get_sender_balance(&self) -> Result<u64, TransactionError> {
+	pub(crate) async fn get_sender_balance(&self) -> Result<u64, TransactionError> {
 		// Call network
 		let sender = &self.signers[0];
 
@@ -851,6 +887,31 @@ impl<'a, P: JsonRpcProvider + 'static> TransactionBuilder<'a, P> {
 		Ok(unsigned_tx)
 	}
 
+	/// Builds the unsigned transaction and packages it for an air-gapped signer.
+	///
+	/// The fees and the network magic are fetched through the client, and the verification
+	/// scripts of the account signers are included so the offline wallet can sign without them.
+	/// See [`SigningEnvelope`] for the rest of the workflow.
+	pub async fn export_for_signing(&mut self) -> Result<SigningEnvelope, TransactionError> {
+		let tx = TransactionData::from(self.get_unsigned_tx().await?);
+		let network = self
+			.client
+			.ok_or_else(|| {
+				TransactionError::TransactionConfiguration(
+					"A client is required to export a transaction".to_string(),
+				)
+			})?
+			.network()
+			.await?;
+		let verification_scripts: Vec<VerificationScript> = self
+			.signers
+			.iter()
+			.filter_map(|signer| signer.as_account_signer())
+			.filter_map(|signer| signer.account().verification_script().clone())
+			.collect();
+		SigningEnvelope::new(&tx, network, &verification_scripts)
+	}
+
 	fn signers_contain_multi_sig_with_committee_member(&self, committee: &HashSet<H160>) -> bool {
 		for signer in &self.signers {
 			if let Some(account_signer) = signer.as_account_signer() {
@@ -968,6 +1029,9 @@ impl<'a, P: JsonRpcProvider + 'static> TransactionBuilder<'a, P> {
 				TransactionAttribute::Conflicts { hash: _ } => {
 					self.add_conflicts_attribute(attr)?;
 				},
+				TransactionAttribute::NotaryAssisted { nkeys: _ } => {
+					self.add_notary_assisted_attribute(attr)?;
+				},
 				// TransactionAttribute::OracleResponse(oracle_response) => {
 				//     self.add_oracle_response_attribute(oracle_response);
 				// },
@@ -1008,6 +1072,20 @@ impl<'a, P: JsonRpcProvider + 'static> TransactionBuilder<'a, P> {
 		Ok(())
 	}
 
+	fn add_notary_assisted_attribute(
+		&mut self,
+		attr: TransactionAttribute,
+	) -> Result<(), TransactionError> {
+		if self.has_attribute_of_type(TransactionAttribute::NotaryAssisted { nkeys: 0 }) {
+			return Err(TransactionError::TransactionConfiguration(
+				"A transaction can only have one NotaryAssisted attribute.".to_string(),
+			));
+		}
+		// Add the attribute to the attributes vector
+		self.attributes.push(attr);
+		Ok(())
+	}
+
 	fn add_conflicts_attribute(
 		&mut self,
 		attr: TransactionAttribute,
@@ -1038,6 +1116,10 @@ impl<'a, P: JsonRpcProvider + 'static> TransactionBuilder<'a, P> {
 					TransactionAttribute::NotValidBefore { .. },
 					TransactionAttribute::NotValidBefore { .. },
 				) | (TransactionAttribute::HighPriority, TransactionAttribute::HighPriority)
+					| (
+						TransactionAttribute::NotaryAssisted { .. },
+						TransactionAttribute::NotaryAssisted { .. },
+					)
 			)
 		})
 	}
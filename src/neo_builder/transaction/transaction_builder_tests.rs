@@ -16,8 +16,9 @@ mod tests {
 
 	use crate::{
 		builder::{
-			init_logger, AccountSigner, BuilderError, ContractSigner, ScriptBuilder, Signer,
-			TransactionAttribute, TransactionBuilder, TransactionError, Witness,
+			init_logger, AccountSigner, BuilderError, ContractSigner, NetworkFeeCalculator,
+			ScriptBuilder, Signer, TransactionAttribute, TransactionBuilder, TransactionError,
+			Witness,
 		},
 		config::{NeoConstants, TestConstants},
 		crypto::{KeyPair, Secp256r1PrivateKey},
@@ -798,6 +799,32 @@ mod tests {
 		);
 	}
 
+	#[test]
+	fn test_attributes_encoding() {
+		use crate::codec::{Decoder, NeoSerializable};
+
+		let hash =
+			H256::from_str("0x8529cf7301d13cc13d85913b8367700080a6e96db045687b8db720e91e80321c")
+				.unwrap();
+		let conflicts = TransactionAttribute::Conflicts { hash };
+		let mut expected = vec![0x21];
+		expected.extend(hash.as_bytes().iter().rev());
+		assert_eq!(conflicts.to_array(), expected);
+
+		for attribute in [
+			TransactionAttribute::NotValidBefore { height: 200 },
+			conflicts,
+			TransactionAttribute::NotaryAssisted { nkeys: 3 },
+		] {
+			let bytes = attribute.to_array();
+			assert_eq!(bytes.len(), attribute.size());
+			assert_eq!(bytes, attribute.to_bytes());
+			assert_eq!(TransactionAttribute::decode(&mut Decoder::new(&bytes)).unwrap(), attribute);
+			assert_eq!(TransactionAttribute::from_bytes(&bytes).unwrap(), attribute);
+		}
+		assert_eq!(TransactionAttribute::NotaryAssisted { nkeys: 3 }.to_array(), vec![0x22, 3]);
+	}
+
 	#[tokio::test]
 	async fn test_attributes_conflicts_multiple() {
 		let mock_provider = Arc::new(Mutex::new(MockClient::new().await));
@@ -1777,6 +1804,36 @@ mod tests {
 		assert!(tx.witnesses.is_empty());
 	}
 
+	#[tokio::test]
+	async fn test_build_without_client() {
+		let account = Account::create().unwrap();
+		let mut tb: TransactionBuilder<'_, HttpProvider> = TransactionBuilder::new();
+		tb.set_script(Some(vec![1, 2, 3]))
+			.set_signers(vec![AccountSigner::called_by_entry(&account).unwrap().into()])
+			.unwrap();
+		tb.set_network_fee_calculator(Some(NetworkFeeCalculator::default()));
+
+		// The block and the system fee can't be fetched without a client
+		assert!(matches!(
+			tb.get_unsigned_tx().await,
+			Err(TransactionError::TransactionConfiguration(_))
+		));
+		tb.valid_until_block(1000).unwrap();
+		assert!(matches!(
+			tb.get_unsigned_tx().await,
+			Err(TransactionError::TransactionConfiguration(_))
+		));
+		tb.system_fee(1000).set_additional_system_fee(10);
+
+		let tx = tb.get_unsigned_tx().await.unwrap();
+		let script = account.get_verification_script().unwrap();
+		let net_fee = NetworkFeeCalculator::default().transaction_fee(&tx, &[script]).unwrap();
+		assert!(tx.network().is_none());
+		assert_eq!(*tx.valid_until_block(), 1000);
+		assert_eq!(*tx.sys_fee(), 1010);
+		assert_eq!(*tx.net_fee(), net_fee);
+	}
+
 	#[tokio::test]
 	async fn test_version() {
 		// init_logger();
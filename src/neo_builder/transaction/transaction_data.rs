@@ -0,0 +1,372 @@
+use std::hash::{Hash, Hasher};
+
+use primitive_types::H256;
+
+use crate::{
+	builder::{
+		AccountSigner, Signer, Transaction, TransactionAttribute, TransactionError, Witness,
+	},
+	codec::{Decoder, Encoder, NeoSerializable, VarSizeTrait},
+	crypto::HashableForVec,
+	neo_clients::{HttpProvider, JsonRpcProvider, RpcClient},
+	neo_crypto::utils::{FromBase64String, ToBase64String},
+	Bytes,
+};
+
+/// A Neo N3 transaction that is not bound to an RPC client.
+///
+/// Unlike [`Transaction`], this type can be decoded from raw bytes received from anywhere, e.g.
+/// the mempool or an air-gapped signer, and its hash and sign data are computed for an explicit
+/// network magic. It is bound to a client with [`bind`](Self::bind) only when it is sent.
+///
+/// # Examples
+///
+/// ```rust
+/// use neo3::neo_builder::{Transaction, TransactionData};
+/// use neo3::neo_clients::HttpProvider;
+///
+/// let mut tx = Transaction::<HttpProvider>::new();
+/// tx.set_script(vec![0x11]);
+///
+/// let data = TransactionData::from(&tx);
+/// let decoded = TransactionData::from_base64(&data.to_base64()).unwrap();
+/// assert_eq!(decoded, data);
+/// assert_eq!(decoded.tx_hash(), data.tx_hash());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct TransactionData {
+	pub version: u8,
+	pub nonce: u32,
+	pub valid_until_block: u32,
+	pub signers: Vec<Signer>,
+	pub sys_fee: i64,
+	pub net_fee: i64,
+	pub attributes: Vec<TransactionAttribute>,
+	pub script: Bytes,
+	pub witnesses: Vec<Witness>,
+}
+
+impl TransactionData {
+	pub fn from_bytes(bytes: &[u8]) -> Result<Self, TransactionError> {
+		let mut reader = Decoder::new(bytes);
+		let data = Self::decode(&mut reader)?;
+		if reader.available() > 0 {
+			return Err(TransactionError::InvalidTransaction);
+		}
+		Ok(data)
+	}
+
+	pub fn from_base64(base64: &str) -> Result<Self, TransactionError> {
+		let bytes = base64.from_base64_string().map_err(|e| {
+			TransactionError::TransactionConfiguration(format!("Invalid base64 transaction: {}", e))
+		})?;
+		Self::from_bytes(&bytes)
+	}
+
+	pub fn to_bytes(&self) -> Bytes {
+		self.to_array()
+	}
+
+	pub fn to_base64(&self) -> String {
+		self.to_array().to_base64_string()
+	}
+
+	/// The hash of the transaction, in the byte order it is displayed in.
+	pub fn tx_hash(&self) -> H256 {
+		let mut data = self.unsigned_bytes().hash256();
+		data.reverse();
+		H256::from_slice(&data)
+	}
+
+	/// The data signed by the witnesses of the transaction on the network `network`.
+	pub fn get_sign_data(&self, network: u32) -> Bytes {
+		let mut data = self.unsigned_bytes().hash256();
+		data.splice(0..0, network.to_le_bytes());
+		data
+	}
+
+	/// The serialized transaction without its witnesses.
+	pub fn unsigned_bytes(&self) -> Bytes {
+		let mut writer = Encoder::new();
+		self.serialize_without_witnesses(&mut writer);
+		writer.to_bytes()
+	}
+
+	/// Binds the transaction to `client` so it can be sent and tracked.
+	pub fn bind<P: JsonRpcProvider + 'static>(self, client: &RpcClient<P>) -> Transaction<'_, P> {
+		let mut tx = Transaction::from(self);
+		tx.network = Some(client);
+		tx
+	}
+
+	pub(crate) fn serialize_without_witnesses(&self, writer: &mut Encoder) {
+		writer.write_u8(self.version);
+		writer.write_u32(self.nonce);
+		writer.write_i64(self.sys_fee);
+		writer.write_i64(self.net_fee);
+		writer.write_u32(self.valid_until_block);
+		writer
+			.write_serializable_variable_list(&self.signers)
+			.expect("Failed to encode signers");
+		writer
+			.write_serializable_variable_list(&self.attributes)
+			.expect("Failed to encode attributes");
+		writer.write_var_bytes(&self.script).expect("Failed to encode script");
+	}
+}
+
+impl Eq for TransactionData {}
+
+impl PartialEq for TransactionData {
+	fn eq(&self, other: &Self) -> bool {
+		self.to_array() == other.to_array()
+	}
+}
+
+impl Hash for TransactionData {
+	fn hash<H: Hasher>(&self, state: &mut H) {
+		self.to_array().hash(state);
+	}
+}
+
+impl<'a, P: JsonRpcProvider + 'static> From<&Transaction<'a, P>> for TransactionData {
+	fn from(tx: &Transaction<'a, P>) -> Self {
+		Self {
+			version: tx.version,
+			nonce: tx.nonce,
+			valid_until_block: tx.valid_until_block,
+			signers: tx.signers.clone(),
+			sys_fee: tx.sys_fee,
+			net_fee: tx.net_fee,
+			attributes: tx.attributes.clone(),
+			script: tx.script.clone(),
+			witnesses: tx.witnesses.clone(),
+		}
+	}
+}
+
+impl<'a, P: JsonRpcProvider + 'static> From<Transaction<'a, P>> for TransactionData {
+	fn from(tx: Transaction<'a, P>) -> Self {
+		Self {
+			version: tx.version,
+			nonce: tx.nonce,
+			valid_until_block: tx.valid_until_block,
+			signers: tx.signers,
+			sys_fee: tx.sys_fee,
+			net_fee: tx.net_fee,
+			attributes: tx.attributes,
+			script: tx.script,
+			witnesses: tx.witnesses,
+		}
+	}
+}
+
+impl<'a, P: JsonRpcProvider + 'static> From<TransactionData> for Transaction<'a, P> {
+	fn from(data: TransactionData) -> Self {
+		let size = NeoSerializable::size(&data) as i32;
+		Transaction {
+			network: None,
+			version: data.version,
+			nonce: data.nonce,
+			valid_until_block: data.valid_until_block,
+			signers: data.signers,
+			size,
+			sys_fee: data.sys_fee,
+			net_fee: data.net_fee,
+			attributes: data.attributes,
+			script: data.script,
+			witnesses: data.witnesses,
+			block_count_when_sent: None,
+		}
+	}
+}
+
+impl NeoSerializable for TransactionData {
+	type Error = TransactionError;
+
+	fn size(&self) -> usize {
+		Transaction::<HttpProvider>::HEADER_SIZE
+			+ self.signers.var_size()
+			+ self.attributes.var_size()
+			+ self.script.var_size()
+			+ self.witnesses.var_size()
+	}
+
+	fn encode(&self, writer: &mut Encoder) {
+		self.serialize_without_witnesses(writer);
+		writer
+			.write_serializable_variable_list(&self.witnesses)
+			.expect("Failed to encode witnesses");
+	}
+
+	fn decode(reader: &mut Decoder) -> Result<Self, Self::Error>
+	where
+		Self: Sized,
+	{
+		let version = reader.try_read_u8()?;
+		if version != 0 {
+			return Err(TransactionError::TransactionConfiguration(format!(
+				"Unsupported transaction version {}",
+				version
+			)));
+		}
+		let nonce = reader.read_u32().map_err(|e| {
+			TransactionError::TransactionConfiguration(format!("Failed to read nonce: {}", e))
+		})?;
+		let sys_fee = reader.read_i64().map_err(|e| {
+			TransactionError::TransactionConfiguration(format!("Failed to read system fee: {}", e))
+		})?;
+		let net_fee = reader.read_i64().map_err(|e| {
+			TransactionError::TransactionConfiguration(format!("Failed to read network fee: {}", e))
+		})?;
+		let valid_until_block = reader.read_u32().map_err(|e| {
+			TransactionError::TransactionConfiguration(format!(
+				"Failed to read valid until block: {}",
+				e
+			))
+		})?;
+
+		// Read signers, which are encoded without their type
+		let signers: Vec<Signer> = reader
+			.read_serializable_list::<AccountSigner>()?
+			.into_iter()
+			.map(Signer::from)
+			.collect();
+
+		let attributes: Vec<TransactionAttribute> =
+			reader.read_serializable_list::<TransactionAttribute>()?;
+
+		let script = reader.read_var_bytes()?.to_vec();
+
+		let mut witnesses = vec![];
+		if reader.available() > 0 {
+			witnesses.append(&mut reader.read_serializable_list::<Witness>()?);
+		}
+
+		Ok(Self {
+			version,
+			nonce,
+			valid_until_block,
+			signers,
+			sys_fee,
+			net_fee,
+			attributes,
+			script,
+			witnesses,
+		})
+	}
+
+	fn to_array(&self) -> Vec<u8> {
+		let mut writer = Encoder::new();
+		self.encode(&mut writer);
+		writer.to_bytes()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		builder::{
+			OracleResponse, OracleResponseCode, SignerTrait, WitnessAction, WitnessCondition,
+			WitnessRule,
+		},
+		neo_protocol::{Account, AccountTrait},
+	};
+
+	fn transaction_data() -> TransactionData {
+		let account = Account::create().unwrap();
+		let mut signer = AccountSigner::called_by_entry(&account).unwrap();
+		signer
+			.set_rules(vec![WitnessRule::new(
+				WitnessAction::Allow,
+				WitnessCondition::CalledByEntry,
+			)])
+			.unwrap();
+
+		TransactionData {
+			version: 0,
+			nonce: 0x12345678,
+			valid_until_block: 1000,
+			signers: vec![signer.into()],
+			sys_fee: 1_0000_0000,
+			net_fee: 123_4567,
+			attributes: vec![
+				TransactionAttribute::HighPriority,
+				TransactionAttribute::OracleResponse(OracleResponse {
+					// Oracle request IDs are 64-bit
+					id: u32::MAX as u64 + 7,
+					response_code: OracleResponseCode::Success,
+					result: [1, 2, 3].to_base64_string(),
+				}),
+				TransactionAttribute::NotValidBefore { height: 900 },
+				TransactionAttribute::Conflicts { hash: H256::repeat_byte(0xab) },
+				TransactionAttribute::NotaryAssisted { nkeys: 1 },
+			],
+			script: vec![0x11, 0x40],
+			witnesses: vec![Witness::from_scripts(vec![0x0c, 0x00], vec![0x41])],
+		}
+	}
+
+	#[test]
+	fn test_round_trip() {
+		let data = transaction_data();
+		let bytes = data.to_bytes();
+		assert_eq!(bytes.len(), NeoSerializable::size(&data));
+		assert_eq!(TransactionData::from_bytes(&bytes).unwrap(), data);
+		assert_eq!(TransactionData::from_base64(&data.to_base64()).unwrap(), data);
+
+		assert!(TransactionData::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+		let mut trailing = bytes.clone();
+		trailing.push(0);
+		assert!(TransactionData::from_bytes(&trailing).is_err());
+	}
+
+	#[test]
+	fn test_malformed_input_is_an_error() {
+		assert!(TransactionData::from_bytes(&[]).is_err());
+		assert!(TransactionData::from_base64("").is_err());
+
+		// Every truncation fails, except the one leaving out all the witnesses
+		let data = transaction_data();
+		let bytes = data.to_bytes();
+		let unsigned = data.unsigned_bytes().len();
+		for len in 0..bytes.len() {
+			let result = TransactionData::from_bytes(&bytes[..len]);
+			assert_eq!(result.is_ok(), len == unsigned, "{} bytes", len);
+		}
+
+		let mut version = bytes.clone();
+		version[0] = 1;
+		assert!(TransactionData::from_bytes(&version).is_err());
+
+		// Conditions nested deeper than the limit are rejected without recursing into them
+		let mut nested = vec![0x01; 100_000];
+		nested.extend([0x00, 0x01]);
+		assert!(WitnessCondition::decode(&mut Decoder::new(&nested)).is_err());
+		let not_true = [0x01, 0x01, 0x00, 0x01];
+		assert_eq!(
+			WitnessCondition::decode(&mut Decoder::new(&not_true)).unwrap(),
+			WitnessCondition::Not(Box::new(WitnessCondition::Not(Box::new(
+				WitnessCondition::Boolean(true)
+			))))
+		);
+	}
+
+	#[test]
+	fn test_matches_bound_transaction() {
+		let data = transaction_data();
+		let tx: Transaction<HttpProvider> = data.clone().into();
+
+		assert_eq!(tx.to_array(), data.to_bytes());
+		assert_eq!(tx.get_tx_id().unwrap(), data.tx_hash());
+		assert_eq!(tx.get_sign_data(860833102), data.get_sign_data(860833102));
+		assert_eq!(*tx.size() as usize, data.to_bytes().len());
+		assert_eq!(TransactionData::from(&tx), data);
+
+		let client = RpcClient::new(HttpProvider::new("http://localhost:10332").unwrap());
+		let bound = data.clone().bind(&client);
+		assert!(bound.network().is_some());
+		assert_eq!(TransactionData::from(bound), data);
+	}
+}
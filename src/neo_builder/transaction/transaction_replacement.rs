@@ -0,0 +1,283 @@
+use primitive_types::H256;
+
+use crate::{
+	builder::{TransactionAttribute, TransactionBuilder, TransactionData, TransactionError},
+	neo_clients::{APITrait, JsonRpcProvider, RpcClient},
+	neo_types::ScriptHashExtension,
+	OpCode,
+};
+
+/// What the replacement of a pending transaction executes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ReplacementKind {
+	/// Replaces the transaction with one that does nothing, cancelling it.
+	Cancel,
+	/// Replaces the transaction with one running the same script, so it is included sooner.
+	SpeedUp,
+}
+
+/// The transaction that was included in a block after a replacement was sent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ReplacementOutcome {
+	Original { height: u32 },
+	Replacement { height: u32 },
+}
+
+impl<'a, P: JsonRpcProvider + 'static> TransactionBuilder<'a, P> {
+	/// Configures the builder to replace the pending transaction `hash` with a transaction that
+	/// conflicts with it and pays a higher network fee.
+	///
+	/// The script is set according to `kind`, a `Conflicts` attribute is added, and the network
+	/// fee of the original transaction plus `fee_increase` is added on top of the fee the
+	/// replacement needs itself, as the mempool only evicts transactions paying less.
+	///
+	/// The signers must be set beforehand, and the sender must be a signer of the original
+	/// transaction. Fails if the original transaction isn't in the mempool anymore.
+	pub async fn replace_transaction(
+		&mut self,
+		hash: H256,
+		kind: ReplacementKind,
+		fee_increase: u64,
+	) -> Result<&mut Self, TransactionError> {
+		let client = self.client.ok_or_else(|| {
+			TransactionError::TransactionConfiguration(
+				"A client is required to replace a transaction".to_string(),
+			)
+		})?;
+		if !client.get_raw_mem_pool().await?.contains(&hash) {
+			return Err(TransactionError::IllegalState(format!(
+				"The transaction {:#x} is not pending in the mempool",
+				hash
+			)));
+		}
+		let original = TransactionData::from_base64(&client.get_raw_transaction(hash).await?)?;
+
+		let sender = self
+			.signers
+			.first()
+			.map(|signer| *signer.get_signer_hash())
+			.ok_or(TransactionError::NoSigners)?;
+		if !original.signers.iter().any(|signer| *signer.get_signer_hash() == sender) {
+			return Err(TransactionError::SignerConfiguration(format!(
+				"The sender {} is not a signer of the transaction {:#x}",
+				sender.to_address(),
+				hash
+			)));
+		}
+
+		let script = match kind {
+			ReplacementKind::Cancel => vec![OpCode::Ret as u8],
+			ReplacementKind::SpeedUp => original.script,
+		};
+		let original_fee =
+			u64::try_from(original.net_fee).map_err(|_| TransactionError::InvalidTransaction)?;
+		let additional_network_fee = self
+			.additional_network_fee()
+			.checked_add(original_fee)
+			.and_then(|fee| fee.checked_add(fee_increase))
+			.ok_or_else(|| {
+				TransactionError::TransactionConfiguration(
+					"The additional network fee overflows".to_string(),
+				)
+			})?;
+
+		self.add_attributes(vec![TransactionAttribute::Conflicts { hash }])?;
+		self.set_script(Some(script));
+		self.set_additional_network_fee(additional_network_fee);
+		Ok(self)
+	}
+}
+
+impl ReplacementOutcome {
+	/// Waits until the transaction `original` or its replacement `replacement` is included in a
+	/// block, for at most `max_blocks` blocks.
+	///
+	/// Fails early if neither transaction is pending nor included anymore, e.g. because both
+	/// expired.
+	pub async fn track<P: JsonRpcProvider + 'static>(
+		client: &RpcClient<P>,
+		original: H256,
+		replacement: H256,
+		max_blocks: u32,
+	) -> Result<Self, TransactionError> {
+		let max_block = client.get_block_count().await?.saturating_add(max_blocks);
+		loop {
+			if let Some(outcome) = Self::find(client, original, replacement).await {
+				return Ok(outcome);
+			}
+
+			let mem_pool = client.get_raw_mem_pool().await?;
+			if !mem_pool.contains(&original) && !mem_pool.contains(&replacement) {
+				// Either transaction may have been included since it was looked up
+				return Self::find(client, original, replacement).await.ok_or_else(|| {
+					TransactionError::IllegalState(format!(
+						"Neither the transaction {:#x} nor its replacement {:#x} is pending",
+						original, replacement
+					))
+				});
+			}
+
+			if client.get_block_count().await? > max_block {
+				return Err(TransactionError::IllegalState(format!(
+					"Neither the transaction {:#x} nor its replacement {:#x} was included after \
+					 {} blocks",
+					original, replacement, max_blocks
+				)));
+			}
+
+			// Wait a bit before checking again
+			tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+		}
+	}
+
+	async fn find<P: JsonRpcProvider + 'static>(
+		client: &RpcClient<P>,
+		original: H256,
+		replacement: H256,
+	) -> Option<Self> {
+		if let Ok(height) = client.get_transaction_height(replacement).await {
+			return Some(Self::Replacement { height });
+		}
+		if let Ok(height) = client.get_transaction_height(original).await {
+			return Some(Self::Original { height });
+		}
+		None
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json::json;
+	use wiremock::{
+		matchers::{body_partial_json, method},
+		Mock, ResponseTemplate,
+	};
+
+	use super::*;
+	use crate::{
+		builder::AccountSigner,
+		neo_clients::{HttpProvider, MockClient},
+		neo_protocol::{Account, AccountTrait},
+	};
+
+	fn original(sender: &Account) -> TransactionData {
+		TransactionData {
+			nonce: 7,
+			valid_until_block: 100,
+			signers: vec![AccountSigner::called_by_entry(sender).unwrap().into()],
+			sys_fee: 1_0000_0000,
+			net_fee: 123_4567,
+			script: vec![0x11, 0x12],
+			..Default::default()
+		}
+	}
+
+	async fn mock_mem_pool(original: &TransactionData, pending: bool) -> MockClient {
+		let mut mock_client = MockClient::new().await;
+		let mem_pool = if pending { vec![format!("{:#x}", original.tx_hash())] } else { vec![] };
+		mock_client.mock_response_ignore_param("getrawmempool", json!(mem_pool)).await;
+		mock_client
+			.mock_response_ignore_param("getrawtransaction", json!(original.to_base64()))
+			.await;
+		mock_client.mount_mocks().await;
+		mock_client
+	}
+
+	#[tokio::test]
+	async fn test_replace_transaction() {
+		let sender = Account::create().unwrap();
+		let original = original(&sender);
+		let mock_client = mock_mem_pool(&original, true).await;
+		let client = mock_client.into_client();
+
+		for (kind, script) in [
+			(ReplacementKind::Cancel, vec![OpCode::Ret as u8]),
+			(ReplacementKind::SpeedUp, original.script.clone()),
+		] {
+			let mut builder = TransactionBuilder::with_client(&client);
+			builder
+				.set_signers(vec![AccountSigner::called_by_entry(&sender).unwrap().into()])
+				.unwrap();
+			builder.replace_transaction(original.tx_hash(), kind, 100).await.unwrap();
+
+			assert_eq!(builder.script(), &Some(script));
+			assert_eq!(
+				builder.attributes(),
+				&vec![TransactionAttribute::Conflicts { hash: original.tx_hash() }]
+			);
+			assert_eq!(*builder.additional_network_fee(), 123_4567 + 100);
+		}
+	}
+
+	#[tokio::test]
+	async fn test_replace_transaction_requirements() {
+		let sender = Account::create().unwrap();
+		let original = original(&sender);
+
+		// The original transaction must be pending
+		let mock_client = mock_mem_pool(&original, false).await;
+		let client = mock_client.into_client();
+		let mut builder = TransactionBuilder::with_client(&client);
+		builder
+			.set_signers(vec![AccountSigner::called_by_entry(&sender).unwrap().into()])
+			.unwrap();
+		assert!(builder
+			.replace_transaction(original.tx_hash(), ReplacementKind::Cancel, 0)
+			.await
+			.is_err());
+
+		// The sender must be a signer of the original transaction
+		let mock_client = mock_mem_pool(&original, true).await;
+		let client = mock_client.into_client();
+		let other = Account::create().unwrap();
+		let mut builder = TransactionBuilder::with_client(&client);
+		builder
+			.set_signers(vec![AccountSigner::called_by_entry(&other).unwrap().into()])
+			.unwrap();
+		assert!(builder
+			.replace_transaction(original.tx_hash(), ReplacementKind::Cancel, 0)
+			.await
+			.is_err());
+
+		let mut builder = TransactionBuilder::<HttpProvider>::new();
+		assert!(builder
+			.replace_transaction(original.tx_hash(), ReplacementKind::Cancel, 0)
+			.await
+			.is_err());
+	}
+
+	#[tokio::test]
+	async fn test_track_replacement() {
+		let sender = Account::create().unwrap();
+		let original = original(&sender);
+		let replacement = H256::repeat_byte(0x01);
+
+		let mut mock_client = MockClient::new().await;
+		mock_client.mock_response_ignore_param("getblockcount", json!(10)).await;
+		mock_client.mock_response_ignore_param("getrawmempool", json!([])).await;
+		mock_client.mount_mocks().await;
+		let client = mock_client.into_client();
+
+		// Neither transaction is pending or included
+		assert!(ReplacementOutcome::track(&client, original.tx_hash(), replacement, 5)
+			.await
+			.is_err());
+
+		Mock::given(method("POST"))
+			.and(body_partial_json(json!({
+				"method": "gettransactionheight",
+				"params": [hex::encode(replacement)],
+			})))
+			.respond_with(ResponseTemplate::new(200).set_body_json(json!({
+				"jsonrpc": "2.0",
+				"id": 1,
+				"result": 11
+			})))
+			.mount(mock_client.server())
+			.await;
+		let outcome = ReplacementOutcome::track(&client, original.tx_hash(), replacement, 5)
+			.await
+			.unwrap();
+		assert_eq!(outcome, ReplacementOutcome::Replacement { height: 11 });
+	}
+}
@@ -0,0 +1,496 @@
+use std::{collections::HashSet, time::Duration};
+
+use primitive_types::{H160, H256};
+use tracing::warn;
+
+use crate::{
+	builder::{
+		Signer, TransactionAttribute, TransactionBuilder, TransactionData, TransactionError,
+	},
+	neo_clients::{APITrait, JsonRpcError, JsonRpcProvider, ProviderError, RpcClient},
+	neo_codec::NeoSerializable,
+	neo_types::ScriptHashExtension,
+	Bytes,
+};
+
+/// Configuration of a [`TransactionSender`].
+#[derive(Debug, Clone)]
+pub struct TransactionSenderConfig {
+	/// The number of blocks a transaction is valid for, starting at the current height. Must be
+	/// at least 1.
+	pub valid_until_block_window: u32,
+	/// The maximum number of transactions per sender that aren't included in a block yet.
+	pub max_in_flight_per_sender: usize,
+	/// How many times an expired transaction is re-signed with a fresh window before it's given
+	/// up.
+	pub max_resigns: u32,
+	/// The interval between polls in [`TransactionSender::wait_all`].
+	pub poll_interval: Duration,
+}
+
+impl Default for TransactionSenderConfig {
+	fn default() -> Self {
+		Self {
+			valid_until_block_window: 100,
+			max_in_flight_per_sender: 64,
+			max_resigns: 3,
+			poll_interval: Duration::from_secs(1),
+		}
+	}
+}
+
+/// What a [`TransactionSender`] sends: a script, its signers and attributes. The first signer
+/// is the sender paying the fees, and every account signer must hold its private key.
+#[derive(Debug, Clone)]
+pub struct TransactionRequest {
+	pub script: Bytes,
+	pub signers: Vec<Signer>,
+	pub attributes: Vec<TransactionAttribute>,
+}
+
+impl TransactionRequest {
+	pub fn new(script: Bytes, signers: Vec<Signer>) -> Self {
+		Self { script, signers, attributes: vec![] }
+	}
+
+	/// The account paying the fees of the transaction.
+	pub fn sender(&self) -> Option<H160> {
+		self.signers.first().map(|signer| *signer.get_signer_hash())
+	}
+}
+
+/// A transaction sent by a [`TransactionSender`] that isn't included in a block yet.
+#[derive(Debug, Clone)]
+pub struct PendingTransaction {
+	pub request: TransactionRequest,
+	pub transaction: TransactionData,
+	/// How many times the transaction was re-signed after expiring.
+	pub resigns: u32,
+}
+
+impl PendingTransaction {
+	pub fn hash(&self) -> H256 {
+		self.transaction.tx_hash()
+	}
+
+	/// The system and network fees of the transaction, in datoshi.
+	pub fn fees(&self) -> i64 {
+		self.transaction.sys_fee + self.transaction.net_fee
+	}
+}
+
+/// What happened to a transaction during a [`TransactionSender::poll`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SenderEvent {
+	/// The transaction was included in the block at `height`.
+	Confirmed { hash: H256, height: u32 },
+	/// The transaction dropped out of the mempool and was sent again.
+	Rebroadcast { hash: H256 },
+	/// The transaction `previous` expired and was re-signed as `hash` with a fresh window.
+	Resigned { previous: H256, hash: H256 },
+	/// The transaction expired after being re-signed the maximum number of times.
+	Expired { hash: H256 },
+}
+
+/// Sends transactions and follows them until they are included in a block.
+///
+/// Each transaction gets a `ValidUntilBlock` window of
+/// [`valid_until_block_window`](TransactionSenderConfig::valid_until_block_window) blocks and a
+/// random nonce. [`poll`](Self::poll) rebroadcasts transactions that dropped out of the mempool
+/// and re-signs expired ones with a fresh window. The transactions in flight per sender are
+/// bounded both by number and by the GAS balance of the sender, which must cover all their fees.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use neo3::neo_builder::{
+///     AccountSigner, TransactionRequest, TransactionSender, TransactionSenderConfig,
+/// };
+/// use neo3::neo_clients::{HttpProvider, RpcClient};
+/// use neo3::neo_protocol::{Account, AccountTrait};
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let client = RpcClient::new(HttpProvider::new("https://testnet1.neo.org:443")?);
+///     let account = Account::from_wif("L1WMhxazScMhUrdv34JqQb1HFSQmWeN2Kpc1R9JGKwL7CDNP21uR")?;
+///     let mut sender = TransactionSender::new(&client, TransactionSenderConfig::default());
+///
+///     for _ in 0..10 {
+///         let signers = vec![AccountSigner::called_by_entry(&account)?.into()];
+///         sender.submit(TransactionRequest::new(vec![0x11, 0x40], signers)).await?;
+///     }
+///     for event in sender.wait_all().await? {
+///         println!("{:?}", event);
+///     }
+///     Ok(())
+/// }
+/// ```
+pub struct TransactionSender<'a, P: JsonRpcProvider + 'static> {
+	client: &'a RpcClient<P>,
+	config: TransactionSenderConfig,
+	pending: Vec<PendingTransaction>,
+}
+
+impl<'a, P: JsonRpcProvider + 'static> TransactionSender<'a, P> {
+	pub fn new(client: &'a RpcClient<P>, config: TransactionSenderConfig) -> Self {
+		Self { client, config, pending: vec![] }
+	}
+
+	pub fn config(&self) -> &TransactionSenderConfig {
+		&self.config
+	}
+
+	/// The transactions that aren't included in a block yet.
+	pub fn pending(&self) -> &[PendingTransaction] {
+		&self.pending
+	}
+
+	/// The number of transactions of `sender` that aren't included in a block yet.
+	pub fn in_flight(&self, sender: &H160) -> usize {
+		self.pending_of(sender).count()
+	}
+
+	/// Signs and sends the transaction of `request`, and returns its hash.
+	///
+	/// Fails if the sender already has the maximum number of transactions in flight, or if its
+	/// GAS balance doesn't cover the fees of this transaction and of those in flight.
+	pub async fn submit(&mut self, request: TransactionRequest) -> Result<H256, TransactionError> {
+		let sender = request.sender().ok_or(TransactionError::NoSigners)?;
+		if self.in_flight(&sender) >= self.config.max_in_flight_per_sender {
+			return Err(TransactionError::IllegalState(format!(
+				"The sender {} already has {} transactions in flight",
+				sender.to_address(),
+				self.config.max_in_flight_per_sender
+			)));
+		}
+
+		let (transaction, balance) = self.sign(&request).await?;
+		let in_flight_fees: i64 = self.pending_of(&sender).map(PendingTransaction::fees).sum();
+		let fees = in_flight_fees + transaction.sys_fee + transaction.net_fee;
+		if fees < 0 || fees as u64 > balance {
+			return Err(TransactionError::InsufficientFunds);
+		}
+
+		self.broadcast(&transaction).await?;
+		let hash = transaction.tx_hash();
+		self.pending.push(PendingTransaction { request, transaction, resigns: 0 });
+		Ok(hash)
+	}
+
+	/// Checks the pending transactions once, and rebroadcasts or re-signs those that need it.
+	pub async fn poll(&mut self) -> Result<Vec<SenderEvent>, TransactionError> {
+		let block_count = self.client.get_block_count().await?;
+		let mem_pool: HashSet<H256> = self.client.get_raw_mem_pool().await?.into_iter().collect();
+
+		let mut events = vec![];
+		let mut still_pending = vec![];
+		for mut pending in std::mem::take(&mut self.pending) {
+			let hash = pending.hash();
+			if mem_pool.contains(&hash) {
+				still_pending.push(pending);
+				continue;
+			}
+			match self.client.get_transaction_height(hash).await {
+				Ok(height) => {
+					events.push(SenderEvent::Confirmed { hash, height });
+					continue;
+				},
+				Err(ProviderError::JsonRpcError(err)) if is_unknown_transaction(&err) => {},
+				Err(err) => {
+					// The transaction may still be included, so it's checked again on the next poll
+					warn!("Failed to look up transaction {:#x}: {}", hash, err);
+					still_pending.push(pending);
+					continue;
+				},
+			}
+
+			// The transaction can't be included anymore once the next block is past its window
+			if block_count > pending.transaction.valid_until_block {
+				if pending.resigns >= self.config.max_resigns {
+					events.push(SenderEvent::Expired { hash });
+					continue;
+				}
+				let (transaction, _) = match self.sign(&pending.request).await {
+					Ok(signed) => signed,
+					Err(err) => {
+						// Keep it, so it's retried on the next poll
+						warn!("Failed to re-sign expired transaction {:#x}: {}", hash, err);
+						still_pending.push(pending);
+						continue;
+					},
+				};
+				pending.transaction = transaction;
+				pending.resigns += 1;
+				if let Err(err) = self.broadcast(&pending.transaction).await {
+					warn!("Failed to send re-signed transaction {:#x}: {}", pending.hash(), err);
+				}
+				events.push(SenderEvent::Resigned { previous: hash, hash: pending.hash() });
+			} else {
+				match self.broadcast(&pending.transaction).await {
+					Ok(()) => events.push(SenderEvent::Rebroadcast { hash }),
+					Err(err) => warn!("Failed to rebroadcast transaction {:#x}: {}", hash, err),
+				}
+			}
+			still_pending.push(pending);
+		}
+		self.pending = still_pending;
+		Ok(events)
+	}
+
+	/// Polls until every pending transaction is included in a block or expired, and returns all
+	/// the events that happened meanwhile.
+	pub async fn wait_all(&mut self) -> Result<Vec<SenderEvent>, TransactionError> {
+		let mut events = vec![];
+		while !self.pending.is_empty() {
+			tokio::time::sleep(self.config.poll_interval).await;
+			events.append(&mut self.poll().await?);
+		}
+		Ok(events)
+	}
+
+	fn pending_of<'b>(&'b self, sender: &'b H160) -> impl Iterator<Item = &'b PendingTransaction> {
+		self.pending
+			.iter()
+			.filter(move |pending| pending.request.sender().as_ref() == Some(sender))
+	}
+
+	/// Signs the transaction of `request` with a fresh window and nonce, and returns it with the
+	/// GAS balance of the sender.
+	async fn sign(
+		&self,
+		request: &TransactionRequest,
+	) -> Result<(TransactionData, u64), TransactionError> {
+		let block_count = self.client.get_block_count().await?;
+		let window = self.config.valid_until_block_window;
+		let valid_until_block = window
+			.checked_sub(1)
+			.and_then(|window| block_count.checked_add(window))
+			.ok_or_else(|| {
+				TransactionError::TransactionConfiguration(format!(
+					"Invalid valid until block window {} at height {}",
+					window, block_count
+				))
+			})?;
+		let mut builder = TransactionBuilder::with_client(self.client);
+		builder
+			.set_script(Some(request.script.clone()))
+			.set_signers(request.signers.clone())?
+			.add_attributes(request.attributes.clone())?
+			.nonce(rand::random())?
+			.valid_until_block(valid_until_block)?;
+		let transaction = TransactionData::from(builder.sign().await?);
+		let balance = builder.get_sender_balance().await?;
+		Ok((transaction, balance))
+	}
+
+	async fn broadcast(&self, transaction: &TransactionData) -> Result<(), TransactionError> {
+		self.client.send_raw_transaction(hex::encode(transaction.to_array())).await?;
+		Ok(())
+	}
+}
+
+/// Whether `err` is the error of a node that doesn't know a transaction, the code of Neo 3.7
+/// and later or the older code with its message.
+fn is_unknown_transaction(err: &JsonRpcError) -> bool {
+	err.code == -103 || (err.code == -100 && err.message.starts_with("Unknown transaction"))
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json::json;
+	use wiremock::{
+		matchers::{body_partial_json, method},
+		Mock, ResponseTemplate,
+	};
+
+	use super::*;
+	use crate::{
+		builder::AccountSigner,
+		neo_clients::{HttpProvider, MockClient},
+		neo_protocol::{Account, AccountTrait},
+	};
+
+	async fn mock_client(balance: i64) -> MockClient {
+		let mut mock_client = MockClient::new().await;
+		mock_client
+			.mock_response_with_file_ignore_param("getblockcount", "getblockcount_1000.json")
+			.await;
+		mock_client
+			.mock_response_with_file_ignore_param(
+				"invokescript",
+				"invokescript_necessary_mock.json",
+			)
+			.await;
+		mock_client
+			.mock_response_with_file_ignore_param("calculatenetworkfee", "calculatenetworkfee.json")
+			.await;
+		mock_client
+			.mock_response_with_file_ignore_param("sendrawtransaction", "sendrawtransaction.json")
+			.await;
+		mock_client
+			.mock_response_ignore_param(
+				"invokefunction",
+				json!({
+					"script": "",
+					"state": "HALT",
+					"gasconsumed": "0",
+					"exception": null,
+					"stack": [{ "type": "Integer", "value": balance.to_string() }]
+				}),
+			)
+			.await;
+		mock_client.mock_response_ignore_param("getrawmempool", json!([])).await;
+		mock_client.mount_mocks().await;
+		Mock::given(method("POST"))
+			.and(body_partial_json(json!({ "method": "gettransactionheight" })))
+			.respond_with(ResponseTemplate::new(200).set_body_json(json!({
+				"jsonrpc": "2.0",
+				"id": 1,
+				"error": { "code": -103, "message": "Unknown transaction" }
+			})))
+			.with_priority(2)
+			.mount(mock_client.server())
+			.await;
+		mock_client
+	}
+
+	async fn override_response(
+		mock_client: &MockClient,
+		method_name: &str,
+		result: serde_json::Value,
+	) {
+		let response = ResponseTemplate::new(200).set_body_json(json!({
+			"jsonrpc": "2.0",
+			"id": 1,
+			"result": result
+		}));
+		override_with(mock_client, method_name, response).await;
+	}
+
+	async fn override_with(
+		mock_client: &MockClient,
+		method_name: &str,
+		response: ResponseTemplate,
+	) {
+		Mock::given(method("POST"))
+			.and(body_partial_json(json!({ "method": method_name })))
+			.respond_with(response)
+			.with_priority(1)
+			.mount(mock_client.server())
+			.await;
+	}
+
+	async fn sent_transactions(mock_client: &MockClient) -> usize {
+		let requests = mock_client.server().received_requests().await.unwrap();
+		requests
+			.iter()
+			.filter(|request| {
+				let body: serde_json::Value = request.body_json().unwrap();
+				body["method"] == "sendrawtransaction"
+			})
+			.count()
+	}
+
+	fn request(account: &Account) -> TransactionRequest {
+		TransactionRequest::new(
+			vec![0x11, 0x40],
+			vec![AccountSigner::called_by_entry(account).unwrap().into()],
+		)
+	}
+
+	#[tokio::test]
+	async fn test_submit_bounds_in_flight_transactions() {
+		let account = Account::create().unwrap();
+		// Each transaction costs 30 + 1230610 datoshi of fees
+		let mock_client = mock_client(3_000_000).await;
+		let client = mock_client.into_client();
+		let mut sender = TransactionSender::new(&client, TransactionSenderConfig::default());
+
+		let first = sender.submit(request(&account)).await.unwrap();
+		let second = sender.submit(request(&account)).await.unwrap();
+		assert_ne!(first, second);
+		assert_eq!(sender.in_flight(&account.get_script_hash()), 2);
+		assert_eq!(sender.pending()[0].transaction.valid_until_block, 1000 + 100 - 1);
+		assert!(matches!(
+			sender.submit(request(&account)).await,
+			Err(TransactionError::InsufficientFunds)
+		));
+
+		let config = TransactionSenderConfig { max_in_flight_per_sender: 1, ..Default::default() };
+		let mut sender = TransactionSender::new(&client, config);
+		sender.submit(request(&account)).await.unwrap();
+		assert!(matches!(
+			sender.submit(request(&account)).await,
+			Err(TransactionError::IllegalState(_))
+		));
+
+		for valid_until_block_window in [0, u32::MAX] {
+			let config = TransactionSenderConfig { valid_until_block_window, ..Default::default() };
+			let mut sender = TransactionSender::new(&client, config);
+			assert!(matches!(
+				sender.submit(request(&account)).await,
+				Err(TransactionError::TransactionConfiguration(_))
+			));
+		}
+	}
+
+	#[tokio::test]
+	async fn test_poll() {
+		let account = Account::create().unwrap();
+		let mock_client = mock_client(1_0000_0000).await;
+		let client = mock_client.into_client();
+		let mut sender = TransactionSender::new(&client, TransactionSenderConfig::default());
+		let hash = sender.submit(request(&account)).await.unwrap();
+
+		// Dropped out of the mempool, but still valid
+		assert_eq!(sender.poll().await.unwrap(), vec![SenderEvent::Rebroadcast { hash }]);
+
+		// Expired
+		override_response(&mock_client, "getblockcount", json!(2000)).await;
+		let events = sender.poll().await.unwrap();
+		let resigned = sender.pending()[0].hash();
+		assert_ne!(resigned, hash);
+		assert_eq!(events, vec![SenderEvent::Resigned { previous: hash, hash: resigned }]);
+		assert_eq!(sender.pending()[0].resigns, 1);
+		assert_eq!(sender.pending()[0].transaction.valid_until_block, 2000 + 100 - 1);
+
+		// Included
+		override_response(&mock_client, "gettransactionheight", json!(2001)).await;
+		assert_eq!(
+			sender.poll().await.unwrap(),
+			vec![SenderEvent::Confirmed { hash: resigned, height: 2001 }]
+		);
+		assert!(sender.pending().is_empty());
+	}
+
+	#[tokio::test]
+	async fn test_poll_keeps_transactions_when_the_lookup_fails() {
+		let account = Account::create().unwrap();
+		let mock_client = mock_client(1_0000_0000).await;
+		let client = mock_client.into_client();
+		let mut sender = TransactionSender::new(&client, TransactionSenderConfig::default());
+		let hash = sender.submit(request(&account)).await.unwrap();
+
+		// Past the window, but the node can't tell whether the transaction was included
+		override_response(&mock_client, "getblockcount", json!(2000)).await;
+		override_with(&mock_client, "gettransactionheight", ResponseTemplate::new(503)).await;
+		assert_eq!(sender.poll().await.unwrap(), vec![]);
+		assert_eq!(sender.pending()[0].hash(), hash);
+		assert_eq!(sender.pending()[0].resigns, 0);
+		assert_eq!(sent_transactions(&mock_client).await, 1);
+	}
+
+	#[tokio::test]
+	async fn test_poll_gives_up_expired_transactions() {
+		let account = Account::create().unwrap();
+		let mock_client = mock_client(1_0000_0000).await;
+		let client = mock_client.into_client();
+		let config = TransactionSenderConfig { max_resigns: 0, ..Default::default() };
+		let mut sender = TransactionSender::<HttpProvider>::new(&client, config);
+		let hash = sender.submit(request(&account)).await.unwrap();
+
+		override_response(&mock_client, "getblockcount", json!(2000)).await;
+		assert_eq!(sender.poll().await.unwrap(), vec![SenderEvent::Expired { hash }]);
+		assert!(sender.pending().is_empty());
+	}
+}
@@ -94,14 +94,15 @@ impl VerificationScript {
 			Ok(n) => n,
 			Err(_) => return false,
 		};
-		if !(1..=NeoConstants::MAX_PUBLIC_KEYS_PER_MULTI_SIG)
-			.contains(&(threshold.to_u32().unwrap()))
+		if !threshold
+			.to_u32()
+			.is_some_and(|n| (1..=NeoConstants::MAX_PUBLIC_KEYS_PER_MULTI_SIG).contains(&n))
 		{
 			return false;
 		}
 
 		let mut m: BigInt = BigInt::zero();
-		while reader.by_ref().read_u8() == OpCode::PushData1.opcode() {
+		while reader.available() > 1 && reader.by_ref().read_u8() == OpCode::PushData1.opcode() {
 			let len = reader.by_ref().read_u8();
 			if len != 33 {
 				return false;
@@ -119,14 +120,21 @@ impl VerificationScript {
 
 		reader.reset();
 
-		if reader.read_push_int().unwrap() != m || reader.read_u8() != OpCode::Syscall.opcode() {
+		if reader.available() == 0
+			|| reader.read_push_int().ok() != Some(m)
+			|| reader.available() == 0
+			|| reader.read_u8() != OpCode::Syscall.opcode()
+		{
 			return false;
 		}
 
-		let service_bytes = &reader.read_bytes(4).unwrap().to_hex_string();
+		let Ok(service_bytes) = reader.read_bytes(4) else {
+			return false;
+		};
+		let service_bytes = &service_bytes.to_hex_string();
 		let hash = &InteropService::SystemCryptoCheckMultiSig.hash(); //.from_hex().unwrap();
 																//assert_eq!(service_bytes, hash);
-		if service_bytes != hash {
+		if service_bytes != hash || reader.available() > 0 {
 			return false;
 		}
 
@@ -182,7 +190,7 @@ impl VerificationScript {
 
 		if self.is_multi_sig() {
 			let mut reader = Decoder::new(&self.script);
-			reader.by_ref().read_var_int().unwrap(); // skip threshold
+			reader.by_ref().read_push_int()?; // skip threshold
 
 			let mut keys = vec![];
 			while reader.by_ref().read_u8() == OpCode::PushData1 as u8 {
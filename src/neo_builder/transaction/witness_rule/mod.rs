@@ -4,6 +4,7 @@
 //! - `WitnessAction`: Represents the action to be taken (Allow or Deny).
 //! - `WitnessCondition`: Represents various conditions for witness rules.
 //! - `WitnessRule`: Combines an action and a condition to form a complete rule.
+//! - `WitnessContext`: Evaluates locally whether a signer's witness scope covers a call.
 //!
 //! This module provides structures and implementations for creating, serializing,
 //! and deserializing witness rules used in NEO smart contracts.
@@ -51,8 +52,10 @@
 
 pub use witness_action::*;
 pub use witness_condition::*;
+pub use witness_context::*;
 pub use witness_rule::*;
 
 mod witness_action;
 mod witness_condition;
+mod witness_context;
 mod witness_rule;
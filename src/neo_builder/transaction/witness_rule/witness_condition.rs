@@ -364,24 +364,41 @@ impl NeoSerializable for WitnessCondition {
 	}
 
 	fn decode(reader: &mut Decoder) -> Result<Self, Self::Error> {
-		let byte = reader.read_u8();
+		Self::decode_nested(reader, Self::MAX_NESTING_DEPTH)
+	}
+
+	fn to_array(&self) -> Vec<u8> {
+		let mut writer = Encoder::new();
+		self.encode(&mut writer);
+		writer.to_bytes()
+	}
+}
+
+impl WitnessCondition {
+	/// Decodes a condition whose `Not`, `And` and `Or` expressions can nest `max_depth` times.
+	fn decode_nested(reader: &mut Decoder, max_depth: usize) -> Result<Self, TransactionError> {
+		let byte = reader.try_read_u8()?;
+		if matches!(byte, Self::NOT_BYTE | Self::AND_BYTE | Self::OR_BYTE) && max_depth == 0 {
+			return Err(TransactionError::InvalidWitnessCondition);
+		}
 		match byte {
-			WitnessCondition::BOOLEAN_BYTE => {
-				let b = reader.read_bool();
-				Ok(WitnessCondition::Boolean(b))
+			WitnessCondition::BOOLEAN_BYTE => match reader.try_read_u8()? {
+				0 => Ok(WitnessCondition::Boolean(false)),
+				1 => Ok(WitnessCondition::Boolean(true)),
+				_ => Err(TransactionError::InvalidWitnessCondition),
 			},
 			WitnessCondition::NOT_BYTE => {
-				let exp = WitnessCondition::decode(reader)?;
+				let exp = WitnessCondition::decode_nested(reader, max_depth - 1)?;
 				Ok(WitnessCondition::Not(Box::from(exp)))
 			},
 			WitnessCondition::OR_BYTE | WitnessCondition::AND_BYTE => {
-				let len = reader.read_var_int()? as usize;
-				if len > Self::MAX_SUBITEMS {
+				let len = reader.read_var_int()?;
+				if !(0..=Self::MAX_SUBITEMS as i64).contains(&len) {
 					return Err(TransactionError::InvalidWitnessCondition);
 				}
-				let mut expressions = Vec::with_capacity(len);
+				let mut expressions = Vec::with_capacity(len as usize);
 				for _ in 0..len {
-					expressions.push(WitnessCondition::decode(reader)?);
+					expressions.push(WitnessCondition::decode_nested(reader, max_depth - 1)?);
 				}
 				if byte == Self::OR_BYTE {
 					Ok(WitnessCondition::Or(expressions))
@@ -409,10 +426,4 @@ impl NeoSerializable for WitnessCondition {
 			_ => Err(TransactionError::InvalidTransaction),
 		}
 	}
-
-	fn to_array(&self) -> Vec<u8> {
-		let mut writer = Encoder::new();
-		self.encode(&mut writer);
-		writer.to_bytes()
-	}
 }
@@ -0,0 +1,302 @@
+use std::fmt;
+
+use primitive_types::H160;
+
+use crate::{
+	builder::{Signer, SignerTrait, WitnessAction, WitnessCondition, WitnessRule, WitnessScope},
+	crypto::Secp256r1PublicKey,
+	neo_types::ScriptHashExtension,
+};
+
+/// The point of execution at which a contract calls `CheckWitness`.
+///
+/// Used to find out locally whether the witness scope of a [`Signer`] covers a call, without
+/// executing anything.
+///
+/// # Examples
+///
+/// ```rust
+/// use neo3::neo_builder::{AccountSigner, Signer, WitnessContext};
+/// use neo3::neo_protocol::{Account, AccountTrait};
+/// use primitive_types::H160;
+///
+/// let account = Account::create().unwrap();
+/// let signer: Signer = AccountSigner::called_by_entry(&account).unwrap().into();
+/// let entry = H160::repeat_byte(0x01);
+/// let contract = H160::repeat_byte(0x02);
+///
+/// // The entry script calls the contract directly
+/// let check = WitnessContext::new(contract, Some(entry), entry).check_witness(&signer);
+/// assert!(check.allowed);
+///
+/// // The contract is called by another contract
+/// let other = H160::repeat_byte(0x03);
+/// let check = WitnessContext::new(contract, Some(other), entry).check_witness(&signer);
+/// assert!(!check.allowed);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct WitnessContext {
+	/// The hash of the contract calling `CheckWitness`.
+	pub current_script_hash: H160,
+	/// The hash of the contract that called the current one, `None` for the entry script.
+	pub calling_script_hash: Option<H160>,
+	/// Whether the current contract is the entry script or was called by it.
+	pub called_by_entry: bool,
+	/// The groups in the manifest of the current contract.
+	pub current_groups: Vec<Secp256r1PublicKey>,
+	/// The groups in the manifest of the calling contract.
+	pub calling_groups: Vec<Secp256r1PublicKey>,
+}
+
+/// The result of a local `CheckWitness`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WitnessCheck {
+	/// Whether `CheckWitness` would pass.
+	pub allowed: bool,
+	/// Why `CheckWitness` passes or fails.
+	pub reason: WitnessReason,
+}
+
+/// The part of a signer's witness scope that decided a `CheckWitness`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum WitnessReason {
+	/// The account is the calling contract itself.
+	CallingContract,
+	/// The signer has the `Global` scope.
+	Global,
+	/// The signer has the `CalledByEntry` scope and the contract is called by the entry script.
+	CalledByEntry,
+	/// The current contract is one of the signer's allowed contracts.
+	CustomContract(H160),
+	/// The current contract is in one of the signer's allowed groups.
+	CustomGroup(Secp256r1PublicKey),
+	/// The condition of the signer's rule at `index` is the first one that matches.
+	Rule { index: usize, rule: WitnessRule },
+	/// No scope of the signer covers the call.
+	NoMatch,
+}
+
+impl WitnessContext {
+	/// Creates a context for `current_script_hash` called by `calling_script_hash`, in an
+	/// execution started by `entry_script_hash`. The contracts are not in any group.
+	pub fn new(
+		current_script_hash: H160,
+		calling_script_hash: Option<H160>,
+		entry_script_hash: H160,
+	) -> Self {
+		let called_by_entry = match calling_script_hash {
+			Some(calling) => calling == entry_script_hash,
+			None => current_script_hash == entry_script_hash,
+		};
+		Self {
+			current_script_hash,
+			calling_script_hash,
+			called_by_entry,
+			current_groups: vec![],
+			calling_groups: vec![],
+		}
+	}
+
+	pub fn with_current_groups(mut self, groups: Vec<Secp256r1PublicKey>) -> Self {
+		self.current_groups = groups;
+		self
+	}
+
+	pub fn with_calling_groups(mut self, groups: Vec<Secp256r1PublicKey>) -> Self {
+		self.calling_groups = groups;
+		self
+	}
+
+	/// Whether `condition` holds at this point of execution.
+	pub fn matches(&self, condition: &WitnessCondition) -> bool {
+		match condition {
+			WitnessCondition::Boolean(value) => *value,
+			WitnessCondition::Not(inner) => !self.matches(inner),
+			WitnessCondition::And(conditions) =>
+				conditions.iter().all(|condition| self.matches(condition)),
+			WitnessCondition::Or(conditions) =>
+				conditions.iter().any(|condition| self.matches(condition)),
+			WitnessCondition::ScriptHash(hash) => self.current_script_hash == *hash,
+			WitnessCondition::Group(group) => self.current_groups.contains(group),
+			WitnessCondition::CalledByEntry => self.called_by_entry,
+			WitnessCondition::CalledByContract(hash) => self.calling_script_hash == Some(*hash),
+			WitnessCondition::CalledByGroup(group) =>
+				self.calling_script_hash.is_some() && self.calling_groups.contains(group),
+		}
+	}
+
+	/// Whether `CheckWitness` for the account of `signer` would pass at this point of execution,
+	/// checking the scopes in the same order as the node.
+	pub fn check_witness(&self, signer: &Signer) -> WitnessCheck {
+		let allow = |reason| WitnessCheck { allowed: true, reason };
+		if self.calling_script_hash == Some(*signer.get_signer_hash()) {
+			return allow(WitnessReason::CallingContract);
+		}
+
+		let scopes = signer.get_scopes();
+		if scopes.contains(&WitnessScope::Global) {
+			return allow(WitnessReason::Global);
+		}
+		if scopes.contains(&WitnessScope::CalledByEntry) && self.called_by_entry {
+			return allow(WitnessReason::CalledByEntry);
+		}
+		if scopes.contains(&WitnessScope::CustomContracts)
+			&& signer.get_allowed_contracts().contains(&self.current_script_hash)
+		{
+			return allow(WitnessReason::CustomContract(self.current_script_hash));
+		}
+		if scopes.contains(&WitnessScope::CustomGroups) {
+			if let Some(group) = signer
+				.get_allowed_groups()
+				.iter()
+				.find(|group| self.current_groups.contains(group))
+			{
+				return allow(WitnessReason::CustomGroup(group.clone()));
+			}
+		}
+		if scopes.contains(&WitnessScope::WitnessRules) {
+			if let Some((index, rule)) = signer
+				.get_rules()
+				.iter()
+				.enumerate()
+				.find(|(_, rule)| self.matches(&rule.condition))
+			{
+				return WitnessCheck {
+					allowed: rule.action == WitnessAction::Allow,
+					reason: WitnessReason::Rule { index, rule: rule.clone() },
+				};
+			}
+		}
+		WitnessCheck { allowed: false, reason: WitnessReason::NoMatch }
+	}
+}
+
+impl fmt::Display for WitnessReason {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::CallingContract => write!(f, "the account is the calling contract"),
+			Self::Global => write!(f, "the signer has the Global scope"),
+			Self::CalledByEntry => write!(f, "the contract is called by the entry script"),
+			Self::CustomContract(hash) =>
+				write!(f, "the contract {} is an allowed contract", hash.to_address()),
+			Self::CustomGroup(group) => write!(
+				f,
+				"the contract is in the allowed group {}",
+				hex::encode(group.get_encoded(true))
+			),
+			Self::Rule { index, rule } => write!(
+				f,
+				"rule {} ({}) is the first rule whose condition matches",
+				index, rule.action
+			),
+			Self::NoMatch => write!(f, "no witness scope of the signer covers the call"),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		builder::AccountSigner,
+		config::TestConstants,
+		neo_protocol::{Account, AccountTrait},
+	};
+
+	const ENTRY: H160 = H160::repeat_byte(0x01);
+	const CONTRACT: H160 = H160::repeat_byte(0x02);
+	const OTHER: H160 = H160::repeat_byte(0x03);
+
+	fn group() -> Secp256r1PublicKey {
+		Secp256r1PublicKey::from_encoded(TestConstants::DEFAULT_ACCOUNT_PUBLIC_KEY).unwrap()
+	}
+
+	fn signer(configure: impl FnOnce(&mut AccountSigner)) -> Signer {
+		let mut signer = AccountSigner::none(&Account::create().unwrap()).unwrap();
+		configure(&mut signer);
+		signer.into()
+	}
+
+	#[test]
+	fn test_scopes() {
+		let called_by_entry = WitnessContext::new(CONTRACT, Some(ENTRY), ENTRY);
+		let called_by_other = WitnessContext::new(CONTRACT, Some(OTHER), ENTRY);
+		assert!(called_by_entry.called_by_entry);
+		assert!(WitnessContext::new(ENTRY, None, ENTRY).called_by_entry);
+		assert!(!called_by_other.called_by_entry);
+
+		let none = signer(|_| {});
+		assert_eq!(called_by_entry.check_witness(&none).reason, WitnessReason::NoMatch);
+		let check = WitnessContext::new(CONTRACT, Some(*none.get_signer_hash()), ENTRY)
+			.check_witness(&none);
+		assert_eq!(check, WitnessCheck { allowed: true, reason: WitnessReason::CallingContract });
+
+		let global = signer(|signer| signer.set_scopes(vec![WitnessScope::Global]));
+		assert_eq!(called_by_other.check_witness(&global).reason, WitnessReason::Global);
+
+		let entry = signer(|signer| signer.set_scopes(vec![WitnessScope::CalledByEntry]));
+		assert_eq!(called_by_entry.check_witness(&entry).reason, WitnessReason::CalledByEntry);
+		assert!(!called_by_other.check_witness(&entry).allowed);
+
+		let contracts = signer(|signer| signer.set_allowed_contracts(vec![CONTRACT]).unwrap());
+		assert_eq!(
+			called_by_other.check_witness(&contracts).reason,
+			WitnessReason::CustomContract(CONTRACT)
+		);
+		assert!(!WitnessContext::new(OTHER, None, OTHER).check_witness(&contracts).allowed);
+
+		let groups = signer(|signer| signer.set_allowed_groups(vec![group()]).unwrap());
+		assert!(!called_by_other.check_witness(&groups).allowed);
+		let check = called_by_other
+			.clone()
+			.with_current_groups(vec![group()])
+			.check_witness(&groups);
+		assert_eq!(
+			check,
+			WitnessCheck { allowed: true, reason: WitnessReason::CustomGroup(group()) }
+		);
+	}
+
+	#[test]
+	fn test_rules() {
+		let deny = WitnessRule::new(
+			WitnessAction::Deny,
+			WitnessCondition::Not(Box::new(WitnessCondition::CalledByGroup(group()))),
+		);
+		let allow = WitnessRule::new(
+			WitnessAction::Allow,
+			WitnessCondition::And(vec![
+				WitnessCondition::ScriptHash(CONTRACT),
+				WitnessCondition::Or(vec![
+					WitnessCondition::CalledByEntry,
+					WitnessCondition::CalledByContract(OTHER),
+				]),
+			]),
+		);
+		let signer = signer(|signer| {
+			signer.set_rules(vec![deny.clone(), allow.clone()]).unwrap();
+		});
+
+		let context = WitnessContext::new(CONTRACT, Some(OTHER), ENTRY);
+		let check = context.check_witness(&signer);
+		assert_eq!(
+			check,
+			WitnessCheck { allowed: false, reason: WitnessReason::Rule { index: 0, rule: deny } }
+		);
+		assert_eq!(
+			check.reason.to_string(),
+			"rule 0 (Deny) is the first rule whose condition matches"
+		);
+
+		let check = context.clone().with_calling_groups(vec![group()]).check_witness(&signer);
+		assert_eq!(
+			check,
+			WitnessCheck { allowed: true, reason: WitnessReason::Rule { index: 1, rule: allow } }
+		);
+
+		let context =
+			WitnessContext::new(OTHER, Some(ENTRY), ENTRY).with_calling_groups(vec![group()]);
+		assert_eq!(context.check_witness(&signer).reason, WitnessReason::NoMatch);
+		assert!(!context.matches(&WitnessCondition::Boolean(false)));
+	}
+}
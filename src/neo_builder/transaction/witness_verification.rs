@@ -0,0 +1,331 @@
+use std::fmt;
+
+use primitive_types::H160;
+
+use crate::{
+	builder::{Signer, TransactionData, VerificationScript, Witness},
+	codec::Decoder,
+	crypto::{Secp256r1PublicKey, Secp256r1Signature},
+	neo_types::ScriptHashExtension,
+	OpCode,
+};
+
+/// The result of verifying the witness of a single signer offline.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum WitnessStatus {
+	/// The signatures of the witness are valid.
+	Valid,
+	/// The transaction has no witness for the signer.
+	MissingWitness,
+	/// The verification script of the witness hashes to another account than the signer's.
+	ScriptHashMismatch(H160),
+	/// The witness has no verification script, so it is verified by the `verify` method of a
+	/// deployed contract, which needs a node.
+	ContractVerification,
+	/// The verification script is neither a single- nor a multi-sig script.
+	NonStandardScript,
+	/// The invocation script doesn't only push signatures.
+	MalformedInvocationScript,
+	/// The invocation script doesn't push as many signatures as the verification script needs.
+	SignatureCount { expected: usize, actual: usize },
+	/// The signatures don't match the public keys of the verification script.
+	InvalidSignature,
+}
+
+/// The offline verification of the witness of a signer.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SignerVerification {
+	pub signer: H160,
+	pub status: WitnessStatus,
+}
+
+/// The offline verification of all witnesses of a transaction.
+///
+/// Only standard single- and multi-sig witnesses can be verified without a node. Whether the
+/// witness scopes of the signers cover the calls of the script isn't checked.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct WitnessVerification {
+	/// The verification of each signer, in the order of the signers.
+	pub signers: Vec<SignerVerification>,
+	/// The number of witnesses without a signer.
+	pub unexpected_witnesses: usize,
+}
+
+impl WitnessVerification {
+	/// Whether every signer has a valid witness and there are no other witnesses.
+	pub fn is_valid(&self) -> bool {
+		self.unexpected_witnesses == 0
+			&& self.signers.iter().all(|signer| signer.status == WitnessStatus::Valid)
+	}
+
+	/// The signers whose witness isn't valid.
+	pub fn failures(&self) -> impl Iterator<Item = &SignerVerification> {
+		self.signers.iter().filter(|signer| signer.status != WitnessStatus::Valid)
+	}
+}
+
+impl TransactionData {
+	/// Verifies the witness of each signer against the sign data of the transaction on the
+	/// network `network`, without a node.
+	///
+	/// The witness at the index of a signer must have a verification script hashing to the signer
+	/// account, and an invocation script pushing the signatures it checks.
+	///
+	/// # Examples
+	///
+	/// ```rust
+	/// use neo3::neo_builder::{AccountSigner, TransactionData, Witness};
+	/// use neo3::neo_protocol::{Account, AccountTrait};
+	///
+	/// let account = Account::create().unwrap();
+	/// let mut tx = TransactionData {
+	///     signers: vec![AccountSigner::called_by_entry(&account).unwrap().into()],
+	///     script: vec![0x11],
+	///     ..Default::default()
+	/// };
+	///
+	/// let key_pair = account.key_pair().clone().unwrap();
+	/// tx.witnesses = vec![Witness::create(tx.get_sign_data(860833102), &key_pair).unwrap()];
+	/// assert!(tx.verify_witnesses(860833102).is_valid());
+	/// assert!(!tx.verify_witnesses(894710606).is_valid());
+	/// ```
+	pub fn verify_witnesses(&self, network: u32) -> WitnessVerification {
+		let sign_data = self.get_sign_data(network);
+		let signers = self
+			.signers
+			.iter()
+			.enumerate()
+			.map(|(index, signer)| SignerVerification {
+				signer: *signer.get_signer_hash(),
+				status: match self.witnesses.get(index) {
+					Some(witness) => verify_witness(signer, witness, &sign_data),
+					None => WitnessStatus::MissingWitness,
+				},
+			})
+			.collect();
+		WitnessVerification {
+			signers,
+			unexpected_witnesses: self.witnesses.len().saturating_sub(self.signers.len()),
+		}
+	}
+}
+
+fn verify_witness(signer: &Signer, witness: &Witness, sign_data: &[u8]) -> WitnessStatus {
+	let verification = &witness.verification;
+	if verification.script().is_empty() {
+		return WitnessStatus::ContractVerification;
+	}
+	let hash = verification.hash();
+	if hash != *signer.get_signer_hash() {
+		return WitnessStatus::ScriptHashMismatch(hash);
+	}
+
+	let Some((threshold, public_keys)) = standard_public_keys(verification) else {
+		return WitnessStatus::NonStandardScript;
+	};
+	let Some(signatures) = pushed_signatures(witness.invocation.script()) else {
+		return WitnessStatus::MalformedInvocationScript;
+	};
+	if signatures.len() != threshold {
+		return WitnessStatus::SignatureCount { expected: threshold, actual: signatures.len() };
+	}
+
+	// Each signature must match a key, in the order of the keys, like `CheckMultisig`
+	let (mut i, mut j) = (0, 0);
+	while i < signatures.len() && j < public_keys.len() {
+		if public_keys[j].verify(sign_data, &signatures[i]).is_ok() {
+			i += 1;
+		}
+		j += 1;
+		if signatures.len() - i > public_keys.len() - j {
+			break;
+		}
+	}
+	if i == signatures.len() {
+		WitnessStatus::Valid
+	} else {
+		WitnessStatus::InvalidSignature
+	}
+}
+
+/// The signing threshold and public keys of a single- or multi-sig verification script.
+fn standard_public_keys(script: &VerificationScript) -> Option<(usize, Vec<Secp256r1PublicKey>)> {
+	if !script.is_single_sig() && !script.is_multi_sig() {
+		return None;
+	}
+	Some((script.get_signing_threshold().ok()?, script.get_public_keys().ok()?))
+}
+
+/// The signatures pushed by an invocation script that does nothing else.
+fn pushed_signatures(script: &[u8]) -> Option<Vec<Secp256r1Signature>> {
+	let mut reader = Decoder::new(script);
+	let mut signatures = vec![];
+	while reader.available() > 0 {
+		if reader.read_u8() != OpCode::PushData1 as u8 || reader.available() == 0 {
+			return None;
+		}
+		if reader.read_u8() != 64 {
+			return None;
+		}
+		let bytes = reader.read_bytes(64).ok()?;
+		signatures.push(Secp256r1Signature::from_bytes(&bytes).ok()?);
+	}
+	Some(signatures)
+}
+
+impl fmt::Display for WitnessStatus {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Valid => write!(f, "the witness is valid"),
+			Self::MissingWitness => write!(f, "the transaction has no witness for the signer"),
+			Self::ScriptHashMismatch(hash) => write!(
+				f,
+				"the verification script is the script of another account, {}",
+				hash.to_address()
+			),
+			Self::ContractVerification =>
+				write!(f, "the witness is verified by a contract, which needs a node"),
+			Self::NonStandardScript =>
+				write!(f, "the verification script is neither a single- nor a multi-sig script"),
+			Self::MalformedInvocationScript =>
+				write!(f, "the invocation script doesn't only push signatures"),
+			Self::SignatureCount { expected, actual } =>
+				write!(f, "the witness has {} signatures instead of {}", actual, expected),
+			Self::InvalidSignature =>
+				write!(f, "the signatures don't match the public keys of the account"),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		builder::{AccountSigner, InvocationScript},
+		crypto::KeyPair,
+		neo_protocol::{Account, AccountTrait},
+	};
+
+	const NETWORK: u32 = 860833102;
+
+	fn transaction(signers: &[&Account]) -> TransactionData {
+		TransactionData {
+			nonce: 1,
+			valid_until_block: 100,
+			signers: signers
+				.iter()
+				.map(|account| AccountSigner::called_by_entry(account).unwrap().into())
+				.collect(),
+			script: vec![0x11, 0x40],
+			..Default::default()
+		}
+	}
+
+	fn statuses(tx: &TransactionData) -> Vec<WitnessStatus> {
+		tx.verify_witnesses(NETWORK)
+			.signers
+			.into_iter()
+			.map(|signer| signer.status)
+			.collect()
+	}
+
+	#[test]
+	fn test_single_sig() {
+		let key_pair = KeyPair::new_random();
+		let other = KeyPair::new_random();
+		let account = Account::from_key_pair(key_pair.clone(), None, None).unwrap();
+		let mut tx = transaction(&[&account]);
+		assert_eq!(statuses(&tx), vec![WitnessStatus::MissingWitness]);
+
+		let sign_data = tx.get_sign_data(NETWORK);
+		tx.witnesses = vec![Witness::create(sign_data.clone(), &key_pair).unwrap()];
+		assert!(tx.verify_witnesses(NETWORK).is_valid());
+		assert_eq!(statuses(&tx), vec![WitnessStatus::Valid]);
+
+		// Signed by another key
+		tx.witnesses = vec![Witness::from_scripts_obj(
+			InvocationScript::from_message_and_key_pair(sign_data.clone(), &other).unwrap(),
+			VerificationScript::from_public_key(&key_pair.public_key()),
+		)];
+		assert_eq!(statuses(&tx), vec![WitnessStatus::InvalidSignature]);
+
+		// The witness of another account
+		tx.witnesses = vec![Witness::create(sign_data.clone(), &other).unwrap()];
+		assert_eq!(
+			statuses(&tx),
+			vec![WitnessStatus::ScriptHashMismatch(
+				VerificationScript::from_public_key(&other.public_key()).hash()
+			)]
+		);
+
+		let verification = VerificationScript::from_public_key(&key_pair.public_key());
+		tx.witnesses =
+			vec![Witness::from_scripts(vec![0x0c, 0x40, 0x01], verification.script().clone())];
+		assert_eq!(statuses(&tx), vec![WitnessStatus::MalformedInvocationScript]);
+		tx.witnesses = vec![Witness::from_scripts(vec![], verification.script().clone())];
+		assert_eq!(statuses(&tx), vec![WitnessStatus::SignatureCount { expected: 1, actual: 0 }]);
+
+		tx.witnesses = vec![
+			Witness::create(sign_data, &key_pair).unwrap(),
+			Witness::from_scripts(vec![], vec![]),
+		];
+		let verification = tx.verify_witnesses(NETWORK);
+		assert_eq!(verification.unexpected_witnesses, 1);
+		assert!(!verification.is_valid());
+		assert_eq!(verification.failures().count(), 0);
+	}
+
+	#[test]
+	fn test_multi_sig() {
+		let key_pairs: Vec<KeyPair> = (0..3).map(|_| KeyPair::new_random()).collect();
+		let mut public_keys: Vec<Secp256r1PublicKey> =
+			key_pairs.iter().map(KeyPair::public_key).collect();
+		let account = Account::multi_sig_from_public_keys(&mut public_keys, 2).unwrap();
+		let single = Account::create().unwrap();
+		let mut tx = transaction(&[&account, &single]);
+		let sign_data = tx.get_sign_data(NETWORK);
+
+		// The signatures must be in the order of the sorted keys
+		let mut sorted = key_pairs.clone();
+		sorted.sort_by_key(KeyPair::public_key);
+		let sign = |key_pairs: &[&KeyPair]| -> Vec<Secp256r1Signature> {
+			key_pairs
+				.iter()
+				.map(|key_pair| key_pair.private_key.sign_tx(&sign_data).unwrap())
+				.collect()
+		};
+		let witness = |signatures: Vec<Secp256r1Signature>| Witness {
+			invocation: InvocationScript::from_signatures(&signatures),
+			verification: account.verification_script().clone().unwrap(),
+		};
+		let single_witness =
+			Witness::create(sign_data.clone(), single.key_pair().as_ref().unwrap()).unwrap();
+
+		tx.witnesses = vec![witness(sign(&[&sorted[0], &sorted[2]])), single_witness.clone()];
+		assert!(tx.verify_witnesses(NETWORK).is_valid());
+
+		tx.witnesses = vec![witness(sign(&[&sorted[2], &sorted[0]])), single_witness.clone()];
+		let verification = tx.verify_witnesses(NETWORK);
+		assert_eq!(
+			verification.failures().cloned().collect::<Vec<_>>(),
+			vec![SignerVerification {
+				signer: account.get_script_hash(),
+				status: WitnessStatus::InvalidSignature
+			}]
+		);
+
+		tx.witnesses = vec![witness(sign(&[&sorted[1]])), single_witness.clone()];
+		assert_eq!(statuses(&tx)[0], WitnessStatus::SignatureCount { expected: 2, actual: 1 });
+
+		tx.witnesses = vec![Witness::from_scripts(vec![], vec![]), single_witness.clone()];
+		assert_eq!(statuses(&tx)[0], WitnessStatus::ContractVerification);
+
+		// A truncated multi-sig script
+		let mut script = account.verification_script().clone().unwrap().script().clone();
+		script.truncate(script.len() - 5);
+		let truncated = VerificationScript::from(script.clone());
+		tx.signers[0] = AccountSigner::called_by_entry_hash160(truncated.hash()).unwrap().into();
+		tx.witnesses = vec![Witness::from_scripts(vec![], script), single_witness];
+		assert_eq!(statuses(&tx)[0], WitnessStatus::NonStandardScript);
+	}
+}
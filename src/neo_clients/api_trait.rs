@@ -17,6 +17,7 @@ use async_trait::async_trait;
 use auto_impl::auto_impl;
 use primitive_types::{H160, H256};
 
+#[allow(clippy::double_must_use)]
 #[async_trait]
 #[auto_impl(&, Box, Arc)]
 pub trait APITrait: Sync + Send + Debug {
@@ -128,6 +129,11 @@ pub trait APITrait: Sync + Send + Debug {
 
 	async fn send_raw_transaction(&self, hex: String) -> Result<RawTransaction, Self::Error>;
 
+	/// Submits a serialized P2P notary request to the notary service of a neo-go node.
+	async fn submit_notary_request(&self, hex: String) -> Result<RawTransaction, ProviderError> {
+		self.rpc_client().submit_notary_request(hex).await
+	}
+
 	/// Sends a transaction to the network
 	///
 	/// # Arguments
@@ -252,6 +252,23 @@ impl RpcCache {
 		Self::new(config)
 	}
 
+	/// How long the result of a call of `method` can be cached, or `None` if it must not be
+	/// cached
+	///
+	/// Blocks and transactions are immutable and kept for an hour, the contract states, balances
+	/// and block count for a few seconds. The node information uses `default_ttl`.
+	pub fn method_ttl(method: &str, default_ttl: Duration) -> Option<Duration> {
+		match method {
+			"getblock" | "getrawtransaction" => Some(Duration::from_secs(3600)),
+			"getcontractstate" => Some(Duration::from_secs(60)),
+			"getnep17balances" => Some(Duration::from_secs(10)),
+			// A block every ~15 seconds
+			"getblockcount" => Some(Duration::from_secs(5)),
+			"getversion" | "getpeers" | "getconnectioncount" => Some(default_ttl),
+			_ => None,
+		}
+	}
+
 	/// Cache a block by hash or index
 	pub async fn cache_block(&self, identifier: String, block: serde_json::Value) {
 		// Blocks are immutable, so cache them for longer
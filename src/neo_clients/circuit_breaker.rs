@@ -91,6 +91,32 @@ impl CircuitBreaker {
 	pub async fn call<F, T>(&self, operation: F) -> Neo3Result<T>
 	where
 		F: std::future::Future<Output = Neo3Result<T>>,
+	{
+		self.call_with(
+			operation,
+			|_| true,
+			|| Neo3Error::Network(crate::neo_error::NetworkError::RateLimitExceeded),
+		)
+		.await
+	}
+
+	/// Execute a request through the circuit breaker, with any error type
+	///
+	/// Only the errors for which `is_failure` returns true count as failures of the service, the
+	/// others are answers of a healthy service. While the circuit is open the request fails with
+	/// the error returned by `rejected`.
+	///
+	/// The other errors count as successes: the service answered, so they close a half-open
+	/// circuit like a successful request does. Otherwise a service answering errors to valid
+	/// requests, like unknown transactions, would keep the circuit half-open.
+	pub async fn call_with<F, T, E>(
+		&self,
+		operation: F,
+		is_failure: impl FnOnce(&E) -> bool,
+		rejected: impl FnOnce() -> E,
+	) -> Result<T, E>
+	where
+		F: std::future::Future<Output = Result<T, E>>,
 	{
 		// Update total requests
 		{
@@ -102,7 +128,7 @@ impl CircuitBreaker {
 		if !self.should_allow_request().await {
 			let mut stats = self.stats.write().await;
 			stats.rejected_requests += 1;
-			return Err(Neo3Error::Network(crate::neo_error::NetworkError::RateLimitExceeded));
+			return Err(rejected());
 		}
 
 		// Execute the operation
@@ -112,7 +138,11 @@ impl CircuitBreaker {
 				Ok(result)
 			},
 			Err(error) => {
-				self.on_failure().await;
+				if is_failure(&error) {
+					self.on_failure().await;
+				} else {
+					self.on_success().await;
+				}
 				Err(error)
 			},
 		}
@@ -363,6 +393,29 @@ mod tests {
 		assert_eq!(cb.get_state().await, CircuitState::HalfOpen);
 	}
 
+	#[tokio::test]
+	async fn test_circuit_breaker_answered_errors_close() {
+		let config = CircuitBreakerConfig {
+			failure_threshold: 1,
+			timeout: Duration::from_millis(100),
+			success_threshold: 1,
+			..Default::default()
+		};
+		let cb = CircuitBreaker::new(config);
+		let call = |failure: bool| {
+			cb.call_with(async move { Err::<(), bool>(failure) }, |failure| *failure, || true)
+		};
+
+		assert_eq!(call(true).await, Err(true));
+		assert_eq!(cb.get_state().await, CircuitState::Open);
+		sleep(Duration::from_millis(150)).await;
+
+		// The service answered, so it recovered
+		assert_eq!(call(false).await, Err(false));
+		assert_eq!(cb.get_state().await, CircuitState::Closed);
+		assert_eq!(cb.get_stats().await.successful_requests, 1);
+	}
+
 	#[tokio::test]
 	async fn test_circuit_breaker_stats() {
 		let cb = CircuitBreaker::new(CircuitBreakerConfig::default());
@@ -1,6 +1,11 @@
 use std::{fmt::Debug, sync::Arc};
 
-use crate::{crypto::CryptoError, neo_clients::JsonRpcError, TypeError};
+use crate::{
+	crypto::CryptoError,
+	neo_clients::{JsonRpcError, QuorumError},
+	neo_error::Neo3Error,
+	TypeError,
+};
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -59,6 +64,21 @@ pub enum ProviderError {
 	/// Network not found
 	#[error("Network not found")]
 	NetworkNotFound,
+	/// The providers of a quorum provider didn't agree
+	#[error(transparent)]
+	QuorumError(Box<QuorumError>),
+	/// The circuit breaker in front of the provider rejected the request
+	#[error("Circuit breaker is open")]
+	CircuitOpen,
+	/// Error of the rate limiter or of the connection pool in front of the provider
+	#[error(transparent)]
+	Neo3Error(Arc<Neo3Error>),
+}
+
+impl From<Neo3Error> for ProviderError {
+	fn from(error: Neo3Error) -> Self {
+		ProviderError::Neo3Error(Arc::new(error))
+	}
 }
 
 impl PartialEq for ProviderError {
@@ -77,6 +97,10 @@ impl PartialEq for ProviderError {
 			(ProviderError::CryptoError(a), ProviderError::CryptoError(b)) => a == b,
 			(ProviderError::TypeError(a), ProviderError::TypeError(b)) => a == b,
 			(ProviderError::InvalidPassword, ProviderError::InvalidPassword) => true,
+			(ProviderError::CircuitOpen, ProviderError::CircuitOpen) => true,
+			(ProviderError::Neo3Error(a), ProviderError::Neo3Error(b)) => {
+				a.to_string() == b.to_string()
+			},
 			_ => false,
 		}
 	}
@@ -107,6 +131,9 @@ impl Clone for ProviderError {
 			ProviderError::LockError => ProviderError::LockError,
 			ProviderError::ProtocolNotFound => ProviderError::ProtocolNotFound,
 			ProviderError::NetworkNotFound => ProviderError::NetworkNotFound,
+			ProviderError::QuorumError(error) => ProviderError::QuorumError(error.clone()),
+			ProviderError::CircuitOpen => ProviderError::CircuitOpen,
+			ProviderError::Neo3Error(error) => ProviderError::Neo3Error(Arc::clone(error)),
 		}
 	}
 }
@@ -100,6 +100,7 @@ pub use mock_client::MockClient;
 pub use production_client::{ProductionClientConfig, ProductionClientStats, ProductionRpcClient};
 pub use rate_limiter::{RateLimitPermit, RateLimiter, RateLimiterBuilder, RateLimiterPresets};
 pub use rpc::*;
+pub use rx::*;
 #[allow(deprecated)]
 pub use test_provider::{MAINNET, TESTNET};
 pub use utils::*;
@@ -10,6 +10,11 @@ use std::{sync::Arc, time::Duration};
 use tokio::sync::RwLock;
 
 /// Production-ready RPC client with connection pooling, caching, and circuit breaker
+///
+/// It only sends raw JSON requests. To keep the typed [`APITrait`](crate::neo_clients::APITrait),
+/// stack the provider wrappers like [`CachedClient`](crate::neo_clients::CachedClient) and
+/// [`CircuitBreakerClient`](crate::neo_clients::CircuitBreakerClient) under a
+/// [`RpcClient`](crate::neo_clients::RpcClient) instead.
 pub struct ProductionRpcClient {
 	pool: ConnectionPool,
 	cache: RpcCache,
@@ -323,27 +328,13 @@ impl ProductionRpcClient {
 
 	/// Check if a method should be cached
 	fn is_cacheable_method(&self, method: &str) -> bool {
-		matches!(
-			method,
-			"getblock"
-				| "getrawtransaction"
-				| "getcontractstate"
-				| "getnep17balances"
-				| "getblockcount"
-				| "getversion"
-				| "getpeers" | "getconnectioncount"
-		)
+		RpcCache::method_ttl(method, self.config.cache_config.default_ttl).is_some()
 	}
 
 	/// Get appropriate cache TTL for different methods
 	fn get_cache_ttl(&self, method: &str) -> Duration {
-		match method {
-			"getblock" | "getrawtransaction" => Duration::from_secs(3600), // 1 hour - immutable
-			"getcontractstate" => Duration::from_secs(60),                 // 1 minute - can change
-			"getnep17balances" => Duration::from_secs(10),                 // 10 seconds - changes frequently
-			"getblockcount" => Duration::from_secs(5), // 5 seconds - changes every ~15 seconds
-			_ => self.config.cache_config.default_ttl,
-		}
+		RpcCache::method_ttl(method, self.config.cache_config.default_ttl)
+			.unwrap_or(self.config.cache_config.default_ttl)
 	}
 }
 
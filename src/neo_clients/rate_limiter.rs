@@ -57,6 +57,15 @@ impl RateLimiter {
 	///
 	/// This will wait if rate limit is exceeded
 	pub async fn acquire(&self) -> Result<RateLimitPermit<'_>, Neo3Error> {
+		self.acquire_many(1).await
+	}
+
+	/// Acquire a permit to make a batch of `count` requests
+	///
+	/// The batch takes a single concurrent slot and `count` tokens. A batch larger than the
+	/// capacity waits for a full bucket, and the requests after it wait for the tokens it
+	/// overdrew.
+	pub async fn acquire_many(&self, count: u32) -> Result<RateLimitPermit<'_>, Neo3Error> {
 		// First acquire semaphore permit for concurrency limiting
 		let _sem_permit = self
 			.semaphore
@@ -75,14 +84,17 @@ impl RateLimiter {
 				(bucket.tokens + elapsed * bucket.refill_rate).min(bucket.capacity as f64);
 			bucket.last_refill = now;
 
-			// Try to consume a token
-			if bucket.tokens >= 1.0 {
-				bucket.tokens -= 1.0;
+			// Try to consume the tokens
+			let needed = (count as f64).min(bucket.capacity as f64);
+			if bucket.tokens >= needed {
+				bucket.tokens -= count as f64;
 				return Ok(RateLimitPermit { _semaphore: _sem_permit });
 			}
 
-			// Calculate wait time until next token
-			let wait_time = Duration::from_secs_f64(1.0 / bucket.refill_rate);
+			// Calculate wait time until enough tokens
+			let wait_time = Duration::from_secs_f64(
+				(needed - bucket.tokens).max(1.0) / bucket.refill_rate,
+			);
 			drop(bucket); // Release lock while waiting
 
 			sleep(wait_time).await;
@@ -260,6 +272,23 @@ mod tests {
 		assert!(limiter.try_acquire().await.is_ok());
 	}
 
+	#[tokio::test]
+	async fn test_rate_limiter_batch() {
+		let limiter = RateLimiter::new(5, Duration::from_secs(1), 2);
+
+		// A batch takes as many tokens as it has requests
+		assert!(limiter.acquire_many(3).await.is_ok());
+		assert!(limiter.try_acquire().await.is_ok());
+		assert!(limiter.try_acquire().await.is_ok());
+		assert!(limiter.try_acquire().await.is_err());
+
+		// A batch larger than the capacity overdraws the bucket
+		limiter.reset().await;
+		assert!(limiter.acquire_many(8).await.is_ok());
+		assert!(limiter.available_tokens().await < -2.0);
+		assert!(limiter.try_acquire().await.is_err());
+	}
+
 	#[tokio::test]
 	async fn test_concurrent_limiting() {
 		let limiter = Arc::new(RateLimiter::new(100, Duration::from_secs(1), 2));
@@ -0,0 +1,328 @@
+use std::{fmt::Debug, marker::PhantomData, sync::Mutex};
+
+use async_trait::async_trait;
+use futures_util::{future::BoxFuture, FutureExt};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::neo_clients::{JsonRpcProvider, ProviderError, RpcClient};
+
+/// A single call of a JSON-RPC batch.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RpcCall {
+	pub method: String,
+	pub params: Value,
+}
+
+impl RpcCall {
+	/// Creates a call of `method`. Parameters that serialize to `null`, like `()`, are sent as an
+	/// empty array.
+	pub fn new<T: Serialize>(method: &str, params: T) -> Result<Self, ProviderError> {
+		let params = match serde_json::to_value(params)? {
+			Value::Null => Value::Array(vec![]),
+			params => params,
+		};
+		Ok(Self { method: method.to_string(), params })
+	}
+}
+
+/// Queues calls to send them to the node in a single JSON-RPC batch request.
+///
+/// Calls are queued with the methods of [`APITrait`](crate::neo_clients::APITrait), or with
+/// [`add_request`](Self::add_request) for methods without a binding. Each queued call returns a
+/// typed [`BatchCall`] handle to get its result from the [`BatchResponse`].
+///
+/// Only calls that send a single request and return its result as is can be batched. Calls like
+/// `get_block` without the full transactions are fine, calls that compute their result from the
+/// response, like `network`, fail with a deserialization error.
+///
+/// # Examples
+///
+/// ```no_run
+/// use neo3::neo_clients::{APITrait, HttpProvider, RpcClient};
+/// use neo3::neo_types::ScriptHash;
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let client = RpcClient::new(HttpProvider::new("https://testnet1.neo.org:443")?);
+/// let accounts = vec![ScriptHash::zero(), ScriptHash::repeat_byte(0x01)];
+///
+/// let mut batch = client.batch();
+/// let count = batch.add(|client| client.get_block_count());
+/// let balances: Vec<_> = accounts
+///     .iter()
+///     .map(|account| batch.add(|client| client.get_nep17_balances(*account)))
+///     .collect();
+///
+/// // One HTTP round-trip for all calls
+/// let response = batch.send().await?;
+/// println!("Block count: {}", response.get(&count)?);
+/// for balance in &balances {
+///     match response.get(balance) {
+///         Ok(balances) => println!("{} balances", balances.balances.len()),
+///         Err(err) => println!("Call failed: {}", err),
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct RpcBatch<'a, P> {
+	client: &'a RpcClient<P>,
+	recorder: RpcClient<BatchRecorder>,
+	calls: Vec<Result<RpcCall, ProviderError>>,
+}
+
+/// The handle of a call queued in a [`RpcBatch`], typed with the result of the call.
+#[derive(Debug)]
+pub struct BatchCall<R> {
+	index: usize,
+	_result: PhantomData<fn() -> R>,
+}
+
+/// The results of the calls of a [`RpcBatch`], in the order they were queued.
+#[derive(Debug, Clone)]
+pub struct BatchResponse {
+	results: Vec<Result<Value, ProviderError>>,
+}
+
+impl<'a, P: JsonRpcProvider> RpcBatch<'a, P> {
+	pub fn new(client: &'a RpcClient<P>) -> Self {
+		Self { client, recorder: RpcClient::new(BatchRecorder::default()), calls: vec![] }
+	}
+
+	/// Queues the request `call` makes with the [`APITrait`](crate::neo_clients::APITrait)
+	/// methods of the client it is given.
+	///
+	/// If `call` fails before making a request, or makes none, its error is returned in its
+	/// place in the response.
+	pub fn add<'b, R, F>(&'b mut self, call: F) -> BatchCall<R>
+	where
+		F: FnOnce(&'b RpcClient<BatchRecorder>) -> BoxFuture<'b, Result<R, ProviderError>>,
+	{
+		// The recorder doesn't wait for anything, so the call completes when first polled
+		let output = call(&self.recorder).now_or_never();
+		let mut recorded = std::mem::take(&mut *self.recorder.as_ref().lock());
+		let call = match (recorded.pop(), output) {
+			(Some(call), _) if recorded.is_empty() => call,
+			(Some(_), _) => Err(ProviderError::IllegalState(
+				"Only calls making a single request can be batched".to_string(),
+			)),
+			(None, Some(Err(err))) => Err(err),
+			(None, _) => Err(ProviderError::IllegalState(
+				"The call didn't make any request to batch".to_string(),
+			)),
+		};
+		// Only the calls are borrowed mutably, the recorder is still borrowed by `call`
+		self.calls.push(call);
+		BatchCall { index: self.calls.len() - 1, _result: PhantomData }
+	}
+
+	/// Queues a call of `method` with `params`.
+	pub fn add_request<T: Serialize, R>(&mut self, method: &str, params: T) -> BatchCall<R> {
+		self.push(RpcCall::new(method, params))
+	}
+
+	fn push<R>(&mut self, call: Result<RpcCall, ProviderError>) -> BatchCall<R> {
+		self.calls.push(call);
+		BatchCall { index: self.calls.len() - 1, _result: PhantomData }
+	}
+
+	/// The number of queued calls.
+	pub fn len(&self) -> usize {
+		self.calls.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.calls.is_empty()
+	}
+
+	/// Sends the queued calls in a single batch request.
+	///
+	/// Fails if the batch as a whole failed. Errors of single calls are returned in their place
+	/// in the response.
+	pub async fn send(self) -> Result<BatchResponse, ProviderError> {
+		let (calls, indices): (Vec<_>, Vec<_>) = self
+			.calls
+			.iter()
+			.enumerate()
+			.filter_map(|(index, call)| call.as_ref().ok().map(|call| (call.clone(), index)))
+			.unzip();
+		let fetched = if calls.is_empty() {
+			vec![]
+		} else {
+			self.client.as_ref().fetch_batch(&calls).await.map_err(Into::into)?
+		};
+
+		let mut results: Vec<_> = self.calls.into_iter().map(|call| call.map(|_| None)).collect();
+		for (index, result) in indices.into_iter().zip(fetched) {
+			results[index] = result.map(Some);
+		}
+		let results = results
+			.into_iter()
+			.enumerate()
+			.map(|(index, result)| {
+				result?.ok_or_else(|| {
+					ProviderError::CustomError(format!("No response for batch call {}", index))
+				})
+			})
+			.collect();
+		Ok(BatchResponse { results })
+	}
+}
+
+impl<R> BatchCall<R> {
+	/// The position of the call in the batch.
+	pub fn index(&self) -> usize {
+		self.index
+	}
+}
+
+impl<R> Clone for BatchCall<R> {
+	fn clone(&self) -> Self {
+		*self
+	}
+}
+
+impl<R> Copy for BatchCall<R> {}
+
+impl BatchResponse {
+	/// The result of `call`.
+	pub fn get<R: DeserializeOwned>(&self, call: &BatchCall<R>) -> Result<R, ProviderError> {
+		let result = self.results.get(call.index).ok_or_else(|| {
+			ProviderError::IllegalState(format!("No call {} in the batch", call.index))
+		})?;
+		Ok(serde_json::from_value(result.clone()?)?)
+	}
+
+	/// The raw results of the calls.
+	pub fn results(&self) -> &[Result<Value, ProviderError>] {
+		&self.results
+	}
+
+	pub fn into_results(self) -> Vec<Result<Value, ProviderError>> {
+		self.results
+	}
+
+	pub fn len(&self) -> usize {
+		self.results.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.results.is_empty()
+	}
+}
+
+/// A provider that records the requests made to it instead of sending them, used to queue the
+/// calls of a [`RpcBatch`].
+#[derive(Debug, Default)]
+pub struct BatchRecorder {
+	calls: Mutex<Vec<Result<RpcCall, ProviderError>>>,
+}
+
+impl BatchRecorder {
+	fn lock(&self) -> std::sync::MutexGuard<'_, Vec<Result<RpcCall, ProviderError>>> {
+		self.calls.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+	}
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(? Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl JsonRpcProvider for BatchRecorder {
+	type Error = ProviderError;
+
+	async fn fetch<T, R>(&self, method: &str, params: T) -> Result<R, ProviderError>
+	where
+		T: Debug + Serialize + Send + Sync,
+		R: DeserializeOwned + Send,
+	{
+		self.lock().push(RpcCall::new(method, params));
+		Err(ProviderError::IllegalState("The request is queued in a batch".to_string()))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json::json;
+	use wiremock::{matchers::method, Mock, MockServer, Request, ResponseTemplate};
+
+	use super::*;
+	use crate::{
+		neo_clients::{APITrait, HttpProvider, JsonRpcError},
+		neo_types::ScriptHash,
+	};
+
+	async fn server() -> MockServer {
+		let server = MockServer::start().await;
+		Mock::given(method("POST"))
+			.respond_with(|request: &Request| {
+				let calls: Vec<Value> = serde_json::from_slice(&request.body).unwrap();
+				// Answer in reverse order, the node doesn't have to keep the order of the batch
+				let responses: Vec<Value> = calls
+					.iter()
+					.rev()
+					.map(|call| match call["method"].as_str().unwrap() {
+						"getblockcount" =>
+							json!({"jsonrpc": "2.0", "id": call["id"], "result": 100}),
+						"getversion" => json!({"jsonrpc": "2.0", "id": call["id"], "result": 1}),
+						_ => json!({
+							"jsonrpc": "2.0",
+							"id": call["id"],
+							"error": {"code": -32601, "message": "Method not found"}
+						}),
+					})
+					.collect();
+				ResponseTemplate::new(200).set_body_json(responses)
+			})
+			.expect(1)
+			.mount(&server)
+			.await;
+		server
+	}
+
+	#[tokio::test]
+	async fn test_batch() {
+		let server = server().await;
+		let client = RpcClient::new(HttpProvider::new(server.uri().as_str()).unwrap());
+
+		let mut batch = client.batch();
+		let count = batch.add(|client| client.get_block_count());
+		let header_count = batch.add(|client| client.get_block_header_count());
+		let invalid = batch.add(|client| client.get_block_by_hash("invalid", true));
+		let raw = batch.add_request::<_, u32>("getversion", ());
+		let balances = batch.add(|client| client.get_nep17_balances(ScriptHash::zero()));
+		assert_eq!(batch.len(), 5);
+
+		let response = batch.send().await.unwrap();
+		assert_eq!(response.len(), 5);
+		assert_eq!(response.get(&count).unwrap(), 100);
+		assert!(matches!(
+			response.get(&header_count),
+			Err(ProviderError::JsonRpcError(JsonRpcError { code: -32601, .. }))
+		));
+		assert!(matches!(response.get(&invalid), Err(ProviderError::ParseError(_))));
+		assert_eq!(response.get(&raw).unwrap(), 1);
+		assert!(response.get(&balances).is_err());
+	}
+
+	#[tokio::test]
+	async fn test_recorded_calls() {
+		let client = RpcClient::new(HttpProvider::new("http://localhost:1").unwrap());
+		let mut batch = client.batch();
+		batch.add(|client| client.get_block_hash(7));
+		batch.add_request::<_, Value>("getblockcount", ());
+		assert_eq!(
+			batch.calls,
+			vec![
+				Ok(RpcCall { method: "getblockhash".to_string(), params: json!([7]) }),
+				Ok(RpcCall { method: "getblockcount".to_string(), params: json!([]) }),
+			]
+		);
+
+		// Nothing is sent for a batch without valid calls
+		let mut batch = client.batch();
+		let invalid = batch.add(|client| client.get_block_by_hash("invalid", false));
+		let response = batch.send().await.unwrap();
+		assert!(response.get(&invalid).is_err());
+		assert_eq!(client.batch().send().await.unwrap().len(), 0);
+	}
+}
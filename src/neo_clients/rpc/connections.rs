@@ -1,10 +1,13 @@
 use std::fmt::Debug;
 
-use crate::neo_clients::ProviderError;
+use crate::neo_clients::{Notifications, ProviderError, RpcCall};
 use async_trait::async_trait;
 use auto_impl::auto_impl;
+use primitive_types::U256;
 use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
 
+#[allow(clippy::double_must_use)]
 #[cfg_attr(target_arch = "wasm32", async_trait(? Send))]
 #[cfg_attr(not(target_arch = "wasm32"), async_trait)]
 #[auto_impl(&, Box, Arc)]
@@ -19,6 +22,35 @@ pub trait JsonRpcProvider: Debug + Send + Sync {
 	where
 		T: Debug + Serialize + Send + Sync,
 		R: DeserializeOwned + Send;
+
+	/// Sends the `calls` in a single JSON-RPC batch and returns their results in the same order.
+	///
+	/// Only fails if the batch as a whole failed, errors of single calls are returned in their
+	/// place. Transports without batch support send the calls one by one.
+	async fn fetch_batch(
+		&self,
+		calls: &[RpcCall],
+	) -> Result<Vec<Result<Value, ProviderError>>, Self::Error> {
+		let mut results = Vec::with_capacity(calls.len());
+		for call in calls {
+			let result = self.fetch::<_, Value>(&call.method, &call.params).await;
+			results.push(result.map_err(Into::into));
+		}
+		Ok(results)
+	}
+
+	/// Whether the transport streams the notifications of subscriptions, like a websocket.
+	fn supports_subscriptions(&self) -> bool {
+		false
+	}
+
+	/// Takes the notifications of the subscription `id`, installed with a `subscribe` request.
+	/// The subscription is cancelled when the stream is dropped.
+	///
+	/// Returns `None` if the transport doesn't support subscriptions.
+	fn notifications(&self, _id: U256) -> Option<Result<Notifications, Self::Error>> {
+		None
+	}
 }
 
 /// Backwards-compatible alias for `JsonRpcProvider`.
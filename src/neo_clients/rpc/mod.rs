@@ -57,13 +57,17 @@
 //! }
 //! ```
 
+pub use batch::{BatchCall, BatchRecorder, BatchResponse, RpcBatch, RpcCall};
 pub use connections::*;
-pub use pubsub::{PubsubClient, SubscriptionStream};
+pub use pubsub::{pubsub_notifications, Notifications, PubsubClient, SubscriptionStream};
 pub use rpc_client::*;
+pub use subscriptions::*;
 pub use transports::*;
 
+mod batch;
 mod rpc_client;
 
 mod connections;
 mod pubsub;
+mod subscriptions;
 mod transports;
@@ -25,9 +25,47 @@ pub trait PubsubClient: JsonRpcProvider {
 	fn unsubscribe<T: Into<U256>>(&self, id: T) -> Result<(), Self::Error>;
 }
 
+/// The notifications of a subscription, see [`JsonRpcProvider::notifications`].
+pub type Notifications = Pin<Box<dyn Stream<Item = Box<RawValue>> + Send>>;
+
+/// Takes the notifications of the subscription `id` of `client`, and cancels the subscription
+/// when they are dropped. Implements [`JsonRpcProvider::notifications`] for pub sub transports.
+pub fn pubsub_notifications<P>(client: &P, id: U256) -> Result<Notifications, P::Error>
+where
+	P: PubsubClient + Clone + 'static,
+	P::NotificationStream: 'static,
+{
+	let rx = client.subscribe(id)?;
+	Ok(Box::pin(OwnedNotifications { client: client.clone(), id, rx }))
+}
+
+/// The notifications of a subscription, holding on to the transport to cancel it on drop.
+struct OwnedNotifications<P: PubsubClient> {
+	client: P,
+	id: U256,
+	rx: P::NotificationStream,
+}
+
+// The transport is never pinned, and the notification stream is `Unpin`
+impl<P: PubsubClient> Unpin for OwnedNotifications<P> {}
+
+impl<P: PubsubClient> Stream for OwnedNotifications<P> {
+	type Item = Box<RawValue>;
+
+	fn poll_next(self: Pin<&mut Self>, ctx: &mut Context) -> Poll<Option<Self::Item>> {
+		Pin::new(&mut self.get_mut().rx).poll_next(ctx)
+	}
+}
+
+impl<P: PubsubClient> Drop for OwnedNotifications<P> {
+	fn drop(&mut self) {
+		let _ = self.client.unsubscribe(self.id);
+	}
+}
+
 #[must_use = "subscriptions do nothing unless you stream them"]
 #[pin_project(PinnedDrop)]
-/// Streams data from an installed filter via `neo_subscribe`
+/// Streams the events of a subscription installed via `subscribe`
 pub struct SubscriptionStream<'a, P: PubsubClient, R: DeserializeOwned> {
 	/// The subscription's installed id on the neo node
 	pub id: U256,
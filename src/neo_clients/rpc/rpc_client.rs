@@ -20,7 +20,9 @@ use url::Url;
 // Replace the generic import with specific imports
 use crate::{
 	neo_builder::{InteropService, ScriptBuilder, TransactionBuilder, TransactionSigner},
-	neo_clients::{APITrait, Http, JsonRpcProvider, ProviderError, RwClient},
+	neo_clients::{
+		APITrait, BlockStreamConfig, Http, JsonRpcProvider, ProviderError, RpcBatch, RwClient,
+	},
 };
 
 use crate::{
@@ -82,6 +84,7 @@ pub struct RpcClient<P> {
 	#[allow(dead_code)]
 	nns: Option<Address>,
 	interval: Option<Duration>,
+	stream_config: BlockStreamConfig,
 	from: Option<Address>,
 	_node_client: Arc<Mutex<Option<NeoVersion>>>,
 	// #[getset(get = "pub")]
@@ -102,6 +105,7 @@ impl<P: JsonRpcProvider> RpcClient<P> {
 			provider,
 			nns: None,
 			interval: None,
+			stream_config: BlockStreamConfig::default(),
 			from: None,
 			_node_client: Arc::new(Mutex::new(None)),
 			// allow_transmission_on_fault: false,
@@ -129,6 +133,11 @@ impl<P: JsonRpcProvider> RpcClient<P> {
 		self
 	}
 
+	/// Starts a batch of calls sent to the node in a single request.
+	pub fn batch(&self) -> RpcBatch<'_, P> {
+		RpcBatch::new(self)
+	}
+
 	/// Make an RPC request via the internal connection, and return the result.
 	pub async fn request<T, R>(&self, method: &str, params: T) -> Result<R, ProviderError>
 	where
@@ -420,6 +429,13 @@ impl<P: JsonRpcProvider> APITrait for RpcClient<P> {
 		self.request("sendrawtransaction", vec![Base64Encode::to_base64(&hex)]).await
 	}
 
+	/// Submits a P2P notary request to the notary service of the node (neo-go only).
+	/// - Parameter hex: The serialized request in hexadecimal
+	/// - Returns: The request object, holding the hash of the fallback transaction
+	async fn submit_notary_request(&self, hex: String) -> Result<RawTransaction, ProviderError> {
+		self.request("submitnotaryrequest", vec![Base64Encode::to_base64(&hex)]).await
+	}
+
 	/// Sends a transaction to the network
 	///
 	/// # Arguments
@@ -1216,6 +1232,28 @@ impl<P: JsonRpcProvider> RpcClient<P> {
 		self.set_interval(interval);
 		self
 	}
+
+	/// The polling interval, the block time of the network if none is set
+	pub fn get_interval(&self) -> Duration {
+		self.interval.unwrap_or_else(|| Duration::from_millis(self.polling_interval() as u64))
+	}
+
+	/// Sets how the block and transaction streams fetch their blocks
+	pub fn set_stream_config(&mut self, config: BlockStreamConfig) -> &mut Self {
+		self.stream_config = config;
+		self
+	}
+
+	/// Sets how the block and transaction streams fetch their blocks
+	#[must_use]
+	pub fn stream_config(mut self, config: BlockStreamConfig) -> Self {
+		self.set_stream_config(config);
+		self
+	}
+
+	pub fn get_stream_config(&self) -> BlockStreamConfig {
+		self.stream_config
+	}
 }
 
 #[cfg(all(feature = "ipc", any(unix, windows)))]
@@ -0,0 +1,639 @@
+use std::fmt;
+
+use primitive_types::{H160, H256, U256};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{value::RawValue, Value};
+
+use crate::{
+	neo_clients::{ProviderError, PubsubClient, RpcClient, SubscriptionStream},
+	neo_protocol::{
+		ApplicationLog, Execution, LogNotification, NeoBlock, NeoWitness, RTransaction,
+	},
+	neo_types::{
+		deserialize_h256, deserialize_h256_option, deserialize_script_hash,
+		deserialize_script_hash_option, serialize_h160, serialize_h256, serialize_h256_option,
+		serialize_script_hash_option, StackItem, VMState,
+	},
+};
+
+/// The events a neo-go node streams to the clients subscribed to them with `subscribe`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum SubscriptionEvent {
+	/// A block was added to the chain, streamed as a [`NeoBlock`].
+	BlockAdded,
+	/// A block was added to the chain, streamed as a [`NeoBlock`] without transactions.
+	HeaderOfAddedBlock,
+	/// A transaction was added to the memory pool, streamed as a [`RTransaction`].
+	TransactionAdded,
+	/// A contract emitted a notification, streamed as a [`NotificationRecord`].
+	NotificationFromExecution,
+	/// A transaction or a block trigger was executed, streamed as an [`ExecutionRecord`].
+	TransactionExecuted,
+	/// A notary request was added to or removed from the notary pool, streamed as a
+	/// [`NotaryRequestRecord`].
+	NotaryRequestEvent,
+}
+
+impl SubscriptionEvent {
+	/// The name of the event in the `subscribe` request and in its notifications.
+	pub fn as_str(&self) -> &'static str {
+		match self {
+			Self::BlockAdded => "block_added",
+			Self::HeaderOfAddedBlock => "header_of_added_block",
+			Self::TransactionAdded => "transaction_added",
+			Self::NotificationFromExecution => "notification_from_execution",
+			Self::TransactionExecuted => "transaction_executed",
+			Self::NotaryRequestEvent => "notary_request_event",
+		}
+	}
+}
+
+impl fmt::Display for SubscriptionEvent {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(self.as_str())
+	}
+}
+
+/// Filters the blocks of `block_added` and `header_of_added_block` subscriptions.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq, Hash)]
+#[serde(default)]
+pub struct BlockFilter {
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub primary: Option<u8>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub since: Option<u32>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub till: Option<u32>,
+}
+
+impl BlockFilter {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Only blocks proposed by the validator with the index `primary`.
+	pub fn primary(mut self, primary: u8) -> Self {
+		self.primary = Some(primary);
+		self
+	}
+
+	/// Only blocks from the index `since`, included.
+	pub fn since(mut self, since: u32) -> Self {
+		self.since = Some(since);
+		self
+	}
+
+	/// Only blocks up to the index `till`, included.
+	pub fn till(mut self, till: u32) -> Self {
+		self.till = Some(till);
+		self
+	}
+
+	pub fn matches(&self, block: &NeoBlock) -> bool {
+		self.primary.is_none_or(|primary| block.primary == Some(primary as i32))
+			&& self.since.is_none_or(|since| block.index as i64 >= since as i64)
+			&& self.till.is_none_or(|till| block.index as i64 <= till as i64)
+	}
+}
+
+/// Filters the transactions of `transaction_added` subscriptions.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq, Hash)]
+#[serde(default)]
+pub struct TransactionFilter {
+	#[serde(
+		skip_serializing_if = "Option::is_none",
+		serialize_with = "serialize_script_hash_option",
+		deserialize_with = "deserialize_script_hash_option"
+	)]
+	pub sender: Option<H160>,
+	#[serde(
+		skip_serializing_if = "Option::is_none",
+		serialize_with = "serialize_script_hash_option",
+		deserialize_with = "deserialize_script_hash_option"
+	)]
+	pub signer: Option<H160>,
+}
+
+impl TransactionFilter {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Only transactions sent by `sender`, their first signer.
+	pub fn sender(mut self, sender: H160) -> Self {
+		self.sender = Some(sender);
+		self
+	}
+
+	/// Only transactions signed by `signer`.
+	pub fn signer(mut self, signer: H160) -> Self {
+		self.signer = Some(signer);
+		self
+	}
+
+	pub fn matches(&self, transaction: &RTransaction) -> bool {
+		let signers = &transaction.signers;
+		self.sender.is_none_or(|sender| signers.first().is_some_and(|s| s.account == sender))
+			&& self.signer.is_none_or(|signer| signers.iter().any(|s| s.account == signer))
+	}
+}
+
+/// Filters the notifications of `notification_from_execution` subscriptions.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq, Hash)]
+#[serde(default)]
+pub struct NotificationFilter {
+	#[serde(
+		skip_serializing_if = "Option::is_none",
+		serialize_with = "serialize_script_hash_option",
+		deserialize_with = "deserialize_script_hash_option"
+	)]
+	pub contract: Option<H160>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub name: Option<String>,
+}
+
+impl NotificationFilter {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Only notifications emitted by `contract`.
+	pub fn contract(mut self, contract: H160) -> Self {
+		self.contract = Some(contract);
+		self
+	}
+
+	/// Only notifications of the event `name`, like `Transfer`.
+	pub fn name(mut self, name: impl Into<String>) -> Self {
+		self.name = Some(name.into());
+		self
+	}
+
+	pub fn matches(&self, notification: &NotificationRecord) -> bool {
+		self.contract.is_none_or(|contract| notification.contract == contract)
+			&& self.name.as_ref().is_none_or(|name| &notification.event_name == name)
+	}
+}
+
+/// Filters the executions of `transaction_executed` subscriptions.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq, Hash)]
+#[serde(default)]
+pub struct ExecutionFilter {
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub state: Option<VMState>,
+	#[serde(
+		skip_serializing_if = "Option::is_none",
+		serialize_with = "serialize_h256_option",
+		deserialize_with = "deserialize_h256_option"
+	)]
+	pub container: Option<H256>,
+}
+
+impl ExecutionFilter {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Only executions ending in `state`.
+	pub fn state(mut self, state: VMState) -> Self {
+		self.state = Some(state);
+		self
+	}
+
+	/// Only the executions of the transaction or block `container`.
+	pub fn container(mut self, container: H256) -> Self {
+		self.container = Some(container);
+		self
+	}
+
+	pub fn matches(&self, execution: &ExecutionRecord) -> bool {
+		self.state.is_none_or(|state| execution.execution.state == state)
+			&& self.container.is_none_or(|container| execution.container == container)
+	}
+}
+
+/// Filters the requests of `notary_request_event` subscriptions.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq, Hash)]
+#[serde(default)]
+pub struct NotaryRequestFilter {
+	#[serde(
+		skip_serializing_if = "Option::is_none",
+		serialize_with = "serialize_script_hash_option",
+		deserialize_with = "deserialize_script_hash_option"
+	)]
+	pub sender: Option<H160>,
+	#[serde(
+		skip_serializing_if = "Option::is_none",
+		serialize_with = "serialize_script_hash_option",
+		deserialize_with = "deserialize_script_hash_option"
+	)]
+	pub signer: Option<H160>,
+	#[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+	pub event_type: Option<NotaryRequestEventType>,
+}
+
+impl NotaryRequestFilter {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Only requests sent by `sender`, the second signer of their fallback transaction.
+	pub fn sender(mut self, sender: H160) -> Self {
+		self.sender = Some(sender);
+		self
+	}
+
+	/// Only requests whose main transaction is signed by `signer`.
+	pub fn signer(mut self, signer: H160) -> Self {
+		self.signer = Some(signer);
+		self
+	}
+
+	/// Only requests that were added to or removed from the pool.
+	pub fn event_type(mut self, event_type: NotaryRequestEventType) -> Self {
+		self.event_type = Some(event_type);
+		self
+	}
+
+	pub fn matches(&self, record: &NotaryRequestRecord) -> bool {
+		let request = &record.notary_request;
+		let sender = request.fallback_transaction.signers.get(1).map(|signer| signer.account);
+		let signers = &request.main_transaction.signers;
+		self.sender.is_none_or(|filter| sender == Some(filter))
+			&& self.signer.is_none_or(|filter| signers.iter().any(|s| s.account == filter))
+			&& self.event_type.is_none_or(|event_type| record.event_type == event_type)
+	}
+}
+
+/// A subscription to the events of a neo-go node, with the filter the node applies to them.
+///
+/// # Examples
+///
+/// ```
+/// use neo3::neo_clients::{NotificationFilter, Subscription};
+/// use primitive_types::H160;
+///
+/// let subscription = Subscription::NotificationFromExecution(
+///     NotificationFilter::new().contract(H160::repeat_byte(0x01)).name("Transfer"),
+/// );
+/// assert_eq!(
+///     serde_json::to_string(&subscription.params().unwrap()).unwrap(),
+///     r#"["notification_from_execution",{"contract":"0x0101010101010101010101010101010101010101","name":"Transfer"}]"#
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Subscription {
+	BlockAdded(BlockFilter),
+	HeaderOfAddedBlock(BlockFilter),
+	TransactionAdded(TransactionFilter),
+	NotificationFromExecution(NotificationFilter),
+	TransactionExecuted(ExecutionFilter),
+	NotaryRequestEvent(NotaryRequestFilter),
+}
+
+impl Subscription {
+	pub fn event(&self) -> SubscriptionEvent {
+		match self {
+			Self::BlockAdded(_) => SubscriptionEvent::BlockAdded,
+			Self::HeaderOfAddedBlock(_) => SubscriptionEvent::HeaderOfAddedBlock,
+			Self::TransactionAdded(_) => SubscriptionEvent::TransactionAdded,
+			Self::NotificationFromExecution(_) => SubscriptionEvent::NotificationFromExecution,
+			Self::TransactionExecuted(_) => SubscriptionEvent::TransactionExecuted,
+			Self::NotaryRequestEvent(_) => SubscriptionEvent::NotaryRequestEvent,
+		}
+	}
+
+	/// The parameters of the `subscribe` request. The filter is left out when it is empty.
+	pub fn params(&self) -> Result<Vec<Value>, serde_json::Error> {
+		let filter = match self {
+			Self::BlockAdded(filter) | Self::HeaderOfAddedBlock(filter) =>
+				serde_json::to_value(filter)?,
+			Self::TransactionAdded(filter) => serde_json::to_value(filter)?,
+			Self::NotificationFromExecution(filter) => serde_json::to_value(filter)?,
+			Self::TransactionExecuted(filter) => serde_json::to_value(filter)?,
+			Self::NotaryRequestEvent(filter) => serde_json::to_value(filter)?,
+		};
+		let mut params = vec![serde_json::to_value(self.event())?];
+		if filter.as_object().is_none_or(|filter| !filter.is_empty()) {
+			params.push(filter);
+		}
+		Ok(params)
+	}
+
+	/// Parses the parameters of a `subscribe` request.
+	pub fn from_params(params: &[Value]) -> Result<Self, serde_json::Error> {
+		let event = serde_json::from_value(params.first().cloned().unwrap_or_default())?;
+		let filter = params.get(1).cloned().unwrap_or_else(|| Value::Object(Default::default()));
+		Ok(match event {
+			SubscriptionEvent::BlockAdded => Self::BlockAdded(serde_json::from_value(filter)?),
+			SubscriptionEvent::HeaderOfAddedBlock =>
+				Self::HeaderOfAddedBlock(serde_json::from_value(filter)?),
+			SubscriptionEvent::TransactionAdded =>
+				Self::TransactionAdded(serde_json::from_value(filter)?),
+			SubscriptionEvent::NotificationFromExecution =>
+				Self::NotificationFromExecution(serde_json::from_value(filter)?),
+			SubscriptionEvent::TransactionExecuted =>
+				Self::TransactionExecuted(serde_json::from_value(filter)?),
+			SubscriptionEvent::NotaryRequestEvent =>
+				Self::NotaryRequestEvent(serde_json::from_value(filter)?),
+		})
+	}
+
+	/// Whether the event `payload` passes the filter of the subscription.
+	///
+	/// The node sends the events matching any of the subscriptions of a connection to all of
+	/// them, so the transports check each event against the filter of each subscription.
+	pub fn matches(&self, payload: &RawValue) -> bool {
+		match self {
+			Self::BlockAdded(filter) | Self::HeaderOfAddedBlock(filter) =>
+				check(filter, payload, BlockFilter::matches),
+			Self::TransactionAdded(filter) => check(filter, payload, TransactionFilter::matches),
+			Self::NotificationFromExecution(filter) =>
+				check(filter, payload, NotificationFilter::matches),
+			Self::TransactionExecuted(filter) => check(filter, payload, ExecutionFilter::matches),
+			Self::NotaryRequestEvent(filter) =>
+				check(filter, payload, NotaryRequestFilter::matches),
+		}
+	}
+}
+
+/// Checks `payload` against `filter`, without parsing it when the filter is empty.
+///
+/// A payload that can't be parsed passes, so that its subscriptions get it like those without
+/// a filter do, instead of missing it silently.
+fn check<F, T>(filter: &F, payload: &RawValue, matches: fn(&F, &T) -> bool) -> bool
+where
+	F: Default + PartialEq,
+	T: DeserializeOwned,
+{
+	if *filter == F::default() {
+		return true;
+	}
+	match serde_json::from_str(payload.get()) {
+		Ok(payload) => matches(filter, &payload),
+		Err(err) => {
+			tracing::warn!(%err, "Forwarding a notification that can't be filtered");
+			true
+		},
+	}
+}
+
+/// A notification emitted by a contract, streamed by `notification_from_execution`
+/// subscriptions.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct NotificationRecord {
+	/// The hash of the transaction or block that emitted the notification.
+	#[serde(serialize_with = "serialize_h256", deserialize_with = "deserialize_h256")]
+	pub container: H256,
+	#[serde(serialize_with = "serialize_h160", deserialize_with = "deserialize_script_hash")]
+	pub contract: H160,
+	#[serde(rename = "eventname")]
+	pub event_name: String,
+	pub state: StackItem,
+}
+
+impl From<NotificationRecord> for LogNotification {
+	fn from(record: NotificationRecord) -> Self {
+		LogNotification::new(record.contract, record.event_name, record.state)
+	}
+}
+
+/// The result of an execution, streamed by `transaction_executed` subscriptions.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ExecutionRecord {
+	/// The hash of the transaction, or of the block for the `OnPersist` and `PostPersist`
+	/// triggers.
+	#[serde(serialize_with = "serialize_h256", deserialize_with = "deserialize_h256")]
+	pub container: H256,
+	#[serde(flatten)]
+	pub execution: Execution,
+}
+
+impl From<ExecutionRecord> for ApplicationLog {
+	fn from(record: ExecutionRecord) -> Self {
+		ApplicationLog { transaction_id: record.container, executions: vec![record.execution] }
+	}
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum NotaryRequestEventType {
+	Added,
+	Removed,
+}
+
+/// A change of the notary pool, streamed by `notary_request_event` subscriptions.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct NotaryRequestRecord {
+	#[serde(rename = "type")]
+	pub event_type: NotaryRequestEventType,
+	#[serde(rename = "notaryrequest")]
+	pub notary_request: NotaryRequest,
+}
+
+/// A transaction waiting for the signatures of the notary service, with the fallback
+/// transaction sent instead when it isn't completed in time.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct NotaryRequest {
+	#[serde(rename = "maintx")]
+	pub main_transaction: RTransaction,
+	#[serde(rename = "fallbacktx")]
+	pub fallback_transaction: RTransaction,
+	pub witness: NeoWitness,
+}
+
+impl<P: PubsubClient> RpcClient<P> {
+	/// Subscribes to the events of `subscription` and streams their payloads as `R`.
+	///
+	/// The node must support the `subscribe` method of neo-go. The subscription is cancelled
+	/// when the stream is dropped.
+	pub async fn subscribe<R: DeserializeOwned>(
+		&self,
+		subscription: Subscription,
+	) -> Result<SubscriptionStream<'_, P, R>, ProviderError> {
+		let id: U256 = self.request("subscribe", subscription.params()?).await?;
+		SubscriptionStream::new(id, self).map_err(Into::into)
+	}
+
+	/// Streams the blocks added to the chain.
+	///
+	/// # Examples
+	///
+	/// ```no_run
+	/// use futures_util::StreamExt;
+	/// use neo3::neo_clients::{BlockFilter, PubsubClient, RpcClient};
+	///
+	/// # async fn example<P: PubsubClient>(
+	/// #     client: RpcClient<P>,
+	/// # ) -> Result<(), Box<dyn std::error::Error>> {
+	/// let mut blocks = client.subscribe_blocks(BlockFilter::new().since(1000)).await?;
+	/// while let Some(block) = blocks.next().await {
+	///     println!("Block {} with {:?} transactions", block.index, block.transactions);
+	/// }
+	/// # Ok(())
+	/// # }
+	/// ```
+	pub async fn subscribe_blocks(
+		&self,
+		filter: BlockFilter,
+	) -> Result<SubscriptionStream<'_, P, NeoBlock>, ProviderError> {
+		self.subscribe(Subscription::BlockAdded(filter)).await
+	}
+
+	/// Streams the headers of the blocks added to the chain.
+	pub async fn subscribe_headers(
+		&self,
+		filter: BlockFilter,
+	) -> Result<SubscriptionStream<'_, P, NeoBlock>, ProviderError> {
+		self.subscribe(Subscription::HeaderOfAddedBlock(filter)).await
+	}
+
+	/// Streams the transactions added to the memory pool.
+	pub async fn subscribe_transactions(
+		&self,
+		filter: TransactionFilter,
+	) -> Result<SubscriptionStream<'_, P, RTransaction>, ProviderError> {
+		self.subscribe(Subscription::TransactionAdded(filter)).await
+	}
+
+	/// Streams the notifications emitted by contracts.
+	pub async fn subscribe_notifications(
+		&self,
+		filter: NotificationFilter,
+	) -> Result<SubscriptionStream<'_, P, NotificationRecord>, ProviderError> {
+		self.subscribe(Subscription::NotificationFromExecution(filter)).await
+	}
+
+	/// Streams the results of executions. Each record converts into the [`ApplicationLog`] of
+	/// its execution.
+	pub async fn subscribe_executions(
+		&self,
+		filter: ExecutionFilter,
+	) -> Result<SubscriptionStream<'_, P, ExecutionRecord>, ProviderError> {
+		self.subscribe(Subscription::TransactionExecuted(filter)).await
+	}
+
+	/// Streams the changes of the notary pool.
+	pub async fn subscribe_notary_requests(
+		&self,
+		filter: NotaryRequestFilter,
+	) -> Result<SubscriptionStream<'_, P, NotaryRequestRecord>, ProviderError> {
+		self.subscribe(Subscription::NotaryRequestEvent(filter)).await
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json::json;
+
+	use super::*;
+
+	fn raw(value: Value) -> Box<RawValue> {
+		serde_json::value::to_raw_value(&value).unwrap()
+	}
+
+	fn block(index: u32, primary: u8) -> Value {
+		json!({
+			"hash": "0x239fea00c54c2f6812612874183b72bef4473fcdf68bf8da08d74fd5b6cab030",
+			"size": 1641,
+			"version": 0,
+			"previousblockhash": "0x04f7580b111ec75f0ce68d3a9fd70a0544b4521b4a98541694d8575c548b759e",
+			"merkleroot": "0x6b9ad1a9c2ea2ca1d4f3c1bcdb3e16f1c3bbb7de6b99b1e2d1dc03cd0ac2f8b4",
+			"time": 1616055166,
+			"nonce": "A3A4ED2C8C6A1AEF",
+			"index": index,
+			"primary": primary,
+			"nextconsensus": "NgPkjjLTNcQad99iRYeXRUuowE4gxLAnDL",
+			"witnesses": [{"invocation": "DEA=", "verification": "EQ=="}],
+			"tx": []
+		})
+	}
+
+	#[test]
+	fn test_params() {
+		let subscription = Subscription::BlockAdded(BlockFilter::new().primary(2).since(10));
+		let params = subscription.params().unwrap();
+		assert_eq!(params, vec![json!("block_added"), json!({"primary": 2, "since": 10})]);
+		assert_eq!(Subscription::from_params(&params).unwrap(), subscription);
+
+		let subscription = Subscription::TransactionExecuted(ExecutionFilter::new());
+		let params = subscription.params().unwrap();
+		assert_eq!(params, vec![json!("transaction_executed")]);
+		assert_eq!(Subscription::from_params(&params).unwrap(), subscription);
+
+		let subscription = Subscription::NotaryRequestEvent(
+			NotaryRequestFilter::new()
+				.signer(H160::repeat_byte(0xab))
+				.event_type(NotaryRequestEventType::Removed),
+		);
+		let params = subscription.params().unwrap();
+		assert_eq!(
+			params[1],
+			json!({"signer": "0xabababababababababababababababababababab", "type": "removed"})
+		);
+		assert_eq!(Subscription::from_params(&params).unwrap(), subscription);
+
+		assert!(Subscription::from_params(&[json!("unknown_event")]).is_err());
+	}
+
+	#[test]
+	fn test_block_filter() {
+		let payload = raw(block(1000, 3));
+		let parsed: NeoBlock = serde_json::from_str(payload.get()).unwrap();
+		assert_eq!(parsed.index, 1000);
+
+		let matches = |filter| Subscription::BlockAdded(filter).matches(&payload);
+		assert!(matches(BlockFilter::new()));
+		assert!(matches(BlockFilter::new().primary(3).since(1000).till(1000)));
+		assert!(!matches(BlockFilter::new().primary(2)));
+		assert!(!matches(BlockFilter::new().since(1001)));
+		assert!(!matches(BlockFilter::new().till(999)));
+
+		let unknown = raw(json!({"index": "unknown"}));
+		assert!(Subscription::BlockAdded(BlockFilter::new().primary(2)).matches(&unknown));
+	}
+
+	#[test]
+	fn test_notification_filter() {
+		let payload = raw(json!({
+			"container": "0x2f7e2e8b6ddb2e5b9d2d6a2c1b0c4a6c9f6b1e4d3c2b1a0f9e8d7c6b5a493827",
+			"contract": "0xd2a4cff31913016155e38e474a2c06d08be276cf",
+			"eventname": "Transfer",
+			"state": {"type": "Array", "value": [{"type": "Integer", "value": "100"}]}
+		}));
+		let record: NotificationRecord = serde_json::from_str(payload.get()).unwrap();
+		let gas = H160::from_slice(&hex::decode("d2a4cff31913016155e38e474a2c06d08be276cf").unwrap());
+		assert_eq!(record.contract, gas);
+		assert_eq!(LogNotification::from(record).event_name, "Transfer");
+
+		let matches = |filter| Subscription::NotificationFromExecution(filter).matches(&payload);
+		assert!(matches(NotificationFilter::new().contract(gas).name("Transfer")));
+		assert!(!matches(NotificationFilter::new().name("Burn")));
+		assert!(!matches(NotificationFilter::new().contract(H160::zero())));
+	}
+
+	#[test]
+	fn test_execution_record() {
+		let container = "0x2f7e2e8b6ddb2e5b9d2d6a2c1b0c4a6c9f6b1e4d3c2b1a0f9e8d7c6b5a493827";
+		let payload = raw(json!({
+			"container": container,
+			"trigger": "Application",
+			"vmstate": "FAULT",
+			"exception": "ABORT is executed",
+			"gasconsumed": "2031260",
+			"stack": [],
+			"notifications": []
+		}));
+
+		let matches = |filter| Subscription::TransactionExecuted(filter).matches(&payload);
+		assert!(matches(ExecutionFilter::new().state(VMState::Fault)));
+		assert!(!matches(ExecutionFilter::new().state(VMState::Halt)));
+		assert!(!matches(ExecutionFilter::new().container(H256::zero())));
+
+		let record: ExecutionRecord = serde_json::from_str(payload.get()).unwrap();
+		let log = ApplicationLog::from(record);
+		assert_eq!(format!("{:?}", log.transaction_id), container);
+		assert_eq!(log.get_first_execution().unwrap().state, VMState::Fault);
+	}
+}
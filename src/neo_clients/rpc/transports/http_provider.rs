@@ -7,11 +7,12 @@ use http::HeaderValue;
 use log::debug;
 use reqwest::{header, Client, Error as ReqwestError};
 use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
 use thiserror::Error;
 use url::Url;
 
 use super::common::{JsonRpcError, Request, Response};
-use crate::neo_clients::{Authorization, JsonRpcProvider, ProviderError};
+use crate::neo_clients::{Authorization, JsonRpcProvider, ProviderError, RpcCall};
 use neo3::config::NeoConstants;
 
 /// A low-level JSON-RPC Client over HTTP.
@@ -109,6 +110,64 @@ impl JsonRpcProvider for HttpProvider {
 
 		Ok(res)
 	}
+
+	async fn fetch_batch(
+		&self,
+		calls: &[RpcCall],
+	) -> Result<Vec<Result<Value, ProviderError>>, ClientError> {
+		// An empty batch is an invalid request
+		if calls.is_empty() {
+			return Ok(vec![]);
+		}
+		let first_id = self.id.fetch_add(calls.len() as u64, Ordering::SeqCst);
+		let payload: Vec<_> = calls
+			.iter()
+			.zip(first_id..)
+			.map(|(call, id)| Request::new(id, &call.method, &call.params))
+			.collect();
+
+		let res = self.client.post(self.url.as_ref()).json(&payload).send().await?;
+		let body = res.bytes().await?;
+
+		let responses: Vec<Response> = match serde_json::from_slice(&body) {
+			Ok(responses) => responses,
+			Err(err) => {
+				// The node answers a batch it can't process with a single error
+				if let Ok(Response::Error { error, .. }) = serde_json::from_slice(&body) {
+					return Err(error.into());
+				}
+				return Err(ClientError::SerdeJson {
+					err,
+					text: String::from_utf8_lossy(&body).to_string(),
+				});
+			},
+		};
+
+		// The responses of a batch can come in any order
+		let mut results: Vec<Option<Result<Value, ProviderError>>> = vec![None; calls.len()];
+		for response in responses {
+			let (id, result) = match response {
+				Response::Success { id, result } =>
+					(id, serde_json::from_str(result.get()).map_err(Into::into)),
+				Response::Error { id, error } => (id, Err(error.into())),
+				Response::Notification { .. } => continue,
+			};
+			let index = id.checked_sub(first_id).map(|index| index as usize);
+			if let Some(slot) = index.and_then(|index| results.get_mut(index)) {
+				*slot = Some(result);
+			}
+		}
+
+		Ok(results
+			.into_iter()
+			.zip(first_id..)
+			.map(|(result, id)| {
+				result.unwrap_or_else(|| {
+					Err(ProviderError::CustomError(format!("No response for request {}", id)))
+				})
+			})
+			.collect())
+	}
 }
 
 impl Default for HttpProvider {
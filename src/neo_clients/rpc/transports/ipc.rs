@@ -28,7 +28,9 @@ use tokio::{
 
 use hashers::fx_hash::FxHasher64;
 
-use crate::neo_clients::{JsonRpcProvider, ProviderError, PubsubClient, RpcError};
+use crate::neo_clients::{
+	pubsub_notifications, JsonRpcProvider, Notifications, ProviderError, PubsubClient, RpcError,
+};
 
 use super::common::{JsonRpcError, Params, Request, Response};
 
@@ -313,6 +315,14 @@ impl JsonRpcProvider for Ipc {
 		// Parse JSON response.
 		Ok(serde_json::from_str(res.get())?)
 	}
+
+	fn supports_subscriptions(&self) -> bool {
+		true
+	}
+
+	fn notifications(&self, id: U256) -> Option<Result<Notifications, IpcError>> {
+		Some(pubsub_notifications(self, id))
+	}
 }
 
 impl PubsubClient for Ipc {
@@ -20,8 +20,9 @@ use thiserror::Error;
 use tracing::trace;
 
 use crate::neo_clients::{
+	pubsub_notifications,
 	rpc::transports::common::{JsonRpcError, Params, Request, Response},
-	JsonRpcProvider, ProviderError, PubsubClient, RpcClient, RpcError,
+	JsonRpcProvider, Notifications, ProviderError, PubsubClient, RpcClient, RpcError,
 };
 
 #[allow(unused_macros)]
@@ -207,6 +208,14 @@ impl JsonRpcProvider for Ws {
 		// parse it
 		Ok(serde_json::from_str(res.get())?)
 	}
+
+	fn supports_subscriptions(&self) -> bool {
+		true
+	}
+
+	fn notifications(&self, id: U256) -> Option<Result<Notifications, ClientError>> {
+		Some(pubsub_notifications(self, id))
+	}
 }
 
 impl PubsubClient for Ws {
@@ -0,0 +1,637 @@
+//! [JsonRpcProvider] wrappers adding rate limiting, circuit breaking, caching and metrics to any
+//! provider, and a provider sending its requests through a [ConnectionPool].
+//!
+//! Each wrapper is a provider itself, so they stack around each other and around a
+//! [RetryClient](super::RetryClient), and the stack keeps the typed
+//! [`APITrait`](crate::neo_clients::APITrait) of the [`RpcClient`](crate::neo_clients::RpcClient)
+//! built on it. Batches sent with `fetch_batch` are forwarded as batches.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use neo3::neo_clients::{
+//!     APITrait, CacheConfig, CachedClient, CircuitBreakerClient, CircuitBreakerConfig,
+//!     HttpProvider, HttpRateLimitRetryPolicy, MetricsClient, RateLimitedClient, RateLimiter,
+//!     RetryClientBuilder, RpcClient,
+//! };
+//! use std::time::Duration;
+//!
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let http = HttpProvider::new("https://testnet1.neo.org:443")?;
+//! let retry =
+//!     RetryClientBuilder::default().build(http, Box::new(HttpRateLimitRetryPolicy::default()));
+//! let limiter = RateLimiter::new(50, Duration::from_secs(1), 10);
+//! let rate_limited = RateLimitedClient::new(retry, limiter);
+//! let breaker = CircuitBreakerClient::new(rate_limited, CircuitBreakerConfig::default());
+//! let cached = CachedClient::new(breaker, CacheConfig::default());
+//! let client = RpcClient::new(MetricsClient::new(cached));
+//!
+//! println!("Block count: {}", client.get_block_count().await?);
+//! println!("{:?}", client.as_ref().total());
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+	collections::HashMap,
+	fmt::{self, Debug},
+	sync::Mutex,
+	time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use primitive_types::U256;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use tracing::debug;
+
+use crate::{
+	neo_clients::{
+		CacheConfig, CircuitBreaker, CircuitBreakerConfig, ConnectionPool, JsonRpcProvider,
+		Notifications, PoolConfig, PoolStats, ProviderError, RateLimiter, RpcCache, RpcCall,
+	},
+	neo_error::{Neo3Error, NetworkError},
+};
+
+/// A provider that waits for a [RateLimiter] permit before each request.
+///
+/// A batch takes as many tokens as it has calls.
+pub struct RateLimitedClient<T> {
+	inner: T,
+	limiter: RateLimiter,
+}
+
+impl<T> RateLimitedClient<T> {
+	pub fn new(inner: T, limiter: RateLimiter) -> Self {
+		Self { inner, limiter }
+	}
+
+	pub fn limiter(&self) -> &RateLimiter {
+		&self.limiter
+	}
+
+	pub fn inner(&self) -> &T {
+		&self.inner
+	}
+}
+
+impl<T: Debug> Debug for RateLimitedClient<T> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("RateLimitedClient")
+			.field("inner", &self.inner)
+			.finish_non_exhaustive()
+	}
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(? Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl<T: JsonRpcProvider> JsonRpcProvider for RateLimitedClient<T> {
+	type Error = ProviderError;
+
+	async fn fetch<P, R>(&self, method: &str, params: P) -> Result<R, ProviderError>
+	where
+		P: Debug + Serialize + Send + Sync,
+		R: DeserializeOwned + Send,
+	{
+		let _permit = self.limiter.acquire().await?;
+		self.inner.fetch(method, params).await.map_err(Into::into)
+	}
+
+	async fn fetch_batch(
+		&self,
+		calls: &[RpcCall],
+	) -> Result<Vec<Result<Value, ProviderError>>, ProviderError> {
+		let count = u32::try_from(calls.len()).unwrap_or(u32::MAX);
+		let _permit = self.limiter.acquire_many(count).await?;
+		self.inner.fetch_batch(calls).await.map_err(Into::into)
+	}
+
+	fn supports_subscriptions(&self) -> bool {
+		self.inner.supports_subscriptions()
+	}
+
+	fn notifications(&self, id: U256) -> Option<Result<Notifications, ProviderError>> {
+		self.inner.notifications(id).map(|notifications| notifications.map_err(Into::into))
+	}
+}
+
+/// A provider that sends its requests through a [CircuitBreaker].
+///
+/// Transport errors count as failures of the node, JSON-RPC errors are answers of a healthy node
+/// and don't. While the circuit is open, requests fail with [ProviderError::CircuitOpen] without
+/// reaching the inner provider.
+pub struct CircuitBreakerClient<T> {
+	inner: T,
+	breaker: CircuitBreaker,
+}
+
+impl<T> CircuitBreakerClient<T> {
+	pub fn new(inner: T, config: CircuitBreakerConfig) -> Self {
+		Self { inner, breaker: CircuitBreaker::new(config) }
+	}
+
+	/// The circuit breaker, to get its state and statistics or to reset it.
+	pub fn breaker(&self) -> &CircuitBreaker {
+		&self.breaker
+	}
+
+	pub fn inner(&self) -> &T {
+		&self.inner
+	}
+}
+
+impl<T: Debug> Debug for CircuitBreakerClient<T> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("CircuitBreakerClient")
+			.field("inner", &self.inner)
+			.finish_non_exhaustive()
+	}
+}
+
+fn is_node_failure(err: &ProviderError) -> bool {
+	!matches!(err, ProviderError::JsonRpcError(_))
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(? Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl<T: JsonRpcProvider> JsonRpcProvider for CircuitBreakerClient<T> {
+	type Error = ProviderError;
+
+	async fn fetch<P, R>(&self, method: &str, params: P) -> Result<R, ProviderError>
+	where
+		P: Debug + Serialize + Send + Sync,
+		R: DeserializeOwned + Send,
+	{
+		// The result is deserialized outside of the breaker, a caller asking for the wrong type
+		// doesn't open the circuit
+		let value: Value = self
+			.breaker
+			.call_with(
+				async { self.inner.fetch(method, params).await.map_err(Into::into) },
+				is_node_failure,
+				|| ProviderError::CircuitOpen,
+			)
+			.await?;
+		Ok(serde_json::from_value(value)?)
+	}
+
+	async fn fetch_batch(
+		&self,
+		calls: &[RpcCall],
+	) -> Result<Vec<Result<Value, ProviderError>>, ProviderError> {
+		self.breaker
+			.call_with(
+				async { self.inner.fetch_batch(calls).await.map_err(Into::into) },
+				is_node_failure,
+				|| ProviderError::CircuitOpen,
+			)
+			.await
+	}
+
+	fn supports_subscriptions(&self) -> bool {
+		self.inner.supports_subscriptions()
+	}
+
+	fn notifications(&self, id: U256) -> Option<Result<Notifications, ProviderError>> {
+		self.inner.notifications(id).map(|notifications| notifications.map_err(Into::into))
+	}
+}
+
+/// A provider that caches the results of the calls that can be cached.
+///
+/// Calls are cached by method and parameters, for the time given by [RpcCache::method_ttl]
+/// unless [with_method_ttl](Self::with_method_ttl) overrides it. Errors and batches are never
+/// cached.
+///
+/// The block count, and the transactions and application logs that may not be confirmed yet,
+/// are not cached by default: the typed API polls them to wait for new blocks and transactions.
+pub struct CachedClient<T> {
+	inner: T,
+	cache: RpcCache,
+	default_ttl: Duration,
+	method_ttls: HashMap<String, Option<Duration>>,
+}
+
+impl<T> CachedClient<T> {
+	pub fn new(inner: T, config: CacheConfig) -> Self {
+		Self {
+			inner,
+			default_ttl: config.default_ttl,
+			cache: RpcCache::new(config),
+			method_ttls: HashMap::new(),
+		}
+	}
+
+	/// Caches the results of `method` for `ttl`, or never with `None`.
+	pub fn with_method_ttl(mut self, method: &str, ttl: Option<Duration>) -> Self {
+		self.method_ttls.insert(method.to_string(), ttl);
+		self
+	}
+
+	/// The cache, to get its statistics or to clear it.
+	pub fn cache(&self) -> &RpcCache {
+		&self.cache
+	}
+
+	pub fn inner(&self) -> &T {
+		&self.inner
+	}
+
+	fn ttl(&self, method: &str) -> Option<Duration> {
+		match self.method_ttls.get(method) {
+			Some(ttl) => *ttl,
+			None if is_polled(method) => None,
+			None => RpcCache::method_ttl(method, self.default_ttl),
+		}
+	}
+}
+
+/// Whether the result of `method` changes while the typed API waits for it.
+fn is_polled(method: &str) -> bool {
+	matches!(method, "getblockcount" | "getrawtransaction" | "getapplicationlog")
+}
+
+impl<T: Debug> Debug for CachedClient<T> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("CachedClient")
+			.field("inner", &self.inner)
+			.field("default_ttl", &self.default_ttl)
+			.field("method_ttls", &self.method_ttls)
+			.finish_non_exhaustive()
+	}
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(? Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl<T: JsonRpcProvider> JsonRpcProvider for CachedClient<T> {
+	type Error = ProviderError;
+
+	async fn fetch<P, R>(&self, method: &str, params: P) -> Result<R, ProviderError>
+	where
+		P: Debug + Serialize + Send + Sync,
+		R: DeserializeOwned + Send,
+	{
+		let ttl = match self.ttl(method) {
+			Some(ttl) => ttl,
+			None => return self.inner.fetch(method, params).await.map_err(Into::into),
+		};
+		let key = format!("{}:{}", method, serde_json::to_string(&params)?);
+		if let Some(value) = self.cache.get(&key).await {
+			return Ok(serde_json::from_value(value)?);
+		}
+
+		let value: Value = self.inner.fetch(method, params).await.map_err(Into::into)?;
+		self.cache.insert_with_ttl(key, value.clone(), ttl).await;
+		Ok(serde_json::from_value(value)?)
+	}
+
+	async fn fetch_batch(
+		&self,
+		calls: &[RpcCall],
+	) -> Result<Vec<Result<Value, ProviderError>>, ProviderError> {
+		self.inner.fetch_batch(calls).await.map_err(Into::into)
+	}
+
+	fn supports_subscriptions(&self) -> bool {
+		self.inner.supports_subscriptions()
+	}
+
+	fn notifications(&self, id: U256) -> Option<Result<Notifications, ProviderError>> {
+		self.inner.notifications(id).map(|notifications| notifications.map_err(Into::into))
+	}
+}
+
+/// A provider that sends its requests through a [ConnectionPool].
+///
+/// The pool limits the requests in flight to [max_connections](PoolConfig::max_connections),
+/// fails them after [request_timeout](PoolConfig::request_timeout) and retries those that
+/// didn't reach the node. JSON-RPC errors are answers of the node and are returned as they are.
+pub struct PooledClient {
+	pool: ConnectionPool,
+}
+
+impl PooledClient {
+	pub fn new(endpoint: impl Into<String>, config: PoolConfig) -> Self {
+		Self { pool: ConnectionPool::new(endpoint.into(), config) }
+	}
+
+	/// The connection pool, to check the health of its connections or to close them.
+	pub fn pool(&self) -> &ConnectionPool {
+		&self.pool
+	}
+
+	pub async fn stats(&self) -> PoolStats {
+		self.pool.get_stats().await
+	}
+}
+
+impl Debug for PooledClient {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("PooledClient").finish_non_exhaustive()
+	}
+}
+
+/// Splits the answers of the node from the failures the pool retries.
+fn pool_result<T>(
+	result: Result<T, impl Into<ProviderError>>,
+) -> Result<Result<T, ProviderError>, Neo3Error> {
+	match result.map_err(Into::into) {
+		Err(err) if is_node_failure(&err) =>
+			Err(Neo3Error::Network(NetworkError::ConnectionFailed(err.to_string()))),
+		result => Ok(result),
+	}
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(? Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl JsonRpcProvider for PooledClient {
+	type Error = ProviderError;
+
+	async fn fetch<P, R>(&self, method: &str, params: P) -> Result<R, ProviderError>
+	where
+		P: Debug + Serialize + Send + Sync,
+		R: DeserializeOwned + Send,
+	{
+		// The pool may send the request several times
+		let params = serde_json::to_value(params)?;
+		let value: Value = self
+			.pool
+			.execute(|client| {
+				let (method, params) = (method.to_string(), params.clone());
+				Box::pin(async move { pool_result(client.as_ref().fetch(&method, params).await) })
+			})
+			.await??;
+		Ok(serde_json::from_value(value)?)
+	}
+
+	async fn fetch_batch(
+		&self,
+		calls: &[RpcCall],
+	) -> Result<Vec<Result<Value, ProviderError>>, ProviderError> {
+		self.pool
+			.execute(|client| {
+				let calls = calls.to_vec();
+				Box::pin(async move { pool_result(client.as_ref().fetch_batch(&calls).await) })
+			})
+			.await?
+	}
+}
+
+/// Request statistics of a method.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RequestMetrics {
+	pub requests: u64,
+	pub failures: u64,
+	pub total_duration: Duration,
+	pub max_duration: Duration,
+}
+
+impl RequestMetrics {
+	pub fn average_duration(&self) -> Duration {
+		match u32::try_from(self.requests) {
+			Ok(0) => Duration::ZERO,
+			Ok(requests) => self.total_duration / requests,
+			Err(_) => self.total_duration.div_f64(self.requests as f64),
+		}
+	}
+
+	pub fn failure_rate(&self) -> f64 {
+		if self.requests == 0 {
+			0.0
+		} else {
+			self.failures as f64 / self.requests as f64
+		}
+	}
+
+	fn record(&mut self, duration: Duration, failed: bool) {
+		self.requests += 1;
+		if failed {
+			self.failures += 1;
+		}
+		self.total_duration += duration;
+		self.max_duration = self.max_duration.max(duration);
+	}
+
+	fn merge(&mut self, other: &RequestMetrics) {
+		self.requests += other.requests;
+		self.failures += other.failures;
+		self.total_duration += other.total_duration;
+		self.max_duration = self.max_duration.max(other.max_duration);
+	}
+}
+
+/// A provider that records the number, failures and durations of the requests, by method.
+///
+/// Each call of a batch is recorded under its method, with the duration of the whole batch.
+#[derive(Debug)]
+pub struct MetricsClient<T> {
+	inner: T,
+	metrics: Mutex<HashMap<String, RequestMetrics>>,
+}
+
+impl<T> MetricsClient<T> {
+	pub fn new(inner: T) -> Self {
+		Self { inner, metrics: Mutex::new(HashMap::new()) }
+	}
+
+	/// The statistics of each method called.
+	pub fn metrics(&self) -> HashMap<String, RequestMetrics> {
+		self.lock().clone()
+	}
+
+	/// The statistics of `method`, if it was called.
+	pub fn method_metrics(&self, method: &str) -> Option<RequestMetrics> {
+		self.lock().get(method).cloned()
+	}
+
+	/// The statistics of all methods together.
+	pub fn total(&self) -> RequestMetrics {
+		self.lock().values().fold(RequestMetrics::default(), |mut total, metrics| {
+			total.merge(metrics);
+			total
+		})
+	}
+
+	pub fn reset(&self) {
+		self.lock().clear();
+	}
+
+	pub fn inner(&self) -> &T {
+		&self.inner
+	}
+
+	fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, RequestMetrics>> {
+		self.metrics.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+	}
+
+	fn record(&self, method: &str, duration: Duration, failed: bool) {
+		debug!("{} completed in {:?}, failed: {}", method, duration, failed);
+		self.lock().entry(method.to_string()).or_default().record(duration, failed);
+	}
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(? Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl<T: JsonRpcProvider> JsonRpcProvider for MetricsClient<T> {
+	type Error = ProviderError;
+
+	async fn fetch<P, R>(&self, method: &str, params: P) -> Result<R, ProviderError>
+	where
+		P: Debug + Serialize + Send + Sync,
+		R: DeserializeOwned + Send,
+	{
+		let start = Instant::now();
+		let result = self.inner.fetch(method, params).await.map_err(Into::into);
+		self.record(method, start.elapsed(), result.is_err());
+		result
+	}
+
+	async fn fetch_batch(
+		&self,
+		calls: &[RpcCall],
+	) -> Result<Vec<Result<Value, ProviderError>>, ProviderError> {
+		let start = Instant::now();
+		let results = self.inner.fetch_batch(calls).await.map_err(Into::into);
+		let duration = start.elapsed();
+		for (index, call) in calls.iter().enumerate() {
+			let failed = match &results {
+				Ok(results) => results.get(index).is_none_or(|result| result.is_err()),
+				Err(_) => true,
+			};
+			self.record(&call.method, duration, failed);
+		}
+		results
+	}
+
+	fn supports_subscriptions(&self) -> bool {
+		self.inner.supports_subscriptions()
+	}
+
+	fn notifications(&self, id: U256) -> Option<Result<Notifications, ProviderError>> {
+		self.inner.notifications(id).map(|notifications| notifications.map_err(Into::into))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json::json;
+	use wiremock::{matchers::method, Mock, MockServer, Request, ResponseTemplate};
+
+	use super::*;
+	use crate::neo_clients::{APITrait, CircuitState, HttpProvider, JsonRpcError, RpcClient};
+
+	/// A node answering `getblockcount` and failing every other method.
+	async fn server(expected_requests: u64) -> MockServer {
+		let server = MockServer::start().await;
+		Mock::given(method("POST"))
+			.respond_with(|request: &Request| {
+				let call: Value = serde_json::from_slice(&request.body).unwrap();
+				let response = match call["method"].as_str().unwrap() {
+					"getblockcount" => json!({"jsonrpc": "2.0", "id": call["id"], "result": 100}),
+					_ => json!({
+						"jsonrpc": "2.0",
+						"id": call["id"],
+						"error": {"code": -32601, "message": "Method not found"}
+					}),
+				};
+				ResponseTemplate::new(200).set_body_json(response)
+			})
+			.expect(expected_requests)
+			.mount(&server)
+			.await;
+		server
+	}
+
+	fn http(server: &MockServer) -> HttpProvider {
+		HttpProvider::new(server.uri().as_str()).unwrap()
+	}
+
+	#[tokio::test]
+	async fn test_stack() {
+		let server = server(2).await;
+		let stack = PooledClient::new(server.uri(), PoolConfig::default());
+		let limiter = RateLimiter::new(100, Duration::from_secs(1), 10);
+		let stack = RateLimitedClient::new(stack, limiter);
+		let stack = CircuitBreakerClient::new(stack, CircuitBreakerConfig::default());
+		let stack = CachedClient::new(stack, CacheConfig::default())
+			.with_method_ttl("getblockcount", Some(Duration::from_secs(5)));
+		let client = RpcClient::new(MetricsClient::new(stack));
+
+		// The second call is served from the cache
+		assert_eq!(client.get_block_count().await.unwrap(), 100);
+		assert_eq!(client.get_block_count().await.unwrap(), 100);
+		assert!(matches!(
+			client.get_block_header_count().await,
+			Err(ProviderError::JsonRpcError(JsonRpcError { code: -32601, .. }))
+		));
+
+		let metrics = client.as_ref();
+		assert_eq!(metrics.method_metrics("getblockcount").unwrap().requests, 2);
+		assert_eq!(metrics.method_metrics("getblockheadercount").unwrap().failures, 1);
+		assert_eq!(metrics.total().requests, 3);
+		assert_eq!(metrics.total().failures, 1);
+		metrics.reset();
+		assert_eq!(metrics.total(), RequestMetrics::default());
+
+		// JSON-RPC errors are not retried by the pool
+		let stats = metrics.inner().inner().inner().inner().stats().await;
+		assert_eq!((stats.total_requests, stats.retried_requests), (2, 0));
+	}
+
+	#[tokio::test]
+	async fn test_pool_retries_unreachable_nodes() {
+		let config = PoolConfig {
+			max_retries: 1,
+			retry_delay: Duration::from_millis(1),
+			..Default::default()
+		};
+		let client = RpcClient::new(PooledClient::new("http://localhost:1", config));
+		assert!(matches!(client.get_block_count().await, Err(ProviderError::Neo3Error(_))));
+		assert_eq!(client.as_ref().stats().await.retried_requests, 1);
+	}
+
+	#[tokio::test]
+	async fn test_circuit_breaker() {
+		let server = server(3).await;
+		let config = CircuitBreakerConfig { failure_threshold: 2, ..Default::default() };
+		let client = RpcClient::new(CircuitBreakerClient::new(http(&server), config));
+
+		// Errors of the node don't open the circuit
+		for _ in 0..2 {
+			assert!(client.get_block_header_count().await.is_err());
+		}
+		assert_eq!(client.as_ref().breaker().get_state().await, CircuitState::Closed);
+		assert_eq!(client.get_block_count().await.unwrap(), 100);
+
+		// Unreachable nodes do
+		let config = CircuitBreakerConfig { failure_threshold: 2, ..Default::default() };
+		let provider = HttpProvider::new("http://localhost:1").unwrap();
+		let client = RpcClient::new(CircuitBreakerClient::new(provider, config));
+		for _ in 0..2 {
+			assert!(!matches!(client.get_block_count().await, Err(ProviderError::CircuitOpen)));
+		}
+		assert_eq!(client.as_ref().breaker().get_state().await, CircuitState::Open);
+		assert_eq!(client.get_block_count().await, Err(ProviderError::CircuitOpen));
+	}
+
+	#[tokio::test]
+	async fn test_cache_ttl() {
+		let server = server(4).await;
+		let client = RpcClient::new(CachedClient::new(http(&server), CacheConfig::default()));
+		assert_eq!(client.get_block_count().await.unwrap(), 100);
+		assert_eq!(client.get_block_count().await.unwrap(), 100);
+		assert_eq!(client.as_ref().cache().size().await, 0);
+
+		let client = RpcClient::new(
+			CachedClient::new(http(&server), CacheConfig::default())
+				.with_method_ttl("getblockcount", Some(Duration::from_secs(5))),
+		);
+		assert_eq!(client.get_block_count().await.unwrap(), 100);
+		assert_eq!(client.get_block_count().await.unwrap(), 100);
+
+		// Errors are not cached
+		let client = RpcClient::new(CachedClient::new(http(&server), CacheConfig::default()));
+		assert!(client.as_ref().fetch::<_, u32>("getversion", ()).await.is_err());
+		assert_eq!(client.as_ref().cache().size().await, 0);
+	}
+}
@@ -4,6 +4,10 @@ pub use http_provider::{ClientError, HttpProvider};
 pub use ipc::{Ipc, IpcError};
 #[cfg(feature = "legacy-ws")]
 pub use legacy_ws::{ClientError as WsClientError, Ws};
+pub use middleware::{
+	CachedClient, CircuitBreakerClient, MetricsClient, PooledClient, RateLimitedClient,
+	RequestMetrics,
+};
 // pub use mock::{MockError, MockProvider, MockResponse};
 pub use retry::*;
 pub use rw::{RwClient, RwClientError};
@@ -15,13 +19,16 @@ pub use self::http_provider::{ClientError as HttpClientError, HttpProvider as Ht
 mod http_provider;
 #[cfg(all(feature = "ipc", any(unix, windows)))]
 mod ipc;
-// mod quorum;
-// pub use quorum::{JsonRpcClientWrapper, Quorum, QuorumError, QuorumProvider, WeightedProvider};
+mod quorum;
+pub use quorum::{
+	JsonRpcClientWrapper, Quorum, QuorumError, QuorumParams, QuorumProvider, WeightedProvider,
+};
 
 mod common;
 /// archival websocket
 #[cfg(feature = "legacy-ws")]
 pub mod legacy_ws;
+mod middleware;
 // mod mock;
 mod retry;
 mod rw;
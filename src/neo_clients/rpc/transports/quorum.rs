@@ -0,0 +1,428 @@
+//! A [JsonRpcProvider] implementation that sends each request to several providers and returns
+//! the response once enough of them agree on it.
+
+use std::fmt::Debug;
+
+use async_trait::async_trait;
+use futures_util::stream::{FuturesUnordered, StreamExt};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use thiserror::Error;
+use tracing::trace;
+
+use crate::neo_clients::{JsonRpcProvider, ProviderError};
+
+/// The weight of the providers that must agree on a response before it is returned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Quorum {
+	/// All providers must agree.
+	All,
+	/// More than half of the total weight must agree.
+	#[default]
+	Majority,
+	/// At least the given percentage of the total weight must agree.
+	Percentage(u8),
+	/// At least the given weight must agree.
+	Weight(u64),
+}
+
+impl Quorum {
+	/// The weight needed for a quorum of providers with a total weight of `total`.
+	fn weight(&self, total: u64) -> u64 {
+		let weight = match *self {
+			Quorum::All => total,
+			Quorum::Majority => total / 2 + 1,
+			Quorum::Percentage(percentage) =>
+				(total as u128 * percentage as u128).div_ceil(100) as u64,
+			Quorum::Weight(weight) => weight,
+		};
+		weight.max(1)
+	}
+}
+
+/// A provider and the weight of its responses.
+#[derive(Debug)]
+pub struct WeightedProvider {
+	inner: Box<dyn JsonRpcClientWrapper>,
+	weight: u64,
+}
+
+impl WeightedProvider {
+	/// Wraps `provider` with a weight of 1.
+	pub fn new<P: JsonRpcProvider + 'static>(provider: P) -> Self {
+		Self::with_weight(provider, 1)
+	}
+
+	pub fn with_weight<P: JsonRpcProvider + 'static>(provider: P, weight: u64) -> Self {
+		Self { inner: Box::new(provider), weight }
+	}
+
+	pub fn weight(&self) -> u64 {
+		self.weight
+	}
+}
+
+/// A provider that sends each request to several [WeightedProvider]s concurrently.
+///
+/// The responses are compared after removing the fields that legitimately differ between nodes,
+/// like the number of confirmations of a block, and the first response whose providers reach the
+/// [Quorum] is returned. If the quorum can't be reached anymore, the distinct responses and the
+/// errors are returned in [QuorumError::NoQuorumReached].
+///
+/// # Example
+///
+/// ```no_run
+/// use neo3::neo_clients::{APITrait, HttpProvider, Quorum, QuorumProvider, RpcClient, WeightedProvider};
+///
+/// # async fn foo() -> Result<(), Box<dyn std::error::Error>> {
+/// // At least two nodes must agree
+/// let provider = QuorumProvider::new(
+///     Quorum::Weight(2),
+///     vec![
+///         WeightedProvider::new(HttpProvider::new("https://node1.example.com:10332")?),
+///         WeightedProvider::new(HttpProvider::new("https://node2.example.com:10332")?),
+///         WeightedProvider::new(HttpProvider::new("https://node3.example.com:10332")?),
+///     ],
+/// )?;
+/// let client = RpcClient::new(provider);
+/// let height = client.get_block_count().await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct QuorumProvider {
+	quorum: Quorum,
+	quorum_weight: u64,
+	providers: Vec<WeightedProvider>,
+	ignored_fields: Vec<String>,
+}
+
+impl QuorumProvider {
+	/// The fields that are ignored by default when comparing responses.
+	pub const DEFAULT_IGNORED_FIELDS: [&'static str; 2] = ["confirmations", "session"];
+
+	/// Fails if the providers can never reach the quorum: when a percentage is above 100, or the
+	/// quorum weight is above the total weight of the providers.
+	pub fn new(quorum: Quorum, providers: Vec<WeightedProvider>) -> Result<Self, QuorumError> {
+		if let Quorum::Percentage(percentage @ 101..) = quorum {
+			return Err(QuorumError::InvalidQuorum(format!(
+				"The percentage {} is above 100",
+				percentage
+			)));
+		}
+		let total = providers
+			.iter()
+			.try_fold(0u64, |total, provider| total.checked_add(provider.weight))
+			.ok_or_else(|| {
+				QuorumError::InvalidQuorum("The total weight overflows a u64".to_string())
+			})?;
+		let quorum_weight = quorum.weight(total);
+		if quorum_weight > total {
+			return Err(QuorumError::InvalidQuorum(format!(
+				"The quorum weight {} is above the total weight {} of the providers",
+				quorum_weight, total
+			)));
+		}
+		Ok(Self {
+			quorum,
+			quorum_weight,
+			providers,
+			ignored_fields: Self::DEFAULT_IGNORED_FIELDS.iter().map(|f| f.to_string()).collect(),
+		})
+	}
+
+	/// Sets the object fields that are ignored when comparing responses, at any depth.
+	pub fn with_ignored_fields(mut self, fields: Vec<String>) -> Self {
+		self.ignored_fields = fields;
+		self
+	}
+
+	pub fn quorum(&self) -> Quorum {
+		self.quorum
+	}
+
+	/// The weight of the providers that must agree on a response.
+	pub fn quorum_weight(&self) -> u64 {
+		self.quorum_weight
+	}
+
+	pub fn providers(&self) -> &[WeightedProvider] {
+		&self.providers
+	}
+
+	/// `value` without the ignored fields.
+	fn normalize(&self, value: &Value) -> Value {
+		match value {
+			Value::Object(object) => Value::Object(
+				object
+					.iter()
+					.filter(|(key, _)| !self.ignored_fields.contains(key))
+					.map(|(key, value)| (key.clone(), self.normalize(value)))
+					.collect(),
+			),
+			Value::Array(values) =>
+				Value::Array(values.iter().map(|v| self.normalize(v)).collect()),
+			value => value.clone(),
+		}
+	}
+}
+
+/// Error thrown when the providers of a [QuorumProvider] don't agree.
+#[derive(Error, Debug, Clone)]
+pub enum QuorumError {
+	/// Not enough providers agreed on a response.
+	#[error(
+		"No quorum reached: {} distinct responses and {} errors",
+		.responses.len(),
+		.errors.len()
+	)]
+	NoQuorumReached {
+		/// The distinct responses, with the total weight of the providers that returned them.
+		responses: Vec<(Value, u64)>,
+		/// The errors of the providers that failed.
+		errors: Vec<ProviderError>,
+	},
+	/// The request couldn't be sent, or the response couldn't be parsed.
+	#[error(transparent)]
+	ProviderError(ProviderError),
+	/// The providers can never reach the quorum.
+	#[error("Invalid quorum: {0}")]
+	InvalidQuorum(String),
+}
+
+impl From<QuorumError> for ProviderError {
+	fn from(src: QuorumError) -> Self {
+		match src {
+			QuorumError::ProviderError(err) => err,
+			err => ProviderError::QuorumError(Box::new(err)),
+		}
+	}
+}
+
+/// The params of a request, which are skipped if they are zero-sized.
+#[derive(Debug, Clone)]
+pub enum QuorumParams {
+	Value(Value),
+	Zst,
+}
+
+/// An object-safe [JsonRpcProvider], so a [QuorumProvider] can hold different kinds of providers.
+#[allow(clippy::double_must_use)]
+#[cfg_attr(target_arch = "wasm32", async_trait(? Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+pub trait JsonRpcClientWrapper: Debug + Send + Sync {
+	/// Sends a request and returns the raw result.
+	async fn fetch(&self, method: &str, params: QuorumParams) -> Result<Value, ProviderError>;
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(? Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl<C: JsonRpcProvider> JsonRpcClientWrapper for C {
+	async fn fetch(&self, method: &str, params: QuorumParams) -> Result<Value, ProviderError> {
+		let result = match params {
+			QuorumParams::Value(params) => JsonRpcProvider::fetch(self, method, params).await,
+			QuorumParams::Zst => JsonRpcProvider::fetch(self, method, ()).await,
+		};
+		result.map_err(Into::into)
+	}
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(? Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl JsonRpcProvider for QuorumProvider {
+	type Error = QuorumError;
+
+	async fn fetch<T, R>(&self, method: &str, params: T) -> Result<R, Self::Error>
+	where
+		T: Debug + Serialize + Send + Sync,
+		R: DeserializeOwned + Send,
+	{
+		let params = if std::mem::size_of::<T>() == 0 {
+			QuorumParams::Zst
+		} else {
+			QuorumParams::Value(
+				serde_json::to_value(params)
+					.map_err(|err| QuorumError::ProviderError(err.into()))?,
+			)
+		};
+
+		let mut requests: FuturesUnordered<_> = self
+			.providers
+			.iter()
+			.map(|provider| {
+				let params = params.clone();
+				async move { (provider.weight, provider.inner.fetch(method, params).await) }
+			})
+			.collect();
+
+		// The normalized responses, with the first raw response and the weight of each
+		let mut responses: Vec<(Value, Value, u64)> = vec![];
+		let mut errors = vec![];
+		while let Some((weight, response)) = requests.next().await {
+			let value = match response {
+				Ok(value) => value,
+				Err(err) => {
+					trace!(err = ?err, "quorum provider failed");
+					errors.push(err);
+					continue;
+				},
+			};
+			let normalized = self.normalize(&value);
+			let index = match responses.iter().position(|(response, ..)| *response == normalized) {
+				Some(index) => index,
+				None => {
+					responses.push((normalized, value, 0));
+					responses.len() - 1
+				},
+			};
+			responses[index].2 += weight;
+			if responses[index].2 >= self.quorum_weight {
+				let (_, value, _) = responses.swap_remove(index);
+				return serde_json::from_value(value)
+					.map_err(|err| QuorumError::ProviderError(err.into()));
+			}
+		}
+
+		Err(QuorumError::NoQuorumReached {
+			responses: responses.into_iter().map(|(_, value, weight)| (value, weight)).collect(),
+			errors,
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json::json;
+	use wiremock::{
+		matchers::{body_partial_json, method},
+		Mock, MockServer, ResponseTemplate,
+	};
+
+	use super::*;
+	use crate::neo_clients::{APITrait, HttpProvider, RpcClient};
+
+	async fn server(result: Value) -> MockServer {
+		let server = MockServer::start().await;
+		Mock::given(method("POST"))
+			.respond_with(ResponseTemplate::new(200).set_body_json(json!({
+				"jsonrpc": "2.0",
+				"id": 1,
+				"result": result
+			})))
+			.mount(&server)
+			.await;
+		server
+	}
+
+	fn provider(server: &MockServer, weight: u64) -> WeightedProvider {
+		WeightedProvider::with_weight(HttpProvider::new(server.uri().as_str()).unwrap(), weight)
+	}
+
+	#[test]
+	fn test_quorum_weight() {
+		assert_eq!(Quorum::All.weight(5), 5);
+		assert_eq!(Quorum::Majority.weight(4), 3);
+		assert_eq!(Quorum::Majority.weight(5), 3);
+		assert_eq!(Quorum::Percentage(50).weight(5), 3);
+		assert_eq!(Quorum::Percentage(0).weight(5), 1);
+		assert_eq!(Quorum::Weight(2).weight(5), 2);
+		assert_eq!(Quorum::Percentage(100).weight(u64::MAX), u64::MAX);
+	}
+
+	#[tokio::test]
+	async fn test_unreachable_quorums() {
+		let server = server(json!(100)).await;
+		let providers = |weights: &[u64]| -> Vec<WeightedProvider> {
+			weights.iter().map(|weight| provider(&server, *weight)).collect()
+		};
+
+		assert!(QuorumProvider::new(Quorum::Percentage(100), providers(&[1, 2])).is_ok());
+		assert!(QuorumProvider::new(Quorum::Percentage(101), providers(&[1, 2])).is_err());
+		assert!(QuorumProvider::new(Quorum::Weight(3), providers(&[1, 2])).is_ok());
+		assert!(QuorumProvider::new(Quorum::Weight(4), providers(&[1, 2])).is_err());
+		assert!(QuorumProvider::new(Quorum::Majority, providers(&[])).is_err());
+		assert!(QuorumProvider::new(Quorum::All, providers(&[u64::MAX, 1])).is_err());
+	}
+
+	#[tokio::test]
+	async fn test_quorum() {
+		let servers =
+			[server(json!(100)).await, server(json!(100)).await, server(json!(101)).await];
+		let quorum_client = |quorum: Quorum, weights: [u64; 3]| {
+			let providers = servers
+				.iter()
+				.zip(weights)
+				.map(|(server, weight)| provider(server, weight))
+				.collect();
+			RpcClient::new(QuorumProvider::new(quorum, providers).unwrap())
+		};
+
+		let client = quorum_client(Quorum::Majority, [1, 1, 1]);
+		assert_eq!(client.get_block_count().await.unwrap(), 100);
+
+		let client = quorum_client(Quorum::Weight(3), [1, 1, 3]);
+		assert_eq!(client.get_block_count().await.unwrap(), 101);
+
+		let client = quorum_client(Quorum::All, [1, 1, 1]);
+		let err = match client.get_block_count().await.unwrap_err() {
+			ProviderError::QuorumError(err) => *err,
+			err => panic!("Unexpected error {}", err),
+		};
+		match err {
+			QuorumError::NoQuorumReached { mut responses, errors } => {
+				responses.sort_by_key(|(_, weight)| *weight);
+				assert_eq!(responses, vec![(json!(101), 1), (json!(100), 2)]);
+				assert!(errors.is_empty());
+			},
+			err => panic!("Unexpected error {}", err),
+		}
+	}
+
+	#[tokio::test]
+	async fn test_quorum_with_failures_and_ignored_fields() {
+		let transaction = |confirmations: u32| json!({ "hash": "0x01", "blocktime": 1, "confirmations": confirmations });
+		let servers = [
+			server(transaction(10)).await,
+			server(transaction(11)).await,
+			MockServer::start().await,
+		];
+		let providers = || servers.iter().map(|server| provider(server, 1)).collect::<Vec<_>>();
+
+		let quorum = QuorumProvider::new(Quorum::Weight(2), providers()).unwrap();
+		let response: Value =
+			JsonRpcProvider::fetch(&quorum, "getrawtransaction", ["0x01"]).await.unwrap();
+		assert_eq!(response["hash"], "0x01");
+
+		let quorum = QuorumProvider::new(Quorum::Weight(2), providers())
+			.unwrap()
+			.with_ignored_fields(vec![]);
+		match JsonRpcProvider::fetch::<_, Value>(&quorum, "getrawtransaction", ["0x01"])
+			.await
+			.unwrap_err()
+		{
+			QuorumError::NoQuorumReached { responses, errors } => {
+				assert_eq!(responses.len(), 2);
+				assert_eq!(errors.len(), 1);
+			},
+			err => panic!("Unexpected error {}", err),
+		}
+
+		// Requests without params
+		let server = MockServer::start().await;
+		Mock::given(method("POST"))
+			.and(body_partial_json(json!({ "method": "getblockcount" })))
+			.respond_with(ResponseTemplate::new(200).set_body_json(json!({
+				"jsonrpc": "2.0",
+				"id": 1,
+				"result": 5
+			})))
+			.mount(&server)
+			.await;
+		let quorum = QuorumProvider::new(Quorum::All, vec![provider(&server, 1)]).unwrap();
+		assert_eq!(
+			JsonRpcProvider::fetch::<_, u32>(&quorum, "getblockcount", ()).await.unwrap(),
+			5
+		);
+		assert!(JsonRpcProvider::fetch::<_, u32>(&quorum, "getversion", ()).await.is_err());
+	}
+}
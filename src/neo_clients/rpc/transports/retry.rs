@@ -3,15 +3,18 @@
 
 use std::{
 	fmt::Debug,
+	future::Future,
 	sync::atomic::{AtomicU32, Ordering},
 	time::Duration,
 };
 
 use super::{common::JsonRpcError, http_provider::ClientError};
-use crate::neo_clients::{JsonRpcProvider, ProviderError};
+use crate::neo_clients::{JsonRpcProvider, Notifications, ProviderError, RpcCall};
 use async_trait::async_trait;
+use primitive_types::U256;
 use reqwest::StatusCode;
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
 use thiserror::Error;
 use tracing::trace;
 
@@ -112,6 +115,88 @@ where
 		self.compute_units_per_second = cpus;
 		self
 	}
+
+	/// Sends a `request` worth `weight` requests of the queue until it succeeds or shouldn't be
+	/// retried anymore.
+	async fn retry<R, F, Fut>(&self, weight: u32, mut request: F) -> Result<R, RetryClientError>
+	where
+		F: FnMut() -> Fut,
+		Fut: Future<Output = Result<R, T::Error>>,
+	{
+		let ahead_in_queue = self.requests_enqueued.fetch_add(weight, Ordering::SeqCst) as u64;
+
+		let mut rate_limit_retry_number: u32 = 0;
+		let mut timeout_retries: u32 = 0;
+
+		loop {
+			let err;
+
+			// hack to not hold `R` across an await in the sleep future and prevent requiring
+			// R: Send + Sync
+			{
+				match request().await {
+					Ok(ret) => {
+						self.requests_enqueued.fetch_sub(weight, Ordering::SeqCst);
+						return Ok(ret);
+					},
+					Err(err_) => err = err_,
+				}
+			}
+
+			let should_retry = self.policy.should_retry(&err);
+			if should_retry {
+				rate_limit_retry_number += 1;
+				if rate_limit_retry_number > self.rate_limit_retries {
+					trace!("request timed out after {} retries", self.rate_limit_retries);
+					return Err(RetryClientError::TimeoutError);
+				}
+
+				let current_queued_requests = self.requests_enqueued.load(Ordering::SeqCst) as u64;
+
+				// try to extract the requested backoff from the error or compute the next backoff
+				// based on retry count
+				let mut next_backoff = self.policy.backoff_hint(&err).unwrap_or_else(|| {
+					Duration::from_millis(self.initial_backoff.as_millis() as u64)
+				});
+
+				// requests are usually weighted and can vary from 10 CU to several 100 CU, cheaper
+				// requests are more common some example alchemy weights:
+				// - `neo_getStorageAt`: 17
+				// - `neo_getBlockByNumber`: 16
+				// - `neo_newFilter`: 20
+				//
+				// (coming from forking mode) assuming here that storage request will be the driver
+				// for Rate limits we choose `17` as the average cost of any request
+				const AVG_COST: u64 = 17u64;
+				let seconds_to_wait_for_compute_budget = compute_unit_offset_in_secs(
+					AVG_COST,
+					self.compute_units_per_second,
+					current_queued_requests,
+					ahead_in_queue,
+				);
+				next_backoff += Duration::from_secs(seconds_to_wait_for_compute_budget);
+
+				trace!("retrying and backing off for {:?}", next_backoff);
+
+				#[cfg(target_arch = "wasm32")]
+				futures_timer::Delay::new(next_backoff).await;
+
+				#[cfg(not(target_arch = "wasm32"))]
+				tokio::time::sleep(next_backoff).await;
+			} else {
+				let err: ProviderError = err.into();
+				if timeout_retries < self.timeout_retries && maybe_connectivity(&err) {
+					timeout_retries += 1;
+					trace!(err = ?err, "retrying due to spurious network");
+					continue;
+				}
+
+				trace!(err = ?err, "should not retry");
+				self.requests_enqueued.fetch_sub(weight, Ordering::SeqCst);
+				return Err(RetryClientError::ProviderError(err));
+			}
+		}
+	}
 }
 
 /// Builder for a [`RetryClient`]
@@ -258,83 +343,32 @@ where
 			RetryParams::Value(params)
 		};
 
-		let ahead_in_queue = self.requests_enqueued.fetch_add(1, Ordering::SeqCst) as u64;
-
-		let mut rate_limit_retry_number: u32 = 0;
-		let mut timeout_retries: u32 = 0;
-
-		loop {
-			let err;
-
-			// hack to not hold `R` across an await in the sleep future and prevent requiring
-			// R: Send + Sync
-			{
-				let resp = match params {
-					RetryParams::Value(ref params) => self.inner.fetch(method, params).await,
-					RetryParams::Zst(unit) => self.inner.fetch(method, unit).await,
-				};
-				match resp {
-					Ok(ret) => {
-						self.requests_enqueued.fetch_sub(1, Ordering::SeqCst);
-						return Ok(ret);
-					},
-					Err(err_) => err = err_,
-				}
+		let params = &params;
+		self.retry(1, move || async move {
+			match params {
+				RetryParams::Value(params) => self.inner.fetch(method, params).await,
+				RetryParams::Zst(()) => self.inner.fetch(method, ()).await,
 			}
+		})
+		.await
+	}
 
-			let should_retry = self.policy.should_retry(&err);
-			if should_retry {
-				rate_limit_retry_number += 1;
-				if rate_limit_retry_number > self.rate_limit_retries {
-					trace!("request timed out after {} retries", self.rate_limit_retries);
-					return Err(RetryClientError::TimeoutError);
-				}
-
-				let current_queued_requests = self.requests_enqueued.load(Ordering::SeqCst) as u64;
-
-				// try to extract the requested backoff from the error or compute the next backoff
-				// based on retry count
-				let mut next_backoff = self.policy.backoff_hint(&err).unwrap_or_else(|| {
-					Duration::from_millis(self.initial_backoff.as_millis() as u64)
-				});
-
-				// requests are usually weighted and can vary from 10 CU to several 100 CU, cheaper
-				// requests are more common some example alchemy weights:
-				// - `neo_getStorageAt`: 17
-				// - `neo_getBlockByNumber`: 16
-				// - `neo_newFilter`: 20
-				//
-				// (coming from forking mode) assuming here that storage request will be the driver
-				// for Rate limits we choose `17` as the average cost of any request
-				const AVG_COST: u64 = 17u64;
-				let seconds_to_wait_for_compute_budget = compute_unit_offset_in_secs(
-					AVG_COST,
-					self.compute_units_per_second,
-					current_queued_requests,
-					ahead_in_queue,
-				);
-				next_backoff += Duration::from_secs(seconds_to_wait_for_compute_budget);
-
-				trace!("retrying and backing off for {:?}", next_backoff);
-
-				#[cfg(target_arch = "wasm32")]
-				futures_timer::Delay::new(next_backoff).await;
+	async fn fetch_batch(
+		&self,
+		calls: &[RpcCall],
+	) -> Result<Vec<Result<Value, ProviderError>>, Self::Error> {
+		// The batch is retried as a whole, and weighs as much as its calls in the queue
+		self.retry(calls.len() as u32, || self.inner.fetch_batch(calls)).await
+	}
 
-				#[cfg(not(target_arch = "wasm32"))]
-				tokio::time::sleep(next_backoff).await;
-			} else {
-				let err: ProviderError = err.into();
-				if timeout_retries < self.timeout_retries && maybe_connectivity(&err) {
-					timeout_retries += 1;
-					trace!(err = ?err, "retrying due to spurious network");
-					continue;
-				}
+	fn supports_subscriptions(&self) -> bool {
+		self.inner.supports_subscriptions()
+	}
 
-				trace!(err = ?err, "should not retry");
-				self.requests_enqueued.fetch_sub(1, Ordering::SeqCst);
-				return Err(RetryClientError::ProviderError(err));
-			}
-		}
+	fn notifications(&self, id: U256) -> Option<Result<Notifications, Self::Error>> {
+		self.inner.notifications(id).map(|notifications| {
+			notifications.map_err(|err| RetryClientError::ProviderError(err.into()))
+		})
 	}
 }
 
@@ -561,4 +595,48 @@ mod tests {
 		let should_retry = HttpRateLimitRetryPolicy.should_retry(&err);
 		assert!(should_retry);
 	}
+
+	#[tokio::test]
+	async fn can_retry_batch() {
+		use crate::neo_clients::HttpProvider;
+		use serde_json::json;
+		use wiremock::{matchers::method, Mock, MockServer, Request, ResponseTemplate};
+
+		// The whole batch is rate limited once
+		let server = MockServer::start().await;
+		Mock::given(method("POST"))
+			.respond_with(ResponseTemplate::new(429).set_body_json(json!({
+				"jsonrpc": "2.0",
+				"error": {"code": 429, "message": "Too many requests"}
+			})))
+			.up_to_n_times(1)
+			.expect(1)
+			.mount(&server)
+			.await;
+		Mock::given(method("POST"))
+			.respond_with(|request: &Request| {
+				let calls: Vec<Value> = serde_json::from_slice(&request.body).unwrap();
+				ResponseTemplate::new(200).set_body_json(json!([
+					{"jsonrpc": "2.0", "id": calls[1]["id"], "result": 7},
+					{"jsonrpc": "2.0", "id": calls[0]["id"], "result": 100}
+				]))
+			})
+			.expect(1)
+			.mount(&server)
+			.await;
+
+		let client = RetryClientBuilder::default()
+			.initial_backoff(Duration::from_millis(1))
+			.build(
+				HttpProvider::new(server.uri().as_str()).unwrap(),
+				Box::new(HttpRateLimitRetryPolicy),
+			);
+		let calls = [
+			RpcCall::new("getblockcount", ()).unwrap(),
+			RpcCall::new("getblockhash", [7]).unwrap(),
+		];
+		let results = client.fetch_batch(&calls).await.unwrap();
+		assert_eq!(results, vec![Ok(json!(100)), Ok(json!(7))]);
+		assert_eq!(client.requests_enqueued.load(Ordering::SeqCst), 0);
+	}
 }
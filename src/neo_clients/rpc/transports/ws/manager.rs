@@ -11,74 +11,58 @@ use futures_util::{select_biased, StreamExt};
 use primitive_types::U256;
 use serde_json::value::{to_raw_value, RawValue};
 
-use crate::JsonRpcError;
+use crate::neo_clients::{JsonRpcError, Subscription};
 
 #[cfg(not(target_arch = "wasm32"))]
 use super::WebSocketConfig;
 use super::{
 	backend::{BackendDriver, WsBackend},
-	ActiveSub, ConnectionDetails, InFlight, Instruction, Notification, PubSubItem, Response, SubId,
-	WsClient, WsClientError,
+	ActiveSub, ConnectionDetails, InFlight, Instruction, Notification, PubSubItem, Response,
+	WsClient, WsClientError, EVENT_MISSED, SUBSCRIBE_METHOD, UNSUBSCRIBE_METHOD,
 };
 
 pub type SharedChannelMap = Arc<Mutex<HashMap<U256, mpsc::UnboundedReceiver<Box<RawValue>>>>>;
 
 pub const DEFAULT_RECONNECTS: usize = 5;
 
-/// This struct manages the relationship between the u64 request ID, and U256
-/// server-side subscription ID. It does this by aliasing the server ID to the
-/// request ID, and returning the Request ID to the caller (hiding the server
-/// ID in the SubscriptionManager internals.) Giving the caller a "fake"
-/// subscription id allows the subscription to behave consistently across
-/// reconnections
+/// This struct manages the relationship between the u64 request ID, and the
+/// server-side subscription ID. It returns the request ID to the caller as the
+/// subscription ID, hiding the server ID in the SubscriptionManager internals.
+/// Giving the caller a "fake" subscription id allows the subscription to behave
+/// consistently across reconnections.
+///
+/// neo-go nodes don't say which subscription a notification is for, so
+/// notifications are forwarded to the subscriptions of their event whose
+/// filter they pass.
 pub struct SubscriptionManager {
 	// Active subs indexed by request id
 	subs: BTreeMap<u64, ActiveSub>,
-	// Maps active server-side IDs to local subscription IDs
-	aliases: HashMap<U256, u64>,
 	// Used to share notification channels with the WsClient(s)
 	channel_map: SharedChannelMap,
 }
 
 impl SubscriptionManager {
 	fn new(channel_map: SharedChannelMap) -> Self {
-		Self { subs: Default::default(), aliases: Default::default(), channel_map }
+		Self { subs: Default::default(), channel_map }
 	}
 
 	fn count(&self) -> usize {
 		self.subs.len()
 	}
 
-	fn add_alias(&mut self, sub: U256, id: u64) {
-		if let Some(entry) = self.subs.get_mut(&id) {
-			entry.current_server_id = Some(sub);
-		}
-		self.aliases.insert(sub, id);
-	}
-
-	fn remove_alias(&mut self, server_id: U256) {
-		if let Some(id) = self.aliases.get(&server_id) {
-			if let Some(sub) = self.subs.get_mut(id) {
-				sub.current_server_id = None;
-			}
-		}
-		self.aliases.remove(&server_id);
-	}
-
 	#[tracing::instrument(skip(self))]
 	fn end_subscription(&mut self, id: u64) -> Option<Box<RawValue>> {
 		if let Some(sub) = self.subs.remove(&id) {
 			if let Some(server_id) = sub.current_server_id {
-				tracing::debug!(server_id = format!("0x{server_id:x}"), "Ending subscription");
-				self.remove_alias(server_id);
+				tracing::debug!(server_id, "Ending subscription");
 				// drop the receiver as we don't need the result
 				let (channel, _) = oneshot::channel();
 				// Serialization errors are ignored, and result in the request
 				// not being dispatched. This is fine, as worst case it will
 				// result in the server sending us notifications we ignore
 				let unsub_request = InFlight {
-					method: "neo_unsubscribe".to_string(),
-					params: SubId(server_id).serialize_raw().ok()?,
+					method: UNSUBSCRIBE_METHOD.to_string(),
+					params: to_raw_value(&[server_id]).ok()?,
 					channel,
 				};
 				// reuse the RPC ID. this is somewhat dirty.
@@ -90,49 +74,63 @@ impl SubscriptionManager {
 		None
 	}
 
-	#[tracing::instrument(skip_all, fields(server_id = ? notification.subscription))]
-	fn handle_notification(&mut self, notification: Notification) {
-		let server_id = notification.subscription;
-
-		// If no alias, just return
-		let id_opt = self.aliases.get(&server_id).copied();
-		if id_opt.is_none() {
-			tracing::debug!(
-				server_id = format!("0x{server_id:x}"),
-				"No aliased subscription found"
-			);
-			return;
-		}
-		let id = id_opt.unwrap();
+	/// Drops a subscription the server refused
+	fn remove(&mut self, id: u64) {
+		self.subs.remove(&id);
+		self.channel_map.lock().unwrap().remove(&id.into());
+	}
 
-		// alias exists, or should be dropped from alias table
-		let sub_opt = self.subs.get(&id);
-		if sub_opt.is_none() {
-			tracing::trace!(id, "Aliased subscription found, but not active");
-			self.aliases.remove(&server_id);
+	/// Forwards `notification` to its listeners, and returns the `unsubscribe` requests of the
+	/// subscriptions whose listener was dropped.
+	#[tracing::instrument(skip_all, fields(event = %notification.method))]
+	fn handle_notification(&mut self, notification: Notification) -> Vec<Box<RawValue>> {
+		if notification.method == EVENT_MISSED {
+			tracing::warn!("The server dropped events, subscriptions missed notifications");
+			return vec![];
 		}
-		let active = sub_opt.unwrap();
-
-		tracing::debug!(id, "Forwarding notification to listener");
-		// send the notification over the channel
-		let send_res = active.channel.unbounded_send(notification.result);
-
-		// receiver has dropped, so we drop the sub
-		if send_res.is_err() {
-			tracing::debug!(id, "Listener dropped. Dropping alias and subs");
-			// Note: We remove the subscription mappings but don't send an unsubscribe request
-			// to the server. This is because the channel receiver has already been dropped,
-			// indicating the consumer is no longer interested in the subscription.
-			// The server will continue sending notifications which we'll ignore.
-			self.aliases.remove(&server_id);
-			self.subs.remove(&id);
+		let payload = match notification.payload() {
+			Some(payload) => payload,
+			None => {
+				tracing::debug!("Notification without payload");
+				return vec![];
+			},
+		};
+
+		let mut dropped = vec![];
+		for (id, active) in self.subs.iter() {
+			let routed = active.current_server_id.is_some() &&
+				active.subscription.as_ref().is_some_and(|subscription| {
+					subscription.event().as_str() == notification.method &&
+						subscription.matches(&payload)
+				});
+			if !routed {
+				continue;
+			}
+
+			tracing::debug!(id, "Forwarding notification to listener");
+			// send the notification over the channel
+			if active.channel.unbounded_send(payload.clone()).is_err() {
+				dropped.push(*id);
+			}
 		}
+
+		// receiver has dropped, so we drop the sub and cancel it on the server, which would
+		// otherwise keep sending its events
+		dropped
+			.into_iter()
+			.filter_map(|id| {
+				tracing::debug!(id, "Listener dropped. Dropping sub");
+				self.end_subscription(id)
+			})
+			.collect()
 	}
 
 	fn req_success(&mut self, id: u64, result: Box<RawValue>) -> Box<RawValue> {
-		if let Ok(server_id) = serde_json::from_str::<SubId>(result.get()) {
-			tracing::debug!(id, server_id = %server_id.0, "Registering new sub alias");
-			self.add_alias(server_id.0, id);
+		if let Ok(server_id) = serde_json::from_str::<String>(result.get()) {
+			tracing::debug!(id, server_id, "Registering server id of the sub");
+			if let Some(sub) = self.subs.get_mut(&id) {
+				sub.current_server_id = Some(server_id);
+			}
 			let result = U256::from(id);
 			to_raw_value(&format!("0x{result:x}")).expect("valid json")
 		} else {
@@ -155,7 +153,11 @@ impl SubscriptionManager {
 	) -> Result<Box<RawValue>, WsClientError> {
 		let (tx, rx) = mpsc::unbounded();
 
-		let active_sub = ActiveSub { params, channel: tx, current_server_id: None };
+		// Subscriptions that can't be parsed are still sent, but never get notifications
+		let subscription = serde_json::from_str::<Vec<serde_json::Value>>(params.get())
+			.ok()
+			.and_then(|params| Subscription::from_params(&params).ok());
+		let active_sub = ActiveSub { params, subscription, channel: tx, current_server_id: None };
 		let req = active_sub.serialize_raw(id)?;
 
 		// Explicit scope for the lock
@@ -363,7 +365,7 @@ impl RequestManager {
 		for (id, sub) in self.subs.to_reissue() {
 			let (tx, _rx) = oneshot::channel();
 			let in_flight = InFlight {
-				method: "neo_subscribe".to_string(),
+				method: SUBSCRIBE_METHOD.to_string(),
 				params: sub.params.clone(),
 				channel: tx,
 			};
@@ -400,6 +402,9 @@ impl RequestManager {
 	}
 
 	fn req_fail(&mut self, id: u64, error: JsonRpcError) {
+		if self.subs.has(id) {
+			self.subs.remove(id);
+		}
 		// pending fut is missing, this is fine
 		if let Some(req) = self.reqs.remove(&id) {
 			// pending fut has been dropped, this is fine
@@ -411,7 +416,11 @@ impl RequestManager {
 		match item {
 			PubSubItem::Success { id, result } => self.req_success(id, result),
 			PubSubItem::Error { id, error } => self.req_fail(id, error),
-			PubSubItem::Notification { params } => self.subs.handle_notification(params),
+			PubSubItem::Notification { params } =>
+				for req in self.subs.handle_notification(params) {
+					// A dead backend is reconnected by the main loop, without the dropped subs
+					let _ = self.backend.dispatcher.unbounded_send(req);
+				},
 		}
 	}
 
@@ -428,7 +437,7 @@ impl RequestManager {
 
 		// Ordering matters here. We want this block above the unbounded send,
 		// and after the serialization
-		if in_flight.method == "neo_subscribe" {
+		if in_flight.method == SUBSCRIBE_METHOD {
 			self.subs.service_subscription_request(id, in_flight.params.clone())?;
 		}
 
@@ -505,3 +514,70 @@ impl RequestManager {
 		tokio::spawn(fut);
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use serde_json::{json, Value};
+
+	use super::*;
+
+	fn raw(value: Value) -> Box<RawValue> {
+		to_raw_value(&value).unwrap()
+	}
+
+	fn block_added(primary: u8) -> Notification {
+		let block = json!({
+			"hash": "0x239fea00c54c2f6812612874183b72bef4473fcdf68bf8da08d74fd5b6cab030",
+			"size": 1641,
+			"version": 0,
+			"previousblockhash": "0x04f7580b111ec75f0ce68d3a9fd70a0544b4521b4a98541694d8575c548b759e",
+			"merkleroot": "0x6b9ad1a9c2ea2ca1d4f3c1bcdb3e16f1c3bbb7de6b99b1e2d1dc03cd0ac2f8b4",
+			"time": 1616055166,
+			"nonce": "A3A4ED2C8C6A1AEF",
+			"index": 207,
+			"primary": primary,
+			"nextconsensus": "NgPkjjLTNcQad99iRYeXRUuowE4gxLAnDL",
+		});
+		Notification { method: "block_added".to_string(), params: raw(json!([block])) }
+	}
+
+	#[test]
+	fn test_notification_routing() {
+		let channel_map = SharedChannelMap::default();
+		let mut subs = SubscriptionManager::new(channel_map.clone());
+		subs.service_subscription_request(1, raw(json!(["block_added"]))).unwrap();
+		subs.service_subscription_request(2, raw(json!(["block_added", {"primary": 1}]))).unwrap();
+		subs.service_subscription_request(3, raw(json!(["transaction_added"]))).unwrap();
+
+		// Nothing is forwarded before the server confirms the subscriptions
+		assert!(subs.handle_notification(block_added(1)).is_empty());
+		for id in 1..=3 {
+			let result = subs.req_success(id, raw(json!(format!("{}", id + 10))));
+			assert_eq!(result.get(), format!("\"0x{id}\""));
+		}
+		assert!(subs.handle_notification(block_added(0)).is_empty());
+		assert!(subs.handle_notification(block_added(1)).is_empty());
+
+		let mut receivers = channel_map.lock().unwrap();
+		let mut received = |id: u64| {
+			let mut receiver = receivers.remove(&id.into()).unwrap();
+			std::iter::from_fn(|| receiver.try_next().ok().flatten()).count()
+		};
+		assert_eq!(received(1), 2);
+		assert_eq!(received(2), 1);
+		assert_eq!(received(3), 0);
+
+		let unsubscribe: Value =
+			serde_json::from_str(subs.end_subscription(2).unwrap().get()).unwrap();
+		assert_eq!(unsubscribe["method"], "unsubscribe");
+		assert_eq!(unsubscribe["params"], json!(["12"]));
+		assert_eq!(subs.count(), 2);
+
+		// The listeners were dropped, so the subscriptions notified next are cancelled
+		let unsubscribes = subs.handle_notification(block_added(1));
+		assert_eq!(unsubscribes.len(), 1);
+		let unsubscribe: Value = serde_json::from_str(unsubscribes[0].get()).unwrap();
+		assert_eq!(unsubscribe["params"], json!(["11"]));
+		assert_eq!(subs.count(), 1);
+	}
+}
@@ -18,9 +18,11 @@ use manager::{RequestManager, SharedChannelMap};
 pub use types::ConnectionDetails;
 use types::*;
 
-use crate::neo_clients::{JsonRpcProvider, ProviderError, PubsubClient, RpcClient};
+use crate::neo_clients::{
+	pubsub_notifications, JsonRpcProvider, Notifications, ProviderError, PubsubClient, RpcClient,
+};
 #[cfg(not(target_arch = "wasm32"))]
-use crate::Authorization;
+use crate::neo_clients::Authorization;
 
 mod backend;
 
@@ -124,6 +126,14 @@ impl JsonRpcProvider for WsClient {
 
 		Ok(res)
 	}
+
+	fn supports_subscriptions(&self) -> bool {
+		true
+	}
+
+	fn notifications(&self, id: U256) -> Option<Result<Notifications, WsClientError>> {
+		Some(pubsub_notifications(self, id))
+	}
 }
 
 impl PubsubClient for WsClient {
@@ -208,7 +218,7 @@ impl RpcClient<WsClient> {
 		reconnects: usize,
 	) -> Result<Self, ProviderError> {
 		let conn = ConnectionDetails::new(url, Some(auth));
-		let ws = crate::Ws::connect_with_reconnects(conn, reconnects).await?;
+		let ws = WsClient::connect_with_reconnects(conn, reconnects).await?;
 		Ok(Self::new(ws))
 	}
 }
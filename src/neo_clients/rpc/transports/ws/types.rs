@@ -7,24 +7,31 @@ use serde_json::value::{to_raw_value, RawValue};
 
 pub use aliases::*;
 
-use crate::{common::Request, JsonRpcError};
+use crate::neo_clients::{JsonRpcError, Request, Subscription};
 
 // Normal JSON-RPC response
 pub type Response = Result<Box<RawValue>, JsonRpcError>;
 
-#[derive(serde::Deserialize, serde::Serialize)]
-pub struct SubId(pub U256);
+/// The method subscribing to the events of a neo-go node
+pub(super) const SUBSCRIBE_METHOD: &str = "subscribe";
+/// The method cancelling a subscription of a neo-go node
+pub(super) const UNSUBSCRIBE_METHOD: &str = "unsubscribe";
+/// The notification sent by neo-go nodes when events were dropped for a slow client
+pub(super) const EVENT_MISSED: &str = "event_missed";
 
-impl SubId {
-	pub(super) fn serialize_raw(&self) -> Result<Box<RawValue>, serde_json::Error> {
-		to_raw_value(&self)
-	}
+/// An event notification. neo-go nodes don't say which subscription a notification is for, the
+/// method is the name of the event and the params hold its payload.
+#[derive(Debug, Clone)]
+pub struct Notification {
+	pub method: String,
+	pub params: Box<RawValue>,
 }
 
-#[derive(Deserialize, Debug, Clone)]
-pub struct Notification {
-	pub subscription: U256,
-	pub result: Box<RawValue>,
+impl Notification {
+	/// The payload of the event, the first of the params
+	pub(super) fn payload(&self) -> Option<Box<RawValue>> {
+		serde_json::from_str::<Vec<Box<RawValue>>>(self.params.get()).ok()?.into_iter().next()
+	}
 }
 
 #[derive(Debug, Clone)]
@@ -119,7 +126,7 @@ impl<'de> Deserialize<'de> for PubSubItem {
 								return Err(de::Error::duplicate_field("params"));
 							}
 
-							let value: Notification = map.next_value()?;
+							let value: Box<RawValue> = map.next_value()?;
 							params = Some(value);
 						},
 						key => {
@@ -146,8 +153,8 @@ impl<'de> Deserialize<'de> for PubSubItem {
 					(Some(id), Some(_), Some(error), None, None) => {
 						Ok(PubSubItem::Error { id, error })
 					},
-					(None, None, None, Some(_), Some(params)) => {
-						Ok(PubSubItem::Notification { params })
+					(None, None, None, Some(method), Some(params)) => {
+						Ok(PubSubItem::Notification { params: Notification { method, params } })
 					},
 					_ => Err(de::Error::custom(
 						"response must be either a success/error or notification object",
@@ -166,7 +173,7 @@ impl std::fmt::Display for PubSubItem {
 			PubSubItem::Success { id, .. } => write!(f, "Req success. ID: {id}"),
 			PubSubItem::Error { id, .. } => write!(f, "Req error. ID: {id}"),
 			PubSubItem::Notification { params } => {
-				write!(f, "Notification for sub: {:?}", params.subscription)
+				write!(f, "Notification of event: {}", params.method)
 			},
 		}
 	}
@@ -176,12 +183,12 @@ impl std::fmt::Display for PubSubItem {
 pub struct ConnectionDetails {
 	pub url: String,
 	#[cfg(not(target_arch = "wasm32"))]
-	pub auth: Option<crate::Authorization>,
+	pub auth: Option<crate::neo_clients::Authorization>,
 }
 
 impl ConnectionDetails {
 	#[cfg(not(target_arch = "wasm32"))]
-	pub fn new(url: impl AsRef<str>, auth: Option<crate::Authorization>) -> Self {
+	pub fn new(url: impl AsRef<str>, auth: Option<crate::neo_clients::Authorization>) -> Self {
 		Self { url: url.as_ref().to_string(), auth }
 	}
 	#[cfg(target_arch = "wasm32")]
@@ -224,13 +231,15 @@ impl InFlight {
 #[derive(Debug)]
 pub(super) struct ActiveSub {
 	pub params: Box<RawValue>,
+	// The subscribed event and its filter, used to route notifications
+	pub subscription: Option<Subscription>,
 	pub channel: mpsc::UnboundedSender<Box<RawValue>>,
-	pub current_server_id: Option<U256>,
+	pub current_server_id: Option<String>,
 }
 
 impl ActiveSub {
 	pub(super) fn to_request(&self, id: u64) -> Request<'static, Box<RawValue>> {
-		Request::new(id, "neo_subscribe", self.params.clone())
+		Request::new(id, SUBSCRIBE_METHOD, self.params.clone())
 	}
 
 	pub(super) fn serialize_raw(&self, id: u64) -> Result<Box<RawValue>, serde_json::Error> {
@@ -305,4 +314,22 @@ mod test {
 		let a = "{\"jsonrpc\":\"2.0\",\"id\":1,\"result\":\"0xcd0c3e8af590364c09d0fa6a1210faf5\"}";
 		let _ = serde_json::from_str::<PubSubItem>(a).unwrap();
 	}
+
+	#[test]
+	fn it_desers_notifications() {
+		let a = r#"{"jsonrpc":"2.0","method":"block_added","params":[{"index":7}]}"#;
+		let params = match serde_json::from_str::<PubSubItem>(a).unwrap() {
+			PubSubItem::Notification { params } => params,
+			item => panic!("Not a notification: {item}"),
+		};
+		assert_eq!(params.method, "block_added");
+		assert_eq!(params.payload().unwrap().get(), r#"{"index":7}"#);
+
+		let a = r#"{"jsonrpc":"2.0","method":"event_missed","params":[]}"#;
+		let params = match serde_json::from_str::<PubSubItem>(a).unwrap() {
+			PubSubItem::Notification { params } => params,
+			item => panic!("Not a notification: {item}"),
+		};
+		assert!(params.payload().is_none());
+	}
 }
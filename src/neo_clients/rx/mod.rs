@@ -1 +1,3 @@
+pub use neo_rust_rx_trait::*;
+
 mod neo_rust_rx_trait;
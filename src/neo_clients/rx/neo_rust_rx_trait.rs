@@ -1,11 +1,94 @@
-#![allow(dead_code)]
+use std::pin::Pin;
 
-use crate::neo_protocol::NeoBlock;
-use futures::stream::Stream;
-use std::{error::Error, pin::Pin};
+use futures::{
+	future::{self, Either},
+	stream::{self, Stream, StreamExt},
+};
+use futures_timer::Delay;
+use primitive_types::U256;
+use thiserror::Error;
+use tracing::error;
+
+use crate::{
+	neo_clients::{
+		APITrait, BlockFilter, JsonRpcProvider, Notifications, ProviderError, RpcClient,
+		Subscription,
+	},
+	neo_protocol::{NeoBlock, RTransaction},
+};
+
+/// A stream of blocks, see [`NeoRustRxTrait`].
+pub type BlockStream<'a> =
+	Pin<Box<dyn Stream<Item = Result<NeoBlock, BlockStreamError>> + Send + 'a>>;
+
+/// A stream of transactions, see [`NeoRustRxTrait`].
+pub type TransactionStream<'a> =
+	Pin<Box<dyn Stream<Item = Result<RTransaction, BlockStreamError>> + Send + 'a>>;
+
+/// A failed request of a block or transaction stream.
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum BlockStreamError {
+	/// The block at `index` couldn't be fetched. The stream fetches it again.
+	#[error("Failed to fetch block {index}: {source}")]
+	Block { index: u32, source: ProviderError },
+	/// The block count couldn't be fetched.
+	#[error("Failed to fetch the block count: {0}")]
+	BlockCount(ProviderError),
+	/// The subscription to new blocks couldn't be installed. The stream subscribes again.
+	#[error("Failed to subscribe to new blocks: {0}")]
+	Subscription(ProviderError),
+}
+
+/// How the block streams of a client fetch their blocks.
+///
+/// The streams are lazy: blocks are only fetched while the stream is polled, and at most
+/// `concurrency` blocks are fetched ahead of the consumer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BlockStreamConfig {
+	/// How many blocks are fetched at the same time.
+	pub concurrency: usize,
+	/// Whether blocks are emitted in the requested order, or as soon as they are fetched.
+	pub ordered: bool,
+}
+
+impl Default for BlockStreamConfig {
+	fn default() -> Self {
+		Self { concurrency: 8, ordered: true }
+	}
+}
 
 /// The JSON-RPC client event API for Neo.
-pub(crate) trait NeoRustRxTrait {
+///
+/// The streams fetch blocks with JSON-RPC requests, over any provider. Failed requests are
+/// emitted as errors and retried after the polling interval of the client, so a consumer can
+/// decide whether to stop or to wait. No block is skipped: a block that couldn't be fetched is
+/// fetched again before the stream carries on.
+///
+/// To follow the chain, the streams subscribe to the blocks added to it if the provider supports
+/// subscriptions, like a websocket, and the node pushes them. Blocks are then only fetched to
+/// catch up. Over HTTP, the node is polled for new blocks.
+///
+/// # Examples
+///
+/// ```no_run
+/// use futures::StreamExt;
+/// use neo3::neo_clients::{BlockStreamConfig, HttpProvider, NeoRustRxTrait, RpcClient};
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let client = RpcClient::new(HttpProvider::new("https://testnet1.neo.org:443")?)
+///     .stream_config(BlockStreamConfig { concurrency: 16, ordered: true });
+///
+/// // Process the blocks from 1000 on, then follow the chain
+/// let mut blocks = client.catch_up_to_latest_and_subscribe_to_new_blocks_stream(1000, true);
+/// while let Some(block) = blocks.next().await {
+///     let block = block?;
+///     let transactions = block.transactions.map_or(0, |transactions| transactions.len());
+///     println!("Block {} has {} transactions", block.index, transactions);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub trait NeoRustRxTrait {
 	/// Create a stream that emits newly created blocks on the blockchain.
 	///
 	/// # Arguments
@@ -15,10 +98,7 @@ pub(crate) trait NeoRustRxTrait {
 	/// # Returns
 	///
 	/// A stream that emits all new blocks as they are added to the blockchain
-	fn block_stream(
-		&self,
-		full_transaction_objects: bool,
-	) -> Pin<Box<dyn Stream<Item = Result<NeoBlock, Box<dyn Error>>> + Send>>;
+	fn block_stream(&self, full_transaction_objects: bool) -> BlockStream<'_>;
 
 	/// Create a stream that emits all blocks from the blockchain contained within the requested range.
 	///
@@ -36,7 +116,9 @@ pub(crate) trait NeoRustRxTrait {
 		start_block: u32,
 		end_block: u32,
 		full_transaction_objects: bool,
-	) -> Pin<Box<dyn Stream<Item = Result<NeoBlock, Box<dyn Error>>> + Send>>;
+	) -> BlockStream<'_> {
+		self.replay_blocks_stream_ordered(start_block, end_block, full_transaction_objects, true)
+	}
 
 	/// Create a stream that emits all blocks from the blockchain contained within the requested range.
 	///
@@ -56,9 +138,9 @@ pub(crate) trait NeoRustRxTrait {
 		end_block: u32,
 		full_transaction_objects: bool,
 		ascending: bool,
-	) -> Pin<Box<dyn Stream<Item = Result<NeoBlock, Box<dyn Error>>> + Send>>;
+	) -> BlockStream<'_>;
 
-	/// Create a stream that emits all transactions from the blockchain starting with a provided block number.
+	/// Create a stream that emits all blocks from the blockchain starting with a provided block number.
 	/// Once it has replayed up to the most current block, the stream completes.
 	///
 	/// # Arguments
@@ -73,7 +155,7 @@ pub(crate) trait NeoRustRxTrait {
 		&self,
 		start_block: u32,
 		full_transaction_objects: bool,
-	) -> Pin<Box<dyn Stream<Item = Result<NeoBlock, Box<dyn Error>>> + Send>>;
+	) -> BlockStream<'_>;
 
 	/// Creates a stream that emits all blocks from the requested block number to the most current.
 	/// Once it has emitted the most current block, it starts emitting new blocks as they are created.
@@ -90,7 +172,7 @@ pub(crate) trait NeoRustRxTrait {
 		&self,
 		start_block: u32,
 		full_transaction_objects: bool,
-	) -> Pin<Box<dyn Stream<Item = Result<NeoBlock, Box<dyn Error>>> + Send>>;
+	) -> BlockStream<'_>;
 
 	/// Creates a stream that emits new blocks as they are created on the blockchain (starting from the latest block).
 	///
@@ -101,8 +183,595 @@ pub(crate) trait NeoRustRxTrait {
 	/// # Returns
 	///
 	/// A stream to emit all future blocks
-	fn subscribe_to_new_blocks_stream(
+	fn subscribe_to_new_blocks_stream(&self, full_transaction_objects: bool) -> BlockStream<'_>;
+
+	/// Creates a stream that emits the transactions of newly created blocks.
+	///
+	/// # Returns
+	///
+	/// A stream that emits all new transactions as they are added to the blockchain
+	fn transaction_stream(&self) -> TransactionStream<'_> {
+		transactions(self.block_stream(true))
+	}
+
+	/// Creates a stream that emits the transactions of the blocks within the requested range.
+	///
+	/// # Arguments
+	///
+	/// * `start_block` - The block number to commence with
+	/// * `end_block` - The block number to finish with
+	///
+	/// # Returns
+	///
+	/// A stream to emit these transactions
+	fn replay_transactions_stream(
 		&self,
+		start_block: u32,
+		end_block: u32,
+	) -> TransactionStream<'_> {
+		transactions(self.replay_blocks_stream(start_block, end_block, true))
+	}
+
+	/// Creates a stream that emits the transactions of all blocks from the requested block number
+	/// on, and then of new blocks as they are created.
+	///
+	/// # Arguments
+	///
+	/// * `start_block` - The block number to commence with
+	///
+	/// # Returns
+	///
+	/// A stream to emit all requested transactions and future transactions
+	fn catch_up_to_latest_and_subscribe_to_new_transactions_stream(
+		&self,
+		start_block: u32,
+	) -> TransactionStream<'_> {
+		transactions(self.catch_up_to_latest_and_subscribe_to_new_blocks_stream(start_block, true))
+	}
+}
+
+/// Flattens the blocks of `blocks` into their transactions.
+fn transactions(blocks: BlockStream<'_>) -> TransactionStream<'_> {
+	blocks
+		.flat_map(|block| match block {
+			Ok(block) => Either::Left(stream::iter(
+				block.transactions.unwrap_or_default().into_iter().map(Ok),
+			)),
+			Err(err) => Either::Right(stream::once(future::ready(Err(err)))),
+		})
+		.boxed()
+}
+
+impl<P: JsonRpcProvider> NeoRustRxTrait for RpcClient<P> {
+	fn block_stream(&self, full_transaction_objects: bool) -> BlockStream<'_> {
+		self.follow_chain(|count| count, full_transaction_objects)
+	}
+
+	fn replay_blocks_stream_ordered(
+		&self,
+		start_block: u32,
+		end_block: u32,
 		full_transaction_objects: bool,
-	) -> Pin<Box<dyn Stream<Item = Result<NeoBlock, Box<dyn Error>>> + Send>>;
+		ascending: bool,
+	) -> BlockStream<'_> {
+		let indices: Box<dyn Iterator<Item = u32> + Send> = if ascending {
+			Box::new(start_block..=end_block)
+		} else {
+			Box::new((start_block..=end_block).rev())
+		};
+		self.fetch_blocks(stream::iter(indices.map(Ok)), full_transaction_objects)
+	}
+
+	fn catch_up_to_latest_block_stream(
+		&self,
+		start_block: u32,
+		full_transaction_objects: bool,
+	) -> BlockStream<'_> {
+		stream::once(self.get_block_count())
+			.flat_map(move |count| match count {
+				Ok(count) if count > start_block => Either::Left(self.replay_blocks_stream(
+					start_block,
+					count - 1,
+					full_transaction_objects,
+				)),
+				Ok(_) => Either::Right(stream::iter(None)),
+				Err(err) =>
+					Either::Right(stream::iter(Some(Err(BlockStreamError::BlockCount(err))))),
+			})
+			.boxed()
+	}
+
+	fn catch_up_to_latest_and_subscribe_to_new_blocks_stream(
+		&self,
+		start_block: u32,
+		full_transaction_objects: bool,
+	) -> BlockStream<'_> {
+		self.follow_chain(move |_| start_block, full_transaction_objects)
+	}
+
+	fn subscribe_to_new_blocks_stream(&self, full_transaction_objects: bool) -> BlockStream<'_> {
+		self.follow_chain(|count| count.saturating_sub(1), full_transaction_objects)
+	}
+}
+
+/// The state of a stream of pushed blocks, see [`RpcClient::pushed_blocks`].
+struct PushedBlocks {
+	notifications: Option<Notifications>,
+	next: Option<u32>,
+	caught_up: bool,
+	failed: bool,
+}
+
+impl<P: JsonRpcProvider> RpcClient<P> {
+	/// Emits the blocks from `start(block_count)` on, where `block_count` is the block count when
+	/// the stream is first polled, and then the blocks added to the chain. The node pushes them if
+	/// the provider supports subscriptions, otherwise it is polled for them.
+	fn follow_chain(
+		&self,
+		start: impl Fn(u32) -> u32 + Copy + Send + 'static,
+		full_transaction_objects: bool,
+	) -> BlockStream<'_> {
+		if self.as_ref().supports_subscriptions() {
+			self.pushed_blocks(start, full_transaction_objects)
+		} else {
+			self.fetch_blocks(self.follow_blocks(start), full_transaction_objects)
+		}
+	}
+
+	/// Subscribes to the blocks added to the chain, and emits the blocks from
+	/// `start(block_count)` on, where `block_count` is the block count once subscribed.
+	///
+	/// The blocks before the first pushed block, and the blocks the node didn't push, are fetched.
+	/// If the subscription ends, the stream subscribes again after the polling interval.
+	fn pushed_blocks(
+		&self,
+		start: impl Fn(u32) -> u32 + Copy + Send + 'static,
+		full_transaction_objects: bool,
+	) -> BlockStream<'_> {
+		let interval = self.get_interval();
+		let subscription = if full_transaction_objects {
+			Subscription::BlockAdded(BlockFilter::new())
+		} else {
+			Subscription::HeaderOfAddedBlock(BlockFilter::new())
+		};
+		let state =
+			PushedBlocks { notifications: None, next: None, caught_up: false, failed: false };
+		stream::unfold(state, move |mut state| {
+			let subscription = subscription.clone();
+			async move {
+				loop {
+					if state.failed {
+						Delay::new(interval).await;
+						state.failed = false;
+					}
+					if state.notifications.is_none() {
+						match self.install_subscription(&subscription).await {
+							Ok(notifications) => {
+								state.notifications = Some(notifications);
+								state.caught_up = false;
+							},
+							Err(err) => {
+								state.failed = true;
+								let err = stream::once(future::ready(Err(
+									BlockStreamError::Subscription(err),
+								)));
+								return Some((err.boxed(), state));
+							},
+						}
+					}
+					if !state.caught_up {
+						match self.get_block_count().await {
+							Ok(count) => {
+								let next = state.next.unwrap_or_else(|| start(count));
+								state.next = Some(next.max(count));
+								state.caught_up = true;
+								if next < count {
+									let indices = stream::iter((next..count).map(Ok));
+									let blocks =
+										self.fetch_blocks(indices, full_transaction_objects);
+									return Some((blocks, state));
+								}
+							},
+							Err(err) => {
+								state.failed = true;
+								let err = stream::once(future::ready(Err(
+									BlockStreamError::BlockCount(err),
+								)));
+								return Some((err.boxed(), state));
+							},
+						}
+					}
+					let notification = match state.notifications.as_mut() {
+						Some(notifications) => notifications.next().await,
+						None => None,
+					};
+					let Some(notification) = notification else {
+						// The subscription ended
+						state.notifications = None;
+						state.failed = true;
+						continue;
+					};
+					let block: NeoBlock = match serde_json::from_str(notification.get()) {
+						Ok(block) => block,
+						Err(err) => {
+							error!("failed to deserialize block {:?}", err);
+							continue;
+						},
+					};
+					let (Ok(index), Some(next)) = (u32::try_from(block.index), state.next) else {
+						continue;
+					};
+					if index < next {
+						continue;
+					}
+					state.next = Some(index + 1);
+					let block = stream::once(future::ready(Ok(block)));
+					let blocks = if index == next {
+						block.boxed()
+					} else {
+						let indices = stream::iter((next..index).map(Ok));
+						self.fetch_blocks(indices, full_transaction_objects).chain(block).boxed()
+					};
+					return Some((blocks, state));
+				}
+			}
+		})
+		.flatten()
+		.boxed()
+	}
+
+	/// Installs `subscription` on the node and takes its notifications.
+	async fn install_subscription(
+		&self,
+		subscription: &Subscription,
+	) -> Result<Notifications, ProviderError> {
+		let id: U256 = self.request("subscribe", subscription.params()?).await?;
+		match self.as_ref().notifications(id) {
+			Some(notifications) => notifications.map_err(Into::into),
+			None => Err(ProviderError::UnsupportedRPC),
+		}
+	}
+
+	/// Fetches the blocks of `indices`, as many at a time as configured in the stream config.
+	///
+	/// A block that fails is fetched again, one request per polling interval, before the blocks
+	/// after it are emitted.
+	fn fetch_blocks<'a>(
+		&'a self,
+		indices: impl Stream<Item = Result<u32, BlockStreamError>> + Send + 'a,
+		full_transaction_objects: bool,
+	) -> BlockStream<'a> {
+		let config = self.get_stream_config();
+		let blocks = indices.map(move |index| async move {
+			let index = index?;
+			self.get_block_by_index(index, full_transaction_objects)
+				.await
+				.map_err(|source| BlockStreamError::Block { index, source })
+		});
+		let concurrency = config.concurrency.max(1);
+		let blocks = if config.ordered {
+			blocks.buffered(concurrency).boxed()
+		} else {
+			blocks.buffer_unordered(concurrency).boxed()
+		};
+		blocks
+			.flat_map(move |block| match block {
+				Err(BlockStreamError::Block { index, source }) =>
+					Either::Left(self.retry_block(index, source, full_transaction_objects)),
+				block => Either::Right(stream::once(future::ready(block))),
+			})
+			.boxed()
+	}
+
+	/// Emits the error `source` of the block at `index`, and fetches the block again until it
+	/// succeeds.
+	fn retry_block(
+		&self,
+		index: u32,
+		source: ProviderError,
+		full_transaction_objects: bool,
+	) -> impl Stream<Item = Result<NeoBlock, BlockStreamError>> + Send + '_ {
+		let interval = self.get_interval();
+		let first = stream::once(future::ready(Err(BlockStreamError::Block { index, source })));
+		let retries = stream::unfold(false, move |done| async move {
+			if done {
+				return None;
+			}
+			Delay::new(interval).await;
+			match self.get_block_by_index(index, full_transaction_objects).await {
+				Ok(block) => Some((Ok(block), true)),
+				Err(source) => Some((Err(BlockStreamError::Block { index, source }), false)),
+			}
+		});
+		first.chain(retries)
+	}
+
+	/// Emits the block indices from `start(block_count)` on, where `block_count` is the block
+	/// count when the stream is first polled. Once it has emitted the most current index, it polls
+	/// the node for new blocks.
+	fn follow_blocks(
+		&self,
+		start: impl Fn(u32) -> u32 + Copy + Send + 'static,
+	) -> impl Stream<Item = Result<u32, BlockStreamError>> + Send + '_ {
+		let interval = self.get_interval();
+		stream::unfold((None, None, false), move |(mut next, mut count, failed)| async move {
+			if failed {
+				Delay::new(interval).await;
+			}
+			loop {
+				if let (Some(index), Some(count)) = (next, count) {
+					if index < count {
+						return Some((Ok(index), (Some(index + 1), Some(count), false)));
+					}
+					Delay::new(interval).await;
+				}
+				match self.get_block_count().await {
+					Ok(new_count) => {
+						next = next.or(Some(start(new_count)));
+						count = Some(new_count);
+					},
+					Err(err) =>
+						return Some((Err(BlockStreamError::BlockCount(err)), (next, count, true))),
+				}
+			}
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::{
+		collections::VecDeque,
+		fmt::Debug,
+		sync::{
+			atomic::{AtomicU32, Ordering},
+			Arc, Mutex,
+		},
+		time::Duration,
+	};
+
+	use async_trait::async_trait;
+	use futures::channel::mpsc;
+	use primitive_types::H256;
+	use serde::{de::DeserializeOwned, Serialize};
+	use serde_json::{json, value::RawValue, Value};
+	use wiremock::{matchers::method, Mock, MockServer, Request, ResponseTemplate};
+
+	use super::*;
+	use crate::neo_clients::HttpProvider;
+
+	fn block(index: u32) -> NeoBlock {
+		let transaction = RTransaction::new(
+			H256::from_low_u64_be(index as u64),
+			0,
+			0,
+			0,
+			String::new(),
+			"0".to_string(),
+			"0".to_string(),
+			0,
+			vec![],
+			vec![],
+			String::new(),
+			vec![],
+		);
+		NeoBlock {
+			hash: H256::from_low_u64_be(index as u64),
+			size: 0,
+			version: 0,
+			prev_block_hash: H256::zero(),
+			merkle_root_hash: H256::zero(),
+			time: 0,
+			nonce: "0".to_string(),
+			index: index as i32,
+			primary: None,
+			next_consensus: String::new(),
+			witnesses: None,
+			transactions: Some(vec![transaction]),
+			confirmations: 0,
+			next_block_hash: None,
+		}
+	}
+
+	/// A node whose block count is `count`.
+	async fn node(count: Arc<AtomicU32>) -> (MockServer, RpcClient<HttpProvider>) {
+		let server = MockServer::start().await;
+		Mock::given(method("POST"))
+			.respond_with(move |request: &Request| {
+				let request: Value = serde_json::from_slice(&request.body).unwrap();
+				let count = count.load(Ordering::SeqCst);
+				let result = match request["method"].as_str().unwrap() {
+					"getblockcount" => json!(count),
+					_ => match request["params"][0].as_u64().unwrap() as u32 {
+						index if index < count => serde_json::to_value(block(index)).unwrap(),
+						_ => {
+							return ResponseTemplate::new(200).set_body_json(json!({
+								"jsonrpc": "2.0",
+								"id": request["id"],
+								"error": {"code": -100, "message": "Unknown block"}
+							}))
+						},
+					},
+				};
+				ResponseTemplate::new(200).set_body_json(json!({
+					"jsonrpc": "2.0",
+					"id": request["id"],
+					"result": result
+				}))
+			})
+			.mount(&server)
+			.await;
+		let client = RpcClient::new(HttpProvider::new(server.uri().as_str()).unwrap())
+			.interval(Duration::from_millis(10));
+		(server, client)
+	}
+
+	/// A node that pushes the blocks of its subscriptions, whose block count is `count`.
+	#[derive(Debug, Default)]
+	struct PushingNode {
+		count: AtomicU32,
+		subscriptions: Mutex<VecDeque<mpsc::UnboundedReceiver<Box<RawValue>>>>,
+		fetched: Mutex<Vec<u32>>,
+	}
+
+	impl PushingNode {
+		/// Adds a subscription, whose blocks are pushed with the returned sender.
+		fn subscription(&self) -> mpsc::UnboundedSender<Box<RawValue>> {
+			let (sender, receiver) = mpsc::unbounded();
+			self.subscriptions.lock().unwrap().push_back(receiver);
+			sender
+		}
+	}
+
+	#[async_trait]
+	impl JsonRpcProvider for PushingNode {
+		type Error = ProviderError;
+
+		async fn fetch<T, R>(&self, method: &str, params: T) -> Result<R, ProviderError>
+		where
+			T: Debug + Serialize + Send + Sync,
+			R: DeserializeOwned + Send,
+		{
+			let params = serde_json::to_value(params)?;
+			let result = match method {
+				"subscribe" => json!("0x1"),
+				"getblockcount" => json!(self.count.load(Ordering::SeqCst)),
+				"getblock" => {
+					let index = params[0].as_u64().unwrap() as u32;
+					self.fetched.lock().unwrap().push(index);
+					serde_json::to_value(block(index))?
+				},
+				_ => return Err(ProviderError::UnsupportedRPC),
+			};
+			Ok(serde_json::from_value(result)?)
+		}
+
+		fn supports_subscriptions(&self) -> bool {
+			true
+		}
+
+		fn notifications(&self, _id: U256) -> Option<Result<Notifications, ProviderError>> {
+			let receiver = self.subscriptions.lock().unwrap().pop_front();
+			Some(receiver.map(|receiver| receiver.boxed()).ok_or(ProviderError::UnsupportedRPC))
+		}
+	}
+
+	fn push(sender: &mpsc::UnboundedSender<Box<RawValue>>, index: u32) {
+		sender.unbounded_send(serde_json::value::to_raw_value(&block(index)).unwrap()).unwrap();
+	}
+
+	async fn indices(blocks: BlockStream<'_>) -> Vec<i32> {
+		blocks.map(|block| block.unwrap().index).collect().await
+	}
+
+	#[tokio::test]
+	async fn test_replay_blocks() {
+		let (_server, mut client) = node(Arc::new(AtomicU32::new(6))).await;
+		assert_eq!(indices(client.replay_blocks_stream(2, 5, false)).await, vec![2, 3, 4, 5]);
+		assert_eq!(
+			indices(client.replay_blocks_stream_ordered(2, 5, true, false)).await,
+			vec![5, 4, 3, 2]
+		);
+		assert_eq!(indices(client.catch_up_to_latest_block_stream(3, false)).await, vec![3, 4, 5]);
+		assert!(indices(client.catch_up_to_latest_block_stream(6, false)).await.is_empty());
+
+		let transactions: Vec<_> = client
+			.replay_transactions_stream(1, 2)
+			.map(|transaction| transaction.unwrap().hash)
+			.collect()
+			.await;
+		assert_eq!(transactions, vec![H256::from_low_u64_be(1), H256::from_low_u64_be(2)]);
+
+		client.set_stream_config(BlockStreamConfig { concurrency: 3, ordered: false });
+		let mut unordered = indices(client.replay_blocks_stream(0, 5, false)).await;
+		unordered.sort();
+		assert_eq!(unordered, vec![0, 1, 2, 3, 4, 5]);
+	}
+
+	#[tokio::test]
+	async fn test_failed_blocks_are_retried() {
+		let count = Arc::new(AtomicU32::new(6));
+		let (_server, mut client) = node(count.clone()).await;
+		client.set_stream_config(BlockStreamConfig { concurrency: 3, ordered: true });
+
+		let mut blocks = client.replay_blocks_stream(5, 6, false);
+		assert_eq!(blocks.next().await.unwrap().unwrap().index, 5);
+		for _ in 0..2 {
+			match blocks.next().await.unwrap() {
+				Err(BlockStreamError::Block { index, source }) => {
+					assert_eq!(index, 6);
+					assert!(matches!(source, ProviderError::JsonRpcError(_)));
+				},
+				block => panic!("Unexpected block {:?}", block),
+			}
+		}
+		count.store(7, Ordering::SeqCst);
+		assert_eq!(indices(blocks).await, vec![6]);
+	}
+
+	#[tokio::test]
+	async fn test_follow_blocks() {
+		let count = Arc::new(AtomicU32::new(3));
+		let (_server, client) = node(count.clone()).await;
+
+		let mut latest = client.subscribe_to_new_blocks_stream(false);
+		let mut new = client.block_stream(false);
+		let mut caught_up = client.catch_up_to_latest_and_subscribe_to_new_blocks_stream(1, false);
+		assert_eq!(latest.next().await.unwrap().unwrap().index, 2);
+		assert_eq!(caught_up.next().await.unwrap().unwrap().index, 1);
+		assert_eq!(caught_up.next().await.unwrap().unwrap().index, 2);
+
+		// The streams wait for new blocks
+		let pending = tokio::time::timeout(Duration::from_millis(50), latest.next()).await;
+		assert!(pending.is_err());
+		count.store(5, Ordering::SeqCst);
+		assert_eq!(latest.next().await.unwrap().unwrap().index, 3);
+		assert_eq!(latest.next().await.unwrap().unwrap().index, 4);
+		assert_eq!(caught_up.next().await.unwrap().unwrap().index, 3);
+
+		// The first new block is the first created after the stream is first polled
+		let pending = tokio::time::timeout(Duration::from_millis(50), new.next()).await;
+		assert!(pending.is_err());
+		count.store(6, Ordering::SeqCst);
+		assert_eq!(new.next().await.unwrap().unwrap().index, 5);
+	}
+
+	#[tokio::test]
+	async fn test_pushed_blocks() {
+		let node = Arc::new(PushingNode { count: AtomicU32::new(3), ..Default::default() });
+		let first = node.subscription();
+		let client = RpcClient::new(node.clone()).interval(Duration::from_millis(10));
+
+		// The blocks before the subscription are fetched, the new ones are pushed
+		let mut blocks = client.catch_up_to_latest_and_subscribe_to_new_blocks_stream(1, true);
+		assert_eq!(blocks.next().await.unwrap().unwrap().index, 1);
+		assert_eq!(blocks.next().await.unwrap().unwrap().index, 2);
+		let pending = tokio::time::timeout(Duration::from_millis(50), blocks.next()).await;
+		assert!(pending.is_err());
+		push(&first, 3);
+		assert_eq!(blocks.next().await.unwrap().unwrap().index, 3);
+
+		// Blocks already emitted are skipped, and blocks that weren't pushed are fetched
+		node.count.store(6, Ordering::SeqCst);
+		push(&first, 2);
+		push(&first, 5);
+		assert_eq!(blocks.next().await.unwrap().unwrap().index, 4);
+		assert_eq!(blocks.next().await.unwrap().unwrap().index, 5);
+
+		// The stream subscribes again when the subscription ends
+		let second = node.subscription();
+		drop(first);
+		push(&second, 6);
+		assert_eq!(blocks.next().await.unwrap().unwrap().index, 6);
+		assert_eq!(*node.fetched.lock().unwrap(), vec![1, 2, 4]);
+
+		// The transactions of the pushed blocks are streamed as well
+		let mut transactions = client.transaction_stream();
+		let third = node.subscription();
+		node.count.store(7, Ordering::SeqCst);
+		let pending = tokio::time::timeout(Duration::from_millis(50), transactions.next()).await;
+		assert!(pending.is_err());
+		push(&third, 7);
+		assert_eq!(transactions.next().await.unwrap().unwrap().hash, H256::from_low_u64_be(7));
+	}
 }
@@ -67,6 +67,12 @@ impl<'a> Decoder<'a> {
 		val
 	}
 
+	/// Reads an unsigned 8-bit integer from the byte slice, failing at the end of the slice.
+	pub fn try_read_u8(&mut self) -> Result<u8, CodecError> {
+		self.next()
+			.ok_or_else(|| CodecError::IndexOutOfBounds("Read beyond end of buffer".to_string()))
+	}
+
 	/// Reads an unsigned 16-bit integer from the byte slice.
 	pub fn read_u16(&mut self) -> Result<u16, CodecError> {
 		let bytes = self.read_bytes(2)?;
@@ -122,12 +128,12 @@ impl<'a> Decoder<'a> {
 	}
 
 	pub fn read_bigint(&mut self) -> Result<BigInt, CodecError> {
-		let byte = self.read_u8();
+		let byte = self.try_read_u8()?;
 
 		let negative = byte & 0x80 != 0;
 		let len = match byte {
 			0..=0x4b => 1,
-			0x4c => self.read_u8() as usize,
+			0x4c => self.try_read_u8()? as usize,
 			0x4d => self.read_u16()? as usize,
 			0x4e => self.read_u32()? as usize,
 			_ => return Err(CodecError::InvalidFormat),
@@ -151,7 +157,7 @@ impl<'a> Decoder<'a> {
 
 	/// Reads an encoded EC point from the byte slice.
 	pub fn read_encoded_ec_point(&mut self) -> Result<Vec<u8>, CodecError> {
-		let byte = self.read_u8();
+		let byte = self.try_read_u8()?;
 		match byte {
 			0x02 | 0x03 => self.read_bytes(32),
 			_ => Err(CodecError::InvalidEncoding("Invalid encoded EC point".to_string())),
@@ -160,7 +166,7 @@ impl<'a> Decoder<'a> {
 
 	/// Reads a byte slice of the given length from the byte slice.
 	pub fn read_bytes(&mut self, length: usize) -> Result<Vec<u8>, CodecError> {
-		if self.pointer + length > self.data.len() {
+		if self.pointer.checked_add(length).is_none_or(|end| end > self.data.len()) {
 			return Err(CodecError::IndexOutOfBounds("Read beyond end of buffer".to_string()));
 		}
 		let result = self.data[self.pointer..self.pointer + length].to_vec();
@@ -176,10 +182,10 @@ impl<'a> Decoder<'a> {
 
 	/// Reads a variable-length integer from the byte slice.
 	pub fn read_var_int(&mut self) -> Result<i64, CodecError> {
-		let first = self.read_u8();
+		let first = self.try_read_u8()?;
 		match first {
-			0xfd => self.read_i16().map(|v| v as i64),
-			0xfe => self.read_i32().map(|v| v as i64),
+			0xfd => self.read_u16().map(|v| v as i64),
+			0xfe => self.read_u32().map(|v| v as i64),
 			0xff => self.read_i64(),
 			_ => Ok(first as i64),
 		}
@@ -204,10 +210,10 @@ impl<'a> Decoder<'a> {
 
 	/// Reads a push byte slice from the byte slice.
 	pub fn read_push_bytes(&mut self) -> Result<Vec<u8>, CodecError> {
-		let opcode = self.read_u8();
+		let opcode = self.try_read_u8()?;
 		let len =
 			match OpCode::try_from(opcode)? {
-				OpCode::PushData1 => self.read_u8() as usize,
+				OpCode::PushData1 => self.try_read_u8()? as usize,
 				OpCode::PushData2 => self.read_i16().map_err(|e| {
 					CodecError::InvalidEncoding(format!("Failed to read i16: {}", e))
 				})? as usize,
@@ -222,7 +228,7 @@ impl<'a> Decoder<'a> {
 
 	/// Reads a push integer from the byte slice.
 	pub fn read_push_int(&mut self) -> Result<BigInt, CodecError> {
-		let byte = self.read_u8();
+		let byte = self.try_read_u8()?;
 
 		if (OpCode::PushM1 as u8..=OpCode::Push16 as u8).contains(&byte) {
 			return Ok(BigInt::from(byte as i8 - OpCode::Push0 as i8));
@@ -260,8 +266,8 @@ impl<'a> Decoder<'a> {
 
 	/// Reads a list of deserializable values from the byte slice.
 	pub fn read_serializable_list<T: NeoSerializable>(&mut self) -> Result<Vec<T>, CodecError> {
-		let len = self.read_var_int()?;
-		let mut list = Vec::with_capacity(len as usize);
+		let len = self.read_list_len()?;
+		let mut list = Vec::with_capacity(len.min(self.available()));
 		for _ in 0..len {
 			T::decode(self)
 				.map(|item| list.push(item))
@@ -273,19 +279,24 @@ impl<'a> Decoder<'a> {
 	pub fn read_serializable_list_var_bytes<T: NeoSerializable>(
 		&mut self,
 	) -> Result<Vec<T>, CodecError> {
-		let len = self.read_var_int()?;
+		let len = self.read_list_len()?;
 		let mut bytes_read = 0;
 		let offset = self.pointer;
-		let mut list = Vec::with_capacity(len as usize);
+		let mut list = Vec::with_capacity(len.min(self.available()));
 		while bytes_read < len {
 			T::decode(self)
 				.map(|item| list.push(item))
 				.map_err(|_| CodecError::InvalidFormat)?;
-			bytes_read = (self.pointer - offset) as i64;
+			bytes_read = self.pointer - offset;
 		}
 		Ok(list)
 	}
 
+	/// Reads the length of a list, which can't be negative.
+	fn read_list_len(&mut self) -> Result<usize, CodecError> {
+		usize::try_from(self.read_var_int()?).map_err(|_| CodecError::InvalidFormat)
+	}
+
 	pub fn mark(&mut self) {
 		self.marker = self.pointer;
 	}
@@ -403,4 +414,13 @@ mod tests {
 		let custom = [0x11, 0x33, 0x22, 0x8c, 0xae, 0x00, 0x00, 0x00, 0xff];
 		assert_eq!(Decoder::new(&custom).read_i64().unwrap(), 749_675_361_041);
 	}
+
+	#[test]
+	fn test_read_var_int() {
+		assert_eq!(Decoder::new(&[0xfc]).read_var_int().unwrap(), 0xfc);
+		assert_eq!(Decoder::new(&[0xfd, 0xff, 0xff]).read_var_int().unwrap(), 0xffff);
+		let max_u32 = [0xfe, 0xff, 0xff, 0xff, 0xff];
+		assert_eq!(Decoder::new(&max_u32).read_var_int().unwrap(), 0xffff_ffff);
+		assert!(Decoder::new(&[0xfd, 0xff]).read_var_int().is_err());
+	}
 }
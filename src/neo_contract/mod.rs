@@ -16,10 +16,12 @@
 //!   - GAS Token contract
 //!   - Policy contract
 //!   - RoleManagement contract
+//!   - Notary contract
 //!   - ContractManagement contract
 //!
 //! - **Token Standards**:
 //!   - NEP-17 fungible token standard (similar to Ethereum's ERC-20)
+//!   - Batched NEP-17 payouts to many recipients
 //!   - NEP-11 non-fungible token standard (similar to Ethereum's ERC-721)
 //!
 //! - **Advanced Contract Interactions**:
@@ -188,6 +190,8 @@ pub use name_service::*;
 pub use neo_token::*;
 pub use neo_uri::*;
 pub use nft_contract::*;
+pub use notary::*;
+pub use payout_builder::*;
 pub use policy_contract::*;
 pub use role_management::*;
 pub use traits::*;
@@ -202,6 +206,8 @@ mod name_service;
 mod neo_token;
 mod neo_uri;
 mod nft_contract;
+mod notary;
+mod payout_builder;
 mod policy_contract;
 mod role_management;
 mod traits;
@@ -0,0 +1,197 @@
+use async_trait::async_trait;
+use primitive_types::H160;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+	neo_builder::{AccountSigner, CallFlags, ScriptBuilder, TransactionBuilder},
+	neo_clients::{JsonRpcProvider, RpcClient},
+	neo_contract::{traits::SmartContractTrait, ContractError, GasToken},
+	neo_protocol::Account,
+	neo_types::{
+		serde_with_utils::{deserialize_script_hash, serialize_script_hash},
+		ContractParameter, ScriptHash,
+	},
+};
+
+/// The Notary native contract, which holds the GAS deposits paying for notary assisted
+/// transactions.
+///
+/// Deposits are made by transferring GAS to the contract, and can be withdrawn once they expire.
+/// See [`P2PNotaryRequest`](crate::neo_builder::P2PNotaryRequest) for the transactions they pay
+/// for.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Notary<'a, P: JsonRpcProvider> {
+	#[serde(deserialize_with = "deserialize_script_hash")]
+	#[serde(serialize_with = "serialize_script_hash")]
+	script_hash: ScriptHash,
+	#[serde(skip)]
+	provider: Option<&'a RpcClient<P>>,
+}
+
+impl<'a, P: JsonRpcProvider + 'static> Notary<'a, P> {
+	pub const NAME: &'static str = "Notary";
+
+	pub fn new(provider: Option<&'a RpcClient<P>>) -> Self {
+		Self { script_hash: Self::calc_native_contract_hash(Self::NAME).unwrap(), provider }
+	}
+
+	/// The GAS deposited by `account`, in datoshi.
+	pub async fn balance_of(&self, account: &H160) -> Result<i64, ContractError> {
+		let output = self.call_invoke_function("balanceOf", vec![account.into()], vec![]).await?;
+		self.throw_if_fault_state(&output)?;
+		output
+			.stack
+			.first()
+			.and_then(|item| item.as_int())
+			.ok_or_else(|| ContractError::UnexpectedReturnType("Int".to_string()))
+	}
+
+	/// The height until which the deposit of `account` is locked.
+	pub async fn expiration_of(&self, account: &H160) -> Result<u32, ContractError> {
+		Ok(self.call_function_returning_int("expirationOf", vec![account.into()]).await? as u32)
+	}
+
+	/// The maximum difference between the `NotValidBefore` height and the `ValidUntilBlock` of a
+	/// fallback transaction.
+	pub async fn get_max_not_valid_before_delta(&self) -> Result<u32, ContractError> {
+		Ok(self.call_function_returning_int("getMaxNotValidBeforeDelta", vec![]).await? as u32)
+	}
+
+	// State modifying methods
+
+	/// Deposits `amount` of GAS from `from` for `to`, or for `from` itself if `to` is `None`,
+	/// locked until the height `till`.
+	pub async fn deposit(
+		&self,
+		from: &Account,
+		to: Option<&H160>,
+		amount: i64,
+		till: u32,
+	) -> Result<TransactionBuilder<'_, P>, ContractError> {
+		if amount <= 0 {
+			return Err(ContractError::InvalidArgError(
+				"The deposit amount must be greater than 0.".to_string(),
+			));
+		}
+
+		let gas_token = GasToken::new(self.provider);
+		let data = ContractParameter::array(vec![
+			to.map_or_else(ContractParameter::any, ContractParameter::from),
+			till.into(),
+		]);
+		let script = ScriptBuilder::new()
+			.contract_call(
+				&gas_token.script_hash(),
+				"transfer",
+				&[
+					from.get_script_hash().into(),
+					self.script_hash.into(),
+					ContractParameter::integer(amount),
+					data,
+				],
+				Some(CallFlags::All),
+			)
+			.map_err(|err| ContractError::RuntimeError(err.to_string()))?
+			.to_bytes();
+
+		let mut builder = TransactionBuilder::new();
+		builder.set_script(Some(script));
+		builder
+			.set_signers(vec![AccountSigner::called_by_entry(from)
+				.map_err(|err| ContractError::InvalidAccount(err.to_string()))?
+				.into()])
+			.map_err(|err| ContractError::RuntimeError(err.to_string()))?;
+		Ok(builder)
+	}
+
+	/// Extends the lock of the deposit of `account` until the height `till`.
+	pub async fn lock_deposit_until(
+		&self,
+		account: &Account,
+		till: u32,
+	) -> Result<TransactionBuilder<'_, P>, ContractError> {
+		let mut builder = self
+			.invoke_function(
+				"lockDepositUntil",
+				vec![account.get_script_hash().into(), till.into()],
+			)
+			.await?;
+		builder
+			.set_signers(vec![AccountSigner::called_by_entry(account)
+				.map_err(|err| ContractError::InvalidAccount(err.to_string()))?
+				.into()])
+			.map_err(|err| ContractError::RuntimeError(err.to_string()))?;
+		Ok(builder)
+	}
+
+	/// Withdraws the expired deposit of `from` to `to`, or to `from` itself if `to` is `None`.
+	pub async fn withdraw(
+		&self,
+		from: &Account,
+		to: Option<&H160>,
+	) -> Result<TransactionBuilder<'_, P>, ContractError> {
+		let to = to.copied().unwrap_or_else(|| from.get_script_hash());
+		let mut builder = self
+			.invoke_function("withdraw", vec![from.get_script_hash().into(), to.into()])
+			.await?;
+		builder
+			.set_signers(vec![AccountSigner::called_by_entry(from)
+				.map_err(|err| ContractError::InvalidAccount(err.to_string()))?
+				.into()])
+			.map_err(|err| ContractError::RuntimeError(err.to_string()))?;
+		Ok(builder)
+	}
+}
+
+#[async_trait]
+impl<'a, P: JsonRpcProvider> SmartContractTrait<'a> for Notary<'a, P> {
+	type P = P;
+
+	fn script_hash(&self) -> H160 {
+		self.script_hash
+	}
+
+	fn set_script_hash(&mut self, script_hash: H160) {
+		self.script_hash = script_hash;
+	}
+
+	fn provider(&self) -> Option<&RpcClient<P>> {
+		self.provider
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{neo_builder::NOTARY_HASH, neo_clients::HttpProvider, neo_protocol::AccountTrait};
+
+	#[test]
+	fn test_script_hash_matches_notary_signer() {
+		let notary = Notary::<HttpProvider>::new(None);
+		assert_eq!(notary.script_hash(), *NOTARY_HASH);
+	}
+
+	#[tokio::test]
+	async fn test_deposit_transfers_gas_to_notary() {
+		let account = Account::create().unwrap();
+		let notary = Notary::<HttpProvider>::new(None);
+		let builder = notary.deposit(&account, None, 1_0000_0000, 1000).await.unwrap();
+
+		let expected = ScriptBuilder::new()
+			.contract_call(
+				&GasToken::<HttpProvider>::new(None).script_hash(),
+				"transfer",
+				&[
+					account.get_script_hash().into(),
+					notary.script_hash().into(),
+					ContractParameter::integer(1_0000_0000),
+					ContractParameter::array(vec![ContractParameter::any(), 1000u32.into()]),
+				],
+				Some(CallFlags::All),
+			)
+			.unwrap()
+			.to_bytes();
+		assert_eq!(builder.script(), &Some(expected));
+		assert!(notary.deposit(&account, None, 0, 1000).await.is_err());
+	}
+}
@@ -0,0 +1,426 @@
+use std::collections::VecDeque;
+
+use primitive_types::{H160, H256};
+use tracing::warn;
+
+use crate::{
+	builder::{Signer, TransactionRequest, TransactionSender},
+	config::NeoConstants,
+	neo_clients::{APITrait, JsonRpcProvider, RpcClient},
+	neo_contract::{ContractError, FungibleTokenContract, FungibleTokenTrait},
+	neo_crypto::utils::ToHexString,
+	neo_types::ScriptHashExtension,
+	Bytes, ContractParameter, OpCode,
+};
+
+/// A transfer of `amount` of the NEP-17 token `token` to `to`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Payment {
+	pub token: H160,
+	pub to: H160,
+	pub amount: i64,
+	pub data: Option<ContractParameter>,
+}
+
+/// Payments that are made by a single transaction.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PayoutBatch {
+	pub payments: Vec<Payment>,
+	/// The script calling `transfer` for each payment, aborting if any of them returns `false`.
+	pub script: Bytes,
+	/// The GAS consumed by the script when it was tested, in datoshi.
+	pub system_fee: i64,
+}
+
+/// The transactions a payout was sent with.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PayoutReport {
+	/// The hash of each transaction that was sent, with the payments it makes.
+	pub transactions: Vec<(H256, PayoutBatch)>,
+	/// The batches that weren't sent because sending a previous batch failed.
+	pub unsent: Vec<PayoutBatch>,
+}
+
+/// Pays many recipients, in one or more NEP-17 tokens, with as few transactions as possible.
+///
+/// The `transfer` calls are packed into scripts that `ASSERT` the result of each call, so a
+/// transaction either makes all its payments or none. The payments are split into batches whose
+/// scripts stay below [`max_script_size`](Self::set_max_script_size), and batches whose test
+/// invocation faults or consumes more than [`max_system_fee`](Self::set_max_system_fee) are
+/// split further.
+///
+/// # Examples
+///
+/// ```no_run
+/// use neo3::neo_builder::{AccountSigner, TransactionSender, TransactionSenderConfig};
+/// use neo3::neo_clients::{HttpProvider, RpcClient};
+/// use neo3::neo_contract::PayoutBuilder;
+/// use neo3::neo_protocol::{Account, AccountTrait};
+/// use primitive_types::H160;
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let client = RpcClient::new(HttpProvider::new("http://localhost:10332")?);
+/// let account = Account::create()?;
+/// let gas = H160::from_slice(&hex::decode("d2a4cff31913016155e38e474a2c06d08be276cf")?);
+///
+/// let mut payout =
+///     PayoutBuilder::new(&client, vec![AccountSigner::called_by_entry(&account)?.into()]);
+/// for recipient in [H160::repeat_byte(0x01), H160::repeat_byte(0x02)] {
+///     payout.pay(&gas, &recipient, 1_0000_0000, None)?;
+/// }
+///
+/// let mut sender = TransactionSender::new(&client, TransactionSenderConfig::default());
+/// let report = payout.send(&mut sender).await?;
+/// println!("{:?}", report.transactions_of(&H160::repeat_byte(0x01)));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct PayoutBuilder<'a, P: JsonRpcProvider + 'static> {
+	client: &'a RpcClient<P>,
+	signers: Vec<Signer>,
+	payments: Vec<Payment>,
+	max_script_size: usize,
+	max_system_fee: i64,
+}
+
+impl<'a, P: JsonRpcProvider + 'static> PayoutBuilder<'a, P> {
+	/// The size reserved for the header, signers and witnesses of a transaction by the default
+	/// maximum script size.
+	pub const RESERVED_SIZE: usize = 4096;
+	/// The default maximum system fee of a batch, which is the default `MaxGasInvoke` of the RPC
+	/// server.
+	pub const DEFAULT_MAX_SYSTEM_FEE: i64 = 10_0000_0000;
+
+	/// Creates a payout from the first of `signers`, which sign each of its transactions.
+	pub fn new(client: &'a RpcClient<P>, signers: Vec<Signer>) -> Self {
+		Self {
+			client,
+			signers,
+			payments: vec![],
+			max_script_size: NeoConstants::MAX_TRANSACTION_SIZE as usize - Self::RESERVED_SIZE,
+			max_system_fee: Self::DEFAULT_MAX_SYSTEM_FEE,
+		}
+	}
+
+	pub fn payments(&self) -> &[Payment] {
+		&self.payments
+	}
+
+	/// Adds a transfer of `amount` of `token` to `to`.
+	pub fn pay(
+		&mut self,
+		token: &H160,
+		to: &H160,
+		amount: i64,
+		data: Option<ContractParameter>,
+	) -> Result<&mut Self, ContractError> {
+		if amount < 0 {
+			return Err(ContractError::InvalidArgError(
+				"The amount must be greater than or equal to 0.".to_string(),
+			));
+		}
+		self.payments.push(Payment { token: *token, to: *to, amount, data });
+		Ok(self)
+	}
+
+	pub fn set_max_script_size(&mut self, max_script_size: usize) -> &mut Self {
+		self.max_script_size = max_script_size;
+		self
+	}
+
+	pub fn set_max_system_fee(&mut self, max_system_fee: i64) -> &mut Self {
+		self.max_system_fee = max_system_fee;
+		self
+	}
+
+	/// Splits the payments into batches and tests the script of each batch.
+	///
+	/// Each batch is tested against the current state of the chain on its own, so batches that
+	/// only succeed if a previous batch failed aren't detected.
+	pub async fn build(&self) -> Result<Vec<PayoutBatch>, ContractError> {
+		let from = self.sender()?;
+
+		let mut chunks: VecDeque<Vec<(Payment, Bytes)>> = VecDeque::new();
+		let mut chunk = vec![];
+		let mut chunk_size = 0;
+		for payment in &self.payments {
+			let script = self.transfer_script(&from, payment).await?;
+			if script.len() > self.max_script_size {
+				return Err(ContractError::InvalidArgError(format!(
+					"The transfer to {} is larger than the maximum script size",
+					payment.to.to_address()
+				)));
+			}
+			if chunk_size + script.len() > self.max_script_size {
+				chunks.push_back(std::mem::take(&mut chunk));
+				chunk_size = 0;
+			}
+			chunk_size += script.len();
+			chunk.push((payment.clone(), script));
+		}
+		if !chunk.is_empty() {
+			chunks.push_back(chunk);
+		}
+
+		let mut batches = vec![];
+		while let Some(chunk) = chunks.pop_front() {
+			let script: Bytes = chunk.iter().flat_map(|(_, script)| script.clone()).collect();
+			let result =
+				self.client.invoke_script(script.to_hex_string(), self.signers.clone()).await?;
+			let system_fee = result.gas_consumed.parse::<i64>().map_err(|_| {
+				ContractError::InvalidResponse(format!(
+					"Invalid GAS consumed {}",
+					result.gas_consumed
+				))
+			})?;
+
+			if !result.has_state_fault() && system_fee <= self.max_system_fee {
+				let payments = chunk.into_iter().map(|(payment, _)| payment).collect();
+				batches.push(PayoutBatch { payments, script, system_fee });
+				continue;
+			}
+			if chunk.len() == 1 {
+				let payment = &chunk[0].0;
+				return Err(if result.has_state_fault() {
+					ContractError::InvocationFailed(format!(
+						"The transfer to {} faulted: {}",
+						payment.to.to_address(),
+						result.exception.unwrap_or_default()
+					))
+				} else {
+					ContractError::InvalidStateError(format!(
+						"The transfer to {} consumes {} datoshi of GAS, more than the maximum \
+						 system fee",
+						payment.to.to_address(),
+						system_fee
+					))
+				});
+			}
+
+			// Test both halves again, in order
+			let mut first = chunk;
+			let second = first.split_off(first.len() / 2);
+			chunks.push_front(second);
+			chunks.push_front(first);
+		}
+		Ok(batches)
+	}
+
+	/// Builds the batches and submits a transaction for each of them to `sender`.
+	///
+	/// If a batch can't be submitted, it and the following batches are reported as unsent.
+	pub async fn send(
+		&self,
+		sender: &mut TransactionSender<'_, P>,
+	) -> Result<PayoutReport, ContractError> {
+		let mut report = PayoutReport::default();
+		let mut batches = self.build().await?.into_iter();
+		for batch in batches.by_ref() {
+			let request = TransactionRequest::new(batch.script.clone(), self.signers.clone());
+			match sender.submit(request).await {
+				Ok(hash) => report.transactions.push((hash, batch)),
+				Err(err) => {
+					warn!("Failed to send a payout batch: {}", err);
+					report.unsent.push(batch);
+					break;
+				},
+			}
+		}
+		report.unsent.extend(batches);
+		Ok(report)
+	}
+
+	fn sender(&self) -> Result<H160, ContractError> {
+		self.signers.first().map(|signer| *signer.get_signer_hash()).ok_or_else(|| {
+			ContractError::InvalidStateError("A payout needs at least one signer".to_string())
+		})
+	}
+
+	async fn transfer_script(
+		&self,
+		from: &H160,
+		payment: &Payment,
+	) -> Result<Bytes, ContractError> {
+		let token = FungibleTokenContract::new(&payment.token, Some(self.client));
+		let mut script = token
+			.build_transfer_script(from, &payment.to, payment.amount, payment.data.clone())
+			.await?;
+		script.push(OpCode::Assert as u8);
+		Ok(script)
+	}
+}
+
+impl PayoutReport {
+	/// The transactions paying `recipient`.
+	pub fn transactions_of(&self, recipient: &H160) -> Vec<H256> {
+		self.transactions
+			.iter()
+			.filter(|(_, batch)| batch.payments.iter().any(|payment| payment.to == *recipient))
+			.map(|(hash, _)| *hash)
+			.collect()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json::json;
+	use wiremock::{
+		matchers::{body_partial_json, method},
+		Mock, Request, ResponseTemplate,
+	};
+
+	use super::*;
+	use crate::{
+		builder::{AccountSigner, TransactionSenderConfig},
+		neo_clients::MockClient,
+		neo_crypto::utils::FromBase64String,
+		neo_protocol::{Account, AccountTrait},
+	};
+
+	const TOKEN: H160 = H160::repeat_byte(0xcf);
+
+	/// Responds to `invokescript` with 10 datoshi of GAS per transfer in the script.
+	async fn mock_invoke_script(mock_client: &MockClient) {
+		Mock::given(method("POST"))
+			.and(body_partial_json(json!({ "method": "invokescript" })))
+			.respond_with(|request: &Request| {
+				let body: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+				let script = body["params"][0].as_str().unwrap().from_base64_string().unwrap();
+				let transfers = script.windows(8).filter(|window| *window == b"transfer").count();
+				ResponseTemplate::new(200).set_body_json(json!({
+					"jsonrpc": "2.0",
+					"id": 1,
+					"result": {
+						"script": "",
+						"state": "HALT",
+						"gasconsumed": (transfers * 10).to_string(),
+						"exception": null,
+						"stack": [{ "type": "Boolean", "value": true }]
+					}
+				}))
+			})
+			.mount(mock_client.server())
+			.await;
+	}
+
+	fn payout<'a, P: JsonRpcProvider>(
+		client: &'a RpcClient<P>,
+		account: &Account,
+		recipients: u8,
+	) -> PayoutBuilder<'a, P> {
+		let mut payout = PayoutBuilder::new(
+			client,
+			vec![AccountSigner::called_by_entry(account).unwrap().into()],
+		);
+		for recipient in 1..=recipients {
+			payout
+				.pay(&TOKEN, &H160::repeat_byte(recipient), recipient as i64, None)
+				.unwrap();
+		}
+		payout
+	}
+
+	#[tokio::test]
+	async fn test_build_splits_by_size() {
+		let mock_client = MockClient::new().await;
+		mock_invoke_script(&mock_client).await;
+		let client = mock_client.into_client();
+		let account = Account::create().unwrap();
+
+		let mut payout = payout(&client, &account, 5);
+		let batches = payout.build().await.unwrap();
+		assert_eq!(batches.len(), 1);
+		assert_eq!(batches[0].payments, payout.payments());
+		assert_eq!(batches[0].system_fee, 50);
+		assert_eq!(batches[0].script.iter().filter(|op| **op == OpCode::Assert as u8).count(), 5);
+
+		let transfer_size = batches[0].script.len() / 5;
+		payout.set_max_script_size(transfer_size * 2);
+		let batches = payout.build().await.unwrap();
+		let sizes: Vec<usize> = batches.iter().map(|batch| batch.payments.len()).collect();
+		assert_eq!(sizes, vec![2, 2, 1]);
+		assert!(batches.iter().all(|batch| batch.script.len() <= transfer_size * 2));
+
+		payout.set_max_script_size(transfer_size - 1);
+		assert!(payout.build().await.is_err());
+
+		// 100 GAS doesn't fit into an i32
+		let amount = 100_0000_0000i64;
+		let mut payout = PayoutBuilder::new(&client, payout.signers.clone());
+		payout.pay(&TOKEN, &H160::repeat_byte(1), amount, None).unwrap();
+		let script = &payout.build().await.unwrap()[0].script;
+		let push = [&[OpCode::PushInt64 as u8], &amount.to_le_bytes()[..]].concat();
+		assert!(script.windows(push.len()).any(|window| window == push));
+	}
+
+	#[tokio::test]
+	async fn test_build_splits_by_system_fee() {
+		let mock_client = MockClient::new().await;
+		mock_invoke_script(&mock_client).await;
+		let client = mock_client.into_client();
+		let account = Account::create().unwrap();
+
+		let mut payout = payout(&client, &account, 5);
+		payout.set_max_system_fee(25);
+		let batches = payout.build().await.unwrap();
+		let recipients: Vec<Vec<H160>> = batches
+			.iter()
+			.map(|batch| batch.payments.iter().map(|payment| payment.to).collect())
+			.collect();
+		assert_eq!(
+			recipients,
+			vec![
+				vec![H160::repeat_byte(1), H160::repeat_byte(2)],
+				vec![H160::repeat_byte(3)],
+				vec![H160::repeat_byte(4), H160::repeat_byte(5)],
+			]
+		);
+
+		payout.set_max_system_fee(5);
+		assert!(payout.build().await.is_err());
+		assert!(PayoutBuilder::new(&client, vec![]).build().await.is_err());
+	}
+
+	#[tokio::test]
+	async fn test_send() {
+		let mut mock_client = MockClient::new().await;
+		mock_client
+			.mock_response_with_file_ignore_param("getblockcount", "getblockcount_1000.json")
+			.await;
+		mock_client
+			.mock_response_with_file_ignore_param("calculatenetworkfee", "calculatenetworkfee.json")
+			.await;
+		mock_client
+			.mock_response_with_file_ignore_param("sendrawtransaction", "sendrawtransaction.json")
+			.await;
+		mock_client
+			.mock_response_ignore_param(
+				"invokefunction",
+				json!({
+					"script": "",
+					"state": "HALT",
+					"gasconsumed": "0",
+					"exception": null,
+					"stack": [{ "type": "Integer", "value": "100000000" }]
+				}),
+			)
+			.await;
+		mock_client.mount_mocks().await;
+		mock_invoke_script(&mock_client).await;
+		let client = mock_client.into_client();
+		let account = Account::create().unwrap();
+
+		let mut payout = payout(&client, &account, 3);
+		payout.set_max_system_fee(20);
+		let mut sender = TransactionSender::new(&client, TransactionSenderConfig::default());
+		let report = payout.send(&mut sender).await.unwrap();
+
+		assert_eq!(report.transactions.len(), 2);
+		assert!(report.unsent.is_empty());
+		let hashes: Vec<H256> = sender.pending().iter().map(|pending| pending.hash()).collect();
+		assert_eq!(report.transactions_of(&H160::repeat_byte(1)), vec![hashes[0]]);
+		assert_eq!(report.transactions_of(&H160::repeat_byte(3)), vec![hashes[1]]);
+		assert!(report.transactions_of(&H160::repeat_byte(4)).is_empty());
+	}
+}
@@ -3,12 +3,12 @@ use primitive_types::H160;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-	neo_builder::TransactionBuilder,
+	neo_builder::{NetworkFeeCalculator, TransactionAttribute, TransactionBuilder},
 	neo_clients::{JsonRpcProvider, RpcClient},
 	neo_contract::{traits::SmartContractTrait, ContractError},
 	neo_types::{
 		serde_with_utils::{deserialize_script_hash, serialize_script_hash},
-		ScriptHash,
+		ContractParameter, ScriptHash,
 	},
 	ScriptHashExtension,
 };
@@ -42,10 +42,33 @@ impl<'a, P: JsonRpcProvider + 'static> PolicyContract<'a, P> {
 		self.call_function_returning_int("getStoragePrice", vec![]).await
 	}
 
+	pub async fn get_attribute_fee(&self, attribute_type: u8) -> Result<i32, ContractError> {
+		self.call_function_returning_int(
+			"getAttributeFee",
+			vec![ContractParameter::integer(attribute_type as i64)],
+		)
+		.await
+	}
+
 	pub async fn is_blocked(&self, script_hash: &H160) -> Result<bool, ContractError> {
 		self.call_function_returning_bool("isBlocked", vec![script_hash.into()]).await
 	}
 
+	/// Fetches the fee per byte, the exec fee factor and the attribute fees once, to calculate
+	/// network fees offline.
+	pub async fn network_fee_calculator(&self) -> Result<NetworkFeeCalculator, ContractError> {
+		let fee_per_byte = self.get_fee_per_byte().await?;
+		let exec_fee_factor = self.get_exec_fee_factor().await?;
+		let notary_assisted_fee =
+			self.get_attribute_fee(TransactionAttribute::NOTARY_ASSISTED).await?;
+		let conflicts_fee = self.get_attribute_fee(TransactionAttribute::CONFLICTS).await?;
+		let mut calculator = NetworkFeeCalculator::new(fee_per_byte as i64, exec_fee_factor as i64);
+		calculator
+			.set_notary_assisted_fee(notary_assisted_fee as i64)
+			.set_conflicts_fee(conflicts_fee as i64);
+		Ok(calculator)
+	}
+
 	// State modifying methods
 
 	pub async fn set_fee_per_byte(
@@ -120,6 +120,7 @@ pub enum Role {
 	PriceFeedOracle,
 	FeeCollector,
 	ComplianceOfficer,
+	P2PNotary = 0x20,
 }
 
 impl Role {
@@ -80,7 +80,7 @@ mod tests {
 
 		// Compute checksum (first 4 bytes of double SHA256)
 		use crate::crypto::HashableForVec;
-		let checksum = file_without_checksum.hash256();
+		let checksum = file_without_checksum.hash256().hash256();
 		writer.write_bytes(&checksum[..4]);
 
 		// Deserialize the properly formatted bytes
@@ -356,4 +356,141 @@ mod tests {
 		// Verify the script contains expected elements
 		assert!(script.len() > 20); // Should be more than just empty
 	}
+
+	#[tokio::test]
+	async fn test_invoke_function_script_call_flags() {
+		use crate::{
+			neo_builder::{AccountSigner, CallFlags, ScriptBuilder},
+			neo_contract::{FungibleTokenTrait, GasToken, SmartContractTrait},
+			neo_types::NeoVMStateType,
+			neo_vm::LocalBlockchain,
+		};
+
+		let chain = LocalBlockchain::new();
+		let validators = chain.settings().validators_address();
+		let alice = H160::repeat_byte(0xaa);
+		let gas = GasToken::<providers::HttpProvider>::new(None);
+		let signers: Vec<Signer> =
+			vec![AccountSigner::called_by_entry_hash160(validators).unwrap().into()];
+
+		let script = gas
+			.build_transfer_script(&validators, &alice, 10, Some(ContractParameter::any()))
+			.await
+			.unwrap();
+		let params = [
+			ContractParameter::h160(&validators),
+			ContractParameter::h160(&alice),
+			ContractParameter::integer(10),
+			ContractParameter::any(),
+		];
+		let call = |flags| {
+			ScriptBuilder::new()
+				.contract_call(&gas.script_hash(), "transfer", &params, Some(flags))
+				.unwrap()
+				.to_bytes()
+		};
+		assert_eq!(script, call(CallFlags::All));
+
+		let result = chain.test_invoke_script(script, signers.clone());
+		assert_eq!(result.state, NeoVMStateType::Halt);
+		assert_eq!(result.stack, vec![StackItem::Boolean { value: true }]);
+		// A transfer reads balances and emits a notification, without flags it faults
+		let result = chain.test_invoke_script(call(CallFlags::None), signers);
+		assert_eq!(result.state, NeoVMStateType::Fault);
+	}
+
+	/// The script of `NEO.transfer` and `NEO.vote` as neo-cli and the other SDKs build them:
+	/// arguments pushed in reverse order and packed, `CallFlags::All`, then the method and the
+	/// contract hash in little-endian order.
+	#[tokio::test]
+	async fn test_scripts_match_reference_encoding() {
+		use crate::{
+			config::TestConstants,
+			neo_contract::{FungibleTokenTrait, NeoToken},
+			neo_crypto::Secp256r1PublicKey,
+		};
+
+		const NEO_LE: &str = "f563ea40bc283d4d0e05c48ea305b3f2a07340ef";
+		const CALL: &str = "41627d5b52";
+		let neo = NeoToken::<providers::HttpProvider>::new(None);
+		let account = H160::from_address(TestConstants::DEFAULT_ACCOUNT_ADDRESS).unwrap();
+		let account_le = "0d165c9899c38bbf5991c5e47b04937258caec69";
+		let to = H160::from_str("0x969a77db482f74ce27105f760efa139223431394").unwrap();
+		let to_le = "941343239213fa0e765f1027ce742f48db779a96";
+
+		let script = neo.build_transfer_script(&account, &to, 1, None).await.unwrap();
+		assert_eq!(
+			hex::encode(script),
+			format!(
+				"0b110c14{}0c14{}14c01f0c087472616e736665720c14{}{}",
+				to_le, account_le, NEO_LE, CALL
+			)
+		);
+
+		let key =
+			Secp256r1PublicKey::from_encoded(TestConstants::DEFAULT_ACCOUNT_PUBLIC_KEY).unwrap();
+		let script = neo.build_vote_script(&account, Some(&key)).await.unwrap();
+		assert_eq!(
+			hex::encode(script),
+			format!(
+				"0c21{}0c14{}12c01f0c04766f74650c14{}{}",
+				TestConstants::DEFAULT_ACCOUNT_PUBLIC_KEY,
+				account_le,
+				NEO_LE,
+				CALL
+			)
+		);
+	}
+
+	#[test]
+	fn test_transfer_script_executes_in_parameter_order() {
+		use crate::{
+			neo_builder::{AccountSigner, ScriptBuilder},
+			neo_types::NeoVMStateType,
+			neo_vm::{LocalBlockchain, NativeContract},
+		};
+
+		let mut chain = LocalBlockchain::new();
+		let validators = chain.settings().validators_address();
+		let alice = H160::from_str("0x969a77db482f74ce27105f760efa139223431394").unwrap();
+		let script = ScriptBuilder::new()
+			.contract_call(
+				&NativeContract::GasToken.hash(),
+				"transfer",
+				&[
+					ContractParameter::h160(&validators),
+					ContractParameter::h160(&alice),
+					ContractParameter::integer(10),
+					ContractParameter::any(),
+				],
+				None,
+			)
+			.unwrap()
+			.to_bytes();
+
+		let signers = vec![AccountSigner::called_by_entry_hash160(validators).unwrap().into()];
+		let result = chain.invoke_script(script, signers).unwrap();
+		assert_eq!(result.state, NeoVMStateType::Halt, "{:?}", result.exception);
+		assert_eq!(
+			chain.balance_of(NativeContract::GasToken, &alice),
+			num_bigint::BigInt::from(10)
+		);
+	}
+
+	#[test]
+	fn test_calc_contract_hash() {
+		use crate::{
+			config::TestConstants,
+			neo_contract::{NeoToken, SmartContractTrait},
+		};
+
+		type Neo = NeoToken<'static, providers::HttpProvider>;
+		let neo = Neo::calc_native_contract_hash("NeoToken").unwrap();
+		assert_eq!(neo, H160::from_str("0xef4073a0f2b305a38ec4050e4d3d28bc40ea63f5").unwrap());
+
+		// The sender is pushed in little-endian order, like in `ContractManagement`
+		let sender = H160::from_str(TestConstants::DEFAULT_ACCOUNT_SCRIPT_HASH).unwrap();
+		let hash = Neo::calc_contract_hash(sender, 0, "Test").unwrap();
+		assert_eq!(hash, H160::from_str("0xbbc34dddc2cfd75fce7598786054dcd9e0e5bbdd").unwrap());
+	}
 }
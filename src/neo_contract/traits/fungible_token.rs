@@ -9,6 +9,7 @@ use crate::{
 use async_trait::async_trait;
 use primitive_types::H160;
 
+#[allow(clippy::double_must_use)]
 #[async_trait]
 pub trait FungibleTokenTrait<'a, P: JsonRpcProvider>: TokenTrait<'a, P> {
 	const BALANCE_OF: &'static str = "balanceOf";
@@ -64,7 +65,8 @@ pub trait FungibleTokenTrait<'a, P: JsonRpcProvider>: TokenTrait<'a, P> {
 			));
 		}
 
-		let transfer_script = self.build_transfer_script(from, to, amount, data).await.unwrap();
+		let transfer_script =
+			self.build_transfer_script(from, to, amount.into(), data).await.unwrap();
 		let mut builder = TransactionBuilder::new();
 		builder.set_script(Some(transfer_script));
 		Ok(builder)
@@ -74,12 +76,17 @@ pub trait FungibleTokenTrait<'a, P: JsonRpcProvider>: TokenTrait<'a, P> {
 		&self,
 		from: &ScriptHash,
 		to: &ScriptHash,
-		amount: i32,
+		amount: i64,
 		data: Option<ContractParameter>,
 	) -> Result<Bytes, ContractError> {
 		self.build_invoke_function_script(
 			<FungibleTokenContract<P> as FungibleTokenTrait<P>>::TRANSFER,
-			vec![from.into(), to.into(), amount.into(), data.unwrap()],
+			vec![
+				from.into(),
+				to.into(),
+				ContractParameter::integer(amount),
+				data.unwrap_or_else(ContractParameter::any),
+			],
 		)
 		.await
 	}
@@ -10,6 +10,7 @@ use crate::{
 use async_trait::async_trait;
 use primitive_types::H160;
 
+#[allow(clippy::double_must_use)]
 #[async_trait]
 pub trait NonFungibleTokenTrait<'a, P: JsonRpcProvider>: TokenTrait<'a, P> + Send {
 	const OWNER_OF: &'static str = "ownerOf";
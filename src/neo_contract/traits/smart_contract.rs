@@ -2,7 +2,6 @@ use std::sync::Arc;
 
 use crate::neo_crypto::utils::ToHexString;
 use async_trait::async_trait;
-use num_bigint::BigInt;
 use primitive_types::H160;
 
 // Replace prelude imports with specific types
@@ -11,14 +10,16 @@ use crate::{
 	neo_clients::{APITrait, JsonRpcProvider, RpcClient},
 	neo_contract::{ContractError, NeoIterator},
 	neo_types::{
-		Bytes, ContractManifest, ContractParameter, InvocationResult, OpCode, ScriptHash, StackItem,
+		Bytes, ContractManifest, ContractParameter, InvocationResult, ScriptHash, StackItem,
 	},
+	neo_vm,
 	ScriptHashExtension,
 };
 
 // Import transaction types from the correct modules
 use crate::neo_builder::{Signer, TransactionBuilder};
 
+#[allow(clippy::double_must_use)]
 #[async_trait]
 pub trait SmartContractTrait<'a>: Send + Sync {
 	const DEFAULT_ITERATOR_COUNT: usize = 100;
@@ -63,8 +64,10 @@ pub trait SmartContractTrait<'a>: Send + Sync {
 			return Err(ContractError::InvalidNeoName("Function name cannot be empty".to_string()));
 		}
 
+		// Like the `invokefunction` RPC method, the contract is called with all flags. Most
+		// methods read the state or emit notifications, and fault with fewer flags
 		let script = ScriptBuilder::new()
-			.contract_call(&self.script_hash(), function, params.as_slice(), Some(CallFlags::None))
+			.contract_call(&self.script_hash(), function, params.as_slice(), None)
 			.unwrap()
 			.to_bytes();
 
@@ -225,14 +228,7 @@ pub trait SmartContractTrait<'a>: Send + Sync {
 		nef_checksum: u32,
 		contract_name: &str,
 	) -> Result<H160, ContractError> {
-		let mut script = ScriptBuilder::new();
-		script
-			.op_code(&[OpCode::Abort])
-			.push_data(sender.to_vec())
-			.push_integer(BigInt::from(nef_checksum))
-			.push_data(contract_name.as_bytes().to_vec());
-
-		Ok(H160::from_slice(&script.to_bytes()))
+		Ok(neo_vm::contract_hash(&sender, nef_checksum, contract_name))
 	}
 
 	async fn get_manifest(&self) -> ContractManifest {
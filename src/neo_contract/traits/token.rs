@@ -9,6 +9,7 @@ use crate::{
 	neo_types::NNSName,
 };
 
+#[allow(clippy::double_must_use)]
 #[async_trait]
 pub trait TokenTrait<'a, P: JsonRpcProvider>: SmartContractTrait<'a, P = P> {
 	const TOTAL_SUPPLY: &'static str = "totalSupply";
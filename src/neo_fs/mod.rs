@@ -155,6 +155,7 @@ pub struct NeoFSAuth {
 }
 
 /// Service trait for interacting with NeoFS
+#[allow(clippy::double_must_use)]
 #[async_trait]
 pub trait NeoFSService {
 	/// Creates a new container in NeoFS
@@ -32,10 +32,11 @@ pub struct NeoBlock {
 	pub witnesses: Option<Vec<NeoWitness>>,
 	#[serde(rename = "tx", default = "default_transactions")]
 	pub transactions: Option<Vec<RTransaction>>,
+	#[serde(default)]
 	pub confirmations: i32,
 	#[serde(serialize_with = "serialize_h256_option")]
 	#[serde(deserialize_with = "deserialize_h256_option")]
-	#[serde(rename = "nextblockhash")]
+	#[serde(rename = "nextblockhash", default)]
 	pub next_block_hash: Option<H256>,
 }
 
@@ -12,6 +12,8 @@ pub(crate) enum Role {
 	Oracle = 0x08,
 	#[strum(serialize = "NeoFSAlphabetNode")]
 	NeoFsAlphabetNode = 0x10,
+	#[strum(serialize = "P2PNotary")]
+	P2PNotary = 0x20,
 }
 
 impl Role {
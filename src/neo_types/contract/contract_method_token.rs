@@ -27,4 +27,25 @@ impl ContractMethodToken {
 	) -> Self {
 		Self { hash, method, param_count, has_return_value, call_flags }
 	}
+
+	pub fn hash(&self) -> H160 {
+		self.hash
+	}
+
+	pub fn method(&self) -> &str {
+		&self.method
+	}
+
+	pub fn param_count(&self) -> u32 {
+		self.param_count
+	}
+
+	pub fn has_return_value(&self) -> bool {
+		self.has_return_value
+	}
+
+	/// The call flags in their JSON form, e.g. `"ReadStates, AllowCall"`.
+	pub fn call_flags(&self) -> &str {
+		&self.call_flags
+	}
 }
@@ -503,6 +503,12 @@ impl ContractParameter {
 		Self { name: None, typ, value: Some(value) }
 	}
 
+	/// Returns the parameter with its name set, e.g. to the parameter name declared in a manifest.
+	pub fn with_name(mut self, name: impl Into<String>) -> Self {
+		self.name = Some(name.into());
+		self
+	}
+
 	pub fn bool(value: bool) -> Self {
 		Self::with_value(ContractParameterType::Boolean, ParameterValue::Boolean(value))
 	}
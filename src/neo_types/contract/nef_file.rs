@@ -53,6 +53,55 @@ impl NefFile {
 	const CHECKSUM_SIZE: usize = 4;
 	pub const HEADER_SIZE: usize = Self::MAGIC_SIZE + Self::COMPILER_SIZE;
 
+	/// Creates a NEF file for `script` and computes its checksum.
+	pub fn new(
+		compiler: &str,
+		source_url: &str,
+		method_tokens: Vec<MethodToken>,
+		script: Bytes,
+	) -> Result<Self, TypeError> {
+		if compiler.len() > Self::COMPILER_SIZE {
+			return Err(TypeError::InvalidArgError("Compiler name is too long".to_string()));
+		}
+		if source_url.len() > Self::MAX_SOURCE_URL_SIZE {
+			return Err(TypeError::InvalidArgError("Source URL is too long".to_string()));
+		}
+		let mut file = Self {
+			compiler: Some(compiler.to_string()),
+			source_url: source_url.to_string(),
+			method_tokens,
+			script,
+			checksum: vec![0; Self::CHECKSUM_SIZE],
+		};
+		file.checksum = Self::compute_checksum(&file)?;
+		Ok(file)
+	}
+
+	/// The name and version of the compiler that produced the file.
+	pub fn compiler(&self) -> &str {
+		self.compiler.as_deref().unwrap_or_default()
+	}
+
+	pub fn source_url(&self) -> &str {
+		&self.source_url
+	}
+
+	/// The static calls to other contracts made with `CALLT`.
+	pub fn method_tokens(&self) -> &[MethodToken] {
+		&self.method_tokens
+	}
+
+	pub fn script(&self) -> &Bytes {
+		&self.script
+	}
+
+	/// The checksum as the little-endian `u32` stored in contract states.
+	pub fn checksum(&self) -> u32 {
+		let mut bytes = [0u8; 4];
+		bytes.copy_from_slice(&self.checksum[..Self::CHECKSUM_SIZE]);
+		u32::from_le_bytes(bytes)
+	}
+
 	fn get_checksum_as_integer(bytes: &Bytes) -> Result<i32, TypeError> {
 		let mut bytes = bytes.clone();
 		bytes.reverse();
@@ -68,7 +117,7 @@ impl NefFile {
 	fn compute_checksum_from_bytes(bytes: Bytes) -> Result<Bytes, TypeError> {
 		let mut file_bytes = bytes.clone();
 		file_bytes.truncate(bytes.len() - Self::CHECKSUM_SIZE);
-		Ok(file_bytes.hash256()[..Self::CHECKSUM_SIZE].to_vec())
+		Ok(file_bytes.hash256().hash256()[..Self::CHECKSUM_SIZE].to_vec())
 	}
 
 	fn read_from_file(file: &str) -> Result<Self, TypeError> {
@@ -163,7 +212,9 @@ impl NeoSerializable for NefFile {
 
 		let compiler_bytes = reader.read_bytes(Self::COMPILER_SIZE)?;
 		let compiler = String::from_utf8(compiler_bytes.to_vec())
-			.map_err(|_| CodecError::InvalidEncoding("Invalid compiler".to_string()))?;
+			.map_err(|_| CodecError::InvalidEncoding("Invalid compiler".to_string()))?
+			.trim_end_matches('\0')
+			.to_string();
 
 		let source_url = reader.read_var_string()?;
 		if source_url.len() > Self::MAX_SOURCE_URL_SIZE {
@@ -188,14 +239,20 @@ impl NeoSerializable for NefFile {
 			return Err(TypeError::InvalidEncoding("Invalid script".to_string()));
 		}
 
-		let file =
-			Self { compiler: Some(compiler), source_url, method_tokens, script, checksum: vec![] };
+		let mut file = Self {
+			compiler: Some(compiler),
+			source_url,
+			method_tokens,
+			script,
+			checksum: vec![0; Self::CHECKSUM_SIZE],
+		};
 
 		let checksum = reader.read_bytes(Self::CHECKSUM_SIZE)?;
 		let computed_checksum = Self::compute_checksum(&file)?;
 		if checksum != computed_checksum {
 			return Err(TypeError::InvalidEncoding("Invalid checksum".to_string()));
 		}
+		file.checksum = checksum.to_vec();
 
 		Ok(file)
 	}
@@ -220,6 +277,38 @@ impl MethodToken {
 	const PARAMS_COUNT_SIZE: usize = 2;
 	const HAS_RETURN_VALUE_SIZE: usize = 1;
 	const CALL_FLAGS_SIZE: usize = 1;
+
+	pub fn new(
+		hash: H160,
+		method: String,
+		params_count: u16,
+		has_return_value: bool,
+		call_flags: u8,
+	) -> Self {
+		Self { hash, method, params_count, has_return_value, call_flags }
+	}
+
+	/// The hash of the called contract.
+	pub fn hash(&self) -> H160 {
+		self.hash
+	}
+
+	pub fn method(&self) -> &str {
+		&self.method
+	}
+
+	pub fn params_count(&self) -> u16 {
+		self.params_count
+	}
+
+	pub fn has_return_value(&self) -> bool {
+		self.has_return_value
+	}
+
+	/// The raw [`CallFlags`](crate::neo_builder::CallFlags) value of the call.
+	pub fn call_flags(&self) -> u8 {
+		self.call_flags
+	}
 }
 
 impl NeoSerializable for MethodToken {
@@ -236,7 +325,10 @@ impl NeoSerializable for MethodToken {
 	}
 
 	fn encode(&self, writer: &mut Encoder) {
-		writer.write_serializable_fixed(&self.hash);
+		// Hashes are stored in little-endian order, the reverse of their display form
+		let mut hash = self.hash.0;
+		hash.reverse();
+		writer.write_bytes(&hash);
 		writer.write_var_string(&self.method);
 		writer.write_u16(self.params_count);
 		writer.write_bool(self.has_return_value);
@@ -247,7 +339,9 @@ impl NeoSerializable for MethodToken {
 	where
 		Self: Sized,
 	{
-		let hash = reader.read_serializable()?;
+		let mut hash = reader.read_bytes(H160::len_bytes())?.to_vec();
+		hash.reverse();
+		let hash = H160::from_slice(&hash);
 		let method = reader.read_var_string()?;
 		let params_count = reader.read_u16().map_err(|e| {
 			TypeError::InvalidEncoding(format!("Failed to read params_count: {}", e))
@@ -0,0 +1,521 @@
+use std::{collections::HashMap, hash::Hash};
+
+use base64::Engine;
+pub use neo3_derive::{FromStackItem, IntoContractParameter};
+use num_bigint::BigInt;
+use primitive_types::{H160, H256};
+
+use crate::{
+	crypto::Secp256r1PublicKey,
+	neo_types::{ContractParameter, ContractParameterMap, StackItem, TypeError},
+};
+
+/// Decodes a value from a [`StackItem`], such as an item of the stack of an invocation result.
+///
+/// Structs mapped from `Struct` or `Array` items, with one item per field in declaration order,
+/// can derive this trait.
+///
+/// # Examples
+///
+/// ```rust
+/// use neo3::neo_types::{FromStackItem, StackItem};
+/// use primitive_types::H160;
+///
+/// #[derive(Debug, PartialEq, FromStackItem)]
+/// struct Balance {
+///     owner: H160,
+///     amount: u64,
+/// }
+///
+/// let owner = H160::repeat_byte(0x01);
+/// let item = StackItem::Struct {
+///     value: vec![StackItem::new_byte_string(owner.0.to_vec()), 100.into()],
+/// };
+/// let balance: Balance = item.decode().unwrap();
+/// assert_eq!(balance, Balance { owner, amount: 100 });
+///
+/// // Type mismatches name the field that failed
+/// let err = StackItem::Struct { value: vec![1.into(), 2.into()] }.decode::<Balance>();
+/// assert!(err.unwrap_err().to_string().contains("Balance.owner"));
+/// ```
+pub trait FromStackItem: Sized {
+	fn from_stack_item(item: &StackItem) -> Result<Self, TypeError>;
+
+	/// Decodes a byte string as a vector of `Self`, which only bytes support.
+	#[doc(hidden)]
+	fn vec_from_bytes(_bytes: &[u8]) -> Option<Vec<Self>> {
+		None
+	}
+}
+
+/// Encodes a value as a [`ContractParameter`], to pass it to a contract method.
+///
+/// Structs can derive this trait, and are encoded as arrays with one item per field in
+/// declaration order. Encoding fails for integers out of the range of `i64`, the range of
+/// integer parameters.
+pub trait IntoContractParameter {
+	fn into_contract_parameter(self) -> Result<ContractParameter, TypeError>;
+
+	/// Encodes a vector of `Self`, which bytes encode as a byte array.
+	#[doc(hidden)]
+	fn vec_into_contract_parameter(values: Vec<Self>) -> Result<ContractParameter, TypeError>
+	where
+		Self: Sized,
+	{
+		values
+			.into_iter()
+			.map(Self::into_contract_parameter)
+			.collect::<Result<_, _>>()
+			.map(ContractParameter::array)
+	}
+}
+
+impl StackItem {
+	/// Decodes the item as a `T`.
+	pub fn decode<T: FromStackItem>(&self) -> Result<T, TypeError> {
+		T::from_stack_item(self)
+	}
+}
+
+/// The error of an integer that can't be passed as an integer parameter.
+fn out_of_parameter_range(value: impl std::fmt::Display) -> TypeError {
+	TypeError::IllegalArgument(format!(
+		"{} is out of the range of integer parameters, the range of i64",
+		value
+	))
+}
+
+/// The error of an item that isn't of the `expected` type.
+fn unexpected(expected: &str, item: &StackItem) -> TypeError {
+	let actual = match item {
+		StackItem::Any => StackItem::ANY_VALUE,
+		StackItem::Pointer { .. } => StackItem::POINTER_VALUE,
+		StackItem::Boolean { .. } => StackItem::BOOLEAN_VALUE,
+		StackItem::Integer { .. } => StackItem::INTEGER_VALUE,
+		StackItem::ByteString { .. } => StackItem::BYTE_STRING_VALUE,
+		StackItem::Buffer { .. } => StackItem::BUFFER_VALUE,
+		StackItem::Array { .. } => StackItem::ARRAY_VALUE,
+		StackItem::Struct { .. } => StackItem::STRUCT_VALUE,
+		StackItem::Map { .. } => StackItem::MAP_VALUE,
+		StackItem::InteropInterface { .. } => StackItem::INTEROP_INTERFACE_VALUE,
+	};
+	TypeError::UnexpectedReturnType(format!("expected {}, got {}", expected, actual))
+}
+
+/// Prefixes the error of a field of a derived type with the path of the field.
+#[doc(hidden)]
+pub fn field_error(path: &str, err: TypeError) -> TypeError {
+	match err {
+		TypeError::UnexpectedReturnType(message) => {
+			TypeError::UnexpectedReturnType(format!("{}: {}", path, message))
+		},
+		err => err,
+	}
+}
+
+/// The items of an `Array` or `Struct` item, checking there are `len` of them.
+#[doc(hidden)]
+pub fn stack_items(item: &StackItem, len: usize) -> Result<&[StackItem], TypeError> {
+	match item {
+		StackItem::Array { value } | StackItem::Struct { value } if value.len() == len => Ok(value),
+		StackItem::Array { value } | StackItem::Struct { value } => Err(
+			TypeError::UnexpectedReturnType(format!("expected {} items, got {}", len, value.len())),
+		),
+		item => Err(unexpected("Array or Struct", item)),
+	}
+}
+
+fn bytes(item: &StackItem) -> Result<Vec<u8>, TypeError> {
+	match item {
+		StackItem::ByteString { value } | StackItem::Buffer { value } => {
+			base64::engine::general_purpose::STANDARD
+				.decode(value.trim_end())
+				.map_err(|err| TypeError::InvalidEncoding(err.to_string()))
+		},
+		item => Err(unexpected("ByteString", item)),
+	}
+}
+
+/// Reads a hash from its little-endian bytes, the order contracts use.
+fn hash_bytes<const N: usize>(item: &StackItem) -> Result<[u8; N], TypeError> {
+	let bytes = bytes(item)?;
+	let mut hash: [u8; N] = bytes.as_slice().try_into().map_err(|_| {
+		TypeError::UnexpectedReturnType(format!("expected {} bytes, got {}", N, bytes.len()))
+	})?;
+	hash.reverse();
+	Ok(hash)
+}
+
+impl FromStackItem for StackItem {
+	fn from_stack_item(item: &StackItem) -> Result<Self, TypeError> {
+		Ok(item.clone())
+	}
+}
+
+impl FromStackItem for bool {
+	fn from_stack_item(item: &StackItem) -> Result<Self, TypeError> {
+		item.as_bool().ok_or_else(|| unexpected(StackItem::BOOLEAN_VALUE, item))
+	}
+}
+
+impl FromStackItem for BigInt {
+	/// Decodes an `Integer` or `Boolean` item, or a byte string holding a little-endian integer.
+	fn from_stack_item(item: &StackItem) -> Result<Self, TypeError> {
+		match item {
+			StackItem::Integer { value } => Ok(BigInt::from(*value)),
+			StackItem::Boolean { value } => Ok(BigInt::from(*value as u8)),
+			StackItem::ByteString { .. } | StackItem::Buffer { .. } => {
+				Ok(BigInt::from_signed_bytes_le(&bytes(item)?))
+			},
+			item => Err(unexpected(StackItem::INTEGER_VALUE, item)),
+		}
+	}
+}
+
+macro_rules! impl_integer {
+	($($t:ty),*) => {$(
+		impl FromStackItem for $t {
+			fn from_stack_item(item: &StackItem) -> Result<Self, TypeError> {
+				let value = BigInt::from_stack_item(item)?;
+				<$t>::try_from(&value).map_err(|_| {
+					TypeError::UnexpectedReturnType(format!(
+						"{} is out of the range of {}",
+						value,
+						stringify!($t)
+					))
+				})
+			}
+		}
+
+		impl IntoContractParameter for $t {
+			#[allow(clippy::unnecessary_fallible_conversions)]
+			fn into_contract_parameter(self) -> Result<ContractParameter, TypeError> {
+				i64::try_from(self)
+					.map(ContractParameter::integer)
+					.map_err(|_| out_of_parameter_range(self))
+			}
+		}
+	)*};
+}
+
+impl_integer!(i8, i16, i32, i64, u16, u32, u64, usize);
+
+impl FromStackItem for u8 {
+	fn from_stack_item(item: &StackItem) -> Result<Self, TypeError> {
+		let value = BigInt::from_stack_item(item)?;
+		u8::try_from(&value).map_err(|_| {
+			TypeError::UnexpectedReturnType(format!("{} is out of the range of u8", value))
+		})
+	}
+
+	fn vec_from_bytes(bytes: &[u8]) -> Option<Vec<Self>> {
+		Some(bytes.to_vec())
+	}
+}
+
+impl FromStackItem for String {
+	fn from_stack_item(item: &StackItem) -> Result<Self, TypeError> {
+		String::from_utf8(bytes(item)?).map_err(|err| TypeError::InvalidEncoding(err.to_string()))
+	}
+}
+
+impl FromStackItem for H160 {
+	fn from_stack_item(item: &StackItem) -> Result<Self, TypeError> {
+		hash_bytes(item).map(H160)
+	}
+}
+
+impl FromStackItem for H256 {
+	fn from_stack_item(item: &StackItem) -> Result<Self, TypeError> {
+		hash_bytes(item).map(H256)
+	}
+}
+
+impl FromStackItem for Secp256r1PublicKey {
+	fn from_stack_item(item: &StackItem) -> Result<Self, TypeError> {
+		Secp256r1PublicKey::from_bytes(&bytes(item)?).map_err(|_| TypeError::InvalidPublicKey)
+	}
+}
+
+impl<T: FromStackItem> FromStackItem for Option<T> {
+	/// Decodes `Any` items, the items of `null`, as `None`.
+	fn from_stack_item(item: &StackItem) -> Result<Self, TypeError> {
+		match item {
+			StackItem::Any => Ok(None),
+			item => T::from_stack_item(item).map(Some),
+		}
+	}
+}
+
+impl<T: FromStackItem> FromStackItem for Vec<T> {
+	/// Decodes an `Array` or `Struct` item, or a byte string for `Vec<u8>`.
+	fn from_stack_item(item: &StackItem) -> Result<Self, TypeError> {
+		match item {
+			StackItem::Array { value } | StackItem::Struct { value } => value
+				.iter()
+				.enumerate()
+				.map(|(index, item)| {
+					T::from_stack_item(item)
+						.map_err(|err| field_error(&format!("[{}]", index), err))
+				})
+				.collect(),
+			StackItem::ByteString { .. } | StackItem::Buffer { .. } => {
+				T::vec_from_bytes(&bytes(item)?).ok_or_else(|| unexpected("Array", item))
+			},
+			item => Err(unexpected(StackItem::ARRAY_VALUE, item)),
+		}
+	}
+}
+
+impl<K: FromStackItem + Eq + Hash, V: FromStackItem> FromStackItem for HashMap<K, V> {
+	fn from_stack_item(item: &StackItem) -> Result<Self, TypeError> {
+		match item {
+			StackItem::Map { value } => value
+				.iter()
+				.map(|entry| {
+					Ok((K::from_stack_item(entry.key())?, V::from_stack_item(entry.value())?))
+				})
+				.collect(),
+			item => Err(unexpected(StackItem::MAP_VALUE, item)),
+		}
+	}
+}
+
+impl IntoContractParameter for ContractParameter {
+	fn into_contract_parameter(self) -> Result<ContractParameter, TypeError> {
+		Ok(self)
+	}
+}
+
+impl IntoContractParameter for bool {
+	fn into_contract_parameter(self) -> Result<ContractParameter, TypeError> {
+		Ok(ContractParameter::bool(self))
+	}
+}
+
+impl IntoContractParameter for u8 {
+	fn into_contract_parameter(self) -> Result<ContractParameter, TypeError> {
+		Ok(ContractParameter::integer(self as i64))
+	}
+
+	fn vec_into_contract_parameter(values: Vec<Self>) -> Result<ContractParameter, TypeError> {
+		Ok(ContractParameter::byte_array(values))
+	}
+}
+
+impl IntoContractParameter for BigInt {
+	fn into_contract_parameter(self) -> Result<ContractParameter, TypeError> {
+		i64::try_from(&self)
+			.map(ContractParameter::integer)
+			.map_err(|_| out_of_parameter_range(self))
+	}
+}
+
+impl IntoContractParameter for String {
+	fn into_contract_parameter(self) -> Result<ContractParameter, TypeError> {
+		Ok(ContractParameter::string(self))
+	}
+}
+
+impl IntoContractParameter for &str {
+	fn into_contract_parameter(self) -> Result<ContractParameter, TypeError> {
+		Ok(ContractParameter::string(self.to_string()))
+	}
+}
+
+impl IntoContractParameter for H160 {
+	fn into_contract_parameter(self) -> Result<ContractParameter, TypeError> {
+		Ok(ContractParameter::h160(&self))
+	}
+}
+
+impl IntoContractParameter for H256 {
+	fn into_contract_parameter(self) -> Result<ContractParameter, TypeError> {
+		Ok(ContractParameter::h256(&self))
+	}
+}
+
+impl IntoContractParameter for Secp256r1PublicKey {
+	fn into_contract_parameter(self) -> Result<ContractParameter, TypeError> {
+		Ok(ContractParameter::public_key(&self))
+	}
+}
+
+impl<T: IntoContractParameter> IntoContractParameter for Option<T> {
+	fn into_contract_parameter(self) -> Result<ContractParameter, TypeError> {
+		self.map_or_else(|| Ok(ContractParameter::any()), T::into_contract_parameter)
+	}
+}
+
+impl<T: IntoContractParameter> IntoContractParameter for Vec<T> {
+	fn into_contract_parameter(self) -> Result<ContractParameter, TypeError> {
+		T::vec_into_contract_parameter(self)
+	}
+}
+
+impl<K: IntoContractParameter, V: IntoContractParameter> IntoContractParameter for HashMap<K, V> {
+	fn into_contract_parameter(self) -> Result<ContractParameter, TypeError> {
+		let map = self
+			.into_iter()
+			.map(|(key, value)| {
+				Ok((key.into_contract_parameter()?, value.into_contract_parameter()?))
+			})
+			.collect::<Result<_, TypeError>>()?;
+		Ok(ContractParameter::map(ContractParameterMap::from_map(map)))
+	}
+}
+
+macro_rules! impl_tuple {
+	($len:expr => $($t:ident $index:tt),+) => {
+		impl<$($t: FromStackItem),+> FromStackItem for ($($t,)+) {
+			fn from_stack_item(item: &StackItem) -> Result<Self, TypeError> {
+				let items = stack_items(item, $len)?;
+				Ok(($(
+					$t::from_stack_item(&items[$index])
+						.map_err(|err| field_error(stringify!([$index]), err))?,
+				)+))
+			}
+		}
+
+		impl<$($t: IntoContractParameter),+> IntoContractParameter for ($($t,)+) {
+			fn into_contract_parameter(self) -> Result<ContractParameter, TypeError> {
+				Ok(ContractParameter::array(vec![$(self.$index.into_contract_parameter()?),+]))
+			}
+		}
+	};
+}
+
+impl_tuple!(1 => A 0);
+impl_tuple!(2 => A 0, B 1);
+impl_tuple!(3 => A 0, B 1, C 2);
+impl_tuple!(4 => A 0, B 1, C 2, D 3);
+impl_tuple!(5 => A 0, B 1, C 2, D 3, E 4);
+impl_tuple!(6 => A 0, B 1, C 2, D 3, E 4, F 5);
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		crypto::KeyPair,
+		neo_types::{ContractParameterType, MapEntry, ParameterValue},
+	};
+
+	fn bytes(value: &[u8]) -> StackItem {
+		StackItem::new_byte_string(value.to_vec())
+	}
+
+	#[derive(Debug, PartialEq, FromStackItem, IntoContractParameter)]
+	struct Transfer {
+		from: Option<H160>,
+		to: H160,
+		amount: BigInt,
+	}
+
+	#[derive(Debug, PartialEq, FromStackItem, IntoContractParameter)]
+	struct Pair(String, Vec<u8>);
+
+	#[test]
+	fn test_primitives() {
+		assert!(StackItem::Integer { value: 1 }.decode::<bool>().unwrap());
+		assert!(bytes(&[0x00, 0x01]).decode::<bool>().unwrap());
+		assert!(!bytes(&[]).decode::<bool>().unwrap());
+		assert!(bytes(&[0x01; 33]).decode::<bool>().is_err());
+		assert_eq!(StackItem::Integer { value: -5 }.decode::<i8>().unwrap(), -5);
+		assert_eq!(bytes(&[0x00, 0x01]).decode::<u32>().unwrap(), 256);
+		assert_eq!(bytes(b"neo").decode::<String>().unwrap(), "neo");
+		assert_eq!(bytes(b"neo").decode::<Vec<u8>>().unwrap(), b"neo");
+
+		let err = StackItem::Integer { value: 256 }.decode::<u8>().unwrap_err();
+		assert_eq!(err.to_string(), "Unexpected returned type: 256 is out of the range of u8");
+		let err = StackItem::Boolean { value: true }.decode::<String>().unwrap_err();
+		assert_eq!(err.to_string(), "Unexpected returned type: expected ByteString, got Boolean");
+	}
+
+	#[test]
+	fn test_hashes_and_keys() {
+		let hash = H160::from_low_u64_be(1);
+		let mut le = hash.0.to_vec();
+		le.reverse();
+		assert_eq!(bytes(&le).decode::<H160>().unwrap(), hash);
+		assert!(bytes(&[1; 19]).decode::<H160>().is_err());
+		assert_eq!(bytes(&[2; 32]).decode::<H256>().unwrap(), H256::repeat_byte(2));
+
+		let key = KeyPair::new_random().public_key();
+		assert_eq!(bytes(&key.get_encoded(true)).decode::<Secp256r1PublicKey>().unwrap(), key);
+	}
+
+	#[test]
+	fn test_collections() {
+		let item = StackItem::Array { value: vec![1.into(), 2.into()] };
+		assert_eq!(item.decode::<Vec<i64>>().unwrap(), vec![1, 2]);
+		assert_eq!(item.decode::<(u8, u16)>().unwrap(), (1, 2));
+		assert!(item.decode::<(u8, u16, u32)>().is_err());
+
+		let map = StackItem::Map {
+			value: vec![MapEntry::new(bytes(b"a"), 1.into()), MapEntry::new(bytes(b"b"), 2.into())],
+		};
+		let map = map.decode::<HashMap<String, u64>>().unwrap();
+		assert_eq!(map, HashMap::from([("a".to_string(), 1), ("b".to_string(), 2)]));
+
+		assert_eq!(StackItem::Any.decode::<Option<u8>>().unwrap(), None);
+		let err = StackItem::Array { value: vec![1.into(), StackItem::Array { value: vec![] }] }
+			.decode::<Vec<bool>>()
+			.unwrap_err();
+		assert_eq!(err.to_string(), "Unexpected returned type: [1]: expected Boolean, got Array");
+	}
+
+	#[test]
+	fn test_derive() {
+		let to = H160::repeat_byte(0xab);
+		let item = StackItem::Struct {
+			value: vec![StackItem::Any, bytes(&to.0), StackItem::Integer { value: 10 }],
+		};
+		let transfer = item.decode::<Transfer>().unwrap();
+		assert_eq!(transfer, Transfer { from: None, to, amount: BigInt::from(10) });
+
+		let item = StackItem::Struct { value: vec![StackItem::Any, 1.into(), 1.into()] };
+		let err = item.decode::<Transfer>().unwrap_err();
+		assert_eq!(
+			err.to_string(),
+			"Unexpected returned type: Transfer.to: expected ByteString, got Integer"
+		);
+		let err = StackItem::Array { value: vec![] }.decode::<Transfer>().unwrap_err();
+		assert_eq!(err.to_string(), "Unexpected returned type: Transfer: expected 3 items, got 0");
+
+		let pair = StackItem::Array { value: vec![bytes(b"neo"), bytes(&[1, 2])] };
+		assert_eq!(pair.decode::<Pair>().unwrap(), Pair("neo".to_string(), vec![1, 2]));
+	}
+
+	#[test]
+	fn test_into_contract_parameter() {
+		let transfer = Transfer { from: None, to: H160::zero(), amount: BigInt::from(5) };
+		let parameter = transfer.into_contract_parameter().unwrap();
+		assert_eq!(
+			parameter,
+			ContractParameter::array(vec![
+				ContractParameter::any(),
+				ContractParameter::h160(&H160::zero()),
+				ContractParameter::integer(5),
+			])
+		);
+
+		let parameter = Pair("a".to_string(), vec![1]).into_contract_parameter().unwrap();
+		let items = parameter.to_array().unwrap();
+		assert_eq!(items[0], ContractParameter::string("a".to_string()));
+		assert_eq!(items[1].get_type(), ContractParameterType::ByteArray);
+
+		let parameter = vec![1u32, 2].into_contract_parameter().unwrap();
+		assert!(
+			matches!(parameter.value, Some(ParameterValue::Array(ref items)) if items.len() == 2)
+		);
+		let parameter = HashMap::from([("k", true)]).into_contract_parameter().unwrap();
+		assert_eq!(parameter.get_type(), ContractParameterType::Map);
+
+		let amount = BigInt::from(i64::MAX) + 1;
+		let transfer = Transfer { from: None, to: H160::zero(), amount };
+		let err = transfer.into_contract_parameter().unwrap_err();
+		assert!(err.to_string().contains("9223372036854775808"));
+		assert!(u64::MAX.into_contract_parameter().is_err());
+		assert!(vec![Some(usize::MAX)].into_contract_parameter().is_err());
+	}
+}
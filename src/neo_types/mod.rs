@@ -14,7 +14,7 @@
 //! - Neo Name Service (NNS) types
 //! - Numeric types with blockchain-specific operations
 //! - Serialization and deserialization utilities
-//! - Stack item representations for VM operations
+//! - Stack item representations for VM operations, with typed decoding through `FromStackItem`
 //! - Blockchain-specific enumerations (OpCode, VMState)
 //!
 //! This module forms the type foundation for the entire SDK, providing the core data structures
@@ -82,6 +82,7 @@ pub use address_or_scripthash::*;
 pub use bytes::*;
 pub use contract::*;
 pub use error::*;
+pub use from_stack_item::*;
 pub use nns::*;
 pub use numeric::*;
 pub use op_code::*;
@@ -107,6 +108,7 @@ mod address;
 mod address_or_scripthash;
 pub mod block;
 mod bytes;
+mod from_stack_item;
 mod numeric;
 mod op_code;
 mod path_or_string;
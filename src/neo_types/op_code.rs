@@ -424,6 +424,8 @@ impl OpCode {
 			| OpCode::PushInt16
 			| OpCode::PushInt32
 			| OpCode::PushInt64
+			| OpCode::PushTrue
+			| OpCode::PushFalse
 			| OpCode::PushNull
 			| OpCode::PushM1
 			| OpCode::Push0
@@ -444,11 +446,17 @@ impl OpCode {
 			| OpCode::Push15
 			| OpCode::Push16
 			| OpCode::Nop
-			| OpCode::Assert => 1,
+			| OpCode::Assert
+			| OpCode::AssertMsg => 1,
 			OpCode::PushInt128
 			| OpCode::PushInt256
 			| OpCode::PushA
 			| OpCode::Try
+			| OpCode::TryL
+			| OpCode::EndTry
+			| OpCode::EndTryL
+			| OpCode::EndFinally
+			| OpCode::Invert
 			| OpCode::Sign
 			| OpCode::Abs
 			| OpCode::Negate
@@ -489,9 +497,14 @@ impl OpCode {
 			| OpCode::NewStruct0
 			| OpCode::Keys
 			| OpCode::Remove
-			| OpCode::ClearItems => 1 << 4,
+			| OpCode::ClearItems
+			| OpCode::PopItem => 1 << 4,
 			OpCode::Equal | OpCode::NotEqual | OpCode::ModMul => 1 << 5,
-			OpCode::InitSlot | OpCode::Pow | OpCode::HasKey | OpCode::PickItem => 1 << 6,
+			OpCode::InitSlot
+			| OpCode::Pow
+			| OpCode::Sqrt
+			| OpCode::HasKey
+			| OpCode::PickItem => 1 << 6,
 			OpCode::NewBuffer => 1 << 8,
 			OpCode::PushData2
 			| OpCode::Call
@@ -506,7 +519,6 @@ impl OpCode {
 			| OpCode::Substr
 			| OpCode::Left
 			| OpCode::Right
-			| OpCode::Sqrt
 			| OpCode::ModPow
 			| OpCode::PackMap
 			| OpCode::PackStruct
@@ -519,7 +531,7 @@ impl OpCode {
 			| OpCode::ReverseItems
 			| OpCode::Convert => 1 << 13,
 			OpCode::CallT => 1 << 15,
-			OpCode::Abort | OpCode::Ret | OpCode::Syscall => 0,
+			OpCode::Abort | OpCode::AbortMsg | OpCode::Ret | OpCode::Syscall => 0,
 			_ => 1 << 1,
 		}
 	}
@@ -8,7 +8,11 @@ use std::{collections::HashMap, fmt};
 use primitive_types::{H160, H256};
 use serde::{de::Visitor, Deserialize, Deserializer, Serialize};
 
-use crate::crypto::Secp256r1PublicKey;
+use crate::{
+	crypto::Secp256r1PublicKey,
+	neo_types::TypeError,
+	neo_vm::{BinarySerializer, ExecutionEngineLimits, VMStackItem, MAX_INTEGER_SIZE},
+};
 use neo3::prelude::{Address, ScriptHashExtension};
 
 /// The `StackItem` enum represents an item on the Neo virtual machine stack.
@@ -121,6 +125,23 @@ pub struct MapEntry {
 	value: StackItem,
 }
 
+impl MapEntry {
+	/// Creates a new map entry.
+	pub fn new(key: StackItem, value: StackItem) -> Self {
+		Self { key, value }
+	}
+
+	/// Returns the key of the entry.
+	pub fn key(&self) -> &StackItem {
+		&self.key
+	}
+
+	/// Returns the value of the entry.
+	pub fn value(&self) -> &StackItem {
+		&self.value
+	}
+}
+
 impl StackItem {
 	/// The string value for `StackItem::Any`.
 	pub const ANY_VALUE: &'static str = "Any";
@@ -182,16 +203,30 @@ impl StackItem {
 	/// The byte value for `StackItem::InteropInterface`.
 	pub const INTEROP_INTERFACE_BYTE: u8 = 0x60;
 
+	/// The maximum size of a serialized item, the `MaxItemSize` of the NeoVM.
+	pub const MAX_SERIALIZED_SIZE: usize = u16::MAX as usize * 2;
+
+	/// The maximum number of items in a serialized item, the `MaxStackSize` of the NeoVM.
+	pub const MAX_SERIALIZED_ITEMS: usize = 2 * 1024;
+
 	pub fn new_byte_string(byte_array: Vec<u8>) -> Self {
 		let byte_string = base64::engine::general_purpose::STANDARD.encode(byte_array);
 		StackItem::ByteString { value: byte_string }
 	}
 
-	/// Returns the boolean value of a `StackItem::Boolean` or `StackItem::Integer`.
+	/// Returns the boolean value of a `StackItem::Boolean`, `StackItem::Integer` or
+	/// `StackItem::ByteString`.
+	///
+	/// Like in the NeoVM, a byte string is `true` if any of its bytes isn't zero, and byte
+	/// strings longer than an integer can be aren't booleans.
 	pub fn as_bool(&self) -> Option<bool> {
 		match self {
 			StackItem::Boolean { value } => Some(*value),
 			StackItem::Integer { value } => Some(value != &0),
+			StackItem::ByteString { .. } => self
+				.as_bytes()
+				.filter(|bytes| bytes.len() <= MAX_INTEGER_SIZE)
+				.map(|bytes| bytes.iter().any(|byte| *byte != 0)),
 			_ => None,
 		}
 	}
@@ -379,6 +414,70 @@ impl StackItem {
 			None
 		}
 	}
+
+	/// Serializes the item in the NeoVM binary format used by `StdLib.serialize` and by
+	/// contracts persisting values in storage, see [`BinarySerializer`].
+	///
+	/// `Pointer` and `InteropInterface` items can't be serialized, and the output can't exceed
+	/// [`StackItem::MAX_SERIALIZED_SIZE`] bytes.
+	pub fn to_binary(&self) -> Result<Vec<u8>, TypeError> {
+		self.to_binary_with_limit(Self::MAX_SERIALIZED_SIZE)
+	}
+
+	/// Serializes the item in the NeoVM binary format, failing if the output exceeds `max_size`
+	/// bytes.
+	pub fn to_binary_with_limit(&self, max_size: usize) -> Result<Vec<u8>, TypeError> {
+		let item = VMStackItem::from_stack_item(self)
+			.map_err(|err| TypeError::UnsupportedOperation(err.to_string()))?;
+		BinarySerializer::serialize(&item, &binary_limits(max_size, Self::MAX_SERIALIZED_ITEMS))
+			.map_err(|err| TypeError::UnsupportedOperation(err.to_string()))
+	}
+
+	/// Deserializes an item in the NeoVM binary format, with the default limits of the NeoVM.
+	///
+	/// Integers of up to 32 bytes are decoded. Those that don't fit into an `i64` are returned
+	/// as a `ByteString` of their little-endian encoding, like [`VMStackItem::to_stack_item`]
+	/// does.
+	///
+	/// # Examples
+	///
+	/// ```rust
+	/// use neo3::neo_types::StackItem;
+	///
+	/// // An array of the integer 42 and the byte string "a"
+	/// let data = [0x40, 0x02, 0x21, 0x01, 0x2a, 0x28, 0x01, 0x61];
+	/// let item = StackItem::from_binary(&data).unwrap();
+	/// let a = StackItem::new_byte_string(b"a".to_vec());
+	/// assert_eq!(item, StackItem::Array { value: vec![42.into(), a] });
+	/// assert_eq!(item.to_binary().unwrap(), data);
+	/// ```
+	pub fn from_binary(data: &[u8]) -> Result<Self, TypeError> {
+		Self::from_binary_with_limits(data, Self::MAX_SERIALIZED_SIZE, Self::MAX_SERIALIZED_ITEMS)
+	}
+
+	/// Deserializes an item in the NeoVM binary format.
+	///
+	/// Byte strings and buffers can't be longer than `max_size` bytes, the item can't contain
+	/// more than `max_items` items, and it can't be nested deeper than
+	/// [`MAX_STACK_ITEM_DEPTH`](crate::neo_vm::MAX_STACK_ITEM_DEPTH).
+	pub fn from_binary_with_limits(
+		data: &[u8],
+		max_size: usize,
+		max_items: usize,
+	) -> Result<Self, TypeError> {
+		BinarySerializer::deserialize(data, &binary_limits(max_size, max_items))
+			.and_then(|item| item.try_to_stack_item())
+			.map_err(|err| TypeError::Deserialization(err.to_string()))
+	}
+}
+
+/// The engine limits bounding the binary format to `max_size` bytes and `max_items` items.
+fn binary_limits(max_size: usize, max_items: usize) -> ExecutionEngineLimits {
+	ExecutionEngineLimits {
+		max_item_size: max_size,
+		max_stack_size: max_items,
+		..ExecutionEngineLimits::default()
+	}
 }
 
 impl From<String> for StackItem {
@@ -439,3 +538,96 @@ impl From<&str> for StackItem {
 		StackItem::ByteString { value: value.to_string() }
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn bytes(value: &[u8]) -> StackItem {
+		StackItem::new_byte_string(value.to_vec())
+	}
+
+	#[test]
+	fn test_binary_primitives() {
+		let cases = [
+			(StackItem::Any, vec![0x00]),
+			(StackItem::Boolean { value: true }, vec![0x20, 0x01]),
+			(StackItem::Integer { value: 0 }, vec![0x21, 0x00]),
+			(StackItem::Integer { value: 255 }, vec![0x21, 0x02, 0xff, 0x00]),
+			(StackItem::Integer { value: -1 }, vec![0x21, 0x01, 0xff]),
+			(StackItem::Integer { value: i64::MIN }, {
+				let mut data = vec![0x21, 0x08];
+				data.extend(i64::MIN.to_le_bytes());
+				data
+			}),
+			(bytes(b"neo"), vec![0x28, 0x03, b'n', b'e', b'o']),
+			(StackItem::Buffer { value: "AQI=".to_string() }, vec![0x30, 0x02, 0x01, 0x02]),
+		];
+		for (item, data) in cases {
+			assert_eq!(item.to_binary().unwrap(), data, "{:?}", item);
+			assert_eq!(StackItem::from_binary(&data).unwrap(), item);
+		}
+	}
+
+	#[test]
+	fn test_binary_compound_items() {
+		let item = StackItem::Array {
+			value: vec![
+				StackItem::Struct { value: vec![1.into(), bytes(&[0xab; 300])] },
+				StackItem::Map {
+					value: vec![MapEntry::new(bytes(b"k"), StackItem::Array { value: vec![] })],
+				},
+			],
+		};
+		let data = item.to_binary().unwrap();
+		assert_eq!(&data[..4], &[0x40, 0x02, 0x41, 0x02]);
+		// The 300 bytes are prefixed with 0xfd and a 16-bit length
+		assert_eq!(&data[7..10], &[0x28, 0xfd, 0x2c]);
+		assert_eq!(StackItem::from_binary(&data).unwrap(), item);
+	}
+
+	#[test]
+	fn test_binary_limits() {
+		let nested = (0..10).fold(StackItem::Any, |item, _| StackItem::Array { value: vec![item] });
+		let data = nested.to_binary().unwrap();
+		assert!(StackItem::from_binary_with_limits(&data, 1024, 10).is_err());
+		assert_eq!(StackItem::from_binary_with_limits(&data, 1024, 11).unwrap(), nested);
+
+		assert!(bytes(&[0; 100]).to_binary_with_limit(100).is_err());
+		assert!(StackItem::from_binary_with_limits(&[0x28, 0x03, 1, 2, 3], 2, 10).is_err());
+		assert!(StackItem::from_binary(&[0x21, 0x21]).is_err());
+
+		let limits = ExecutionEngineLimits::default();
+		assert_eq!(StackItem::MAX_SERIALIZED_SIZE, limits.max_item_size);
+		assert_eq!(StackItem::MAX_SERIALIZED_ITEMS, limits.max_stack_size);
+	}
+
+	#[test]
+	fn test_binary_big_integers() {
+		// Integers of up to 32 bytes that overflow i64 come back as their little-endian bytes
+		let mut data = vec![0x21, 0x20];
+		data.extend([0xff; 31]);
+		data.push(0x7f);
+		let value = StackItem::from_binary(&data).unwrap();
+		assert_eq!(value, bytes(&data[2..]));
+
+		let data = [0x21, 0x09, 0, 0, 0, 0, 0, 0, 0, 0x80, 0];
+		let value = StackItem::from_binary(&data).unwrap();
+		assert_eq!(value, bytes(&data[2..]));
+	}
+
+	#[test]
+	fn test_binary_invalid_data() {
+		assert!(StackItem::from_binary(&[]).is_err());
+		assert!(StackItem::from_binary(&[0x00, 0x00]).is_err());
+		assert!(StackItem::from_binary(&[0x20, 0x02]).is_err());
+		assert!(StackItem::from_binary(&[0x40, 0x02, 0x00]).is_err());
+		assert!(StackItem::from_binary(&[0x60]).is_err());
+		// Arrays can't be map keys
+		assert!(StackItem::from_binary(&[0x48, 0x01, 0x40, 0x00, 0x00]).is_err());
+
+		assert!(StackItem::Pointer { value: 0 }.to_binary().is_err());
+		let map = StackItem::Map { value: vec![MapEntry::new(StackItem::Any, StackItem::Any)] };
+		assert!(map.to_binary().is_err());
+	}
+}
@@ -0,0 +1,1336 @@
+use std::{any::Any, cell::Cell, collections::HashMap, rc::Rc, str::FromStr};
+
+use base64::{engine::general_purpose, Engine};
+use num_bigint::BigInt;
+use num_traits::ToPrimitive;
+use primitive_types::H160;
+use strum_macros::Display;
+
+use crate::{
+	builder::{CallFlags, InteropService, ScriptBuilder, WitnessContext},
+	crypto::{Secp256r1PublicKey, Secp256r1Signature},
+	neo_types::{
+		ContractManifest, ContractMethod, ContractState, InvocationResult, NeoVMStateType,
+		Notification, ScriptHashExtension, VMState,
+	},
+	neo_vm::{
+		native::{self, call_flags_from_string, hash160_from_item, hash160_item, NativeContract},
+		BinarySerializer, BlockRecord, BlockchainState, ExecutionContext, ExecutionEngine,
+		InteropHost, InteropObject, TransactionRecord, VMError, VMStackItem,
+	},
+};
+
+/// The maximum size of an event name, in bytes.
+const MAX_EVENT_NAME: usize = 32;
+/// The maximum size of the serialized state of a notification, in bytes.
+const MAX_NOTIFICATION_SIZE: usize = 1024;
+/// The maximum size of a `System.Runtime.Log` message, in bytes.
+const MAX_LOG_SIZE: usize = 1024;
+/// The maximum size of a storage key, in bytes.
+const MAX_STORAGE_KEY_SIZE: usize = 64;
+/// The maximum size of a storage value, in bytes.
+const MAX_STORAGE_VALUE_SIZE: usize = u16::MAX as usize;
+/// The price of verifying a single signature, before the execution fee factor.
+const CHECK_SIG_PRICE: i64 = 1 << 15;
+
+/// The reason a script is executed.
+#[derive(Display, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum TriggerType {
+	/// Native contract bookkeeping before the transactions of a block.
+	OnPersist = 0x01,
+	/// Native contract bookkeeping after the transactions of a block.
+	PostPersist = 0x02,
+	/// A verification script or a contract's `verify` method.
+	Verification = 0x20,
+	/// A transaction script.
+	Application = 0x40,
+}
+
+/// A notification sent by `System.Runtime.Notify`.
+#[derive(Debug, Clone)]
+pub struct NotifyEvent {
+	pub script_hash: H160,
+	pub event_name: String,
+	pub state: VMStackItem,
+}
+
+impl NotifyEvent {
+	/// Converts the event to the form returned by `invokescript`.
+	pub fn to_notification(&self) -> Notification {
+		Notification {
+			contract: self.script_hash,
+			event_name: self.event_name.clone(),
+			state: self.state.to_stack_item(),
+		}
+	}
+}
+
+/// A message sent by `System.Runtime.Log`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogEvent {
+	pub script_hash: H160,
+	pub message: String,
+}
+
+/// The state the [`ApplicationEngine`] attaches to every context it loads.
+#[derive(Debug)]
+pub(crate) struct ContextState {
+	/// The hash of the executing contract, or of the script for dynamic scripts.
+	pub(crate) script_hash: H160,
+	/// The executing contract, `None` for dynamic scripts.
+	pub(crate) contract: Option<ContractState>,
+	pub(crate) calling_script_hash: Option<H160>,
+	/// Whether the context was loaded by the entry context, or is the entry context.
+	pub(crate) called_by_entry: bool,
+	pub(crate) call_flags: u8,
+	/// Whether the context was loaded by `System.Contract.Call`, which always returns a value.
+	dynamic_call: bool,
+	/// The journal position to revert to if the context is unwound by an exception.
+	checkpoint: usize,
+	notification_count: usize,
+	/// Notifications a native contract sends once this callback returns.
+	then_notify: Vec<NotifyEvent>,
+}
+
+/// A call made by a native contract to a deployed contract, such as `onNEP17Payment`.
+///
+/// Native methods run to completion inside a single `SYSCALL`, so their calls are queued and
+/// loaded once the method has returned.
+#[derive(Debug)]
+pub(crate) struct NativeCall {
+	native: H160,
+	contract: H160,
+	method: String,
+	args: Vec<VMStackItem>,
+	then_notify: Vec<NotifyEvent>,
+}
+
+/// The storage context returned by `System.Storage.GetContext`.
+#[derive(Debug)]
+pub(crate) struct StorageContext {
+	id: i32,
+	read_only: bool,
+}
+
+impl InteropObject for StorageContext {
+	fn interface_name(&self) -> &str {
+		"StorageContext"
+	}
+
+	fn as_any(&self) -> &dyn Any {
+		self
+	}
+}
+
+/// Options of `System.Storage.Find`.
+pub(crate) struct FindOptions;
+
+impl FindOptions {
+	pub(crate) const KEYS_ONLY: u8 = 0x01;
+	pub(crate) const REMOVE_PREFIX: u8 = 0x02;
+	pub(crate) const VALUES_ONLY: u8 = 0x04;
+	pub(crate) const DESERIALIZE_VALUES: u8 = 0x08;
+	pub(crate) const PICK_FIELD0: u8 = 0x10;
+	pub(crate) const PICK_FIELD1: u8 = 0x20;
+	pub(crate) const BACKWARDS: u8 = 0x80;
+	const ALL: u8 = 0xbf;
+}
+
+/// The iterator returned by `System.Storage.Find`.
+#[derive(Debug)]
+pub(crate) struct StorageIterator {
+	entries: Vec<(Vec<u8>, Vec<u8>)>,
+	prefix_length: usize,
+	options: u8,
+	position: Cell<Option<usize>>,
+}
+
+impl StorageIterator {
+	pub(crate) fn new(entries: Vec<(Vec<u8>, Vec<u8>)>, prefix_length: usize, options: u8) -> Self {
+		Self { entries, prefix_length, options, position: Cell::new(None) }
+	}
+
+	fn next(&self) -> bool {
+		let position = self.position.get().map_or(0, |position| position + 1);
+		self.position.set(Some(position.min(self.entries.len())));
+		position < self.entries.len()
+	}
+
+	fn value(&self, engine: &ExecutionEngine) -> Result<VMStackItem, VMError> {
+		let (key, value) =
+			self.position.get().and_then(|position| self.entries.get(position)).ok_or_else(
+				|| VMError::InvalidOperation("The iterator has no value".to_string()),
+			)?;
+		let key = if self.options & FindOptions::REMOVE_PREFIX != 0 {
+			key[self.prefix_length..].to_vec()
+		} else {
+			key.clone()
+		};
+		let mut value = if self.options & FindOptions::DESERIALIZE_VALUES != 0 {
+			BinarySerializer::deserialize(value, engine.limits())?
+		} else {
+			VMStackItem::ByteString(value.clone())
+		};
+		if self.options & (FindOptions::PICK_FIELD0 | FindOptions::PICK_FIELD1) != 0 {
+			let index = if self.options & FindOptions::PICK_FIELD0 != 0 { 0 } else { 1 };
+			value = match &value {
+				VMStackItem::Array(items) | VMStackItem::Struct(items) =>
+					items.borrow().get(index).cloned().ok_or_else(|| {
+						VMError::InvalidOperation(format!("The value has no field {}", index))
+					})?,
+				other =>
+					return Err(VMError::InvalidCast(format!("{} has no fields", other.item_type()))),
+			};
+		}
+		Ok(if self.options & FindOptions::KEYS_ONLY != 0 {
+			VMStackItem::ByteString(key)
+		} else if self.options & FindOptions::VALUES_ONLY != 0 {
+			value
+		} else {
+			VMStackItem::new_struct(vec![VMStackItem::ByteString(key), value])
+		})
+	}
+}
+
+impl InteropObject for StorageIterator {
+	fn interface_name(&self) -> &str {
+		"IIterator"
+	}
+
+	fn as_any(&self) -> &dyn Any {
+		self
+	}
+}
+
+/// The blockchain side of an [`ApplicationEngine`]: interop services, native contracts and state.
+#[derive(Debug)]
+pub(crate) struct ApplicationHost {
+	pub(crate) trigger: TriggerType,
+	pub(crate) state: BlockchainState,
+	pub(crate) container: Option<TransactionRecord>,
+	pub(crate) persisting_block: BlockRecord,
+	pub(crate) storage_price: i64,
+	notifications: Vec<NotifyEvent>,
+	logs: Vec<LogEvent>,
+	invocation_counters: HashMap<H160, u32>,
+	native_calls: Vec<NativeCall>,
+	nonce_data: [u8; 16],
+	random_times: u32,
+}
+
+impl ApplicationHost {
+	/// The state of the current context.
+	pub(crate) fn current_state(engine: &ExecutionEngine) -> Result<Rc<ContextState>, VMError> {
+		engine
+			.current_context()
+			.and_then(ExecutionContext::state::<ContextState>)
+			.ok_or_else(|| VMError::InvalidOperation("No context is loaded".to_string()))
+	}
+
+	/// The hash of the contract that called the current contract.
+	pub(crate) fn calling_script_hash(engine: &ExecutionEngine) -> Option<H160> {
+		Self::current_state(engine).ok()?.calling_script_hash
+	}
+
+	/// Reads and deserializes a storage item.
+	pub(crate) fn get_item(
+		&self,
+		engine: &ExecutionEngine,
+		id: i32,
+		key: &[u8],
+	) -> Result<Option<VMStackItem>, VMError> {
+		self.state
+			.get_storage(&crate::neo_vm::StorageKey::new(id, key))
+			.map(|value| BinarySerializer::deserialize(value, engine.limits()))
+			.transpose()
+	}
+
+	/// Serializes and writes a storage item.
+	pub(crate) fn put_item(
+		&mut self,
+		engine: &ExecutionEngine,
+		id: i32,
+		key: &[u8],
+		item: &VMStackItem,
+	) -> Result<(), VMError> {
+		let value = BinarySerializer::serialize(item, engine.limits())?;
+		self.state.put_storage(crate::neo_vm::StorageKey::new(id, key), value);
+		Ok(())
+	}
+
+	/// Reads an integer storage item, as stored by native contracts.
+	pub(crate) fn get_integer(&self, id: i32, key: &[u8]) -> Option<BigInt> {
+		self.state
+			.get_storage(&crate::neo_vm::StorageKey::new(id, key))
+			.map(|value| crate::neo_vm::bytes_to_integer(value))
+	}
+
+	pub(crate) fn put_integer(&mut self, id: i32, key: &[u8], value: &BigInt) {
+		self.state.put_storage(
+			crate::neo_vm::StorageKey::new(id, key),
+			crate::neo_vm::integer_to_bytes(value),
+		);
+	}
+
+	pub(crate) fn delete(&mut self, id: i32, key: &[u8]) {
+		self.state.delete_storage(&crate::neo_vm::StorageKey::new(id, key));
+	}
+
+	/// Sends a notification on behalf of a native contract.
+	///
+	/// If the native method has already queued calls to other contracts, the notification is
+	/// sent once the last of them returns, so that events keep the order of the C# node.
+	pub(crate) fn notify(&mut self, script_hash: H160, event_name: &str, state: Vec<VMStackItem>) {
+		let event = NotifyEvent {
+			script_hash,
+			event_name: event_name.to_string(),
+			state: VMStackItem::new_array(state),
+		};
+		match self.native_calls.last_mut() {
+			Some(call) => call.then_notify.push(event),
+			None => self.notifications.push(event),
+		}
+	}
+
+	/// Queues a call from a native contract to `contract`.
+	pub(crate) fn call_from_native(
+		&mut self,
+		native: NativeContract,
+		contract: H160,
+		method: &str,
+		args: Vec<VMStackItem>,
+	) {
+		self.native_calls.push(NativeCall {
+			native: native.hash(),
+			contract,
+			method: method.to_string(),
+			args,
+			then_notify: Vec::new(),
+		});
+	}
+
+	/// Checks whether the transaction was signed by `hash` with a scope covering the current call.
+	pub(crate) fn check_witness(
+		&self,
+		engine: &ExecutionEngine,
+		hash: &H160,
+	) -> Result<bool, VMError> {
+		if Self::calling_script_hash(engine) == Some(*hash) {
+			return Ok(true);
+		}
+		let Some(transaction) = &self.container else { return Ok(false) };
+		let Some(signer) =
+			transaction.signers.iter().find(|signer| signer.get_signer_hash() == hash)
+		else {
+			return Ok(false);
+		};
+
+		let state = Self::current_state(engine)?;
+		let calling = state.calling_script_hash.and_then(|hash| self.state.contract(&hash));
+		let context = WitnessContext {
+			current_script_hash: state.script_hash,
+			calling_script_hash: state.calling_script_hash,
+			called_by_entry: state.called_by_entry,
+			current_groups: manifest_groups(state.contract.as_ref()),
+			calling_groups: manifest_groups(calling),
+		};
+		Ok(context.check_witness(signer).allowed)
+	}
+
+	/// Loads `method` of `contract` with the given arguments, like `CallContractInternal` in C#.
+	fn call_contract(
+		&mut self,
+		engine: &mut ExecutionEngine,
+		contract: ContractState,
+		method: &ContractMethod,
+		mut flags: u8,
+		args: Vec<VMStackItem>,
+		dynamic_call: bool,
+		then_notify: Vec<NotifyEvent>,
+	) -> Result<(), VMError> {
+		if native::is_blocked(&self.state, &contract.hash) {
+			return Err(VMError::InvalidOperation(format!(
+				"The contract {:?} has been blocked",
+				contract.hash
+			)));
+		}
+		let state = Self::current_state(engine)?;
+		if method.safe {
+			flags &= !(CallFlags::WriteStates.value() | CallFlags::AllowNotify.value());
+		} else if let Some(executing) = &state.contract {
+			if !can_call(&executing.manifest, &contract, &method.name) {
+				return Err(VMError::InvalidOperation(format!(
+					"Cannot call method {} of contract {:?} from contract {:?}",
+					method.name, contract.hash, executing.hash
+				)));
+			}
+		}
+		if args.len() != method.parameters.len() {
+			return Err(VMError::InvalidOperation(format!(
+				"Method {} expects {} arguments, got {}",
+				method.name,
+				method.parameters.len(),
+				args.len()
+			)));
+		}
+		*self.invocation_counters.entry(contract.hash).or_insert(0) += 1;
+
+		let script = general_purpose::STANDARD.decode(&contract.nef.script).map_err(|err| {
+			VMError::InvalidOperation(format!(
+				"The script of {:?} is invalid: {}",
+				contract.hash, err
+			))
+		})?;
+		if method.offset >= script.len() {
+			return Err(VMError::InvalidOperation(format!(
+				"The offset of method {} is outside of the script",
+				method.name
+			)));
+		}
+		let rvcount =
+			if method.return_type == crate::neo_types::ContractParameterType::Void { 0 } else { 1 };
+		let initialize = contract
+			.manifest
+			.abi
+			.as_ref()
+			.and_then(|abi| {
+				abi.methods.iter().find(|m| m.name == "_initialize" && m.parameters.is_empty())
+			})
+			.map(|m| m.offset);
+		let called_by_entry = engine
+			.entry_context()
+			.zip(engine.current_context())
+			.is_some_and(|(entry, current)| entry.shares_stack_with(current));
+
+		let mut context = ExecutionContext::new(Rc::from(script), rvcount);
+		context.set_instruction_pointer(method.offset);
+		context.set_state(ContextState {
+			script_hash: contract.hash,
+			calling_script_hash: Some(state.script_hash),
+			called_by_entry,
+			call_flags: flags & state.call_flags,
+			dynamic_call,
+			checkpoint: self.state.checkpoint(),
+			notification_count: self.notifications.len(),
+			then_notify,
+			contract: Some(contract),
+		});
+		let initialize = initialize.map(|offset| context.clone_at(offset));
+		engine.load_context(context)?;
+		for arg in args.into_iter().rev() {
+			engine.push(arg)?;
+		}
+		if let Some(initialize) = initialize {
+			engine.load_context(initialize)?;
+		}
+		Ok(())
+	}
+
+	fn contract_call(&mut self, engine: &mut ExecutionEngine) -> Result<(), VMError> {
+		let hash = hash160_from_item(&engine.pop()?)?;
+		let method = engine.pop()?.get_string()?;
+		let flags = engine.pop_integer()?.to_u8().filter(|flags| flags & !0x0f == 0);
+		let flags =
+			flags.ok_or_else(|| VMError::InvalidOperation("Invalid call flags".to_string()))?;
+		let args = pop_array(engine)?;
+		if method.starts_with('_') {
+			return Err(VMError::InvalidOperation(format!(
+				"Method {} can't be called directly",
+				method
+			)));
+		}
+		let contract = self.state.contract(&hash).cloned().ok_or_else(|| {
+			VMError::InvalidOperation(format!("Called contract {:?} does not exist", hash))
+		})?;
+		let abi_method = find_method(&contract.manifest, &method, args.len()).ok_or_else(|| {
+			VMError::InvalidOperation(format!(
+				"Method {} with {} parameter(s) doesn't exist in the contract {:?}",
+				method,
+				args.len(),
+				hash
+			))
+		})?;
+		self.call_contract(engine, contract, &abi_method, flags, args, true, Vec::new())
+	}
+
+	fn call_native(&mut self, engine: &mut ExecutionEngine) -> Result<(), VMError> {
+		let state = Self::current_state(engine)?;
+		let native = NativeContract::from_hash(&state.script_hash).ok_or_else(|| {
+			VMError::InvalidOperation("CallNative can only be used by native contracts".to_string())
+		})?;
+		let version = engine.pop_integer()?;
+		if version != BigInt::from(0) {
+			return Err(VMError::InvalidOperation(format!(
+				"The native contract of version {} is not active",
+				version
+			)));
+		}
+		let position = engine.current_context().map_or(0, ExecutionContext::instruction_pointer);
+		let method = native.method_at(position).ok_or_else(|| {
+			VMError::InvalidOperation(format!("No method of {} at {}", native.name(), position))
+		})?;
+		if state.call_flags & method.required_flags != method.required_flags {
+			return Err(VMError::InvalidOperation(format!(
+				"Cannot call method {} of {} with the flags {}",
+				method.name,
+				native.name(),
+				native::call_flags_to_string(state.call_flags)
+			)));
+		}
+		engine.add_gas(
+			method.cpu_fee * engine.exec_fee_factor() as i64
+				+ method.storage_fee * self.storage_price,
+		)?;
+		let mut args = Vec::with_capacity(method.parameters.len());
+		for _ in 0..method.parameters.len() {
+			args.push(engine.pop()?);
+		}
+
+		self.native_calls.clear();
+		let result = (method.handler)(self, engine, args)?;
+		if method.return_type != crate::neo_types::ContractParameterType::Void {
+			engine.push(result)?;
+		}
+		// The first queued call must run first, so it's loaded last
+		for call in std::mem::take(&mut self.native_calls).into_iter().rev() {
+			let contract = self.state.contract(&call.contract).cloned().ok_or_else(|| {
+				VMError::InvalidOperation(format!(
+					"Called contract {:?} does not exist",
+					call.contract
+				))
+			})?;
+			let abi_method = find_method(&contract.manifest, &call.method, call.args.len())
+				.ok_or_else(|| {
+					VMError::InvalidOperation(format!(
+						"Method {} doesn't exist in the contract {:?}",
+						call.method, call.contract
+					))
+				})?;
+			debug_assert_eq!(call.native, state.script_hash);
+			self.call_contract(
+				engine,
+				contract,
+				&abi_method,
+				CallFlags::All.value(),
+				call.args,
+				false,
+				call.then_notify,
+			)?;
+		}
+		Ok(())
+	}
+
+	fn native_on_persist(&mut self, engine: &mut ExecutionEngine) -> Result<(), VMError> {
+		if self.trigger != TriggerType::OnPersist {
+			return Err(VMError::InvalidOperation("NativeOnPersist requires OnPersist".to_string()));
+		}
+		for native in NativeContract::all() {
+			if self.state.contract(&native.hash()).is_none() {
+				self.state.put_contract(native.contract_state());
+				native.initialize(self, engine)?;
+			}
+		}
+		for native in NativeContract::all() {
+			native.on_persist(self, engine)?;
+		}
+		Ok(())
+	}
+
+	fn native_post_persist(&mut self, engine: &mut ExecutionEngine) -> Result<(), VMError> {
+		if self.trigger != TriggerType::PostPersist {
+			return Err(VMError::InvalidOperation(
+				"NativePostPersist requires PostPersist".to_string(),
+			));
+		}
+		for native in NativeContract::all() {
+			native.post_persist(self, engine)?;
+		}
+		Ok(())
+	}
+
+	fn check_sig(&self, public_key: &[u8], signature: &[u8]) -> bool {
+		let Some(transaction) = &self.container else { return false };
+		let mut message = self.state.settings().network.to_le_bytes().to_vec();
+		message.extend(transaction.hash.0.iter().rev());
+		let (Ok(public_key), Ok(signature)) =
+			(Secp256r1PublicKey::from_bytes(public_key), Secp256r1Signature::from_bytes(signature))
+		else {
+			return false;
+		};
+		public_key.verify(&message, &signature).is_ok()
+	}
+
+	fn check_multisig(&self, engine: &mut ExecutionEngine) -> Result<bool, VMError> {
+		let public_keys = pop_array(engine)?;
+		let signatures = pop_array(engine)?;
+		let (n, m) = (public_keys.len(), signatures.len());
+		if m == 0 || m > n {
+			return Err(VMError::InvalidOperation(format!(
+				"Invalid multisig: {} signatures for {} keys",
+				m, n
+			)));
+		}
+		engine.add_gas(CHECK_SIG_PRICE * n as i64 * engine.exec_fee_factor() as i64)?;
+		let (mut i, mut j) = (0, 0);
+		while i < m && j < n {
+			if self.check_sig(&public_keys[j].get_span()?, &signatures[i].get_span()?) {
+				i += 1;
+			}
+			j += 1;
+			if m - i > n - j {
+				return Ok(false);
+			}
+		}
+		Ok(i == m)
+	}
+
+	fn runtime_notify(&mut self, engine: &mut ExecutionEngine) -> Result<(), VMError> {
+		let name = engine.pop_bytes()?;
+		let state = engine.pop()?;
+		if name.len() > MAX_EVENT_NAME {
+			return Err(VMError::InvalidOperation(format!(
+				"Event names can't be longer than {} bytes",
+				MAX_EVENT_NAME
+			)));
+		}
+		let name = String::from_utf8(name)
+			.map_err(|_| VMError::InvalidOperation("The event name isn't UTF-8".to_string()))?;
+		let context_state = Self::current_state(engine)?;
+		let contract = context_state.contract.as_ref().ok_or_else(|| {
+			VMError::InvalidOperation(
+				"Notifications are not allowed in dynamic scripts".to_string(),
+			)
+		})?;
+		let event = contract
+			.manifest
+			.abi
+			.as_ref()
+			.and_then(|abi| abi.events.iter().find(|event| event.name == name))
+			.ok_or_else(|| VMError::InvalidOperation(format!("Event {} does not exist", name)))?;
+		let count = match &state {
+			VMStackItem::Array(items) => items.borrow().len(),
+			other =>
+				return Err(VMError::InvalidCast(format!("{} is not an Array", other.item_type()))),
+		};
+		if count != event.parameters.len() {
+			return Err(VMError::InvalidOperation(format!(
+				"Event {} expects {} parameters, got {}",
+				name,
+				event.parameters.len(),
+				count
+			)));
+		}
+		let mut limits = engine.limits().clone();
+		limits.max_item_size = MAX_NOTIFICATION_SIZE;
+		let data = BinarySerializer::serialize(&state, &limits)?;
+		self.notifications.push(NotifyEvent {
+			script_hash: context_state.script_hash,
+			event_name: name,
+			state: BinarySerializer::deserialize(&data, engine.limits())?,
+		});
+		Ok(())
+	}
+
+	fn get_notifications(&self, engine: &mut ExecutionEngine) -> Result<VMStackItem, VMError> {
+		let filter = match engine.pop()? {
+			VMStackItem::Null => None,
+			item => Some(hash160_from_item(&item)?),
+		};
+		let notifications: Vec<VMStackItem> = self
+			.notifications
+			.iter()
+			.filter(|event| filter.is_none_or(|hash| event.script_hash == hash))
+			.map(|event| {
+				VMStackItem::new_array(vec![
+					hash160_item(&event.script_hash),
+					VMStackItem::from(event.event_name.as_str()),
+					event.state.clone(),
+				])
+			})
+			.collect();
+		if notifications.len() > engine.limits().max_stack_size {
+			return Err(VMError::InvalidOperation("Too many notifications".to_string()));
+		}
+		Ok(VMStackItem::new_array(notifications))
+	}
+
+	fn get_random(&mut self) -> VMStackItem {
+		let seed = self.state.settings().network.wrapping_add(self.random_times);
+		self.random_times += 1;
+		self.nonce_data = murmur128(&self.nonce_data, seed);
+		VMStackItem::Integer(BigInt::from_bytes_le(num_bigint::Sign::Plus, &self.nonce_data))
+	}
+
+	fn storage_context(
+		&self,
+		engine: &ExecutionEngine,
+		read_only: bool,
+	) -> Result<VMStackItem, VMError> {
+		let state = Self::current_state(engine)?;
+		let contract = self.state.contract(&state.script_hash).ok_or_else(|| {
+			VMError::InvalidOperation(format!(
+				"The contract {:?} is not deployed",
+				state.script_hash
+			))
+		})?;
+		Ok(VMStackItem::new_interop(StorageContext { id: contract.id, read_only }))
+	}
+
+	fn storage_find(&self, engine: &mut ExecutionEngine) -> Result<VMStackItem, VMError> {
+		let context = pop_interop(engine)?;
+		let context = downcast::<StorageContext>(&context)?;
+		let prefix = engine.pop_bytes()?;
+		let options =
+			engine.pop_integer()?.to_u8().filter(|options| options & !FindOptions::ALL == 0);
+		let options =
+			options.ok_or_else(|| VMError::InvalidOperation("Invalid find options".to_string()))?;
+		let has = |flag: u8| options & flag != 0;
+		let pick_field = has(FindOptions::PICK_FIELD0) || has(FindOptions::PICK_FIELD1);
+		let invalid = (has(FindOptions::KEYS_ONLY)
+			&& (has(FindOptions::VALUES_ONLY)
+				|| has(FindOptions::DESERIALIZE_VALUES)
+				|| pick_field))
+			|| (has(FindOptions::VALUES_ONLY)
+				&& (has(FindOptions::KEYS_ONLY) || has(FindOptions::REMOVE_PREFIX)))
+			|| (has(FindOptions::PICK_FIELD0) && has(FindOptions::PICK_FIELD1))
+			|| (pick_field && !has(FindOptions::DESERIALIZE_VALUES));
+		if invalid {
+			return Err(VMError::InvalidOperation(format!(
+				"Invalid find options 0x{:02x}",
+				options
+			)));
+		}
+		let mut entries = self.state.find_storage(context.id, &prefix);
+		if has(FindOptions::BACKWARDS) {
+			entries.reverse();
+		}
+		Ok(VMStackItem::new_interop(StorageIterator::new(entries, prefix.len(), options)))
+	}
+
+	fn storage_put(&mut self, engine: &mut ExecutionEngine) -> Result<(), VMError> {
+		let context = pop_interop(engine)?;
+		let context = downcast::<StorageContext>(&context)?;
+		let key = engine.pop_bytes()?;
+		let value = engine.pop_bytes()?;
+		if key.len() > MAX_STORAGE_KEY_SIZE || value.len() > MAX_STORAGE_VALUE_SIZE {
+			return Err(VMError::InvalidOperation("The key or value is too large".to_string()));
+		}
+		if context.read_only {
+			return Err(VMError::InvalidOperation("The storage context is read-only".to_string()));
+		}
+		let storage_key = crate::neo_vm::StorageKey::new(context.id, key.clone());
+		let new_data_size = match self.state.get_storage(&storage_key) {
+			None => key.len() + value.len(),
+			Some(_) if value.is_empty() => 0,
+			Some(old) if value.len() <= old.len() => (value.len() - 1) / 4 + 1,
+			Some(old) if old.is_empty() => value.len(),
+			Some(old) => (old.len() - 1) / 4 + 1 + value.len() - old.len(),
+		};
+		engine.add_gas(new_data_size as i64 * self.storage_price)?;
+		self.state.put_storage(storage_key, value);
+		Ok(())
+	}
+
+	fn dispatch(
+		&mut self,
+		engine: &mut ExecutionEngine,
+		service: InteropService,
+	) -> Result<(), VMError> {
+		match service {
+			InteropService::SystemCryptoCheckSig => {
+				let public_key = engine.pop_bytes()?;
+				let signature = engine.pop_bytes()?;
+				let valid = self.check_sig(&public_key, &signature);
+				engine.push(VMStackItem::Boolean(valid))?;
+			},
+			InteropService::SystemCryptoCheckMultiSig => {
+				let valid = self.check_multisig(engine)?;
+				engine.push(VMStackItem::Boolean(valid))?;
+			},
+			InteropService::SystemContractCall => self.contract_call(engine)?,
+			InteropService::SystemContractCallNative => self.call_native(engine)?,
+			InteropService::SystemContractGetCallFlags => {
+				let flags = Self::current_state(engine)?.call_flags;
+				engine.push(VMStackItem::from(flags as i64))?;
+			},
+			InteropService::SystemContractCreateStandardAccount => {
+				let public_key = Secp256r1PublicKey::from_bytes(&engine.pop_bytes()?)
+					.map_err(|err| VMError::InvalidOperation(err.to_string()))?;
+				let script = ScriptBuilder::build_verification_script(&public_key);
+				engine.push(hash160_item(&H160::from_script(&script)))?;
+			},
+			InteropService::SystemContractCreateMultiSigAccount => {
+				let threshold = engine.pop_integer()?;
+				let mut public_keys = pop_array(engine)?
+					.iter()
+					.map(|key| {
+						Secp256r1PublicKey::from_bytes(&key.get_span()?)
+							.map_err(|err| VMError::InvalidOperation(err.to_string()))
+					})
+					.collect::<Result<Vec<_>, _>>()?;
+				let threshold = threshold
+					.to_u8()
+					.filter(|threshold| *threshold >= 1 && *threshold as usize <= public_keys.len())
+					.ok_or_else(|| VMError::InvalidOperation("Invalid threshold".to_string()))?;
+				let script = ScriptBuilder::build_multi_sig_script(&mut public_keys, threshold)
+					.map_err(|err| VMError::InvalidOperation(err.to_string()))?;
+				engine.push(hash160_item(&H160::from_script(&script)))?;
+			},
+			InteropService::SystemContractNativeOnPersist => self.native_on_persist(engine)?,
+			InteropService::SystemContractNativePostPersist => self.native_post_persist(engine)?,
+			InteropService::SystemIteratorNext => {
+				let iterator = pop_interop(engine)?;
+				let has_next = downcast::<StorageIterator>(&iterator)?.next();
+				engine.push(VMStackItem::Boolean(has_next))?;
+			},
+			InteropService::SystemIteratorValue => {
+				let iterator = pop_interop(engine)?;
+				let value = downcast::<StorageIterator>(&iterator)?.value(engine)?;
+				engine.push(value)?;
+			},
+			InteropService::SystemRuntimePlatform => engine.push(VMStackItem::from("NEO"))?,
+			InteropService::SystemRuntimeGetTrigger =>
+				engine.push(VMStackItem::from(self.trigger as i64))?,
+			InteropService::SystemRuntimeGetTime =>
+				engine.push(VMStackItem::Integer(BigInt::from(self.persisting_block.timestamp)))?,
+			InteropService::SystemRuntimeGetScriptContainer => {
+				let transaction = self.container.as_ref().ok_or_else(|| {
+					VMError::InvalidOperation("There is no script container".to_string())
+				})?;
+				engine.push(native::transaction_item(transaction))?;
+			},
+			InteropService::SystemRuntimeGetExecutingScriptHash => {
+				let hash = Self::current_state(engine)?.script_hash;
+				engine.push(hash160_item(&hash))?;
+			},
+			InteropService::SystemRuntimeGetCallingScriptHash => {
+				let item = Self::calling_script_hash(engine)
+					.map_or(VMStackItem::Null, |hash| hash160_item(&hash));
+				engine.push(item)?;
+			},
+			InteropService::SystemRuntimeGetEntryScriptHash => {
+				let entry =
+					engine.entry_context().and_then(ExecutionContext::state::<ContextState>);
+				let hash = entry.map(|state| state.script_hash).unwrap_or_default();
+				engine.push(hash160_item(&hash))?;
+			},
+			InteropService::SystemRuntimeCheckWitness => {
+				let hash_or_key = engine.pop_bytes()?;
+				let hash = match hash_or_key.len() {
+					20 => hash160_from_item(&VMStackItem::ByteString(hash_or_key))?,
+					33 => {
+						let public_key = Secp256r1PublicKey::from_bytes(&hash_or_key)
+							.map_err(|err| VMError::InvalidOperation(err.to_string()))?;
+						H160::from_script(&ScriptBuilder::build_verification_script(&public_key))
+					},
+					length =>
+						return Err(VMError::InvalidOperation(format!(
+							"Invalid hash or public key of {} bytes",
+							length
+						))),
+				};
+				let result = self.check_witness(engine, &hash)?;
+				engine.push(VMStackItem::Boolean(result))?;
+			},
+			InteropService::SystemRuntimeGetInvocationCounter => {
+				let hash = Self::current_state(engine)?.script_hash;
+				let counter = *self.invocation_counters.entry(hash).or_insert(1);
+				engine.push(VMStackItem::from(counter as i64))?;
+			},
+			InteropService::SystemRuntimeLog => {
+				let message = engine.pop_bytes()?;
+				if message.len() > MAX_LOG_SIZE {
+					return Err(VMError::InvalidOperation(
+						"The log message is too long".to_string(),
+					));
+				}
+				let message = String::from_utf8(message).map_err(|_| {
+					VMError::InvalidOperation("The log message isn't UTF-8".to_string())
+				})?;
+				let script_hash = Self::current_state(engine)?.script_hash;
+				self.logs.push(LogEvent { script_hash, message });
+			},
+			InteropService::SystemRuntimeNotify => self.runtime_notify(engine)?,
+			InteropService::SystemRuntimeGetNotifications => {
+				let notifications = self.get_notifications(engine)?;
+				engine.push(notifications)?;
+			},
+			InteropService::SystemRuntimeGasLeft => {
+				let left = engine.gas_limit() - engine.gas_consumed();
+				engine.push(VMStackItem::from(left))?;
+			},
+			InteropService::SystemRuntimeBurnGas => {
+				let datoshi = engine.pop_integer()?.to_i64().filter(|datoshi| *datoshi > 0);
+				let datoshi = datoshi
+					.ok_or_else(|| VMError::InvalidOperation("GAS must be positive".to_string()))?;
+				engine.add_gas(datoshi)?;
+			},
+			InteropService::SystemRuntimeGetNetwork =>
+				engine.push(VMStackItem::from(self.state.settings().network as i64))?,
+			InteropService::SystemRuntimeGetRandom => {
+				let random = self.get_random();
+				engine.push(random)?;
+			},
+			InteropService::SystemStorageGetContext => {
+				let context = self.storage_context(engine, false)?;
+				engine.push(context)?;
+			},
+			InteropService::SystemStorageGetReadOnlyContext => {
+				let context = self.storage_context(engine, true)?;
+				engine.push(context)?;
+			},
+			InteropService::SystemStorageAsReadOnly => {
+				let context = pop_interop(engine)?;
+				let context = downcast::<StorageContext>(&context)?;
+				engine.push(VMStackItem::new_interop(StorageContext {
+					id: context.id,
+					read_only: true,
+				}))?;
+			},
+			InteropService::SystemStorageGet => {
+				let context = pop_interop(engine)?;
+				let context = downcast::<StorageContext>(&context)?;
+				let key = crate::neo_vm::StorageKey::new(context.id, engine.pop_bytes()?);
+				let value = self.state.get_storage(&key).cloned();
+				engine.push(value.map_or(VMStackItem::Null, VMStackItem::ByteString))?;
+			},
+			InteropService::SystemStorageFind => {
+				let iterator = self.storage_find(engine)?;
+				engine.push(iterator)?;
+			},
+			InteropService::SystemStoragePut => self.storage_put(engine)?,
+			InteropService::SystemStorageDelete => {
+				let context = pop_interop(engine)?;
+				let context = downcast::<StorageContext>(&context)?;
+				let key = engine.pop_bytes()?;
+				if context.read_only {
+					return Err(VMError::InvalidOperation(
+						"The storage context is read-only".to_string(),
+					));
+				}
+				self.state.delete_storage(&crate::neo_vm::StorageKey::new(context.id, key));
+			},
+		}
+		Ok(())
+	}
+}
+
+impl InteropHost for ApplicationHost {
+	fn on_syscall(&mut self, engine: &mut ExecutionEngine, method: u32) -> Result<(), VMError> {
+		let service = InteropService::from_hash(hex::encode(method.to_le_bytes()))
+			.ok_or_else(|| VMError::UnsupportedSyscall(crate::neo_vm::syscall_name(method)))?;
+		let required = required_flags(service);
+		let flags = Self::current_state(engine)?.call_flags;
+		if flags & required != required {
+			return Err(VMError::Catchable(format!(
+				"Cannot call {} with the flags {}",
+				service,
+				native::call_flags_to_string(flags)
+			)));
+		}
+		self.dispatch(engine, service).map_err(catchable)
+	}
+
+	fn on_call_token(&mut self, engine: &mut ExecutionEngine, token: u16) -> Result<(), VMError> {
+		let state = Self::current_state(engine)?;
+		let required = CallFlags::ReadStates.value() | CallFlags::AllowCall.value();
+		if state.call_flags & required != required {
+			return Err(VMError::Catchable("CALLT requires ReadStates and AllowCall".to_string()));
+		}
+		let token = state
+			.contract
+			.as_ref()
+			.and_then(|contract| contract.nef.tokens.get(token as usize))
+			.cloned()
+			.ok_or_else(|| {
+				VMError::InvalidOperation(format!("Method token {} doesn't exist", token))
+			})?;
+		let result: Result<(), VMError> = (|| {
+			let mut args = Vec::with_capacity(token.param_count() as usize);
+			for _ in 0..token.param_count() {
+				args.push(engine.pop()?);
+			}
+			let contract = self.state.contract(&token.hash()).cloned().ok_or_else(|| {
+				VMError::InvalidOperation(format!(
+					"Called contract {:?} does not exist",
+					token.hash()
+				))
+			})?;
+			let method = find_method(&contract.manifest, token.method(), args.len())
+				.filter(|method| {
+					(method.return_type != crate::neo_types::ContractParameterType::Void)
+						== token.has_return_value()
+				})
+				.ok_or_else(|| {
+					VMError::InvalidOperation(format!(
+						"Method {} doesn't exist in the contract {:?}",
+						token.method(),
+						token.hash()
+					))
+				})?;
+			let flags = call_flags_from_string(token.call_flags()).ok_or_else(|| {
+				VMError::InvalidOperation(format!("Invalid call flags {}", token.call_flags()))
+			})?;
+			self.call_contract(engine, contract, &method, flags, args, false, Vec::new())
+		})();
+		result.map_err(catchable)
+	}
+
+	fn on_context_unloaded(
+		&mut self,
+		engine: &mut ExecutionEngine,
+		context: &ExecutionContext,
+		unwound: bool,
+	) -> Result<(), VMError> {
+		// Internal CALLs share the state of the contract that made them
+		if engine
+			.current_context()
+			.is_some_and(|current| current.shares_stack_with(context))
+		{
+			return Ok(());
+		}
+		let Some(state) = context.state::<ContextState>() else { return Ok(()) };
+		if unwound {
+			self.state.revert(state.checkpoint);
+			self.notifications.truncate(state.notification_count);
+			return Ok(());
+		}
+		self.notifications.extend(state.then_notify.iter().cloned());
+		if state.dynamic_call && context.rvcount() == 0 && engine.current_context().is_some() {
+			engine.push(VMStackItem::Null)?;
+		}
+		Ok(())
+	}
+}
+
+/// A NeoVM engine with the interop services and native contracts of a Neo N3 node.
+///
+/// The engine executes scripts against a [`BlockchainState`]. Changes made by a contract call
+/// that throws are reverted, like on a real node, and [`into_state`](Self::into_state) returns
+/// the state with all changes of a halted execution, or none of a faulted one.
+///
+/// # Examples
+///
+/// ```rust
+/// use neo3::neo_builder::ScriptBuilder;
+/// use neo3::neo_types::{ContractParameter, StackItem, VMState};
+/// use neo3::neo_vm::{LocalBlockchain, NativeContract};
+///
+/// let chain = LocalBlockchain::new();
+/// let script = ScriptBuilder::new()
+///     .contract_call(&NativeContract::GasToken.hash(), "symbol", &[], None)
+///     .unwrap()
+///     .to_bytes();
+///
+/// let result = chain.test_invoke_script(script, vec![]);
+/// assert_eq!(result.state, neo3::neo_types::NeoVMStateType::Halt);
+/// assert_eq!(result.stack[0].as_string(), Some("GAS".to_string()));
+/// ```
+#[derive(Debug)]
+pub struct ApplicationEngine {
+	engine: ExecutionEngine,
+	host: ApplicationHost,
+}
+
+impl ApplicationEngine {
+	/// Creates an engine executing in the context of `persisting_block`.
+	///
+	/// `container` is the transaction whose signers are checked by `System.Runtime.CheckWitness`.
+	pub fn new(
+		trigger: TriggerType,
+		state: BlockchainState,
+		container: Option<TransactionRecord>,
+		persisting_block: BlockRecord,
+		gas_limit: i64,
+	) -> Self {
+		let mut engine = ExecutionEngine::new();
+		engine.set_gas_limit(gas_limit);
+		let mut storage_price = native::DEFAULT_STORAGE_PRICE;
+		if persisting_block.index > 0 {
+			engine.set_exec_fee_factor(native::exec_fee_factor(&state));
+			storage_price = native::storage_price(&state);
+		}
+
+		let mut nonce_data = [0u8; 16];
+		if let Some(transaction) = &container {
+			let hash: Vec<u8> = transaction.hash.0.iter().rev().copied().collect();
+			nonce_data.copy_from_slice(&hash[..16]);
+		}
+		let nonce = u64::from_le_bytes(nonce_data[..8].try_into().unwrap_or_default())
+			^ persisting_block.nonce;
+		nonce_data[..8].copy_from_slice(&nonce.to_le_bytes());
+
+		Self {
+			engine,
+			host: ApplicationHost {
+				trigger,
+				state,
+				container,
+				persisting_block,
+				storage_price,
+				notifications: Vec::new(),
+				logs: Vec::new(),
+				invocation_counters: HashMap::new(),
+				native_calls: Vec::new(),
+				nonce_data,
+				random_times: 0,
+			},
+		}
+	}
+
+	/// Loads `script` as the entry script with all call flags.
+	pub fn load_script(&mut self, script: Vec<u8>) -> Result<(), VMError> {
+		let context = ExecutionContext::new(Rc::from(script), -1);
+		context.set_state(ContextState {
+			script_hash: context.script_hash(),
+			contract: None,
+			calling_script_hash: None,
+			called_by_entry: true,
+			call_flags: CallFlags::All.value(),
+			dynamic_call: false,
+			checkpoint: self.host.state.checkpoint(),
+			notification_count: self.host.notifications.len(),
+			then_notify: Vec::new(),
+		});
+		self.engine.load_context(context)
+	}
+
+	/// Executes until the engine halts or faults.
+	pub fn execute(&mut self) -> VMState {
+		self.engine.execute_with(&mut self.host)
+	}
+
+	/// Executes a single instruction, leaving the engine in the `BREAK` state unless it halted
+	/// or faulted.
+	pub fn step(&mut self) -> VMState {
+		self.engine.step_with(&mut self.host)
+	}
+
+	pub fn trigger(&self) -> TriggerType {
+		self.host.trigger
+	}
+
+	/// The underlying NeoVM engine.
+	pub fn engine(&self) -> &ExecutionEngine {
+		&self.engine
+	}
+
+	/// The blockchain state, including the changes made so far.
+	pub fn blockchain(&self) -> &BlockchainState {
+		&self.host.state
+	}
+
+	pub fn notifications(&self) -> &[NotifyEvent] {
+		&self.host.notifications
+	}
+
+	pub fn logs(&self) -> &[LogEvent] {
+		&self.host.logs
+	}
+
+	/// Returns the blockchain state, keeping the changes only if the engine halted.
+	pub fn into_state(mut self) -> BlockchainState {
+		if self.engine.state() == VMState::Halt {
+			self.host.state.commit();
+		} else {
+			self.host.state.revert(0);
+		}
+		self.host.state
+	}
+
+	/// Builds the result `invokescript` would return for the execution of `script`.
+	pub fn invocation_result(&self, script: &[u8]) -> InvocationResult {
+		let result = self.engine.result();
+		let state = match result.state {
+			VMState::Halt => NeoVMStateType::Halt,
+			VMState::Fault => NeoVMStateType::Fault,
+			VMState::Break => NeoVMStateType::Break,
+			VMState::None => NeoVMStateType::None,
+		};
+		InvocationResult::new(
+			general_purpose::STANDARD.encode(script),
+			state,
+			result.gas_consumed.to_string(),
+			result.exception,
+			Some(self.host.notifications.iter().map(NotifyEvent::to_notification).collect()),
+			None,
+			result.stack,
+			None,
+			None,
+			None,
+		)
+	}
+}
+
+/// The call flags an interop service requires.
+fn required_flags(service: InteropService) -> u8 {
+	match service {
+		InteropService::SystemContractCall =>
+			CallFlags::ReadStates.value() | CallFlags::AllowCall.value(),
+		InteropService::SystemRuntimeLog | InteropService::SystemRuntimeNotify =>
+			CallFlags::AllowNotify.value(),
+		InteropService::SystemStorageGetContext
+		| InteropService::SystemStorageGetReadOnlyContext
+		| InteropService::SystemStorageAsReadOnly
+		| InteropService::SystemStorageGet
+		| InteropService::SystemStorageFind => CallFlags::ReadStates.value(),
+		InteropService::SystemStoragePut | InteropService::SystemStorageDelete =>
+			CallFlags::WriteStates.value(),
+		InteropService::SystemContractNativeOnPersist
+		| InteropService::SystemContractNativePostPersist => CallFlags::States.value(),
+		_ => CallFlags::None.value(),
+	}
+}
+
+/// Turns errors raised by interop services into exceptions scripts can catch, like on a node.
+fn catchable(err: VMError) -> VMError {
+	match err {
+		VMError::InsufficientGas { .. } | VMError::Catchable(_) => err,
+		other => VMError::Catchable(other.to_string()),
+	}
+}
+
+fn find_method(
+	manifest: &ContractManifest,
+	name: &str,
+	parameters: usize,
+) -> Option<ContractMethod> {
+	manifest
+		.abi
+		.as_ref()?
+		.methods
+		.iter()
+		.find(|method| method.name == name && method.parameters.len() == parameters)
+		.cloned()
+}
+
+/// The groups declared in the manifest of `contract`.
+fn manifest_groups(contract: Option<&ContractState>) -> Vec<Secp256r1PublicKey> {
+	contract.map_or_else(Vec::new, |contract| {
+		contract
+			.manifest
+			.groups
+			.iter()
+			.filter_map(|member| Secp256r1PublicKey::from_encoded(&member.pub_key))
+			.collect()
+	})
+}
+
+fn has_group(contract: Option<&ContractState>, group: &Secp256r1PublicKey) -> bool {
+	let encoded = group.get_encoded(true);
+	contract.is_some_and(|contract| {
+		contract.manifest.groups.iter().any(|member| {
+			hex::decode(member.pub_key.trim_start_matches("0x")).is_ok_and(|key| key == encoded)
+		})
+	})
+}
+
+/// Whether a contract with `manifest` may call `method` of `target`.
+fn can_call(manifest: &ContractManifest, target: &ContractState, method: &str) -> bool {
+	manifest.permissions.iter().any(|permission| {
+		let contract_matches = match permission.contract.as_str() {
+			"*" => true,
+			descriptor if descriptor.len() == 66 => Secp256r1PublicKey::from_encoded(descriptor)
+				.is_some_and(|key| has_group(Some(target), &key)),
+			descriptor => H160::from_str(descriptor).is_ok_and(|hash| hash == target.hash),
+		};
+		contract_matches && permission.methods.iter().any(|name| name == "*" || name == method)
+	})
+}
+
+fn pop_array(engine: &mut ExecutionEngine) -> Result<Vec<VMStackItem>, VMError> {
+	match engine.pop()? {
+		VMStackItem::Array(items) | VMStackItem::Struct(items) => Ok(items.borrow().clone()),
+		other => Err(VMError::InvalidCast(format!("{} is not an Array", other.item_type()))),
+	}
+}
+
+fn pop_interop(engine: &mut ExecutionEngine) -> Result<Rc<dyn InteropObject>, VMError> {
+	match engine.pop()? {
+		VMStackItem::InteropInterface(object) => Ok(object),
+		other =>
+			Err(VMError::InvalidCast(format!("{} is not an InteropInterface", other.item_type()))),
+	}
+}
+
+fn downcast<T: 'static>(object: &Rc<dyn InteropObject>) -> Result<&T, VMError> {
+	object.as_any().downcast_ref::<T>().ok_or_else(|| {
+		VMError::InvalidCast(format!("{} has an unexpected type", object.interface_name()))
+	})
+}
+
+/// MurmurHash3 x64 128-bit, as used by `System.Runtime.GetRandom`.
+fn murmur128(data: &[u8], seed: u32) -> [u8; 16] {
+	const C1: u64 = 0x87c3_7b91_1142_53d5;
+	const C2: u64 = 0x4cf5_ad43_2745_937f;
+	let fmix = |mut k: u64| {
+		k ^= k >> 33;
+		k = k.wrapping_mul(0xff51_afd7_ed55_8ccd);
+		k ^= k >> 33;
+		k = k.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+		k ^ (k >> 33)
+	};
+	let (mut h1, mut h2) = (seed as u64, seed as u64);
+	let (chunks, tail) = data.as_chunks::<16>();
+	for chunk in chunks {
+		let k1 = u64::from_le_bytes(chunk[..8].try_into().unwrap_or_default());
+		let k2 = u64::from_le_bytes(chunk[8..].try_into().unwrap_or_default());
+		h1 ^= k1.wrapping_mul(C1).rotate_left(31).wrapping_mul(C2);
+		h1 = h1.rotate_left(27).wrapping_add(h2).wrapping_mul(5).wrapping_add(0x52dc_e729);
+		h2 ^= k2.wrapping_mul(C2).rotate_left(33).wrapping_mul(C1);
+		h2 = h2.rotate_left(31).wrapping_add(h1).wrapping_mul(5).wrapping_add(0x3849_5ab5);
+	}
+	if !tail.is_empty() {
+		let mut padded = [0u8; 16];
+		padded[..tail.len()].copy_from_slice(tail);
+		let k1 = u64::from_le_bytes(padded[..8].try_into().unwrap_or_default());
+		let k2 = u64::from_le_bytes(padded[8..].try_into().unwrap_or_default());
+		if tail.len() > 8 {
+			h2 ^= k2.wrapping_mul(C2).rotate_left(33).wrapping_mul(C1);
+		}
+		h1 ^= k1.wrapping_mul(C1).rotate_left(31).wrapping_mul(C2);
+	}
+	let length = data.len() as u64;
+	h1 ^= length;
+	h2 ^= length;
+	h1 = h1.wrapping_add(h2);
+	h2 = h2.wrapping_add(h1);
+	h1 = fmix(h1);
+	h2 = fmix(h2);
+	h1 = h1.wrapping_add(h2);
+	h2 = h2.wrapping_add(h1);
+	let mut output = [0u8; 16];
+	output[..8].copy_from_slice(&h1.to_le_bytes());
+	output[8..].copy_from_slice(&h2.to_le_bytes());
+	output
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_murmur128() {
+		// Test vectors of the C# implementation
+		assert_eq!(hex::encode(murmur128(b"hello", 123)), "0bc59d0ad25fde2982ed65af61227a0e");
+		assert_eq!(hex::encode(murmur128(b"world", 123)), "3d3810fed480472bd214a14023bb407f");
+		assert_eq!(hex::encode(murmur128(b"hello world", 123)), "e0a0632d4f51302c55e3b3e48d28795d");
+	}
+
+	#[test]
+	fn test_storage_iterator_options() {
+		let engine = ExecutionEngine::new();
+		let entries = vec![(vec![0x01, 0xaa], vec![0x01]), (vec![0x01, 0xbb], vec![0x02])];
+		let iterator =
+			StorageIterator::new(entries, 1, FindOptions::REMOVE_PREFIX | FindOptions::KEYS_ONLY);
+
+		assert!(iterator.value(&engine).is_err());
+		assert!(iterator.next());
+		assert_eq!(iterator.value(&engine).unwrap().get_span().unwrap(), vec![0xaa]);
+		assert!(iterator.next());
+		assert_eq!(iterator.value(&engine).unwrap().get_span().unwrap(), vec![0xbb]);
+		assert!(!iterator.next());
+		assert!(!iterator.next());
+	}
+}
@@ -0,0 +1,295 @@
+use std::{cell::RefCell, collections::HashSet, rc::Rc};
+
+use crate::{
+	neo_codec::{CodecError, Decoder, Encoder},
+	neo_vm::{
+		bytes_to_integer, integer_to_bytes, ExecutionEngineLimits, StackItemType, VMError,
+		VMStackItem, MAX_INTEGER_SIZE,
+	},
+};
+
+/// The binary format used by `StdLib.serialize` and by native contracts to persist stack items.
+///
+/// Each item is written as its [`StackItemType`] tag followed by its payload: a single byte for
+/// booleans, variable-length bytes for integers and byte strings, and an element count followed
+/// by the elements for arrays, structs and maps. `Pointer` and `InteropInterface` items can't be
+/// serialized.
+///
+/// Like the C# implementation, both directions walk the item with an explicit stack rather than
+/// recursion, so the nesting depth is only bounded by the item count limit.
+pub struct BinarySerializer;
+
+/// An item read by [`BinarySerializer::deserialize`], whose elements are not assembled yet.
+enum Deserialized {
+	Item(VMStackItem),
+	Container(StackItemType, usize),
+}
+
+impl BinarySerializer {
+	/// Serializes `item`, failing if the output would exceed `limits.max_item_size` bytes or if
+	/// `item` holds more than `limits.max_stack_size` items.
+	///
+	/// Compound items may appear only once in the serialized graph, so circular and shared
+	/// references are rejected.
+	pub fn serialize(
+		item: &VMStackItem,
+		limits: &ExecutionEngineLimits,
+	) -> Result<Vec<u8>, VMError> {
+		let mut writer = Encoder::new();
+		let mut serialized = HashSet::new();
+		let mut unserialized = vec![item.clone()];
+		let mut items = 0;
+		while let Some(item) = unserialized.pop() {
+			items += 1;
+			if items > limits.max_stack_size {
+				return Err(VMError::InvalidOperation(format!(
+					"Serialized item holds more than {} items",
+					limits.max_stack_size
+				)));
+			}
+			writer.write_u8(item.item_type() as u8);
+			match &item {
+				VMStackItem::Null => {},
+				VMStackItem::Boolean(value) => writer.write_bool(*value),
+				VMStackItem::Integer(value) =>
+					writer.write_var_bytes(&integer_to_bytes(value)).map_err(invalid_length)?,
+				VMStackItem::ByteString(bytes) =>
+					writer.write_var_bytes(bytes).map_err(invalid_length)?,
+				VMStackItem::Buffer(buffer) =>
+					writer.write_var_bytes(&buffer.borrow()).map_err(invalid_length)?,
+				VMStackItem::Array(items) | VMStackItem::Struct(items) => {
+					if !serialized.insert(Rc::as_ptr(items) as *const ()) {
+						return Err(VMError::InvalidOperation(
+							"Items referenced more than once can't be serialized".to_string(),
+						));
+					}
+					let items = items.borrow();
+					writer.write_var_int(items.len() as i64).map_err(invalid_length)?;
+					unserialized.extend(items.iter().rev().cloned());
+				},
+				VMStackItem::Map(entries) => {
+					if !serialized.insert(Rc::as_ptr(entries) as *const ()) {
+						return Err(VMError::InvalidOperation(
+							"Items referenced more than once can't be serialized".to_string(),
+						));
+					}
+					let entries = entries.borrow();
+					writer.write_var_int(entries.len() as i64).map_err(invalid_length)?;
+					for (key, value) in entries.iter().rev() {
+						unserialized.push(value.clone());
+						unserialized.push(key.clone());
+					}
+				},
+				VMStackItem::Pointer { .. } | VMStackItem::InteropInterface(_) =>
+					return Err(VMError::InvalidOperation(format!(
+						"{} items can't be serialized",
+						item.item_type()
+					))),
+			}
+			if writer.size() > limits.max_item_size {
+				return Err(VMError::ItemTooLarge(format!(
+					"Serialized item exceeds {} bytes",
+					limits.max_item_size
+				)));
+			}
+		}
+		Ok(writer.to_bytes())
+	}
+
+	/// Deserializes an item, enforcing the size and item count limits of the engine.
+	pub fn deserialize(
+		data: &[u8],
+		limits: &ExecutionEngineLimits,
+	) -> Result<VMStackItem, VMError> {
+		let mut reader = Decoder::new(data);
+		let mut deserialized = Vec::new();
+		let mut undeserialized = 1;
+		while undeserialized > 0 {
+			undeserialized -= 1;
+			let tag = reader.try_read_u8().map_err(invalid_data)?;
+			let item_type = StackItemType::try_from(tag).map_err(|_| {
+				VMError::InvalidOperation(format!("Invalid stack item type 0x{:02x}", tag))
+			})?;
+			let item = match item_type {
+				StackItemType::Any => VMStackItem::Null,
+				StackItemType::Boolean => match reader.try_read_u8().map_err(invalid_data)? {
+					0 => VMStackItem::Boolean(false),
+					1 => VMStackItem::Boolean(true),
+					value =>
+						return Err(VMError::InvalidOperation(format!(
+							"Invalid boolean value {}",
+							value
+						))),
+				},
+				StackItemType::Integer => {
+					let bytes = read_var_bytes(&mut reader, MAX_INTEGER_SIZE)?;
+					VMStackItem::Integer(bytes_to_integer(&bytes))
+				},
+				StackItemType::ByteString =>
+					VMStackItem::ByteString(read_var_bytes(&mut reader, limits.max_item_size)?),
+				StackItemType::Buffer =>
+					VMStackItem::new_buffer(read_var_bytes(&mut reader, limits.max_item_size)?),
+				StackItemType::Array | StackItemType::Struct | StackItemType::Map => {
+					let count = read_var_int(&mut reader, limits.max_stack_size)?;
+					undeserialized +=
+						if item_type == StackItemType::Map { count * 2 } else { count };
+					deserialized.push(Deserialized::Container(item_type, count));
+					continue;
+				},
+				StackItemType::Pointer | StackItemType::InteropInterface =>
+					return Err(VMError::InvalidOperation(format!(
+						"{} items can't be deserialized",
+						item_type
+					))),
+			};
+			deserialized.push(Deserialized::Item(item));
+			if deserialized.len() > limits.max_stack_size {
+				return Err(VMError::InvalidOperation(format!(
+					"Serialized data holds more than {} items",
+					limits.max_stack_size
+				)));
+			}
+		}
+		if reader.available() != 0 {
+			return Err(VMError::InvalidOperation(
+				"Unexpected data after the serialized item".to_string(),
+			));
+		}
+
+		// Assemble the containers from the last item read, so their elements are complete
+		let mut assembled: Vec<VMStackItem> = Vec::new();
+		while let Some(item) = deserialized.pop() {
+			let mut next = || assembled.pop().ok_or(VMError::StackUnderflow);
+			let item = match item {
+				Deserialized::Item(item) => item,
+				Deserialized::Container(StackItemType::Map, count) => {
+					let mut entries = Vec::with_capacity(count);
+					for _ in 0..count {
+						let key = next()?;
+						key.check_map_key()?;
+						entries.push((key, next()?));
+					}
+					VMStackItem::Map(Rc::new(RefCell::new(entries)))
+				},
+				Deserialized::Container(item_type, count) => {
+					let items = (0..count).map(|_| next()).collect::<Result<Vec<_>, _>>()?;
+					if item_type == StackItemType::Array {
+						VMStackItem::new_array(items)
+					} else {
+						VMStackItem::new_struct(items)
+					}
+				},
+			};
+			assembled.push(item);
+		}
+		assembled.pop().ok_or(VMError::StackUnderflow)
+	}
+}
+
+fn invalid_data(err: CodecError) -> VMError {
+	VMError::InvalidOperation(format!("Invalid serialized data: {}", err))
+}
+
+fn invalid_length(err: std::io::Error) -> VMError {
+	VMError::InvalidOperation(format!("Invalid length: {}", err))
+}
+
+fn read_var_int(reader: &mut Decoder, max: usize) -> Result<usize, VMError> {
+	let value = reader.read_var_int().map_err(invalid_data)?;
+	usize::try_from(value).ok().filter(|value| *value <= max).ok_or_else(|| {
+		VMError::InvalidOperation(format!("Length {} exceeds the maximum of {}", value, max))
+	})
+}
+
+fn read_var_bytes(reader: &mut Decoder, max: usize) -> Result<Vec<u8>, VMError> {
+	let length = read_var_int(reader, max)?;
+	reader.read_bytes(length).map_err(invalid_data)
+}
+
+#[cfg(test)]
+mod tests {
+	use num_bigint::BigInt;
+
+	use super::*;
+	use crate::{neo_types::StackItem, neo_vm::MAX_STACK_ITEM_DEPTH};
+
+	#[test]
+	fn test_serialize_round_trip() {
+		let limits = ExecutionEngineLimits::default();
+		let map = VMStackItem::new_map();
+		if let VMStackItem::Map(entries) = &map {
+			entries.borrow_mut().push((VMStackItem::from("key"), VMStackItem::from(-1i64)));
+		}
+		let item = VMStackItem::new_array(vec![
+			VMStackItem::Null,
+			VMStackItem::Boolean(true),
+			VMStackItem::from(BigInt::from(256)),
+			VMStackItem::from(vec![0xaa, 0xbb]),
+			VMStackItem::new_struct(vec![VMStackItem::from(0i64)]),
+			map,
+		]);
+
+		let data = BinarySerializer::serialize(&item, &limits).unwrap();
+
+		assert_eq!(hex::encode(&data), "4006002001210200012802aabb41012100480128036b65792101ff");
+		let decoded = BinarySerializer::deserialize(&data, &limits).unwrap();
+		assert!(decoded.equals(&item, &limits).is_ok());
+		assert_eq!(decoded.to_stack_item(), item.to_stack_item());
+	}
+
+	#[test]
+	fn test_serialize_rejects_references() {
+		let limits = ExecutionEngineLimits::default();
+		let inner = VMStackItem::new_array(vec![]);
+		let outer = VMStackItem::new_array(vec![inner.clone(), inner]);
+
+		assert!(BinarySerializer::serialize(&outer, &limits).is_err());
+		assert!(BinarySerializer::serialize(
+			&VMStackItem::Pointer { script: Rc::from(vec![0x40]), position: 0 },
+			&limits
+		)
+		.is_err());
+	}
+
+	#[test]
+	fn test_deeply_nested_items() {
+		let limits = ExecutionEngineLimits::default();
+		let item = (1..limits.max_stack_size)
+			.fold(VMStackItem::Null, |item, _| VMStackItem::new_array(vec![item]));
+
+		// The nesting is only bounded by the item count
+		let data = BinarySerializer::serialize(&item, &limits).unwrap();
+		assert_eq!(data.len(), 2 * limits.max_stack_size - 1);
+		let decoded = BinarySerializer::deserialize(&data, &limits).unwrap();
+		assert_eq!(BinarySerializer::serialize(&decoded, &limits).unwrap(), data);
+
+		let item = VMStackItem::new_array(vec![item]);
+		assert!(BinarySerializer::serialize(&item, &limits).is_err());
+		let data = [[0x40, 0x01].as_slice(), &data].concat();
+		assert!(BinarySerializer::deserialize(&data, &limits).is_err());
+
+		// The recursive conversions to and from JSON stack items are bounded separately
+		assert!(item.try_to_stack_item().is_err());
+		let json = item.to_stack_item();
+		let (mut depth, mut inner) = (0, &json);
+		while let StackItem::Array { value } = inner {
+			(depth, inner) = (depth + 1, &value[0]);
+		}
+		assert_eq!((depth, inner), (MAX_STACK_ITEM_DEPTH, &StackItem::Any));
+		assert!(VMStackItem::from_stack_item(&json).is_ok());
+		let json = StackItem::Array { value: vec![json] };
+		assert!(VMStackItem::from_stack_item(&json).is_err());
+	}
+
+	#[test]
+	fn test_deserialize_rejects_malformed_data() {
+		let limits = ExecutionEngineLimits::default();
+
+		// Truncated byte string, trailing data and an unknown type tag
+		assert!(BinarySerializer::deserialize(&[0x28, 0x02, 0xaa], &limits).is_err());
+		assert!(BinarySerializer::deserialize(&[0x00, 0x00], &limits).is_err());
+		assert!(BinarySerializer::deserialize(&[0x99], &limits).is_err());
+		// Arrays can't be used as map keys
+		assert!(BinarySerializer::deserialize(&[0x48, 0x01, 0x40, 0x00, 0x00], &limits).is_err());
+	}
+}
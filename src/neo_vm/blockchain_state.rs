@@ -0,0 +1,561 @@
+use std::{
+	collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+	sync::{Arc, Mutex},
+};
+
+use primitive_types::{H160, H256};
+use sha2::{Digest, Sha256};
+
+use crate::{
+	builder::{ScriptBuilder, Signer},
+	codec::{CodecError, Decoder, Encoder},
+	crypto::{Secp256r1PrivateKey, Secp256r1PublicKey},
+	neo_types::{ContractState, ScriptHashExtension, VMState},
+	neo_vm::NativeContract,
+};
+
+/// A key in contract storage: the id of the owning contract followed by the key bytes.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct StorageKey {
+	pub id: i32,
+	pub key: Vec<u8>,
+}
+
+impl StorageKey {
+	pub fn new(id: i32, key: impl Into<Vec<u8>>) -> Self {
+		Self { id, key: key.into() }
+	}
+}
+
+/// The protocol settings of a local chain.
+#[derive(Debug, Clone)]
+pub struct ChainSettings {
+	/// The network magic, mixed into signed data and random numbers.
+	pub network: u32,
+	/// The target time between blocks, in milliseconds.
+	pub ms_per_block: u32,
+	/// The committee members. The first `validators_count` members are the validators.
+	pub standby_committee: Vec<Secp256r1PublicKey>,
+	pub validators_count: usize,
+	/// The amount of GAS, in datoshi, minted to the validators at genesis.
+	pub initial_gas_distribution: i64,
+}
+
+impl Default for ChainSettings {
+	/// A single-node chain whose only committee member is the key with all bytes set to `1`.
+	fn default() -> Self {
+		let key = Secp256r1PrivateKey::from_bytes(&[1u8; 32])
+			.expect("A private key of all 1s is valid")
+			.to_public_key();
+		Self {
+			network: 0x334F454E,
+			ms_per_block: 15_000,
+			standby_committee: vec![key],
+			validators_count: 1,
+			initial_gas_distribution: 5_200_000_000_000_000,
+		}
+	}
+}
+
+impl ChainSettings {
+	/// The multi-signature address controlled by a majority of the committee.
+	pub fn committee_address(&self) -> H160 {
+		let count = self.standby_committee.len();
+		multi_sig_address(&self.standby_committee, count - (count - 1) / 2)
+	}
+
+	/// The multi-signature address of the validators, which receives the genesis NEO and GAS.
+	pub fn validators_address(&self) -> H160 {
+		let count = self.validators_count;
+		multi_sig_address(&self.standby_committee[..count], count - (count - 1) / 3)
+	}
+}
+
+/// A block persisted by a local chain.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockRecord {
+	pub hash: H256,
+	pub version: u32,
+	pub prev_hash: H256,
+	pub merkle_root: H256,
+	/// Milliseconds since the Unix epoch.
+	pub timestamp: u64,
+	pub nonce: u64,
+	pub index: u32,
+	pub primary_index: u8,
+	pub next_consensus: H160,
+	pub transactions: Vec<H256>,
+}
+
+impl BlockRecord {
+	/// Decodes the header of a serialized block, leaving its transactions empty.
+	pub fn from_header(bytes: &[u8]) -> Result<Self, CodecError> {
+		let mut decoder = Decoder::new(bytes);
+		let hash = |bytes: Vec<u8>| H256::from_slice(&bytes.into_iter().rev().collect::<Vec<_>>());
+		let mut block = Self {
+			hash: H256::zero(),
+			version: decoder.read_u32()?,
+			prev_hash: hash(decoder.read_bytes(32)?),
+			merkle_root: hash(decoder.read_bytes(32)?),
+			timestamp: decoder.read_u64()?,
+			nonce: decoder.read_u64()?,
+			index: decoder.read_u32()?,
+			primary_index: decoder.read_bytes(1)?[0],
+			next_consensus: H160::from_slice(
+				&decoder.read_bytes(20)?.into_iter().rev().collect::<Vec<_>>(),
+			),
+			transactions: Vec::new(),
+		};
+		block.hash = block.compute_hash();
+		Ok(block)
+	}
+
+	/// Creates a block and computes its hash and merkle root.
+	pub fn new(
+		prev_hash: H256,
+		index: u32,
+		timestamp: u64,
+		nonce: u64,
+		next_consensus: H160,
+		transactions: Vec<H256>,
+	) -> Self {
+		let mut block = Self {
+			hash: H256::zero(),
+			version: 0,
+			prev_hash,
+			merkle_root: merkle_root(&transactions),
+			timestamp,
+			nonce,
+			index,
+			primary_index: 0,
+			next_consensus,
+			transactions,
+		};
+		block.hash = block.compute_hash();
+		block
+	}
+
+	fn compute_hash(&self) -> H256 {
+		let mut data = Vec::new();
+		data.extend_from_slice(&self.version.to_le_bytes());
+		data.extend(self.prev_hash.0.iter().rev());
+		data.extend(self.merkle_root.0.iter().rev());
+		data.extend_from_slice(&self.timestamp.to_le_bytes());
+		data.extend_from_slice(&self.nonce.to_le_bytes());
+		data.extend_from_slice(&self.index.to_le_bytes());
+		data.push(self.primary_index);
+		data.extend(self.next_consensus.0.iter().rev());
+		hash256(&data)
+	}
+}
+
+/// A transaction persisted by a local chain.
+#[derive(Debug, Clone)]
+pub struct TransactionRecord {
+	pub hash: H256,
+	pub block_index: u32,
+	pub vm_state: VMState,
+	pub version: u8,
+	pub nonce: u32,
+	pub system_fee: i64,
+	pub network_fee: i64,
+	pub valid_until_block: u32,
+	pub signers: Vec<Signer>,
+	pub script: Vec<u8>,
+}
+
+impl TransactionRecord {
+	/// Creates a transaction and computes its hash from its unsigned serialization.
+	pub fn new(nonce: u32, valid_until_block: u32, signers: Vec<Signer>, script: Vec<u8>) -> Self {
+		let mut transaction = Self {
+			hash: H256::zero(),
+			block_index: 0,
+			vm_state: VMState::None,
+			version: 0,
+			nonce,
+			system_fee: 0,
+			network_fee: 0,
+			valid_until_block,
+			signers,
+			script,
+		};
+		transaction.hash = transaction.compute_hash();
+		transaction
+	}
+
+	/// The account that pays the fees of the transaction.
+	pub fn sender(&self) -> Option<H160> {
+		self.signers.first().map(|signer| *signer.get_signer_hash())
+	}
+
+	fn compute_hash(&self) -> H256 {
+		let mut writer = Encoder::new();
+		writer.write_u8(self.version);
+		writer.write_u32(self.nonce);
+		writer.write_i64(self.system_fee);
+		writer.write_i64(self.network_fee);
+		writer.write_u32(self.valid_until_block);
+		writer
+			.write_serializable_variable_list(&self.signers)
+			.expect("Failed to encode signers");
+		// No attributes
+		writer.write_u8(0);
+		writer.write_var_bytes(&self.script).expect("Failed to encode script");
+		hash256(&writer.to_bytes())
+	}
+}
+
+/// State a forked chain needs from the chain it forks, reported by reads of a
+/// [`BlockchainState`] that hasn't loaded it yet.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum MissingState {
+	Contract(H160),
+	ContractId(i32),
+	Storage(StorageKey),
+	/// All the entries of a contract whose key starts with a prefix.
+	StoragePrefix(StorageKey),
+}
+
+/// What a forked state has loaded from the chain it forks.
+#[derive(Debug, Clone, Default)]
+struct ForkCache {
+	contracts: HashSet<H160>,
+	contract_ids: HashSet<i32>,
+	keys: HashSet<StorageKey>,
+	/// Prefixes whose entries are all loaded, including the empty prefix of local contracts.
+	prefixes: Vec<StorageKey>,
+	/// Shared by clones, so that engines running on a copy of the state report to the chain.
+	missing: Arc<Mutex<BTreeSet<MissingState>>>,
+}
+
+impl ForkCache {
+	fn has_prefix(&self, id: i32, key: &[u8]) -> bool {
+		self.prefixes
+			.iter()
+			.any(|prefix| prefix.id == id && key.starts_with(&prefix.key))
+	}
+
+	fn has_key(&self, key: &StorageKey) -> bool {
+		self.keys.contains(key) || self.has_prefix(key.id, &key.key)
+	}
+
+	fn report(&self, missing: MissingState) {
+		if let Ok(mut set) = self.missing.lock() {
+			set.insert(missing);
+		}
+	}
+}
+
+#[derive(Debug, Clone)]
+enum JournalEntry {
+	Storage(StorageKey, Option<Vec<u8>>),
+	Contract(H160, Option<Box<ContractState>>),
+	Transaction(H256),
+}
+
+/// The state of a local chain: contract storage, deployed contracts, blocks and transactions.
+///
+/// Every change is journaled so that the [`ApplicationEngine`](crate::neo_vm::ApplicationEngine)
+/// can revert the changes of a contract call that throws, or of a whole faulted execution.
+///
+/// A state created by [`fork`](Self::fork) starts from a block of another chain. Reads of
+/// contracts and storage it hasn't loaded yet return nothing and are reported as
+/// [`MissingState`], which [`ForkedBlockchain`](crate::neo_vm::ForkedBlockchain) loads before
+/// running the execution again.
+#[derive(Debug, Clone, Default)]
+pub struct BlockchainState {
+	settings: ChainSettings,
+	fork: Option<ForkCache>,
+	/// The index of the first block, which is the forked block for forks.
+	base_index: u32,
+	storage: BTreeMap<StorageKey, Vec<u8>>,
+	contracts: HashMap<H160, ContractState>,
+	contract_ids: BTreeMap<i32, H160>,
+	blocks: Vec<BlockRecord>,
+	block_indexes: HashMap<H256, u32>,
+	transactions: HashMap<H256, TransactionRecord>,
+	journal: Vec<JournalEntry>,
+}
+
+impl BlockchainState {
+	/// Creates an empty state. Native contracts are deployed by the genesis block of
+	/// [`LocalBlockchain`](crate::neo_vm::LocalBlockchain).
+	pub fn new(settings: ChainSettings) -> Self {
+		Self { settings, ..Default::default() }
+	}
+
+	/// Creates a state continuing from `block` of another chain, with nothing loaded but the
+	/// native contracts.
+	pub fn fork(settings: ChainSettings, block: BlockRecord) -> Self {
+		let mut state = Self { settings, base_index: block.index, ..Default::default() };
+		state.fork = Some(ForkCache::default());
+		for native in NativeContract::all() {
+			state.load_contract(native.hash(), Some(native.contract_state()));
+		}
+		state.add_block(block);
+		state
+	}
+
+	pub fn settings(&self) -> &ChainSettings {
+		&self.settings
+	}
+
+	pub fn is_fork(&self) -> bool {
+		self.fork.is_some()
+	}
+
+	/// The index of the latest persisted block, or `None` before genesis.
+	pub fn height(&self) -> Option<u32> {
+		self.blocks.last().map(|block| block.index)
+	}
+
+	pub fn current_block(&self) -> Option<&BlockRecord> {
+		self.blocks.last()
+	}
+
+	/// The block at `index`. Forks only have the blocks from the forked block on.
+	pub fn block(&self, index: u32) -> Option<&BlockRecord> {
+		self.blocks.get(index.checked_sub(self.base_index)? as usize)
+	}
+
+	pub fn block_by_hash(&self, hash: &H256) -> Option<&BlockRecord> {
+		self.block_indexes.get(hash).and_then(|index| self.block(*index))
+	}
+
+	pub fn transaction(&self, hash: &H256) -> Option<&TransactionRecord> {
+		self.transactions.get(hash)
+	}
+
+	pub fn get_storage(&self, key: &StorageKey) -> Option<&Vec<u8>> {
+		self.require_key(key);
+		self.storage.get(key)
+	}
+
+	/// Returns the entries of contract `id` whose key starts with `prefix`, in ascending key order.
+	pub fn find_storage(&self, id: i32, prefix: &[u8]) -> Vec<(Vec<u8>, Vec<u8>)> {
+		if let Some(fork) = self.fork.as_ref().filter(|fork| !fork.has_prefix(id, prefix)) {
+			fork.report(MissingState::StoragePrefix(StorageKey::new(id, prefix)));
+		}
+		self.storage
+			.range(StorageKey::new(id, prefix)..)
+			.take_while(|(key, _)| key.id == id && key.key.starts_with(prefix))
+			.map(|(key, value)| (key.key.clone(), value.clone()))
+			.collect()
+	}
+
+	pub fn put_storage(&mut self, key: StorageKey, value: Vec<u8>) {
+		// The previous value is journaled, so it must be loaded even though it isn't returned
+		self.require_key(&key);
+		let previous = self.storage.insert(key.clone(), value);
+		self.journal.push(JournalEntry::Storage(key, previous));
+	}
+
+	pub fn delete_storage(&mut self, key: &StorageKey) {
+		self.require_key(key);
+		if let Some(previous) = self.storage.remove(key) {
+			self.journal.push(JournalEntry::Storage(key.clone(), Some(previous)));
+		}
+	}
+
+	pub fn contract(&self, hash: &H160) -> Option<&ContractState> {
+		if let Some(fork) = self.fork.as_ref().filter(|fork| !fork.contracts.contains(hash)) {
+			fork.report(MissingState::Contract(*hash));
+		}
+		self.contracts.get(hash)
+	}
+
+	pub fn contract_by_id(&self, id: i32) -> Option<&ContractState> {
+		if let Some(fork) = self.fork.as_ref().filter(|fork| !fork.contract_ids.contains(&id)) {
+			fork.report(MissingState::ContractId(id));
+		}
+		self.contract_ids.get(&id).and_then(|hash| self.contracts.get(hash))
+	}
+
+	/// Returns all contracts ordered by id, native contracts first. Forks only return the
+	/// contracts they have loaded.
+	pub fn contracts(&self) -> impl Iterator<Item = &ContractState> {
+		self.contract_ids.values().filter_map(|hash| self.contracts.get(hash))
+	}
+
+	pub fn put_contract(&mut self, contract: ContractState) {
+		let hash = contract.hash;
+		if let Some(fork) = &mut self.fork {
+			fork.contracts.insert(hash);
+			fork.contract_ids.insert(contract.id);
+			if !self.contracts.contains_key(&hash) {
+				// A new contract has no storage on the forked chain
+				fork.prefixes.push(StorageKey::new(contract.id, []));
+			}
+		}
+		self.contract_ids.insert(contract.id, hash);
+		let previous = self.contracts.insert(hash, contract);
+		self.journal.push(JournalEntry::Contract(hash, previous.map(Box::new)));
+	}
+
+	/// Removes a contract and all of its storage.
+	pub fn delete_contract(&mut self, hash: &H160) {
+		let Some(contract) = self.contracts.remove(hash) else { return };
+		self.contract_ids.remove(&contract.id);
+		let id = contract.id;
+		self.journal.push(JournalEntry::Contract(*hash, Some(Box::new(contract))));
+		for (key, _) in self.find_storage(id, &[]) {
+			self.delete_storage(&StorageKey::new(id, key));
+		}
+	}
+
+	/// Records a transaction executed in the next block.
+	pub fn add_transaction(&mut self, transaction: TransactionRecord) {
+		let hash = transaction.hash;
+		self.transactions.insert(hash, transaction);
+		self.journal.push(JournalEntry::Transaction(hash));
+	}
+
+	/// Appends a block. Blocks are never reverted.
+	pub fn add_block(&mut self, block: BlockRecord) {
+		self.block_indexes.insert(block.hash, block.index);
+		self.blocks.push(block);
+	}
+
+	/// The position in the journal, used to revert later changes with [`revert`](Self::revert).
+	pub(crate) fn checkpoint(&self) -> usize {
+		self.journal.len()
+	}
+
+	/// Undoes every change made after `checkpoint`.
+	pub(crate) fn revert(&mut self, checkpoint: usize) {
+		while self.journal.len() > checkpoint {
+			match self.journal.pop() {
+				Some(JournalEntry::Storage(key, Some(value))) => {
+					self.storage.insert(key, value);
+				},
+				Some(JournalEntry::Storage(key, None)) => {
+					self.storage.remove(&key);
+				},
+				Some(JournalEntry::Contract(hash, previous)) => {
+					if let Some(current) = self.contracts.remove(&hash) {
+						self.contract_ids.remove(&current.id);
+					}
+					if let Some(previous) = previous {
+						self.contract_ids.insert(previous.id, hash);
+						self.contracts.insert(hash, *previous);
+					}
+				},
+				Some(JournalEntry::Transaction(hash)) => {
+					self.transactions.remove(&hash);
+				},
+				None => {},
+			}
+		}
+	}
+
+	/// Makes all changes permanent.
+	pub(crate) fn commit(&mut self) {
+		self.journal.clear();
+	}
+
+	fn require_key(&self, key: &StorageKey) {
+		if let Some(fork) = self.fork.as_ref().filter(|fork| !fork.has_key(key)) {
+			fork.report(MissingState::Storage(key.clone()));
+		}
+	}
+
+	/// Returns the state reported missing since the last call, and forgets it.
+	pub(crate) fn take_missing(&self) -> Vec<MissingState> {
+		let Some(fork) = &self.fork else { return Vec::new() };
+		fork.missing
+			.lock()
+			.map(|mut set| std::mem::take(&mut *set).into_iter().collect())
+			.unwrap_or_default()
+	}
+
+	/// The hash of a contract by id, without reporting it as missing.
+	pub(crate) fn loaded_contract_hash(&self, id: i32) -> Option<H160> {
+		self.contract_ids.get(&id).copied()
+	}
+
+	/// Records a contract of the forked chain, or that it doesn't exist.
+	pub(crate) fn load_contract(&mut self, hash: H160, contract: Option<ContractState>) {
+		let Some(fork) = &mut self.fork else { return };
+		if !fork.contracts.insert(hash) {
+			return;
+		}
+		if let Some(contract) = contract {
+			fork.contract_ids.insert(contract.id);
+			self.contract_ids.insert(contract.id, hash);
+			self.contracts.insert(hash, contract);
+		}
+	}
+
+	/// Records that the forked chain has no contract with `id`.
+	pub(crate) fn load_missing_contract_id(&mut self, id: i32) {
+		if let Some(fork) = &mut self.fork {
+			fork.contract_ids.insert(id);
+		}
+	}
+
+	/// Records a storage entry of the forked chain, unless the key was already loaded.
+	pub(crate) fn load_storage(&mut self, key: StorageKey, value: Option<Vec<u8>>) {
+		let Some(fork) = &mut self.fork else { return };
+		if fork.has_key(&key) {
+			return;
+		}
+		fork.keys.insert(key.clone());
+		if let Some(value) = value {
+			self.storage.insert(key, value);
+		}
+	}
+
+	/// Records all the entries of the forked chain under `prefix`.
+	pub(crate) fn load_storage_prefix(
+		&mut self,
+		prefix: StorageKey,
+		entries: Vec<(Vec<u8>, Vec<u8>)>,
+	) {
+		for (key, value) in entries {
+			self.load_storage(StorageKey::new(prefix.id, key), Some(value));
+		}
+		if let Some(fork) = &mut self.fork {
+			fork.prefixes.push(prefix);
+		}
+	}
+}
+
+pub(crate) fn multi_sig_address(keys: &[Secp256r1PublicKey], threshold: usize) -> H160 {
+	let mut keys = keys.to_vec();
+	let script = ScriptBuilder::build_multi_sig_script(&mut keys, threshold as u8)
+		.expect("The committee has between 1 and 1024 members");
+	H160::from_script(&script)
+}
+
+pub(crate) fn hash256(data: &[u8]) -> H256 {
+	let mut hash: [u8; 32] = Sha256::digest(Sha256::digest(data)).into();
+	hash.reverse();
+	H256(hash)
+}
+
+fn merkle_root(hashes: &[H256]) -> H256 {
+	if hashes.is_empty() {
+		return H256::zero();
+	}
+	let mut level: Vec<[u8; 32]> = hashes
+		.iter()
+		.map(|hash| {
+			let mut bytes = hash.0;
+			bytes.reverse();
+			bytes
+		})
+		.collect();
+	while level.len() > 1 {
+		level = level
+			.chunks(2)
+			.map(|pair| {
+				let mut data = pair[0].to_vec();
+				data.extend_from_slice(pair.get(1).unwrap_or(&pair[0]));
+				Sha256::digest(Sha256::digest(&data)).into()
+			})
+			.collect();
+	}
+	let mut root = level[0];
+	root.reverse();
+	H256(root)
+}
@@ -0,0 +1,425 @@
+use std::io::Read;
+
+use flate2::read::DeflateDecoder;
+use primitive_types::H160;
+use serde::Deserialize;
+
+use crate::neo_vm::DebugInfoError;
+
+/// The debug information a compiler such as neon or neow3j emits next to a NEF file.
+///
+/// It maps the instructions of the script to methods and to the source lines they were compiled
+/// from, and names the variables of each method.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DebugInfo {
+	/// The hash of the NEF script the information describes.
+	pub hash: H160,
+	/// The source files, referenced by index from the sequence points.
+	pub documents: Vec<String>,
+	/// The directory the documents are relative to, if any.
+	pub document_root: Option<String>,
+	pub static_variables: Vec<DebugVariable>,
+	pub methods: Vec<DebugMethod>,
+}
+
+/// A method of a contract and the range of instructions it was compiled to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DebugMethod {
+	pub id: String,
+	/// The namespace and class of the method, e.g. `Neo.SmartContract.Token`.
+	pub namespace: String,
+	pub name: String,
+	/// The offset of the first instruction of the method.
+	pub start: usize,
+	/// The offset of the last instruction of the method.
+	pub end: usize,
+	pub parameters: Vec<DebugVariable>,
+	pub return_type: String,
+	pub variables: Vec<DebugVariable>,
+	/// The sequence points of the method, in ascending address order.
+	pub sequence_points: Vec<SequencePoint>,
+}
+
+/// A named variable stored in a slot.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DebugVariable {
+	pub name: String,
+	pub typ: String,
+	/// The index of the variable in its slot.
+	pub index: usize,
+}
+
+/// The first instruction of a source statement, and the range of the statement in its document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SequencePoint {
+	pub address: usize,
+	/// The index of the source file in [`DebugInfo::documents`].
+	pub document: usize,
+	pub start_line: u32,
+	pub start_column: u32,
+	pub end_line: u32,
+	pub end_column: u32,
+}
+
+#[derive(Deserialize)]
+struct RawDebugInfo {
+	hash: String,
+	#[serde(default)]
+	documents: Vec<String>,
+	#[serde(rename = "document-root", default)]
+	document_root: Option<String>,
+	#[serde(rename = "static-variables", default)]
+	static_variables: Vec<String>,
+	#[serde(default)]
+	methods: Vec<RawMethod>,
+}
+
+#[derive(Deserialize)]
+struct RawMethod {
+	id: String,
+	name: String,
+	range: String,
+	#[serde(default)]
+	params: Vec<String>,
+	#[serde(rename = "return", default)]
+	return_type: String,
+	#[serde(default)]
+	variables: Vec<String>,
+	#[serde(rename = "sequence-points", default)]
+	sequence_points: Vec<String>,
+}
+
+impl DebugInfo {
+	/// Parses the JSON debug information, as found in `.debug.json` files.
+	pub fn from_json(json: &str) -> Result<Self, DebugInfoError> {
+		let raw: RawDebugInfo = serde_json::from_str(json)
+			.map_err(|err| DebugInfoError::InvalidDebugInfo(err.to_string()))?;
+		let hash = hex::decode(raw.hash.trim_start_matches("0x"))
+			.ok()
+			.filter(|bytes| bytes.len() == 20)
+			.map(|bytes| H160::from_slice(&bytes))
+			.ok_or_else(|| invalid(format!("Invalid script hash {}", raw.hash)))?;
+		let methods = raw.methods.into_iter().map(parse_method).collect::<Result<_, _>>()?;
+		Ok(Self {
+			hash,
+			documents: raw.documents,
+			document_root: raw.document_root,
+			static_variables: parse_variables(&raw.static_variables)?,
+			methods,
+		})
+	}
+
+	/// Parses a `.nefdbgnfo` file, a zip archive holding the JSON debug information.
+	pub fn from_nefdbgnfo(archive: &[u8]) -> Result<Self, DebugInfoError> {
+		let json = String::from_utf8(read_debug_json(archive)?)
+			.map_err(|err| DebugInfoError::InvalidArchive(err.to_string()))?;
+		Self::from_json(&json)
+	}
+
+	/// The source file with index `document`.
+	pub fn document(&self, document: usize) -> Option<&str> {
+		self.documents.get(document).map(String::as_str)
+	}
+
+	/// The method containing the instruction at `offset`.
+	pub fn method_at(&self, offset: usize) -> Option<&DebugMethod> {
+		self.methods
+			.iter()
+			.find(|method| method.start <= offset && offset <= method.end)
+	}
+
+	/// The sequence point of the statement the instruction at `offset` belongs to.
+	pub fn sequence_point_at(&self, offset: usize) -> Option<&SequencePoint> {
+		self.method_at(offset)?
+			.sequence_points
+			.iter()
+			.rev()
+			.find(|point| point.address <= offset)
+	}
+
+	/// Whether the instruction at `offset` is the first instruction of a statement.
+	pub fn is_sequence_point(&self, offset: usize) -> bool {
+		self.sequence_point_at(offset).is_some_and(|point| point.address == offset)
+	}
+
+	/// The addresses of the statements starting on `line` of `document`.
+	///
+	/// `document` matches a source file by its full path or by its trailing path components,
+	/// so `Token.cs` matches `src/Token.cs`.
+	pub fn offsets_at_line(&self, document: &str, line: u32) -> Vec<usize> {
+		let document = document.replace('\\', "/");
+		let matches = |path: &str| {
+			let path = path.replace('\\', "/");
+			path == document || path.ends_with(&format!("/{}", document))
+		};
+		self.methods
+			.iter()
+			.flat_map(|method| &method.sequence_points)
+			.filter(|point| point.start_line == line)
+			.filter(|point| self.document(point.document).is_some_and(matches))
+			.map(|point| point.address)
+			.collect()
+	}
+}
+
+fn invalid(message: String) -> DebugInfoError {
+	DebugInfoError::InvalidDebugInfo(message)
+}
+
+fn parse_method(raw: RawMethod) -> Result<DebugMethod, DebugInfoError> {
+	let (namespace, name) = raw.name.rsplit_once(',').unwrap_or(("", &raw.name));
+	let (start, end) = raw
+		.range
+		.split_once('-')
+		.and_then(|(start, end)| Some((start.parse().ok()?, end.parse().ok()?)))
+		.ok_or_else(|| invalid(format!("Invalid range {} of {}", raw.range, raw.name)))?;
+	let mut sequence_points = raw
+		.sequence_points
+		.iter()
+		.map(|point| {
+			parse_sequence_point(point)
+				.ok_or_else(|| invalid(format!("Invalid sequence point {}", point)))
+		})
+		.collect::<Result<Vec<_>, _>>()?;
+	sequence_points.sort_by_key(|point| point.address);
+	Ok(DebugMethod {
+		id: raw.id,
+		namespace: namespace.to_string(),
+		name: name.to_string(),
+		start,
+		end,
+		parameters: parse_variables(&raw.params)?,
+		return_type: raw.return_type,
+		variables: parse_variables(&raw.variables)?,
+		sequence_points,
+	})
+}
+
+/// Parses variables in the `name,type[,index]` format, indexed by position if they have no index.
+fn parse_variables(variables: &[String]) -> Result<Vec<DebugVariable>, DebugInfoError> {
+	variables
+		.iter()
+		.enumerate()
+		.map(|(position, variable)| {
+			let mut parts = variable.split(',');
+			let (Some(name), Some(typ)) = (parts.next(), parts.next()) else {
+				return Err(invalid(format!("Invalid variable {}", variable)));
+			};
+			let index = match parts.next() {
+				Some(index) => {
+					index.parse().map_err(|_| invalid(format!("Invalid variable {}", variable)))?
+				},
+				None => position,
+			};
+			Ok(DebugVariable { name: name.to_string(), typ: typ.to_string(), index })
+		})
+		.collect()
+}
+
+/// Parses a sequence point in the `address[document]startLine:startColumn-endLine:endColumn`
+/// format.
+fn parse_sequence_point(point: &str) -> Option<SequencePoint> {
+	let (address, rest) = point.split_once('[')?;
+	let (document, range) = rest.split_once(']')?;
+	let (start, end) = range.split_once('-')?;
+	let (start_line, start_column) = start.split_once(':')?;
+	let (end_line, end_column) = end.split_once(':')?;
+	Some(SequencePoint {
+		address: address.parse().ok()?,
+		document: document.parse().ok()?,
+		start_line: start_line.parse().ok()?,
+		start_column: start_column.parse().ok()?,
+		end_line: end_line.parse().ok()?,
+		end_column: end_column.parse().ok()?,
+	})
+}
+
+/// Extracts the `.debug.json` entry of a zip archive.
+fn read_debug_json(archive: &[u8]) -> Result<Vec<u8>, DebugInfoError> {
+	let error = |message: &str| DebugInfoError::InvalidArchive(message.to_string());
+	let u16_at = |offset: usize| -> Result<usize, DebugInfoError> {
+		let bytes = archive.get(offset..offset + 2).ok_or_else(|| error("Truncated archive"))?;
+		Ok(u16::from_le_bytes([bytes[0], bytes[1]]) as usize)
+	};
+	let u32_at = |offset: usize| -> Result<usize, DebugInfoError> {
+		let bytes = archive.get(offset..offset + 4).ok_or_else(|| error("Truncated archive"))?;
+		Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize)
+	};
+
+	// The end of central directory record is at the end, followed by a comment of up to 64 KiB
+	let end = (0..=archive.len().saturating_sub(22))
+		.rev()
+		.take(u16::MAX as usize + 1)
+		.find(|&offset| archive[offset..].starts_with(&[0x50, 0x4b, 0x05, 0x06]))
+		.ok_or_else(|| error("No end of central directory record"))?;
+	let mut entry = u32_at(end + 16)?;
+	for _ in 0..u16_at(end + 10)? {
+		if u32_at(entry)? != 0x0201_4b50 {
+			return Err(error("Invalid central directory entry"));
+		}
+		let method = u16_at(entry + 10)?;
+		let compressed_size = u32_at(entry + 20)?;
+		let size = u32_at(entry + 24)?;
+		let name_length = u16_at(entry + 28)?;
+		let local_header = u32_at(entry + 42)?;
+		let name = archive
+			.get(entry + 46..entry + 46 + name_length)
+			.ok_or_else(|| error("Truncated archive"))?;
+		entry += 46 + name_length + u16_at(entry + 30)? + u16_at(entry + 32)?;
+		if !name.ends_with(b".debug.json") {
+			continue;
+		}
+
+		if u32_at(local_header)? != 0x0403_4b50 {
+			return Err(error("Invalid local file header"));
+		}
+		let data = local_header + 30 + u16_at(local_header + 26)? + u16_at(local_header + 28)?;
+		let data = archive
+			.get(data..data + compressed_size)
+			.ok_or_else(|| error("Truncated archive"))?;
+		return match method {
+			0 => Ok(data.to_vec()),
+			8 => {
+				let mut json = Vec::with_capacity(size);
+				DeflateDecoder::new(data)
+					.read_to_end(&mut json)
+					.map_err(|err| DebugInfoError::InvalidArchive(err.to_string()))?;
+				Ok(json)
+			},
+			_ => Err(error("Unsupported compression method")),
+		};
+	}
+	Err(error("No .debug.json entry"))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+	use std::io::Write;
+
+	use flate2::{write::DeflateEncoder, Compression};
+
+	use super::*;
+
+	/// The debug info of [`debugged_contract`](crate::neo_vm::debugger::tests::debugged_contract).
+	pub(crate) fn debug_json(hash: &H160) -> String {
+		format!(
+			r#"{{
+				"hash": "0x{}",
+				"documents": ["C:\\src\\Contract.cs"],
+				"static-variables": [],
+				"methods": [
+					{{
+						"id": "main",
+						"name": "Test.Contract,main",
+						"range": "0-9",
+						"params": ["a,Integer,0"],
+						"return": "Integer",
+						"variables": ["result,Integer,0"],
+						"sequence-points": ["0[0]1:1-1:10", "3[0]2:5-2:30", "6[0]3:5-3:20", "8[0]4:5-4:15"]
+					}},
+					{{
+						"id": "double",
+						"name": "Test.Contract,double",
+						"range": "10-16",
+						"params": ["x,Integer"],
+						"return": "Integer",
+						"variables": [],
+						"sequence-points": ["10[0]7:1-7:10", "13[0]8:5-8:20"]
+					}}
+				],
+				"events": []
+			}}"#,
+			hex::encode(hash.0)
+		)
+	}
+
+	/// A zip archive holding `json` as a deflated `contract.debug.json` entry.
+	fn nefdbgnfo(json: &str) -> Vec<u8> {
+		let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+		encoder.write_all(json.as_bytes()).unwrap();
+		let data = encoder.finish().unwrap();
+		let name = b"contract.debug.json";
+		let sizes = |out: &mut Vec<u8>| {
+			out.extend_from_slice(&0u32.to_le_bytes());
+			out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+			out.extend_from_slice(&(json.len() as u32).to_le_bytes());
+			out.extend_from_slice(&(name.len() as u16).to_le_bytes());
+			out.extend_from_slice(&0u16.to_le_bytes());
+		};
+
+		let mut archive = vec![0x50, 0x4b, 0x03, 0x04, 20, 0, 0, 0, 8, 0, 0, 0, 0, 0];
+		sizes(&mut archive);
+		archive.extend_from_slice(name);
+		archive.extend_from_slice(&data);
+		let directory = archive.len();
+		archive.extend_from_slice(&[0x50, 0x4b, 0x01, 0x02, 20, 0, 20, 0, 0, 0, 8, 0, 0, 0, 0, 0]);
+		sizes(&mut archive);
+		// No comment, on disk 0, no attributes, and the local header at offset 0
+		archive.extend_from_slice(&[0; 14]);
+		archive.extend_from_slice(name);
+		let directory_size = archive.len() - directory;
+		archive.extend_from_slice(&[0x50, 0x4b, 0x05, 0x06, 0, 0, 0, 0, 1, 0, 1, 0]);
+		archive.extend_from_slice(&(directory_size as u32).to_le_bytes());
+		archive.extend_from_slice(&(directory as u32).to_le_bytes());
+		archive.extend_from_slice(&0u16.to_le_bytes());
+		archive
+	}
+
+	#[test]
+	fn test_parse_debug_info() {
+		let hash = H160::repeat_byte(0x12);
+		let info = DebugInfo::from_json(&debug_json(&hash)).unwrap();
+
+		assert_eq!(info.hash, hash);
+		assert_eq!(info.methods.len(), 2);
+		let main = &info.methods[0];
+		assert_eq!((main.namespace.as_str(), main.name.as_str()), ("Test.Contract", "main"));
+		assert_eq!((main.start, main.end), (0, 9));
+		assert_eq!(
+			main.variables,
+			vec![DebugVariable {
+				name: "result".to_string(),
+				typ: "Integer".to_string(),
+				index: 0
+			}]
+		);
+		assert_eq!(info.methods[1].parameters[0].index, 0);
+		assert_eq!(
+			main.sequence_points[1],
+			SequencePoint {
+				address: 3,
+				document: 0,
+				start_line: 2,
+				start_column: 5,
+				end_line: 2,
+				end_column: 30
+			}
+		);
+
+		assert_eq!(info.method_at(12).map(|method| method.name.as_str()), Some("double"));
+		assert_eq!(info.sequence_point_at(4).map(|point| point.start_line), Some(2));
+		assert!(info.is_sequence_point(3));
+		assert!(!info.is_sequence_point(4));
+		assert_eq!(info.offsets_at_line("Contract.cs", 3), vec![6]);
+		assert_eq!(info.offsets_at_line("src/Contract.cs", 8), vec![13]);
+		assert!(info.offsets_at_line("Other.cs", 3).is_empty());
+	}
+
+	#[test]
+	fn test_parse_nefdbgnfo() {
+		let json = debug_json(&H160::repeat_byte(0x12));
+		let info = DebugInfo::from_nefdbgnfo(&nefdbgnfo(&json)).unwrap();
+		assert_eq!(info, DebugInfo::from_json(&json).unwrap());
+
+		assert!(matches!(
+			DebugInfo::from_nefdbgnfo(json.as_bytes()),
+			Err(DebugInfoError::InvalidArchive(_))
+		));
+	}
+
+	#[test]
+	fn test_invalid_sequence_point() {
+		let json = debug_json(&H160::zero()).replace("8[0]4:5-4:15", "8[0]4:5");
+		assert!(matches!(DebugInfo::from_json(&json), Err(DebugInfoError::InvalidDebugInfo(_))));
+	}
+}
@@ -0,0 +1,503 @@
+use std::collections::{HashMap, HashSet};
+
+use primitive_types::H160;
+
+use crate::{
+	neo_types::{NefFile, ScriptHashExtension, StackItem, VMState},
+	neo_vm::{
+		ApplicationEngine, ContextState, DebugInfo, DebugInfoError, DebugMethod, DebugVariable,
+		ExecutionContext, SequencePoint, Slot,
+	},
+};
+
+/// A position in a script, where execution stops before executing the instruction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Breakpoint {
+	/// The hash of the script, which is the hash of the NEF script for contracts.
+	pub script_hash: H160,
+	pub offset: usize,
+}
+
+/// The statement a frame is executing, resolved through the debug info of its script.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceLocation {
+	pub document: String,
+	pub sequence_point: SequencePoint,
+}
+
+/// A context on the invocation stack, as seen by the [`Debugger`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StackFrame {
+	pub script_hash: H160,
+	/// The hash of the executing contract, or of the script for dynamic scripts.
+	pub contract_hash: H160,
+	pub instruction_pointer: usize,
+	/// The name of the executing method, if the script has debug info.
+	pub method: Option<String>,
+	pub location: Option<SourceLocation>,
+}
+
+/// A variable of a frame, named after the debug info when it has a name.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Variable {
+	pub name: String,
+	/// The type declared in the source, if the variable is named by the debug info.
+	pub typ: Option<String>,
+	pub value: StackItem,
+}
+
+/// A source-level debugger for the [`ApplicationEngine`].
+///
+/// Scripts are matched with their [`DebugInfo`] by script hash. Stepping moves from statement to
+/// statement in scripts with debug info and from instruction to instruction in scripts without,
+/// such as the entry script of a transaction. Every step also stops at breakpoints.
+///
+/// Breakpoints are checked after each instruction, so a breakpoint on the very first instruction
+/// of the entry script is never hit.
+///
+/// # Examples
+///
+/// ```no_run
+/// use neo3::neo_types::NefFile;
+/// use neo3::neo_vm::{DebugInfo, LocalBlockchain};
+/// # use neo3::neo_types::ContractParameter;
+/// # use primitive_types::H160;
+///
+/// # fn example(nef: NefFile, archive: Vec<u8>, hash: H160) -> Result<(), Box<dyn std::error::Error>> {
+/// let chain = LocalBlockchain::new();
+/// let params = [ContractParameter::integer(21)];
+/// let mut debugger = chain.debug_function(&hash, "main", &params, vec![])?;
+/// debugger.load_contract(&nef, DebugInfo::from_nefdbgnfo(&archive)?)?;
+/// debugger.add_source_breakpoint("Contract.cs", 12);
+///
+/// debugger.run();
+/// println!("{:?}", debugger.call_stack().last().and_then(|frame| frame.location.clone()));
+/// println!("{:?}", debugger.local_variables(debugger.call_stack().len() - 1));
+/// debugger.step_over();
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct Debugger {
+	engine: ApplicationEngine,
+	debug_info: HashMap<H160, DebugInfo>,
+	breakpoints: HashSet<Breakpoint>,
+}
+
+impl Debugger {
+	/// Debugs an engine whose script is loaded but hasn't started executing.
+	pub fn new(engine: ApplicationEngine) -> Self {
+		Self { engine, debug_info: HashMap::new(), breakpoints: HashSet::new() }
+	}
+
+	/// Adds the debug info of a script.
+	pub fn add_debug_info(&mut self, debug_info: DebugInfo) {
+		self.debug_info.insert(debug_info.hash, debug_info);
+	}
+
+	/// Adds the debug info of a contract, checking that it was emitted for its NEF file.
+	pub fn load_contract(
+		&mut self,
+		nef: &NefFile,
+		debug_info: DebugInfo,
+	) -> Result<(), DebugInfoError> {
+		let actual = H160::from_script(nef.script());
+		if debug_info.hash != actual {
+			return Err(DebugInfoError::ScriptMismatch { expected: debug_info.hash, actual });
+		}
+		self.add_debug_info(debug_info);
+		Ok(())
+	}
+
+	pub fn debug_info(&self, script_hash: &H160) -> Option<&DebugInfo> {
+		self.debug_info.get(script_hash)
+	}
+
+	pub fn add_breakpoint(&mut self, script_hash: H160, offset: usize) {
+		self.breakpoints.insert(Breakpoint { script_hash, offset });
+	}
+
+	/// Adds a breakpoint on every statement starting on `line` of `document`, in all the
+	/// scripts with debug info, and returns them.
+	pub fn add_source_breakpoint(&mut self, document: &str, line: u32) -> Vec<Breakpoint> {
+		let breakpoints: Vec<_> = self
+			.debug_info
+			.values()
+			.flat_map(|info| {
+				info.offsets_at_line(document, line)
+					.into_iter()
+					.map(|offset| Breakpoint { script_hash: info.hash, offset })
+			})
+			.collect();
+		self.breakpoints.extend(breakpoints.iter().copied());
+		breakpoints
+	}
+
+	/// Removes a breakpoint, returning whether it was set.
+	pub fn remove_breakpoint(&mut self, script_hash: H160, offset: usize) -> bool {
+		self.breakpoints.remove(&Breakpoint { script_hash, offset })
+	}
+
+	pub fn clear_breakpoints(&mut self) {
+		self.breakpoints.clear();
+	}
+
+	pub fn breakpoints(&self) -> impl Iterator<Item = &Breakpoint> {
+		self.breakpoints.iter()
+	}
+
+	/// The engine being debugged.
+	pub fn engine(&self) -> &ApplicationEngine {
+		&self.engine
+	}
+
+	pub fn into_engine(self) -> ApplicationEngine {
+		self.engine
+	}
+
+	pub fn state(&self) -> VMState {
+		self.engine.engine().state()
+	}
+
+	/// Executes until a breakpoint is hit or the engine halts or faults.
+	pub fn run(&mut self) -> VMState {
+		self.run_until(|_, _| false)
+	}
+
+	/// Executes a single instruction.
+	pub fn step_instruction(&mut self) -> VMState {
+		self.engine.step()
+	}
+
+	/// Executes until the next statement, entering the methods and contracts it calls.
+	pub fn step_in(&mut self) -> VMState {
+		self.run_until(|debugger, _| debugger.at_statement())
+	}
+
+	/// Executes until the next statement of the current method or of its callers.
+	pub fn step_over(&mut self) -> VMState {
+		let depth = self.depth();
+		self.run_until(|debugger, current| current <= depth && debugger.at_statement())
+	}
+
+	/// Executes until the current method returns to its caller.
+	pub fn step_out(&mut self) -> VMState {
+		let depth = self.depth();
+		self.run_until(|_, current| current < depth)
+	}
+
+	/// The invocation stack, from the entry context to the current context.
+	pub fn call_stack(&self) -> Vec<StackFrame> {
+		self.engine
+			.engine()
+			.invocation_stack()
+			.iter()
+			.map(|context| {
+				let script_hash = context.script_hash();
+				let instruction_pointer = context.instruction_pointer();
+				let info = self.debug_info.get(&script_hash);
+				StackFrame {
+					script_hash,
+					contract_hash: context
+						.state::<ContextState>()
+						.map_or(script_hash, |state| state.script_hash),
+					instruction_pointer,
+					method: info
+						.and_then(|info| info.method_at(instruction_pointer))
+						.map(|method| method.name.clone()),
+					location: info.and_then(|info| {
+						let point = info.sequence_point_at(instruction_pointer)?;
+						Some(SourceLocation {
+							document: info.document(point.document)?.to_string(),
+							sequence_point: *point,
+						})
+					}),
+				}
+			})
+			.collect()
+	}
+
+	/// The evaluation stack of frame `frame`, from bottom to top.
+	pub fn evaluation_stack(&self, frame: usize) -> Vec<StackItem> {
+		self.context(frame).map_or_else(Vec::new, |context| {
+			context
+				.evaluation_stack()
+				.items()
+				.iter()
+				.map(|item| item.to_stack_item())
+				.collect()
+		})
+	}
+
+	/// The arguments of frame `frame`, named after the parameters of its method.
+	pub fn arguments(&self, frame: usize) -> Vec<Variable> {
+		let Some(context) = self.context(frame) else { return Vec::new() };
+		let method = self.method(context);
+		variables(context.arguments(), method.map(|method| &method.parameters[..]), "arg")
+	}
+
+	/// The local variables of frame `frame`, named after the variables of its method.
+	pub fn local_variables(&self, frame: usize) -> Vec<Variable> {
+		let Some(context) = self.context(frame) else { return Vec::new() };
+		let method = self.method(context);
+		variables(context.local_variables(), method.map(|method| &method.variables[..]), "local")
+	}
+
+	/// The static fields of the script of frame `frame`.
+	pub fn static_fields(&self, frame: usize) -> Vec<Variable> {
+		let Some(context) = self.context(frame) else { return Vec::new() };
+		let info = self.debug_info.get(&context.script_hash());
+		let slot = context.static_fields();
+		variables(slot.as_ref(), info.map(|info| &info.static_variables[..]), "static")
+	}
+
+	/// The storage of a contract, including the changes made so far, in ascending key order.
+	pub fn storage(&self, contract_hash: &H160) -> Vec<(Vec<u8>, Vec<u8>)> {
+		let state = self.engine.blockchain();
+		state
+			.contract(contract_hash)
+			.map_or_else(Vec::new, |contract| state.find_storage(contract.id, &[]))
+	}
+
+	fn context(&self, frame: usize) -> Option<&ExecutionContext> {
+		self.engine.engine().invocation_stack().get(frame)
+	}
+
+	fn method(&self, context: &ExecutionContext) -> Option<&DebugMethod> {
+		self.debug_info
+			.get(&context.script_hash())?
+			.method_at(context.instruction_pointer())
+	}
+
+	fn depth(&self) -> usize {
+		self.engine.engine().invocation_stack().len()
+	}
+
+	/// Whether the current instruction starts a statement, or is in a script without debug info.
+	fn at_statement(&self) -> bool {
+		let Some(context) = self.engine.engine().current_context() else { return false };
+		self.debug_info
+			.get(&context.script_hash())
+			.is_none_or(|info| info.is_sequence_point(context.instruction_pointer()))
+	}
+
+	fn at_breakpoint(&self) -> bool {
+		self.engine.engine().current_context().is_some_and(|context| {
+			self.breakpoints.contains(&Breakpoint {
+				script_hash: context.script_hash(),
+				offset: context.instruction_pointer(),
+			})
+		})
+	}
+
+	/// Steps at least once, until `stop` returns `true` for the new invocation depth or a
+	/// breakpoint is hit.
+	fn run_until(&mut self, stop: impl Fn(&Self, usize) -> bool) -> VMState {
+		loop {
+			let state = self.engine.step();
+			if state != VMState::Break {
+				return state;
+			}
+			// Once the last context has returned, the next step halts
+			if self.depth() > 0 && (self.at_breakpoint() || stop(self, self.depth())) {
+				return state;
+			}
+		}
+	}
+}
+
+fn variables(slot: Option<&Slot>, names: Option<&[DebugVariable]>, prefix: &str) -> Vec<Variable> {
+	let Some(slot) = slot else { return Vec::new() };
+	slot.items()
+		.iter()
+		.enumerate()
+		.map(|(index, item)| {
+			let variable =
+				names.and_then(|names| names.iter().find(|variable| variable.index == index));
+			Variable {
+				name: variable.map_or_else(
+					|| format!("{}{}", prefix, index),
+					|variable| variable.name.clone(),
+				),
+				typ: variable.map(|variable| variable.typ.clone()),
+				value: item.to_stack_item(),
+			}
+		})
+		.collect()
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+	use super::*;
+	use crate::{
+		neo_types::{ContractManifest, ContractParameter, NeoVMStateType, OpCode},
+		neo_vm::{debug_info::tests::debug_json, LocalBlockchain, NativeContract},
+	};
+
+	/// A contract whose `main(a)` method returns `double(a)`, a private method.
+	pub(crate) fn debugged_contract() -> (NefFile, ContractManifest) {
+		let script = vec![
+			OpCode::InitSlot as u8,
+			1,
+			1,
+			OpCode::LdArg0 as u8,
+			OpCode::Call as u8,
+			6,
+			OpCode::StLoc0 as u8,
+			OpCode::LdLoc0 as u8,
+			OpCode::Ret as u8,
+			OpCode::Nop as u8,
+			OpCode::InitSlot as u8,
+			0,
+			1,
+			OpCode::LdArg0 as u8,
+			OpCode::LdArg0 as u8,
+			OpCode::Add as u8,
+			OpCode::Ret as u8,
+		];
+		let nef = NefFile::new("test", "", vec![], script).unwrap();
+		let manifest = serde_json::from_str(
+			r#"{
+				"name": "Debugged",
+				"groups": [],
+				"features": {},
+				"supportedstandards": [],
+				"abi": {
+					"methods": [
+						{
+							"name": "main",
+							"parameters": [{ "name": "a", "type": "Integer" }],
+							"offset": 0,
+							"returntype": "Integer",
+							"safe": false
+						}
+					],
+					"events": []
+				},
+				"permissions": [{ "contract": "*", "methods": ["*"] }],
+				"trusts": [],
+				"extra": null
+			}"#,
+		)
+		.unwrap();
+		(nef, manifest)
+	}
+
+	/// A debugger stopped before the first instruction of a call to `main(21)`.
+	fn debugger() -> (Debugger, H160) {
+		let mut chain = LocalBlockchain::new();
+		let (nef, manifest) = debugged_contract();
+		let sender = chain.settings().validators_address();
+		let contract = chain.deploy(&nef, &manifest, &sender, None).unwrap();
+		let mut debugger = chain
+			.debug_function(&contract, "main", &[ContractParameter::integer(21)], vec![])
+			.unwrap();
+		let info = DebugInfo::from_json(&debug_json(&H160::from_script(nef.script()))).unwrap();
+		debugger.load_contract(&nef, info).unwrap();
+		(debugger, contract)
+	}
+
+	fn line(debugger: &Debugger) -> Option<u32> {
+		let frame = debugger.call_stack().pop()?;
+		Some(frame.location?.sequence_point.start_line)
+	}
+
+	fn int(value: i64) -> StackItem {
+		StackItem::Integer { value }
+	}
+
+	#[test]
+	fn test_source_breakpoint() {
+		let (mut debugger, contract) = debugger();
+		assert_eq!(debugger.add_source_breakpoint("Contract.cs", 2).len(), 1);
+
+		assert_eq!(debugger.run(), VMState::Break);
+		let stack = debugger.call_stack();
+		assert_eq!(stack.len(), 2);
+		let frame = &stack[1];
+		assert_eq!(frame.contract_hash, contract);
+		assert_eq!(frame.instruction_pointer, 3);
+		assert_eq!(frame.method.as_deref(), Some("main"));
+		assert_eq!(frame.location.as_ref().unwrap().document, "C:\\src\\Contract.cs");
+		assert_eq!(stack[0].method, None);
+		assert_eq!(
+			debugger.arguments(1),
+			vec![Variable {
+				name: "a".to_string(),
+				typ: Some("Integer".to_string()),
+				value: int(21)
+			}]
+		);
+
+		assert_eq!(debugger.run(), VMState::Halt);
+		let result = debugger.into_engine().invocation_result(&[]);
+		assert_eq!(result.state, NeoVMStateType::Halt);
+		assert_eq!(result.stack, vec![int(42)]);
+	}
+
+	#[test]
+	fn test_step_over_in_and_out() {
+		let (mut debugger, _) = debugger();
+		let (nef, _) = debugged_contract();
+		debugger.add_breakpoint(H160::from_script(nef.script()), 3);
+		debugger.run();
+		assert_eq!(line(&debugger), Some(2));
+
+		debugger.step_in();
+		assert_eq!(line(&debugger), Some(7));
+		assert_eq!(debugger.call_stack().len(), 3);
+		assert_eq!(debugger.call_stack()[2].method.as_deref(), Some("double"));
+		debugger.step_in();
+		assert_eq!(line(&debugger), Some(8));
+		assert_eq!(debugger.arguments(2)[0].name, "x");
+		assert_eq!(debugger.arguments(2)[0].value, int(21));
+
+		debugger.step_out();
+		assert_eq!(debugger.call_stack().len(), 2);
+		assert_eq!(line(&debugger), Some(3));
+		assert_eq!(debugger.evaluation_stack(1), vec![int(42)]);
+
+		debugger.step_over();
+		assert_eq!(line(&debugger), Some(4));
+		assert_eq!(
+			debugger.local_variables(1),
+			vec![Variable {
+				name: "result".to_string(),
+				typ: Some("Integer".to_string()),
+				value: int(42)
+			}]
+		);
+	}
+
+	#[test]
+	fn test_step_over_skips_calls() {
+		let (mut debugger, _) = debugger();
+		debugger.add_source_breakpoint("Contract.cs", 2);
+		debugger.run();
+		debugger.step_over();
+		assert_eq!(debugger.call_stack().len(), 2);
+		assert_eq!(line(&debugger), Some(3));
+	}
+
+	#[test]
+	fn test_load_contract_checks_hash() {
+		let (mut debugger, _) = debugger();
+		let (nef, _) = debugged_contract();
+		let info = DebugInfo::from_json(&debug_json(&H160::zero())).unwrap();
+		assert!(matches!(
+			debugger.load_contract(&nef, info),
+			Err(DebugInfoError::ScriptMismatch { .. })
+		));
+	}
+
+	#[test]
+	fn test_storage_inspection() {
+		let (debugger, _) = debugger();
+		let validators = debugger.engine().blockchain().settings().validators_address();
+		let mut key = vec![20];
+		key.extend(validators.0.iter().rev());
+		let storage = debugger.storage(&NativeContract::GasToken.hash());
+		assert!(storage.iter().any(|(stored, _)| *stored == key));
+		assert!(debugger.storage(&H160::zero()).is_empty());
+	}
+}
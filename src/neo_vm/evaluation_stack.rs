@@ -0,0 +1,89 @@
+use crate::neo_vm::{VMError, VMStackItem};
+
+/// The evaluation stack of an execution context.
+///
+/// Indices passed to [`peek`](EvaluationStack::peek), [`remove`](EvaluationStack::remove) and
+/// [`insert`](EvaluationStack::insert) are counted from the top of the stack, as in the NeoVM.
+#[derive(Debug, Default, Clone)]
+pub struct EvaluationStack {
+	items: Vec<VMStackItem>,
+}
+
+impl EvaluationStack {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	pub fn len(&self) -> usize {
+		self.items.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.items.is_empty()
+	}
+
+	pub fn push(&mut self, item: VMStackItem) {
+		self.items.push(item);
+	}
+
+	pub fn pop(&mut self) -> Result<VMStackItem, VMError> {
+		self.items.pop().ok_or(VMError::StackUnderflow)
+	}
+
+	/// Returns the item `index` positions below the top of the stack.
+	pub fn peek(&self, index: usize) -> Result<&VMStackItem, VMError> {
+		let position = self.position(index)?;
+		Ok(&self.items[position])
+	}
+
+	/// Removes and returns the item `index` positions below the top of the stack.
+	pub fn remove(&mut self, index: usize) -> Result<VMStackItem, VMError> {
+		let position = self.position(index)?;
+		Ok(self.items.remove(position))
+	}
+
+	/// Inserts `item` so that it ends up `index` positions below the top of the stack.
+	pub fn insert(&mut self, index: usize, item: VMStackItem) -> Result<(), VMError> {
+		if index > self.items.len() {
+			return Err(VMError::InvalidOperation(format!(
+				"Insert index {} is out of range for a stack of size {}",
+				index,
+				self.items.len()
+			)));
+		}
+		let position = self.items.len() - index;
+		self.items.insert(position, item);
+		Ok(())
+	}
+
+	/// Reverses the order of the top `count` items.
+	pub fn reverse(&mut self, count: usize) -> Result<(), VMError> {
+		if count > self.items.len() {
+			return Err(VMError::StackUnderflow);
+		}
+		let start = self.items.len() - count;
+		self.items[start..].reverse();
+		Ok(())
+	}
+
+	pub fn clear(&mut self) {
+		self.items.clear();
+	}
+
+	/// Returns the items from bottom to top.
+	pub fn items(&self) -> &[VMStackItem] {
+		&self.items
+	}
+
+	/// Moves every item of this stack on top of `other`, preserving their order.
+	pub fn move_to(&mut self, other: &mut EvaluationStack) {
+		other.items.append(&mut self.items);
+	}
+
+	fn position(&self, index: usize) -> Result<usize, VMError> {
+		if index >= self.items.len() {
+			return Err(VMError::StackUnderflow);
+		}
+		Ok(self.items.len() - 1 - index)
+	}
+}
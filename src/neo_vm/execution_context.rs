@@ -0,0 +1,180 @@
+use std::{
+	any::Any,
+	cell::{Ref, RefCell, RefMut},
+	rc::Rc,
+};
+
+use primitive_types::H160;
+
+use crate::{
+	neo_types::ScriptHashExtension,
+	neo_vm::{EvaluationStack, Instruction, Slot, VMError},
+};
+
+/// The state of a `TRY` block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExceptionHandlingState {
+	Try,
+	Catch,
+	Finally,
+}
+
+/// Bookkeeping for a `TRY`/`CATCH`/`FINALLY` block in an execution context.
+#[derive(Debug, Clone)]
+pub struct ExceptionHandlingContext {
+	pub catch_pointer: Option<usize>,
+	pub finally_pointer: Option<usize>,
+	pub end_pointer: Option<usize>,
+	pub state: ExceptionHandlingState,
+}
+
+impl ExceptionHandlingContext {
+	pub fn new(catch_pointer: Option<usize>, finally_pointer: Option<usize>) -> Self {
+		Self {
+			catch_pointer,
+			finally_pointer,
+			end_pointer: None,
+			state: ExceptionHandlingState::Try,
+		}
+	}
+
+	pub fn has_catch(&self) -> bool {
+		self.catch_pointer.is_some()
+	}
+
+	pub fn has_finally(&self) -> bool {
+		self.finally_pointer.is_some()
+	}
+}
+
+/// State shared between a context and the contexts created from it by `CALL`.
+#[derive(Debug)]
+struct SharedStates {
+	script: Rc<[u8]>,
+	script_hash: H160,
+	evaluation_stack: RefCell<EvaluationStack>,
+	static_fields: RefCell<Option<Slot>>,
+	state: RefCell<Option<Rc<dyn Any>>>,
+}
+
+/// A frame on the invocation stack of the NeoVM.
+#[derive(Debug)]
+pub struct ExecutionContext {
+	shared: Rc<SharedStates>,
+	instruction_pointer: usize,
+	rvcount: i32,
+	pub(crate) local_variables: Option<Slot>,
+	pub(crate) arguments: Option<Slot>,
+	pub(crate) try_stack: Vec<ExceptionHandlingContext>,
+}
+
+impl ExecutionContext {
+	/// Creates a context for `script`.
+	///
+	/// `rvcount` is the number of items the context must leave on its evaluation stack when it
+	/// returns, or `-1` to accept any number of items.
+	pub fn new(script: Rc<[u8]>, rvcount: i32) -> Self {
+		let script_hash = H160::from_script(&script);
+		Self {
+			shared: Rc::new(SharedStates {
+				script,
+				script_hash,
+				evaluation_stack: RefCell::new(EvaluationStack::new()),
+				static_fields: RefCell::new(None),
+				state: RefCell::new(None),
+			}),
+			instruction_pointer: 0,
+			rvcount,
+			local_variables: None,
+			arguments: None,
+			try_stack: Vec::new(),
+		}
+	}
+
+	/// Creates a context for a `CALL` into the same script, sharing the evaluation stack and
+	/// static fields with this context.
+	pub fn clone_at(&self, position: usize) -> Self {
+		Self {
+			shared: self.shared.clone(),
+			instruction_pointer: position,
+			rvcount: 0,
+			local_variables: None,
+			arguments: None,
+			try_stack: Vec::new(),
+		}
+	}
+
+	pub fn script(&self) -> &Rc<[u8]> {
+		&self.shared.script
+	}
+
+	/// The hash of the script executed by this context.
+	pub fn script_hash(&self) -> H160 {
+		self.shared.script_hash
+	}
+
+	pub fn instruction_pointer(&self) -> usize {
+		self.instruction_pointer
+	}
+
+	pub fn set_instruction_pointer(&mut self, position: usize) {
+		self.instruction_pointer = position;
+	}
+
+	pub fn rvcount(&self) -> i32 {
+		self.rvcount
+	}
+
+	/// Decodes the instruction at the instruction pointer.
+	///
+	/// Returns an implicit `RET` once the end of the script has been reached.
+	pub fn current_instruction(&self) -> Result<Instruction, VMError> {
+		if self.instruction_pointer >= self.shared.script.len() {
+			return Ok(Instruction::ret());
+		}
+		Instruction::decode(&self.shared.script, self.instruction_pointer)
+	}
+
+	pub fn evaluation_stack(&self) -> Ref<'_, EvaluationStack> {
+		self.shared.evaluation_stack.borrow()
+	}
+
+	pub fn evaluation_stack_mut(&self) -> RefMut<'_, EvaluationStack> {
+		self.shared.evaluation_stack.borrow_mut()
+	}
+
+	/// Returns `true` if both contexts operate on the same evaluation stack.
+	pub fn shares_stack_with(&self, other: &ExecutionContext) -> bool {
+		Rc::ptr_eq(&self.shared, &other.shared)
+	}
+
+	pub fn static_fields(&self) -> Ref<'_, Option<Slot>> {
+		self.shared.static_fields.borrow()
+	}
+
+	pub(crate) fn static_fields_mut(&self) -> RefMut<'_, Option<Slot>> {
+		self.shared.static_fields.borrow_mut()
+	}
+
+	/// Attaches host-defined state to this context and the contexts created from it by `CALL`.
+	pub fn set_state<T: Any>(&self, state: T) {
+		*self.shared.state.borrow_mut() = Some(Rc::new(state));
+	}
+
+	/// The state attached with [`set_state`](Self::set_state), if it is of type `T`.
+	pub fn state<T: Any>(&self) -> Option<Rc<T>> {
+		self.shared.state.borrow().clone()?.downcast::<T>().ok()
+	}
+
+	pub fn local_variables(&self) -> Option<&Slot> {
+		self.local_variables.as_ref()
+	}
+
+	pub fn arguments(&self) -> Option<&Slot> {
+		self.arguments.as_ref()
+	}
+
+	pub fn try_stack(&self) -> &[ExceptionHandlingContext] {
+		&self.try_stack
+	}
+}
@@ -0,0 +1,1964 @@
+use std::{cell::RefCell, rc::Rc};
+
+use num_bigint::{BigInt, Sign};
+use num_traits::{One, Signed, ToPrimitive, Zero};
+
+use crate::{
+	builder::InteropService,
+	neo_types::{Bytes, OpCode, StackItem, VMState},
+	neo_vm::{
+		integer_to_bytes, EvaluationStack, ExceptionHandlingContext, ExceptionHandlingState,
+		ExecutionContext, Instruction, ReferenceCounter, Slot, StackItemType, VMError, VMStackItem,
+		MAX_INTEGER_SIZE,
+	},
+};
+
+/// The default fee multiplier applied to opcode and syscall prices, as set by the Policy contract.
+pub const DEFAULT_EXEC_FEE_FACTOR: u32 = 30;
+
+/// The default GAS limit of a local execution (20 GAS, the default `MaxGasInvoke` of RPC nodes).
+pub const DEFAULT_GAS_LIMIT: i64 = 20_0000_0000;
+
+/// Restrictions enforced by the [`ExecutionEngine`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExecutionEngineLimits {
+	/// The maximum number of bits that `SHL`, `SHR` and `POW` may shift.
+	pub max_shift: u32,
+	/// The maximum number of items on all stacks and slots, including nested items.
+	pub max_stack_size: usize,
+	/// The maximum size in bytes of a single item.
+	pub max_item_size: usize,
+	/// The maximum size in bytes of byte strings compared by `EQUAL`.
+	pub max_comparable_size: usize,
+	/// The maximum depth of the invocation stack.
+	pub max_invocation_stack_size: usize,
+	/// The maximum number of nested `TRY` blocks in a single context.
+	pub max_try_nesting_depth: usize,
+	/// Whether runtime errors flagged as catchable may be handled by `TRY` blocks.
+	pub catch_engine_exceptions: bool,
+}
+
+impl Default for ExecutionEngineLimits {
+	fn default() -> Self {
+		Self {
+			max_shift: 256,
+			max_stack_size: 2 * 1024,
+			max_item_size: u16::MAX as usize * 2,
+			max_comparable_size: 65536,
+			max_invocation_stack_size: 1024,
+			max_try_nesting_depth: 16,
+			catch_engine_exceptions: true,
+		}
+	}
+}
+
+/// Provides the environment-specific parts of execution to the [`ExecutionEngine`].
+///
+/// The engine itself only implements the pure NeoVM instruction set. Everything that needs a
+/// blockchain (`SYSCALL`, `CALLT`) is delegated to the host.
+pub trait InteropHost {
+	/// Handles a `SYSCALL`. `method` is the little-endian interop method hash.
+	///
+	/// The fixed price of known [`InteropService`]s has already been charged when this is called.
+	fn on_syscall(&mut self, engine: &mut ExecutionEngine, method: u32) -> Result<(), VMError>;
+
+	/// Handles a `CALLT` to the method token at index `token`.
+	fn on_call_token(&mut self, _engine: &mut ExecutionEngine, token: u16) -> Result<(), VMError> {
+		Err(VMError::InvalidOperation(format!("Method token {} can't be resolved", token)))
+	}
+
+	/// Called after `context` was removed from the invocation stack, either by `RET` or
+	/// because an exception unwound it (`unwound` is `true`).
+	fn on_context_unloaded(
+		&mut self,
+		_engine: &mut ExecutionEngine,
+		_context: &ExecutionContext,
+		_unwound: bool,
+	) -> Result<(), VMError> {
+		Ok(())
+	}
+}
+
+/// A host without any interop services. Every `SYSCALL` faults.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NoInterop;
+
+impl InteropHost for NoInterop {
+	fn on_syscall(&mut self, _engine: &mut ExecutionEngine, method: u32) -> Result<(), VMError> {
+		Err(VMError::UnsupportedSyscall(syscall_name(method)))
+	}
+}
+
+/// Returns the name of the interop service with the given method hash, or its hex form.
+pub fn syscall_name(method: u32) -> String {
+	let hash = hex::encode(method.to_le_bytes());
+	InteropService::from_hash(hash.clone())
+		.map(|service| service.to_string())
+		.unwrap_or_else(|| format!("0x{}", hash))
+}
+
+/// The outcome of a local script execution.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExecutionResult {
+	/// The final state of the VM, either `HALT` or `FAULT`.
+	pub state: VMState,
+	/// The GAS consumed by the execution, in datoshi (10^-8 GAS).
+	pub gas_consumed: i64,
+	/// The result stack, from bottom to top.
+	pub stack: Vec<StackItem>,
+	/// The fault reason if the execution ended in `FAULT`.
+	pub exception: Option<String>,
+}
+
+/// A pure-Rust NeoVM execution engine.
+///
+/// The engine executes scripts built with [`ScriptBuilder`](crate::neo_builder::ScriptBuilder)
+/// locally, without a node, and charges opcode prices the same way Neo N3 nodes do.
+///
+/// # Examples
+///
+/// ```rust
+/// use neo3::neo_builder::ScriptBuilder;
+/// use neo3::neo_types::{OpCode, StackItem, VMState};
+/// use neo3::neo_vm::ExecutionEngine;
+/// use num_bigint::BigInt;
+///
+/// let script = ScriptBuilder::new()
+///     .push_integer(BigInt::from(2))
+///     .push_integer(BigInt::from(3))
+///     .op_code(&[OpCode::Mul])
+///     .to_bytes();
+///
+/// let result = ExecutionEngine::run(script);
+/// assert_eq!(result.state, VMState::Halt);
+/// assert_eq!(result.stack, vec![StackItem::Integer { value: 6 }]);
+/// ```
+#[derive(Debug)]
+pub struct ExecutionEngine {
+	limits: ExecutionEngineLimits,
+	state: VMState,
+	invocation_stack: Vec<ExecutionContext>,
+	result_stack: EvaluationStack,
+	reference_counter: ReferenceCounter,
+	uncaught_exception: Option<VMStackItem>,
+	fault_exception: Option<VMError>,
+	gas_limit: i64,
+	gas_consumed: i64,
+	exec_fee_factor: u32,
+	is_jumping: bool,
+	unloaded_contexts: Vec<(ExecutionContext, bool)>,
+}
+
+impl Default for ExecutionEngine {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl ExecutionEngine {
+	/// Creates an engine with the default limits, GAS limit and fee factor.
+	pub fn new() -> Self {
+		Self::with_limits(ExecutionEngineLimits::default())
+	}
+
+	/// Creates an engine with custom limits.
+	pub fn with_limits(limits: ExecutionEngineLimits) -> Self {
+		Self {
+			limits,
+			state: VMState::None,
+			invocation_stack: Vec::new(),
+			result_stack: EvaluationStack::new(),
+			reference_counter: ReferenceCounter::new(),
+			uncaught_exception: None,
+			fault_exception: None,
+			gas_limit: DEFAULT_GAS_LIMIT,
+			gas_consumed: 0,
+			exec_fee_factor: DEFAULT_EXEC_FEE_FACTOR,
+			is_jumping: false,
+			unloaded_contexts: Vec::new(),
+		}
+	}
+
+	/// Loads `script` into a fresh engine, executes it without interop services and returns the result.
+	pub fn run(script: Bytes) -> ExecutionResult {
+		let mut engine = Self::new();
+		if let Err(err) = engine.load_script(script, -1) {
+			engine.on_fault(err);
+		}
+		engine.execute();
+		engine.result()
+	}
+
+	/// Sets the maximum GAS, in datoshi, the execution may consume.
+	pub fn set_gas_limit(&mut self, gas_limit: i64) -> &mut Self {
+		self.gas_limit = gas_limit;
+		self
+	}
+
+	/// Sets the multiplier applied to opcode and syscall prices.
+	pub fn set_exec_fee_factor(&mut self, exec_fee_factor: u32) -> &mut Self {
+		self.exec_fee_factor = exec_fee_factor;
+		self
+	}
+
+	pub fn limits(&self) -> &ExecutionEngineLimits {
+		&self.limits
+	}
+
+	pub fn state(&self) -> VMState {
+		self.state
+	}
+
+	pub fn gas_limit(&self) -> i64 {
+		self.gas_limit
+	}
+
+	pub fn gas_consumed(&self) -> i64 {
+		self.gas_consumed
+	}
+
+	pub fn exec_fee_factor(&self) -> u32 {
+		self.exec_fee_factor
+	}
+
+	/// The error that put the engine into the `FAULT` state, if any.
+	pub fn fault_exception(&self) -> Option<&VMError> {
+		self.fault_exception.as_ref()
+	}
+
+	/// The exception currently being propagated through `TRY` blocks, if any.
+	pub fn uncaught_exception(&self) -> Option<&VMStackItem> {
+		self.uncaught_exception.as_ref()
+	}
+
+	/// The invocation stack, from the entry context to the current context.
+	pub fn invocation_stack(&self) -> &[ExecutionContext] {
+		&self.invocation_stack
+	}
+
+	pub fn result_stack(&self) -> &EvaluationStack {
+		&self.result_stack
+	}
+
+	/// Counts the items on the stacks and slots, checked against `max_stack_size`.
+	pub fn reference_counter(&self) -> &ReferenceCounter {
+		&self.reference_counter
+	}
+
+	/// The context currently being executed.
+	pub fn current_context(&self) -> Option<&ExecutionContext> {
+		self.invocation_stack.last()
+	}
+
+	/// The context that loaded the current context, if any.
+	pub fn calling_context(&self) -> Option<&ExecutionContext> {
+		let len = self.invocation_stack.len();
+		if len < 2 {
+			return None;
+		}
+		self.invocation_stack.get(len - 2)
+	}
+
+	/// The first context loaded into the engine.
+	pub fn entry_context(&self) -> Option<&ExecutionContext> {
+		self.invocation_stack.first()
+	}
+
+	/// Loads `script` as a new context on top of the invocation stack.
+	///
+	/// `rvcount` is the number of items the script must return, or `-1` for any number.
+	pub fn load_script(&mut self, script: Bytes, rvcount: i32) -> Result<(), VMError> {
+		self.load_context(ExecutionContext::new(Rc::from(script), rvcount))
+	}
+
+	/// Pushes `context` on top of the invocation stack.
+	pub fn load_context(&mut self, context: ExecutionContext) -> Result<(), VMError> {
+		if self.invocation_stack.len() >= self.limits.max_invocation_stack_size {
+			return Err(VMError::StackOverflow(format!(
+				"MaxInvocationStackSize exceed: {}",
+				self.invocation_stack.len()
+			)));
+		}
+		self.invocation_stack.push(context);
+		Ok(())
+	}
+
+	/// Charges `datoshi` against the GAS limit.
+	pub fn add_gas(&mut self, datoshi: i64) -> Result<(), VMError> {
+		self.gas_consumed = self.gas_consumed.saturating_add(datoshi);
+		if self.gas_consumed > self.gas_limit {
+			return Err(VMError::InsufficientGas {
+				consumed: self.gas_consumed,
+				limit: self.gas_limit,
+			});
+		}
+		Ok(())
+	}
+
+	/// Pushes an item onto the evaluation stack of the current context.
+	pub fn push(&mut self, item: VMStackItem) -> Result<(), VMError> {
+		self.reference_counter.add_reference(&item);
+		self.context()?.evaluation_stack_mut().push(item);
+		Ok(())
+	}
+
+	/// Pops an item from the evaluation stack of the current context.
+	pub fn pop(&mut self) -> Result<VMStackItem, VMError> {
+		let item = self.context()?.evaluation_stack_mut().pop()?;
+		self.reference_counter.remove_references(1);
+		Ok(item)
+	}
+
+	/// Returns a copy of the item `index` positions below the top of the current evaluation stack.
+	pub fn peek(&self, index: usize) -> Result<VMStackItem, VMError> {
+		self.context()?.evaluation_stack().peek(index).cloned()
+	}
+
+	pub fn pop_integer(&mut self) -> Result<BigInt, VMError> {
+		self.pop()?.get_integer()
+	}
+
+	pub fn pop_bool(&mut self) -> Result<bool, VMError> {
+		self.pop()?.get_boolean()
+	}
+
+	pub fn pop_bytes(&mut self) -> Result<Vec<u8>, VMError> {
+		self.pop()?.get_span()
+	}
+
+	/// Executes until the engine halts or faults, faulting on every `SYSCALL`.
+	pub fn execute(&mut self) -> VMState {
+		self.execute_with(&mut NoInterop)
+	}
+
+	/// Executes until the engine halts or faults, delegating interop calls to `host`.
+	pub fn execute_with<H: InteropHost + ?Sized>(&mut self, host: &mut H) -> VMState {
+		if self.state == VMState::Break {
+			self.state = VMState::None;
+		}
+		while self.state != VMState::Halt && self.state != VMState::Fault {
+			self.execute_next(host);
+		}
+		self.state
+	}
+
+	/// Executes a single instruction. The engine is left in the `BREAK` state unless it halted or faulted.
+	pub fn step_with<H: InteropHost + ?Sized>(&mut self, host: &mut H) -> VMState {
+		if self.state == VMState::Halt || self.state == VMState::Fault {
+			return self.state;
+		}
+		self.execute_next(host);
+		if self.state == VMState::None {
+			self.state = VMState::Break;
+		}
+		self.state
+	}
+
+	/// Builds an [`ExecutionResult`] from the current state of the engine.
+	pub fn result(&self) -> ExecutionResult {
+		ExecutionResult {
+			state: self.state,
+			gas_consumed: self.gas_consumed,
+			stack: self.result_stack.items().iter().map(VMStackItem::to_stack_item).collect(),
+			exception: self.fault_exception.as_ref().map(ToString::to_string),
+		}
+	}
+
+	fn execute_next<H: InteropHost + ?Sized>(&mut self, host: &mut H) {
+		if self.invocation_stack.is_empty() {
+			self.state = VMState::Halt;
+			return;
+		}
+		if let Err(err) = self.try_execute_next(host) {
+			self.on_fault(err);
+		}
+	}
+
+	fn try_execute_next<H: InteropHost + ?Sized>(&mut self, host: &mut H) -> Result<(), VMError> {
+		let depth = self.invocation_stack.len() - 1;
+		let instruction = self.context()?.current_instruction()?;
+		self.add_gas(instruction.opcode.price() as i64 * self.exec_fee_factor as i64)?;
+
+		match self.execute_instruction(&instruction, host) {
+			Ok(()) => {},
+			Err(VMError::Catchable(message)) if self.limits.catch_engine_exceptions =>
+				self.execute_throw(VMStackItem::from(message.as_str()))?,
+			Err(err) => return Err(err),
+		}
+		for (context, unwound) in std::mem::take(&mut self.unloaded_contexts) {
+			host.on_context_unloaded(self, &context, unwound)?;
+		}
+
+		self.check_stack_size()?;
+		if !self.is_jumping {
+			if let Some(context) = self.invocation_stack.get_mut(depth) {
+				let next = context.instruction_pointer() + instruction.size;
+				context.set_instruction_pointer(next);
+			}
+		}
+		self.is_jumping = false;
+		Ok(())
+	}
+
+	fn on_fault(&mut self, err: VMError) {
+		self.state = VMState::Fault;
+		self.fault_exception = Some(err);
+	}
+
+	fn context(&self) -> Result<&ExecutionContext, VMError> {
+		self.invocation_stack
+			.last()
+			.ok_or_else(|| VMError::InvalidOperation("No context is loaded".to_string()))
+	}
+
+	fn context_mut(&mut self) -> Result<&mut ExecutionContext, VMError> {
+		self.invocation_stack
+			.last_mut()
+			.ok_or_else(|| VMError::InvalidOperation("No context is loaded".to_string()))
+	}
+
+	/// Checks the reference count against `max_stack_size`. The count includes the items of
+	/// unreachable compounds, so the references are counted again from the stacks and slots
+	/// before failing. They are also counted again once too many compounds are tracked.
+	fn check_stack_size(&mut self) -> Result<(), VMError> {
+		let max_stack_size = self.limits.max_stack_size;
+		if self.reference_counter.count() <= max_stack_size
+			&& self.reference_counter.tracked_count() <= max_stack_size
+		{
+			return Ok(());
+		}
+		let roots = self.roots();
+		let count = self.reference_counter.recount(&roots);
+		if count > max_stack_size {
+			return Err(VMError::StackOverflow(format!("MaxStackSize exceed: {}", count)));
+		}
+		Ok(())
+	}
+
+	/// The items on the stacks and slots.
+	fn roots(&self) -> Vec<VMStackItem> {
+		let mut roots = Vec::new();
+		for (index, context) in self.invocation_stack.iter().enumerate() {
+			let shared_seen = self.invocation_stack[..index]
+				.iter()
+				.any(|other| other.shares_stack_with(context));
+			if !shared_seen {
+				roots.extend(context.evaluation_stack().items().iter().cloned());
+				if let Some(slot) = context.static_fields().as_ref() {
+					roots.extend(slot.items().iter().cloned());
+				}
+			}
+			for slot in [context.local_variables(), context.arguments()].into_iter().flatten() {
+				roots.extend(slot.items().iter().cloned());
+			}
+		}
+		roots.extend(self.result_stack.items().iter().cloned());
+		roots
+	}
+
+	/// Removes the references of the slots of `context`, which was removed from the invocation
+	/// stack. Its evaluation stack and static fields are shared with the contexts created by
+	/// `CALL`, and are only released with the last of them.
+	fn release_context(&mut self, context: &ExecutionContext) {
+		let slots = [context.local_variables(), context.arguments()];
+		let mut released: usize = slots.into_iter().flatten().map(Slot::len).sum();
+		if !self.invocation_stack.iter().any(|other| other.shares_stack_with(context)) {
+			released += context.evaluation_stack().len();
+			released += context.static_fields().as_ref().map_or(0, Slot::len);
+		}
+		self.reference_counter.remove_references(released);
+	}
+
+	fn execute_instruction<H: InteropHost + ?Sized>(
+		&mut self,
+		instruction: &Instruction,
+		host: &mut H,
+	) -> Result<(), VMError> {
+		match instruction.opcode {
+			// Constants
+			OpCode::PushInt8
+			| OpCode::PushInt16
+			| OpCode::PushInt32
+			| OpCode::PushInt64
+			| OpCode::PushInt128
+			| OpCode::PushInt256 =>
+				self.push(VMStackItem::Integer(BigInt::from_signed_bytes_le(&instruction.operand)))?,
+			OpCode::PushTrue => self.push(VMStackItem::Boolean(true))?,
+			OpCode::PushFalse => self.push(VMStackItem::Boolean(false))?,
+			OpCode::PushA => {
+				let context = self.context()?;
+				let position = self.offset_position(instruction.token_i32())?;
+				if position > context.script().len() {
+					return Err(VMError::InvalidOperation(format!(
+						"Bad pointer address: {}",
+						position
+					)));
+				}
+				let script = context.script().clone();
+				self.push(VMStackItem::Pointer { script, position })?
+			},
+			OpCode::PushNull => self.push(VMStackItem::Null)?,
+			OpCode::PushData1 | OpCode::PushData2 | OpCode::PushData4 => {
+				self.check_item_size(instruction.operand.len())?;
+				self.push(VMStackItem::ByteString(instruction.operand.clone()))?
+			},
+			OpCode::PushM1
+			| OpCode::Push0
+			| OpCode::Push1
+			| OpCode::Push2
+			| OpCode::Push3
+			| OpCode::Push4
+			| OpCode::Push5
+			| OpCode::Push6
+			| OpCode::Push7
+			| OpCode::Push8
+			| OpCode::Push9
+			| OpCode::Push10
+			| OpCode::Push11
+			| OpCode::Push12
+			| OpCode::Push13
+			| OpCode::Push14
+			| OpCode::Push15
+			| OpCode::Push16 => {
+				let value = instruction.opcode as i64 - OpCode::Push0 as i64;
+				self.push(VMStackItem::from(value))?
+			},
+
+			// Flow control
+			OpCode::Nop => {},
+			OpCode::Jmp => self.execute_jump_offset(instruction.token_i8() as i32)?,
+			OpCode::JmpL => self.execute_jump_offset(instruction.token_i32())?,
+			OpCode::JmpIf | OpCode::JmpIfL | OpCode::JmpIfNot | OpCode::JmpIfNotL => {
+				let expected = matches!(instruction.opcode, OpCode::JmpIf | OpCode::JmpIfL);
+				if self.pop_bool()? == expected {
+					self.execute_jump_offset(Self::jump_offset(instruction))?;
+				}
+			},
+			OpCode::JmpEq
+			| OpCode::JmpEqL
+			| OpCode::JmpNe
+			| OpCode::JmpNeL
+			| OpCode::JmpGt
+			| OpCode::JmpGtL
+			| OpCode::JmpGe
+			| OpCode::JmpGeL
+			| OpCode::JmpLt
+			| OpCode::JmpLtL
+			| OpCode::JmpLe
+			| OpCode::JmpLeL => {
+				let x2 = self.pop_integer()?;
+				let x1 = self.pop_integer()?;
+				let jump = match instruction.opcode {
+					OpCode::JmpEq | OpCode::JmpEqL => x1 == x2,
+					OpCode::JmpNe | OpCode::JmpNeL => x1 != x2,
+					OpCode::JmpGt | OpCode::JmpGtL => x1 > x2,
+					OpCode::JmpGe | OpCode::JmpGeL => x1 >= x2,
+					OpCode::JmpLt | OpCode::JmpLtL => x1 < x2,
+					_ => x1 <= x2,
+				};
+				if jump {
+					self.execute_jump_offset(Self::jump_offset(instruction))?;
+				}
+			},
+			OpCode::Call => {
+				let position = self.offset_position(instruction.token_i8() as i32)?;
+				self.execute_call(position)?
+			},
+			OpCode::CallL => {
+				let position = self.offset_position(instruction.token_i32())?;
+				self.execute_call(position)?
+			},
+			OpCode::CallA => match self.pop()? {
+				VMStackItem::Pointer { script, position } => {
+					if !Rc::ptr_eq(&script, self.context()?.script()) {
+						return Err(VMError::InvalidOperation(
+							"Pointers can't be shared between scripts".to_string(),
+						));
+					}
+					self.execute_call(position)?
+				},
+				other =>
+					return Err(VMError::InvalidCast(format!(
+						"CALLA expects a Pointer, found {}",
+						other.item_type()
+					))),
+			},
+			OpCode::CallT => host.on_call_token(self, instruction.token_u16())?,
+			OpCode::Abort => return Err(VMError::Abort(String::new())),
+			OpCode::AbortMsg => {
+				let message = self.pop()?.get_string()?;
+				return Err(VMError::Abort(format!(". Reason: {}", message)));
+			},
+			OpCode::Assert =>
+				if !self.pop_bool()? {
+					return Err(VMError::AssertFailed(String::new()));
+				},
+			OpCode::AssertMsg => {
+				let message = self.pop()?.get_string()?;
+				if !self.pop_bool()? {
+					return Err(VMError::AssertFailed(format!(". Reason: {}", message)));
+				}
+			},
+			OpCode::Throw => {
+				let exception = self.pop()?;
+				self.execute_throw(exception)?
+			},
+			OpCode::Try =>
+				self.execute_try(instruction.token_i8() as i32, instruction.token_i8_1() as i32)?,
+			OpCode::TryL => self.execute_try(instruction.token_i32(), instruction.token_i32_1())?,
+			OpCode::EndTry => self.execute_end_try(instruction.token_i8() as i32)?,
+			OpCode::EndTryL => self.execute_end_try(instruction.token_i32())?,
+			OpCode::EndFinally => {
+				let context = self.context_mut()?;
+				let current_try = context.try_stack.pop().ok_or_else(|| {
+					VMError::InvalidOperation(
+						"The corresponding TRY block cannot be found".to_string(),
+					)
+				})?;
+				if self.uncaught_exception.is_none() {
+					let end_pointer = current_try.end_pointer.ok_or_else(|| {
+						VMError::InvalidOperation("The TRY block has no end pointer".to_string())
+					})?;
+					self.context_mut()?.set_instruction_pointer(end_pointer);
+				} else {
+					self.handle_exception()?;
+				}
+				self.is_jumping = true;
+			},
+			OpCode::Ret => self.execute_ret()?,
+			OpCode::Syscall => {
+				let method = instruction.token_u32();
+				if let Some(service) = InteropService::from_hash(hex::encode(&instruction.operand))
+				{
+					self.add_gas(service.price() as i64 * self.exec_fee_factor as i64)?;
+				}
+				host.on_syscall(self, method)?
+			},
+
+			// Stack
+			OpCode::Depth => {
+				let depth = self.context()?.evaluation_stack().len() as i64;
+				self.push(VMStackItem::from(depth))?
+			},
+			OpCode::Drop => {
+				self.pop()?;
+			},
+			OpCode::Nip => {
+				self.context()?.evaluation_stack_mut().remove(1)?;
+				self.reference_counter.remove_references(1);
+			},
+			OpCode::Xdrop => {
+				let n = self.pop_index()?;
+				self.context()?.evaluation_stack_mut().remove(n)?;
+				self.reference_counter.remove_references(1);
+			},
+			OpCode::Clear => {
+				let mut stack = self.context()?.evaluation_stack_mut();
+				let count = stack.len();
+				stack.clear();
+				drop(stack);
+				self.reference_counter.remove_references(count);
+			},
+			OpCode::Dup => {
+				let item = self.peek(0)?;
+				self.push(item)?
+			},
+			OpCode::Over => {
+				let item = self.peek(1)?;
+				self.push(item)?
+			},
+			OpCode::Pick => {
+				let n = self.pop_index()?;
+				let item = self.peek(n)?;
+				self.push(item)?
+			},
+			OpCode::Tuck => {
+				let item = self.peek(0)?;
+				self.context()?.evaluation_stack_mut().insert(2, item.clone())?;
+				self.reference_counter.add_reference(&item);
+			},
+			OpCode::Swap => {
+				let item = self.context()?.evaluation_stack_mut().remove(1)?;
+				self.push(item)?
+			},
+			OpCode::Rot => {
+				let item = self.context()?.evaluation_stack_mut().remove(2)?;
+				self.push(item)?
+			},
+			OpCode::Roll => {
+				let n = self.pop_index()?;
+				if n != 0 {
+					let item = self.context()?.evaluation_stack_mut().remove(n)?;
+					self.push(item)?
+				}
+			},
+			OpCode::Reverse3 => self.context()?.evaluation_stack_mut().reverse(3)?,
+			OpCode::Reverse4 => self.context()?.evaluation_stack_mut().reverse(4)?,
+			OpCode::ReverseN => {
+				let n = self.pop_index()?;
+				self.context()?.evaluation_stack_mut().reverse(n)?
+			},
+
+			// Slots
+			OpCode::InitSSLot => {
+				let count = instruction.token_u8() as usize;
+				let context = self.context()?;
+				if context.static_fields().is_some() {
+					return Err(VMError::InvalidOperation(
+						"INITSSLOT cannot be executed twice".to_string(),
+					));
+				}
+				if count == 0 {
+					return Err(VMError::InvalidOperation(
+						"The operand of INITSSLOT can't be 0".to_string(),
+					));
+				}
+				*context.static_fields_mut() = Some(Slot::new(count));
+				self.reference_counter.add_references(&VMStackItem::Null, count);
+			},
+			OpCode::InitSlot => {
+				let locals = instruction.token_u8() as usize;
+				let args = instruction.token_u8_1() as usize;
+				let context = self.context()?;
+				if context.local_variables.is_some() || context.arguments.is_some() {
+					return Err(VMError::InvalidOperation(
+						"INITSLOT cannot be executed twice".to_string(),
+					));
+				}
+				if locals == 0 && args == 0 {
+					return Err(VMError::InvalidOperation(
+						"The operands of INITSLOT can't both be 0".to_string(),
+					));
+				}
+				let mut arguments = Vec::with_capacity(args);
+				for _ in 0..args {
+					arguments.push(self.pop()?);
+				}
+				self.reference_counter.add_references(&VMStackItem::Null, locals);
+				for argument in &arguments {
+					self.reference_counter.add_reference(argument);
+				}
+				let context = self.context_mut()?;
+				if locals > 0 {
+					context.local_variables = Some(Slot::new(locals));
+				}
+				if args > 0 {
+					context.arguments = Some(Slot::with_items(arguments));
+				}
+			},
+			OpCode::LdSFLd0
+			| OpCode::LdSFLd1
+			| OpCode::LdSFLd2
+			| OpCode::LdSFLd3
+			| OpCode::LdSFLd4
+			| OpCode::LdSFLd5
+			| OpCode::LdSFLd6
+			| OpCode::LdSFLd => {
+				let index = Self::slot_index(instruction, OpCode::LdSFLd0, OpCode::LdSFLd);
+				let item = {
+					let context = self.context()?;
+					let slot = context.static_fields();
+					Self::require_slot(slot.as_ref(), "static fields")?.get(index)?.clone()
+				};
+				self.push(item)?
+			},
+			OpCode::StSFLd0
+			| OpCode::StSFLd1
+			| OpCode::StSFLd2
+			| OpCode::StSFLd3
+			| OpCode::StSFLd4
+			| OpCode::StSFLd5
+			| OpCode::StSFLd6
+			| OpCode::StSFLd => {
+				let index = Self::slot_index(instruction, OpCode::StSFLd0, OpCode::StSFLd);
+				// The item replaces another one in the slot, only the pop changes the count
+				let item = self.pop()?;
+				let context = self.context()?;
+				let mut slot = context.static_fields_mut();
+				Self::require_slot_mut(slot.as_mut(), "static fields")?.set(index, item)?
+			},
+			OpCode::LdLoc0
+			| OpCode::LdLoc1
+			| OpCode::LdLoc2
+			| OpCode::LdLoc3
+			| OpCode::LdLoc4
+			| OpCode::LdLoc5
+			| OpCode::LdLoc6
+			| OpCode::LdLoc => {
+				let index = Self::slot_index(instruction, OpCode::LdLoc0, OpCode::LdLoc);
+				let context = self.context()?;
+				let item = Self::require_slot(context.local_variables.as_ref(), "local variables")?
+					.get(index)?
+					.clone();
+				self.push(item)?
+			},
+			OpCode::StLoc0
+			| OpCode::StLoc1
+			| OpCode::StLoc2
+			| OpCode::StLoc3
+			| OpCode::StLoc4
+			| OpCode::StLoc5
+			| OpCode::StLoc6
+			| OpCode::StLoc => {
+				let index = Self::slot_index(instruction, OpCode::StLoc0, OpCode::StLoc);
+				let item = self.pop()?;
+				let context = self.context_mut()?;
+				Self::require_slot_mut(context.local_variables.as_mut(), "local variables")?
+					.set(index, item)?
+			},
+			OpCode::LdArg0
+			| OpCode::LdArg1
+			| OpCode::LdArg2
+			| OpCode::LdArg3
+			| OpCode::LdArg4
+			| OpCode::LdArg5
+			| OpCode::LdArg6
+			| OpCode::LdArg => {
+				let index = Self::slot_index(instruction, OpCode::LdArg0, OpCode::LdArg);
+				let context = self.context()?;
+				let item = Self::require_slot(context.arguments.as_ref(), "arguments")?
+					.get(index)?
+					.clone();
+				self.push(item)?
+			},
+			OpCode::StArg0
+			| OpCode::StArg1
+			| OpCode::StArg2
+			| OpCode::StArg3
+			| OpCode::StArg4
+			| OpCode::StArg5
+			| OpCode::StArg6
+			| OpCode::StArg => {
+				let index = Self::slot_index(instruction, OpCode::StArg0, OpCode::StArg);
+				let item = self.pop()?;
+				let context = self.context_mut()?;
+				Self::require_slot_mut(context.arguments.as_mut(), "arguments")?.set(index, item)?
+			},
+
+			// Splice
+			OpCode::NewBuffer => {
+				let length = self.pop_index()?;
+				self.check_item_size(length)?;
+				self.push(VMStackItem::new_buffer(vec![0; length]))?
+			},
+			OpCode::MemCpy => {
+				let count = self.pop_index()?;
+				let src_index = self.pop_index()?;
+				let src = self.pop_bytes()?;
+				if src_index.checked_add(count).is_none_or(|end| end > src.len()) {
+					return Err(VMError::InvalidOperation(
+						"The source range of MEMCPY is out of bounds".to_string(),
+					));
+				}
+				let dst_index = self.pop_index()?;
+				let dst = match self.pop()? {
+					VMStackItem::Buffer(buffer) => buffer,
+					other =>
+						return Err(VMError::InvalidCast(format!(
+							"MEMCPY expects a Buffer, found {}",
+							other.item_type()
+						))),
+				};
+				let mut dst = dst.borrow_mut();
+				if dst_index.checked_add(count).is_none_or(|end| end > dst.len()) {
+					return Err(VMError::InvalidOperation(
+						"The destination range of MEMCPY is out of bounds".to_string(),
+					));
+				}
+				dst[dst_index..dst_index + count]
+					.copy_from_slice(&src[src_index..src_index + count]);
+			},
+			OpCode::Cat => {
+				let x2 = self.pop_bytes()?;
+				let mut x1 = self.pop_bytes()?;
+				self.check_item_size(x1.len() + x2.len())?;
+				x1.extend_from_slice(&x2);
+				self.push(VMStackItem::new_buffer(x1))?
+			},
+			OpCode::Substr => {
+				let count = self.pop_index()?;
+				let index = self.pop_index()?;
+				let x = self.pop_bytes()?;
+				if index.checked_add(count).is_none_or(|end| end > x.len()) {
+					return Err(VMError::InvalidOperation(
+						"The range of SUBSTR is out of bounds".to_string(),
+					));
+				}
+				self.push(VMStackItem::new_buffer(x[index..index + count].to_vec()))?
+			},
+			OpCode::Left | OpCode::Right => {
+				let count = self.pop_index()?;
+				let x = self.pop_bytes()?;
+				if count > x.len() {
+					return Err(VMError::InvalidOperation(format!(
+						"The count of {} is out of bounds",
+						instruction.opcode
+					)));
+				}
+				let bytes = if instruction.opcode == OpCode::Left {
+					x[..count].to_vec()
+				} else {
+					x[x.len() - count..].to_vec()
+				};
+				self.push(VMStackItem::new_buffer(bytes))?
+			},
+
+			// Bitwise logic
+			OpCode::Invert => {
+				let x = self.pop_integer()?;
+				self.push(VMStackItem::Integer(!x))?
+			},
+			OpCode::And | OpCode::Or | OpCode::Xor => {
+				let x2 = self.pop_integer()?;
+				let x1 = self.pop_integer()?;
+				let result = match instruction.opcode {
+					OpCode::And => x1 & x2,
+					OpCode::Or => x1 | x2,
+					_ => x1 ^ x2,
+				};
+				self.push(VMStackItem::Integer(result))?
+			},
+			OpCode::Equal | OpCode::NotEqual => {
+				let x2 = self.pop()?;
+				let x1 = self.pop()?;
+				let equal = x1.equals(&x2, &self.limits)?;
+				self.push(VMStackItem::Boolean(equal == (instruction.opcode == OpCode::Equal)))?
+			},
+
+			// Arithmetic
+			OpCode::Sign => {
+				let x = self.pop_integer()?;
+				let sign = match x.sign() {
+					Sign::Minus => -1,
+					Sign::NoSign => 0,
+					Sign::Plus => 1,
+				};
+				self.push(VMStackItem::from(sign))?
+			},
+			OpCode::Abs => {
+				let x = self.pop_integer()?;
+				self.push_integer(x.abs())?
+			},
+			OpCode::Negate => {
+				let x = self.pop_integer()?;
+				self.push_integer(-x)?
+			},
+			OpCode::Inc => {
+				let x = self.pop_integer()?;
+				self.push_integer(x + 1)?
+			},
+			OpCode::Dec => {
+				let x = self.pop_integer()?;
+				self.push_integer(x - 1)?
+			},
+			OpCode::Add
+			| OpCode::Sub
+			| OpCode::Mul
+			| OpCode::Div
+			| OpCode::Mod
+			| OpCode::Min
+			| OpCode::Max => {
+				let x2 = self.pop_integer()?;
+				let x1 = self.pop_integer()?;
+				if matches!(instruction.opcode, OpCode::Div | OpCode::Mod) && x2.is_zero() {
+					return Err(VMError::InvalidOperation(
+						"Attempted to divide by zero".to_string(),
+					));
+				}
+				let result = match instruction.opcode {
+					OpCode::Add => x1 + x2,
+					OpCode::Sub => x1 - x2,
+					OpCode::Mul => x1 * x2,
+					OpCode::Div => x1 / x2,
+					OpCode::Mod => x1 % x2,
+					OpCode::Min => x1.min(x2),
+					_ => x1.max(x2),
+				};
+				self.push_integer(result)?
+			},
+			OpCode::Pow => {
+				let exponent = self.pop_integer()?;
+				let exponent = self.check_shift(&exponent)?;
+				let value = self.pop_integer()?;
+				self.push_integer(value.pow(exponent))?
+			},
+			OpCode::Sqrt => {
+				let x = self.pop_integer()?;
+				if x.is_negative() {
+					return Err(VMError::InvalidOperation("value can not be negative".to_string()));
+				}
+				self.push_integer(x.sqrt())?
+			},
+			OpCode::ModMul => {
+				let modulus = self.pop_integer()?;
+				let x2 = self.pop_integer()?;
+				let x1 = self.pop_integer()?;
+				if modulus.is_zero() {
+					return Err(VMError::InvalidOperation(
+						"Attempted to divide by zero".to_string(),
+					));
+				}
+				self.push_integer(x1 * x2 % modulus)?
+			},
+			OpCode::ModPow => {
+				let modulus = self.pop_integer()?;
+				let exponent = self.pop_integer()?;
+				let value = self.pop_integer()?;
+				let result = if exponent == BigInt::from(-1) {
+					mod_inverse(&value, &modulus)?
+				} else {
+					mod_pow(&value, &exponent, &modulus)?
+				};
+				self.push_integer(result)?
+			},
+			OpCode::Shl | OpCode::Shr => {
+				let shift = self.pop_integer()?;
+				let shift = self.check_shift(&shift)?;
+				if shift != 0 {
+					let x = self.pop_integer()?;
+					let result = if instruction.opcode == OpCode::Shl {
+						x << shift as usize
+					} else {
+						x >> shift as usize
+					};
+					self.push_integer(result)?
+				}
+			},
+			OpCode::Not => {
+				let x = self.pop_bool()?;
+				self.push(VMStackItem::Boolean(!x))?
+			},
+			OpCode::BoolAnd | OpCode::BoolOr => {
+				let x2 = self.pop_bool()?;
+				let x1 = self.pop_bool()?;
+				let result =
+					if instruction.opcode == OpCode::BoolAnd { x1 && x2 } else { x1 || x2 };
+				self.push(VMStackItem::Boolean(result))?
+			},
+			OpCode::Nz => {
+				let x = self.pop_integer()?;
+				self.push(VMStackItem::Boolean(!x.is_zero()))?
+			},
+			OpCode::NumEqual | OpCode::NumNotEqual => {
+				let x2 = self.pop_integer()?;
+				let x1 = self.pop_integer()?;
+				let result = (x1 == x2) == (instruction.opcode == OpCode::NumEqual);
+				self.push(VMStackItem::Boolean(result))?
+			},
+			OpCode::Lt | OpCode::Le | OpCode::Gt | OpCode::Ge => {
+				let x2 = self.pop()?;
+				let x1 = self.pop()?;
+				let result = if x1.is_null() || x2.is_null() {
+					false
+				} else {
+					let (x1, x2) = (x1.get_integer()?, x2.get_integer()?);
+					match instruction.opcode {
+						OpCode::Lt => x1 < x2,
+						OpCode::Le => x1 <= x2,
+						OpCode::Gt => x1 > x2,
+						_ => x1 >= x2,
+					}
+				};
+				self.push(VMStackItem::Boolean(result))?
+			},
+			OpCode::Within => {
+				let b = self.pop_integer()?;
+				let a = self.pop_integer()?;
+				let x = self.pop_integer()?;
+				self.push(VMStackItem::Boolean(a <= x && x < b))?
+			},
+
+			// Compound types
+			OpCode::PackMap => {
+				let size = self.pop_index()?;
+				if size.saturating_mul(2) > self.context()?.evaluation_stack().len() {
+					return Err(VMError::InvalidOperation(format!("Invalid map size: {}", size)));
+				}
+				let mut entries: Vec<(VMStackItem, VMStackItem)> = Vec::with_capacity(size);
+				for _ in 0..size {
+					let key = self.pop()?;
+					key.check_map_key()?;
+					let value = self.pop()?;
+					map_insert(&mut entries, key, value);
+				}
+				self.push(VMStackItem::Map(Rc::new(RefCell::new(entries))))?
+			},
+			OpCode::PackStruct | OpCode::Pack => {
+				let size = self.pop_index()?;
+				if size > self.context()?.evaluation_stack().len() {
+					return Err(VMError::InvalidOperation(format!("Invalid array size: {}", size)));
+				}
+				let mut items = Vec::with_capacity(size);
+				for _ in 0..size {
+					items.push(self.pop()?);
+				}
+				let item = if instruction.opcode == OpCode::Pack {
+					VMStackItem::new_array(items)
+				} else {
+					VMStackItem::new_struct(items)
+				};
+				self.push(item)?
+			},
+			OpCode::Unpack => {
+				let compound = self.pop()?;
+				let count = match &compound {
+					VMStackItem::Map(entries) => {
+						let entries = entries.borrow();
+						for (key, value) in entries.iter().rev() {
+							self.push(value.clone())?;
+							self.push(key.clone())?;
+						}
+						entries.len()
+					},
+					VMStackItem::Array(items) | VMStackItem::Struct(items) => {
+						let items = items.borrow();
+						for item in items.iter().rev() {
+							self.push(item.clone())?;
+						}
+						items.len()
+					},
+					other =>
+						return Err(VMError::InvalidCast(format!(
+							"UNPACK expects a compound type, found {}",
+							other.item_type()
+						))),
+				};
+				self.push(VMStackItem::from(count as i64))?
+			},
+			OpCode::NewArray0 => self.push(VMStackItem::new_array(Vec::new()))?,
+			OpCode::NewArray | OpCode::NewArrayT | OpCode::NewStruct => {
+				let n = self.pop_index()?;
+				if n > self.limits.max_stack_size {
+					return Err(VMError::InvalidOperation(format!("MaxStackSize exceed: {}", n)));
+				}
+				let item = if instruction.opcode == OpCode::NewArrayT {
+					match Self::operand_type(instruction)? {
+						StackItemType::Boolean => VMStackItem::Boolean(false),
+						StackItemType::Integer => VMStackItem::from(0),
+						StackItemType::ByteString => VMStackItem::ByteString(Vec::new()),
+						_ => VMStackItem::Null,
+					}
+				} else {
+					VMStackItem::Null
+				};
+				let items = vec![item; n];
+				let item = if instruction.opcode == OpCode::NewStruct {
+					VMStackItem::new_struct(items)
+				} else {
+					VMStackItem::new_array(items)
+				};
+				self.push(item)?
+			},
+			OpCode::NewStruct0 => self.push(VMStackItem::new_struct(Vec::new()))?,
+			OpCode::NewMap => self.push(VMStackItem::new_map())?,
+			OpCode::Size => {
+				let size = self.pop()?.size()?;
+				self.push(VMStackItem::from(size as i64))?
+			},
+			OpCode::HasKey => {
+				let key = self.pop()?;
+				key.check_map_key()?;
+				let x = self.pop()?;
+				let result = match &x {
+					VMStackItem::Map(entries) =>
+						entries.borrow().iter().any(|(existing, _)| existing.key_equals(&key)),
+					_ => {
+						let index = key.get_integer()?;
+						if index.is_negative() {
+							return Err(VMError::InvalidOperation(format!(
+								"The negative value {} is invalid for HASKEY",
+								index
+							)));
+						}
+						let len = match &x {
+							VMStackItem::Array(items) | VMStackItem::Struct(items) =>
+								items.borrow().len(),
+							VMStackItem::Buffer(buffer) => buffer.borrow().len(),
+							VMStackItem::ByteString(bytes) => bytes.len(),
+							other =>
+								return Err(VMError::InvalidCast(format!(
+									"HASKEY is not supported on {}",
+									other.item_type()
+								))),
+						};
+						index < BigInt::from(len)
+					},
+				};
+				self.push(VMStackItem::Boolean(result))?
+			},
+			OpCode::Keys => match self.pop()? {
+				VMStackItem::Map(entries) => {
+					let keys = entries.borrow().iter().map(|(key, _)| key.clone()).collect();
+					self.push(VMStackItem::new_array(keys))?
+				},
+				other =>
+					return Err(VMError::InvalidCast(format!(
+						"KEYS expects a Map, found {}",
+						other.item_type()
+					))),
+			},
+			OpCode::Values => {
+				let values: Vec<VMStackItem> = match self.pop()? {
+					VMStackItem::Array(items) | VMStackItem::Struct(items) =>
+						items.borrow().clone(),
+					VMStackItem::Map(entries) =>
+						entries.borrow().iter().map(|(_, value)| value.clone()).collect(),
+					other =>
+						return Err(VMError::InvalidCast(format!(
+							"VALUES expects a compound type, found {}",
+							other.item_type()
+						))),
+				};
+				let values = values
+					.iter()
+					.map(|value| value.clone_struct(&self.limits))
+					.collect::<Result<Vec<_>, _>>()?;
+				self.push(VMStackItem::new_array(values))?
+			},
+			OpCode::PickItem => {
+				let key = self.pop()?;
+				key.check_map_key()?;
+				let x = self.pop()?;
+				let item = match &x {
+					VMStackItem::Map(entries) => entries
+						.borrow()
+						.iter()
+						.find(|(existing, _)| existing.key_equals(&key))
+						.map(|(_, value)| value.clone())
+						.ok_or_else(|| {
+							VMError::Catchable(format!("Key not found in Map: {:?}", key))
+						})?,
+					VMStackItem::Array(items) | VMStackItem::Struct(items) => {
+						let items = items.borrow();
+						let index = Self::checked_index(&key, items.len())?;
+						items[index].clone()
+					},
+					VMStackItem::Boolean(_)
+					| VMStackItem::Integer(_)
+					| VMStackItem::ByteString(_)
+					| VMStackItem::Buffer(_) => {
+						let bytes = x.get_span()?;
+						let index = Self::checked_index(&key, bytes.len())?;
+						VMStackItem::from(bytes[index] as i64)
+					},
+					other =>
+						return Err(VMError::InvalidCast(format!(
+							"PICKITEM is not supported on {}",
+							other.item_type()
+						))),
+				};
+				self.push(item)?
+			},
+			OpCode::Append => {
+				let item = self.pop()?.clone_struct(&self.limits)?;
+				match self.pop()? {
+					VMStackItem::Array(items) | VMStackItem::Struct(items) => {
+						self.reference_counter.add_reference(&item);
+						items.borrow_mut().push(item)
+					},
+					other =>
+						return Err(VMError::InvalidCast(format!(
+							"APPEND expects an Array or Struct, found {}",
+							other.item_type()
+						))),
+				}
+			},
+			OpCode::SetItem => {
+				let value = self.pop()?.clone_struct(&self.limits)?;
+				let key = self.pop()?;
+				key.check_map_key()?;
+				match self.pop()? {
+					VMStackItem::Array(items) | VMStackItem::Struct(items) => {
+						let mut items = items.borrow_mut();
+						let index = Self::checked_index(&key, items.len())?;
+						self.reference_counter.add_reference(&value);
+						self.reference_counter.remove_references(1);
+						items[index] = value;
+					},
+					VMStackItem::Map(entries) => {
+						self.reference_counter.add_reference(&value);
+						match map_insert(&mut entries.borrow_mut(), key.clone(), value) {
+							Some(_) => self.reference_counter.remove_references(1),
+							None => self.reference_counter.add_reference(&key),
+						}
+					},
+					VMStackItem::Buffer(buffer) => {
+						let mut buffer = buffer.borrow_mut();
+						let index = Self::checked_index(&key, buffer.len())?;
+						if !value.is_primitive() {
+							return Err(VMError::InvalidCast(format!(
+								"Only primitive values can be stored in a Buffer, found {}",
+								value.item_type()
+							)));
+						}
+						let byte = value
+							.get_integer()?
+							.to_i64()
+							.filter(|b| (i8::MIN as i64..=u8::MAX as i64).contains(b))
+							.ok_or_else(|| {
+								VMError::InvalidOperation("Overflow in SETITEM".to_string())
+							})?;
+						buffer[index] = byte as u8;
+					},
+					other =>
+						return Err(VMError::InvalidCast(format!(
+							"SETITEM is not supported on {}",
+							other.item_type()
+						))),
+				}
+			},
+			OpCode::ReverseItems => match self.pop()? {
+				VMStackItem::Array(items) | VMStackItem::Struct(items) =>
+					items.borrow_mut().reverse(),
+				VMStackItem::Buffer(buffer) => buffer.borrow_mut().reverse(),
+				other =>
+					return Err(VMError::InvalidCast(format!(
+						"REVERSEITEMS is not supported on {}",
+						other.item_type()
+					))),
+			},
+			OpCode::Remove => {
+				let key = self.pop()?;
+				key.check_map_key()?;
+				match self.pop()? {
+					VMStackItem::Array(items) | VMStackItem::Struct(items) => {
+						let mut items = items.borrow_mut();
+						let index = Self::checked_index(&key, items.len())?;
+						items.remove(index);
+						self.reference_counter.remove_references(1);
+					},
+					VMStackItem::Map(entries) => {
+						let mut entries = entries.borrow_mut();
+						let count = entries.len();
+						entries.retain(|(existing, _)| !existing.key_equals(&key));
+						self.reference_counter.remove_references((count - entries.len()) * 2);
+					},
+					other =>
+						return Err(VMError::InvalidCast(format!(
+							"REMOVE is not supported on {}",
+							other.item_type()
+						))),
+				}
+			},
+			OpCode::ClearItems => match self.pop()? {
+				VMStackItem::Array(items) | VMStackItem::Struct(items) => {
+					let count = std::mem::take(&mut *items.borrow_mut()).len();
+					self.reference_counter.remove_references(count);
+				},
+				VMStackItem::Map(entries) => {
+					let count = std::mem::take(&mut *entries.borrow_mut()).len();
+					self.reference_counter.remove_references(count * 2);
+				},
+				other =>
+					return Err(VMError::InvalidCast(format!(
+						"CLEARITEMS is not supported on {}",
+						other.item_type()
+					))),
+			},
+			OpCode::PopItem => {
+				let item = match self.pop()? {
+					VMStackItem::Array(items) | VMStackItem::Struct(items) => {
+						let item = items.borrow_mut().pop().ok_or_else(|| {
+							VMError::Catchable(
+								"POPITEM can't be executed on an empty array".to_string(),
+							)
+						})?;
+						self.reference_counter.remove_references(1);
+						item
+					},
+					other =>
+						return Err(VMError::InvalidCast(format!(
+							"POPITEM expects an Array or Struct, found {}",
+							other.item_type()
+						))),
+				};
+				self.push(item)?
+			},
+
+			// Types
+			OpCode::IsNull => {
+				let x = self.pop()?;
+				self.push(VMStackItem::Boolean(x.is_null()))?
+			},
+			OpCode::IsType => {
+				let target = Self::operand_type(instruction)?;
+				let x = self.pop()?;
+				self.push(VMStackItem::Boolean(x.item_type() == target))?
+			},
+			OpCode::Convert => {
+				let target = Self::operand_type(instruction)?;
+				let x = self.pop()?;
+				let converted = x.convert_to(target)?;
+				self.push(converted)?
+			},
+		}
+		Ok(())
+	}
+
+	fn push_integer(&mut self, value: BigInt) -> Result<(), VMError> {
+		if integer_to_bytes(&value).len() > MAX_INTEGER_SIZE {
+			return Err(VMError::ItemTooLarge(format!(
+				"Integer results can't be larger than {} bytes",
+				MAX_INTEGER_SIZE
+			)));
+		}
+		self.push(VMStackItem::Integer(value))
+	}
+
+	/// Pops a non-negative integer used as a count or an index.
+	fn pop_index(&mut self) -> Result<usize, VMError> {
+		let value = self.pop_integer()?;
+		value.to_usize().ok_or_else(|| {
+			VMError::InvalidOperation(format!("The value {} is out of range", value))
+		})
+	}
+
+	fn checked_index(key: &VMStackItem, len: usize) -> Result<usize, VMError> {
+		let index = key.get_integer()?;
+		index
+			.to_usize()
+			.filter(|index| *index < len)
+			.ok_or_else(|| VMError::Catchable(format!("The value {} is out of range.", index)))
+	}
+
+	fn check_item_size(&self, size: usize) -> Result<(), VMError> {
+		if size > self.limits.max_item_size {
+			return Err(VMError::ItemTooLarge(format!(
+				"{} bytes exceeds MaxItemSize {}",
+				size, self.limits.max_item_size
+			)));
+		}
+		Ok(())
+	}
+
+	fn check_shift(&self, shift: &BigInt) -> Result<u32, VMError> {
+		shift
+			.to_u32()
+			.filter(|shift| *shift <= self.limits.max_shift)
+			.ok_or_else(|| VMError::InvalidOperation(format!("Invalid shift value: {}", shift)))
+	}
+
+	fn operand_type(instruction: &Instruction) -> Result<StackItemType, VMError> {
+		match StackItemType::try_from(instruction.token_u8()) {
+			Ok(StackItemType::Any) | Err(_) => Err(VMError::InvalidOperation(format!(
+				"Invalid type for {}: 0x{:02X}",
+				instruction.opcode,
+				instruction.token_u8()
+			))),
+			Ok(item_type) => Ok(item_type),
+		}
+	}
+
+	fn jump_offset(instruction: &Instruction) -> i32 {
+		if instruction.operand.len() == 4 {
+			instruction.token_i32()
+		} else {
+			instruction.token_i8() as i32
+		}
+	}
+
+	fn slot_index(instruction: &Instruction, first: OpCode, indexed: OpCode) -> usize {
+		if instruction.opcode == indexed {
+			instruction.token_u8() as usize
+		} else {
+			(instruction.opcode as u8 - first as u8) as usize
+		}
+	}
+
+	fn require_slot<'s>(slot: Option<&'s Slot>, name: &str) -> Result<&'s Slot, VMError> {
+		slot.ok_or_else(|| {
+			VMError::InvalidOperation(format!("The {} slot is not initialized", name))
+		})
+	}
+
+	fn require_slot_mut<'s>(
+		slot: Option<&'s mut Slot>,
+		name: &str,
+	) -> Result<&'s mut Slot, VMError> {
+		slot.ok_or_else(|| {
+			VMError::InvalidOperation(format!("The {} slot is not initialized", name))
+		})
+	}
+
+	/// Resolves an offset relative to the current instruction.
+	fn offset_position(&self, offset: i32) -> Result<usize, VMError> {
+		let position = self.context()?.instruction_pointer() as i64 + offset as i64;
+		usize::try_from(position)
+			.map_err(|_| VMError::InvalidOperation(format!("Invalid jump target: {}", position)))
+	}
+
+	fn execute_jump_offset(&mut self, offset: i32) -> Result<(), VMError> {
+		let position = self.offset_position(offset)?;
+		self.execute_jump(position)
+	}
+
+	fn execute_jump(&mut self, position: usize) -> Result<(), VMError> {
+		let context = self.context_mut()?;
+		if position >= context.script().len() {
+			return Err(VMError::InvalidOperation(format!("Jump out of range: {}", position)));
+		}
+		context.set_instruction_pointer(position);
+		self.is_jumping = true;
+		Ok(())
+	}
+
+	fn execute_call(&mut self, position: usize) -> Result<(), VMError> {
+		let context = self.context()?;
+		if position > context.script().len() {
+			return Err(VMError::InvalidOperation(format!("Call out of range: {}", position)));
+		}
+		let callee = context.clone_at(position);
+		self.load_context(callee)
+	}
+
+	fn execute_ret(&mut self) -> Result<(), VMError> {
+		let context = self.invocation_stack.pop().ok_or(VMError::StackUnderflow)?;
+		let shares_stack = self
+			.invocation_stack
+			.last()
+			.is_some_and(|caller| caller.shares_stack_with(&context));
+		if !shares_stack {
+			let mut returned = context.evaluation_stack_mut();
+			if context.rvcount() >= 0 && returned.len() != context.rvcount() as usize {
+				return Err(VMError::InvalidOperation(
+					"RVCount doesn't match with EvaluationStack".to_string(),
+				));
+			}
+			match self.invocation_stack.last() {
+				Some(caller) => returned.move_to(&mut caller.evaluation_stack_mut()),
+				None => returned.move_to(&mut self.result_stack),
+			}
+		}
+		if self.invocation_stack.is_empty() {
+			self.state = VMState::Halt;
+		}
+		self.release_context(&context);
+		self.unloaded_contexts.push((context, false));
+		self.is_jumping = true;
+		Ok(())
+	}
+
+	fn execute_try(&mut self, catch_offset: i32, finally_offset: i32) -> Result<(), VMError> {
+		if catch_offset == 0 && finally_offset == 0 {
+			return Err(VMError::InvalidOperation(
+				"catchOffset and finallyOffset can't be 0 in a TRY block".to_string(),
+			));
+		}
+		if self.context()?.try_stack.len() >= self.limits.max_try_nesting_depth {
+			return Err(VMError::InvalidOperation("MaxTryNestingDepth exceed".to_string()));
+		}
+		let catch_pointer =
+			if catch_offset == 0 { None } else { Some(self.offset_position(catch_offset)?) };
+		let finally_pointer =
+			if finally_offset == 0 { None } else { Some(self.offset_position(finally_offset)?) };
+		self.context_mut()?
+			.try_stack
+			.push(ExceptionHandlingContext::new(catch_pointer, finally_pointer));
+		Ok(())
+	}
+
+	fn execute_end_try(&mut self, end_offset: i32) -> Result<(), VMError> {
+		let end_pointer = self.offset_position(end_offset)?;
+		let context = self.context_mut()?;
+		let current_try = context.try_stack.last_mut().ok_or_else(|| {
+			VMError::InvalidOperation("The corresponding TRY block cannot be found".to_string())
+		})?;
+		if current_try.state == ExceptionHandlingState::Finally {
+			return Err(VMError::InvalidOperation(
+				"The opcode ENDTRY can't be executed in a FINALLY block".to_string(),
+			));
+		}
+		match current_try.finally_pointer {
+			Some(finally_pointer) => {
+				current_try.state = ExceptionHandlingState::Finally;
+				current_try.end_pointer = Some(end_pointer);
+				context.set_instruction_pointer(finally_pointer);
+			},
+			None => {
+				context.try_stack.pop();
+				context.set_instruction_pointer(end_pointer);
+			},
+		}
+		self.is_jumping = true;
+		Ok(())
+	}
+
+	/// Throws `exception` as a VM exception, transferring control to the nearest `CATCH` or
+	/// `FINALLY` block.
+	pub fn execute_throw(&mut self, exception: VMStackItem) -> Result<(), VMError> {
+		self.uncaught_exception = Some(exception);
+		self.handle_exception()
+	}
+
+	fn handle_exception(&mut self) -> Result<(), VMError> {
+		let mut unwound = 0;
+		for index in (0..self.invocation_stack.len()).rev() {
+			let context = &mut self.invocation_stack[index];
+			while let Some(try_context) = context.try_stack.last() {
+				let done = try_context.state == ExceptionHandlingState::Finally
+					|| (try_context.state == ExceptionHandlingState::Catch
+						&& !try_context.has_finally());
+				if done {
+					context.try_stack.pop();
+					continue;
+				}
+
+				let remaining = self.invocation_stack.len() - unwound;
+				while self.invocation_stack.len() > remaining {
+					let context = self.invocation_stack.pop().ok_or(VMError::StackUnderflow)?;
+					self.release_context(&context);
+					self.unloaded_contexts.push((context, true));
+				}
+				let context = self.invocation_stack.last_mut().ok_or(VMError::StackUnderflow)?;
+				let try_context = context.try_stack.last_mut().ok_or(VMError::StackUnderflow)?;
+				if try_context.state == ExceptionHandlingState::Try && try_context.has_catch() {
+					try_context.state = ExceptionHandlingState::Catch;
+					let catch_pointer = try_context.catch_pointer.unwrap_or_default();
+					context.set_instruction_pointer(catch_pointer);
+					if let Some(exception) = self.uncaught_exception.take() {
+						self.reference_counter.add_reference(&exception);
+						context.evaluation_stack_mut().push(exception);
+					}
+				} else {
+					try_context.state = ExceptionHandlingState::Finally;
+					let finally_pointer = try_context.finally_pointer.unwrap_or_default();
+					context.set_instruction_pointer(finally_pointer);
+				}
+				self.is_jumping = true;
+				return Ok(());
+			}
+			unwound += 1;
+		}
+
+		let message = match &self.uncaught_exception {
+			Some(VMStackItem::ByteString(bytes)) => String::from_utf8_lossy(bytes).to_string(),
+			Some(item) => format!("{:?}", item),
+			None => String::new(),
+		};
+		Err(VMError::UnhandledException(message))
+	}
+}
+
+/// Sets the value of `key`, returning the value it replaced.
+fn map_insert(
+	entries: &mut Vec<(VMStackItem, VMStackItem)>,
+	key: VMStackItem,
+	value: VMStackItem,
+) -> Option<VMStackItem> {
+	match entries.iter_mut().find(|(existing, _)| existing.key_equals(&key)) {
+		Some((_, existing)) => Some(std::mem::replace(existing, value)),
+		None => {
+			entries.push((key, value));
+			None
+		},
+	}
+}
+
+/// Computes `value ^ exponent % modulus` with the sign of the dividend, like .NET's `BigInteger.ModPow`.
+fn mod_pow(value: &BigInt, exponent: &BigInt, modulus: &BigInt) -> Result<BigInt, VMError> {
+	if exponent.is_negative() {
+		return Err(VMError::InvalidOperation("The exponent can't be negative".to_string()));
+	}
+	if modulus.is_zero() {
+		return Err(VMError::InvalidOperation("Attempted to divide by zero".to_string()));
+	}
+	let result = value.abs().modpow(exponent, &modulus.abs());
+	let odd = exponent % BigInt::from(2) == BigInt::one();
+	Ok(if value.is_negative() && odd { -result } else { result })
+}
+
+/// Computes the modular multiplicative inverse of `value`, as used by `MODPOW` with exponent `-1`.
+fn mod_inverse(value: &BigInt, modulus: &BigInt) -> Result<BigInt, VMError> {
+	if value.sign() != Sign::Plus {
+		return Err(VMError::InvalidOperation("The value must be positive".to_string()));
+	}
+	if *modulus < BigInt::from(2) {
+		return Err(VMError::InvalidOperation("The modulus must be at least 2".to_string()));
+	}
+	let (mut r, mut old_r) = (value.clone(), modulus.clone());
+	let (mut s, mut old_s) = (BigInt::one(), BigInt::zero());
+	while r.is_positive() {
+		let q = &old_r / &r;
+		let next_r = &old_r % &r;
+		old_r = std::mem::replace(&mut r, next_r);
+		let next_s = &old_s - &q * &s;
+		old_s = std::mem::replace(&mut s, next_s);
+	}
+	let mut result = old_s % modulus;
+	if result.is_negative() {
+		result += modulus;
+	}
+	if !(value * &result % modulus).is_one() {
+		return Err(VMError::InvalidOperation("No modular inverse exists".to_string()));
+	}
+	Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::neo_builder::ScriptBuilder;
+
+	fn run(script: &[u8]) -> ExecutionResult {
+		ExecutionEngine::run(script.to_vec())
+	}
+
+	fn int(value: i64) -> StackItem {
+		StackItem::Integer { value }
+	}
+
+	#[test]
+	fn test_arithmetic() {
+		let script = ScriptBuilder::new()
+			.push_integer(BigInt::from(7))
+			.push_integer(BigInt::from(5))
+			.op_code(&[OpCode::Sub, OpCode::Push3, OpCode::Mul, OpCode::Push4, OpCode::Mod])
+			.to_bytes();
+		let result = run(&script);
+		assert_eq!(result.state, VMState::Halt);
+		assert_eq!(result.stack, vec![int(2)]);
+	}
+
+	#[test]
+	fn test_gas_consumed_uses_opcode_prices() {
+		// PUSH1 PUSH2 ADD: 1 + 1 + 8 datoshi, multiplied by the exec fee factor. The implicit RET is free.
+		let result = run(&[0x11, 0x12, 0x9e]);
+		assert_eq!(result.state, VMState::Halt);
+		assert_eq!(result.gas_consumed, 10 * DEFAULT_EXEC_FEE_FACTOR as i64);
+	}
+
+	#[test]
+	fn test_out_of_gas() {
+		// An endless loop: JMP 0
+		let mut engine = ExecutionEngine::new();
+		engine.set_gas_limit(1000);
+		engine.load_script(vec![0x22, 0x00], -1).unwrap();
+		assert_eq!(engine.execute(), VMState::Fault);
+		assert!(matches!(engine.fault_exception(), Some(VMError::InsufficientGas { .. })));
+	}
+
+	#[test]
+	fn test_division_by_zero_faults() {
+		let result = run(&[0x11, 0x10, 0xa1]);
+		assert_eq!(result.state, VMState::Fault);
+		assert!(result.exception.unwrap().contains("divide by zero"));
+	}
+
+	#[test]
+	fn test_jumps_and_comparisons() {
+		// PUSH3 PUSH2 JMPGT +4 PUSH0 RET PUSH1
+		let script = [0x13, 0x12, 0x2c, 0x04, 0x10, 0x40, 0x11];
+		let result = run(&script);
+		assert_eq!(result.state, VMState::Halt);
+		assert_eq!(result.stack, vec![int(1)]);
+	}
+
+	#[test]
+	fn test_call_and_slots() {
+		// 00: PUSH5  01: CALL +3  03: RET
+		// Function at 04: INITSLOT 1 local, 1 arg, LDARG0, DUP, MUL, STLOC0, LDLOC0, RET
+		let script = [0x15, 0x34, 0x03, 0x40, 0x57, 0x01, 0x01, 0x78, 0x4a, 0xa0, 0x70, 0x68, 0x40];
+		let result = run(&script);
+		assert_eq!(result.state, VMState::Halt);
+		assert_eq!(result.stack, vec![int(25)]);
+	}
+
+	#[test]
+	fn test_try_catch_handles_throw() {
+		// 00: TRY catch=+7 finally=0
+		// 03: PUSHDATA1 "e"  06: THROW
+		// 07: (catch) DROP  08: PUSH7  09: ENDTRY +2  11: RET
+		let script = [0x3b, 0x07, 0x00, 0x0c, 0x01, 0x65, 0x3a, 0x45, 0x17, 0x3d, 0x02, 0x40];
+		let result = run(&script);
+		assert_eq!(result.state, VMState::Halt, "{:?}", result.exception);
+		assert_eq!(result.stack, vec![int(7)]);
+	}
+
+	#[test]
+	fn test_try_finally_rethrows() {
+		// 00: TRY catch=0 finally=+5  03: PUSH1 04: THROW  05: PUSH2 06: ENDFINALLY
+		let script = [0x3b, 0x00, 0x05, 0x11, 0x3a, 0x12, 0x3f];
+		let result = run(&script);
+		assert_eq!(result.state, VMState::Fault);
+		assert!(matches!(result.exception, Some(message) if message.contains("unhandled")));
+	}
+
+	#[test]
+	fn test_catchable_engine_exception() {
+		// TRY catch=+6, NEWARRAY0 PUSH1 PICKITEM (out of range), then catch: DROP PUSH9 ENDTRY +2 RET
+		let script = [0x3b, 0x06, 0x00, 0xc2, 0x11, 0xce, 0x45, 0x19, 0x3d, 0x02, 0x40];
+		let result = run(&script);
+		assert_eq!(result.state, VMState::Halt, "{:?}", result.exception);
+		assert_eq!(result.stack, vec![int(9)]);
+	}
+
+	#[test]
+	fn test_assert_faults() {
+		let result = run(&[0x09, 0x39]);
+		assert_eq!(result.state, VMState::Fault);
+		assert!(matches!(result.exception, Some(message) if message.contains("ASSERT")));
+	}
+
+	#[test]
+	fn test_compound_types_have_reference_semantics() {
+		// NEWARRAY0 DUP PUSH5 APPEND SIZE
+		let result = run(&[0xc2, 0x4a, 0x15, 0xcf, 0xca]);
+		assert_eq!(result.stack, vec![int(1)]);
+
+		// PUSH1 PUSH2 PUSH2 PACK -> [2, 1]
+		let result = run(&[0x11, 0x12, 0x12, 0xc0]);
+		assert_eq!(result.stack, vec![StackItem::Array { value: vec![int(2), int(1)] }]);
+	}
+
+	#[test]
+	fn test_struct_value_semantics() {
+		// Appending a struct to an array stores a copy, so changing the original is not visible.
+		let script = [
+			0xc2, // NEWARRAY0     -> a
+			0xc5, // NEWSTRUCT0    -> a s
+			0x4b, // OVER          -> a s a
+			0x4b, // OVER          -> a s a s
+			0xcf, // APPEND        -> a s      (a holds a copy of s)
+			0x4a, // DUP           -> a s s
+			0x11, // PUSH1         -> a s s 1
+			0xcf, // APPEND        -> a s      (s holds one item)
+			0xca, // SIZE          -> a 1
+			0x50, // SWAP          -> 1 a
+			0x10, // PUSH0         -> 1 a 0
+			0xce, // PICKITEM      -> 1 copy
+			0xca, // SIZE          -> 1 0
+		];
+		let result = run(&script);
+		assert_eq!(result.state, VMState::Halt, "{:?}", result.exception);
+		// The original has one item, the copy in the array is still empty
+		assert_eq!(result.stack, vec![int(1), int(0)]);
+
+		let equal = run(&[0xc5, 0xc5, 0x97]);
+		assert_eq!(equal.stack, vec![StackItem::Boolean { value: true }]);
+		let not_equal = run(&[0xc2, 0xc2, 0x97]);
+		assert_eq!(not_equal.stack, vec![StackItem::Boolean { value: false }]);
+	}
+
+	#[test]
+	fn test_maps() {
+		// NEWMAP DUP PUSHDATA1 "a" PUSH5 SETITEM PUSHDATA1 "a" PICKITEM
+		let script = [0xc8, 0x4a, 0x0c, 0x01, 0x61, 0x15, 0xd0, 0x0c, 0x01, 0x61, 0xce];
+		let result = run(&script);
+		assert_eq!(result.state, VMState::Halt, "{:?}", result.exception);
+		assert_eq!(result.stack, vec![int(5)]);
+	}
+
+	#[test]
+	fn test_cat_and_convert() {
+		let script = ScriptBuilder::new()
+			.push_data(b"Neo".to_vec())
+			.push_data(b"Rust".to_vec())
+			.op_code(&[OpCode::Cat])
+			.op_code_with_arg(OpCode::Convert, vec![StackItemType::ByteString as u8])
+			.to_bytes();
+		let result = run(&script);
+		assert_eq!(result.stack, vec![StackItem::new_byte_string(b"NeoRust".to_vec())]);
+	}
+
+	#[test]
+	fn test_big_integers() {
+		let big = BigInt::from(2).pow(200);
+		let script = ScriptBuilder::new()
+			.push_integer(big.clone())
+			.op_code(&[OpCode::Dup, OpCode::Mul])
+			.to_bytes();
+		let mut engine = ExecutionEngine::new();
+		engine.load_script(script, -1).unwrap();
+		assert_eq!(engine.execute(), VMState::Fault, "2^400 exceeds the 32 byte integer limit");
+
+		let script = ScriptBuilder::new()
+			.push_integer(big.clone())
+			.push_integer(BigInt::from(3))
+			.op_code(&[OpCode::Shr])
+			.to_bytes();
+		let mut engine = ExecutionEngine::new();
+		engine.load_script(script, -1).unwrap();
+		assert_eq!(engine.execute(), VMState::Halt);
+		assert_eq!(
+			engine.result_stack().items()[0].get_integer().unwrap(),
+			BigInt::from(2).pow(197)
+		);
+	}
+
+	#[test]
+	fn test_modpow_and_mod_inverse() {
+		let script = ScriptBuilder::new()
+			.push_integer(BigInt::from(19))
+			.push_integer(BigInt::from(-1))
+			.push_integer(BigInt::from(141))
+			.op_code(&[OpCode::ModPow])
+			.to_bytes();
+		let result = run(&script);
+		assert_eq!(result.stack, vec![int(52)]);
+	}
+
+	#[test]
+	fn test_syscall_without_host_faults() {
+		let mut builder = ScriptBuilder::new();
+		builder.sys_call(InteropService::SystemRuntimeGetTime);
+		let result = run(&builder.to_bytes());
+		assert_eq!(result.state, VMState::Fault);
+		assert!(result.exception.unwrap().contains("System.Runtime.GetTime"));
+	}
+
+	#[test]
+	fn test_invalid_opcode_faults() {
+		let result = run(&[0xff]);
+		assert_eq!(result.state, VMState::Fault);
+		assert_eq!(result.exception, Some(VMError::InvalidOpCode(0xff).to_string()));
+	}
+
+	#[test]
+	fn test_step_breaks_after_each_instruction() {
+		let mut engine = ExecutionEngine::new();
+		engine.load_script(vec![0x11, 0x12, 0x9e], -1).unwrap();
+		assert_eq!(engine.step_with(&mut NoInterop), VMState::Break);
+		assert_eq!(engine.current_context().unwrap().instruction_pointer(), 1);
+		assert_eq!(engine.execute(), VMState::Halt);
+		assert_eq!(engine.result().stack, vec![int(3)]);
+	}
+
+	#[test]
+	fn test_reference_counter() {
+		let script = [
+			0x11, // PUSH1      1 reference
+			0x12, // PUSH2      2
+			0x12, // PUSH2      3
+			0xc0, // PACK       the array and its 2 items
+			0x4a, // DUP        4
+			0x15, // PUSH5      5
+			0xcf, // APPEND     the array and its 3 items
+			0x45, // DROP       the items stay counted until the references are counted again
+		];
+		let mut engine = ExecutionEngine::new();
+		engine.load_script(script.to_vec(), -1).unwrap();
+		let mut counts = Vec::new();
+		for _ in 0..script.len() {
+			assert_eq!(engine.step_with(&mut NoInterop), VMState::Break);
+			counts.push(engine.reference_counter().count());
+		}
+		assert_eq!(counts, vec![1, 2, 3, 3, 4, 5, 4, 3]);
+	}
+
+	#[test]
+	fn test_max_stack_size() {
+		// PUSHINT16 2048 NEWARRAY: the array and its items are 2049 references
+		let result = run(&[0x01, 0x00, 0x08, 0xc3]);
+		assert_eq!(result.state, VMState::Fault);
+		assert!(matches!(result.exception, Some(message) if message.contains("MaxStackSize")));
+
+		// 00: PUSH10  01: PUSHINT16 1000  04: NEWARRAY  05: DROP  06: DEC  07: DUP  08: JMPIF -7
+		// 10: DROP. The dropped arrays are released once they would exceed the limit.
+		let script = [0x1a, 0x01, 0xe8, 0x03, 0xc3, 0x45, 0x9d, 0x4a, 0x24, 0xf9, 0x45];
+		let mut engine = ExecutionEngine::new();
+		engine.load_script(script.to_vec(), -1).unwrap();
+		assert_eq!(engine.execute(), VMState::Halt, "{:?}", engine.fault_exception());
+		assert!(engine.reference_counter().count() <= 2048);
+		assert!(engine.reference_counter().tracked_count() <= 2048);
+	}
+}
@@ -0,0 +1,660 @@
+use base64::{engine::general_purpose, Engine};
+use num_bigint::BigInt;
+use primitive_types::{H160, H256};
+
+use crate::{
+	builder::Signer,
+	crypto::Secp256r1PublicKey,
+	neo_clients::{APITrait, JsonRpcError, ProviderError},
+	neo_types::{ContractManifest, ContractParameter, ContractState, InvocationResult, NefFile},
+	neo_vm::{
+		native::{contract_from_item, contract_hash_key, contract_key},
+		BinarySerializer, BlockRecord, BlockchainState, ChainSettings, ExecutionEngineLimits,
+		ForkError, LocalBlockchain, MissingState, NativeContract, StorageKey, VMError,
+	},
+};
+
+/// The number of entries requested per `findstates` call, the default limit of the nodes.
+const PAGE_SIZE: u32 = 100;
+
+/// The error code of `getstate` for keys without a value.
+const UNKNOWN_STORAGE_ITEM: i64 = -104;
+
+/// The error code of `getstate` for keys without a value on nodes older than 3.7, which
+/// report the `KeyNotFoundException` of the trie as is.
+const KEY_NOT_FOUND: i64 = -2146232969;
+
+/// A [`LocalBlockchain`] continuing from a block of a real network.
+///
+/// Nothing is downloaded up front: contracts and storage are fetched from the state root of
+/// the forked block the first time an execution reads them, with the `getstate` and
+/// `findstates` RPC methods of the StateService plugin, and cached. Single entries are read
+/// with `getstate`, and only searches by prefix page through `findstates`. Executions then
+/// run and persist blocks in memory exactly like on a local chain, so operations can be
+/// rehearsed against real contracts and balances, acting as any account.
+///
+/// The fork has no blocks or transactions before the forked block, and only enumerates the
+/// contracts it has loaded. Like on a local chain, transactions aren't checked against
+/// witnesses and don't pay fees.
+///
+/// # Examples
+///
+/// ```no_run
+/// use neo3::neo_clients::{HttpProvider, RpcClient};
+/// use neo3::neo_vm::{ForkedBlockchain, NativeContract};
+/// use primitive_types::H160;
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let client = RpcClient::new(HttpProvider::new("https://mainnet1.neo.coz.io:443")?);
+/// let mut fork = ForkedBlockchain::new(&client, 5_000_000).await?;
+///
+/// let whale = H160::repeat_byte(0x01);
+/// let alice = H160::repeat_byte(0xaa);
+/// fork.transfer(NativeContract::NeoToken, &whale, &alice, 100).await?;
+/// println!("{}", fork.balance_of(NativeContract::NeoToken, &alice).await?);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ForkedBlockchain<'a, P: APITrait> {
+	provider: &'a P,
+	root_hash: H256,
+	chain: LocalBlockchain,
+}
+
+impl<'a, P: APITrait> ForkedBlockchain<'a, P> {
+	/// Forks the network of `provider` at block `height`, which must have a state root.
+	pub async fn new(provider: &'a P, height: u32) -> Result<Self, ForkError> {
+		let version = provider.get_version().await.map_err(provider_error)?;
+		let protocol = version.protocol.ok_or_else(|| {
+			ForkError::InvalidState("The node didn't send its protocol".to_string())
+		})?;
+		let standby_committee = provider
+			.get_committee()
+			.await
+			.map_err(provider_error)?
+			.iter()
+			.map(|key| {
+				Secp256r1PublicKey::from_encoded(key).ok_or_else(|| {
+					ForkError::InvalidState(format!("Invalid committee member: {}", key))
+				})
+			})
+			.collect::<Result<Vec<_>, _>>()?;
+		let settings = ChainSettings {
+			network: protocol.network,
+			ms_per_block: protocol.ms_per_block,
+			validators_count: protocol
+				.validators_count
+				.map_or(standby_committee.len(), |count| count as usize),
+			standby_committee,
+			initial_gas_distribution: protocol.initial_gas_distribution as i64,
+		};
+
+		let root_hash = provider.get_state_root(height).await.map_err(provider_error)?.root_hash;
+		let block = provider.get_raw_block_by_index(height).await.map_err(provider_error)?;
+		let block = general_purpose::STANDARD
+			.decode(block)
+			.map_err(|err| ForkError::InvalidState(format!("Invalid block: {}", err)))?;
+		let block = BlockRecord::from_header(&block)
+			.map_err(|err| ForkError::InvalidState(format!("Invalid block: {}", err)))?;
+
+		let chain = LocalBlockchain::from_state(BlockchainState::fork(settings, block));
+		Ok(Self { provider, root_hash, chain })
+	}
+
+	/// The local chain, with the state loaded so far.
+	pub fn chain(&self) -> &LocalBlockchain {
+		&self.chain
+	}
+
+	/// The state root of the forked block, which the state is loaded from.
+	pub fn root_hash(&self) -> H256 {
+		self.root_hash
+	}
+
+	/// The state of a contract, loading it if needed.
+	pub async fn contract(&mut self, hash: &H160) -> Result<Option<ContractState>, ForkError> {
+		self.resolve(|chain| Ok(chain.contract(hash).cloned())).await
+	}
+
+	/// The value of a storage entry, loading it if needed.
+	pub async fn storage(&mut self, hash: &H160, key: &[u8]) -> Result<Option<Vec<u8>>, ForkError> {
+		self.resolve(|chain| Ok(chain.storage(hash, key).cloned())).await
+	}
+
+	/// Overwrites a storage entry of a contract, e.g. to set a balance directly.
+	pub async fn put_storage(
+		&mut self,
+		hash: &H160,
+		key: &[u8],
+		value: Vec<u8>,
+	) -> Result<(), ForkError> {
+		let contract = self
+			.contract(hash)
+			.await?
+			.ok_or_else(|| VMError::InvalidOperation(format!("Unknown contract {:?}", hash)))?;
+		self.resolve(|chain| {
+			let state = chain.state_mut();
+			state.put_storage(StorageKey::new(contract.id, key), value.clone());
+			state.commit();
+			Ok(())
+		})
+		.await
+	}
+
+	/// The balance of `account` in a native token, in the token's smallest unit.
+	pub async fn balance_of(
+		&mut self,
+		token: NativeContract,
+		account: &H160,
+	) -> Result<BigInt, ForkError> {
+		self.resolve(|chain| Ok(chain.balance_of(token, account))).await
+	}
+
+	/// Persists `script` in a new block, as a transaction signed by `signers`.
+	pub async fn invoke_script(
+		&mut self,
+		script: Vec<u8>,
+		signers: Vec<Signer>,
+	) -> Result<InvocationResult, ForkError> {
+		self.resolve(|chain| chain.invoke_script(script.clone(), signers.clone())).await
+	}
+
+	/// Persists a call to `method` of a contract in a new block.
+	pub async fn invoke_function(
+		&mut self,
+		hash: &H160,
+		method: &str,
+		params: &[ContractParameter],
+		signers: Vec<Signer>,
+	) -> Result<InvocationResult, ForkError> {
+		self.resolve(|chain| chain.invoke_function(hash, method, params, signers.clone()))
+			.await
+	}
+
+	/// Runs `script` on top of the current state without persisting anything.
+	pub async fn test_invoke_script(
+		&mut self,
+		script: Vec<u8>,
+		signers: Vec<Signer>,
+	) -> Result<InvocationResult, ForkError> {
+		self.resolve(|chain| Ok(chain.test_invoke_script(script.clone(), signers.clone())))
+			.await
+	}
+
+	/// Runs a call to `method` of a contract without persisting anything.
+	pub async fn test_invoke_function(
+		&mut self,
+		hash: &H160,
+		method: &str,
+		params: &[ContractParameter],
+		signers: Vec<Signer>,
+	) -> Result<InvocationResult, ForkError> {
+		self.resolve(|chain| Ok(chain.test_invoke_function(hash, method, params, signers.clone())))
+			.await
+	}
+
+	/// Deploys a contract on behalf of `sender` and returns its hash.
+	pub async fn deploy(
+		&mut self,
+		nef: &NefFile,
+		manifest: &ContractManifest,
+		sender: &H160,
+		data: Option<ContractParameter>,
+	) -> Result<H160, ForkError> {
+		self.resolve(|chain| chain.deploy(nef, manifest, sender, data.clone())).await
+	}
+
+	/// Transfers `amount` of a native token from `from`, which is trusted to have signed.
+	pub async fn transfer(
+		&mut self,
+		token: NativeContract,
+		from: &H160,
+		to: &H160,
+		amount: i64,
+	) -> Result<InvocationResult, ForkError> {
+		self.resolve(|chain| chain.transfer(token, from, to, amount)).await
+	}
+
+	/// Runs `f` on a copy of the chain until it doesn't read any state that isn't loaded,
+	/// loading what it read in between, and keeps the copy of the last run.
+	async fn resolve<T>(
+		&mut self,
+		mut f: impl FnMut(&mut LocalBlockchain) -> Result<T, VMError>,
+	) -> Result<T, ForkError> {
+		loop {
+			let mut chain = self.chain.clone();
+			let result = f(&mut chain);
+			let missing = self.chain.state().take_missing();
+			if missing.is_empty() {
+				self.chain = chain;
+				return Ok(result?);
+			}
+			for missing in missing {
+				self.load(missing).await?;
+			}
+		}
+	}
+
+	async fn load(&mut self, missing: MissingState) -> Result<(), ForkError> {
+		match missing {
+			MissingState::Contract(hash) => {
+				let contract = self.fetch_contract(&hash).await?;
+				self.chain.state_mut().load_contract(hash, contract);
+			},
+			MissingState::ContractId(id) => {
+				self.contract_hash(id).await?;
+			},
+			MissingState::Storage(key) => {
+				let value = match self.contract_hash(key.id).await? {
+					Some(hash) => self.fetch_entry(&hash, &key.key).await?,
+					None => None,
+				};
+				self.chain.state_mut().load_storage(key, value);
+			},
+			MissingState::StoragePrefix(prefix) => {
+				let entries = match self.contract_hash(prefix.id).await? {
+					Some(hash) => self.fetch_entries(&hash, &prefix.key).await?,
+					None => Vec::new(),
+				};
+				self.chain.state_mut().load_storage_prefix(prefix, entries);
+			},
+		}
+		Ok(())
+	}
+
+	/// The hash of the contract with `id`, loading the contract if needed.
+	async fn contract_hash(&mut self, id: i32) -> Result<Option<H160>, ForkError> {
+		if let Some(hash) = self.chain.state().loaded_contract_hash(id) {
+			return Ok(Some(hash));
+		}
+		let management = NativeContract::ContractManagement.hash();
+		let Some(bytes) = self.fetch_entry(&management, &contract_hash_key(id)).await? else {
+			self.chain.state_mut().load_missing_contract_id(id);
+			return Ok(None);
+		};
+		if bytes.len() != 20 {
+			return Err(ForkError::InvalidState(format!("Invalid hash of contract {}", id)));
+		}
+		let hash = H160::from_slice(&bytes.into_iter().rev().collect::<Vec<_>>());
+		let contract = self.fetch_contract(&hash).await?;
+		self.chain.state_mut().load_contract(hash, contract);
+		Ok(Some(hash))
+	}
+
+	async fn fetch_contract(&self, hash: &H160) -> Result<Option<ContractState>, ForkError> {
+		let management = NativeContract::ContractManagement.hash();
+		let Some(bytes) = self.fetch_entry(&management, &contract_key(hash)).await? else {
+			return Ok(None);
+		};
+		let item = BinarySerializer::deserialize(&bytes, &ExecutionEngineLimits::default())?;
+		Ok(Some(contract_from_item(&item)?))
+	}
+
+	async fn fetch_entry(&self, hash: &H160, key: &[u8]) -> Result<Option<Vec<u8>>, ForkError> {
+		match self.provider.get_state(self.root_hash, *hash, &hex::encode(key)).await {
+			Ok(value) => Ok(Some(decode(&value)?)),
+			Err(err) if is_unknown_storage_item(&err) => Ok(None),
+			Err(err) => Err(provider_error(err)),
+		}
+	}
+
+	async fn fetch_entries(
+		&self,
+		hash: &H160,
+		prefix: &[u8],
+	) -> Result<Vec<(Vec<u8>, Vec<u8>)>, ForkError> {
+		let prefix_hex = hex::encode(prefix);
+		let mut entries = Vec::new();
+		let mut start = None;
+		loop {
+			let states = self
+				.provider
+				.find_states(self.root_hash, *hash, &prefix_hex, start.as_deref(), Some(PAGE_SIZE))
+				.await
+				.map_err(provider_error)?;
+			for result in &states.results {
+				entries.push((decode(&result.key)?, decode(&result.value)?));
+			}
+			match entries.last() {
+				Some((key, _)) if states.truncated => start = Some(hex::encode(key)),
+				_ => return Ok(entries),
+			}
+		}
+	}
+}
+
+fn provider_error(err: impl std::error::Error) -> ForkError {
+	ForkError::Provider(err.to_string())
+}
+
+/// Whether `err` is the answer of a node to `getstate` for a key without a value.
+fn is_unknown_storage_item(err: &(dyn std::error::Error + 'static)) -> bool {
+	matches!(
+		err.downcast_ref::<ProviderError>(),
+		Some(ProviderError::JsonRpcError(JsonRpcError {
+			code: UNKNOWN_STORAGE_ITEM | KEY_NOT_FOUND,
+			..
+		}))
+	)
+}
+
+fn decode(base64: &str) -> Result<Vec<u8>, ForkError> {
+	general_purpose::STANDARD
+		.decode(base64)
+		.map_err(|err| ForkError::InvalidState(format!("Invalid base64 data: {}", err)))
+}
+
+#[cfg(test)]
+mod tests {
+	use std::{collections::BTreeMap, fmt::Debug, sync::Mutex};
+
+	use async_trait::async_trait;
+	use serde::{de::DeserializeOwned, Serialize};
+	use serde_json::{json, Value};
+
+	use super::*;
+	use crate::{
+		builder::{AccountSigner, InteropService, ScriptBuilder},
+		neo_clients::{JsonRpcProvider, RpcClient},
+		neo_types::{NeoVMStateType, OpCode, ScriptHashExtension, StackItem},
+		neo_vm::native::contract_item,
+	};
+
+	/// A node serving the state of a local chain through the RPC methods used by forks.
+	#[derive(Debug)]
+	struct FakeNode {
+		chain: LocalBlockchain,
+		get_state_calls: Mutex<usize>,
+		find_states_calls: Mutex<usize>,
+	}
+
+	impl FakeNode {
+		/// The storage of contract `hash`, including the contracts stored by ContractManagement.
+		fn storage(&self, hash: &str) -> BTreeMap<Vec<u8>, Vec<u8>> {
+			let state = self.chain.state();
+			let Some(contract) = state.contracts().find(|contract| contract.hash.to_hex() == hash)
+			else {
+				return BTreeMap::new();
+			};
+			let mut storage: BTreeMap<_, _> =
+				state.find_storage(contract.id, &[]).into_iter().collect();
+			if contract.hash == NativeContract::ContractManagement.hash() {
+				for contract in state.contracts() {
+					let item = contract_item(contract).unwrap();
+					let value =
+						BinarySerializer::serialize(&item, &ExecutionEngineLimits::default())
+							.unwrap();
+					storage.insert(contract_key(&contract.hash), value);
+					storage.insert(contract_hash_key(contract.id), contract.hash.to_le_vec());
+				}
+			}
+			storage
+		}
+
+		fn get_state(&self, params: &[Value]) -> Result<Value, ProviderError> {
+			*self.get_state_calls.lock().unwrap() += 1;
+			let key = general_purpose::STANDARD.decode(params[2].as_str().unwrap()).unwrap();
+			match self.storage(params[1].as_str().unwrap()).get(&key) {
+				Some(value) => Ok(json!(general_purpose::STANDARD.encode(value))),
+				None => Err(ProviderError::JsonRpcError(JsonRpcError {
+					code: UNKNOWN_STORAGE_ITEM,
+					message: "Unknown storage item".to_string(),
+					data: None,
+				})),
+			}
+		}
+
+		fn find_states(&self, params: &[Value]) -> Value {
+			*self.find_states_calls.lock().unwrap() += 1;
+			let param = |index: usize| {
+				let value = params.get(index).and_then(Value::as_str).unwrap_or_default();
+				general_purpose::STANDARD.decode(value).unwrap()
+			};
+			let (prefix, start) = (param(2), param(3));
+			let count = params.get(4).and_then(Value::as_u64).unwrap_or(100) as usize;
+			let mut results: Vec<_> = self
+				.storage(params[1].as_str().unwrap())
+				.into_iter()
+				.filter(|(key, _)| key.starts_with(&prefix) && (start.is_empty() || *key > start))
+				.map(|(key, value)| {
+					json!({
+						"key": general_purpose::STANDARD.encode(key),
+						"value": general_purpose::STANDARD.encode(value),
+					})
+				})
+				.collect();
+			let truncated = results.len() > count;
+			results.truncate(count);
+			json!({ "truncated": truncated, "results": results })
+		}
+
+		fn raw_block(&self, index: u32) -> String {
+			let block = self.chain.state().block(index).unwrap();
+			let mut header = Vec::new();
+			header.extend_from_slice(&block.version.to_le_bytes());
+			header.extend(block.prev_hash.0.iter().rev());
+			header.extend(block.merkle_root.0.iter().rev());
+			header.extend_from_slice(&block.timestamp.to_le_bytes());
+			header.extend_from_slice(&block.nonce.to_le_bytes());
+			header.extend_from_slice(&block.index.to_le_bytes());
+			header.push(block.primary_index);
+			header.extend(block.next_consensus.0.iter().rev());
+			general_purpose::STANDARD.encode(header)
+		}
+	}
+
+	#[async_trait]
+	impl JsonRpcProvider for FakeNode {
+		type Error = ProviderError;
+
+		async fn fetch<T, R>(&self, method: &str, params: T) -> Result<R, ProviderError>
+		where
+			T: Debug + Serialize + Send + Sync,
+			R: DeserializeOwned + Send,
+		{
+			let params = serde_json::to_value(params)?;
+			let params = params.as_array().cloned().unwrap_or_default();
+			let settings = self.chain.settings();
+			let result = match method {
+				"getversion" => json!({
+					"protocol": {
+						"network": settings.network,
+						"validatorscount": settings.validators_count,
+						"msperblock": settings.ms_per_block,
+						"initialgasdistribution": settings.initial_gas_distribution,
+					}
+				}),
+				"getcommittee" => json!(settings
+					.standby_committee
+					.iter()
+					.map(|key| key.get_encoded_compressed_hex())
+					.collect::<Vec<_>>()),
+				"getstateroot" => json!({
+					"version": 0,
+					"index": params[0],
+					"roothash": format!("0x{}", "11".repeat(32)),
+				}),
+				"getblock" => json!(self.raw_block(params[0].as_u64().unwrap() as u32)),
+				"getstate" => self.get_state(&params)?,
+				"findstates" => self.find_states(&params),
+				_ => return Err(ProviderError::UnsupportedRPC),
+			};
+			Ok(serde_json::from_value(result)?)
+		}
+	}
+
+	/// A contract whose `get(key)` method reads its storage.
+	fn reader_contract() -> (NefFile, ContractManifest) {
+		let mut builder = ScriptBuilder::new();
+		builder
+			.sys_call(InteropService::SystemStorageGetContext)
+			.sys_call(InteropService::SystemStorageGet)
+			.op_code(&[OpCode::Ret]);
+		let nef = NefFile::new("test", "", vec![], builder.to_bytes()).unwrap();
+		let manifest = serde_json::from_str(
+			r#"{
+				"name": "Reader",
+				"groups": [],
+				"features": {},
+				"supportedstandards": ["NEP-0"],
+				"abi": {
+					"methods": [
+						{
+							"name": "get",
+							"parameters": [{ "name": "key", "type": "ByteArray" }],
+							"offset": 0,
+							"returntype": "ByteArray",
+							"safe": true
+						}
+					],
+					"events": []
+				},
+				"permissions": [{ "contract": "*", "methods": ["*"] }],
+				"trusts": [],
+				"extra": { "Author": "Neo" }
+			}"#,
+		)
+		.unwrap();
+		(nef, manifest)
+	}
+
+	/// A node whose chain has a `Reader` contract storing `value` under `key`, and Alice with
+	/// 10 GAS.
+	fn fake_node() -> (RpcClient<FakeNode>, H160) {
+		let mut chain = LocalBlockchain::new();
+		let validators = chain.settings().validators_address();
+		let (nef, manifest) = reader_contract();
+		let reader = chain.deploy(&nef, &manifest, &validators, None).unwrap();
+		let id = chain.contract(&reader).unwrap().id;
+		chain
+			.state_mut()
+			.put_storage(StorageKey::new(id, b"key".to_vec()), b"value".to_vec());
+		chain.state_mut().commit();
+		let alice = H160::repeat_byte(0xaa);
+		chain
+			.transfer(NativeContract::GasToken, &validators, &alice, 10_0000_0000)
+			.unwrap();
+		let node = FakeNode {
+			chain,
+			get_state_calls: Mutex::new(0),
+			find_states_calls: Mutex::new(0),
+		};
+		(RpcClient::new(node), reader)
+	}
+
+	#[tokio::test]
+	async fn test_fork_block() {
+		let (client, _) = fake_node();
+		let remote = &client.as_ref().chain;
+		let fork = ForkedBlockchain::new(&client, remote.height()).await.unwrap();
+
+		assert_eq!(fork.chain().height(), remote.height());
+		assert_eq!(
+			fork.chain().state().current_block().unwrap().hash,
+			remote.state().current_block().unwrap().hash
+		);
+		assert_eq!(fork.chain().settings().network, remote.settings().network);
+		assert_eq!(
+			fork.chain().settings().validators_address(),
+			remote.settings().validators_address()
+		);
+		assert!(fork.chain().state().block(remote.height() - 1).is_none());
+	}
+
+	#[tokio::test]
+	async fn test_transfer_on_fork() {
+		let (client, _) = fake_node();
+		let remote = &client.as_ref().chain;
+		let mut fork = ForkedBlockchain::new(&client, remote.height()).await.unwrap();
+		let alice = H160::repeat_byte(0xaa);
+		let bob = H160::repeat_byte(0xbb);
+
+		assert_eq!(
+			fork.balance_of(NativeContract::GasToken, &alice).await.unwrap(),
+			BigInt::from(10_0000_0000)
+		);
+		fork.transfer(NativeContract::GasToken, &alice, &bob, 4_0000_0000)
+			.await
+			.unwrap();
+
+		assert_eq!(fork.chain().height(), remote.height() + 1);
+		assert_eq!(
+			fork.balance_of(NativeContract::GasToken, &alice).await.unwrap(),
+			BigInt::from(6_0000_0000)
+		);
+		assert_eq!(
+			fork.balance_of(NativeContract::GasToken, &bob).await.unwrap(),
+			BigInt::from(4_0000_0000)
+		);
+		assert_eq!(remote.balance_of(NativeContract::GasToken, &bob), BigInt::from(0));
+	}
+
+	#[tokio::test]
+	async fn test_contract_loaded_on_demand() {
+		let (client, reader) = fake_node();
+		let remote = &client.as_ref().chain;
+		let mut fork = ForkedBlockchain::new(&client, remote.height()).await.unwrap();
+		assert!(fork.chain().contract(&reader).is_none());
+
+		let key = [ContractParameter::byte_array(b"key".to_vec())];
+		let result = fork.test_invoke_function(&reader, "get", &key, vec![]).await.unwrap();
+		assert_eq!(result.state, NeoVMStateType::Halt);
+		assert_eq!(result.stack, vec![StackItem::ByteString { value: "dmFsdWU=".to_string() }]);
+		assert_eq!(fork.chain().contract(&reader), remote.contract(&reader));
+
+		// Loaded state is cached
+		let calls = *client.as_ref().get_state_calls.lock().unwrap();
+		fork.test_invoke_function(&reader, "get", &key, vec![]).await.unwrap();
+		assert_eq!(*client.as_ref().get_state_calls.lock().unwrap(), calls);
+
+		fork.put_storage(&reader, b"key", b"changed".to_vec()).await.unwrap();
+		let result = fork.test_invoke_function(&reader, "get", &key, vec![]).await.unwrap();
+		assert_eq!(result.stack, vec![StackItem::ByteString { value: "Y2hhbmdlZA==".to_string() }]);
+	}
+
+	#[tokio::test]
+	async fn test_storage_point_reads() {
+		let (client, reader) = fake_node();
+		let remote = &client.as_ref().chain;
+		let mut fork = ForkedBlockchain::new(&client, remote.height()).await.unwrap();
+
+		assert_eq!(fork.storage(&reader, b"key").await.unwrap(), Some(b"value".to_vec()));
+		assert_eq!(fork.storage(&reader, b"ke").await.unwrap(), None);
+		assert_eq!(fork.storage(&reader, b"key2").await.unwrap(), None);
+		// The contract and the three entries are read one by one
+		assert_eq!(*client.as_ref().get_state_calls.lock().unwrap(), 4);
+		assert_eq!(*client.as_ref().find_states_calls.lock().unwrap(), 0);
+
+		// Missing entries are cached too
+		assert_eq!(fork.storage(&reader, b"ke").await.unwrap(), None);
+		assert_eq!(*client.as_ref().get_state_calls.lock().unwrap(), 4);
+	}
+
+	#[tokio::test]
+	async fn test_deploy_on_fork() {
+		let (client, reader) = fake_node();
+		let remote = &client.as_ref().chain;
+		let mut fork = ForkedBlockchain::new(&client, remote.height()).await.unwrap();
+		let (nef, mut manifest) = reader_contract();
+		manifest.name = Some("Reader2".to_string());
+		let sender = H160::repeat_byte(0xaa);
+
+		let hash = fork.deploy(&nef, &manifest, &sender, None).await.unwrap();
+
+		let deployed = fork.contract(&hash).await.unwrap().unwrap();
+		assert_eq!(deployed.id, remote.contract(&reader).unwrap().id + 1);
+		let signer = Signer::AccountSigner(AccountSigner::none_hash160(sender).unwrap());
+		let result = fork
+			.test_invoke_function(
+				&hash,
+				"get",
+				&[ContractParameter::byte_array(vec![1])],
+				vec![signer],
+			)
+			.await
+			.unwrap();
+		assert_eq!(result.stack, vec![StackItem::Any]);
+		assert_eq!(deployed.nef.checksum, nef.checksum() as i64);
+	}
+}
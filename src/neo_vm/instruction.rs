@@ -0,0 +1,90 @@
+use crate::{neo_types::OpCode, neo_vm::VMError};
+
+/// A single decoded NeoVM instruction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Instruction {
+	/// The opcode of the instruction.
+	pub opcode: OpCode,
+	/// The operand bytes, without the length prefix of `PUSHDATA` instructions.
+	pub operand: Vec<u8>,
+	/// The total encoded size of the instruction, including opcode and length prefix.
+	pub size: usize,
+}
+
+impl Instruction {
+	/// The implicit `RET` executed when the instruction pointer reaches the end of a script.
+	pub fn ret() -> Self {
+		Self { opcode: OpCode::Ret, operand: Vec::new(), size: 1 }
+	}
+
+	/// Decodes the instruction starting at `offset` in `script`.
+	pub fn decode(script: &[u8], offset: usize) -> Result<Self, VMError> {
+		let byte = *script.get(offset).ok_or_else(|| {
+			VMError::InvalidInstruction(offset, "offset is outside of the script".to_string())
+		})?;
+		let opcode = OpCode::try_from(byte).map_err(|_| VMError::InvalidOpCode(byte))?;
+		let truncated =
+			|| VMError::InvalidInstruction(offset, format!("{} operand is truncated", opcode));
+
+		let Some(operand_size) = opcode.operand_size() else {
+			return Ok(Self { opcode, operand: Vec::new(), size: 1 });
+		};
+
+		let mut position = offset + 1;
+		let length = match *operand_size.prefix_size() as usize {
+			0 => *operand_size.size() as usize,
+			prefix_size => {
+				let prefix = script.get(position..position + prefix_size).ok_or_else(truncated)?;
+				position += prefix_size;
+				let mut bytes = [0u8; 4];
+				bytes[..prefix_size].copy_from_slice(prefix);
+				u32::from_le_bytes(bytes) as usize
+			},
+		};
+		let operand = script
+			.get(position..position.checked_add(length).ok_or_else(truncated)?)
+			.ok_or_else(truncated)?
+			.to_vec();
+		Ok(Self { opcode, size: position + length - offset, operand })
+	}
+
+	/// The operand interpreted as a signed byte (`JMP`, `CALL`, `TRY` catch offset, ...).
+	pub fn token_i8(&self) -> i8 {
+		self.operand[0] as i8
+	}
+
+	/// The second byte of the operand interpreted as a signed byte (`TRY` finally offset).
+	pub fn token_i8_1(&self) -> i8 {
+		self.operand[1] as i8
+	}
+
+	/// The operand interpreted as a little-endian `i32` (`JMP_L`, `CALL_L`, ...).
+	pub fn token_i32(&self) -> i32 {
+		i32::from_le_bytes([self.operand[0], self.operand[1], self.operand[2], self.operand[3]])
+	}
+
+	/// The second four bytes of the operand interpreted as a little-endian `i32` (`TRY_L`).
+	pub fn token_i32_1(&self) -> i32 {
+		i32::from_le_bytes([self.operand[4], self.operand[5], self.operand[6], self.operand[7]])
+	}
+
+	/// The operand interpreted as an unsigned byte.
+	pub fn token_u8(&self) -> u8 {
+		self.operand[0]
+	}
+
+	/// The second byte of the operand interpreted as an unsigned byte (`INITSLOT` arguments).
+	pub fn token_u8_1(&self) -> u8 {
+		self.operand[1]
+	}
+
+	/// The operand interpreted as a little-endian `u16` (`CALLT`).
+	pub fn token_u16(&self) -> u16 {
+		u16::from_le_bytes([self.operand[0], self.operand[1]])
+	}
+
+	/// The operand interpreted as a little-endian `u32` (`SYSCALL`).
+	pub fn token_u32(&self) -> u32 {
+		u32::from_le_bytes([self.operand[0], self.operand[1], self.operand[2], self.operand[3]])
+	}
+}
@@ -0,0 +1,575 @@
+use num_bigint::BigInt;
+use primitive_types::{H160, H256};
+
+use crate::{
+	builder::{AccountSigner, InteropService, ScriptBuilder, Signer},
+	codec::NeoSerializable,
+	neo_types::{
+		ContractManifest, ContractParameter, ContractState, InvocationResult, NefFile,
+		NeoVMStateType,
+	},
+	neo_vm::{
+		contract_hash, ApplicationEngine, BlockRecord, BlockchainState, ChainSettings, Debugger,
+		NativeContract, Profiler, StorageKey, TransactionRecord, TriggerType, VMError, DEFAULT_GAS_LIMIT,
+	},
+};
+
+/// The timestamp of the genesis block, in milliseconds since the Unix epoch.
+const GENESIS_TIMESTAMP: u64 = 1_468_595_301_000;
+
+/// A blockchain running entirely in memory, with emulated native contracts.
+///
+/// Every invocation is persisted in its own block, produced on demand. Transactions are
+/// trusted: their signers aren't checked against witnesses and they don't pay fees, so tests
+/// can act as any account, including the committee.
+///
+/// # Examples
+///
+/// ```rust
+/// use neo3::neo_vm::{LocalBlockchain, NativeContract};
+/// use num_bigint::BigInt;
+/// use primitive_types::H160;
+///
+/// let mut chain = LocalBlockchain::new();
+/// let validators = chain.settings().validators_address();
+/// let alice = H160::repeat_byte(0xaa);
+///
+/// chain.transfer(NativeContract::GasToken, &validators, &alice, 10_0000_0000).unwrap();
+///
+/// assert_eq!(chain.balance_of(NativeContract::GasToken, &alice), BigInt::from(10_0000_0000));
+/// assert_eq!(chain.height(), 1);
+/// ```
+#[derive(Debug, Clone)]
+pub struct LocalBlockchain {
+	state: BlockchainState,
+	next_nonce: u32,
+}
+
+impl Default for LocalBlockchain {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl LocalBlockchain {
+	/// Creates a chain with the default [`ChainSettings`] and persists its genesis block.
+	pub fn new() -> Self {
+		Self::with_settings(ChainSettings::default())
+	}
+
+	pub fn with_settings(settings: ChainSettings) -> Self {
+		let mut chain = Self { state: BlockchainState::new(settings), next_nonce: 0 };
+		chain.persist_block(Vec::new()).expect("The genesis block is valid");
+		chain
+	}
+
+	/// Wraps a state whose blocks were already persisted, like a fork.
+	pub(crate) fn from_state(state: BlockchainState) -> Self {
+		Self { state, next_nonce: 0 }
+	}
+
+	pub fn settings(&self) -> &ChainSettings {
+		self.state.settings()
+	}
+
+	/// The state of the chain after the last persisted block.
+	pub fn state(&self) -> &BlockchainState {
+		&self.state
+	}
+
+	pub(crate) fn state_mut(&mut self) -> &mut BlockchainState {
+		&mut self.state
+	}
+
+	/// The index of the last persisted block.
+	pub fn height(&self) -> u32 {
+		self.state.height().unwrap_or_default()
+	}
+
+	pub fn contract(&self, hash: &H160) -> Option<&ContractState> {
+		self.state.contract(hash)
+	}
+
+	/// The storage value of a contract, or `None` if the contract or key doesn't exist.
+	pub fn storage(&self, hash: &H160, key: &[u8]) -> Option<&Vec<u8>> {
+		let contract = self.state.contract(hash)?;
+		self.state.get_storage(&StorageKey::new(contract.id, key))
+	}
+
+	/// The balance of `account` in a native token, in the token's smallest unit.
+	pub fn balance_of(&self, token: NativeContract, account: &H160) -> BigInt {
+		let result = self.test_invoke_function(
+			&token.hash(),
+			"balanceOf",
+			&[ContractParameter::h160(account)],
+			Vec::new(),
+		);
+		result
+			.stack
+			.first()
+			.and_then(|item| item.as_int())
+			.map(BigInt::from)
+			.unwrap_or_default()
+	}
+
+	/// Builds the next block from `transactions` and persists it, running the `OnPersist`,
+	/// `Application` and `PostPersist` triggers like a node.
+	///
+	/// Returns the result of every transaction, in order.
+	pub fn persist_block(
+		&mut self,
+		transactions: Vec<TransactionRecord>,
+	) -> Result<Vec<InvocationResult>, VMError> {
+		let index = self.state.height().map_or(0, |height| height + 1);
+		let prev_hash = self.state.current_block().map_or(H256::zero(), |block| block.hash);
+		let settings = self.state.settings().clone();
+		let timestamp = self.next_timestamp();
+		let nonce = u64::from(index).wrapping_mul(0x9e37_79b9_7f4a_7c15);
+		let mut transactions = transactions;
+		for transaction in &mut transactions {
+			transaction.block_index = index;
+		}
+		let block = BlockRecord::new(
+			prev_hash,
+			index,
+			timestamp,
+			nonce,
+			settings.validators_address(),
+			transactions.iter().map(|transaction| transaction.hash).collect(),
+		);
+
+		// Work on a copy so that the chain is left untouched if the block can't be persisted
+		let mut state = self.state.clone();
+		for transaction in &transactions {
+			state.add_transaction(transaction.clone());
+		}
+		state.commit();
+		state = run_system_trigger(TriggerType::OnPersist, state, &block)?;
+
+		let mut results = Vec::with_capacity(transactions.len());
+		for mut transaction in transactions {
+			let script = transaction.script.clone();
+			let mut engine = ApplicationEngine::new(
+				TriggerType::Application,
+				state,
+				Some(transaction.clone()),
+				block.clone(),
+				DEFAULT_GAS_LIMIT,
+			);
+			engine.load_script(script.clone())?;
+			transaction.vm_state = engine.execute();
+			results.push(engine.invocation_result(&script));
+			state = engine.into_state();
+			state.add_transaction(transaction);
+			state.commit();
+		}
+
+		state = run_system_trigger(TriggerType::PostPersist, state, &block)?;
+		state.add_block(block);
+		self.state = state;
+		Ok(results)
+	}
+
+	/// Persists `script` in a new block, as a transaction signed by `signers`.
+	pub fn invoke_script(
+		&mut self,
+		script: Vec<u8>,
+		signers: Vec<Signer>,
+	) -> Result<InvocationResult, VMError> {
+		let transaction = self.new_transaction(script, signers);
+		self.next_nonce += 1;
+		let mut results = self.persist_block(vec![transaction])?;
+		Ok(results.remove(0))
+	}
+
+	/// Persists a call to `method` of a contract in a new block.
+	pub fn invoke_function(
+		&mut self,
+		hash: &H160,
+		method: &str,
+		params: &[ContractParameter],
+		signers: Vec<Signer>,
+	) -> Result<InvocationResult, VMError> {
+		self.invoke_script(call_script(hash, method, params)?, signers)
+	}
+
+	/// Runs `script` on top of the current state without persisting anything, like the
+	/// `invokescript` RPC method.
+	pub fn test_invoke_script(&self, script: Vec<u8>, signers: Vec<Signer>) -> InvocationResult {
+		let mut engine = self.test_engine(&script, signers);
+		if let Err(err) = engine.load_script(script.clone()) {
+			let mut result = engine.invocation_result(&script);
+			result.state = NeoVMStateType::Fault;
+			result.exception = Some(err.to_string());
+			return result;
+		}
+		engine.execute();
+		engine.invocation_result(&script)
+	}
+
+	/// Runs a call to `method` of a contract without persisting anything, like the
+	/// `invokefunction` RPC method.
+	pub fn test_invoke_function(
+		&self,
+		hash: &H160,
+		method: &str,
+		params: &[ContractParameter],
+		signers: Vec<Signer>,
+	) -> InvocationResult {
+		match call_script(hash, method, params) {
+			Ok(script) => self.test_invoke_script(script, signers),
+			Err(err) => InvocationResult {
+				state: NeoVMStateType::Fault,
+				exception: Some(err.to_string()),
+				..Default::default()
+			},
+		}
+	}
+
+	/// Loads `script` into a [`Debugger`] running on top of the current state, without
+	/// persisting anything.
+	pub fn debug_script(&self, script: Vec<u8>, signers: Vec<Signer>) -> Result<Debugger, VMError> {
+		let mut engine = self.test_engine(&script, signers);
+		engine.load_script(script)?;
+		Ok(Debugger::new(engine))
+	}
+
+	/// Loads a call to `method` of a contract into a [`Debugger`].
+	pub fn debug_function(
+		&self,
+		hash: &H160,
+		method: &str,
+		params: &[ContractParameter],
+		signers: Vec<Signer>,
+	) -> Result<Debugger, VMError> {
+		self.debug_script(call_script(hash, method, params)?, signers)
+	}
+
+	/// Loads `script` into a [`Profiler`] running on top of the current state, without
+	/// persisting anything.
+	pub fn profile_script(&self, script: Vec<u8>, signers: Vec<Signer>) -> Result<Profiler, VMError> {
+		let mut engine = self.test_engine(&script, signers);
+		engine.load_script(script)?;
+		Ok(Profiler::new(engine))
+	}
+
+	/// Loads a call to `method` of a contract into a [`Profiler`].
+	pub fn profile_function(
+		&self,
+		hash: &H160,
+		method: &str,
+		params: &[ContractParameter],
+		signers: Vec<Signer>,
+	) -> Result<Profiler, VMError> {
+		self.profile_script(call_script(hash, method, params)?, signers)
+	}
+
+	/// Deploys a contract on behalf of `sender` and returns its hash.
+	///
+	/// `data` is passed to the `_deploy` method of the contract, if it has one.
+	pub fn deploy(
+		&mut self,
+		nef: &NefFile,
+		manifest: &ContractManifest,
+		sender: &H160,
+		data: Option<ContractParameter>,
+	) -> Result<H160, VMError> {
+		let manifest_json = serde_json::to_string(manifest)
+			.map_err(|err| VMError::InvalidOperation(format!("Invalid manifest: {}", err)))?;
+		let mut params = vec![
+			ContractParameter::byte_array(nef.to_array()),
+			ContractParameter::string(manifest_json),
+		];
+		params.extend(data);
+		let result = self.invoke_function(
+			&NativeContract::ContractManagement.hash(),
+			"deploy",
+			&params,
+			vec![called_by_entry(sender)?],
+		)?;
+		if result.state != NeoVMStateType::Halt {
+			return Err(VMError::InvalidOperation(format!(
+				"The deployment failed: {}",
+				result.exception.unwrap_or_default()
+			)));
+		}
+		Ok(contract_hash(sender, nef.checksum(), manifest.name.as_deref().unwrap_or_default()))
+	}
+
+	/// Transfers `amount` of a native token from `from`, which is trusted to have signed.
+	pub fn transfer(
+		&mut self,
+		token: NativeContract,
+		from: &H160,
+		to: &H160,
+		amount: i64,
+	) -> Result<InvocationResult, VMError> {
+		let params = [
+			ContractParameter::h160(from),
+			ContractParameter::h160(to),
+			ContractParameter::integer(amount),
+			ContractParameter::any(),
+		];
+		let result =
+			self.invoke_function(&token.hash(), "transfer", &params, vec![called_by_entry(from)?])?;
+		if result.stack.first().and_then(|item| item.as_bool()) != Some(true) {
+			return Err(VMError::InvalidOperation(format!(
+				"The transfer failed: {}",
+				result.exception.clone().unwrap_or_default()
+			)));
+		}
+		Ok(result)
+	}
+
+	/// An engine executing `script` in the next block, on top of the current state.
+	fn test_engine(&self, script: &[u8], signers: Vec<Signer>) -> ApplicationEngine {
+		let transaction = self.new_transaction(script.to_vec(), signers);
+		let index = self.height() + 1;
+		let prev_hash = self.state.current_block().map_or(H256::zero(), |block| block.hash);
+		let block = BlockRecord::new(
+			prev_hash,
+			index,
+			self.next_timestamp(),
+			0,
+			self.settings().validators_address(),
+			Vec::new(),
+		);
+		ApplicationEngine::new(
+			TriggerType::Application,
+			self.state.clone(),
+			Some(transaction),
+			block,
+			DEFAULT_GAS_LIMIT,
+		)
+	}
+
+	/// The timestamp of the next block, `ms_per_block` after the current one.
+	fn next_timestamp(&self) -> u64 {
+		self.state.current_block().map_or(GENESIS_TIMESTAMP, |block| {
+			block.timestamp + self.settings().ms_per_block as u64
+		})
+	}
+
+	/// A transaction valid for the next 100 blocks, using the next unused nonce.
+	fn new_transaction(&self, script: Vec<u8>, signers: Vec<Signer>) -> TransactionRecord {
+		TransactionRecord::new(self.next_nonce, self.height() + 100, signers, script)
+	}
+}
+
+fn call_script(
+	hash: &H160,
+	method: &str,
+	params: &[ContractParameter],
+) -> Result<Vec<u8>, VMError> {
+	let mut builder = ScriptBuilder::new();
+	builder
+		.contract_call(hash, method, params, None)
+		.map_err(|err| VMError::InvalidOperation(err.to_string()))?;
+	Ok(builder.to_bytes())
+}
+
+fn called_by_entry(account: &H160) -> Result<Signer, VMError> {
+	AccountSigner::called_by_entry_hash160(*account)
+		.map(Signer::AccountSigner)
+		.map_err(|err| VMError::InvalidOperation(err.to_string()))
+}
+
+/// Runs the native `OnPersist` or `PostPersist` logic for `block`.
+fn run_system_trigger(
+	trigger: TriggerType,
+	state: BlockchainState,
+	block: &BlockRecord,
+) -> Result<BlockchainState, VMError> {
+	let service = match trigger {
+		TriggerType::OnPersist => InteropService::SystemContractNativeOnPersist,
+		_ => InteropService::SystemContractNativePostPersist,
+	};
+	let script = ScriptBuilder::new().sys_call(service).to_bytes();
+	let mut engine = ApplicationEngine::new(trigger, state, None, block.clone(), 0);
+	engine.load_script(script)?;
+	if engine.execute() != crate::neo_types::VMState::Halt {
+		return Err(VMError::InvalidOperation(format!(
+			"{} failed: {}",
+			trigger,
+			engine.engine().result().exception.unwrap_or_default()
+		)));
+	}
+	Ok(engine.into_state())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::neo_types::{OpCode, StackItem};
+
+	/// A contract with `get(key)` and `put(key, value)` methods over its storage.
+	fn storage_contract() -> (NefFile, ContractManifest) {
+		let mut builder = ScriptBuilder::new();
+		builder
+			.sys_call(InteropService::SystemStorageGetContext)
+			.sys_call(InteropService::SystemStorageGet)
+			.op_code(&[OpCode::Ret])
+			.sys_call(InteropService::SystemStorageGetContext)
+			.sys_call(InteropService::SystemStoragePut)
+			.op_code(&[OpCode::Ret]);
+		let nef = NefFile::new("test", "", vec![], builder.to_bytes()).unwrap();
+		let manifest = serde_json::from_str(
+			r#"{
+				"name": "Store",
+				"groups": [],
+				"features": {},
+				"supportedstandards": [],
+				"abi": {
+					"methods": [
+						{
+							"name": "get",
+							"parameters": [{ "name": "key", "type": "ByteArray" }],
+							"offset": 0,
+							"returntype": "ByteArray",
+							"safe": true
+						},
+						{
+							"name": "put",
+							"parameters": [
+								{ "name": "key", "type": "ByteArray" },
+								{ "name": "value", "type": "ByteArray" }
+							],
+							"offset": 11,
+							"returntype": "Void",
+							"safe": false
+						}
+					],
+					"events": []
+				},
+				"permissions": [{ "contract": "*", "methods": ["*"] }],
+				"trusts": [],
+				"extra": null
+			}"#,
+		)
+		.unwrap();
+		(nef, manifest)
+	}
+
+	#[test]
+	fn test_genesis() {
+		let chain = LocalBlockchain::new();
+		let validators = chain.settings().validators_address();
+
+		assert_eq!(chain.height(), 0);
+		assert_eq!(
+			chain.balance_of(NativeContract::NeoToken, &validators),
+			BigInt::from(100_000_000)
+		);
+		assert_eq!(
+			chain.balance_of(NativeContract::GasToken, &validators),
+			BigInt::from(chain.settings().initial_gas_distribution)
+		);
+		for native in NativeContract::all() {
+			assert_eq!(
+				chain.contract(&native.hash()).map(|contract| contract.id),
+				Some(native.id())
+			);
+		}
+	}
+
+	#[test]
+	fn test_transfer() {
+		let mut chain = LocalBlockchain::new();
+		let validators = chain.settings().validators_address();
+		let alice = H160::repeat_byte(0xaa);
+
+		let result = chain.transfer(NativeContract::NeoToken, &validators, &alice, 10).unwrap();
+
+		assert_eq!(result.notifications.unwrap()[0].event_name, "Transfer");
+		assert_eq!(chain.balance_of(NativeContract::NeoToken, &alice), BigInt::from(10));
+		assert_eq!(chain.height(), 1);
+		// Alice didn't sign, so she can't send the tokens back
+		let result = chain
+			.invoke_function(
+				&NativeContract::NeoToken.hash(),
+				"transfer",
+				&[
+					ContractParameter::h160(&alice),
+					ContractParameter::h160(&validators),
+					ContractParameter::integer(10),
+					ContractParameter::any(),
+				],
+				vec![called_by_entry(&validators).unwrap()],
+			)
+			.unwrap();
+		assert_eq!(result.stack, vec![StackItem::Boolean { value: false }]);
+	}
+
+	#[test]
+	fn test_deploy_and_invoke() {
+		let mut chain = LocalBlockchain::new();
+		let sender = chain.settings().validators_address();
+		let (nef, manifest) = storage_contract();
+
+		let hash = chain.deploy(&nef, &manifest, &sender, None).unwrap();
+		let result = chain
+			.invoke_function(
+				&hash,
+				"put",
+				&[
+					ContractParameter::byte_array(b"key".to_vec()),
+					ContractParameter::byte_array(b"value".to_vec()),
+				],
+				vec![],
+			)
+			.unwrap();
+
+		assert_eq!(result.state, NeoVMStateType::Halt, "{:?}", result.exception);
+		assert_eq!(chain.contract(&hash).unwrap().id, 1);
+		assert_eq!(chain.storage(&hash, b"key"), Some(&b"value".to_vec()));
+		let result = chain.test_invoke_function(
+			&hash,
+			"get",
+			&[ContractParameter::byte_array(b"key".to_vec())],
+			vec![],
+		);
+		assert_eq!(result.stack[0].as_bytes(), Some(b"value".to_vec()));
+		assert!(chain.deploy(&nef, &manifest, &sender, None).is_err());
+	}
+
+	#[test]
+	fn test_committee_methods() {
+		let mut chain = LocalBlockchain::new();
+		let committee = chain.settings().committee_address();
+		let policy = NativeContract::PolicyContract.hash();
+		let params = [ContractParameter::integer(2000)];
+
+		let result = chain.invoke_function(&policy, "setFeePerByte", &params, vec![]).unwrap();
+		assert_eq!(result.state, NeoVMStateType::Fault);
+		let signers = vec![called_by_entry(&committee).unwrap()];
+		let result = chain.invoke_function(&policy, "setFeePerByte", &params, signers).unwrap();
+		assert_eq!(result.state, NeoVMStateType::Halt, "{:?}", result.exception);
+
+		let result = chain.test_invoke_function(&policy, "getFeePerByte", &[], vec![]);
+		assert_eq!(result.stack[0].as_int(), Some(2000));
+	}
+
+	#[test]
+	fn test_library_contracts() {
+		let chain = LocalBlockchain::new();
+		let std_lib = NativeContract::StdLib.hash();
+
+		let result = chain.test_invoke_function(
+			&std_lib,
+			"itoa",
+			&[ContractParameter::integer(255), ContractParameter::integer(16)],
+			vec![],
+		);
+		assert_eq!(result.stack[0].as_string(), Some("0ff".to_string()));
+		let result = chain.test_invoke_function(
+			&NativeContract::CryptoLib.hash(),
+			"sha256",
+			&[ContractParameter::byte_array(b"abc".to_vec())],
+			vec![],
+		);
+		assert_eq!(
+			hex::encode(result.stack[0].as_bytes().unwrap()),
+			"ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+		);
+	}
+}
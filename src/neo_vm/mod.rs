@@ -0,0 +1,80 @@
+//! # Neo VM Module
+//!
+//! A pure-Rust implementation of the NeoVM for executing scripts locally.
+//!
+//! ## Overview
+//!
+//! The neo_vm module runs scripts produced by the
+//! [`ScriptBuilder`](crate::neo_builder::ScriptBuilder) without a node. It provides:
+//!
+//! - **Execution Engine**: Evaluation stacks, slots, `CALL`/`RET` and `TRY`/`CATCH`/`FINALLY`
+//! - **Full Instruction Set**: Every NeoVM opcode, with the same limits as Neo N3 nodes
+//! - **GAS Accounting**: Opcode prices multiplied by the execution fee factor
+//! - **Interop Hosts**: A trait for plugging `SYSCALL` and `CALLT` handlers into the engine
+//! - **Application Engine**: The `System.*` interop services, with storage, notifications,
+//!   witness checks and contract calls
+//! - **Native Contracts**: Emulated ContractManagement, StdLib, CryptoLib, Ledger, NEO, GAS,
+//!   Policy and RoleManagement contracts
+//! - **Local Chain**: An in-memory blockchain to deploy and invoke contracts without a node
+//! - **Forking**: A local chain continuing from a block of MainNet or TestNet, loading contracts
+//!   and storage on demand through the StateService RPC methods
+//! - **Debugger**: Breakpoints, stepping and variable inspection at the source level, driven by
+//!   the `.nefdbgnfo` debug info of compilers
+//! - **Profiler**: GAS consumed per opcode, interop service, contract and method, with code
+//!   coverage reports in the lcov format
+//!
+//! ## Example
+//!
+//! ```rust
+//! use neo3::neo_builder::ScriptBuilder;
+//! use neo3::neo_types::{OpCode, StackItem, VMState};
+//! use neo3::neo_vm::ExecutionEngine;
+//! use num_bigint::BigInt;
+//!
+//! let script = ScriptBuilder::new()
+//!     .push_integer(BigInt::from(40))
+//!     .push_integer(BigInt::from(2))
+//!     .op_code(&[OpCode::Add])
+//!     .to_bytes();
+//!
+//! let result = ExecutionEngine::run(script);
+//! assert_eq!(result.state, VMState::Halt);
+//! assert_eq!(result.stack, vec![StackItem::Integer { value: 42 }]);
+//! println!("GAS consumed: {}", result.gas_consumed);
+//! ```
+
+pub use application_engine::*;
+pub use binary_serializer::*;
+pub use blockchain_state::*;
+pub use debug_info::*;
+pub use debugger::*;
+pub use evaluation_stack::*;
+pub use execution_context::*;
+pub use execution_engine::*;
+pub use forked_blockchain::*;
+pub use instruction::*;
+pub use local_blockchain::*;
+pub use native::{contract_hash, NativeContract};
+pub use profiler::*;
+pub use reference_counter::*;
+pub use slot::*;
+pub use vm_error::*;
+pub use vm_stack_item::*;
+
+mod application_engine;
+mod binary_serializer;
+mod blockchain_state;
+mod debug_info;
+mod debugger;
+mod evaluation_stack;
+mod execution_context;
+mod execution_engine;
+mod forked_blockchain;
+mod instruction;
+mod local_blockchain;
+mod native;
+mod profiler;
+mod reference_counter;
+mod slot;
+mod vm_error;
+mod vm_stack_item;
@@ -0,0 +1,695 @@
+use std::str::FromStr;
+
+use base64::{engine::general_purpose, Engine};
+use num_bigint::BigInt;
+use num_traits::{Signed, ToPrimitive};
+use primitive_types::H160;
+
+use crate::{
+	builder::CallFlags,
+	codec::NeoSerializable,
+	crypto::{Secp256r1PublicKey, Secp256r1Signature},
+	neo_types::{
+		ContractABI, ContractEvent, ContractGroup, ContractManifest, ContractMethod,
+		ContractParameter, ContractParameter2, ContractParameterType, ContractPermission,
+		ContractState, NefFile,
+	},
+	neo_vm::{
+		native::{
+			check_committee, contract_hash, contract_nef, hash160_bytes, hash160_from_item,
+			hash160_item, nef_file, policy_contract, NativeContract, NativeMethod,
+		},
+		ApplicationHost, ExecutionEngine, FindOptions, StorageIterator, VMError, VMStackItem,
+	},
+};
+
+const PREFIX_CONTRACT: u8 = 8;
+const PREFIX_NEXT_AVAILABLE_ID: u8 = 15;
+const PREFIX_CONTRACT_HASH: u8 = 12;
+const PREFIX_MINIMUM_DEPLOYMENT_FEE: u8 = 20;
+const MAX_MANIFEST_SIZE: usize = u16::MAX as usize;
+
+const ID: i32 = -1;
+
+pub(super) fn methods() -> Vec<NativeMethod> {
+	type P = ContractParameterType;
+	let deploy_flags = CallFlags::States;
+	vec![
+		NativeMethod::new(
+			"getMinimumDeploymentFee",
+			vec![],
+			P::Integer,
+			1 << 15,
+			CallFlags::ReadStates,
+			get_minimum_deployment_fee,
+		),
+		NativeMethod::new(
+			"setMinimumDeploymentFee",
+			vec![("value", P::Integer)],
+			P::Void,
+			1 << 15,
+			CallFlags::States,
+			set_minimum_deployment_fee,
+		),
+		NativeMethod::new(
+			"getContract",
+			vec![("hash", P::H160)],
+			P::Array,
+			1 << 15,
+			CallFlags::ReadStates,
+			get_contract,
+		),
+		NativeMethod::new(
+			"getContractById",
+			vec![("id", P::Integer)],
+			P::Array,
+			1 << 15,
+			CallFlags::ReadStates,
+			get_contract_by_id,
+		),
+		NativeMethod::new(
+			"getContractHashes",
+			vec![],
+			P::InteropInterface,
+			1 << 15,
+			CallFlags::ReadStates,
+			get_contract_hashes,
+		),
+		NativeMethod::new(
+			"hasMethod",
+			vec![("hash", P::H160), ("method", P::String), ("pcount", P::Integer)],
+			P::Boolean,
+			1 << 15,
+			CallFlags::ReadStates,
+			has_method,
+		),
+		NativeMethod::new(
+			"deploy",
+			vec![("nefFile", P::ByteArray), ("manifest", P::ByteArray)],
+			P::Array,
+			0,
+			deploy_flags,
+			deploy,
+		)
+		.with_flags(CallFlags::AllowNotify),
+		NativeMethod::new(
+			"deploy",
+			vec![("nefFile", P::ByteArray), ("manifest", P::ByteArray), ("data", P::Any)],
+			P::Array,
+			0,
+			deploy_flags,
+			deploy,
+		)
+		.with_flags(CallFlags::AllowNotify),
+		NativeMethod::new(
+			"update",
+			vec![("nefFile", P::ByteArray), ("manifest", P::ByteArray)],
+			P::Void,
+			0,
+			deploy_flags,
+			update,
+		)
+		.with_flags(CallFlags::AllowNotify),
+		NativeMethod::new(
+			"update",
+			vec![("nefFile", P::ByteArray), ("manifest", P::ByteArray), ("data", P::Any)],
+			P::Void,
+			0,
+			deploy_flags,
+			update,
+		)
+		.with_flags(CallFlags::AllowNotify),
+		NativeMethod::new("destroy", vec![], P::Void, 1 << 15, deploy_flags, destroy)
+			.with_flags(CallFlags::AllowNotify),
+	]
+}
+
+pub(super) fn initialize(host: &mut ApplicationHost) -> Result<(), VMError> {
+	host.put_integer(ID, &[PREFIX_MINIMUM_DEPLOYMENT_FEE], &BigInt::from(10_0000_0000i64));
+	host.put_integer(ID, &[PREFIX_NEXT_AVAILABLE_ID], &BigInt::from(1));
+	Ok(())
+}
+
+/// Converts a contract to the stack item returned by `getContract`.
+pub(crate) fn contract_item(contract: &ContractState) -> Result<VMStackItem, VMError> {
+	let nef = nef_file(&contract.nef)?;
+	Ok(VMStackItem::new_array(vec![
+		VMStackItem::from(contract.id as i64),
+		VMStackItem::from(contract.update_counter as i64),
+		hash160_item(&contract.hash),
+		VMStackItem::ByteString(nef.to_array()),
+		manifest_item(&contract.manifest)?,
+	]))
+}
+
+/// The key of the state of a contract in the storage of ContractManagement on a node.
+pub(crate) fn contract_key(hash: &H160) -> Vec<u8> {
+	let mut key = vec![PREFIX_CONTRACT];
+	key.extend(hash160_bytes(hash));
+	key
+}
+
+/// The key of the hash of a contract in the storage of ContractManagement on a node.
+pub(crate) fn contract_hash_key(id: i32) -> Vec<u8> {
+	let mut key = vec![PREFIX_CONTRACT_HASH];
+	key.extend_from_slice(&id.to_be_bytes());
+	key
+}
+
+/// Converts the stack item form of a contract, as stored by a node, back to a contract state.
+pub(crate) fn contract_from_item(item: &VMStackItem) -> Result<ContractState, VMError> {
+	let fields = fields(item, 5)?;
+	let id = fields[0]
+		.get_integer()?
+		.to_i32()
+		.ok_or_else(|| VMError::InvalidOperation("Invalid contract id".to_string()))?;
+	let update_counter = fields[1]
+		.get_integer()?
+		.to_i32()
+		.ok_or_else(|| VMError::InvalidOperation("Invalid update counter".to_string()))?;
+	let nef = NefFile::deserialize(&fields[3].get_span()?)
+		.map_err(|err| VMError::InvalidOperation(format!("Invalid NEF: {}", err)))?;
+	Ok(ContractState::new(
+		id,
+		update_counter,
+		hash160_from_item(&fields[2])?,
+		contract_nef(&nef),
+		manifest_from_item(&fields[4])?,
+	))
+}
+
+/// The fields of an array or struct, which must have `count` of them.
+fn fields(item: &VMStackItem, count: usize) -> Result<Vec<VMStackItem>, VMError> {
+	match item {
+		VMStackItem::Array(fields) | VMStackItem::Struct(fields)
+			if fields.borrow().len() == count =>
+		{
+			Ok(fields.borrow().clone())
+		},
+		other => Err(VMError::InvalidCast(format!(
+			"Expected {} fields, got {}",
+			count,
+			other.item_type()
+		))),
+	}
+}
+
+fn manifest_from_item(item: &VMStackItem) -> Result<ContractManifest, VMError> {
+	let list = |item: &VMStackItem| -> Result<Vec<VMStackItem>, VMError> {
+		match item {
+			VMStackItem::Array(items) | VMStackItem::Struct(items) => Ok(items.borrow().clone()),
+			other => Err(VMError::InvalidCast(format!("{} is not an Array", other.item_type()))),
+		}
+	};
+	let parameter_type = |item: &VMStackItem| {
+		item.get_integer()?
+			.to_u8()
+			.and_then(|typ| ContractParameterType::try_from(typ).ok())
+			.ok_or_else(|| VMError::InvalidOperation("Invalid parameter type".to_string()))
+	};
+	let parameters = |item: &VMStackItem| {
+		list(item)?
+			.iter()
+			.map(|parameter| {
+				let fields = fields(parameter, 2)?;
+				Ok((fields[0].get_string()?, parameter_type(&fields[1])?))
+			})
+			.collect::<Result<Vec<_>, VMError>>()
+	};
+	let descriptor = |item: &VMStackItem| -> Result<String, VMError> {
+		let bytes = item.get_span()?;
+		match bytes.len() {
+			33 => Ok(hex::encode(bytes)),
+			_ => Ok(format!("0x{}", hex::encode(hash160_from_item(item)?.0))),
+		}
+	};
+	let fields = fields(item, 8)?;
+	let groups = list(&fields[1])?
+		.iter()
+		.map(|group| {
+			let group = self::fields(group, 2)?;
+			Ok(ContractGroup {
+				pub_key: hex::encode(group[0].get_span()?),
+				signature: general_purpose::STANDARD.encode(group[1].get_span()?),
+			})
+		})
+		.collect::<Result<Vec<_>, VMError>>()?;
+	let supported_standards = list(&fields[3])?
+		.iter()
+		.map(VMStackItem::get_string)
+		.collect::<Result<Vec<_>, _>>()?;
+	let abi = self::fields(&fields[4], 2)?;
+	let methods = list(&abi[0])?
+		.iter()
+		.map(|method| {
+			let method = self::fields(method, 5)?;
+			let parameters = parameters(&method[1])?
+				.into_iter()
+				.map(|(name, typ)| ContractParameter2::new(name, typ))
+				.collect();
+			let offset = method[3]
+				.get_integer()?
+				.to_usize()
+				.ok_or_else(|| VMError::InvalidOperation("Invalid method offset".to_string()))?;
+			Ok(ContractMethod::new(
+				method[0].get_string()?,
+				Some(parameters),
+				offset,
+				parameter_type(&method[2])?,
+				method[4].get_boolean()?,
+			))
+		})
+		.collect::<Result<Vec<_>, VMError>>()?;
+	let events = list(&abi[1])?
+		.iter()
+		.map(|event| {
+			let event = self::fields(event, 2)?;
+			let parameters = parameters(&event[1])?
+				.into_iter()
+				.map(|(name, typ)| ContractParameter::new(typ).with_name(name))
+				.collect();
+			Ok(ContractEvent { name: event[0].get_string()?, parameters })
+		})
+		.collect::<Result<Vec<_>, VMError>>()?;
+	let permissions = list(&fields[5])?
+		.iter()
+		.map(|permission| {
+			let permission = self::fields(permission, 2)?;
+			let contract = match &permission[0] {
+				VMStackItem::Null => "*".to_string(),
+				item => descriptor(item)?,
+			};
+			let methods = match &permission[1] {
+				VMStackItem::Null => vec!["*".to_string()],
+				item => {
+					list(item)?.iter().map(VMStackItem::get_string).collect::<Result<_, _>>()?
+				},
+			};
+			Ok(ContractPermission::new(contract, methods))
+		})
+		.collect::<Result<Vec<_>, VMError>>()?;
+	let trusts = match &fields[6] {
+		VMStackItem::Null => vec!["*".to_string()],
+		item => list(item)?.iter().map(descriptor).collect::<Result<_, _>>()?,
+	};
+	let extra = serde_json::from_str(&fields[7].get_string()?)
+		.map_err(|err| VMError::InvalidOperation(format!("Invalid manifest extra: {}", err)))?;
+	Ok(ContractManifest::new(
+		Some(fields[0].get_string()?),
+		groups,
+		None,
+		supported_standards,
+		Some(ContractABI::new(Some(methods), Some(events))),
+		permissions,
+		trusts,
+		extra,
+	))
+}
+
+fn manifest_item(manifest: &ContractManifest) -> Result<VMStackItem, VMError> {
+	let string = |value: &str| VMStackItem::from(value);
+	let parameter = |name: &str, typ: &ContractParameterType| {
+		VMStackItem::new_struct(vec![string(name), VMStackItem::from(*typ as u8 as i64)])
+	};
+	let groups = manifest
+		.groups
+		.iter()
+		.map(|group| {
+			let public_key = hex::decode(group.pub_key.trim_start_matches("0x"))
+				.map_err(|err| VMError::InvalidOperation(format!("Invalid group key: {}", err)))?;
+			let signature = general_purpose::STANDARD.decode(&group.signature).map_err(|err| {
+				VMError::InvalidOperation(format!("Invalid group signature: {}", err))
+			})?;
+			Ok(VMStackItem::new_struct(vec![
+				VMStackItem::ByteString(public_key),
+				VMStackItem::ByteString(signature),
+			]))
+		})
+		.collect::<Result<Vec<_>, VMError>>()?;
+	let (abi_methods, abi_events) = match &manifest.abi {
+		Some(abi) => (abi.methods.as_slice(), abi.events.as_slice()),
+		None => (&[][..], &[][..]),
+	};
+	let methods = abi_methods
+		.iter()
+		.map(|method| {
+			VMStackItem::new_struct(vec![
+				string(&method.name),
+				VMStackItem::new_array(
+					method.parameters.iter().map(|p| parameter(&p.name, &p.typ)).collect(),
+				),
+				VMStackItem::from(method.return_type as u8 as i64),
+				VMStackItem::from(method.offset as i64),
+				VMStackItem::Boolean(method.safe),
+			])
+		})
+		.collect();
+	let events = abi_events
+		.iter()
+		.map(|event| {
+			VMStackItem::new_struct(vec![
+				string(&event.name),
+				VMStackItem::new_array(
+					event
+						.parameters
+						.iter()
+						.map(|p| parameter(p.name().as_deref().unwrap_or_default(), p.typ()))
+						.collect(),
+				),
+			])
+		})
+		.collect();
+	let permissions = manifest
+		.permissions
+		.iter()
+		.map(|permission| {
+			let contract = match permission.contract.as_str() {
+				"*" => VMStackItem::Null,
+				descriptor => VMStackItem::ByteString(descriptor_bytes(descriptor)?),
+			};
+			let methods = if permission.methods.iter().any(|method| method == "*") {
+				VMStackItem::Null
+			} else {
+				VMStackItem::new_array(permission.methods.iter().map(|m| string(m)).collect())
+			};
+			Ok(VMStackItem::new_struct(vec![contract, methods]))
+		})
+		.collect::<Result<Vec<_>, VMError>>()?;
+	let trusts = if manifest.trusts.iter().any(|trust| trust == "*") {
+		VMStackItem::Null
+	} else {
+		VMStackItem::new_array(
+			manifest
+				.trusts
+				.iter()
+				.map(|trust| descriptor_bytes(trust).map(VMStackItem::ByteString))
+				.collect::<Result<Vec<_>, VMError>>()?,
+		)
+	};
+	let extra = match &manifest.extra {
+		Some(extra) => serde_json::to_string(extra)
+			.map_err(|err| VMError::InvalidOperation(err.to_string()))?,
+		None => "null".to_string(),
+	};
+	Ok(VMStackItem::new_struct(vec![
+		string(manifest.name.as_deref().unwrap_or_default()),
+		VMStackItem::new_array(groups),
+		VMStackItem::new_map(),
+		VMStackItem::new_array(
+			manifest.supported_standards.iter().map(|standard| string(standard)).collect(),
+		),
+		VMStackItem::new_struct(vec![
+			VMStackItem::new_array(methods),
+			VMStackItem::new_array(events),
+		]),
+		VMStackItem::new_array(permissions),
+		trusts,
+		string(&extra),
+	]))
+}
+
+/// The bytes of a permission or trust descriptor: a contract hash or a group public key.
+fn descriptor_bytes(descriptor: &str) -> Result<Vec<u8>, VMError> {
+	if descriptor.trim_start_matches("0x").len() == 66 {
+		return hex::decode(descriptor)
+			.map_err(|err| VMError::InvalidOperation(format!("Invalid group key: {}", err)));
+	}
+	H160::from_str(descriptor).map(|hash| hash160_bytes(&hash)).map_err(|_| {
+		VMError::InvalidOperation(format!("Invalid contract descriptor {}", descriptor))
+	})
+}
+
+fn get_minimum_deployment_fee(
+	host: &mut ApplicationHost,
+	_engine: &mut ExecutionEngine,
+	_args: Vec<VMStackItem>,
+) -> Result<VMStackItem, VMError> {
+	Ok(VMStackItem::Integer(minimum_deployment_fee(host)))
+}
+
+fn minimum_deployment_fee(host: &ApplicationHost) -> BigInt {
+	host.get_integer(ID, &[PREFIX_MINIMUM_DEPLOYMENT_FEE]).unwrap_or_default()
+}
+
+fn set_minimum_deployment_fee(
+	host: &mut ApplicationHost,
+	engine: &mut ExecutionEngine,
+	args: Vec<VMStackItem>,
+) -> Result<VMStackItem, VMError> {
+	let value = args[0].get_integer()?;
+	if value.is_negative() {
+		return Err(VMError::InvalidOperation("The fee can't be negative".to_string()));
+	}
+	check_committee(host, engine)?;
+	host.put_integer(ID, &[PREFIX_MINIMUM_DEPLOYMENT_FEE], &value);
+	Ok(VMStackItem::Null)
+}
+
+fn get_contract(
+	host: &mut ApplicationHost,
+	_engine: &mut ExecutionEngine,
+	args: Vec<VMStackItem>,
+) -> Result<VMStackItem, VMError> {
+	let hash = hash160_from_item(&args[0])?;
+	host.state.contract(&hash).map_or(Ok(VMStackItem::Null), contract_item)
+}
+
+fn get_contract_by_id(
+	host: &mut ApplicationHost,
+	_engine: &mut ExecutionEngine,
+	args: Vec<VMStackItem>,
+) -> Result<VMStackItem, VMError> {
+	let id = args[0].get_integer()?.to_i32();
+	let contract = id.and_then(|id| host.state.contract_by_id(id));
+	contract.map_or(Ok(VMStackItem::Null), contract_item)
+}
+
+fn get_contract_hashes(
+	host: &mut ApplicationHost,
+	_engine: &mut ExecutionEngine,
+	_args: Vec<VMStackItem>,
+) -> Result<VMStackItem, VMError> {
+	let entries = host
+		.state
+		.contracts()
+		.filter(|contract| contract.id >= 0)
+		.map(|contract| {
+			let mut key = vec![PREFIX_CONTRACT_HASH];
+			key.extend_from_slice(&contract.id.to_be_bytes());
+			(key, hash160_bytes(&contract.hash))
+		})
+		.collect();
+	Ok(VMStackItem::new_interop(StorageIterator::new(entries, 1, FindOptions::REMOVE_PREFIX)))
+}
+
+fn has_method(
+	host: &mut ApplicationHost,
+	_engine: &mut ExecutionEngine,
+	args: Vec<VMStackItem>,
+) -> Result<VMStackItem, VMError> {
+	let hash = hash160_from_item(&args[0])?;
+	let method = args[1].get_string()?;
+	let count = args[2].get_integer()?;
+	let found = host
+		.state
+		.contract(&hash)
+		.and_then(|contract| contract.manifest.abi.as_ref())
+		.is_some_and(|abi| {
+			abi.methods.iter().any(|m| {
+				m.name == method
+					&& (count == BigInt::from(-1) || count == BigInt::from(m.parameters.len()))
+			})
+		});
+	Ok(VMStackItem::Boolean(found))
+}
+
+fn deploy(
+	host: &mut ApplicationHost,
+	engine: &mut ExecutionEngine,
+	args: Vec<VMStackItem>,
+) -> Result<VMStackItem, VMError> {
+	let nef_bytes = args[0].get_span()?;
+	let manifest_bytes = args[1].get_span()?;
+	let data = args.get(2).cloned().unwrap_or(VMStackItem::Null);
+	let sender = host
+		.container
+		.as_ref()
+		.and_then(|transaction| transaction.sender())
+		.ok_or_else(|| {
+			VMError::InvalidOperation("Contracts can only be deployed by transactions".to_string())
+		})?;
+	if nef_bytes.is_empty() || manifest_bytes.is_empty() || manifest_bytes.len() > MAX_MANIFEST_SIZE
+	{
+		return Err(VMError::InvalidOperation("Invalid NEF or manifest length".to_string()));
+	}
+	let fee = BigInt::from(host.storage_price * (nef_bytes.len() + manifest_bytes.len()) as i64)
+		.max(minimum_deployment_fee(host));
+	engine.add_gas(fee.to_i64().unwrap_or(i64::MAX))?;
+
+	let nef = NefFile::deserialize(&nef_bytes)
+		.map_err(|err| VMError::InvalidOperation(format!("Invalid NEF: {}", err)))?;
+	let manifest = parse_manifest(&manifest_bytes)?;
+	let name = manifest.name.clone().unwrap_or_default();
+	let hash = contract_hash(&sender, nef.checksum(), &name);
+	if policy_contract::is_blocked(&host.state, &hash) {
+		return Err(VMError::InvalidOperation(format!("The contract {:?} has been blocked", hash)));
+	}
+	if host.state.contract(&hash).is_some() {
+		return Err(VMError::InvalidOperation(format!("Contract already exists: {:?}", hash)));
+	}
+	check_manifest(&manifest, &hash, &nef)?;
+
+	let id = host.get_integer(ID, &[PREFIX_NEXT_AVAILABLE_ID]).unwrap_or_default();
+	host.put_integer(ID, &[PREFIX_NEXT_AVAILABLE_ID], &(&id + 1));
+	let id = id
+		.to_i32()
+		.ok_or_else(|| VMError::InvalidOperation("No ids left".to_string()))?;
+	let contract = ContractState::new(id, 0, hash, contract_nef(&nef), manifest);
+	host.state.put_contract(contract.clone());
+	on_deploy(host, &contract, data, false);
+	contract_item(&contract)
+}
+
+fn update(
+	host: &mut ApplicationHost,
+	engine: &mut ExecutionEngine,
+	args: Vec<VMStackItem>,
+) -> Result<VMStackItem, VMError> {
+	let nef_bytes = match &args[0] {
+		VMStackItem::Null => None,
+		item => Some(item.get_span()?),
+	};
+	let manifest_bytes = match &args[1] {
+		VMStackItem::Null => None,
+		item => Some(item.get_span()?),
+	};
+	let data = args.get(2).cloned().unwrap_or(VMStackItem::Null);
+	if nef_bytes.is_none() && manifest_bytes.is_none() {
+		return Err(VMError::InvalidOperation("Nothing to update".to_string()));
+	}
+	let size = nef_bytes.as_ref().map_or(0, Vec::len) + manifest_bytes.as_ref().map_or(0, Vec::len);
+	engine.add_gas(host.storage_price * size as i64)?;
+
+	let hash = ApplicationHost::calling_script_hash(engine).ok_or_else(|| {
+		VMError::InvalidOperation("Update must be called by a contract".to_string())
+	})?;
+	let mut contract = host.state.contract(&hash).cloned().ok_or_else(|| {
+		VMError::InvalidOperation("Can't update a contract that does not exist".to_string())
+	})?;
+	if contract.update_counter == u16::MAX as i32 {
+		return Err(VMError::InvalidOperation(
+			"The contract reached the maximum update count".to_string(),
+		));
+	}
+	if let Some(nef_bytes) = nef_bytes {
+		if nef_bytes.is_empty() {
+			return Err(VMError::InvalidOperation("The NEF can't be empty".to_string()));
+		}
+		let nef = NefFile::deserialize(&nef_bytes)
+			.map_err(|err| VMError::InvalidOperation(format!("Invalid NEF: {}", err)))?;
+		contract.nef = contract_nef(&nef);
+	}
+	if let Some(manifest_bytes) = manifest_bytes {
+		if manifest_bytes.is_empty() || manifest_bytes.len() > MAX_MANIFEST_SIZE {
+			return Err(VMError::InvalidOperation("Invalid manifest length".to_string()));
+		}
+		let manifest = parse_manifest(&manifest_bytes)?;
+		if manifest.name != contract.manifest.name {
+			return Err(VMError::InvalidOperation(
+				"The name of the contract can't be changed".to_string(),
+			));
+		}
+		contract.manifest = manifest;
+	}
+	check_manifest(&contract.manifest, &hash, &nef_file(&contract.nef)?)?;
+	contract.update_counter += 1;
+	host.state.put_contract(contract.clone());
+	on_deploy(host, &contract, data, true);
+	Ok(VMStackItem::Null)
+}
+
+fn destroy(
+	host: &mut ApplicationHost,
+	engine: &mut ExecutionEngine,
+	_args: Vec<VMStackItem>,
+) -> Result<VMStackItem, VMError> {
+	let hash = ApplicationHost::calling_script_hash(engine).ok_or_else(|| {
+		VMError::InvalidOperation("Destroy must be called by a contract".to_string())
+	})?;
+	if host.state.contract(&hash).is_none() {
+		return Ok(VMStackItem::Null);
+	}
+	host.state.delete_contract(&hash);
+	policy_contract::block_account(host, &hash);
+	host.notify(NativeContract::ContractManagement.hash(), "Destroy", vec![hash160_item(&hash)]);
+	Ok(VMStackItem::Null)
+}
+
+/// Calls `_deploy` if the contract has it, then sends the `Deploy` or `Update` notification.
+fn on_deploy(
+	host: &mut ApplicationHost,
+	contract: &ContractState,
+	data: VMStackItem,
+	update: bool,
+) {
+	let has_deploy = contract.manifest.abi.as_ref().is_some_and(|abi| {
+		abi.methods
+			.iter()
+			.any(|method| method.name == "_deploy" && method.parameters.len() == 2)
+	});
+	if has_deploy {
+		host.call_from_native(
+			NativeContract::ContractManagement,
+			contract.hash,
+			"_deploy",
+			vec![data, VMStackItem::Boolean(update)],
+		);
+	}
+	let event = if update { "Update" } else { "Deploy" };
+	host.notify(
+		NativeContract::ContractManagement.hash(),
+		event,
+		vec![hash160_item(&contract.hash)],
+	);
+}
+
+fn parse_manifest(bytes: &[u8]) -> Result<ContractManifest, VMError> {
+	serde_json::from_slice(bytes)
+		.map_err(|err| VMError::InvalidOperation(format!("Invalid manifest: {}", err)))
+}
+
+/// Checks the group signatures of a manifest and that its ABI matches the script.
+fn check_manifest(manifest: &ContractManifest, hash: &H160, nef: &NefFile) -> Result<(), VMError> {
+	if manifest.name.as_deref().unwrap_or_default().is_empty() {
+		return Err(VMError::InvalidOperation("The manifest has no name".to_string()));
+	}
+	for group in &manifest.groups {
+		let public_key = Secp256r1PublicKey::from_encoded(&group.pub_key);
+		let signature = general_purpose::STANDARD
+			.decode(&group.signature)
+			.ok()
+			.and_then(|signature| Secp256r1Signature::from_bytes(&signature).ok());
+		let valid = public_key
+			.zip(signature)
+			.is_some_and(|(key, signature)| key.verify(&hash160_bytes(hash), &signature).is_ok());
+		if !valid {
+			return Err(VMError::InvalidOperation(format!(
+				"Invalid signature of group {}",
+				group.pub_key
+			)));
+		}
+	}
+	let abi = manifest
+		.abi
+		.as_ref()
+		.ok_or_else(|| VMError::InvalidOperation("The manifest has no ABI".to_string()))?;
+	for method in &abi.methods {
+		if method.offset >= nef.script().len() {
+			return Err(VMError::InvalidOperation(format!(
+				"The offset of method {} is outside of the script",
+				method.name
+			)));
+		}
+	}
+	Ok(())
+}
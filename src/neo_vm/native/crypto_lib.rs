@@ -0,0 +1,157 @@
+use num_traits::ToPrimitive;
+use p256::ecdsa::signature::hazmat::PrehashVerifier;
+use sha2::{Digest, Sha256};
+use sha3::Keccak256;
+
+use crate::{
+	builder::CallFlags,
+	crypto::HashableForVec,
+	neo_types::ContractParameterType,
+	neo_vm::{native::NativeMethod, VMError, VMStackItem},
+};
+
+/// The curve and hash of `verifyWithECDsa`, as defined by `NamedCurveHash` in C#.
+const SECP256K1_SHA256: u8 = 22;
+const SECP256R1_SHA256: u8 = 23;
+const SECP256K1_KECCAK256: u8 = 122;
+const SECP256R1_KECCAK256: u8 = 123;
+
+pub(super) fn methods() -> Vec<NativeMethod> {
+	type P = ContractParameterType;
+	vec![
+		NativeMethod::new(
+			"sha256",
+			vec![("data", P::ByteArray)],
+			P::ByteArray,
+			1 << 15,
+			CallFlags::None,
+			|_, _, args| Ok(VMStackItem::ByteString(args[0].get_span()?.hash256())),
+		),
+		NativeMethod::new(
+			"ripemd160",
+			vec![("data", P::ByteArray)],
+			P::ByteArray,
+			1 << 15,
+			CallFlags::None,
+			|_, _, args| Ok(VMStackItem::ByteString(args[0].get_span()?.ripemd160())),
+		),
+		NativeMethod::new(
+			"keccak256",
+			vec![("data", P::ByteArray)],
+			P::ByteArray,
+			1 << 15,
+			CallFlags::None,
+			|_, _, args| {
+				Ok(VMStackItem::ByteString(Keccak256::digest(args[0].get_span()?).to_vec()))
+			},
+		),
+		NativeMethod::new(
+			"murmur32",
+			vec![("data", P::ByteArray), ("seed", P::Integer)],
+			P::ByteArray,
+			1 << 13,
+			CallFlags::None,
+			|_, _, args| {
+				let seed = args[1]
+					.get_integer()?
+					.to_u32()
+					.ok_or_else(|| VMError::InvalidOperation("Invalid seed".to_string()))?;
+				Ok(VMStackItem::ByteString(
+					murmur32(&args[0].get_span()?, seed).to_le_bytes().to_vec(),
+				))
+			},
+		),
+		NativeMethod::new(
+			"verifyWithECDsa",
+			vec![
+				("message", P::ByteArray),
+				("pubkey", P::ByteArray),
+				("signature", P::ByteArray),
+				("curveHash", P::Integer),
+			],
+			P::Boolean,
+			1 << 15,
+			CallFlags::None,
+			|_, _, args| {
+				let curve = args[3]
+					.get_integer()?
+					.to_u8()
+					.ok_or_else(|| VMError::InvalidOperation("Invalid curve".to_string()))?;
+				let (message, public_key, signature) =
+					(args[0].get_span()?, args[1].get_span()?, args[2].get_span()?);
+				Ok(VMStackItem::Boolean(verify_with_ecdsa(
+					&message,
+					&public_key,
+					&signature,
+					curve,
+				)?))
+			},
+		),
+	]
+}
+
+fn verify_with_ecdsa(
+	message: &[u8],
+	public_key: &[u8],
+	signature: &[u8],
+	curve: u8,
+) -> Result<bool, VMError> {
+	let digest: [u8; 32] = match curve {
+		SECP256K1_SHA256 | SECP256R1_SHA256 => Sha256::digest(message).into(),
+		SECP256K1_KECCAK256 | SECP256R1_KECCAK256 => Keccak256::digest(message).into(),
+		_ => return Err(VMError::InvalidOperation(format!("Unsupported curve {}", curve))),
+	};
+	Ok(match curve {
+		SECP256K1_SHA256 | SECP256K1_KECCAK256 => {
+			let key = k256::ecdsa::VerifyingKey::from_sec1_bytes(public_key);
+			let signature = k256::ecdsa::Signature::from_slice(signature);
+			match (key, signature) {
+				(Ok(key), Ok(signature)) => key.verify_prehash(&digest, &signature).is_ok(),
+				_ => false,
+			}
+		},
+		_ => {
+			let key = p256::ecdsa::VerifyingKey::from_sec1_bytes(public_key);
+			let signature = p256::ecdsa::Signature::from_slice(signature);
+			match (key, signature) {
+				(Ok(key), Ok(signature)) => key.verify_prehash(&digest, &signature).is_ok(),
+				_ => false,
+			}
+		},
+	})
+}
+
+/// MurmurHash3 x86 32-bit.
+pub(crate) fn murmur32(data: &[u8], seed: u32) -> u32 {
+	const C1: u32 = 0xcc9e_2d51;
+	const C2: u32 = 0x1b87_3593;
+	let mut hash = seed;
+	let (chunks, tail) = data.as_chunks::<4>();
+	for chunk in chunks {
+		let k = u32::from_le_bytes(*chunk);
+		hash ^= k.wrapping_mul(C1).rotate_left(15).wrapping_mul(C2);
+		hash = hash.rotate_left(13).wrapping_mul(5).wrapping_add(0xe654_6b64);
+	}
+	if !tail.is_empty() {
+		let k = tail.iter().rev().fold(0u32, |k, byte| (k << 8) | *byte as u32);
+		hash ^= k.wrapping_mul(C1).rotate_left(15).wrapping_mul(C2);
+	}
+	hash ^= data.len() as u32;
+	hash ^= hash >> 16;
+	hash = hash.wrapping_mul(0x85eb_ca6b);
+	hash ^= hash >> 13;
+	hash = hash.wrapping_mul(0xc2b2_ae35);
+	hash ^ (hash >> 16)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_murmur32() {
+		assert_eq!(murmur32(b"hello worldhello world", 0), 0x84bc7024);
+		assert_eq!(murmur32(b"", 0), 0);
+		assert_eq!(murmur32(b"test", 0x9747b28c), 0x704b81dc);
+	}
+}
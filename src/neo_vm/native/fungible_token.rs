@@ -0,0 +1,334 @@
+//! The NEP-17 logic shared by the NEO and GAS tokens.
+
+use num_bigint::BigInt;
+use num_traits::{Signed, Zero};
+use primitive_types::H160;
+
+use crate::{
+	builder::CallFlags,
+	neo_types::ContractParameterType,
+	neo_vm::{
+		native::{hash160_from_item, hash160_item, neo_token, NativeContract, NativeMethod},
+		ApplicationHost, ExecutionEngine, VMError, VMStackItem,
+	},
+};
+
+const PREFIX_TOTAL_SUPPLY: u8 = 11;
+const PREFIX_ACCOUNT: u8 = 20;
+
+/// The NEP-17 methods shared by both tokens. Handlers find their token from the executing hash.
+pub(super) fn methods() -> Vec<NativeMethod> {
+	type P = ContractParameterType;
+	vec![
+		NativeMethod::new("symbol", vec![], P::String, 0, CallFlags::None, |_, engine, _| {
+			let symbol = match executing_token(engine)? {
+				NativeContract::NeoToken => "NEO",
+				_ => "GAS",
+			};
+			Ok(VMStackItem::from(symbol))
+		}),
+		NativeMethod::new("decimals", vec![], P::Integer, 0, CallFlags::None, |_, engine, _| {
+			let decimals = match executing_token(engine)? {
+				NativeContract::NeoToken => 0,
+				_ => 8,
+			};
+			Ok(VMStackItem::from(decimals as i64))
+		}),
+		NativeMethod::new(
+			"totalSupply",
+			vec![],
+			P::Integer,
+			1 << 15,
+			CallFlags::ReadStates,
+			|host, engine, _| {
+				Ok(VMStackItem::Integer(total_supply(host, executing_token(engine)?)))
+			},
+		),
+		NativeMethod::new(
+			"balanceOf",
+			vec![("account", P::H160)],
+			P::Integer,
+			1 << 15,
+			CallFlags::ReadStates,
+			|host, engine, args| {
+				let account = hash160_from_item(&args[0])?;
+				let token = executing_token(engine)?;
+				Ok(VMStackItem::Integer(balance_of(host, engine, token, &account)?))
+			},
+		),
+		NativeMethod::new(
+			"transfer",
+			vec![("from", P::H160), ("to", P::H160), ("amount", P::Integer), ("data", P::Any)],
+			P::Boolean,
+			1 << 17,
+			CallFlags::States,
+			|host, engine, args| {
+				let from = hash160_from_item(&args[0])?;
+				let to = hash160_from_item(&args[1])?;
+				let amount = args[2].get_integer()?;
+				let token = executing_token(engine)?;
+				let result = transfer(host, engine, token, &from, &to, &amount, args[3].clone())?;
+				Ok(VMStackItem::Boolean(result))
+			},
+		)
+		.with_storage_fee(50)
+		.with_flags(CallFlags::AllowCall)
+		.with_flags(CallFlags::AllowNotify),
+	]
+}
+
+fn executing_token(engine: &ExecutionEngine) -> Result<NativeContract, VMError> {
+	let hash = ApplicationHost::current_state(engine)?.script_hash;
+	NativeContract::from_hash(&hash).ok_or_else(|| {
+		VMError::InvalidOperation("The executing contract isn't a token".to_string())
+	})
+}
+
+fn account_key(account: &H160) -> Vec<u8> {
+	let mut key = vec![PREFIX_ACCOUNT];
+	key.extend(super::hash160_bytes(account));
+	key
+}
+
+/// The fields of the account state of `account`, of which the first is the balance.
+pub(super) fn account_state(
+	host: &ApplicationHost,
+	engine: &ExecutionEngine,
+	token: NativeContract,
+	account: &H160,
+) -> Result<Option<Vec<VMStackItem>>, VMError> {
+	match host.get_item(engine, token.id(), &account_key(account))? {
+		Some(VMStackItem::Struct(fields)) => Ok(Some(fields.borrow().clone())),
+		Some(other) =>
+			Err(VMError::InvalidCast(format!("{} is not an account state", other.item_type()))),
+		None => Ok(None),
+	}
+}
+
+pub(super) fn put_account_state(
+	host: &mut ApplicationHost,
+	engine: &ExecutionEngine,
+	token: NativeContract,
+	account: &H160,
+	state: Vec<VMStackItem>,
+) -> Result<(), VMError> {
+	host.put_item(engine, token.id(), &account_key(account), &VMStackItem::new_struct(state))
+}
+
+fn new_account_state(token: NativeContract) -> Vec<VMStackItem> {
+	match token {
+		NativeContract::NeoToken => neo_token::new_account_state(),
+		_ => vec![VMStackItem::from(0i64)],
+	}
+}
+
+/// The balance of `account`, in the token's smallest unit.
+fn balance_of(
+	host: &ApplicationHost,
+	engine: &ExecutionEngine,
+	token: NativeContract,
+	account: &H160,
+) -> Result<BigInt, VMError> {
+	match account_state(host, engine, token, account)? {
+		Some(state) => state[0].get_integer(),
+		None => Ok(BigInt::zero()),
+	}
+}
+
+fn total_supply(host: &ApplicationHost, token: NativeContract) -> BigInt {
+	host.get_integer(token.id(), &[PREFIX_TOTAL_SUPPLY]).unwrap_or_default()
+}
+
+/// Applies `amount` to the balance of an account, running the token's balance hook first.
+///
+/// Returns the GAS the NEO token distributes to the account because of the change.
+fn change_balance(
+	host: &mut ApplicationHost,
+	engine: &ExecutionEngine,
+	token: NativeContract,
+	account: &H160,
+	state: &mut [VMStackItem],
+	amount: &BigInt,
+) -> Result<Option<(H160, BigInt)>, VMError> {
+	let distribution = match token {
+		NativeContract::NeoToken =>
+			neo_token::on_balance_changing(host, engine, account, state, amount)?,
+		_ => None,
+	};
+	state[0] = VMStackItem::Integer(state[0].get_integer()? + amount);
+	Ok(distribution)
+}
+
+/// Transfers `amount` from `from` to `to`, like `transfer` in C#.
+///
+/// Returns `false` if `from` didn't witness the transfer or has an insufficient balance.
+fn transfer(
+	host: &mut ApplicationHost,
+	engine: &mut ExecutionEngine,
+	token: NativeContract,
+	from: &H160,
+	to: &H160,
+	amount: &BigInt,
+	data: VMStackItem,
+) -> Result<bool, VMError> {
+	if amount.is_negative() {
+		return Err(VMError::InvalidOperation("The amount can't be negative".to_string()));
+	}
+	if !host.check_witness(engine, from)? {
+		return Ok(false);
+	}
+	let mut distributions = Vec::new();
+	let state_from = account_state(host, engine, token, from)?;
+	if amount.is_zero() {
+		if let Some(mut state) = state_from {
+			distributions.extend(change_balance(host, engine, token, from, &mut state, amount)?);
+			put_account_state(host, engine, token, from, state)?;
+		}
+	} else {
+		let Some(mut state_from) = state_from else { return Ok(false) };
+		let balance = state_from[0].get_integer()?;
+		if balance < *amount {
+			return Ok(false);
+		}
+		if from == to {
+			let zero = BigInt::zero();
+			distributions.extend(change_balance(
+				host,
+				engine,
+				token,
+				from,
+				&mut state_from,
+				&zero,
+			)?);
+			put_account_state(host, engine, token, from, state_from)?;
+		} else {
+			distributions.extend(change_balance(
+				host,
+				engine,
+				token,
+				from,
+				&mut state_from,
+				&-amount,
+			)?);
+			if balance == *amount {
+				host.delete(token.id(), &account_key(from));
+			} else {
+				put_account_state(host, engine, token, from, state_from)?;
+			}
+			let mut state_to =
+				account_state(host, engine, token, to)?.unwrap_or_else(|| new_account_state(token));
+			distributions.extend(change_balance(host, engine, token, to, &mut state_to, amount)?);
+			put_account_state(host, engine, token, to, state_to)?;
+		}
+	}
+	post_transfer(host, engine, token, Some(from), Some(to), amount, data, true, distributions)?;
+	Ok(true)
+}
+
+/// Creates `amount` tokens for `account`.
+pub(super) fn mint(
+	host: &mut ApplicationHost,
+	engine: &mut ExecutionEngine,
+	token: NativeContract,
+	account: &H160,
+	amount: &BigInt,
+	call_on_payment: bool,
+) -> Result<(), VMError> {
+	if amount.is_negative() {
+		return Err(VMError::InvalidOperation("The amount can't be negative".to_string()));
+	}
+	if amount.is_zero() {
+		return Ok(());
+	}
+	let mut state =
+		account_state(host, engine, token, account)?.unwrap_or_else(|| new_account_state(token));
+	let distribution = change_balance(host, engine, token, account, &mut state, amount)?;
+	put_account_state(host, engine, token, account, state)?;
+	let supply = total_supply(host, token) + amount;
+	host.put_integer(token.id(), &[PREFIX_TOTAL_SUPPLY], &supply);
+	post_transfer(
+		host,
+		engine,
+		token,
+		None,
+		Some(account),
+		amount,
+		VMStackItem::Null,
+		call_on_payment,
+		distribution.into_iter().collect(),
+	)
+}
+
+/// Destroys `amount` tokens of `account`.
+pub(super) fn burn(
+	host: &mut ApplicationHost,
+	engine: &mut ExecutionEngine,
+	token: NativeContract,
+	account: &H160,
+	amount: &BigInt,
+) -> Result<(), VMError> {
+	if amount.is_negative() {
+		return Err(VMError::InvalidOperation("The amount can't be negative".to_string()));
+	}
+	if amount.is_zero() {
+		return Ok(());
+	}
+	let mut state = account_state(host, engine, token, account)?
+		.ok_or_else(|| VMError::InvalidOperation(format!("{:?} has no balance", account)))?;
+	let balance = state[0].get_integer()?;
+	if balance < *amount {
+		return Err(VMError::InvalidOperation(format!("{:?} has an insufficient balance", account)));
+	}
+	let distribution = change_balance(host, engine, token, account, &mut state, &-amount)?;
+	if balance == *amount {
+		host.delete(token.id(), &account_key(account));
+	} else {
+		put_account_state(host, engine, token, account, state)?;
+	}
+	let supply = total_supply(host, token) - amount;
+	host.put_integer(token.id(), &[PREFIX_TOTAL_SUPPLY], &supply);
+	post_transfer(
+		host,
+		engine,
+		token,
+		Some(account),
+		None,
+		amount,
+		VMStackItem::Null,
+		false,
+		distribution.into_iter().collect(),
+	)
+}
+
+/// Sends the `Transfer` notification, queues `onNEP17Payment` and mints the distributed GAS.
+#[allow(clippy::too_many_arguments)]
+fn post_transfer(
+	host: &mut ApplicationHost,
+	engine: &mut ExecutionEngine,
+	token: NativeContract,
+	from: Option<&H160>,
+	to: Option<&H160>,
+	amount: &BigInt,
+	data: VMStackItem,
+	call_on_payment: bool,
+	distributions: Vec<(H160, BigInt)>,
+) -> Result<(), VMError> {
+	let hash_or_null = |hash: Option<&H160>| hash.map_or(VMStackItem::Null, hash160_item);
+	host.notify(
+		token.hash(),
+		"Transfer",
+		vec![hash_or_null(from), hash_or_null(to), VMStackItem::Integer(amount.clone())],
+	);
+	if let Some(to) = to.filter(|to| call_on_payment && host.state.contract(to).is_some()) {
+		host.call_from_native(
+			token,
+			*to,
+			"onNEP17Payment",
+			vec![hash_or_null(from), VMStackItem::Integer(amount.clone()), data],
+		);
+	}
+	for (account, amount) in distributions {
+		mint(host, engine, NativeContract::GasToken, &account, &amount, call_on_payment)?;
+	}
+	Ok(())
+}
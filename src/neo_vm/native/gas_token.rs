@@ -0,0 +1,52 @@
+use num_bigint::BigInt;
+use primitive_types::H160;
+
+use crate::{
+	builder::ScriptBuilder,
+	crypto::Secp256r1PublicKey,
+	neo_types::ScriptHashExtension,
+	neo_vm::{
+		native::{
+			fungible_token::{self, burn, mint},
+			neo_token, NativeContract, NativeMethod,
+		},
+		ApplicationHost, ExecutionEngine, VMError,
+	},
+};
+
+pub(super) fn methods() -> Vec<NativeMethod> {
+	fungible_token::methods()
+}
+
+pub(super) fn initialize(
+	host: &mut ApplicationHost,
+	engine: &mut ExecutionEngine,
+) -> Result<(), VMError> {
+	let settings = host.state.settings().clone();
+	let amount = BigInt::from(settings.initial_gas_distribution);
+	mint(host, engine, NativeContract::GasToken, &settings.validators_address(), &amount, false)
+}
+
+/// Burns the fees of the transactions in the persisting block and pays the network fees to the
+/// primary validator.
+pub(super) fn on_persist(
+	host: &mut ApplicationHost,
+	engine: &mut ExecutionEngine,
+) -> Result<(), VMError> {
+	let mut total_network_fee = 0;
+	for hash in host.persisting_block.transactions.clone() {
+		let Some(transaction) = host.state.transaction(&hash).cloned() else { continue };
+		let Some(sender) = transaction.sender() else { continue };
+		let fee = BigInt::from(transaction.system_fee + transaction.network_fee);
+		burn(host, engine, NativeContract::GasToken, &sender, &fee)?;
+		total_network_fee += transaction.network_fee;
+	}
+	let validators = neo_token::next_block_validators(host, engine)?;
+	let primary = validators
+		.get(host.persisting_block.primary_index as usize)
+		.ok_or_else(|| VMError::InvalidOperation("Invalid primary index".to_string()))?;
+	let primary = Secp256r1PublicKey::from_bytes(primary)
+		.map_err(|err| VMError::InvalidOperation(format!("Invalid public key: {}", err)))?;
+	let primary: H160 = H160::from_script(&ScriptBuilder::build_verification_script(&primary));
+	mint(host, engine, NativeContract::GasToken, &primary, &BigInt::from(total_network_fee), false)
+}
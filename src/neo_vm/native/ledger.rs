@@ -0,0 +1,203 @@
+use num_bigint::BigInt;
+use num_traits::ToPrimitive;
+
+use crate::{
+	builder::{CallFlags, Signer, SignerTrait, WitnessCondition, WitnessScope},
+	neo_types::ContractParameterType,
+	neo_vm::{
+		native::{hash160_item, hash256_from_bytes, hash256_item, NativeMethod},
+		ApplicationHost, BlockRecord, TransactionRecord, VMError, VMStackItem,
+	},
+};
+
+pub(super) fn methods() -> Vec<NativeMethod> {
+	type P = ContractParameterType;
+	vec![
+		NativeMethod::new(
+			"currentHash",
+			vec![],
+			P::H256,
+			1 << 15,
+			CallFlags::ReadStates,
+			|host, _, _| {
+				let block = host.state.current_block();
+				Ok(block.map_or(VMStackItem::Null, |block| hash256_item(&block.hash)))
+			},
+		),
+		NativeMethod::new(
+			"currentIndex",
+			vec![],
+			P::Integer,
+			1 << 15,
+			CallFlags::ReadStates,
+			|host, _, _| Ok(VMStackItem::from(host.state.height().unwrap_or_default() as i64)),
+		),
+		NativeMethod::new(
+			"getBlock",
+			vec![("indexOrHash", P::ByteArray)],
+			P::Array,
+			1 << 15,
+			CallFlags::ReadStates,
+			|host, _, args| Ok(find_block(host, &args[0])?.map_or(VMStackItem::Null, block_item)),
+		),
+		NativeMethod::new(
+			"getTransaction",
+			vec![("hash", P::H256)],
+			P::Array,
+			1 << 15,
+			CallFlags::ReadStates,
+			|host, _, args| {
+				let transaction = find_transaction(host, &args[0])?;
+				Ok(transaction.map_or(VMStackItem::Null, transaction_item))
+			},
+		),
+		NativeMethod::new(
+			"getTransactionFromBlock",
+			vec![("blockIndexOrHash", P::ByteArray), ("txIndex", P::Integer)],
+			P::Array,
+			1 << 16,
+			CallFlags::ReadStates,
+			|host, _, args| {
+				let Some(block) = find_block(host, &args[0])? else { return Ok(VMStackItem::Null) };
+				let index = args[1].get_integer()?;
+				let hash =
+					index.to_usize().and_then(|index| block.transactions.get(index)).ok_or_else(
+						|| VMError::InvalidOperation("Invalid transaction index".to_string()),
+					)?;
+				let transaction = host.state.transaction(hash);
+				Ok(transaction.map_or(VMStackItem::Null, transaction_item))
+			},
+		),
+		NativeMethod::new(
+			"getTransactionHeight",
+			vec![("hash", P::H256)],
+			P::Integer,
+			1 << 15,
+			CallFlags::ReadStates,
+			|host, _, args| {
+				let height = find_transaction(host, &args[0])?
+					.map_or(-1, |transaction| transaction.block_index as i64);
+				Ok(VMStackItem::from(height))
+			},
+		),
+		NativeMethod::new(
+			"getTransactionSigners",
+			vec![("hash", P::H256)],
+			P::Array,
+			1 << 15,
+			CallFlags::ReadStates,
+			|host, _, args| {
+				let transaction = find_transaction(host, &args[0])?;
+				Ok(transaction.map_or(VMStackItem::Null, |transaction| {
+					VMStackItem::new_array(transaction.signers.iter().map(signer_item).collect())
+				}))
+			},
+		),
+		NativeMethod::new(
+			"getTransactionVMState",
+			vec![("hash", P::H256)],
+			P::Integer,
+			1 << 15,
+			CallFlags::ReadStates,
+			|host, _, args| {
+				let state = find_transaction(host, &args[0])?
+					.map_or(0, |transaction| transaction.vm_state as i64);
+				Ok(VMStackItem::from(state))
+			},
+		),
+	]
+}
+
+fn find_block<'a>(
+	host: &'a ApplicationHost,
+	index_or_hash: &VMStackItem,
+) -> Result<Option<&'a BlockRecord>, VMError> {
+	let bytes = index_or_hash.get_span()?;
+	if bytes.len() < 32 {
+		let index = crate::neo_vm::bytes_to_integer(&bytes)
+			.to_u32()
+			.ok_or_else(|| VMError::InvalidOperation("Invalid block index".to_string()))?;
+		return Ok(host.state.block(index));
+	}
+	Ok(host.state.block_by_hash(&hash256_from_bytes(&bytes)?))
+}
+
+fn find_transaction<'a>(
+	host: &'a ApplicationHost,
+	hash: &VMStackItem,
+) -> Result<Option<&'a TransactionRecord>, VMError> {
+	Ok(host.state.transaction(&hash256_from_bytes(&hash.get_span()?)?))
+}
+
+/// Converts a block to the stack item returned by `getBlock`.
+fn block_item(block: &BlockRecord) -> VMStackItem {
+	VMStackItem::new_array(vec![
+		hash256_item(&block.hash),
+		VMStackItem::from(block.version as i64),
+		hash256_item(&block.prev_hash),
+		hash256_item(&block.merkle_root),
+		VMStackItem::Integer(BigInt::from(block.timestamp)),
+		VMStackItem::Integer(BigInt::from(block.nonce)),
+		VMStackItem::from(block.index as i64),
+		VMStackItem::from(block.primary_index as i64),
+		hash160_item(&block.next_consensus),
+		VMStackItem::from(block.transactions.len() as i64),
+	])
+}
+
+/// Converts a transaction to the stack item returned by `getTransaction`.
+pub(crate) fn transaction_item(transaction: &TransactionRecord) -> VMStackItem {
+	VMStackItem::new_array(vec![
+		hash256_item(&transaction.hash),
+		VMStackItem::from(transaction.version as i64),
+		VMStackItem::from(transaction.nonce as i64),
+		transaction.sender().map_or(VMStackItem::Null, |sender| hash160_item(&sender)),
+		VMStackItem::from(transaction.system_fee),
+		VMStackItem::from(transaction.network_fee),
+		VMStackItem::from(transaction.valid_until_block as i64),
+		VMStackItem::ByteString(transaction.script.clone()),
+	])
+}
+
+fn signer_item(signer: &Signer) -> VMStackItem {
+	let scopes = WitnessScope::combine(signer.get_scopes());
+	let rules = signer
+		.get_rules()
+		.iter()
+		.map(|rule| {
+			VMStackItem::new_array(vec![
+				VMStackItem::from(rule.action as u8 as i64),
+				condition_item(&rule.condition),
+			])
+		})
+		.collect();
+	VMStackItem::new_array(vec![
+		hash160_item(signer.get_signer_hash()),
+		VMStackItem::from(scopes as i64),
+		VMStackItem::new_array(signer.get_allowed_contracts().iter().map(hash160_item).collect()),
+		VMStackItem::new_array(
+			signer
+				.get_allowed_groups()
+				.iter()
+				.map(|group| VMStackItem::ByteString(group.get_encoded(true)))
+				.collect(),
+		),
+		VMStackItem::new_array(rules),
+	])
+}
+
+fn condition_item(condition: &WitnessCondition) -> VMStackItem {
+	let mut fields = vec![VMStackItem::from(condition.byte() as i64)];
+	match condition {
+		WitnessCondition::Boolean(value) => fields.push(VMStackItem::Boolean(*value)),
+		WitnessCondition::Not(inner) => fields.push(condition_item(inner)),
+		WitnessCondition::And(conditions) | WitnessCondition::Or(conditions) =>
+			fields.push(VMStackItem::new_array(conditions.iter().map(condition_item).collect())),
+		WitnessCondition::ScriptHash(hash) | WitnessCondition::CalledByContract(hash) =>
+			fields.push(hash160_item(hash)),
+		WitnessCondition::Group(group) | WitnessCondition::CalledByGroup(group) =>
+			fields.push(VMStackItem::ByteString(group.get_encoded(true))),
+		WitnessCondition::CalledByEntry => {},
+	}
+	VMStackItem::new_array(fields)
+}
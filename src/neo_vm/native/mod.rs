@@ -0,0 +1,521 @@
+//! Emulation of the Neo N3 native contracts.
+//!
+//! Every native contract is deployed at genesis with a stub script of `PUSH0`, `SYSCALL
+//! System.Contract.CallNative` and `RET` per method, exactly like on a real node, so that
+//! `System.Contract.Call`, `CALLT` and `getContract` behave the same for native and deployed
+//! contracts. Native state uses the same storage layout as the C# implementation.
+
+use std::sync::OnceLock;
+
+use base64::{engine::general_purpose, Engine};
+use num_bigint::BigInt;
+use primitive_types::{H160, H256};
+
+use crate::{
+	builder::{CallFlags, InteropService, ScriptBuilder},
+	neo_types::{
+		ContractABI, ContractEvent, ContractManifest, ContractMethod, ContractMethodToken,
+		ContractNef, ContractParameter, ContractParameter2, ContractParameterType,
+		ContractPermission, ContractState, MethodToken, NefFile, OpCode, ScriptHashExtension,
+	},
+	neo_vm::{ApplicationHost, ExecutionEngine, VMError, VMStackItem},
+};
+
+#[cfg(test)]
+pub(crate) use contract_management::contract_item;
+pub(crate) use contract_management::{contract_from_item, contract_hash_key, contract_key};
+pub(crate) use ledger::transaction_item;
+pub(crate) use neo_token::check_committee;
+pub(crate) use policy_contract::{
+	exec_fee_factor, is_blocked, storage_price, DEFAULT_STORAGE_PRICE,
+};
+
+mod contract_management;
+mod crypto_lib;
+mod fungible_token;
+mod gas_token;
+mod ledger;
+mod neo_token;
+mod policy_contract;
+mod role_management;
+mod std_lib;
+
+/// The size of the stub script of a native method.
+const METHOD_STUB_SIZE: usize = 7;
+
+/// A native contract of Neo N3.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NativeContract {
+	ContractManagement,
+	StdLib,
+	CryptoLib,
+	Ledger,
+	NeoToken,
+	GasToken,
+	PolicyContract,
+	RoleManagement,
+}
+
+impl NativeContract {
+	/// All emulated native contracts, in the order they are deployed.
+	pub fn all() -> [NativeContract; 8] {
+		[
+			NativeContract::ContractManagement,
+			NativeContract::StdLib,
+			NativeContract::CryptoLib,
+			NativeContract::Ledger,
+			NativeContract::NeoToken,
+			NativeContract::GasToken,
+			NativeContract::PolicyContract,
+			NativeContract::RoleManagement,
+		]
+	}
+
+	/// The contract name, which also determines its hash.
+	pub fn name(&self) -> &'static str {
+		match self {
+			NativeContract::ContractManagement => "ContractManagement",
+			NativeContract::StdLib => "StdLib",
+			NativeContract::CryptoLib => "CryptoLib",
+			NativeContract::Ledger => "LedgerContract",
+			NativeContract::NeoToken => "NeoToken",
+			NativeContract::GasToken => "GasToken",
+			NativeContract::PolicyContract => "PolicyContract",
+			NativeContract::RoleManagement => "RoleManagement",
+		}
+	}
+
+	/// The contract id. Native contracts have negative ids.
+	pub fn id(&self) -> i32 {
+		match self {
+			NativeContract::ContractManagement => -1,
+			NativeContract::StdLib => -2,
+			NativeContract::CryptoLib => -3,
+			NativeContract::Ledger => -4,
+			NativeContract::NeoToken => -5,
+			NativeContract::GasToken => -6,
+			NativeContract::PolicyContract => -7,
+			NativeContract::RoleManagement => -8,
+		}
+	}
+
+	/// The contract hash, computed like the hash of a contract deployed by the zero address.
+	pub fn hash(&self) -> H160 {
+		contract_hash(&H160::zero(), 0, self.name())
+	}
+
+	pub fn from_hash(hash: &H160) -> Option<NativeContract> {
+		Self::all().into_iter().find(|native| native.hash() == *hash)
+	}
+
+	/// The state of the contract as returned by `ContractManagement.getContract`.
+	pub fn contract_state(&self) -> ContractState {
+		let methods = self.methods();
+		let script: Vec<u8> = methods.iter().flat_map(|_| method_stub()).collect();
+		let nef = NefFile::new("neo-core-v3.0", "", vec![], script)
+			.expect("The native contract NEF is valid");
+		let abi_methods = methods
+			.iter()
+			.enumerate()
+			.map(|(index, method)| {
+				let parameters = method
+					.parameters
+					.iter()
+					.map(|(name, typ)| ContractParameter2::new(name.to_string(), *typ))
+					.collect();
+				ContractMethod::new(
+					method.name.to_string(),
+					Some(parameters),
+					index * METHOD_STUB_SIZE,
+					method.return_type,
+					method.required_flags & !CallFlags::ReadOnly.value() == 0,
+				)
+			})
+			.collect();
+		let events = self
+			.events()
+			.into_iter()
+			.map(|(name, parameters)| ContractEvent {
+				name: name.to_string(),
+				parameters: parameters
+					.iter()
+					.map(|(name, typ)| ContractParameter::new(*typ).with_name(*name))
+					.collect(),
+			})
+			.collect();
+		let supported_standards = match self {
+			NativeContract::NeoToken | NativeContract::GasToken => vec!["NEP-17".to_string()],
+			_ => vec![],
+		};
+		let manifest = ContractManifest::new(
+			Some(self.name().to_string()),
+			vec![],
+			None,
+			supported_standards,
+			Some(ContractABI::new(Some(abi_methods), Some(events))),
+			vec![ContractPermission::new("*".to_string(), vec!["*".to_string()])],
+			vec![],
+			None,
+		);
+		ContractState::new(self.id(), 0, self.hash(), contract_nef(&nef), manifest)
+	}
+
+	/// The methods of the contract, sorted by name and parameter count like their stubs.
+	pub(crate) fn methods(&self) -> &'static [NativeMethod] {
+		static METHODS: OnceLock<Vec<Vec<NativeMethod>>> = OnceLock::new();
+		let methods = METHODS.get_or_init(|| {
+			Self::all()
+				.iter()
+				.map(|native| {
+					let mut methods = match native {
+						NativeContract::ContractManagement => contract_management::methods(),
+						NativeContract::StdLib => std_lib::methods(),
+						NativeContract::CryptoLib => crypto_lib::methods(),
+						NativeContract::Ledger => ledger::methods(),
+						NativeContract::NeoToken => neo_token::methods(),
+						NativeContract::GasToken => gas_token::methods(),
+						NativeContract::PolicyContract => policy_contract::methods(),
+						NativeContract::RoleManagement => role_management::methods(),
+					};
+					methods.sort_by(|a, b| {
+						a.name.cmp(b.name).then(a.parameters.len().cmp(&b.parameters.len()))
+					});
+					methods
+				})
+				.collect()
+		});
+		let index = Self::all().iter().position(|native| native == self).unwrap_or_default();
+		&methods[index]
+	}
+
+	/// The method whose stub contains the instruction at `position`.
+	pub(crate) fn method_at(&self, position: usize) -> Option<&'static NativeMethod> {
+		self.methods().get(position / METHOD_STUB_SIZE)
+	}
+
+	fn events(&self) -> Vec<(&'static str, Vec<(&'static str, ContractParameterType)>)> {
+		type P = ContractParameterType;
+		let transfer =
+			("Transfer", vec![("from", P::H160), ("to", P::H160), ("amount", P::Integer)]);
+		match self {
+			NativeContract::ContractManagement => vec![
+				("Deploy", vec![("Hash", P::H160)]),
+				("Update", vec![("Hash", P::H160)]),
+				("Destroy", vec![("Hash", P::H160)]),
+			],
+			NativeContract::NeoToken => vec![
+				transfer,
+				(
+					"CandidateStateChanged",
+					vec![
+						("pubkey", P::PublicKey),
+						("registered", P::Boolean),
+						("votes", P::Integer),
+					],
+				),
+				(
+					"Vote",
+					vec![
+						("account", P::H160),
+						("from", P::PublicKey),
+						("to", P::PublicKey),
+						("amount", P::Integer),
+					],
+				),
+			],
+			NativeContract::GasToken => vec![transfer],
+			NativeContract::RoleManagement =>
+				vec![("Designation", vec![("Role", P::Integer), ("BlockIndex", P::Integer)])],
+			_ => vec![],
+		}
+	}
+
+	/// Writes the initial state of the contract at genesis.
+	pub(crate) fn initialize(
+		&self,
+		host: &mut ApplicationHost,
+		engine: &mut ExecutionEngine,
+	) -> Result<(), VMError> {
+		match self {
+			NativeContract::ContractManagement => contract_management::initialize(host),
+			NativeContract::NeoToken => neo_token::initialize(host, engine),
+			NativeContract::GasToken => gas_token::initialize(host, engine),
+			NativeContract::PolicyContract => policy_contract::initialize(host),
+			_ => Ok(()),
+		}
+	}
+
+	/// Runs the `OnPersist` logic of the contract, before the transactions of a block.
+	pub(crate) fn on_persist(
+		&self,
+		host: &mut ApplicationHost,
+		engine: &mut ExecutionEngine,
+	) -> Result<(), VMError> {
+		match self {
+			NativeContract::NeoToken => neo_token::on_persist(host, engine),
+			NativeContract::GasToken => gas_token::on_persist(host, engine),
+			_ => Ok(()),
+		}
+	}
+
+	/// Runs the `PostPersist` logic of the contract, after the transactions of a block.
+	pub(crate) fn post_persist(
+		&self,
+		host: &mut ApplicationHost,
+		engine: &mut ExecutionEngine,
+	) -> Result<(), VMError> {
+		match self {
+			NativeContract::NeoToken => neo_token::post_persist(host, engine),
+			_ => Ok(()),
+		}
+	}
+}
+
+/// Handles a call to a native method. The arguments are in declaration order.
+pub(crate) type NativeHandler = fn(
+	&mut ApplicationHost,
+	&mut ExecutionEngine,
+	Vec<VMStackItem>,
+) -> Result<VMStackItem, VMError>;
+
+/// A method of a native contract.
+pub(crate) struct NativeMethod {
+	pub(crate) name: &'static str,
+	pub(crate) parameters: Vec<(&'static str, ContractParameterType)>,
+	pub(crate) return_type: ContractParameterType,
+	/// The execution price, multiplied by the execution fee factor.
+	pub(crate) cpu_fee: i64,
+	/// The storage price, multiplied by the storage price of the Policy contract.
+	pub(crate) storage_fee: i64,
+	pub(crate) required_flags: u8,
+	pub(crate) handler: NativeHandler,
+}
+
+impl NativeMethod {
+	pub(crate) fn new(
+		name: &'static str,
+		parameters: Vec<(&'static str, ContractParameterType)>,
+		return_type: ContractParameterType,
+		cpu_fee: i64,
+		required_flags: CallFlags,
+		handler: NativeHandler,
+	) -> Self {
+		Self {
+			name,
+			parameters,
+			return_type,
+			cpu_fee,
+			storage_fee: 0,
+			required_flags: required_flags.value(),
+			handler,
+		}
+	}
+
+	pub(crate) fn with_storage_fee(mut self, storage_fee: i64) -> Self {
+		self.storage_fee = storage_fee;
+		self
+	}
+
+	pub(crate) fn with_flags(mut self, flags: CallFlags) -> Self {
+		self.required_flags |= flags.value();
+		self
+	}
+}
+
+/// Computes the hash of a contract deployed by `sender`.
+pub fn contract_hash(sender: &H160, nef_checksum: u32, name: &str) -> H160 {
+	let mut builder = ScriptBuilder::new();
+	builder
+		.op_code(&[OpCode::Abort])
+		.push_data(hash160_bytes(sender))
+		.push_integer(BigInt::from(nef_checksum))
+		.push_data(name.as_bytes().to_vec());
+	H160::from_script(&builder.to_bytes())
+}
+
+/// Converts a [`NefFile`] to the JSON form used in contract states.
+pub(crate) fn contract_nef(nef: &NefFile) -> ContractNef {
+	let tokens = nef
+		.method_tokens()
+		.iter()
+		.map(|token| {
+			ContractMethodToken::new(
+				token.hash(),
+				token.method().to_string(),
+				token.params_count() as u32,
+				token.has_return_value(),
+				call_flags_to_string(token.call_flags()),
+			)
+		})
+		.collect();
+	ContractNef::new(
+		0x3346454E,
+		nef.compiler().to_string(),
+		Some(nef.source_url().to_string()),
+		tokens,
+		general_purpose::STANDARD.encode(nef.script()),
+		nef.checksum() as i64,
+	)
+}
+
+/// Converts the JSON form of a NEF back to a [`NefFile`].
+pub(crate) fn nef_file(nef: &ContractNef) -> Result<NefFile, VMError> {
+	let script = general_purpose::STANDARD
+		.decode(&nef.script)
+		.map_err(|err| VMError::InvalidOperation(format!("Invalid NEF script: {}", err)))?;
+	let tokens = nef
+		.tokens
+		.iter()
+		.map(|token| {
+			let call_flags = call_flags_from_string(token.call_flags()).ok_or_else(|| {
+				VMError::InvalidOperation(format!("Invalid call flags {}", token.call_flags()))
+			})?;
+			Ok(MethodToken::new(
+				token.hash(),
+				token.method().to_string(),
+				token.param_count() as u16,
+				token.has_return_value(),
+				call_flags,
+			))
+		})
+		.collect::<Result<Vec<_>, VMError>>()?;
+	NefFile::new(&nef.compiler, &nef.source, tokens, script)
+		.map_err(|err| VMError::InvalidOperation(format!("Invalid NEF: {}", err)))
+}
+
+/// Sorts encoded public keys by their X coordinate, like `ECPoint` does in C#.
+pub(crate) fn sort_public_keys(keys: &mut [Vec<u8>]) {
+	keys.sort_by(|a, b| a[1..].cmp(&b[1..]));
+}
+
+fn method_stub() -> Vec<u8> {
+	let mut builder = ScriptBuilder::new();
+	builder
+		.op_code(&[OpCode::Push0])
+		.sys_call(InteropService::SystemContractCallNative)
+		.op_code(&[OpCode::Ret]);
+	builder.to_bytes()
+}
+
+/// Formats call flags the way C# formats `[Flags]` enums, e.g. `"States, AllowNotify"`.
+pub(crate) fn call_flags_to_string(flags: u8) -> String {
+	const NAMES: [(u8, &str); 7] = [
+		(0x0f, "All"),
+		(0x08, "AllowNotify"),
+		(0x05, "ReadOnly"),
+		(0x04, "AllowCall"),
+		(0x03, "States"),
+		(0x02, "WriteStates"),
+		(0x01, "ReadStates"),
+	];
+	if flags == 0 {
+		return "None".to_string();
+	}
+	let mut remaining = flags;
+	let mut names = Vec::new();
+	for (value, name) in NAMES {
+		if remaining & value == value {
+			names.push(name);
+			remaining &= !value;
+		}
+	}
+	names.reverse();
+	names.join(", ")
+}
+
+/// Parses call flags formatted by [`call_flags_to_string`].
+pub(crate) fn call_flags_from_string(flags: &str) -> Option<u8> {
+	flags.split(',').map(str::trim).try_fold(0u8, |value, name| {
+		let flag = match name {
+			"None" => 0x00,
+			"ReadStates" => 0x01,
+			"WriteStates" => 0x02,
+			"AllowCall" => 0x04,
+			"AllowNotify" => 0x08,
+			"States" => 0x03,
+			"ReadOnly" => 0x05,
+			"All" => 0x0f,
+			_ => return None,
+		};
+		Some(value | flag)
+	})
+}
+
+/// The little-endian bytes of a hash, as used in scripts and storage.
+pub(crate) fn hash160_bytes(hash: &H160) -> Vec<u8> {
+	hash.0.iter().rev().copied().collect()
+}
+
+pub(crate) fn hash160_item(hash: &H160) -> VMStackItem {
+	VMStackItem::ByteString(hash160_bytes(hash))
+}
+
+pub(crate) fn hash256_item(hash: &H256) -> VMStackItem {
+	VMStackItem::ByteString(hash.0.iter().rev().copied().collect())
+}
+
+/// Reads a hash from the little-endian bytes of an item.
+pub(crate) fn hash160_from_item(item: &VMStackItem) -> Result<H160, VMError> {
+	let mut bytes = item.get_span()?;
+	if bytes.len() != 20 {
+		return Err(VMError::InvalidOperation(format!(
+			"A Hash160 must be 20 bytes long, got {}",
+			bytes.len()
+		)));
+	}
+	bytes.reverse();
+	Ok(H160::from_slice(&bytes))
+}
+
+pub(crate) fn hash256_from_bytes(bytes: &[u8]) -> Result<H256, VMError> {
+	if bytes.len() != 32 {
+		return Err(VMError::InvalidOperation(format!(
+			"A Hash256 must be 32 bytes long, got {}",
+			bytes.len()
+		)));
+	}
+	let mut bytes = bytes.to_vec();
+	bytes.reverse();
+	Ok(H256::from_slice(&bytes))
+}
+
+/// Reads a `u32` argument, failing if it's out of range.
+pub(crate) fn u32_from_item(item: &VMStackItem) -> Result<u32, VMError> {
+	num_traits::ToPrimitive::to_u32(&item.get_integer()?)
+		.ok_or_else(|| VMError::InvalidOperation("The value is out of range".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+	use std::str::FromStr;
+
+	use super::*;
+
+	#[test]
+	fn test_native_hashes() {
+		let expected = [
+			(NativeContract::ContractManagement, "fffdc93764dbaddd97c48f252a53ea4643faa3fd"),
+			(NativeContract::StdLib, "acce6fd80d44e1796aa0c2c625e9e4e0ce39efc0"),
+			(NativeContract::CryptoLib, "726cb6e0cd8628a1350a611384688911ab75f51b"),
+			(NativeContract::Ledger, "da65b600f7124ce6c79950c1772a36403104f2be"),
+			(NativeContract::NeoToken, "ef4073a0f2b305a38ec4050e4d3d28bc40ea63f5"),
+			(NativeContract::GasToken, "d2a4cff31913016155e38e474a2c06d08be276cf"),
+			(NativeContract::PolicyContract, "cc5e4edd9f5f8dba8bb65734541df7a1c081c67b"),
+			(NativeContract::RoleManagement, "49cf4e5378ffcd4dec034fd98a174c5491e395e2"),
+		];
+		for (native, hash) in expected {
+			assert_eq!(native.hash(), H160::from_str(hash).unwrap(), "{}", native.name());
+			assert_eq!(NativeContract::from_hash(&native.hash()), Some(native));
+		}
+	}
+
+	#[test]
+	fn test_call_flags_strings() {
+		assert_eq!(call_flags_to_string(0x0f), "All");
+		assert_eq!(call_flags_to_string(0x0b), "States, AllowNotify");
+		assert_eq!(call_flags_to_string(0x00), "None");
+		assert_eq!(call_flags_from_string("States, AllowNotify"), Some(0x0b));
+		assert_eq!(call_flags_from_string("ReadOnly"), Some(0x05));
+		assert_eq!(call_flags_from_string("Everything"), None);
+	}
+}
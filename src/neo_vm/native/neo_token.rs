@@ -0,0 +1,757 @@
+use num_bigint::BigInt;
+use num_traits::{Signed, ToPrimitive, Zero};
+use primitive_types::H160;
+
+use crate::{
+	builder::{CallFlags, ScriptBuilder},
+	crypto::Secp256r1PublicKey,
+	neo_types::{ContractParameterType, ScriptHashExtension},
+	neo_vm::{
+		multi_sig_address,
+		native::{
+			fungible_token::{self, account_state, mint, put_account_state},
+			hash160_from_item, hash160_item, policy_contract, sort_public_keys, u32_from_item,
+			NativeContract, NativeMethod,
+		},
+		ApplicationHost, ExecutionEngine, FindOptions, StorageIterator, VMError, VMStackItem,
+	},
+};
+
+const PREFIX_VOTERS_COUNT: u8 = 1;
+const PREFIX_REGISTER_PRICE: u8 = 13;
+const PREFIX_COMMITTEE: u8 = 14;
+const PREFIX_VOTER_REWARD_PER_COMMITTEE: u8 = 23;
+const PREFIX_GAS_PER_BLOCK: u8 = 29;
+const PREFIX_CANDIDATE: u8 = 33;
+
+/// The total amount of NEO, which is indivisible.
+const TOTAL_AMOUNT: i64 = 100_000_000;
+const GAS_FACTOR: i64 = 1_0000_0000;
+const NEO_HOLDER_REWARD_RATIO: i64 = 10;
+const COMMITTEE_REWARD_RATIO: i64 = 10;
+const VOTER_REWARD_RATIO: i64 = 80;
+const MAX_CANDIDATES: usize = 256;
+
+const ID: i32 = -5;
+
+pub(super) fn methods() -> Vec<NativeMethod> {
+	type P = ContractParameterType;
+	let mut methods = fungible_token::methods();
+	methods.extend([
+		NativeMethod::new(
+			"unclaimedGas",
+			vec![("account", P::H160), ("end", P::Integer)],
+			P::Integer,
+			1 << 17,
+			CallFlags::ReadStates,
+			unclaimed_gas,
+		),
+		NativeMethod::new(
+			"registerCandidate",
+			vec![("pubkey", P::PublicKey)],
+			P::Boolean,
+			0,
+			CallFlags::States,
+			register_candidate,
+		)
+		.with_flags(CallFlags::AllowNotify),
+		NativeMethod::new(
+			"unregisterCandidate",
+			vec![("pubkey", P::PublicKey)],
+			P::Boolean,
+			1 << 16,
+			CallFlags::States,
+			unregister_candidate,
+		)
+		.with_flags(CallFlags::AllowNotify),
+		NativeMethod::new(
+			"vote",
+			vec![("account", P::H160), ("voteTo", P::PublicKey)],
+			P::Boolean,
+			1 << 16,
+			CallFlags::States,
+			vote,
+		)
+		.with_flags(CallFlags::AllowNotify),
+		NativeMethod::new(
+			"getCandidates",
+			vec![],
+			P::Array,
+			1 << 22,
+			CallFlags::ReadStates,
+			|host, engine, _| {
+				let candidates = candidates(host, engine)?
+					.into_iter()
+					.take(MAX_CANDIDATES)
+					.map(|(public_key, votes)| {
+						VMStackItem::new_struct(vec![
+							VMStackItem::ByteString(public_key),
+							VMStackItem::Integer(votes),
+						])
+					})
+					.collect();
+				Ok(VMStackItem::new_array(candidates))
+			},
+		),
+		NativeMethod::new(
+			"getAllCandidates",
+			vec![],
+			P::InteropInterface,
+			1 << 22,
+			CallFlags::ReadStates,
+			|host, engine, _| {
+				let mut entries = Vec::new();
+				for (key, value) in host.state.find_storage(ID, &[PREFIX_CANDIDATE]) {
+					if is_registered_candidate(host, engine, &key[1..], &value)? {
+						entries.push((key, value));
+					}
+				}
+				let options = FindOptions::REMOVE_PREFIX
+					| FindOptions::DESERIALIZE_VALUES
+					| FindOptions::PICK_FIELD1;
+				Ok(VMStackItem::new_interop(StorageIterator::new(entries, 1, options)))
+			},
+		),
+		NativeMethod::new(
+			"getCandidateVote",
+			vec![("pubKey", P::PublicKey)],
+			P::Integer,
+			1 << 15,
+			CallFlags::ReadStates,
+			|host, engine, args| {
+				let votes = match candidate_state(host, engine, &args[0].get_span()?)? {
+					Some((true, votes)) => votes,
+					_ => BigInt::from(-1),
+				};
+				Ok(VMStackItem::Integer(votes))
+			},
+		),
+		NativeMethod::new(
+			"getCommittee",
+			vec![],
+			P::Array,
+			1 << 16,
+			CallFlags::ReadStates,
+			|host, engine, _| {
+				let mut committee: Vec<Vec<u8>> = committee(host, engine)?
+					.into_iter()
+					.map(|(public_key, _)| public_key)
+					.collect();
+				sort_public_keys(&mut committee);
+				Ok(VMStackItem::new_array(
+					committee.into_iter().map(VMStackItem::ByteString).collect(),
+				))
+			},
+		),
+		NativeMethod::new(
+			"getCommitteeAddress",
+			vec![],
+			P::H160,
+			1 << 16,
+			CallFlags::ReadStates,
+			|host, engine, _| Ok(hash160_item(&committee_address(host, engine)?)),
+		),
+		NativeMethod::new(
+			"getNextBlockValidators",
+			vec![],
+			P::Array,
+			1 << 16,
+			CallFlags::ReadStates,
+			|host, engine, _| {
+				let validators = next_block_validators(host, engine)?;
+				Ok(VMStackItem::new_array(
+					validators.into_iter().map(VMStackItem::ByteString).collect(),
+				))
+			},
+		),
+		NativeMethod::new(
+			"getGasPerBlock",
+			vec![],
+			P::Integer,
+			1 << 15,
+			CallFlags::ReadStates,
+			|host, _, _| {
+				let end = host.state.height().map_or(0, |height| height + 1);
+				Ok(VMStackItem::Integer(gas_per_block(host, end)))
+			},
+		),
+		NativeMethod::new(
+			"setGasPerBlock",
+			vec![("gasPerBlock", P::Integer)],
+			P::Void,
+			1 << 15,
+			CallFlags::States,
+			set_gas_per_block,
+		),
+		NativeMethod::new(
+			"getRegisterPrice",
+			vec![],
+			P::Integer,
+			1 << 15,
+			CallFlags::ReadStates,
+			|host, _, _| Ok(VMStackItem::Integer(register_price(host))),
+		),
+		NativeMethod::new(
+			"setRegisterPrice",
+			vec![("registerPrice", P::Integer)],
+			P::Void,
+			1 << 15,
+			CallFlags::States,
+			|host, engine, args| {
+				let price = args[0].get_integer()?;
+				if !price.is_positive() {
+					return Err(VMError::InvalidOperation("The price must be positive".to_string()));
+				}
+				check_committee(host, engine)?;
+				host.put_integer(ID, &[PREFIX_REGISTER_PRICE], &price);
+				Ok(VMStackItem::Null)
+			},
+		),
+		NativeMethod::new(
+			"getAccountState",
+			vec![("account", P::H160)],
+			P::Array,
+			1 << 15,
+			CallFlags::ReadStates,
+			|host, engine, args| {
+				let account = hash160_from_item(&args[0])?;
+				let state = account_state(host, engine, NativeContract::NeoToken, &account)?;
+				Ok(state.map_or(VMStackItem::Null, VMStackItem::new_struct))
+			},
+		),
+	]);
+	methods
+}
+
+pub(super) fn initialize(
+	host: &mut ApplicationHost,
+	engine: &mut ExecutionEngine,
+) -> Result<(), VMError> {
+	let settings = host.state.settings().clone();
+	let committee = settings
+		.standby_committee
+		.iter()
+		.map(|key| (key.get_encoded(true), BigInt::zero()))
+		.collect::<Vec<_>>();
+	put_committee(host, engine, &committee)?;
+	host.state
+		.put_storage(crate::neo_vm::StorageKey::new(ID, [PREFIX_VOTERS_COUNT]), Vec::new());
+	host.put_integer(ID, &gas_per_block_key(0), &BigInt::from(5 * GAS_FACTOR));
+	host.put_integer(ID, &[PREFIX_REGISTER_PRICE], &BigInt::from(1000 * GAS_FACTOR));
+	mint(
+		host,
+		engine,
+		NativeContract::NeoToken,
+		&settings.validators_address(),
+		&BigInt::from(TOTAL_AMOUNT),
+		false,
+	)
+}
+
+/// Refreshes the committee at the start of every committee epoch.
+pub(super) fn on_persist(
+	host: &mut ApplicationHost,
+	engine: &mut ExecutionEngine,
+) -> Result<(), VMError> {
+	let members = host.state.settings().standby_committee.len() as u32;
+	if host.persisting_block.index.is_multiple_of(members) {
+		let committee = compute_committee(host, engine)?;
+		put_committee(host, engine, &committee)?;
+	}
+	Ok(())
+}
+
+/// Mints the committee reward and records the rewards of the voters of every member.
+pub(super) fn post_persist(
+	host: &mut ApplicationHost,
+	engine: &mut ExecutionEngine,
+) -> Result<(), VMError> {
+	let settings = host.state.settings().clone();
+	let (m, n) = (settings.standby_committee.len(), settings.validators_count);
+	let index = host.persisting_block.index;
+	let gas_per_block = gas_per_block(host, index);
+	let committee = committee(host, engine)?;
+	let account = signature_account(&committee[index as usize % m].0)?;
+	let reward = &gas_per_block * COMMITTEE_REWARD_RATIO / 100;
+	mint(host, engine, NativeContract::GasToken, &account, &reward, false)?;
+
+	if index.is_multiple_of(m as u32) {
+		let voter_reward_of_each_committee =
+			&gas_per_block * VOTER_REWARD_RATIO * GAS_FACTOR * m as i64 / (m + n) as i64 / 100;
+		for (position, (public_key, votes)) in committee.iter().enumerate() {
+			if !votes.is_positive() {
+				continue;
+			}
+			let factor = if position < n { 2 } else { 1 };
+			let reward_per_neo = &voter_reward_of_each_committee * factor / votes;
+			let key = voter_reward_key(public_key);
+			let total = host.get_integer(ID, &key).unwrap_or_default() + reward_per_neo;
+			host.put_integer(ID, &key, &total);
+		}
+	}
+	Ok(())
+}
+
+/// The NEO account state of a new holder: balance, balance height, vote and last GAS per vote.
+pub(super) fn new_account_state() -> Vec<VMStackItem> {
+	vec![
+		VMStackItem::from(0i64),
+		VMStackItem::from(0i64),
+		VMStackItem::Null,
+		VMStackItem::from(0i64),
+	]
+}
+
+/// Distributes the GAS earned by the account and moves its votes with its balance.
+pub(super) fn on_balance_changing(
+	host: &mut ApplicationHost,
+	engine: &ExecutionEngine,
+	account: &H160,
+	state: &mut [VMStackItem],
+	amount: &BigInt,
+) -> Result<Option<(H160, BigInt)>, VMError> {
+	let distribution = distribute_gas(host, account, state)?;
+	if amount.is_zero() {
+		return Ok(distribution);
+	}
+	let VMStackItem::ByteString(vote_to) = &state[2] else { return Ok(distribution) };
+	let vote_to = vote_to.clone();
+	add_voters_count(host, amount);
+	let (registered, votes) = candidate_state(host, engine, &vote_to)?.unwrap_or_default();
+	put_candidate(host, engine, &vote_to, registered, &(votes + amount))?;
+	Ok(distribution)
+}
+
+/// Asserts that the committee witnessed the current transaction.
+pub(crate) fn check_committee(
+	host: &ApplicationHost,
+	engine: &ExecutionEngine,
+) -> Result<(), VMError> {
+	let address = committee_address(host, engine)?;
+	if !host.check_witness(engine, &address)? {
+		return Err(VMError::InvalidOperation("Invalid committee signature".to_string()));
+	}
+	Ok(())
+}
+
+/// The validators of the next block, sorted.
+pub(super) fn next_block_validators(
+	host: &ApplicationHost,
+	engine: &ExecutionEngine,
+) -> Result<Vec<Vec<u8>>, VMError> {
+	let count = host.state.settings().validators_count;
+	let mut validators: Vec<Vec<u8>> = committee(host, engine)?
+		.into_iter()
+		.take(count)
+		.map(|(public_key, _)| public_key)
+		.collect();
+	sort_public_keys(&mut validators);
+	Ok(validators)
+}
+
+/// The address of the current committee.
+fn committee_address(host: &ApplicationHost, engine: &ExecutionEngine) -> Result<H160, VMError> {
+	let keys = committee(host, engine)?
+		.iter()
+		.map(|(public_key, _)| public_key_from_bytes(public_key))
+		.collect::<Result<Vec<_>, _>>()?;
+	Ok(multi_sig_address(&keys, keys.len() - (keys.len() - 1) / 2))
+}
+
+/// The cached committee members and their votes, in the order they were elected.
+fn committee(
+	host: &ApplicationHost,
+	engine: &ExecutionEngine,
+) -> Result<Vec<(Vec<u8>, BigInt)>, VMError> {
+	let Some(VMStackItem::Array(members)) = host.get_item(engine, ID, &[PREFIX_COMMITTEE])? else {
+		return Err(VMError::InvalidOperation("The committee is not initialized".to_string()));
+	};
+	let members = members.borrow();
+	members
+		.iter()
+		.map(|member| match member {
+			VMStackItem::Struct(fields) => {
+				let fields = fields.borrow();
+				Ok((fields[0].get_span()?, fields[1].get_integer()?))
+			},
+			other => Err(VMError::InvalidCast(format!("{} is not a member", other.item_type()))),
+		})
+		.collect()
+}
+
+fn put_committee(
+	host: &mut ApplicationHost,
+	engine: &ExecutionEngine,
+	committee: &[(Vec<u8>, BigInt)],
+) -> Result<(), VMError> {
+	let members = committee
+		.iter()
+		.map(|(public_key, votes)| {
+			VMStackItem::new_struct(vec![
+				VMStackItem::ByteString(public_key.clone()),
+				VMStackItem::Integer(votes.clone()),
+			])
+		})
+		.collect();
+	host.put_item(engine, ID, &[PREFIX_COMMITTEE], &VMStackItem::new_array(members))
+}
+
+fn compute_committee(
+	host: &ApplicationHost,
+	engine: &ExecutionEngine,
+) -> Result<Vec<(Vec<u8>, BigInt)>, VMError> {
+	let standby = &host.state.settings().standby_committee;
+	let voters_count = host.get_integer(ID, &[PREFIX_VOTERS_COUNT]).unwrap_or_default();
+	let mut candidates = candidates(host, engine)?;
+	// The committee is elected once 20% of all NEO takes part in voting
+	if voters_count * 5 < BigInt::from(TOTAL_AMOUNT) || candidates.len() < standby.len() {
+		return Ok(standby
+			.iter()
+			.map(|key| {
+				let key = key.get_encoded(true);
+				let votes = candidates
+					.iter()
+					.find(|(public_key, _)| *public_key == key)
+					.map(|(_, votes)| votes.clone())
+					.unwrap_or_default();
+				(key, votes)
+			})
+			.collect());
+	}
+	candidates.sort_by(|a, b| b.1.cmp(&a.1).then(a.0[1..].cmp(&b.0[1..])));
+	candidates.truncate(standby.len());
+	Ok(candidates)
+}
+
+/// The registered candidates that aren't blocked, with their votes.
+fn candidates(
+	host: &ApplicationHost,
+	engine: &ExecutionEngine,
+) -> Result<Vec<(Vec<u8>, BigInt)>, VMError> {
+	let mut candidates = Vec::new();
+	for (key, value) in host.state.find_storage(ID, &[PREFIX_CANDIDATE]) {
+		if is_registered_candidate(host, engine, &key[1..], &value)? {
+			let (_, votes) = candidate_state(host, engine, &key[1..])?.unwrap_or_default();
+			candidates.push((key[1..].to_vec(), votes));
+		}
+	}
+	Ok(candidates)
+}
+
+fn is_registered_candidate(
+	host: &ApplicationHost,
+	engine: &ExecutionEngine,
+	public_key: &[u8],
+	value: &[u8],
+) -> Result<bool, VMError> {
+	let state = crate::neo_vm::BinarySerializer::deserialize(value, engine.limits())?;
+	let registered = match &state {
+		VMStackItem::Struct(fields) => fields.borrow()[0].get_boolean()?,
+		_ => false,
+	};
+	Ok(registered && !policy_contract::is_blocked(&host.state, &signature_account(public_key)?))
+}
+
+fn candidate_key(public_key: &[u8]) -> Vec<u8> {
+	let mut key = vec![PREFIX_CANDIDATE];
+	key.extend_from_slice(public_key);
+	key
+}
+
+fn voter_reward_key(public_key: &[u8]) -> Vec<u8> {
+	let mut key = vec![PREFIX_VOTER_REWARD_PER_COMMITTEE];
+	key.extend_from_slice(public_key);
+	key
+}
+
+fn gas_per_block_key(index: u32) -> Vec<u8> {
+	let mut key = vec![PREFIX_GAS_PER_BLOCK];
+	key.extend_from_slice(&index.to_be_bytes());
+	key
+}
+
+fn candidate_state(
+	host: &ApplicationHost,
+	engine: &ExecutionEngine,
+	public_key: &[u8],
+) -> Result<Option<(bool, BigInt)>, VMError> {
+	match host.get_item(engine, ID, &candidate_key(public_key))? {
+		Some(VMStackItem::Struct(fields)) => {
+			let fields = fields.borrow();
+			Ok(Some((fields[0].get_boolean()?, fields[1].get_integer()?)))
+		},
+		Some(other) =>
+			Err(VMError::InvalidCast(format!("{} is not a candidate state", other.item_type()))),
+		None => Ok(None),
+	}
+}
+
+/// Stores a candidate, or deletes it once it's unregistered and has no votes left.
+fn put_candidate(
+	host: &mut ApplicationHost,
+	engine: &ExecutionEngine,
+	public_key: &[u8],
+	registered: bool,
+	votes: &BigInt,
+) -> Result<(), VMError> {
+	if !registered && votes.is_zero() {
+		host.delete(ID, &voter_reward_key(public_key));
+		host.delete(ID, &candidate_key(public_key));
+		return Ok(());
+	}
+	let state = VMStackItem::new_struct(vec![
+		VMStackItem::Boolean(registered),
+		VMStackItem::Integer(votes.clone()),
+	]);
+	host.put_item(engine, ID, &candidate_key(public_key), &state)
+}
+
+fn add_voters_count(host: &mut ApplicationHost, amount: &BigInt) {
+	let count = host.get_integer(ID, &[PREFIX_VOTERS_COUNT]).unwrap_or_default() + amount;
+	host.put_integer(ID, &[PREFIX_VOTERS_COUNT], &count);
+}
+
+fn register_price(host: &ApplicationHost) -> BigInt {
+	host.get_integer(ID, &[PREFIX_REGISTER_PRICE]).unwrap_or_default()
+}
+
+/// The GAS generated per block at block `index`.
+fn gas_per_block(host: &ApplicationHost, index: u32) -> BigInt {
+	gas_records(host, index).first().map(|(_, gas)| gas.clone()).unwrap_or_default()
+}
+
+/// The GAS per block records up to block `end`, latest first.
+fn gas_records(host: &ApplicationHost, end: u32) -> Vec<(u32, BigInt)> {
+	let mut records: Vec<(u32, BigInt)> = host
+		.state
+		.find_storage(ID, &[PREFIX_GAS_PER_BLOCK])
+		.into_iter()
+		.filter_map(|(key, value)| {
+			let index = u32::from_be_bytes(key[1..].try_into().ok()?);
+			(index <= end).then(|| (index, crate::neo_vm::bytes_to_integer(&value)))
+		})
+		.collect();
+	records.reverse();
+	records
+}
+
+/// The GAS earned by holding `value` NEO from block `start` to block `end`.
+fn neo_holder_reward(host: &ApplicationHost, value: &BigInt, start: u32, mut end: u32) -> BigInt {
+	let mut sum = BigInt::zero();
+	for (index, gas_per_block) in gas_records(host, end.saturating_sub(1)) {
+		if index > start {
+			sum += gas_per_block * (end - index);
+			end = index;
+		} else {
+			sum += gas_per_block * (end - start);
+			break;
+		}
+	}
+	value * sum * NEO_HOLDER_REWARD_RATIO / 100 / TOTAL_AMOUNT
+}
+
+/// The GAS an account has earned but not claimed by block `end`.
+fn calculate_bonus(
+	host: &ApplicationHost,
+	state: &[VMStackItem],
+	end: u32,
+) -> Result<BigInt, VMError> {
+	let value = state[0].get_integer()?;
+	let start = u32_from_item(&state[1])?;
+	if value.is_zero() || start >= end {
+		return Ok(BigInt::zero());
+	}
+	let holder_reward = neo_holder_reward(host, &value, start, end);
+	let VMStackItem::ByteString(vote_to) = &state[2] else { return Ok(holder_reward) };
+	let latest_gas_per_vote = host.get_integer(ID, &voter_reward_key(vote_to)).unwrap_or_default();
+	let vote_reward = &value * (latest_gas_per_vote - state[3].get_integer()?) / GAS_FACTOR;
+	Ok(holder_reward + vote_reward)
+}
+
+fn distribute_gas(
+	host: &ApplicationHost,
+	account: &H160,
+	state: &mut [VMStackItem],
+) -> Result<Option<(H160, BigInt)>, VMError> {
+	let index = host.persisting_block.index;
+	let gas = calculate_bonus(host, state, index)?;
+	state[1] = VMStackItem::from(index as i64);
+	if let VMStackItem::ByteString(vote_to) = &state[2] {
+		let latest = host.get_integer(ID, &voter_reward_key(vote_to)).unwrap_or_default();
+		state[3] = VMStackItem::Integer(latest);
+	}
+	Ok((!gas.is_zero()).then_some((*account, gas)))
+}
+
+fn unclaimed_gas(
+	host: &mut ApplicationHost,
+	engine: &mut ExecutionEngine,
+	args: Vec<VMStackItem>,
+) -> Result<VMStackItem, VMError> {
+	let account = hash160_from_item(&args[0])?;
+	let end = u32_from_item(&args[1])?;
+	if end > host.state.height().map_or(0, |height| height + 1) {
+		return Err(VMError::InvalidOperation("The end block is in the future".to_string()));
+	}
+	let gas = match account_state(host, engine, NativeContract::NeoToken, &account)? {
+		Some(state) => calculate_bonus(host, &state, end)?,
+		None => BigInt::zero(),
+	};
+	Ok(VMStackItem::Integer(gas))
+}
+
+fn register_candidate(
+	host: &mut ApplicationHost,
+	engine: &mut ExecutionEngine,
+	args: Vec<VMStackItem>,
+) -> Result<VMStackItem, VMError> {
+	let public_key = args[0].get_span()?;
+	if !host.check_witness(engine, &signature_account(&public_key)?)? {
+		return Ok(VMStackItem::Boolean(false));
+	}
+	engine.add_gas(register_price(host).to_i64().unwrap_or(i64::MAX))?;
+	let (registered, votes) = candidate_state(host, engine, &public_key)?.unwrap_or_default();
+	if registered {
+		return Ok(VMStackItem::Boolean(true));
+	}
+	put_candidate(host, engine, &public_key, true, &votes)?;
+	host.notify(
+		NativeContract::NeoToken.hash(),
+		"CandidateStateChanged",
+		vec![
+			VMStackItem::ByteString(public_key),
+			VMStackItem::Boolean(true),
+			VMStackItem::Integer(votes),
+		],
+	);
+	Ok(VMStackItem::Boolean(true))
+}
+
+fn unregister_candidate(
+	host: &mut ApplicationHost,
+	engine: &mut ExecutionEngine,
+	args: Vec<VMStackItem>,
+) -> Result<VMStackItem, VMError> {
+	let public_key = args[0].get_span()?;
+	if !host.check_witness(engine, &signature_account(&public_key)?)? {
+		return Ok(VMStackItem::Boolean(false));
+	}
+	let Some((true, votes)) = candidate_state(host, engine, &public_key)? else {
+		return Ok(VMStackItem::Boolean(true));
+	};
+	put_candidate(host, engine, &public_key, false, &votes)?;
+	host.notify(
+		NativeContract::NeoToken.hash(),
+		"CandidateStateChanged",
+		vec![
+			VMStackItem::ByteString(public_key),
+			VMStackItem::Boolean(false),
+			VMStackItem::Integer(votes),
+		],
+	);
+	Ok(VMStackItem::Boolean(true))
+}
+
+fn vote(
+	host: &mut ApplicationHost,
+	engine: &mut ExecutionEngine,
+	args: Vec<VMStackItem>,
+) -> Result<VMStackItem, VMError> {
+	let account = hash160_from_item(&args[0])?;
+	let vote_to = match &args[1] {
+		VMStackItem::Null => None,
+		item => Some(item.get_span()?),
+	};
+	if !host.check_witness(engine, &account)? {
+		return Ok(VMStackItem::Boolean(false));
+	}
+	let Some(mut state) = account_state(host, engine, NativeContract::NeoToken, &account)? else {
+		return Ok(VMStackItem::Boolean(false));
+	};
+	let balance = state[0].get_integer()?;
+	if balance.is_zero() {
+		return Ok(VMStackItem::Boolean(false));
+	}
+	let new_candidate = match &vote_to {
+		Some(public_key) => match candidate_state(host, engine, public_key)? {
+			Some((true, votes)) => Some(votes),
+			_ => return Ok(VMStackItem::Boolean(false)),
+		},
+		None => None,
+	};
+	let from = match &state[2] {
+		VMStackItem::ByteString(public_key) => Some(public_key.clone()),
+		_ => None,
+	};
+	if from.is_none() != vote_to.is_none() {
+		add_voters_count(host, &if from.is_none() { balance.clone() } else { -balance.clone() });
+	}
+	let distribution = distribute_gas(host, &account, &mut state)?;
+	if let Some(from) = &from {
+		let (registered, votes) = candidate_state(host, engine, from)?.unwrap_or_default();
+		put_candidate(host, engine, from, registered, &(votes - &balance))?;
+	}
+	if let Some(public_key) =
+		vote_to.as_ref().filter(|public_key| from.as_ref() != Some(*public_key))
+	{
+		state[3] = VMStackItem::Integer(
+			host.get_integer(ID, &voter_reward_key(public_key)).unwrap_or_default(),
+		);
+	}
+	state[2] = vote_to.clone().map_or(VMStackItem::Null, VMStackItem::ByteString);
+	match (&vote_to, new_candidate) {
+		(Some(public_key), Some(votes)) => {
+			// The votes of the previous candidate changed if it's the same one
+			let votes = if from.as_ref() == Some(public_key) {
+				candidate_state(host, engine, public_key)?.unwrap_or_default().1
+			} else {
+				votes
+			};
+			put_candidate(host, engine, public_key, true, &(votes + &balance))?;
+		},
+		_ => state[3] = VMStackItem::from(0i64),
+	}
+	put_account_state(host, engine, NativeContract::NeoToken, &account, state)?;
+	let public_key_or_null =
+		|public_key: Option<Vec<u8>>| public_key.map_or(VMStackItem::Null, VMStackItem::ByteString);
+	host.notify(
+		NativeContract::NeoToken.hash(),
+		"Vote",
+		vec![
+			hash160_item(&account),
+			public_key_or_null(from),
+			public_key_or_null(vote_to),
+			VMStackItem::Integer(balance),
+		],
+	);
+	if let Some((account, amount)) = distribution {
+		mint(host, engine, NativeContract::GasToken, &account, &amount, true)?;
+	}
+	Ok(VMStackItem::Boolean(true))
+}
+
+fn set_gas_per_block(
+	host: &mut ApplicationHost,
+	engine: &mut ExecutionEngine,
+	args: Vec<VMStackItem>,
+) -> Result<VMStackItem, VMError> {
+	let gas_per_block = args[0].get_integer()?;
+	if gas_per_block.is_negative() || gas_per_block > BigInt::from(10 * GAS_FACTOR) {
+		return Err(VMError::InvalidOperation(format!("Invalid GAS per block {}", gas_per_block)));
+	}
+	check_committee(host, engine)?;
+	let index = host.persisting_block.index + 1;
+	host.put_integer(ID, &gas_per_block_key(index), &gas_per_block);
+	Ok(VMStackItem::Null)
+}
+
+fn public_key_from_bytes(bytes: &[u8]) -> Result<Secp256r1PublicKey, VMError> {
+	Secp256r1PublicKey::from_bytes(bytes)
+		.map_err(|err| VMError::InvalidOperation(format!("Invalid public key: {}", err)))
+}
+
+/// The address of the single-signature account of a public key.
+fn signature_account(public_key: &[u8]) -> Result<H160, VMError> {
+	let public_key = public_key_from_bytes(public_key)?;
+	Ok(H160::from_script(&ScriptBuilder::build_verification_script(&public_key)))
+}
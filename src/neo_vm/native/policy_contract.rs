@@ -0,0 +1,270 @@
+use num_bigint::BigInt;
+use num_traits::ToPrimitive;
+use primitive_types::H160;
+
+use crate::{
+	builder::CallFlags,
+	neo_types::ContractParameterType,
+	neo_vm::{
+		bytes_to_integer,
+		native::{check_committee, hash160_bytes, hash160_from_item, NativeContract, NativeMethod},
+		ApplicationHost, BlockchainState, ExecutionEngine, StorageKey, VMError, VMStackItem,
+	},
+};
+
+const PREFIX_BLOCKED_ACCOUNT: u8 = 15;
+const PREFIX_FEE_PER_BYTE: u8 = 10;
+const PREFIX_EXEC_FEE_FACTOR: u8 = 18;
+const PREFIX_STORAGE_PRICE: u8 = 19;
+const PREFIX_ATTRIBUTE_FEE: u8 = 20;
+
+/// The execution fee factor before the Policy contract is initialized.
+pub(crate) const DEFAULT_EXEC_FEE_FACTOR: u32 = 30;
+/// The storage price, in datoshi per byte, before the Policy contract is initialized.
+pub(crate) const DEFAULT_STORAGE_PRICE: i64 = 100_000;
+const DEFAULT_FEE_PER_BYTE: i64 = 1000;
+const MAX_EXEC_FEE_FACTOR: u32 = 100;
+const MAX_STORAGE_PRICE: i64 = 10_000_000;
+const MAX_ATTRIBUTE_FEE: i64 = 10_0000_0000;
+
+/// The transaction attribute types whose fee can be set.
+const ATTRIBUTE_TYPES: [u8; 5] = [0x01, 0x11, 0x20, 0x21, 0x22];
+
+const ID: i32 = -7;
+
+pub(super) fn methods() -> Vec<NativeMethod> {
+	type P = ContractParameterType;
+	vec![
+		NativeMethod::new(
+			"getFeePerByte",
+			vec![],
+			P::Integer,
+			1 << 15,
+			CallFlags::ReadStates,
+			|host, _, _| Ok(VMStackItem::from(fee_per_byte(&host.state))),
+		),
+		NativeMethod::new(
+			"setFeePerByte",
+			vec![("value", P::Integer)],
+			P::Void,
+			1 << 15,
+			CallFlags::States,
+			|host, engine, args| {
+				set_value(host, engine, &args[0], PREFIX_FEE_PER_BYTE, 0, 1_0000_0000)
+			},
+		),
+		NativeMethod::new(
+			"getExecFeeFactor",
+			vec![],
+			P::Integer,
+			1 << 15,
+			CallFlags::ReadStates,
+			|host, _, _| Ok(VMStackItem::from(exec_fee_factor(&host.state) as i64)),
+		),
+		NativeMethod::new(
+			"setExecFeeFactor",
+			vec![("value", P::Integer)],
+			P::Void,
+			1 << 15,
+			CallFlags::States,
+			|host, engine, args| {
+				set_value(
+					host,
+					engine,
+					&args[0],
+					PREFIX_EXEC_FEE_FACTOR,
+					1,
+					MAX_EXEC_FEE_FACTOR as i64,
+				)
+			},
+		),
+		NativeMethod::new(
+			"getStoragePrice",
+			vec![],
+			P::Integer,
+			1 << 15,
+			CallFlags::ReadStates,
+			|host, _, _| Ok(VMStackItem::from(storage_price(&host.state))),
+		),
+		NativeMethod::new(
+			"setStoragePrice",
+			vec![("value", P::Integer)],
+			P::Void,
+			1 << 15,
+			CallFlags::States,
+			|host, engine, args| {
+				set_value(host, engine, &args[0], PREFIX_STORAGE_PRICE, 1, MAX_STORAGE_PRICE)
+			},
+		),
+		NativeMethod::new(
+			"getAttributeFee",
+			vec![("attributeType", P::Integer)],
+			P::Integer,
+			1 << 15,
+			CallFlags::ReadStates,
+			get_attribute_fee,
+		),
+		NativeMethod::new(
+			"setAttributeFee",
+			vec![("attributeType", P::Integer), ("value", P::Integer)],
+			P::Void,
+			1 << 15,
+			CallFlags::States,
+			set_attribute_fee,
+		),
+		NativeMethod::new(
+			"isBlocked",
+			vec![("account", P::H160)],
+			P::Boolean,
+			1 << 15,
+			CallFlags::ReadStates,
+			|host, _, args| {
+				Ok(VMStackItem::Boolean(is_blocked(&host.state, &hash160_from_item(&args[0])?)))
+			},
+		),
+		NativeMethod::new(
+			"blockAccount",
+			vec![("account", P::H160)],
+			P::Boolean,
+			1 << 15,
+			CallFlags::States,
+			|host, engine, args| {
+				let account = hash160_from_item(&args[0])?;
+				check_committee(host, engine)?;
+				if NativeContract::from_hash(&account).is_some() {
+					return Err(VMError::InvalidOperation(
+						"Native contracts can't be blocked".to_string(),
+					));
+				}
+				Ok(VMStackItem::Boolean(block_account(host, &account)))
+			},
+		),
+		NativeMethod::new(
+			"unblockAccount",
+			vec![("account", P::H160)],
+			P::Boolean,
+			1 << 15,
+			CallFlags::States,
+			|host, engine, args| {
+				let account = hash160_from_item(&args[0])?;
+				check_committee(host, engine)?;
+				if !is_blocked(&host.state, &account) {
+					return Ok(VMStackItem::Boolean(false));
+				}
+				host.delete(ID, &blocked_key(&account));
+				Ok(VMStackItem::Boolean(true))
+			},
+		),
+	]
+}
+
+pub(super) fn initialize(host: &mut ApplicationHost) -> Result<(), VMError> {
+	host.put_integer(ID, &[PREFIX_FEE_PER_BYTE], &BigInt::from(DEFAULT_FEE_PER_BYTE));
+	host.put_integer(ID, &[PREFIX_EXEC_FEE_FACTOR], &BigInt::from(DEFAULT_EXEC_FEE_FACTOR));
+	host.put_integer(ID, &[PREFIX_STORAGE_PRICE], &BigInt::from(DEFAULT_STORAGE_PRICE));
+	Ok(())
+}
+
+fn get_integer(state: &BlockchainState, key: &[u8]) -> Option<BigInt> {
+	state
+		.get_storage(&StorageKey::new(ID, key))
+		.map(|value| bytes_to_integer(value))
+}
+
+/// The network fee per byte of a transaction, in datoshi.
+fn fee_per_byte(state: &BlockchainState) -> i64 {
+	get_integer(state, &[PREFIX_FEE_PER_BYTE])
+		.and_then(|value| value.to_i64())
+		.unwrap_or(DEFAULT_FEE_PER_BYTE)
+}
+
+/// The factor opcode and interop prices are multiplied by.
+pub(crate) fn exec_fee_factor(state: &BlockchainState) -> u32 {
+	get_integer(state, &[PREFIX_EXEC_FEE_FACTOR])
+		.and_then(|value| value.to_u32())
+		.unwrap_or(DEFAULT_EXEC_FEE_FACTOR)
+}
+
+/// The price of a byte of storage, in datoshi.
+pub(crate) fn storage_price(state: &BlockchainState) -> i64 {
+	get_integer(state, &[PREFIX_STORAGE_PRICE])
+		.and_then(|value| value.to_i64())
+		.unwrap_or(DEFAULT_STORAGE_PRICE)
+}
+
+/// The additional network fee of a transaction attribute, in datoshi.
+pub(crate) fn attribute_fee(state: &BlockchainState, attribute_type: u8) -> i64 {
+	get_integer(state, &[PREFIX_ATTRIBUTE_FEE, attribute_type])
+		.and_then(|value| value.to_i64())
+		.unwrap_or_default()
+}
+
+pub(crate) fn is_blocked(state: &BlockchainState, account: &H160) -> bool {
+	state.get_storage(&StorageKey::new(ID, blocked_key(account))).is_some()
+}
+
+/// Blocks `account`, returning `false` if it was already blocked.
+pub(crate) fn block_account(host: &mut ApplicationHost, account: &H160) -> bool {
+	if is_blocked(&host.state, account) {
+		return false;
+	}
+	host.state.put_storage(StorageKey::new(ID, blocked_key(account)), Vec::new());
+	true
+}
+
+fn blocked_key(account: &H160) -> Vec<u8> {
+	let mut key = vec![PREFIX_BLOCKED_ACCOUNT];
+	key.extend(hash160_bytes(account));
+	key
+}
+
+fn set_value(
+	host: &mut ApplicationHost,
+	engine: &mut ExecutionEngine,
+	value: &VMStackItem,
+	prefix: u8,
+	min: i64,
+	max: i64,
+) -> Result<VMStackItem, VMError> {
+	let value = value.get_integer()?;
+	if value < BigInt::from(min) || value > BigInt::from(max) {
+		return Err(VMError::InvalidOperation(format!(
+			"The value {} is outside of [{}, {}]",
+			value, min, max
+		)));
+	}
+	check_committee(host, engine)?;
+	host.put_integer(ID, &[prefix], &value);
+	Ok(VMStackItem::Null)
+}
+
+fn attribute_type(item: &VMStackItem) -> Result<u8, VMError> {
+	item.get_integer()?
+		.to_u8()
+		.filter(|attribute_type| ATTRIBUTE_TYPES.contains(attribute_type))
+		.ok_or_else(|| VMError::InvalidOperation("Invalid attribute type".to_string()))
+}
+
+fn get_attribute_fee(
+	host: &mut ApplicationHost,
+	_engine: &mut ExecutionEngine,
+	args: Vec<VMStackItem>,
+) -> Result<VMStackItem, VMError> {
+	let attribute_type = attribute_type(&args[0])?;
+	Ok(VMStackItem::from(attribute_fee(&host.state, attribute_type)))
+}
+
+fn set_attribute_fee(
+	host: &mut ApplicationHost,
+	engine: &mut ExecutionEngine,
+	args: Vec<VMStackItem>,
+) -> Result<VMStackItem, VMError> {
+	let attribute_type = attribute_type(&args[0])?;
+	let value = args[1].get_integer()?;
+	if value < BigInt::from(0) || value > BigInt::from(MAX_ATTRIBUTE_FEE) {
+		return Err(VMError::InvalidOperation(format!("Invalid attribute fee {}", value)));
+	}
+	check_committee(host, engine)?;
+	host.put_integer(ID, &[PREFIX_ATTRIBUTE_FEE, attribute_type], &value);
+	Ok(VMStackItem::Null)
+}
@@ -0,0 +1,131 @@
+use num_traits::ToPrimitive;
+
+use crate::{
+	builder::CallFlags,
+	crypto::Secp256r1PublicKey,
+	neo_types::ContractParameterType,
+	neo_vm::{
+		native::{check_committee, sort_public_keys, u32_from_item, NativeContract, NativeMethod},
+		ApplicationHost, ExecutionEngine, VMError, VMStackItem,
+	},
+};
+
+/// The roles nodes can be designated to: state validator, oracle, NeoFS alphabet node and
+/// P2P notary.
+const ROLES: [u8; 4] = [4, 8, 16, 32];
+const MAX_NODES: usize = 32;
+
+const ID: i32 = -8;
+
+pub(super) fn methods() -> Vec<NativeMethod> {
+	type P = ContractParameterType;
+	vec![
+		NativeMethod::new(
+			"getDesignatedByRole",
+			vec![("role", P::Integer), ("index", P::Integer)],
+			P::Array,
+			1 << 15,
+			CallFlags::ReadStates,
+			get_designated_by_role,
+		),
+		NativeMethod::new(
+			"designateAsRole",
+			vec![("role", P::Integer), ("nodes", P::Array)],
+			P::Void,
+			1 << 15,
+			CallFlags::States,
+			designate_as_role,
+		)
+		.with_flags(CallFlags::AllowNotify),
+	]
+}
+
+fn role(item: &VMStackItem) -> Result<u8, VMError> {
+	item.get_integer()?
+		.to_u8()
+		.filter(|role| ROLES.contains(role))
+		.ok_or_else(|| VMError::InvalidOperation("Invalid role".to_string()))
+}
+
+fn role_key(role: u8, index: u32) -> Vec<u8> {
+	let mut key = vec![role];
+	key.extend_from_slice(&index.to_be_bytes());
+	key
+}
+
+/// The public keys designated to `role` as of block `index`.
+fn designated_by_role(
+	host: &ApplicationHost,
+	engine: &ExecutionEngine,
+	role: u8,
+	index: u32,
+) -> Result<Vec<Vec<u8>>, VMError> {
+	let last = host
+		.state
+		.find_storage(ID, &[role])
+		.into_iter()
+		.rfind(|(key, _)| key.as_slice() <= role_key(role, index).as_slice());
+	let Some((key, _)) = last else { return Ok(Vec::new()) };
+	match host.get_item(engine, ID, &key)? {
+		Some(VMStackItem::Array(nodes)) =>
+			nodes.borrow().iter().map(VMStackItem::get_span).collect(),
+		_ => Ok(Vec::new()),
+	}
+}
+
+fn get_designated_by_role(
+	host: &mut ApplicationHost,
+	engine: &mut ExecutionEngine,
+	args: Vec<VMStackItem>,
+) -> Result<VMStackItem, VMError> {
+	let role = role(&args[0])?;
+	let index = u32_from_item(&args[1])?;
+	if index > host.state.height().map_or(0, |height| height + 1) {
+		return Err(VMError::InvalidOperation("The index is in the future".to_string()));
+	}
+	let nodes = designated_by_role(host, engine, role, index)?;
+	Ok(VMStackItem::new_array(nodes.into_iter().map(VMStackItem::ByteString).collect()))
+}
+
+fn designate_as_role(
+	host: &mut ApplicationHost,
+	engine: &mut ExecutionEngine,
+	args: Vec<VMStackItem>,
+) -> Result<VMStackItem, VMError> {
+	let role = role(&args[0])?;
+	let nodes = match &args[1] {
+		VMStackItem::Array(nodes) | VMStackItem::Struct(nodes) => nodes.borrow().clone(),
+		other => return Err(VMError::InvalidCast(format!("{} is not an Array", other.item_type()))),
+	};
+	if nodes.is_empty() || nodes.len() > MAX_NODES {
+		return Err(VMError::InvalidOperation(format!("Between 1 and {} nodes", MAX_NODES)));
+	}
+	let mut keys = nodes
+		.iter()
+		.map(|node| {
+			let bytes = node.get_span()?;
+			Secp256r1PublicKey::from_bytes(&bytes)
+				.map_err(|err| VMError::InvalidOperation(format!("Invalid public key: {}", err)))?;
+			Ok(bytes)
+		})
+		.collect::<Result<Vec<_>, VMError>>()?;
+	check_committee(host, engine)?;
+	let index = host.persisting_block.index + 1;
+	let key = role_key(role, index);
+	if host
+		.state
+		.get_storage(&crate::neo_vm::StorageKey::new(ID, key.clone()))
+		.is_some()
+	{
+		return Err(VMError::InvalidOperation("The role was already designated".to_string()));
+	}
+	sort_public_keys(&mut keys);
+	let nodes = VMStackItem::new_array(keys.into_iter().map(VMStackItem::ByteString).collect());
+	host.put_item(engine, ID, &key, &nodes)?;
+	host.notify(
+		NativeContract::RoleManagement.hash(),
+		"Designation",
+		vec![VMStackItem::from(role as i64), VMStackItem::from(host.persisting_block.index as i64)],
+	);
+	Ok(VMStackItem::Null)
+}
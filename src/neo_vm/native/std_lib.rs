@@ -0,0 +1,497 @@
+use base64::{engine::general_purpose, Engine};
+use num_bigint::BigInt;
+use num_traits::{Signed, ToPrimitive};
+
+use crate::{
+	builder::CallFlags,
+	crypto::{base58check_decode, base58check_encode},
+	neo_types::ContractParameterType,
+	neo_vm::{
+		native::NativeMethod, ApplicationHost, BinarySerializer, ExecutionEngine,
+		ExecutionEngineLimits, VMError, VMStackItem,
+	},
+};
+
+/// The maximum length of the input of string and encoding methods.
+const MAX_INPUT_LENGTH: usize = 1024;
+/// Integers outside of this range can't be represented exactly by JSON numbers.
+const MAX_SAFE_INTEGER: i64 = (1 << 53) - 1;
+
+pub(super) fn methods() -> Vec<NativeMethod> {
+	type P = ContractParameterType;
+	vec![
+		NativeMethod::new(
+			"serialize",
+			vec![("item", P::Any)],
+			P::ByteArray,
+			1 << 12,
+			CallFlags::None,
+			|_, engine, args| {
+				Ok(VMStackItem::ByteString(BinarySerializer::serialize(&args[0], engine.limits())?))
+			},
+		),
+		NativeMethod::new(
+			"deserialize",
+			vec![("data", P::ByteArray)],
+			P::Any,
+			1 << 14,
+			CallFlags::None,
+			|_, engine, args| BinarySerializer::deserialize(&args[0].get_span()?, engine.limits()),
+		),
+		NativeMethod::new(
+			"jsonSerialize",
+			vec![("item", P::Any)],
+			P::ByteArray,
+			1 << 12,
+			CallFlags::None,
+			|_, engine, args| {
+				let mut json = String::new();
+				write_json(&args[0], &mut json, engine.limits())?;
+				Ok(VMStackItem::ByteString(json.into_bytes()))
+			},
+		),
+		NativeMethod::new(
+			"jsonDeserialize",
+			vec![("json", P::ByteArray)],
+			P::Any,
+			1 << 14,
+			CallFlags::None,
+			|_, engine, args| {
+				let json: serde_json::Value = serde_json::from_slice(&args[0].get_span()?)
+					.map_err(|err| VMError::InvalidOperation(format!("Invalid JSON: {}", err)))?;
+				let mut count = 0;
+				json_to_item(&json, engine.limits(), 0, &mut count)
+			},
+		),
+		NativeMethod::new(
+			"itoa",
+			vec![("value", P::Integer)],
+			P::String,
+			1 << 12,
+			CallFlags::None,
+			|_, _, args| Ok(VMStackItem::from(args[0].get_integer()?.to_string().as_str())),
+		),
+		NativeMethod::new(
+			"itoa",
+			vec![("value", P::Integer), ("base", P::Integer)],
+			P::String,
+			1 << 12,
+			CallFlags::None,
+			|_, _, args| {
+				let value = args[0].get_integer()?;
+				let string = match base(&args[1])? {
+					10 => value.to_string(),
+					_ => to_hex(&value),
+				};
+				Ok(VMStackItem::from(string.as_str()))
+			},
+		),
+		NativeMethod::new(
+			"atoi",
+			vec![("value", P::String)],
+			P::Integer,
+			1 << 6,
+			CallFlags::None,
+			|_, _, args| Ok(VMStackItem::Integer(parse_decimal(&input_string(&args[0])?)?)),
+		),
+		NativeMethod::new(
+			"atoi",
+			vec![("value", P::String), ("base", P::Integer)],
+			P::Integer,
+			1 << 6,
+			CallFlags::None,
+			|_, _, args| {
+				let value = input_string(&args[0])?;
+				let value = match base(&args[1])? {
+					10 => parse_decimal(&value)?,
+					_ => parse_hex(&value)?,
+				};
+				Ok(VMStackItem::Integer(value))
+			},
+		),
+		NativeMethod::new(
+			"base64Encode",
+			vec![("data", P::ByteArray)],
+			P::String,
+			1 << 5,
+			CallFlags::None,
+			|_, _, args| {
+				Ok(VMStackItem::from(
+					general_purpose::STANDARD.encode(input_bytes(&args[0])?).as_str(),
+				))
+			},
+		),
+		NativeMethod::new(
+			"base64Decode",
+			vec![("s", P::String)],
+			P::ByteArray,
+			1 << 5,
+			CallFlags::None,
+			|_, _, args| {
+				let bytes = general_purpose::STANDARD
+					.decode(input_string(&args[0])?)
+					.map_err(|err| VMError::InvalidOperation(format!("Invalid base64: {}", err)))?;
+				Ok(VMStackItem::ByteString(bytes))
+			},
+		),
+		NativeMethod::new(
+			"base58Encode",
+			vec![("data", P::ByteArray)],
+			P::String,
+			1 << 13,
+			CallFlags::None,
+			|_, _, args| {
+				Ok(VMStackItem::from(bs58::encode(input_bytes(&args[0])?).into_string().as_str()))
+			},
+		),
+		NativeMethod::new(
+			"base58Decode",
+			vec![("s", P::String)],
+			P::ByteArray,
+			1 << 10,
+			CallFlags::None,
+			|_, _, args| {
+				let bytes = bs58::decode(input_string(&args[0])?)
+					.into_vec()
+					.map_err(|err| VMError::InvalidOperation(format!("Invalid base58: {}", err)))?;
+				Ok(VMStackItem::ByteString(bytes))
+			},
+		),
+		NativeMethod::new(
+			"base58CheckEncode",
+			vec![("data", P::ByteArray)],
+			P::String,
+			1 << 16,
+			CallFlags::None,
+			|_, _, args| {
+				Ok(VMStackItem::from(base58check_encode(&input_bytes(&args[0])?).as_str()))
+			},
+		),
+		NativeMethod::new(
+			"base58CheckDecode",
+			vec![("s", P::String)],
+			P::ByteArray,
+			1 << 16,
+			CallFlags::None,
+			|_, _, args| {
+				let bytes = base58check_decode(&input_string(&args[0])?)
+					.ok_or_else(|| VMError::InvalidOperation("Invalid base58check".to_string()))?;
+				Ok(VMStackItem::ByteString(bytes))
+			},
+		),
+		NativeMethod::new(
+			"memoryCompare",
+			vec![("str1", P::ByteArray), ("str2", P::ByteArray)],
+			P::Integer,
+			1 << 5,
+			CallFlags::None,
+			|_, _, args| {
+				let ordering = input_bytes(&args[0])?.cmp(&input_bytes(&args[1])?);
+				Ok(VMStackItem::from(ordering as i64))
+			},
+		),
+		NativeMethod::new(
+			"memorySearch",
+			vec![("mem", P::ByteArray), ("value", P::ByteArray)],
+			P::Integer,
+			1 << 6,
+			CallFlags::None,
+			memory_search,
+		),
+		NativeMethod::new(
+			"memorySearch",
+			vec![("mem", P::ByteArray), ("value", P::ByteArray), ("start", P::Integer)],
+			P::Integer,
+			1 << 6,
+			CallFlags::None,
+			memory_search,
+		),
+		NativeMethod::new(
+			"memorySearch",
+			vec![
+				("mem", P::ByteArray),
+				("value", P::ByteArray),
+				("start", P::Integer),
+				("backward", P::Boolean),
+			],
+			P::Integer,
+			1 << 6,
+			CallFlags::None,
+			memory_search,
+		),
+		NativeMethod::new(
+			"stringSplit",
+			vec![("str", P::String), ("separator", P::String)],
+			P::Array,
+			1 << 8,
+			CallFlags::None,
+			string_split,
+		),
+		NativeMethod::new(
+			"stringSplit",
+			vec![("str", P::String), ("separator", P::String), ("removeEmptyEntries", P::Boolean)],
+			P::Array,
+			1 << 8,
+			CallFlags::None,
+			string_split,
+		),
+		NativeMethod::new(
+			"strLen",
+			vec![("str", P::String)],
+			P::Integer,
+			1 << 8,
+			CallFlags::None,
+			|_, _, args| Ok(VMStackItem::from(input_string(&args[0])?.chars().count() as i64)),
+		),
+	]
+}
+
+fn input_bytes(item: &VMStackItem) -> Result<Vec<u8>, VMError> {
+	let bytes = item.get_span()?;
+	if bytes.len() > MAX_INPUT_LENGTH {
+		return Err(VMError::InvalidOperation(format!(
+			"The input exceeds {} bytes",
+			MAX_INPUT_LENGTH
+		)));
+	}
+	Ok(bytes)
+}
+
+fn input_string(item: &VMStackItem) -> Result<String, VMError> {
+	String::from_utf8(input_bytes(item)?)
+		.map_err(|_| VMError::InvalidOperation("The input isn't UTF-8".to_string()))
+}
+
+fn base(item: &VMStackItem) -> Result<u32, VMError> {
+	match item.get_integer()?.to_u32() {
+		Some(base @ (10 | 16)) => Ok(base),
+		_ => Err(VMError::InvalidOperation("The base must be 10 or 16".to_string())),
+	}
+}
+
+fn parse_decimal(value: &str) -> Result<BigInt, VMError> {
+	let digits = value.strip_prefix(['+', '-']).unwrap_or(value);
+	if digits.is_empty() || !digits.bytes().all(|byte| byte.is_ascii_digit()) {
+		return Err(VMError::InvalidOperation(format!("Invalid number {}", value)));
+	}
+	value
+		.parse()
+		.map_err(|_| VMError::InvalidOperation(format!("Invalid number {}", value)))
+}
+
+/// Parses two's complement hex digits, like `BigInteger.Parse` with `AllowHexSpecifier`.
+fn parse_hex(value: &str) -> Result<BigInt, VMError> {
+	let magnitude = BigInt::parse_bytes(value.as_bytes(), 16)
+		.filter(|_| !value.starts_with(['+', '-']))
+		.ok_or_else(|| VMError::InvalidOperation(format!("Invalid hex number {}", value)))?;
+	let negative = value
+		.chars()
+		.next()
+		.and_then(|c| c.to_digit(16))
+		.is_some_and(|digit| digit >= 8);
+	Ok(if negative { magnitude - (BigInt::from(1) << (4 * value.len())) } else { magnitude })
+}
+
+/// Formats the shortest two's complement hex digits, like `BigInteger.ToString("x")`.
+fn to_hex(value: &BigInt) -> String {
+	let mut digits: usize = 1;
+	loop {
+		let limit = BigInt::from(8) << (4 * (digits - 1));
+		if *value >= -&limit && *value < limit {
+			break;
+		}
+		digits += 1;
+	}
+	let modulus = BigInt::from(1) << (4 * digits);
+	let unsigned: BigInt = ((value % &modulus) + &modulus) % &modulus;
+	format!("{:0>width$}", unsigned.to_str_radix(16), width = digits)
+}
+
+fn memory_search(
+	_host: &mut ApplicationHost,
+	_engine: &mut ExecutionEngine,
+	args: Vec<VMStackItem>,
+) -> Result<VMStackItem, VMError> {
+	let memory = input_bytes(&args[0])?;
+	let value = args[1].get_span()?;
+	let backward = args.get(3).map(VMStackItem::get_boolean).transpose()?.unwrap_or_default();
+	let start = match args.get(2) {
+		Some(start) => start
+			.get_integer()?
+			.to_usize()
+			.filter(|start| *start <= memory.len())
+			.ok_or_else(|| VMError::InvalidOperation("Invalid start".to_string()))?,
+		None => 0,
+	};
+	let position = |haystack: &[u8]| -> Option<usize> {
+		if backward {
+			(0..=haystack.len().checked_sub(value.len())?)
+				.rev()
+				.find(|i| haystack[*i..].starts_with(&value))
+		} else {
+			(0..=haystack.len().checked_sub(value.len())?)
+				.find(|i| haystack[*i..].starts_with(&value))
+		}
+	};
+	let index = if backward {
+		position(&memory[..start])
+	} else {
+		position(&memory[start..]).map(|index| index + start)
+	};
+	Ok(VMStackItem::from(index.map_or(-1, |index| index as i64)))
+}
+
+fn string_split(
+	_host: &mut ApplicationHost,
+	engine: &mut ExecutionEngine,
+	args: Vec<VMStackItem>,
+) -> Result<VMStackItem, VMError> {
+	let string = input_string(&args[0])?;
+	let separator = args[1].get_string()?;
+	let remove_empty = args.get(2).map(VMStackItem::get_boolean).transpose()?.unwrap_or_default();
+	let parts: Vec<VMStackItem> = string
+		.split(separator.as_str())
+		.filter(|part| !remove_empty || !part.is_empty())
+		.map(VMStackItem::from)
+		.collect();
+	if parts.len() > engine.limits().max_stack_size {
+		return Err(VMError::InvalidOperation("Too many parts".to_string()));
+	}
+	Ok(VMStackItem::new_array(parts))
+}
+
+/// Writes `item` as JSON: byte strings as UTF-8 strings, maps as objects with string keys.
+fn write_json(
+	item: &VMStackItem,
+	output: &mut String,
+	limits: &ExecutionEngineLimits,
+) -> Result<(), VMError> {
+	let string = |bytes: Vec<u8>| -> Result<String, VMError> {
+		let string = String::from_utf8(bytes)
+			.map_err(|_| VMError::InvalidOperation("Strings must be UTF-8".to_string()))?;
+		serde_json::to_string(&string).map_err(|err| VMError::InvalidOperation(err.to_string()))
+	};
+	match item {
+		VMStackItem::Null => output.push_str("null"),
+		VMStackItem::Boolean(value) => output.push_str(if *value { "true" } else { "false" }),
+		VMStackItem::Integer(value) => {
+			if value.abs() > BigInt::from(MAX_SAFE_INTEGER) {
+				return Err(VMError::InvalidOperation(format!(
+					"{} can't be represented in JSON",
+					value
+				)));
+			}
+			output.push_str(&value.to_string());
+		},
+		VMStackItem::ByteString(_) | VMStackItem::Buffer(_) =>
+			output.push_str(&string(item.get_span()?)?),
+		VMStackItem::Array(items) | VMStackItem::Struct(items) => {
+			output.push('[');
+			for (index, item) in items.borrow().iter().enumerate() {
+				if index > 0 {
+					output.push(',');
+				}
+				write_json(item, output, limits)?;
+			}
+			output.push(']');
+		},
+		VMStackItem::Map(entries) => {
+			output.push('{');
+			for (index, (key, value)) in entries.borrow().iter().enumerate() {
+				if index > 0 {
+					output.push(',');
+				}
+				output.push_str(&string(key.get_span()?)?);
+				output.push(':');
+				write_json(value, output, limits)?;
+			}
+			output.push('}');
+		},
+		VMStackItem::Pointer { .. } | VMStackItem::InteropInterface(_) =>
+			return Err(VMError::InvalidOperation(format!(
+				"{} items can't be serialized to JSON",
+				item.item_type()
+			))),
+	}
+	if output.len() > limits.max_item_size {
+		return Err(VMError::ItemTooLarge(format!("JSON exceeds {} bytes", limits.max_item_size)));
+	}
+	Ok(())
+}
+
+fn json_to_item(
+	json: &serde_json::Value,
+	limits: &ExecutionEngineLimits,
+	depth: usize,
+	count: &mut usize,
+) -> Result<VMStackItem, VMError> {
+	*count += 1;
+	if depth > 10 || *count > limits.max_stack_size {
+		return Err(VMError::InvalidOperation("The JSON is too complex".to_string()));
+	}
+	Ok(match json {
+		serde_json::Value::Null => VMStackItem::Null,
+		serde_json::Value::Bool(value) => VMStackItem::Boolean(*value),
+		serde_json::Value::Number(number) => {
+			let value = number
+				.as_i64()
+				.filter(|value| value.abs() <= MAX_SAFE_INTEGER)
+				.ok_or_else(|| VMError::InvalidOperation(format!("Invalid integer {}", number)))?;
+			VMStackItem::from(value)
+		},
+		serde_json::Value::String(value) => VMStackItem::from(value.as_str()),
+		serde_json::Value::Array(values) => VMStackItem::new_array(
+			values
+				.iter()
+				.map(|value| json_to_item(value, limits, depth + 1, count))
+				.collect::<Result<_, _>>()?,
+		),
+		serde_json::Value::Object(entries) => {
+			let map = VMStackItem::new_map();
+			if let VMStackItem::Map(map_entries) = &map {
+				for (key, value) in entries {
+					let value = json_to_item(value, limits, depth + 1, count)?;
+					map_entries.borrow_mut().push((VMStackItem::from(key.as_str()), value));
+				}
+			}
+			map
+		},
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_hex_conversions() {
+		for (value, hex) in
+			[(0, "0"), (15, "0f"), (16, "10"), (255, "0ff"), (-1, "f"), (-129, "f7f")]
+		{
+			assert_eq!(to_hex(&BigInt::from(value)), hex);
+			assert_eq!(parse_hex(hex).unwrap(), BigInt::from(value));
+		}
+		assert!(parse_hex("-1").is_err());
+		assert!(parse_decimal("1e3").is_err());
+		assert_eq!(parse_decimal("-42").unwrap(), BigInt::from(-42));
+	}
+
+	#[test]
+	fn test_json_serialization() {
+		let limits = ExecutionEngineLimits::default();
+		let map = VMStackItem::new_map();
+		if let VMStackItem::Map(entries) = &map {
+			entries.borrow_mut().push((VMStackItem::from("b"), VMStackItem::from(1i64)));
+			entries.borrow_mut().push((VMStackItem::from("a"), VMStackItem::Null));
+		}
+		let item =
+			VMStackItem::new_array(vec![VMStackItem::Boolean(true), VMStackItem::from("x\""), map]);
+
+		let mut json = String::new();
+		write_json(&item, &mut json, &limits).unwrap();
+
+		assert_eq!(json, r#"[true,"x\"",{"b":1,"a":null}]"#);
+		let mut too_large = String::new();
+		assert!(write_json(&VMStackItem::from(1i64 << 60), &mut too_large, &limits).is_err());
+	}
+}
@@ -0,0 +1,439 @@
+use std::{
+	collections::{BTreeMap, HashMap},
+	fmt::Write,
+	path::Path,
+	rc::Rc,
+};
+
+use primitive_types::H160;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+	builder::InteropService,
+	neo_types::{OpCode, VMState},
+	neo_vm::{syscall_name, ApplicationEngine, ContextState, DebugInfo, Instruction},
+};
+
+/// The number of times something was executed and the GAS it consumed, in datoshi.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GasUsage {
+	pub count: u64,
+	pub gas: i64,
+}
+
+/// The GAS consumed by the calls to an interop service.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyscallUsage {
+	pub count: u64,
+	/// The fixed price of the calls, from [`InteropService::price`].
+	pub fixed_gas: i64,
+	/// The total cost of the calls, including storage fees and the prices of native methods, but
+	/// not the `SYSCALL` opcode itself.
+	pub gas: i64,
+}
+
+/// How often a conditional jump was taken.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BranchUsage {
+	pub taken: u64,
+	pub not_taken: u64,
+}
+
+/// The GAS consumed by the instructions of a contract, and the instructions it executed.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContractProfile {
+	/// The name of the contract, `None` for dynamic scripts.
+	pub name: Option<String>,
+	/// The hash of the executed script, which is the hash of the NEF script for contracts.
+	pub script_hash: H160,
+	pub gas: i64,
+	/// The GAS consumed by each method. Without debug info, methods are the ABI methods, and
+	/// private methods are counted in the ABI method they're compiled after.
+	pub methods: BTreeMap<String, GasUsage>,
+	/// The number of times the instruction at each offset was executed.
+	pub hits: BTreeMap<usize, u64>,
+	/// The conditional jumps executed, by offset.
+	pub branches: BTreeMap<usize, BranchUsage>,
+}
+
+/// Where the GAS of an execution went, as recorded by a [`Profiler`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GasReport {
+	pub gas_consumed: i64,
+	/// The price of the opcodes, by opcode name.
+	pub opcodes: BTreeMap<String, GasUsage>,
+	/// The cost of the interop services, by service name.
+	pub syscalls: BTreeMap<String, SyscallUsage>,
+	/// The GAS consumed by each contract, keyed by contract hash or, for dynamic scripts, by
+	/// script hash.
+	pub contracts: BTreeMap<H160, ContractProfile>,
+}
+
+/// Executes an [`ApplicationEngine`] instruction by instruction, attributing the GAS consumed
+/// to opcodes, interop services, contracts and methods, and recording code coverage.
+///
+/// # Examples
+///
+/// ```rust
+/// use neo3::neo_builder::ScriptBuilder;
+/// use neo3::neo_vm::{LocalBlockchain, NativeContract};
+///
+/// let chain = LocalBlockchain::new();
+/// let script = ScriptBuilder::new()
+///     .contract_call(&NativeContract::GasToken.hash(), "symbol", &[], None)
+///     .unwrap()
+///     .to_bytes();
+///
+/// let mut profiler = chain.profile_script(script, vec![]).unwrap();
+/// profiler.run();
+/// let report = profiler.report();
+/// assert!(report.syscalls.contains_key("System.Contract.Call"));
+/// println!("{}", serde_json::to_string_pretty(report).unwrap());
+/// ```
+#[derive(Debug)]
+pub struct Profiler {
+	engine: ApplicationEngine,
+	debug_info: HashMap<H160, DebugInfo>,
+	scripts: HashMap<H160, Rc<[u8]>>,
+	report: GasReport,
+}
+
+impl Profiler {
+	/// Profiles an engine whose script is loaded but hasn't started executing.
+	pub fn new(engine: ApplicationEngine) -> Self {
+		Self {
+			engine,
+			debug_info: HashMap::new(),
+			scripts: HashMap::new(),
+			report: GasReport::default(),
+		}
+	}
+
+	/// Adds the debug info of a script, used to name methods and to report coverage by line.
+	pub fn add_debug_info(&mut self, debug_info: DebugInfo) {
+		self.debug_info.insert(debug_info.hash, debug_info);
+	}
+
+	/// The engine being profiled.
+	pub fn engine(&self) -> &ApplicationEngine {
+		&self.engine
+	}
+
+	pub fn into_engine(self) -> ApplicationEngine {
+		self.engine
+	}
+
+	pub fn report(&self) -> &GasReport {
+		&self.report
+	}
+
+	/// Executes until the engine halts or faults.
+	pub fn run(&mut self) -> VMState {
+		loop {
+			let state = self.step();
+			if state == VMState::Halt || state == VMState::Fault {
+				return state;
+			}
+		}
+	}
+
+	/// Executes and profiles a single instruction.
+	pub fn step(&mut self) -> VMState {
+		let engine = self.engine.engine();
+		let Some(context) = engine.current_context() else { return self.engine.step() };
+		let depth = engine.invocation_stack().len();
+		let offset = context.instruction_pointer();
+		let script_hash = context.script_hash();
+		let state = context.state::<ContextState>();
+		let contract_hash = state.as_ref().map_or(script_hash, |state| state.script_hash);
+		let instruction = context.current_instruction().ok();
+		let method = self.method_name(state.as_deref(), &script_hash, offset);
+		self.scripts.entry(script_hash).or_insert_with(|| context.script().clone());
+		let exec_fee_factor = engine.exec_fee_factor() as i64;
+		let gas_before = engine.gas_consumed();
+
+		let vm_state = self.engine.step();
+		let engine = self.engine.engine();
+		let gas = engine.gas_consumed() - gas_before;
+		self.report.gas_consumed = engine.gas_consumed();
+
+		let contract =
+			self.report.contracts.entry(contract_hash).or_insert_with(|| ContractProfile {
+				name: state
+					.as_ref()
+					.and_then(|state| state.contract.as_ref())
+					.and_then(|contract| contract.manifest.name.clone()),
+				script_hash,
+				..Default::default()
+			});
+		contract.gas += gas;
+		*contract.hits.entry(offset).or_default() += 1;
+		let usage = contract.methods.entry(method).or_default();
+		usage.count += 1;
+		usage.gas += gas;
+
+		let Some(instruction) = instruction else { return vm_state };
+		let opcode_gas = (instruction.opcode.price() as i64 * exec_fee_factor).min(gas);
+		let usage = self
+			.report
+			.opcodes
+			.entry(format!("{:?}", instruction.opcode).to_uppercase())
+			.or_default();
+		usage.count += 1;
+		usage.gas += opcode_gas;
+
+		if instruction.opcode == OpCode::Syscall {
+			if let Ok(bytes) = <[u8; 4]>::try_from(instruction.operand.as_slice()) {
+				let method = u32::from_le_bytes(bytes);
+				let price = InteropService::from_hash(hex::encode(bytes))
+					.map_or(0, |service| service.price() as i64 * exec_fee_factor);
+				let usage = self.report.syscalls.entry(syscall_name(method)).or_default();
+				usage.count += 1;
+				usage.fixed_gas += price;
+				usage.gas += gas - opcode_gas;
+			}
+		}
+		if is_conditional_jump(instruction.opcode) && vm_state != VMState::Fault {
+			if let Some(context) = engine.invocation_stack().get(depth - 1) {
+				let branch = contract.branches.entry(offset).or_default();
+				if context.instruction_pointer() == offset + instruction.size {
+					branch.not_taken += 1;
+				} else {
+					branch.taken += 1;
+				}
+			}
+		}
+		vm_state
+	}
+
+	/// Writes the coverage of the scripts with debug info in the lcov tracefile format.
+	pub fn lcov(&self) -> String {
+		let mut lcov = String::new();
+		for profile in self.report.contracts.values() {
+			let Some(info) = self.debug_info.get(&profile.script_hash) else { continue };
+			let branch_offsets = self
+				.scripts
+				.get(&profile.script_hash)
+				.map(|script| conditional_jumps(script))
+				.unwrap_or_default();
+			for (index, document) in info.documents.iter().enumerate() {
+				let path = match &info.document_root {
+					Some(root) => Path::new(root).join(document).to_string_lossy().into_owned(),
+					None => document.clone(),
+				};
+				write_document(&mut lcov, &path, index, info, profile, &branch_offsets);
+			}
+		}
+		lcov
+	}
+
+	/// The debug info name of the method at `offset`, or the name of the ABI method it's in.
+	fn method_name(
+		&self,
+		state: Option<&ContextState>,
+		script_hash: &H160,
+		offset: usize,
+	) -> String {
+		if let Some(method) =
+			self.debug_info.get(script_hash).and_then(|info| info.method_at(offset))
+		{
+			return method.name.clone();
+		}
+		let abi = state
+			.and_then(|state| state.contract.as_ref())
+			.and_then(|contract| contract.manifest.abi.as_ref());
+		abi.and_then(|abi| {
+			abi.methods
+				.iter()
+				.filter(|method| method.offset <= offset)
+				.max_by_key(|method| method.offset)
+		})
+		.map_or_else(String::new, |method| method.name.clone())
+	}
+}
+
+fn is_conditional_jump(opcode: OpCode) -> bool {
+	(OpCode::JmpIf as u8..=OpCode::JmpLeL as u8).contains(&(opcode as u8))
+}
+
+/// The offsets of the conditional jumps of a script.
+fn conditional_jumps(script: &[u8]) -> Vec<usize> {
+	let mut offsets = Vec::new();
+	let mut offset = 0;
+	while let Ok(instruction) = Instruction::decode(script, offset) {
+		if is_conditional_jump(instruction.opcode) {
+			offsets.push(offset);
+		}
+		offset += instruction.size;
+	}
+	offsets
+}
+
+/// Writes the record of a source file of a contract.
+fn write_document(
+	lcov: &mut String,
+	path: &str,
+	document: usize,
+	info: &DebugInfo,
+	profile: &ContractProfile,
+	branch_offsets: &[usize],
+) {
+	let hits = |offset: usize| profile.hits.get(&offset).copied().unwrap_or_default();
+	let methods: Vec<_> = info
+		.methods
+		.iter()
+		.filter(|method| method.sequence_points.first().is_some_and(|p| p.document == document))
+		.collect();
+	let mut lines = BTreeMap::<u32, u64>::new();
+	for point in methods.iter().flat_map(|method| &method.sequence_points) {
+		if point.document == document {
+			*lines.entry(point.start_line).or_default() += hits(point.address);
+		}
+	}
+	if lines.is_empty() {
+		return;
+	}
+
+	let _ = writeln!(lcov, "TN:");
+	let _ = writeln!(lcov, "SF:{}", path);
+	for method in &methods {
+		let line = method.sequence_points[0].start_line;
+		let _ = writeln!(lcov, "FN:{},{}", line, method.name);
+	}
+	for method in &methods {
+		let _ = writeln!(lcov, "FNDA:{},{}", hits(method.start), method.name);
+	}
+	let _ = writeln!(lcov, "FNF:{}", methods.len());
+	let _ =
+		writeln!(lcov, "FNH:{}", methods.iter().filter(|method| hits(method.start) > 0).count());
+
+	let (mut found, mut hit) = (0, 0);
+	for &offset in branch_offsets {
+		let Some(point) = info.sequence_point_at(offset).filter(|p| p.document == document) else {
+			continue;
+		};
+		let usage = profile.branches.get(&offset);
+		for (branch, count) in [(0, usage.map(|u| u.taken)), (1, usage.map(|u| u.not_taken))] {
+			let count = count.map_or_else(|| "-".to_string(), |count| count.to_string());
+			let _ = writeln!(lcov, "BRDA:{},{},{},{}", point.start_line, offset, branch, count);
+			found += 1;
+			hit += usize::from(count != "-" && count != "0");
+		}
+	}
+	let _ = writeln!(lcov, "BRF:{}", found);
+	let _ = writeln!(lcov, "BRH:{}", hit);
+
+	for (line, count) in &lines {
+		let _ = writeln!(lcov, "DA:{},{}", line, count);
+	}
+	let _ = writeln!(lcov, "LF:{}", lines.len());
+	let _ = writeln!(lcov, "LH:{}", lines.values().filter(|&&count| count > 0).count());
+	let _ = writeln!(lcov, "end_of_record");
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		neo_types::{ContractParameter, ScriptHashExtension},
+		neo_vm::{
+			debug_info::tests::debug_json, debugger::tests::debugged_contract, LocalBlockchain,
+			NativeContract,
+		},
+	};
+
+	fn profiler() -> (Profiler, H160) {
+		let mut chain = LocalBlockchain::new();
+		let (nef, manifest) = debugged_contract();
+		let sender = chain.settings().validators_address();
+		let contract = chain.deploy(&nef, &manifest, &sender, None).unwrap();
+		let profiler = chain
+			.profile_function(&contract, "main", &[ContractParameter::integer(21)], vec![])
+			.unwrap();
+		(profiler, contract)
+	}
+
+	#[test]
+	fn test_gas_attribution() {
+		let (mut profiler, contract) = profiler();
+		assert_eq!(profiler.run(), VMState::Halt);
+		let report = profiler.report().clone();
+
+		assert_eq!(report.gas_consumed, profiler.engine().engine().gas_consumed());
+		let opcode_gas: i64 = report.opcodes.values().map(|usage| usage.gas).sum();
+		let syscall_gas: i64 = report.syscalls.values().map(|usage| usage.gas).sum();
+		assert_eq!(opcode_gas + syscall_gas, report.gas_consumed);
+		let contract_gas: i64 = report.contracts.values().map(|profile| profile.gas).sum();
+		assert_eq!(contract_gas, report.gas_consumed);
+
+		let factor = profiler.engine().engine().exec_fee_factor() as i64;
+		let call = report.syscalls["System.Contract.Call"];
+		assert_eq!(call.count, 1);
+		assert_eq!(call.fixed_gas, (1 << 15) * factor);
+		assert!(call.gas >= call.fixed_gas);
+		assert_eq!(report.opcodes["LDARG0"].count, 3);
+		assert_eq!(report.opcodes["ADD"], GasUsage { count: 1, gas: (1 << 3) * factor });
+
+		let profile = &report.contracts[&contract];
+		assert_eq!(profile.name.as_deref(), Some("Debugged"));
+		// Without debug info, the private method is counted in `main`
+		assert_eq!(profile.methods.keys().collect::<Vec<_>>(), vec!["main"]);
+		assert_eq!(profile.methods["main"].count, 11);
+		assert_eq!(profile.hits.get(&9), None);
+		assert_eq!(profile.hits[&13], 1);
+
+		let json = serde_json::to_string(&report).unwrap();
+		assert_eq!(serde_json::from_str::<GasReport>(&json).unwrap(), report);
+	}
+
+	#[test]
+	fn test_native_method_gas() {
+		let chain = LocalBlockchain::new();
+		let alice = H160::repeat_byte(0xaa);
+		let mut profiler = chain
+			.profile_function(
+				&NativeContract::GasToken.hash(),
+				"balanceOf",
+				&[ContractParameter::h160(&alice)],
+				vec![],
+			)
+			.unwrap();
+		profiler.run();
+		let profile = &profiler.report().contracts[&NativeContract::GasToken.hash()];
+		assert_eq!(profile.methods.keys().collect::<Vec<_>>(), vec!["balanceOf"]);
+		assert!(profiler.report().syscalls.contains_key("System.Contract.CallNative"));
+	}
+
+	#[test]
+	fn test_lcov() {
+		let (mut profiler, _) = profiler();
+		let (nef, _) = debugged_contract();
+		let mut json = debug_json(&H160::from_script(nef.script()));
+		json = json.replace(r#""C:\\src\\Contract.cs""#, r#""Contract.cs""#);
+		profiler.add_debug_info(DebugInfo::from_json(&json).unwrap());
+		profiler.run();
+
+		let lcov = profiler.lcov();
+		assert_eq!(
+			lcov,
+			"TN:\nSF:Contract.cs\nFN:1,main\nFN:7,double\nFNDA:1,main\nFNDA:1,double\nFNF:2\nFNH:2\n\
+			 BRF:0\nBRH:0\nDA:1,1\nDA:2,1\nDA:3,1\nDA:4,1\nDA:7,1\nDA:8,1\nLF:6\nLH:6\n\
+			 end_of_record\n"
+		);
+		let profile = profiler.report().contracts.values().find(|p| p.name.is_some()).unwrap();
+		assert_eq!(profile.methods["double"].count, 5);
+	}
+
+	#[test]
+	fn test_branch_coverage() {
+		// PUSH1 JMPIF +3 PUSH2 PUSH3 RET: the jump is taken and skips PUSH2
+		let script = vec![0x11, OpCode::JmpIf as u8, 3, 0x12, 0x13, 0x40];
+		let chain = LocalBlockchain::new();
+		let mut profiler = chain.profile_script(script.clone(), vec![]).unwrap();
+		profiler.run();
+		let profile = &profiler.report().contracts[&H160::from_script(&script)];
+		assert_eq!(profile.branches[&1], BranchUsage { taken: 1, not_taken: 0 });
+		assert_eq!(profile.hits.get(&3), None);
+		assert_eq!(conditional_jumps(&script), vec![1]);
+	}
+}
@@ -0,0 +1,98 @@
+use std::{
+	any::Any,
+	collections::HashMap,
+	rc::{Rc, Weak},
+};
+
+use crate::neo_vm::VMStackItem;
+
+/// Counts the items referenced by the stacks and slots of an
+/// [`ExecutionEngine`](crate::neo_vm::ExecutionEngine), like the `ReferenceCounter` of the C#
+/// NeoVM.
+///
+/// Every reference from a stack, a slot or a compound item counts once. The count is updated when
+/// items are pushed, popped, stored, and added to or removed from compound items. The items of a
+/// compound are counted when it is first referenced, and stay counted after the compound becomes
+/// unreachable, until [`recount`](Self::recount) walks the items that are still reachable.
+#[derive(Debug, Default)]
+pub struct ReferenceCounter {
+	references: usize,
+	// The compounds whose items are counted. The weak references keep their addresses from being
+	// reused by other compounds.
+	tracked: HashMap<*const (), Weak<dyn Any>>,
+}
+
+impl ReferenceCounter {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// The number of references, including the items of the compounds that became unreachable
+	/// since the last recount.
+	pub fn count(&self) -> usize {
+		self.references
+	}
+
+	/// The number of compounds whose items are counted.
+	pub fn tracked_count(&self) -> usize {
+		self.tracked.len()
+	}
+
+	/// Counts a reference to `item`, and the items of `item` if it is a compound referenced for
+	/// the first time.
+	pub fn add_reference(&mut self, item: &VMStackItem) {
+		self.add_references(item, 1);
+	}
+
+	/// Counts `count` references to `item`, see [`add_reference`](Self::add_reference).
+	pub fn add_references(&mut self, item: &VMStackItem, count: usize) {
+		self.references += count;
+		let mut pending = vec![item.clone()];
+		while let Some(item) = pending.pop() {
+			match &item {
+				VMStackItem::Array(items) | VMStackItem::Struct(items) if self.track(items) => {
+					let items = items.borrow();
+					self.references += items.len();
+					pending.extend(items.iter().filter(|item| is_compound(item)).cloned());
+				},
+				VMStackItem::Map(entries) if self.track(entries) => {
+					let entries = entries.borrow();
+					self.references += entries.len() * 2;
+					let values = entries.iter().map(|(_, value)| value);
+					pending.extend(values.filter(|value| is_compound(value)).cloned());
+				},
+				_ => {},
+			}
+		}
+	}
+
+	/// Removes `count` references. The items of compounds stay counted until the next recount.
+	pub fn remove_references(&mut self, count: usize) {
+		self.references = self.references.saturating_sub(count);
+	}
+
+	/// Counts the references again from `roots`, the items of the stacks and slots, and forgets
+	/// the compounds that are no longer reachable. Returns the exact count.
+	pub fn recount<'a>(&mut self, roots: impl IntoIterator<Item = &'a VMStackItem>) -> usize {
+		self.references = 0;
+		self.tracked.clear();
+		for item in roots {
+			self.add_reference(item);
+		}
+		self.references
+	}
+
+	/// Tracks `compound`, returning `false` if it was already tracked.
+	fn track<T: Any>(&mut self, compound: &Rc<T>) -> bool {
+		let key = Rc::as_ptr(compound) as *const ();
+		if self.tracked.contains_key(&key) {
+			return false;
+		}
+		self.tracked.insert(key, Rc::downgrade(compound) as Weak<dyn Any>);
+		true
+	}
+}
+
+fn is_compound(item: &VMStackItem) -> bool {
+	matches!(item, VMStackItem::Array(_) | VMStackItem::Struct(_) | VMStackItem::Map(_))
+}
@@ -0,0 +1,49 @@
+use crate::neo_vm::{VMError, VMStackItem};
+
+/// A fixed-size group of variables: static fields, local variables or arguments.
+#[derive(Debug, Clone)]
+pub struct Slot {
+	items: Vec<VMStackItem>,
+}
+
+impl Slot {
+	/// Creates a slot with `count` variables initialized to `Null`.
+	pub fn new(count: usize) -> Self {
+		Self { items: vec![VMStackItem::Null; count] }
+	}
+
+	/// Creates a slot holding the given values.
+	pub fn with_items(items: Vec<VMStackItem>) -> Self {
+		Self { items }
+	}
+
+	pub fn len(&self) -> usize {
+		self.items.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.items.is_empty()
+	}
+
+	pub fn get(&self, index: usize) -> Result<&VMStackItem, VMError> {
+		self.items.get(index).ok_or_else(|| {
+			VMError::InvalidOperation(format!("Slot index {} is out of range", index))
+		})
+	}
+
+	pub fn set(&mut self, index: usize, item: VMStackItem) -> Result<(), VMError> {
+		let len = self.items.len();
+		let slot = self.items.get_mut(index).ok_or_else(|| {
+			VMError::InvalidOperation(format!(
+				"Slot index {} is out of range for a slot of size {}",
+				index, len
+			))
+		})?;
+		*slot = item;
+		Ok(())
+	}
+
+	pub fn items(&self) -> &[VMStackItem] {
+		&self.items
+	}
+}
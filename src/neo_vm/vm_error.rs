@@ -0,0 +1,59 @@
+use primitive_types::H160;
+use thiserror::Error;
+
+/// Errors raised while executing a script in the local NeoVM.
+///
+/// Every variant except [`VMError::Catchable`] puts the engine into the `FAULT` state.
+/// `Catchable` errors are turned into a VM exception that a surrounding `TRY` block can handle,
+/// mirroring the behaviour of the reference C# implementation.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum VMError {
+	#[error("Invalid opcode 0x{0:02X}")]
+	InvalidOpCode(u8),
+	#[error("Invalid instruction at offset {0}: {1}")]
+	InvalidInstruction(usize, String),
+	#[error("Stack underflow")]
+	StackUnderflow,
+	#[error("Stack overflow: {0}")]
+	StackOverflow(String),
+	#[error("Invalid cast: {0}")]
+	InvalidCast(String),
+	#[error("Invalid operation: {0}")]
+	InvalidOperation(String),
+	#[error("Item size exceeds limit: {0}")]
+	ItemTooLarge(String),
+	#[error("Insufficient GAS: consumed {consumed}, limit {limit}")]
+	InsufficientGas { consumed: i64, limit: i64 },
+	#[error("Unsupported syscall: {0}")]
+	UnsupportedSyscall(String),
+	#[error("ABORT is executed{0}")]
+	Abort(String),
+	#[error("ASSERT is executed with false result{0}")]
+	AssertFailed(String),
+	#[error("An unhandled exception was thrown: {0}")]
+	UnhandledException(String),
+	#[error("{0}")]
+	Catchable(String),
+}
+
+/// Errors raised while loading the state of a forked chain.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ForkError {
+	#[error("RPC request failed: {0}")]
+	Provider(String),
+	#[error("Invalid state returned by the node: {0}")]
+	InvalidState(String),
+	#[error(transparent)]
+	VM(#[from] VMError),
+}
+
+/// Errors raised while loading debug information.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum DebugInfoError {
+	#[error("Invalid debug info: {0}")]
+	InvalidDebugInfo(String),
+	#[error("Invalid .nefdbgnfo archive: {0}")]
+	InvalidArchive(String),
+	#[error("The debug info is for script {expected:?}, not {actual:?}")]
+	ScriptMismatch { expected: H160, actual: H160 },
+}
@@ -0,0 +1,606 @@
+use std::{any::Any, cell::RefCell, fmt, rc::Rc};
+
+use num_bigint::{BigInt, Sign};
+use num_enum::TryFromPrimitive;
+use strum_macros::Display;
+
+use crate::{
+	neo_crypto::utils::FromBase64String,
+	neo_types::{MapEntry, StackItem},
+	neo_vm::{ExecutionEngineLimits, VMError},
+};
+
+/// Maximum size in bytes of a NeoVM integer.
+pub const MAX_INTEGER_SIZE: usize = 32;
+
+/// Maximum size in bytes of a map key.
+pub const MAX_KEY_SIZE: usize = 64;
+
+/// Maximum number of nested compound items converted to and from [`StackItem`]s.
+///
+/// The conversions are recursive, so the depth is bounded well below what the stack allows.
+pub const MAX_STACK_ITEM_DEPTH: usize = 128;
+
+/// The type tag of an item on the NeoVM stack.
+///
+/// The discriminants match the values used by the NeoVM (`ISTYPE`, `CONVERT`, `NEWARRAY_T`)
+/// and by the binary serialization format.
+#[derive(Display, TryFromPrimitive, Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum StackItemType {
+	Any = 0x00,
+	Pointer = 0x10,
+	Boolean = 0x20,
+	Integer = 0x21,
+	ByteString = 0x28,
+	Buffer = 0x30,
+	Array = 0x40,
+	Struct = 0x41,
+	Map = 0x48,
+	InteropInterface = 0x60,
+}
+
+/// An object that can be placed on the stack as an `InteropInterface` item.
+///
+/// Interop services use this to hand opaque host objects (iterators, storage contexts, ...)
+/// to a running script.
+pub trait InteropObject: fmt::Debug {
+	/// The interface name reported when the item is converted to a [`StackItem`].
+	fn interface_name(&self) -> &str;
+
+	/// Gives access to the concrete type for downcasting.
+	fn as_any(&self) -> &dyn Any;
+}
+
+/// An item on the evaluation stack of the local NeoVM.
+///
+/// Unlike [`StackItem`], which is the JSON representation returned by RPC nodes,
+/// `VMStackItem` keeps the runtime semantics of the NeoVM: integers are arbitrary precision,
+/// and `Buffer`, `Array`, `Struct` and `Map` are reference types shared between all copies.
+#[derive(Clone)]
+pub enum VMStackItem {
+	Null,
+	Boolean(bool),
+	Integer(BigInt),
+	ByteString(Vec<u8>),
+	Buffer(Rc<RefCell<Vec<u8>>>),
+	Pointer { script: Rc<[u8]>, position: usize },
+	Array(Rc<RefCell<Vec<VMStackItem>>>),
+	Struct(Rc<RefCell<Vec<VMStackItem>>>),
+	Map(Rc<RefCell<Vec<(VMStackItem, VMStackItem)>>>),
+	InteropInterface(Rc<dyn InteropObject>),
+}
+
+impl VMStackItem {
+	/// Creates a new `Array` item holding `items`.
+	pub fn new_array(items: Vec<VMStackItem>) -> Self {
+		VMStackItem::Array(Rc::new(RefCell::new(items)))
+	}
+
+	/// Creates a new `Struct` item holding `items`.
+	pub fn new_struct(items: Vec<VMStackItem>) -> Self {
+		VMStackItem::Struct(Rc::new(RefCell::new(items)))
+	}
+
+	/// Creates a new, empty `Map` item.
+	pub fn new_map() -> Self {
+		VMStackItem::Map(Rc::new(RefCell::new(Vec::new())))
+	}
+
+	/// Creates a new `Buffer` item holding a copy of `bytes`.
+	pub fn new_buffer(bytes: Vec<u8>) -> Self {
+		VMStackItem::Buffer(Rc::new(RefCell::new(bytes)))
+	}
+
+	/// Wraps a host object in an `InteropInterface` item.
+	pub fn new_interop<T: InteropObject + 'static>(object: T) -> Self {
+		VMStackItem::InteropInterface(Rc::new(object))
+	}
+
+	/// Returns the NeoVM type of the item.
+	pub fn item_type(&self) -> StackItemType {
+		match self {
+			VMStackItem::Null => StackItemType::Any,
+			VMStackItem::Boolean(_) => StackItemType::Boolean,
+			VMStackItem::Integer(_) => StackItemType::Integer,
+			VMStackItem::ByteString(_) => StackItemType::ByteString,
+			VMStackItem::Buffer(_) => StackItemType::Buffer,
+			VMStackItem::Pointer { .. } => StackItemType::Pointer,
+			VMStackItem::Array(_) => StackItemType::Array,
+			VMStackItem::Struct(_) => StackItemType::Struct,
+			VMStackItem::Map(_) => StackItemType::Map,
+			VMStackItem::InteropInterface(_) => StackItemType::InteropInterface,
+		}
+	}
+
+	pub fn is_null(&self) -> bool {
+		matches!(self, VMStackItem::Null)
+	}
+
+	/// Returns `true` for `Boolean`, `Integer` and `ByteString` items, the only types allowed as map keys.
+	pub fn is_primitive(&self) -> bool {
+		matches!(
+			self,
+			VMStackItem::Boolean(_) | VMStackItem::Integer(_) | VMStackItem::ByteString(_)
+		)
+	}
+
+	/// Converts the item to a boolean following the NeoVM rules.
+	pub fn get_boolean(&self) -> Result<bool, VMError> {
+		match self {
+			VMStackItem::Null => Ok(false),
+			VMStackItem::Boolean(value) => Ok(*value),
+			VMStackItem::Integer(value) => Ok(value.sign() != Sign::NoSign),
+			VMStackItem::ByteString(bytes) => {
+				if bytes.len() > MAX_INTEGER_SIZE {
+					return Err(VMError::InvalidCast(format!(
+						"ByteString of size {} can't be converted to Boolean",
+						bytes.len()
+					)));
+				}
+				Ok(bytes.iter().any(|b| *b != 0))
+			},
+			_ => Ok(true),
+		}
+	}
+
+	/// Converts the item to an integer following the NeoVM rules.
+	pub fn get_integer(&self) -> Result<BigInt, VMError> {
+		match self {
+			VMStackItem::Boolean(value) => Ok(BigInt::from(*value as u8)),
+			VMStackItem::Integer(value) => Ok(value.clone()),
+			VMStackItem::ByteString(bytes) => {
+				if bytes.len() > MAX_INTEGER_SIZE {
+					return Err(VMError::InvalidCast(format!(
+						"ByteString of size {} can't be converted to Integer",
+						bytes.len()
+					)));
+				}
+				Ok(bytes_to_integer(bytes))
+			},
+			other => Err(VMError::InvalidCast(format!(
+				"{} can't be converted to Integer",
+				other.item_type()
+			))),
+		}
+	}
+
+	/// Returns the byte representation of a primitive or `Buffer` item.
+	pub fn get_span(&self) -> Result<Vec<u8>, VMError> {
+		match self {
+			VMStackItem::Boolean(value) => Ok(vec![*value as u8]),
+			VMStackItem::Integer(value) => Ok(integer_to_bytes(value)),
+			VMStackItem::ByteString(bytes) => Ok(bytes.clone()),
+			VMStackItem::Buffer(buffer) => Ok(buffer.borrow().clone()),
+			other => Err(VMError::InvalidCast(format!(
+				"{} can't be converted to a byte array",
+				other.item_type()
+			))),
+		}
+	}
+
+	/// Interprets the byte representation of the item as a UTF-8 string.
+	pub fn get_string(&self) -> Result<String, VMError> {
+		String::from_utf8(self.get_span()?)
+			.map_err(|e| VMError::InvalidCast(format!("Invalid UTF-8 string: {}", e)))
+	}
+
+	/// Returns the value of the `SIZE` opcode for this item.
+	pub fn size(&self) -> Result<usize, VMError> {
+		match self {
+			VMStackItem::Array(items) | VMStackItem::Struct(items) => Ok(items.borrow().len()),
+			VMStackItem::Map(entries) => Ok(entries.borrow().len()),
+			VMStackItem::Buffer(buffer) => Ok(buffer.borrow().len()),
+			VMStackItem::Boolean(_) | VMStackItem::Integer(_) | VMStackItem::ByteString(_) =>
+				Ok(self.get_span()?.len()),
+			other => Err(VMError::InvalidCast(format!("{} has no size", other.item_type()))),
+		}
+	}
+
+	/// Compares two items with the semantics of the `EQUAL` opcode.
+	pub fn equals(
+		&self,
+		other: &VMStackItem,
+		limits: &ExecutionEngineLimits,
+	) -> Result<bool, VMError> {
+		let mut budget = limits.max_stack_size;
+		self.equals_bounded(other, limits, &mut budget)
+	}
+
+	fn equals_bounded(
+		&self,
+		other: &VMStackItem,
+		limits: &ExecutionEngineLimits,
+		budget: &mut usize,
+	) -> Result<bool, VMError> {
+		if *budget == 0 {
+			return Err(VMError::InvalidOperation("Too many struct items to compare".to_string()));
+		}
+		*budget -= 1;
+		match (self, other) {
+			(VMStackItem::Null, VMStackItem::Null) => Ok(true),
+			(VMStackItem::Boolean(a), VMStackItem::Boolean(b)) => Ok(a == b),
+			(VMStackItem::Integer(a), VMStackItem::Integer(b)) => Ok(a == b),
+			(VMStackItem::ByteString(a), VMStackItem::ByteString(b)) => {
+				if a.len() > limits.max_comparable_size || b.len() > limits.max_comparable_size {
+					return Err(VMError::InvalidOperation(
+						"The operand exceeds the maximum comparable size".to_string(),
+					));
+				}
+				Ok(a == b)
+			},
+			(VMStackItem::Buffer(a), VMStackItem::Buffer(b)) => Ok(Rc::ptr_eq(a, b)),
+			(VMStackItem::Array(a), VMStackItem::Array(b)) => Ok(Rc::ptr_eq(a, b)),
+			(VMStackItem::Map(a), VMStackItem::Map(b)) => Ok(Rc::ptr_eq(a, b)),
+			(VMStackItem::Struct(a), VMStackItem::Struct(b)) => {
+				if Rc::ptr_eq(a, b) {
+					return Ok(true);
+				}
+				let (a, b) = (a.borrow(), b.borrow());
+				if a.len() != b.len() {
+					return Ok(false);
+				}
+				for (x, y) in a.iter().zip(b.iter()) {
+					if !x.equals_bounded(y, limits, budget)? {
+						return Ok(false);
+					}
+				}
+				Ok(true)
+			},
+			(
+				VMStackItem::Pointer { script: s1, position: p1 },
+				VMStackItem::Pointer { script: s2, position: p2 },
+			) => Ok(Rc::ptr_eq(s1, s2) && p1 == p2),
+			(VMStackItem::InteropInterface(a), VMStackItem::InteropInterface(b)) =>
+				Ok(std::ptr::addr_eq(Rc::as_ptr(a), Rc::as_ptr(b))),
+			_ => Ok(false),
+		}
+	}
+
+	/// Compares two map keys. Keys are always primitive items.
+	pub(crate) fn key_equals(&self, other: &VMStackItem) -> bool {
+		match (self, other) {
+			(VMStackItem::Boolean(a), VMStackItem::Boolean(b)) => a == b,
+			(VMStackItem::Integer(a), VMStackItem::Integer(b)) => a == b,
+			(VMStackItem::ByteString(a), VMStackItem::ByteString(b)) => a == b,
+			_ => false,
+		}
+	}
+
+	/// Checks that the item can be used as a map key.
+	pub(crate) fn check_map_key(&self) -> Result<(), VMError> {
+		if !self.is_primitive() {
+			return Err(VMError::InvalidCast(format!(
+				"{} can't be used as a map key",
+				self.item_type()
+			)));
+		}
+		if self.get_span()?.len() > MAX_KEY_SIZE {
+			return Err(VMError::InvalidOperation(format!(
+				"Map keys can't be larger than {} bytes",
+				MAX_KEY_SIZE
+			)));
+		}
+		Ok(())
+	}
+
+	/// Converts the item to another type with the semantics of the `CONVERT` opcode.
+	pub fn convert_to(&self, target: StackItemType) -> Result<VMStackItem, VMError> {
+		if target == self.item_type() {
+			return Ok(self.clone());
+		}
+		if target == StackItemType::Boolean {
+			return Ok(VMStackItem::Boolean(self.get_boolean()?));
+		}
+		let invalid = || {
+			VMError::InvalidCast(format!("{} can't be converted to {}", self.item_type(), target))
+		};
+		match self {
+			VMStackItem::Null => match target {
+				StackItemType::Any => Err(invalid()),
+				_ => Ok(VMStackItem::Null),
+			},
+			VMStackItem::Boolean(_) | VMStackItem::Integer(_) | VMStackItem::ByteString(_) =>
+				match target {
+					StackItemType::Integer => Ok(VMStackItem::Integer(self.get_integer()?)),
+					StackItemType::ByteString => Ok(VMStackItem::ByteString(self.get_span()?)),
+					StackItemType::Buffer => Ok(VMStackItem::new_buffer(self.get_span()?)),
+					_ => Err(invalid()),
+				},
+			VMStackItem::Buffer(buffer) => match target {
+				StackItemType::Integer => {
+					let buffer = buffer.borrow();
+					if buffer.len() > MAX_INTEGER_SIZE {
+						return Err(invalid());
+					}
+					Ok(VMStackItem::Integer(bytes_to_integer(&buffer)))
+				},
+				StackItemType::ByteString => Ok(VMStackItem::ByteString(buffer.borrow().clone())),
+				_ => Err(invalid()),
+			},
+			VMStackItem::Array(items) if target == StackItemType::Struct =>
+				Ok(VMStackItem::new_struct(items.borrow().clone())),
+			VMStackItem::Struct(items) if target == StackItemType::Array =>
+				Ok(VMStackItem::new_array(items.borrow().clone())),
+			_ => Err(invalid()),
+		}
+	}
+
+	/// Returns a copy of a `Struct` item, recursively copying nested structs.
+	///
+	/// Any other item is returned unchanged, which gives structs their value semantics when
+	/// they are stored in arrays or maps.
+	pub fn clone_struct(&self, limits: &ExecutionEngineLimits) -> Result<VMStackItem, VMError> {
+		let mut budget = limits.max_stack_size;
+		self.clone_struct_bounded(&mut budget)
+	}
+
+	fn clone_struct_bounded(&self, budget: &mut usize) -> Result<VMStackItem, VMError> {
+		match self {
+			VMStackItem::Struct(items) => {
+				let mut copy = Vec::with_capacity(items.borrow().len());
+				for item in items.borrow().iter() {
+					if *budget == 0 {
+						return Err(VMError::InvalidOperation(
+							"Beyond struct subitem clone limits".to_string(),
+						));
+					}
+					*budget -= 1;
+					copy.push(item.clone_struct_bounded(budget)?);
+				}
+				Ok(VMStackItem::new_struct(copy))
+			},
+			other => Ok(other.clone()),
+		}
+	}
+
+	/// Converts the item to the JSON-oriented [`StackItem`] used by the RPC layer.
+	///
+	/// Integers that do not fit into an `i64` are returned as a `ByteString` holding their
+	/// little-endian two's complement encoding. Circular references and items nested deeper
+	/// than [`MAX_STACK_ITEM_DEPTH`] are cut with `Any`.
+	pub fn to_stack_item(&self) -> StackItem {
+		// Cutting deep items never fails
+		self.to_stack_item_inner(&mut Vec::new(), true).unwrap_or(StackItem::Any)
+	}
+
+	/// Converts the item like [`to_stack_item`](Self::to_stack_item), but fails on items
+	/// nested deeper than [`MAX_STACK_ITEM_DEPTH`] instead of cutting them.
+	pub fn try_to_stack_item(&self) -> Result<StackItem, VMError> {
+		self.to_stack_item_inner(&mut Vec::new(), false)
+	}
+
+	fn to_stack_item_inner(
+		&self,
+		path: &mut Vec<*const ()>,
+		cut_deep_items: bool,
+	) -> Result<StackItem, VMError> {
+		let ptr = match self {
+			VMStackItem::Array(items) | VMStackItem::Struct(items) =>
+				Rc::as_ptr(items) as *const (),
+			VMStackItem::Map(entries) => Rc::as_ptr(entries) as *const (),
+			_ => std::ptr::null(),
+		};
+		if !ptr.is_null() {
+			if path.contains(&ptr) {
+				return Ok(StackItem::Any);
+			}
+			if path.len() >= MAX_STACK_ITEM_DEPTH {
+				return if cut_deep_items {
+					Ok(StackItem::Any)
+				} else {
+					Err(VMError::InvalidCast(format!(
+						"Items nested deeper than {} can't be converted",
+						MAX_STACK_ITEM_DEPTH
+					)))
+				};
+			}
+		}
+		Ok(match self {
+			VMStackItem::Null => StackItem::Any,
+			VMStackItem::Boolean(value) => StackItem::Boolean { value: *value },
+			VMStackItem::Integer(value) => match i64::try_from(value) {
+				Ok(value) => StackItem::Integer { value },
+				Err(_) => StackItem::new_byte_string(integer_to_bytes(value)),
+			},
+			VMStackItem::ByteString(bytes) => StackItem::new_byte_string(bytes.clone()),
+			VMStackItem::Buffer(buffer) =>
+				match StackItem::new_byte_string(buffer.borrow().clone()) {
+					StackItem::ByteString { value } => StackItem::Buffer { value },
+					other => other,
+				},
+			VMStackItem::Pointer { position, .. } => StackItem::Pointer { value: *position as i64 },
+			VMStackItem::Array(items) | VMStackItem::Struct(items) => {
+				path.push(ptr);
+				let value = items
+					.borrow()
+					.iter()
+					.map(|item| item.to_stack_item_inner(path, cut_deep_items))
+					.collect::<Result<_, _>>()?;
+				path.pop();
+				if matches!(self, VMStackItem::Array(_)) {
+					StackItem::Array { value }
+				} else {
+					StackItem::Struct { value }
+				}
+			},
+			VMStackItem::Map(entries) => {
+				path.push(ptr);
+				let value = entries
+					.borrow()
+					.iter()
+					.map(|(key, value)| {
+						Ok(MapEntry::new(
+							key.to_stack_item_inner(path, cut_deep_items)?,
+							value.to_stack_item_inner(path, cut_deep_items)?,
+						))
+					})
+					.collect::<Result<_, VMError>>()?;
+				path.pop();
+				StackItem::Map { value }
+			},
+			VMStackItem::InteropInterface(object) => StackItem::InteropInterface {
+				id: String::new(),
+				interface: object.interface_name().to_string(),
+			},
+		})
+	}
+
+	/// Builds a stack item from the JSON-oriented [`StackItem`] representation.
+	///
+	/// `Pointer` and `InteropInterface` items can't be reconstructed and are rejected, as are
+	/// items nested deeper than [`MAX_STACK_ITEM_DEPTH`].
+	pub fn from_stack_item(item: &StackItem) -> Result<VMStackItem, VMError> {
+		Self::from_stack_item_inner(item, 0)
+	}
+
+	fn from_stack_item_inner(item: &StackItem, depth: usize) -> Result<VMStackItem, VMError> {
+		let compound = matches!(
+			item,
+			StackItem::Array { .. } | StackItem::Struct { .. } | StackItem::Map { .. }
+		);
+		if compound && depth >= MAX_STACK_ITEM_DEPTH {
+			return Err(VMError::InvalidCast(format!(
+				"Items nested deeper than {} can't be converted",
+				MAX_STACK_ITEM_DEPTH
+			)));
+		}
+		let convert = |item| Self::from_stack_item_inner(item, depth + 1);
+		let decode = |value: &str| {
+			value
+				.trim_end()
+				.from_base64_string()
+				.map_err(|e| VMError::InvalidCast(format!("Invalid base64 value: {}", e)))
+		};
+		Ok(match item {
+			StackItem::Any => VMStackItem::Null,
+			StackItem::Boolean { value } => VMStackItem::Boolean(*value),
+			StackItem::Integer { value } => VMStackItem::Integer(BigInt::from(*value)),
+			StackItem::ByteString { value } => VMStackItem::ByteString(decode(value)?),
+			StackItem::Buffer { value } => VMStackItem::new_buffer(decode(value)?),
+			StackItem::Array { value } =>
+				VMStackItem::new_array(value.iter().map(convert).collect::<Result<_, _>>()?),
+			StackItem::Struct { value } =>
+				VMStackItem::new_struct(value.iter().map(convert).collect::<Result<_, _>>()?),
+			StackItem::Map { value } => {
+				let mut entries = Vec::with_capacity(value.len());
+				for entry in value {
+					let key = convert(entry.key())?;
+					key.check_map_key()?;
+					entries.push((key, convert(entry.value())?));
+				}
+				VMStackItem::Map(Rc::new(RefCell::new(entries)))
+			},
+			StackItem::Pointer { .. } | StackItem::InteropInterface { .. } =>
+				return Err(VMError::InvalidCast(format!(
+					"{} items can't be loaded into the VM",
+					match item {
+						StackItem::Pointer { .. } => StackItem::POINTER_VALUE,
+						_ => StackItem::INTEROP_INTERFACE_VALUE,
+					}
+				))),
+		})
+	}
+
+	fn fmt_bounded(&self, f: &mut fmt::Formatter<'_>, depth: usize) -> fmt::Result {
+		const MAX_DEPTH: usize = 8;
+		let write_items = |f: &mut fmt::Formatter<'_>, name: &str, items: &[VMStackItem]| {
+			write!(f, "{}([", name)?;
+			if depth >= MAX_DEPTH {
+				write!(f, "..")?;
+			} else {
+				for (i, item) in items.iter().enumerate() {
+					if i > 0 {
+						write!(f, ", ")?;
+					}
+					item.fmt_bounded(f, depth + 1)?;
+				}
+			}
+			write!(f, "])")
+		};
+		match self {
+			VMStackItem::Null => write!(f, "Null"),
+			VMStackItem::Boolean(value) => write!(f, "Boolean({})", value),
+			VMStackItem::Integer(value) => write!(f, "Integer({})", value),
+			VMStackItem::ByteString(bytes) => write!(f, "ByteString(0x{})", hex::encode(bytes)),
+			VMStackItem::Buffer(buffer) =>
+				write!(f, "Buffer(0x{})", hex::encode(&*buffer.borrow())),
+			VMStackItem::Pointer { position, .. } => write!(f, "Pointer({})", position),
+			VMStackItem::Array(items) => write_items(f, "Array", &items.borrow()),
+			VMStackItem::Struct(items) => write_items(f, "Struct", &items.borrow()),
+			VMStackItem::Map(entries) => {
+				write!(f, "Map({{")?;
+				if depth >= MAX_DEPTH {
+					write!(f, "..")?;
+				} else {
+					for (i, (key, value)) in entries.borrow().iter().enumerate() {
+						if i > 0 {
+							write!(f, ", ")?;
+						}
+						key.fmt_bounded(f, depth + 1)?;
+						write!(f, ": ")?;
+						value.fmt_bounded(f, depth + 1)?;
+					}
+				}
+				write!(f, "}})")
+			},
+			VMStackItem::InteropInterface(object) =>
+				write!(f, "InteropInterface({})", object.interface_name()),
+		}
+	}
+}
+
+impl fmt::Debug for VMStackItem {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		self.fmt_bounded(f, 0)
+	}
+}
+
+impl From<bool> for VMStackItem {
+	fn from(value: bool) -> Self {
+		VMStackItem::Boolean(value)
+	}
+}
+
+impl From<i64> for VMStackItem {
+	fn from(value: i64) -> Self {
+		VMStackItem::Integer(BigInt::from(value))
+	}
+}
+
+impl From<BigInt> for VMStackItem {
+	fn from(value: BigInt) -> Self {
+		VMStackItem::Integer(value)
+	}
+}
+
+impl From<Vec<u8>> for VMStackItem {
+	fn from(value: Vec<u8>) -> Self {
+		VMStackItem::ByteString(value)
+	}
+}
+
+impl From<&str> for VMStackItem {
+	fn from(value: &str) -> Self {
+		VMStackItem::ByteString(value.as_bytes().to_vec())
+	}
+}
+
+/// Encodes an integer the way the NeoVM does: minimal little-endian two's complement,
+/// with zero encoded as an empty byte array.
+pub fn integer_to_bytes(value: &BigInt) -> Vec<u8> {
+	if value.sign() == Sign::NoSign {
+		Vec::new()
+	} else {
+		value.to_signed_bytes_le()
+	}
+}
+
+/// Decodes a little-endian two's complement integer. An empty slice decodes to zero.
+pub fn bytes_to_integer(bytes: &[u8]) -> BigInt {
+	if bytes.is_empty() {
+		BigInt::from(0)
+	} else {
+		BigInt::from_signed_bytes_le(bytes)
+	}
+}
@@ -12,8 +12,8 @@ use rayon::prelude::*;
 use serde_derive::{Deserialize, Serialize};
 
 use crate::{
-	neo_builder::{Transaction, TransactionBuilder, Witness},
-	neo_clients::{APITrait, JsonRpcProvider, ProviderError, RpcClient},
+	neo_builder::{SigningEnvelope, Transaction, TransactionBuilder, Witness},
+	neo_clients::{public_key_to_script_hash, APITrait, JsonRpcProvider, ProviderError, RpcClient},
 	neo_config::NeoConstants,
 	neo_crypto::{CryptoError, HashableForVec, KeyPair, Secp256r1Signature},
 	neo_protocol::{Account, AccountTrait, UnclaimedGas},
@@ -163,8 +163,8 @@ impl Wallet {
 		let accounts = self
 			.accounts
 			.clone()
-			.into_iter()
-			.filter_map(|(_, account)| match NEP6Account::from_account(&account) {
+			.into_values()
+			.filter_map(|account| match NEP6Account::from_account(&account) {
 				Ok(nep6_account) => Some(nep6_account),
 				Err(e) => {
 					eprintln!("Failed to convert account to NEP6Account: {}", e);
@@ -850,6 +850,55 @@ impl Wallet {
 		Ok(tx)
 	}
 
+	/// Signs an envelope exported for air-gapped signing with every account of the wallet that
+	/// is a signer of its transaction or holds a key of one of its multi-sig signers.
+	///
+	/// # Arguments
+	///
+	/// * `envelope` - The envelope to add the signatures to
+	/// * `password` - The password to decrypt the private keys of encrypted accounts
+	///
+	/// # Returns
+	///
+	/// A `Result` containing the number of signatures added or a `WalletError`
+	pub fn sign_envelope(
+		&self,
+		envelope: &mut SigningEnvelope,
+		password: &str,
+	) -> Result<usize, WalletError> {
+		// Find the accounts first, so each one is decrypted and signs only once
+		let mut accounts = Vec::new();
+		for script in envelope.verification_scripts()? {
+			if !script.is_single_sig() && !script.is_multi_sig() {
+				continue;
+			}
+			for public_key in script.get_public_keys()? {
+				if let Some(account) = self.get_account(&public_key_to_script_hash(&public_key)) {
+					if !accounts.contains(&account) {
+						accounts.push(account);
+					}
+				}
+			}
+		}
+
+		let params = self.effective_scrypt_params();
+		let mut added = 0;
+		for account in accounts {
+			let key_pair = match account.key_pair() {
+				Some(kp) => kp.clone(),
+				None => {
+					let mut account_clone = account.clone();
+					account_clone.decrypt_private_key_with_params(password, &params).map_err(
+						|e| WalletError::DecryptionError(format!("Failed to decrypt account: {e}")),
+					)?;
+					account_clone.key_pair().clone().ok_or(WalletError::NoKeyPair)?
+				},
+			};
+			added += envelope.sign(&key_pair)?;
+		}
+		Ok(added)
+	}
+
 	/// Returns the address of the wallet's default account.
 	///
 	/// This method provides access to the blockchain address associated with the
@@ -1065,6 +1114,7 @@ impl Wallet {
 #[cfg(test)]
 mod tests {
 	use crate::{
+		neo_builder::{AccountSigner, SigningEnvelope, TransactionData},
 		neo_config::TestConstants,
 		neo_protocol::{Account, AccountTrait},
 		neo_wallets::{Wallet, WalletTrait},
@@ -1170,6 +1220,41 @@ mod tests {
 		assert!(wallet.accounts()[1].key_pair().is_none());
 	}
 
+	#[test]
+	fn test_sign_envelope() {
+		let mut wallet: Wallet = Wallet::new();
+		apply_fast_scrypt(&mut wallet);
+		let first = Account::create().expect("Should be able to create account in test");
+		let second = Account::create().expect("Should be able to create account in test");
+		wallet.add_account(first.clone());
+		wallet.add_account(second.clone());
+		wallet.encrypt_accounts("pw");
+
+		let mut public_keys = vec![
+			first.key_pair().as_ref().unwrap().public_key(),
+			second.key_pair().as_ref().unwrap().public_key(),
+		];
+		let multi_sig = Account::multi_sig_from_public_keys(&mut public_keys, 2).unwrap();
+		let tx = TransactionData {
+			signers: vec![
+				AccountSigner::called_by_entry(&first).unwrap().into(),
+				AccountSigner::called_by_entry(&multi_sig).unwrap().into(),
+			],
+			script: vec![0x11],
+			..Default::default()
+		};
+		let scripts = [
+			first.verification_script().clone().unwrap(),
+			multi_sig.verification_script().clone().unwrap(),
+		];
+		let mut envelope = SigningEnvelope::new(&tx, 860833102, &scripts).unwrap();
+
+		assert!(wallet.sign_envelope(&mut envelope, "wrong").is_err());
+		assert_eq!(wallet.sign_envelope(&mut envelope, "pw").unwrap(), 3);
+		assert!(envelope.is_completed().unwrap());
+		assert_eq!(envelope.signed_transaction().unwrap().witnesses.len(), 2);
+	}
+
 	#[test]
 	fn test_encrypt_wallet_parallel() {
 		let mut wallet: Wallet = Wallet::new();
@@ -53,6 +53,11 @@ use tungstenite::protocol::Message;
 ///
 /// Defines the different types of events that can be subscribed to via WebSocket.
 /// Each subscription type provides specific event data tailored to its purpose.
+///
+/// For nodes implementing the neo-go `subscribe` protocol, prefer the typed subscriptions
+/// of [`RpcClient`](crate::neo_clients::RpcClient), like
+/// [`subscribe_notifications`](crate::neo_clients::RpcClient::subscribe_notifications), which
+/// apply their [filters](crate::neo_clients::Subscription) on the node.
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum SubscriptionType {
 	/// Subscribe to new blocks